@@ -0,0 +1,173 @@
+//! Cache of chunk apply results.
+//!
+//! The same chunk may be applied more than once on top of the same state, for
+//! example when processing of a block is retried or when a block is applied
+//! again during catchup. Applying the chunk is deterministic, so the trie
+//! changes and outcomes of the first application can be reused instead of
+//! executing the chunk again. Including the chunk in another block, e.g. on
+//! a different fork, is not a cache hit, see [`ApplyChunkInputs::block_hash`].
+//!
+//! Entries are never invalidated explicitly. A result stays correct for as
+//! long as its key and inputs match, so entries are only evicted in LRU order
+//! or replaced when the same chunk is applied with other inputs. The cache is
+//! bounded by the number of entries, not by bytes: each entry holds the full
+//! trie changes, outcomes and receipts of a chunk, so `apply_chunk_cache_size`
+//! should be kept small.
+
+use std::sync::Mutex;
+
+use borsh::BorshSerialize;
+use lru::LruCache;
+use near_primitives::challenge::ChallengesResult;
+use near_primitives::hash::CryptoHash;
+use near_primitives::receipt::Receipt;
+use near_primitives::sharding::ChunkHash;
+use near_primitives::types::{Balance, BlockHeight, Gas, ShardId, StateRoot};
+use tracing::warn;
+
+use crate::metrics;
+use crate::types::ApplyTransactionResult;
+
+/// Everything `RuntimeAdapter::apply_transactions` depends on which is not
+/// committed to by the chunk hash. Transactions, validator proposals and the
+/// gas limit are part of the chunk header, the state root is part of the key.
+#[derive(BorshSerialize)]
+pub(crate) struct ApplyChunkInputs<'a> {
+    pub shard_id: ShardId,
+    pub height: BlockHeight,
+    pub block_timestamp: u64,
+    pub prev_block_hash: &'a CryptoHash,
+    /// The ids of new receipts are derived from the hash of the block the
+    /// chunk is applied in (see `create_receipt_id_from_receipt`), so the
+    /// outcomes differ between blocks even if all other inputs are equal.
+    pub block_hash: &'a CryptoHash,
+    pub receipts: &'a [Receipt],
+    pub gas_price: Balance,
+    pub gas_limit: Gas,
+    pub challenges_result: &'a ChallengesResult,
+    pub random_seed: CryptoHash,
+    pub is_first_block_with_chunk_of_version: bool,
+    pub use_flat_storage: bool,
+}
+
+impl ApplyChunkInputs<'_> {
+    pub fn hash(&self) -> CryptoHash {
+        CryptoHash::hash_borsh(self)
+    }
+}
+
+struct CachedApplyResult {
+    inputs_hash: CryptoHash,
+    result: ApplyTransactionResult,
+}
+
+/// Apply results of new chunks, keyed by the state root the chunk was applied
+/// on and the chunk hash. A cached result is only returned if all other inputs
+/// of the application match as well.
+pub(crate) struct ApplyChunkCache {
+    /// `None` if the cache is disabled.
+    results: Option<Mutex<LruCache<(StateRoot, ChunkHash), CachedApplyResult>>>,
+}
+
+impl ApplyChunkCache {
+    /// Creates a cache holding up to `size` results. Zero disables the cache.
+    pub fn new(size: usize) -> Self {
+        Self { results: (size > 0).then(|| Mutex::new(LruCache::new(size))) }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.results.is_some()
+    }
+
+    pub fn get(
+        &self,
+        key: &(StateRoot, ChunkHash),
+        inputs_hash: &CryptoHash,
+    ) -> Option<ApplyTransactionResult> {
+        let mut results = self.results.as_ref()?.lock().unwrap();
+        match results.get(key) {
+            Some(cached) if &cached.inputs_hash == inputs_hash => {
+                metrics::APPLY_CHUNK_CACHE.with_label_values(&["hit"]).inc();
+                Some(cached.result.clone())
+            }
+            Some(_) => {
+                warn!(
+                    target: "chain",
+                    state_root = ?key.0,
+                    chunk_hash = ?key.1,
+                    "Chunk was applied before with different inputs, not using the cached result"
+                );
+                metrics::APPLY_CHUNK_CACHE.with_label_values(&["mismatch"]).inc();
+                None
+            }
+            None => {
+                metrics::APPLY_CHUNK_CACHE.with_label_values(&["miss"]).inc();
+                None
+            }
+        }
+    }
+
+    pub fn put(
+        &self,
+        key: (StateRoot, ChunkHash),
+        inputs_hash: CryptoHash,
+        result: &ApplyTransactionResult,
+    ) {
+        if let Some(results) = &self.results {
+            let cached = CachedApplyResult { inputs_hash, result: result.clone() };
+            results.lock().unwrap().put(key, cached);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ApplyChunkCache;
+    use crate::types::ApplyTransactionResult;
+    use near_primitives::hash::{hash, CryptoHash};
+    use near_primitives::shard_layout::ShardUId;
+    use near_primitives::sharding::ChunkHash;
+    use near_store::test_utils::create_test_store;
+    use near_store::{ShardTries, TrieChanges, WrappedTrieChanges};
+
+    fn apply_result(new_root: CryptoHash) -> ApplyTransactionResult {
+        ApplyTransactionResult {
+            trie_changes: WrappedTrieChanges::new(
+                ShardTries::test(create_test_store(), 1),
+                ShardUId::single_shard(),
+                TrieChanges::empty(new_root),
+                vec![],
+                CryptoHash::default(),
+            ),
+            new_root,
+            outcomes: vec![],
+            outgoing_receipts: vec![],
+            validator_proposals: vec![],
+            total_gas_burnt: 0,
+            total_balance_burnt: 0,
+            proof: None,
+            processed_delayed_receipts: vec![],
+        }
+    }
+
+    #[test]
+    fn test_apply_chunk_cache() {
+        let key = (hash(b"state root"), ChunkHash(hash(b"chunk")));
+        let inputs_hash = hash(b"inputs");
+
+        let cache = ApplyChunkCache::new(1);
+        assert!(cache.get(&key, &inputs_hash).is_none());
+        cache.put(key.clone(), inputs_hash, &apply_result(hash(b"new root")));
+        let cached = cache.get(&key, &inputs_hash).unwrap();
+        assert_eq!(cached.new_root, hash(b"new root"));
+        // Other inputs than the ones the chunk was applied with.
+        assert!(cache.get(&key, &hash(b"other inputs")).is_none());
+        // Other state root.
+        assert!(cache.get(&(hash(b"other root"), key.1.clone()), &inputs_hash).is_none());
+
+        let cache = ApplyChunkCache::new(0);
+        assert!(!cache.is_enabled());
+        cache.put(key.clone(), inputs_hash, &apply_result(hash(b"new root")));
+        assert!(cache.get(&key, &inputs_hash).is_none());
+    }
+}
@@ -176,6 +176,14 @@ impl BlocksDelayTracker {
         }
     }
 
+    /// Time when the block was first received, if it is still tracked.
+    pub fn get_block_received_time(
+        &self,
+        block_hash: &CryptoHash,
+    ) -> Option<DateTime<chrono::Utc>> {
+        self.blocks.get(block_hash).map(|stats| stats.received_utc_timestamp)
+    }
+
     pub fn mark_block_dropped(&mut self, block_hash: &CryptoHash, reason: DroppedReason) {
         if let Some(block_entry) = self.blocks.get_mut(block_hash) {
             block_entry.dropped = Some(reason);
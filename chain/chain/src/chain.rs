@@ -52,8 +52,9 @@ use near_primitives::views::{
 };
 #[cfg(feature = "protocol_feature_flat_state")]
 use near_store::{flat_state, StorageError};
-use near_store::{DBCol, ShardTries, StoreUpdate, WrappedTrieChanges};
+use near_store::{DBCol, ShardTries, StoreUpdate, TrieChanges, WrappedTrieChanges};
 
+use crate::apply_chunk_cache::{ApplyChunkCache, ApplyChunkInputs};
 use crate::block_processing_utils::{
     BlockPreprocessInfo, BlockProcessingArtifact, BlocksInProcessing, DoneApplyChunkCallback,
 };
@@ -82,9 +83,9 @@ use delay_detector::DelayDetector;
 use lru::LruCache;
 use near_client_primitives::types::StateSplitApplyingStatus;
 use near_primitives::shard_layout::{
-    account_id_to_shard_id, account_id_to_shard_uid, ShardLayout, ShardUId,
+    account_id_to_shard_id, account_id_to_shard_uid, get_block_shard_uid, ShardLayout, ShardUId,
 };
-use near_primitives::version::PROTOCOL_VERSION;
+use near_primitives::version::{ProtocolFeature, PROTOCOL_VERSION};
 use near_store::flat_state::FlatStorageError;
 #[cfg(feature = "protocol_feature_flat_state")]
 use near_store::flat_state::{store_helper, FlatStateDelta};
@@ -126,6 +127,10 @@ const ACCEPTABLE_TIME_DIFFERENCE: i64 = 12 * 10;
 /// Over this block height delta in advance if we are not chunk producer - route tx to upcoming validators.
 pub const TX_ROUTING_HEIGHT_HORIZON: BlockHeightDelta = 4;
 
+/// Every how many blocks the keys of the most recently used trie nodes are
+/// saved, to warm up the shard caches on the next start.
+const TRIE_CACHE_WARMUP_SAVE_PERIOD: BlockHeight = 100;
+
 /// Private constant for 1 NEAR (copy from near/config.rs) used for reporting.
 const NEAR_BASE: Balance = 1_000_000_000_000_000_000_000_000;
 
@@ -456,6 +461,10 @@ pub struct Chain {
 
     invalid_blocks: LruCache<CryptoHash, ()>,
 
+    /// Results of recently applied chunks, reused when the same chunk is
+    /// applied again on top of the same state.
+    apply_chunk_cache: Arc<ApplyChunkCache>,
+
     /// Support for sandbox's patch_state requests.
     ///
     /// Sandbox needs ability to arbitrary modify the state. Blockchains
@@ -536,6 +545,7 @@ impl Chain {
             last_time_head_updated: Clock::instant(),
             flat_storage_creator: None,
             invalid_blocks: LruCache::new(INVALID_CHUNKS_POOL_SIZE),
+            apply_chunk_cache: Arc::new(ApplyChunkCache::new(0)),
             pending_state_patch: Default::default(),
             requested_state_parts: StateRequestTracker::new(),
         })
@@ -693,6 +703,7 @@ impl Chain {
             apply_chunks_receiver: rc,
             last_time_head_updated: Clock::instant(),
             flat_storage_creator,
+            apply_chunk_cache: Arc::new(ApplyChunkCache::new(chain_config.apply_chunk_cache_size)),
             pending_state_patch: Default::default(),
             requested_state_parts: StateRequestTracker::new(),
         })
@@ -711,7 +722,7 @@ impl Chain {
     ) -> Result<CryptoHash, Error> {
         let bps = runtime_adapter.get_epoch_block_producers_ordered(&epoch_id, last_known_hash)?;
         let protocol_version = runtime_adapter.get_epoch_protocol_version(&prev_epoch_id)?;
-        if checked_feature!("stable", BlockHeaderV3, protocol_version) {
+        if ProtocolFeature::BlockHeaderV3.enabled(protocol_version) {
             let validator_stakes = bps.into_iter().map(|(bp, _)| bp);
             Ok(CryptoHash::hash_borsh_iter(validator_stakes))
         } else {
@@ -3752,13 +3763,13 @@ impl Chain {
                     // this check may require old block headers, which may not exist in storage
                     // of the client in the mock network
                     #[cfg(not(feature = "mock_node"))]
-                    if checked_feature!("stable", AccessKeyNonceRange, protocol_version) {
+                    if ProtocolFeature::AccessKeyNonceRange.enabled(protocol_version) {
                         let transaction_validity_period = self.transaction_validity_period;
                         for transaction in transactions {
                             self.store()
                                 .check_transaction_validity_period(
                                     prev_block.header(),
-                                    &transaction.transaction.block_hash,
+                                    transaction.transaction.block_hash(),
                                     transaction_validity_period,
                                 )
                                 .map_err(|_| {
@@ -3791,6 +3802,7 @@ impl Chain {
                     let random_seed = *block.header().random_value();
                     let height = chunk_header.height_included();
                     let prev_block_hash = chunk_header.prev_block_hash().clone();
+                    let apply_chunk_cache = self.apply_chunk_cache.clone();
 
                     Ok(Some(Box::new(move |parent_span| -> Result<ApplyChunkResult, Error> {
                         let _span = tracing::debug_span!(
@@ -3800,25 +3812,64 @@ impl Chain {
                             shard_id)
                         .entered();
                         let _timer = CryptoHashTimer::new(chunk.chunk_hash().0);
-                        match runtime_adapter.apply_transactions(
-                            shard_id,
-                            chunk_inner.prev_state_root(),
-                            height,
-                            block_timestamp,
-                            &prev_block_hash,
-                            &block_hash,
-                            &receipts,
-                            chunk.transactions(),
-                            chunk_inner.validator_proposals(),
-                            gas_price,
-                            gas_limit,
-                            &challenges_result,
-                            random_seed,
-                            true,
-                            is_first_block_with_chunk_of_version,
-                            state_patch,
-                            cares_about_shard_this_epoch,
-                        ) {
+                        let cache_key = (*chunk_inner.prev_state_root(), chunk.chunk_hash());
+                        // Results of chunks applied with a sandbox state patch
+                        // are not cached, the patch isn't part of the inputs.
+                        let inputs_hash =
+                            (apply_chunk_cache.is_enabled() && state_patch.is_empty()).then(|| {
+                                ApplyChunkInputs {
+                                    shard_id,
+                                    height,
+                                    block_timestamp,
+                                    prev_block_hash: &prev_block_hash,
+                                    block_hash: &block_hash,
+                                    receipts: &receipts,
+                                    gas_price,
+                                    gas_limit,
+                                    challenges_result: &challenges_result,
+                                    random_seed,
+                                    is_first_block_with_chunk_of_version,
+                                    use_flat_storage: cares_about_shard_this_epoch,
+                                }
+                                .hash()
+                            });
+                        let cached_result = inputs_hash.and_then(|inputs_hash| {
+                            apply_chunk_cache.get(&cache_key, &inputs_hash)
+                        });
+                        let apply_result = match cached_result {
+                            Some(apply_result) => Ok(apply_result),
+                            None => runtime_adapter
+                                .apply_transactions(
+                                    shard_id,
+                                    chunk_inner.prev_state_root(),
+                                    height,
+                                    block_timestamp,
+                                    &prev_block_hash,
+                                    &block_hash,
+                                    &receipts,
+                                    chunk.transactions(),
+                                    chunk_inner.validator_proposals(),
+                                    gas_price,
+                                    gas_limit,
+                                    &challenges_result,
+                                    random_seed,
+                                    true,
+                                    is_first_block_with_chunk_of_version,
+                                    state_patch,
+                                    cares_about_shard_this_epoch,
+                                )
+                                .map(|apply_result| {
+                                    if let Some(inputs_hash) = inputs_hash {
+                                        apply_chunk_cache.put(
+                                            cache_key,
+                                            inputs_hash,
+                                            &apply_result,
+                                        );
+                                    }
+                                    apply_result
+                                }),
+                        };
+                        match apply_result {
                             Ok(apply_result) => {
                                 let apply_split_result_or_state_changes =
                                     if will_shard_layout_change {
@@ -5165,7 +5216,24 @@ impl<'a> ChainUpdate<'a> {
         self.update_final_head_from_block(header)?;
         let head = self.chain_store_update.head()?;
         if header.height() > head.height {
+            if header.prev_hash() != &head.last_block_hash {
+                // Switching to another fork. This only affects caching, so
+                // failures are not propagated.
+                if let Err(err) = self.evict_abandoned_fork_from_caches(&head, header) {
+                    warn!(target: "chain", ?err, "Failed to evict abandoned fork from trie caches");
+                }
+            }
             let tip = Tip::from_header(header);
+            if tip.height % TRIE_CACHE_WARMUP_SAVE_PERIOD == 0 {
+                let tries = self.runtime_adapter.get_tries();
+                let mut store_update = tries.store_update();
+                match tries.save_cache_warmup(&mut store_update) {
+                    Ok(()) => self.chain_store_update.merge(store_update),
+                    Err(err) => {
+                        warn!(target: "chain", ?err, "Failed to save trie cache warm-up data")
+                    }
+                }
+            }
 
             self.chain_store_update.save_body_head(&tip)?;
             metrics::BLOCK_HEIGHT_HEAD.set(tip.height as i64);
@@ -5177,6 +5245,39 @@ impl<'a> ChainUpdate<'a> {
         }
     }
 
+    /// Evicts trie nodes that were only inserted by blocks of the fork that
+    /// ends at `old_head` from the shard caches, when `new_head` is on
+    /// another fork.
+    fn evict_abandoned_fork_from_caches(
+        &mut self,
+        old_head: &Tip,
+        new_head: &BlockHeader,
+    ) -> Result<(), Error> {
+        let tries = self.runtime_adapter.get_tries();
+        let mut abandoned_hash = old_head.last_block_hash;
+        let mut canonical_hash = *new_head.prev_hash();
+        while abandoned_hash != canonical_hash {
+            let abandoned = self.chain_store_update.get_block_header(&abandoned_hash)?;
+            let canonical = self.chain_store_update.get_block_header(&canonical_hash)?;
+            if canonical.height() > abandoned.height() {
+                canonical_hash = *canonical.prev_hash();
+                continue;
+            }
+            let shard_layout = self.runtime_adapter.get_shard_layout(abandoned.epoch_id())?;
+            for shard_uid in shard_layout.get_shard_uids() {
+                let trie_changes: Option<TrieChanges> = self.chain_store_update.store().get_ser(
+                    DBCol::TrieChanges,
+                    &get_block_shard_uid(&abandoned_hash, &shard_uid),
+                )?;
+                if let Some(trie_changes) = trie_changes {
+                    tries.evict_reverted_insertions(&trie_changes, shard_uid)?;
+                }
+            }
+            abandoned_hash = *abandoned.prev_hash();
+        }
+        Ok(())
+    }
+
     /// Marks a block as invalid,
     fn mark_block_as_challenged(
         &mut self,
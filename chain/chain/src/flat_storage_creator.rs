@@ -98,13 +98,13 @@ impl FlatStorageShardCreator {
     #[allow(unused)]
     fn fetch_state_part(
         store: Store,
-        shard_uid: ShardUId,
+        state_shard_uid: ShardUId,
         state_root: StateRoot,
         part_id: PartId,
         progress: Arc<AtomicU64>,
         result_sender: Sender<u64>,
     ) {
-        let trie_storage = TrieDBStorage::new(store.clone(), shard_uid);
+        let trie_storage = TrieDBStorage::new(store.clone(), state_shard_uid);
         let trie = Trie::new(Box::new(trie_storage), state_root, None);
         let path_begin = trie.find_path_for_part_boundary(part_id.idx, part_id.total).unwrap();
         let path_end = trie.find_path_for_part_boundary(part_id.idx + 1, part_id.total).unwrap();
@@ -193,7 +193,9 @@ impl FlatStorageShardCreator {
                     let store = self.runtime_adapter.store().clone();
                     let epoch_id = self.runtime_adapter.get_epoch_id(&block_hash)?;
                     let shard_uid = self.runtime_adapter.shard_id_to_uid(shard_id, &epoch_id)?;
-                    let trie_storage = TrieDBStorage::new(store.clone(), shard_uid);
+                    let state_shard_uid =
+                        self.runtime_adapter.get_tries().get_state_shard_uid(shard_uid);
+                    let trie_storage = TrieDBStorage::new(store.clone(), state_shard_uid);
                     let state_root =
                         chain_store.get_chunk_extra(&block_hash, &shard_uid)?.state_root().clone();
                     let trie = Trie::new(Box::new(trie_storage), state_root, None);
@@ -232,6 +234,8 @@ impl FlatStorageShardCreator {
                             .get_chunk_extra(&block_hash, &shard_uid)?
                             .state_root()
                             .clone();
+                        let state_shard_uid =
+                            self.runtime_adapter.get_tries().get_state_shard_uid(shard_uid);
                         let progress = Arc::new(std::sync::atomic::AtomicU64::new(0));
                         debug!(
                             target: "store", %shard_id, %block_hash, %start_part_id, %next_start_part_id, %num_parts,
@@ -246,7 +250,7 @@ impl FlatStorageShardCreator {
                             thread_pool.spawn(move || {
                                 Self::fetch_state_part(
                                     inner_store,
-                                    shard_uid,
+                                    state_shard_uid,
                                     inner_state_root,
                                     PartId::new(part_id, num_parts),
                                     inner_progress,
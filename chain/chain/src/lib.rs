@@ -8,6 +8,7 @@ pub use store::{ChainStore, ChainStoreAccess, ChainStoreUpdate};
 pub use store_validator::{ErrorMessage, StoreValidator};
 pub use types::{Block, BlockHeader, BlockStatus, ChainGenesis, Provenance, RuntimeAdapter};
 
+mod apply_chunk_cache;
 mod block_processing_utils;
 pub mod blocks_delay_tracker;
 pub mod chain;
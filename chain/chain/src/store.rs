@@ -2409,6 +2409,9 @@ impl<'a> ChainStoreUpdate<'a> {
             | DBCol::_TransactionRefCount
             | DBCol::_TransactionResult
             | DBCol::StateChangesForSplitStates
+            | DBCol::StateShardUIdMapping
+            | DBCol::TrieCacheWarmup
+            | DBCol::TransactionPool
             | DBCol::CachedContractCode => {
                 unreachable!();
             }
@@ -13,7 +13,6 @@ use near_chain_primitives::Error;
 use near_client_primitives::types::StateSplitApplyingStatus;
 use near_pool::types::PoolIterator;
 use near_primitives::challenge::{ChallengesResult, SlashedValidator};
-use near_primitives::checked_feature;
 use near_primitives::errors::InvalidTxError;
 use near_primitives::hash::CryptoHash;
 use near_primitives::merkle::{merklize, MerklePath};
@@ -27,8 +26,8 @@ use near_primitives::types::{
     ShardId, StateChangesForSplitStates, StateRoot, StateRootNode,
 };
 use near_primitives::version::{
-    ProtocolVersion, MIN_GAS_PRICE_NEP_92, MIN_GAS_PRICE_NEP_92_FIX, MIN_PROTOCOL_VERSION_NEP_92,
-    MIN_PROTOCOL_VERSION_NEP_92_FIX,
+    ProtocolFeature, ProtocolVersion, MIN_GAS_PRICE_NEP_92, MIN_GAS_PRICE_NEP_92_FIX,
+    MIN_PROTOCOL_VERSION_NEP_92, MIN_PROTOCOL_VERSION_NEP_92_FIX,
 };
 use near_primitives::views::{QueryRequest, QueryResponse};
 use near_store::flat_state::ChainAccessForFlatStorage;
@@ -94,6 +93,7 @@ pub enum ApplySplitStateResultOrStateChanges {
     StateChangesForSplitStates(StateChangesForSplitStates),
 }
 
+#[derive(Clone)]
 pub struct ApplyTransactionResult {
     pub trie_changes: WrappedTrieChanges,
     pub new_root: StateRoot,
@@ -195,7 +195,7 @@ impl BlockEconomicsConfig {
     }
 
     pub fn max_gas_price(&self, protocol_version: ProtocolVersion) -> Balance {
-        if checked_feature!("stable", CapMaxGasPrice, protocol_version) {
+        if ProtocolFeature::CapMaxGasPrice.enabled(protocol_version) {
             std::cmp::min(
                 self.genesis_max_gas_price,
                 Self::MAX_GAS_MULTIPLIER * self.min_gas_price(protocol_version),
@@ -242,11 +242,14 @@ pub struct ChainConfig {
     /// Number of threads to execute background migration work.
     /// Currently used for flat storage background creation.
     pub background_migration_threads: usize,
+    /// Number of chunk apply results to cache, see `ApplyChunkCache`.
+    /// Zero disables the cache.
+    pub apply_chunk_cache_size: usize,
 }
 
 impl ChainConfig {
     pub fn test() -> Self {
-        Self { save_trie_changes: true, background_migration_threads: 1 }
+        Self { save_trie_changes: true, background_migration_threads: 1, apply_chunk_cache_size: 0 }
     }
 }
 
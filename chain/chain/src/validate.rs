@@ -1,22 +1,27 @@
 use std::collections::HashMap;
+use std::time::{Duration, Instant};
 
 use borsh::BorshDeserialize;
 
 use near_crypto::PublicKey;
 use near_primitives::block::{Block, BlockHeader};
 use near_primitives::challenge::{
-    BlockDoubleSign, Challenge, ChallengeBody, ChunkProofs, ChunkState, MaybeEncodedShardChunk,
+    BlockDoubleSign, Challenge, ChallengeBody, ChallengesResult, ChunkProofs, ChunkState,
+    MaybeEncodedShardChunk, PartialState,
 };
 use near_primitives::hash::CryptoHash;
 use near_primitives::merkle::merklize;
+use near_primitives::receipt::Receipt;
 use near_primitives::sharding::{
     ShardChunk, ShardChunkHeader, ShardChunkHeaderV1, ShardChunkHeaderV2, ShardChunkHeaderV3,
 };
 use near_primitives::transaction::SignedTransaction;
 use near_primitives::types::chunk_extra::ChunkExtra;
-use near_primitives::types::{AccountId, BlockHeight, EpochId, Nonce};
+use near_primitives::types::{AccountId, Balance, BlockHeight, EpochId, Gas, Nonce, StateRoot};
+use near_store::PartialStorage;
 
-use crate::{byzantine_assert, Chain};
+use crate::types::ApplyTransactionResult;
+use crate::{byzantine_assert, metrics, Chain};
 use crate::{ChainStore, Error, RuntimeAdapter};
 
 /// Gas limit cannot be adjusted for more than 0.1% at a time.
@@ -92,10 +97,10 @@ pub fn validate_transactions_order(transactions: &[SignedTransaction]) -> bool {
     let mut current_batch = 1;
 
     for tx in transactions {
-        let key = (&tx.transaction.signer_id, &tx.transaction.public_key);
+        let key = (tx.transaction.signer_id(), tx.transaction.public_key());
 
         // Verifying nonce
-        let nonce = tx.transaction.nonce;
+        let nonce = tx.transaction.nonce();
         if let Some(last_nonce) = nonces.get(&key) {
             if nonce <= *last_nonce {
                 // Nonces should increase.
@@ -411,6 +416,87 @@ pub fn validate_challenge(
     }
 }
 
+/// Result of applying a chunk on top of a state witness.
+///
+/// Nothing is committed, this is what a chunk validator compares against the
+/// results claimed by the chunk producer.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StateWitnessApplyResult {
+    pub new_root: StateRoot,
+    pub outcome_root: CryptoHash,
+    /// Hash of each execution outcome, in the order they are merklized into
+    /// `outcome_root`.
+    pub outcome_hashes: Vec<CryptoHash>,
+    pub total_gas_burnt: Gas,
+    pub total_balance_burnt: Balance,
+    /// Total size in bytes of the trie nodes and values in the witness.
+    pub witness_size: usize,
+    pub apply_time: Duration,
+}
+
+/// Applies `chunk` with the given incoming receipts, reading the state only
+/// from `state_witness`, which must contain every trie node and value that
+/// is accessed while applying the chunk.
+///
+/// The store is never used to read or write state, so this can run on a node
+/// that does not track the shard.
+pub fn apply_chunk_with_state_witness(
+    runtime_adapter: &dyn RuntimeAdapter,
+    state_witness: PartialState,
+    chunk: &ShardChunk,
+    receipts: &[Receipt],
+    block_header: &BlockHeader,
+    prev_block_header: &BlockHeader,
+    challenges_result: &ChallengesResult,
+    is_first_block_with_chunk_of_version: bool,
+) -> Result<StateWitnessApplyResult, Error> {
+    let chunk_header = chunk.cloned_header();
+    let shard_id = chunk_header.shard_id();
+    let shard_label = shard_id.to_string();
+    let witness_size = state_witness.0.iter().map(|item| item.len()).sum();
+    metrics::STATE_WITNESS_SIZE.with_label_values(&[&shard_label]).observe(witness_size as f64);
+
+    let start = Instant::now();
+    let result = runtime_adapter.check_state_transition(
+        PartialStorage { nodes: state_witness },
+        shard_id,
+        &chunk_header.prev_state_root(),
+        block_header.height(),
+        block_header.raw_timestamp(),
+        block_header.prev_hash(),
+        block_header.hash(),
+        receipts,
+        chunk.transactions(),
+        chunk_header.validator_proposals(),
+        prev_block_header.gas_price(),
+        chunk_header.gas_limit(),
+        challenges_result,
+        *block_header.random_value(),
+        true,
+        is_first_block_with_chunk_of_version,
+    )?;
+    let apply_time = start.elapsed();
+    metrics::STATE_WITNESS_APPLY_TIME
+        .with_label_values(&[&shard_label])
+        .observe(apply_time.as_secs_f64());
+
+    let outcome_hashes = result
+        .outcomes
+        .iter()
+        .map(|outcome| CryptoHash::hash_borsh(&outcome.to_hashes()))
+        .collect();
+    let (outcome_root, _) = ApplyTransactionResult::compute_outcomes_proof(&result.outcomes);
+    Ok(StateWitnessApplyResult {
+        new_root: result.new_root,
+        outcome_root,
+        outcome_hashes,
+        total_gas_burnt: result.total_gas_burnt,
+        total_balance_burnt: result.total_balance_burnt,
+        witness_size,
+        apply_time,
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use near_crypto::{InMemorySigner, KeyType};
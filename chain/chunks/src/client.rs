@@ -88,6 +88,15 @@ impl ShardedTransactionPool {
         }
     }
 
+    /// Returns the transactions in the pool of each shard.
+    pub fn transactions_by_shard(
+        &self,
+    ) -> impl Iterator<Item = (ShardId, Vec<SignedTransaction>)> + '_ {
+        self.tx_pools
+            .iter()
+            .map(|(shard_id, pool)| (*shard_id, pool.transactions().cloned().collect()))
+    }
+
     /// Computes a deterministic random seed for given `shard_id`.
     /// This seed is used to randomize the transaction pool.
     /// For better security we want the seed to different in each shard.
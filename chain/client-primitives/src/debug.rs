@@ -26,6 +26,23 @@ pub struct TrackedShardsView {
     pub shards_tracked_next_epoch: Vec<bool>,
 }
 
+/// Size and eviction counters of a shard cache.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct TrieCacheStatsView {
+    pub shard_id: u64,
+    pub is_view: bool,
+    pub len: u64,
+    pub total_size: u64,
+    pub total_size_limit: u64,
+    pub deletions_queue_len: u64,
+    // Values evicted because the cache was full.
+    pub capacity_evictions: u64,
+    // Values evicted because their nodes were deleted.
+    pub deletion_evictions: u64,
+    // Values not cached because they are larger than the size limit for values.
+    pub too_large: u64,
+}
+
 #[derive(Serialize, Deserialize, Debug)]
 pub struct EpochInfoView {
     pub epoch_id: CryptoHash,
@@ -142,6 +159,25 @@ pub struct ProductionAtHeight {
     pub chunk_production: HashMap<u64, ChunkProduction>,
 }
 
+// Timestamps of the stages of producing a block by this node.
+// For debug purposes only.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct BlockProductionTimingView {
+    pub block_height: BlockHeight,
+    pub block_hash: CryptoHash,
+    pub prev_block_hash: CryptoHash,
+    // Time when the previous block was received, or produced if it was produced by this node.
+    // None if this is no longer known.
+    pub prev_block_received: Option<DateTime<chrono::Utc>>,
+    // Time when the last new chunk included in the block was received.
+    // None if the block doesn't include any new chunk.
+    pub chunks_ready: Option<DateTime<chrono::Utc>>,
+    // Time when the block was produced.
+    pub block_produced: DateTime<chrono::Utc>,
+    // Time when the block was sent out to the network.
+    pub block_broadcast: Option<DateTime<chrono::Utc>>,
+}
+
 // Infromation about the approvals that we received.
 #[derive(Serialize, Debug, Default, Clone)]
 pub struct ApprovalAtHeightStatus {
@@ -188,6 +224,10 @@ pub enum DebugStatus {
     ChainProcessingStatus,
     // The state parts already requested.
     RequestedStateParts,
+    // Size and eviction counters of the shard caches.
+    TrieCacheStats,
+    // Timestamps of the production stages of the last blocks produced by this node.
+    BlockProductionTimings,
 }
 
 impl Message for DebugStatus {
@@ -209,4 +249,8 @@ pub enum DebugStatusResponse {
     ChainProcessingStatus(ChainProcessingInfo),
     // The state parts already requested.
     RequestedStateParts(Vec<RequestedStatePartsView>),
+    // Size and eviction counters of the shard caches.
+    TrieCacheStats(Vec<TrieCacheStatsView>),
+    // Timestamps of the production stages of the last blocks produced by this node, oldest first.
+    BlockProductionTimings(Vec<BlockProductionTimingView>),
 }
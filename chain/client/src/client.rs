@@ -6,12 +6,13 @@ use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
 use std::time::{Duration, Instant};
 
+use borsh::BorshDeserialize;
 use lru::LruCache;
 use near_chunks::client::{ClientAdapterForShardsManager, ShardedTransactionPool};
 use near_chunks::logic::{
     cares_about_shard_this_or_next_epoch, decode_encoded_chunk, persist_chunk,
 };
-use near_client_primitives::debug::ChunkProduction;
+use near_client_primitives::debug::{BlockProductionTimingView, ChunkProduction};
 use near_primitives::time::Clock;
 use tracing::{debug, error, info, trace, warn};
 
@@ -47,8 +48,8 @@ use near_primitives::utils::MaybeValidated;
 use near_primitives::validator_signer::ValidatorSigner;
 
 use crate::adapter::ProcessTxResponse;
-use crate::debug::BlockProductionTracker;
 use crate::debug::PRODUCTION_TIMES_CACHE_SIZE;
+use crate::debug::{BlockProductionTimings, BlockProductionTracker};
 use crate::sync::{BlockSync, EpochSync, HeaderSync, StateSync, StateSyncResult};
 use crate::{metrics, SyncStatus};
 use near_client_primitives::types::{Error, ShardSyncDownload, ShardSyncStatus};
@@ -59,6 +60,7 @@ use near_primitives::epoch_manager::RngSeed;
 use near_primitives::network::PeerId;
 use near_primitives::version::PROTOCOL_VERSION;
 use near_primitives::views::{CatchupStatusView, DroppedReason};
+use near_store::DBCol;
 
 const NUM_REBROADCAST_BLOCKS: usize = 30;
 const CHUNK_HEADERS_FOR_INCLUSION_CACHE_SIZE: usize = 2048;
@@ -136,6 +138,9 @@ pub struct Client {
     /// Block production timing information. Used only for debug purposes.
     /// Stores approval information and production time of the block
     pub block_production_info: BlockProductionTracker,
+    /// Timestamps of the production stages of the last blocks produced by this node. Used only
+    /// for debug purposes.
+    pub block_production_timings: BlockProductionTimings,
     /// Chunk production timing information. Used only for debug purposes.
     pub chunk_production_info: lru::LruCache<(BlockHeight, ShardId), ChunkProduction>,
 
@@ -191,6 +196,7 @@ impl Client {
             ChainConfig {
                 save_trie_changes: !config.archive,
                 background_migration_threads: config.client_background_migration_threads,
+                apply_chunk_cache_size: config.apply_chunk_cache_size,
             },
         )?;
         let me = validator_signer.as_ref().map(|x| x.validator_id().clone());
@@ -281,6 +287,7 @@ impl Client {
             rebroadcasted_blocks: lru::LruCache::new(NUM_REBROADCAST_BLOCKS),
             last_time_head_progress_made: Clock::instant(),
             block_production_info: BlockProductionTracker::new(),
+            block_production_timings: BlockProductionTimings::new(),
             chunk_production_info: lru::LruCache::new(PRODUCTION_TIMES_CACHE_SIZE),
             tier1_accounts_cache: None,
         })
@@ -624,6 +631,8 @@ impl Client {
             )?,
         );
 
+        let chunks_ready = new_chunks.values().map(|(_, received_time, _)| *received_time).max();
+
         // Collect new chunks.
         for (shard_id, (mut chunk_header, _, _)) in new_chunks {
             *chunk_header.height_included_mut() = next_height;
@@ -690,6 +699,21 @@ impl Client {
             seen: block.header().raw_timestamp(),
         })?;
 
+        let prev_block_received = self
+            .chain
+            .blocks_delay_tracker
+            .get_block_received_time(&prev_hash)
+            .or_else(|| self.block_production_timings.get_produced_time(&prev_hash));
+        self.block_production_timings.record_block_produced(BlockProductionTimingView {
+            block_height: next_height,
+            block_hash: *block.hash(),
+            prev_block_hash: prev_hash,
+            prev_block_received,
+            chunks_ready,
+            block_produced: Clock::utc(),
+            block_broadcast: None,
+        });
+
         metrics::BLOCK_PRODUCED_TOTAL.inc();
 
         Ok(Some(block))
@@ -878,7 +902,7 @@ impl Client {
                         .store()
                         .check_transaction_validity_period(
                             prev_block_header,
-                            &tx.transaction.block_hash,
+                            tx.transaction.block_hash(),
                             transaction_validity_period,
                         )
                         .is_ok()
@@ -1818,7 +1842,7 @@ impl Client {
     /// Forwards given transaction to upcoming validators.
     fn forward_tx(&self, epoch_id: &EpochId, tx: &SignedTransaction) -> Result<(), Error> {
         let shard_id =
-            self.runtime_adapter.account_id_to_shard_id(&tx.transaction.signer_id, epoch_id)?;
+            self.runtime_adapter.account_id_to_shard_id(tx.transaction.signer_id(), epoch_id)?;
         let head = self.chain.head()?;
         let maybe_next_epoch_id = self.get_next_epoch_id_if_at_boundary(&head)?;
 
@@ -1832,7 +1856,7 @@ impl Client {
             if let Some(next_epoch_id) = &maybe_next_epoch_id {
                 let next_shard_id = self
                     .runtime_adapter
-                    .account_id_to_shard_id(&tx.transaction.signer_id, next_epoch_id)?;
+                    .account_id_to_shard_id(tx.transaction.signer_id(), next_epoch_id)?;
                 let validator = self.chain.find_chunk_producer_for_forwarding(
                     next_epoch_id,
                     next_shard_id,
@@ -1880,6 +1904,67 @@ impl Client {
         })
     }
 
+    /// Saves the transactions in the pool to `DBCol::TransactionPool`, so
+    /// that they can be restored by `restore_tx_pool` after a restart.
+    /// Returns the number of saved transactions.
+    pub fn save_tx_pool(&self) -> Result<usize, Error> {
+        let mut store_update = self.chain.store().store().store_update();
+        let mut num_transactions = 0;
+        for (shard_id, transactions) in self.sharded_tx_pool.transactions_by_shard() {
+            num_transactions += transactions.len();
+            store_update
+                .set_ser(DBCol::TransactionPool, &shard_id.to_le_bytes(), &transactions)
+                .map_err(near_chain::Error::from)?;
+        }
+        store_update.commit().map_err(near_chain::Error::from)?;
+        Ok(num_transactions)
+    }
+
+    /// Takes the transactions saved by `save_tx_pool` out of the database and
+    /// inserts those into the pool which are still valid at the current head.
+    /// Returns the number of restored transactions.
+    pub fn restore_tx_pool(&mut self) -> Result<usize, Error> {
+        let store = self.chain.store().store().clone();
+        let mut saved = vec![];
+        let mut store_update = store.store_update();
+        for item in store.iter(DBCol::TransactionPool) {
+            let (key, value) = item.map_err(near_chain::Error::from)?;
+            let transactions = Vec::<SignedTransaction>::try_from_slice(&value)
+                .map_err(near_chain::Error::from)?;
+            saved.extend(transactions);
+            store_update.delete(DBCol::TransactionPool, &key);
+        }
+        // Delete the saved transactions before processing them, so that they
+        // are never restored twice.
+        store_update.commit().map_err(near_chain::Error::from)?;
+
+        let head = self.chain.head()?;
+        let epoch_id = self.runtime_adapter.get_epoch_id_from_prev_block(&head.last_block_hash)?;
+        let mut num_restored = 0;
+        for tx in saved {
+            let tx_hash = tx.get_hash();
+            // Validates the transaction like a forwarded one, without
+            // forwarding it again.
+            match self.process_tx_internal(&tx, true, true) {
+                Ok(ProcessTxResponse::ValidTx) => {
+                    let shard_id = self
+                        .runtime_adapter
+                        .account_id_to_shard_id(tx.transaction.signer_id(), &epoch_id)?;
+                    if self.sharded_tx_pool.insert_transaction(shard_id, tx) {
+                        num_restored += 1;
+                    }
+                }
+                Ok(response) => {
+                    debug!(target: "client", ?tx_hash, ?response, "Not restoring transaction");
+                }
+                Err(err) => {
+                    debug!(target: "client", ?tx_hash, ?err, "Not restoring transaction");
+                }
+            }
+        }
+        Ok(num_restored)
+    }
+
     /// If we are close to epoch boundary, return next epoch id, otherwise return None.
     fn get_next_epoch_id_if_at_boundary(&self, head: &Tip) -> Result<Option<EpochId>, Error> {
         let next_epoch_started =
@@ -1928,7 +2013,7 @@ impl Client {
         // `cur_block_header`.
         if let Err(e) = self.chain.store().check_transaction_validity_period(
             &cur_block_header,
-            &tx.transaction.block_hash,
+            tx.transaction.block_hash(),
             transaction_validity_period,
         ) {
             debug!(target: "client", "Invalid tx: expired or from a different fork -- {:?}", tx);
@@ -1949,7 +2034,7 @@ impl Client {
         }
 
         let shard_id =
-            self.runtime_adapter.account_id_to_shard_id(&tx.transaction.signer_id, &epoch_id)?;
+            self.runtime_adapter.account_id_to_shard_id(tx.transaction.signer_id(), &epoch_id)?;
         if self.runtime_adapter.cares_about_shard(me, &head.last_block_hash, shard_id, true)
             || self.runtime_adapter.will_care_about_shard(me, &head.last_block_hash, shard_id, true)
         {
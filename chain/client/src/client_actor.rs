@@ -252,6 +252,15 @@ impl Actor for ClientActor {
     type Context = Context<Self>;
 
     fn started(&mut self, ctx: &mut Self::Context) {
+        if self.client.config.persist_tx_pool {
+            match self.client.restore_tx_pool() {
+                Ok(num_transactions) => {
+                    info!(target: "client", num_transactions, "Restored transaction pool")
+                }
+                Err(err) => warn!(target: "client", ?err, "Failed to restore transaction pool"),
+            }
+        }
+
         // Start syncing job.
         self.start_sync(ctx);
 
@@ -1365,6 +1374,7 @@ impl ClientActor {
                 })
                 .with_span_context(),
             );
+            self.client.block_production_timings.record_block_broadcast(block.hash());
             // We’ve produced the block so that counts as validated block.
             let block = MaybeValidated::from_validated(block);
             let res = self.client.start_process_block(
@@ -1870,6 +1880,14 @@ impl Drop for ClientActor {
     fn drop(&mut self) {
         let _span = tracing::debug_span!(target: "client", "drop").entered();
         self.state_parts_client_arbiter.stop();
+        if self.client.config.persist_tx_pool {
+            match self.client.save_tx_pool() {
+                Ok(num_transactions) => {
+                    info!(target: "client", num_transactions, "Saved transaction pool")
+                }
+                Err(err) => warn!(target: "client", ?err, "Failed to save transaction pool"),
+            }
+        }
     }
 }
 
@@ -7,8 +7,9 @@ use itertools::Itertools;
 use near_chain::crypto_hash_timer::CryptoHashTimer;
 use near_chain::{near_chain_primitives, Chain, ChainStoreAccess, RuntimeAdapter};
 use near_client_primitives::debug::{
-    ApprovalAtHeightStatus, BlockProduction, ChunkCollection, DebugBlockStatusData, DebugStatus,
-    DebugStatusResponse, MissedHeightInfo, ProductionAtHeight, ValidatorStatus,
+    ApprovalAtHeightStatus, BlockProduction, BlockProductionTimingView, ChunkCollection,
+    DebugBlockStatusData, DebugStatus, DebugStatusResponse, MissedHeightInfo, ProductionAtHeight,
+    TrieCacheStatsView, ValidatorStatus,
 };
 use near_client_primitives::types::Error;
 use near_client_primitives::{
@@ -27,7 +28,7 @@ use near_primitives::{
 };
 use near_store::DBCol;
 use std::cmp::{max, min};
-use std::collections::{HashMap, HashSet};
+use std::collections::{HashMap, HashSet, VecDeque};
 
 use near_client_primitives::debug::{DebugBlockStatus, DebugChunkStatus};
 use near_network::types::{ConnectedPeerInfo, NetworkInfo, PeerType};
@@ -141,6 +142,47 @@ impl BlockProductionTracker {
     }
 }
 
+/// Number of blocks produced by this node for which to keep the timestamps of the production
+/// stages for debug purposes.
+pub const PRODUCTION_TIMINGS_BUFFER_SIZE: usize = 1000;
+
+/// Ring buffer with the timestamps of the production stages of the last blocks produced by this
+/// node, oldest first.
+pub struct BlockProductionTimings(VecDeque<BlockProductionTimingView>);
+
+impl BlockProductionTimings {
+    pub(crate) fn new() -> Self {
+        Self(VecDeque::with_capacity(PRODUCTION_TIMINGS_BUFFER_SIZE))
+    }
+
+    /// Record a freshly produced block, evicting the oldest one if the buffer is full.
+    pub(crate) fn record_block_produced(&mut self, timing: BlockProductionTimingView) {
+        if self.0.len() == PRODUCTION_TIMINGS_BUFFER_SIZE {
+            self.0.pop_front();
+        }
+        self.0.push_back(timing);
+    }
+
+    /// Record that a block produced by this node was sent out to the network.
+    pub(crate) fn record_block_broadcast(&mut self, block_hash: &CryptoHash) {
+        if let Some(timing) = self.0.iter_mut().rev().find(|t| &t.block_hash == block_hash) {
+            timing.block_broadcast.get_or_insert_with(Clock::utc);
+        }
+    }
+
+    /// Time when the given block was produced, if it was produced by this node recently.
+    pub(crate) fn get_produced_time(
+        &self,
+        block_hash: &CryptoHash,
+    ) -> Option<chrono::DateTime<chrono::Utc>> {
+        self.0.iter().rev().find(|t| &t.block_hash == block_hash).map(|t| t.block_produced)
+    }
+
+    pub(crate) fn to_vec(&self) -> Vec<BlockProductionTimingView> {
+        self.0.iter().cloned().collect()
+    }
+}
+
 impl Handler<WithSpanContext<DebugStatus>> for ClientActor {
     type Result = Result<DebugStatusResponse, StatusError>;
 
@@ -176,11 +218,37 @@ impl Handler<WithSpanContext<DebugStatus>> for ClientActor {
             DebugStatus::ChainProcessingStatus => Ok(DebugStatusResponse::ChainProcessingStatus(
                 self.client.chain.get_chain_processing_info(),
             )),
+            DebugStatus::TrieCacheStats => {
+                Ok(DebugStatusResponse::TrieCacheStats(self.get_trie_cache_stats()))
+            }
+            DebugStatus::BlockProductionTimings => Ok(DebugStatusResponse::BlockProductionTimings(
+                self.client.block_production_timings.to_vec(),
+            )),
         }
     }
 }
 
 impl ClientActor {
+    fn get_trie_cache_stats(&self) -> Vec<TrieCacheStatsView> {
+        self.client
+            .runtime_adapter
+            .get_tries()
+            .cache_stats()
+            .into_iter()
+            .map(|stats| TrieCacheStatsView {
+                shard_id: stats.shard_id,
+                is_view: stats.is_view,
+                len: stats.len as u64,
+                total_size: stats.total_size,
+                total_size_limit: stats.total_size_limit,
+                deletions_queue_len: stats.deletions_queue_len as u64,
+                capacity_evictions: stats.capacity_evictions,
+                deletion_evictions: stats.deletion_evictions,
+                too_large: stats.too_large,
+            })
+            .collect()
+    }
+
     // Gets a list of block producers and chunk-only producers for a given epoch.
     fn get_producers_for_epoch(
         &self,
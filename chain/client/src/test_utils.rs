@@ -222,7 +222,11 @@ pub fn setup(
         runtime.clone(),
         &chain_genesis,
         doomslug_threshold_mode,
-        ChainConfig { save_trie_changes: !archive, background_migration_threads: 1 },
+        ChainConfig {
+            save_trie_changes: !archive,
+            background_migration_threads: 1,
+            apply_chunk_cache_size: 0,
+        },
     )
     .unwrap();
     let genesis_block = chain.get_block(&chain.genesis().hash().clone()).unwrap();
@@ -312,7 +316,11 @@ pub fn setup_only_view(
         runtime.clone(),
         &chain_genesis,
         doomslug_threshold_mode,
-        ChainConfig { save_trie_changes: !archive, background_migration_threads: 1 },
+        ChainConfig {
+            save_trie_changes: !archive,
+            background_migration_threads: 1,
+            apply_chunk_cache_size: 0,
+        },
     )
     .unwrap();
 
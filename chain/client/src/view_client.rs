@@ -299,6 +299,15 @@ impl ViewClientActor {
     }
 
     fn handle_query(&mut self, msg: Query) -> Result<QueryResponse, QueryError> {
+        // Contract calls and state views can read large parts of the trie,
+        // which a degraded disk can't afford next to block processing.
+        if matches!(msg.request, QueryRequest::CallFunction { .. } | QueryRequest::ViewState { .. })
+            && self.chain.store().store().should_shed_load("query")
+        {
+            return Err(QueryError::InternalError {
+                error_message: "disk latency is degraded, view calls are rejected".to_string(),
+            });
+        }
         let header = self.get_block_header_by_reference(&msg.block_reference);
         let header = match header {
             Ok(Some(header)) => Ok(header),
@@ -511,6 +520,9 @@ impl ViewClientActor {
     }
 
     fn check_state_sync_request(&self) -> bool {
+        if self.chain.store().store().should_shed_load("state_sync") {
+            return false;
+        }
         let mut cache = self.state_request_cache.lock().expect(POISONED_LOCK_ERR);
         let now = Clock::instant();
         let cutoff = now - self.config.view_client_throttle_period;
@@ -1,6 +1,5 @@
 use std::collections::HashMap;
 
-use near_primitives::checked_feature;
 use near_primitives::epoch_manager::epoch_info::EpochInfo;
 use near_primitives::epoch_manager::{EpochConfig, RngSeed};
 use near_primitives::errors::EpochError;
@@ -8,6 +7,7 @@ use near_primitives::types::validator_stake::ValidatorStake;
 use near_primitives::types::{
     AccountId, Balance, NumSeats, ProtocolVersion, ValidatorKickoutReason,
 };
+use near_primitives::version::ProtocolFeature;
 
 /// Find threshold of stake per seat, given provided stakes and required number of seats.
 pub(crate) fn find_threshold(
@@ -48,7 +48,7 @@ pub fn proposals_to_epoch_info(
     next_version: ProtocolVersion,
     last_epoch_version: ProtocolVersion,
 ) -> Result<EpochInfo, EpochError> {
-    if checked_feature!("stable", AliasValidatorSelectionAlgorithm, last_epoch_version) {
+    if ProtocolFeature::AliasValidatorSelectionAlgorithm.enabled(last_epoch_version) {
         return crate::validator_selection::proposals_to_epoch_info(
             epoch_config,
             rng_seed,
@@ -4,9 +4,10 @@ use num_rational::Rational32;
 use primitive_types::U256;
 
 use near_chain_configs::GenesisConfig;
-use near_primitives::checked_feature;
 use near_primitives::types::{AccountId, Balance, BlockChunkValidatorStats};
-use near_primitives::version::{ProtocolVersion, ENABLE_INFLATION_PROTOCOL_VERSION};
+use near_primitives::version::{
+    ProtocolFeature, ProtocolVersion, ENABLE_INFLATION_PROTOCOL_VERSION,
+};
 
 pub(crate) const NUM_NS_IN_SECOND: u64 = 1_000_000_000;
 pub const NUM_SECONDS_IN_A_YEAR: u64 = 24 * 60 * 60 * 365;
@@ -60,7 +61,7 @@ impl RewardCalculator {
             self.protocol_reward_rate
         };
         let epoch_total_reward: u128 =
-            if checked_feature!("stable", RectifyInflation, protocol_version) {
+            if ProtocolFeature::RectifyInflation.enabled(protocol_version) {
                 (U256::from(*max_inflation_rate.numer() as u64)
                     * U256::from(total_supply)
                     * U256::from(epoch_duration)
@@ -109,7 +110,7 @@ impl RewardCalculator {
             let online_min_denom = U256::from(*self.online_min_threshold.denom() as u64);
             // If average of produced blocks below online min threshold, validator gets 0 reward.
             let chunk_only_producers_enabled =
-                checked_feature!("stable", ChunkOnlyProducers, protocol_version);
+                ProtocolFeature::ChunkOnlyProducers.enabled(protocol_version);
             let reward = if average_produced_numer * online_min_denom
                 < online_min_numer * average_produced_denom
                 || (chunk_only_producers_enabled
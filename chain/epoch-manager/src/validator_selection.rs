@@ -7,6 +7,7 @@ use near_primitives::types::validator_stake::ValidatorStake;
 use near_primitives::types::{
     AccountId, Balance, ProtocolVersion, ValidatorId, ValidatorKickoutReason,
 };
+use near_primitives::version::ProtocolFeature;
 use num_rational::Ratio;
 use std::cmp::{self, Ordering};
 use std::collections::hash_map;
@@ -54,7 +55,7 @@ pub fn proposals_to_epoch_info(
         last_version,
     );
     let (chunk_producer_proposals, chunk_producers, cp_stake_threshold) =
-        if checked_feature!("stable", ChunkOnlyProducers, next_version) {
+        if ProtocolFeature::ChunkOnlyProducers.enabled(next_version) {
             let mut chunk_producer_proposals = order_proposals(proposals.into_values());
             let max_cp_selected = max_bp_selected
                 + (epoch_config.validator_selection_config.num_chunk_only_producer_seats as usize);
@@ -107,8 +108,7 @@ pub fn proposals_to_epoch_info(
         all_validators.push(bp);
     }
 
-    let chunk_producers_settlement = if checked_feature!("stable", ChunkOnlyProducers, next_version)
-    {
+    let chunk_producers_settlement = if ProtocolFeature::ChunkOnlyProducers.enabled(next_version) {
         let minimum_validators_per_shard =
             epoch_config.validator_selection_config.minimum_validators_per_shard as usize;
         let shard_assignment =
@@ -24,22 +24,24 @@ pub(crate) async fn convert_transactions_sir_into_local_receipts(
             .map(|tx| {
                 let cost = tx_cost(
                     &protocol_config.runtime_config.transaction_costs,
-                    &near_primitives::transaction::Transaction {
-                        signer_id: tx.transaction.signer_id.clone(),
-                        public_key: tx.transaction.public_key.clone(),
-                        nonce: tx.transaction.nonce,
-                        receiver_id: tx.transaction.receiver_id.clone(),
-                        block_hash: block.header.hash,
-                        actions: tx
-                            .transaction
-                            .actions
-                            .clone()
-                            .into_iter()
-                            .map(|action| {
-                                near_primitives::transaction::Action::try_from(action).unwrap()
-                            })
-                            .collect(),
-                    },
+                    &near_primitives::transaction::Transaction::V0(
+                        near_primitives::transaction::TransactionV0 {
+                            signer_id: tx.transaction.signer_id.clone(),
+                            public_key: tx.transaction.public_key.clone(),
+                            nonce: tx.transaction.nonce,
+                            receiver_id: tx.transaction.receiver_id.clone(),
+                            block_hash: block.header.hash,
+                            actions: tx
+                                .transaction
+                                .actions
+                                .clone()
+                                .into_iter()
+                                .map(|action| {
+                                    near_primitives::transaction::Action::try_from(action).unwrap()
+                                })
+                                .collect(),
+                        },
+                    ),
                     prev_block_gas_price,
                     true,
                     protocol_config.protocol_version.clone(),
@@ -59,6 +61,10 @@ pub(crate) async fn convert_transactions_sir_into_local_receipts(
                         output_data_receivers: vec![],
                         input_data_ids: vec![],
                         actions: tx.transaction.actions.clone(),
+                        priority: near_primitives::receipt::ActionReceipt::priority_from_fee(
+                            tx.transaction.priority_fee,
+                            protocol_config.protocol_version,
+                        ),
                     },
                 }
             })
@@ -1,4 +1,5 @@
-use near_primitives::types::MaybeBlockId;
+use near_primitives::serialize::dec_format;
+use near_primitives::types::{Balance, Gas, MaybeBlockId};
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 
@@ -13,6 +14,27 @@ pub struct RpcGasPriceResponse {
     pub gas_price_view: near_primitives::views::GasPriceView,
 }
 
+#[derive(Serialize, Deserialize, Debug, arbitrary::Arbitrary)]
+pub struct RpcPessimisticGasPriceRequest {
+    pub block_id: MaybeBlockId,
+    /// Gas attached to the actions of the transaction.
+    pub prepaid_gas: Gas,
+    /// Whether the transaction is sent by the account to itself, in which
+    /// case the first receipt is executed in the same block.
+    #[serde(default)]
+    pub signer_is_receiver: bool,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct RpcPessimisticGasPriceResponse {
+    /// Gas price of the block.
+    #[serde(with = "dec_format")]
+    pub gas_price: Balance,
+    /// Gas price at which the prepaid gas of the transaction is purchased.
+    #[serde(with = "dec_format")]
+    pub pessimistic_gas_price: Balance,
+}
+
 #[derive(thiserror::Error, Debug, Serialize, Deserialize)]
 #[serde(tag = "name", content = "info", rename_all = "SCREAMING_SNAKE_CASE")]
 pub enum RpcGasPriceError {
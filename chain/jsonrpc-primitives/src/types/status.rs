@@ -1,5 +1,6 @@
 use near_client_primitives::debug::{
-    DebugBlockStatusData, EpochInfoView, TrackedShardsView, ValidatorStatus,
+    BlockProductionTimingView, DebugBlockStatusData, EpochInfoView, TrackedShardsView,
+    TrieCacheStatsView, ValidatorStatus,
 };
 use near_primitives::views::{
     CatchupStatusView, ChainProcessingInfo, NetworkGraphView, PeerStoreView,
@@ -29,6 +30,10 @@ pub enum DebugStatusResponse {
     // The state parts already requested.
     RequestedStateParts(Vec<RequestedStatePartsView>),
     NetworkGraph(NetworkGraphView),
+    // Size and eviction counters of the shard caches.
+    TrieCacheStats(Vec<TrieCacheStatsView>),
+    // Timestamps of the production stages of the last blocks produced by this node, oldest first.
+    BlockProductionTimings(Vec<BlockProductionTimingView>),
 }
 
 #[cfg(feature = "debug_types")]
@@ -1,8 +1,10 @@
 use serde_json::Value;
 
-use near_client_primitives::types::GetGasPriceError;
+use near_client_primitives::types::{GetGasPriceError, GetProtocolConfigError};
 use near_jsonrpc_primitives::errors::RpcParseError;
-use near_jsonrpc_primitives::types::gas_price::{RpcGasPriceError, RpcGasPriceRequest};
+use near_jsonrpc_primitives::types::gas_price::{
+    RpcGasPriceError, RpcGasPriceRequest, RpcPessimisticGasPriceRequest,
+};
 use near_primitives::types::MaybeBlockId;
 
 use super::{parse_params, RpcFrom, RpcRequest};
@@ -13,6 +15,12 @@ impl RpcRequest for RpcGasPriceRequest {
     }
 }
 
+impl RpcRequest for RpcPessimisticGasPriceRequest {
+    fn parse(value: Option<Value>) -> Result<Self, RpcParseError> {
+        parse_params::<Self>(value)
+    }
+}
+
 impl RpcFrom<actix::MailboxError> for RpcGasPriceError {
     fn rpc_from(error: actix::MailboxError) -> Self {
         Self::InternalError { error_message: error.to_string() }
@@ -38,3 +46,21 @@ impl RpcFrom<GetGasPriceError> for RpcGasPriceError {
         }
     }
 }
+
+impl RpcFrom<GetProtocolConfigError> for RpcGasPriceError {
+    fn rpc_from(error: GetProtocolConfigError) -> Self {
+        match error {
+            GetProtocolConfigError::UnknownBlock(error_message) => {
+                Self::UnknownBlock { error_message }
+            }
+            GetProtocolConfigError::IOError(error_message) => Self::InternalError { error_message },
+            GetProtocolConfigError::Unreachable(ref error_message) => {
+                tracing::warn!(target: "jsonrpc", "Unreachable error occurred: {}", error_message);
+                crate::metrics::RPC_UNREACHABLE_ERROR_COUNT
+                    .with_label_values(&["RpcGasPriceError"])
+                    .inc();
+                Self::InternalError { error_message: error.to_string() }
+            }
+        }
+    }
+}
@@ -3,7 +3,6 @@ use serde_json::Value;
 
 use near_jsonrpc_primitives::errors::RpcParseError;
 use near_jsonrpc_primitives::errors::{RpcError, ServerError};
-use near_primitives::borsh::BorshDeserialize;
 
 mod blocks;
 mod changes;
@@ -93,6 +92,6 @@ fn parse_signed_transaction(
     let (encoded,) = parse_params::<(String,)>(value)?;
     let bytes = near_primitives::serialize::from_base64(&encoded)
         .map_err(|err| RpcParseError(err.to_string()))?;
-    Ok(near_primitives::transaction::SignedTransaction::try_from_slice(&bytes)
+    Ok(near_primitives::transaction::SignedTransaction::from_borsh_bytes(&bytes)
         .map_err(|err| RpcParseError(format!("Failed to decode transaction: {}", err)))?)
 }
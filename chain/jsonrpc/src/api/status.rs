@@ -49,6 +49,14 @@ impl RpcFrom<near_client_primitives::debug::DebugStatusResponse>
                     x,
                 )
             }
+            near_client_primitives::debug::DebugStatusResponse::TrieCacheStats(x) => {
+                near_jsonrpc_primitives::types::status::DebugStatusResponse::TrieCacheStats(x)
+            }
+            near_client_primitives::debug::DebugStatusResponse::BlockProductionTimings(x) => {
+                near_jsonrpc_primitives::types::status::DebugStatusResponse::BlockProductionTimings(
+                    x,
+                )
+            }
         }
     }
 }
@@ -0,0 +1,249 @@
+//! Minimal Ethereum-style JSON-RPC façade.
+//!
+//! Translates a small subset of the Ethereum JSON-RPC API onto NEAR queries so
+//! that monitoring systems built on EVM tooling can check liveness and account
+//! balances of a node.  Supported methods:
+//!
+//! * `eth_chainId` returns the chain id from [`EthRpcConfig`],
+//! * `eth_blockNumber` returns the height of the latest block,
+//! * `eth_getBalance` returns the liquid balance of an implicit account, in
+//!   yoctoNEAR.  The address is the 64 hex characters of the implicit account
+//!   id, optionally prefixed with `0x`.  Unknown accounts have zero balance.
+//!
+//! The façade is served on its own address and is only compiled with the
+//! `eth_rpc` feature.
+
+use actix::Addr;
+use actix_web::{middleware, web, App, HttpResponse, HttpServer};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tracing::info;
+
+use near_client::{GetBlock, Query, ViewClientActor};
+use near_jsonrpc_primitives::errors::RpcError;
+use near_jsonrpc_primitives::message::{Message, Request};
+use near_jsonrpc_primitives::types::blocks::RpcBlockError;
+use near_jsonrpc_primitives::types::query::RpcQueryError;
+use near_o11y::WithSpanContextExt;
+use near_primitives::types::{
+    AccountId, BlockHeight, BlockId, BlockReference, Finality, SyncCheckpoint,
+};
+use near_primitives::views::{QueryRequest, QueryResponse, QueryResponseKind};
+
+use crate::api::RpcFrom;
+
+fn default_cors_allowed_origins() -> Vec<String> {
+    vec!["*".to_owned()]
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EthRpcConfig {
+    pub addr: String,
+    /// Chain id reported by `eth_chainId`.  NEAR has no Ethereum chain id, so
+    /// it has to be chosen by the operator.
+    pub chain_id: u64,
+    #[serde(default = "default_cors_allowed_origins")]
+    pub cors_allowed_origins: Vec<String>,
+}
+
+impl EthRpcConfig {
+    pub fn new(addr: &str, chain_id: u64) -> Self {
+        Self {
+            addr: addr.to_owned(),
+            chain_id,
+            cors_allowed_origins: default_cors_allowed_origins(),
+        }
+    }
+}
+
+struct EthRpcHandler {
+    view_client_addr: Addr<ViewClientActor>,
+    chain_id: u64,
+}
+
+impl EthRpcHandler {
+    async fn process(&self, message: Message) -> Message {
+        let id = message.id();
+        match message {
+            Message::Request(request) => Message::response(id, self.process_request(request).await),
+            _ => Message::error(RpcError::parse_error(
+                "JSON RPC Request format was expected".to_owned(),
+            )),
+        }
+    }
+
+    async fn process_request(&self, request: Request) -> Result<Value, RpcError> {
+        match request.method.as_ref() {
+            "eth_blockNumber" => Ok(quantity(self.block_number().await?)),
+            "eth_chainId" => Ok(quantity(self.chain_id)),
+            "eth_getBalance" => {
+                let (account_id, block_reference) = parse_get_balance_params(request.params)?;
+                Ok(quantity(self.get_balance(account_id, block_reference).await?))
+            }
+            _ => Err(RpcError::method_not_found(request.method)),
+        }
+    }
+
+    async fn view_client_send<M, T, E, F>(&self, msg: M) -> Result<T, E>
+    where
+        ViewClientActor: actix::Handler<near_o11y::WithSpanContext<M>>,
+        M: actix::Message<Result = Result<T, F>> + Send + 'static,
+        M::Result: Send,
+        E: RpcFrom<F>,
+        E: RpcFrom<actix::MailboxError>,
+    {
+        self.view_client_addr
+            .send(msg.with_span_context())
+            .await
+            .map_err(RpcFrom::rpc_from)?
+            .map_err(RpcFrom::rpc_from)
+    }
+
+    async fn block_number(&self) -> Result<BlockHeight, RpcBlockError> {
+        let block_view = self.view_client_send(GetBlock(BlockReference::latest())).await?;
+        Ok(block_view.header.height)
+    }
+
+    async fn get_balance(
+        &self,
+        account_id: AccountId,
+        block_reference: BlockReference,
+    ) -> Result<u128, RpcQueryError> {
+        let response: Result<QueryResponse, RpcQueryError> = self
+            .view_client_send(Query::new(block_reference, QueryRequest::ViewAccount { account_id }))
+            .await;
+        match response {
+            Ok(response) => match response.kind {
+                QueryResponseKind::ViewAccount(account_view) => Ok(account_view.amount),
+                kind => Err(RpcQueryError::InternalError {
+                    error_message: format!("unexpected query response: {:?}", kind),
+                }),
+            },
+            // Ethereum reports a zero balance for addresses it has never seen.
+            Err(RpcQueryError::UnknownAccount { .. }) => Ok(0),
+            Err(err) => Err(err),
+        }
+    }
+}
+
+/// Formats a number as an Ethereum quantity, i.e. hex without leading zeros.
+fn quantity(value: impl std::fmt::LowerHex) -> Value {
+    Value::String(format!("0x{:x}", value))
+}
+
+fn parse_quantity(value: &str) -> Option<u64> {
+    u64::from_str_radix(value.strip_prefix("0x")?, 16).ok()
+}
+
+/// Parses the `[address, block]` params of `eth_getBalance`.  The block is
+/// optional and defaults to `latest`.
+fn parse_get_balance_params(
+    params: Option<Value>,
+) -> Result<(AccountId, BlockReference), RpcError> {
+    let params: Vec<String> = match params {
+        Some(params) => serde_json::from_value(params).map_err(|err| {
+            RpcError::invalid_params(format!("expected [address, block]: {}", err))
+        })?,
+        None => vec![],
+    };
+    match params.as_slice() {
+        [address] => Ok((parse_address(address)?, parse_block_tag("latest")?)),
+        [address, block] => Ok((parse_address(address)?, parse_block_tag(block)?)),
+        _ => Err(RpcError::invalid_params("expected [address, block]")),
+    }
+}
+
+/// Parses an implicit account id, optionally prefixed with `0x`.
+fn parse_address(address: &str) -> Result<AccountId, RpcError> {
+    let account_id = address.strip_prefix("0x").unwrap_or(address).to_ascii_lowercase();
+    match account_id.parse::<AccountId>() {
+        Ok(account_id) if account_id.is_implicit() => Ok(account_id),
+        _ => Err(RpcError::invalid_params(format!(
+            "`{}` is not an implicit account id, only 64 hex character addresses are supported",
+            address
+        ))),
+    }
+}
+
+fn parse_block_tag(block: &str) -> Result<BlockReference, RpcError> {
+    match block {
+        "latest" | "pending" => Ok(BlockReference::latest()),
+        "safe" | "finalized" => Ok(BlockReference::Finality(Finality::Final)),
+        "earliest" => Ok(BlockReference::SyncCheckpoint(SyncCheckpoint::Genesis)),
+        number => match parse_quantity(number) {
+            Some(height) => Ok(BlockReference::BlockId(BlockId::Height(height))),
+            None => Err(RpcError::invalid_params(format!("invalid block `{}`", block))),
+        },
+    }
+}
+
+async fn eth_rpc_handler(
+    message: web::Json<Message>,
+    handler: web::Data<EthRpcHandler>,
+) -> HttpResponse {
+    HttpResponse::Ok().json(&handler.process(message.0).await)
+}
+
+/// Starts the HTTP server of the Ethereum JSON-RPC façade.
+pub fn start_eth_rpc(
+    config: EthRpcConfig,
+    view_client_addr: Addr<ViewClientActor>,
+) -> actix_web::dev::ServerHandle {
+    let EthRpcConfig { addr, chain_id, cors_allowed_origins } = config;
+    info!(target:"network", "Starting Ethereum JSON RPC server at {}", addr);
+    let server = HttpServer::new(move || {
+        App::new()
+            .wrap(crate::get_cors(&cors_allowed_origins))
+            .app_data(web::Data::new(EthRpcHandler {
+                view_client_addr: view_client_addr.clone(),
+                chain_id,
+            }))
+            .wrap(middleware::Logger::default())
+            .service(web::resource("/").route(web::post().to(eth_rpc_handler)))
+    })
+    .bind(addr)
+    .unwrap()
+    .workers(2)
+    .shutdown_timeout(5)
+    .disable_signals()
+    .run();
+    let handle = server.handle();
+    tokio::spawn(server);
+    handle
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_quantity() {
+        assert_eq!(quantity(0u64), "0x0");
+        assert_eq!(quantity(1024u64), "0x400");
+        assert_eq!(quantity(10u128.pow(24)), "0xd3c21bcecceda1000000");
+        assert_eq!(parse_quantity("0x400"), Some(1024));
+        assert_eq!(parse_quantity("400"), None);
+        assert_eq!(parse_quantity("0xzz"), None);
+    }
+
+    #[test]
+    fn test_parse_get_balance_params() {
+        let implicit = "a".repeat(64);
+        let (account_id, block_reference) =
+            parse_get_balance_params(Some(serde_json::json!([format!("0x{}", implicit)]))).unwrap();
+        assert_eq!(account_id.as_str(), implicit);
+        assert_eq!(block_reference, BlockReference::latest());
+
+        let (_, block_reference) =
+            parse_get_balance_params(Some(serde_json::json!([implicit.to_uppercase(), "0x10"])))
+                .unwrap();
+        assert_eq!(block_reference, BlockReference::BlockId(BlockId::Height(16)));
+
+        // Ethereum addresses have no NEAR counterpart.
+        let eth_address = format!("0x{}", "a".repeat(40));
+        assert!(parse_get_balance_params(Some(serde_json::json!([eth_address]))).is_err());
+        assert!(parse_get_balance_params(Some(serde_json::json!(["test.near"]))).is_err());
+        assert!(parse_get_balance_params(Some(serde_json::json!([implicit, "tip"]))).is_err());
+        assert!(parse_get_balance_params(None).is_err());
+    }
+}
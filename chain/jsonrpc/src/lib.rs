@@ -30,14 +30,18 @@ use near_jsonrpc_primitives::types::config::RpcProtocolConfigResponse;
 use near_o11y::metrics::{prometheus, Encoder, TextEncoder};
 use near_primitives::hash::CryptoHash;
 use near_primitives::transaction::SignedTransaction;
-use near_primitives::types::{AccountId, BlockHeight};
+use near_primitives::types::{AccountId, BlockHeight, BlockReference};
 use near_primitives::views::FinalExecutionOutcomeViewEnum;
 
 mod api;
+#[cfg(feature = "eth_rpc")]
+mod eth;
 mod metrics;
 
 use api::RpcRequest;
 pub use api::{RpcFrom, RpcInto};
+#[cfg(feature = "eth_rpc")]
+pub use eth::{start_eth_rpc, EthRpcConfig};
 use near_o11y::{WithSpanContext, WithSpanContextExt};
 
 #[derive(Serialize, Deserialize, Clone, Copy, Debug)]
@@ -334,6 +338,9 @@ impl JsonRpcHandler {
                 })
                 .await
             }
+            "EXPERIMENTAL_pessimistic_gas_price" => {
+                process_method_call(request, |params| self.pessimistic_gas_price(params)).await
+            }
             "EXPERIMENTAL_protocol_config" => {
                 process_method_call(request, |params| self.protocol_config(params)).await
             }
@@ -504,7 +511,7 @@ impl JsonRpcHandler {
     > {
         let (tx_hash, account_id) = match &tx_info {
             near_jsonrpc_primitives::types::transactions::TransactionInfo::Transaction(tx) => {
-                (tx.get_hash(), tx.transaction.signer_id.clone())
+                (tx.get_hash(), tx.transaction.signer_id().clone())
             }
             near_jsonrpc_primitives::types::transactions::TransactionInfo::TransactionId {
                 hash,
@@ -605,7 +612,7 @@ impl JsonRpcHandler {
     ) -> Result<ProcessTxResponse, near_jsonrpc_primitives::types::transactions::RpcTransactionError>
     {
         let tx_hash = tx.get_hash();
-        let signer_account_id = tx.transaction.signer_id.clone();
+        let signer_account_id = tx.transaction.signer_id().clone();
         let response = self
             .client_addr
             .send(
@@ -794,6 +801,12 @@ impl JsonRpcHandler {
                     "/debug/api/requested_state_parts" => {
                         self.client_send(DebugStatus::RequestedStateParts).await?.rpc_into()
                     }
+                    "/debug/api/trie_cache_stats" => {
+                        self.client_send(DebugStatus::TrieCacheStats).await?.rpc_into()
+                    }
+                    "/debug/api/block_production_timings" => {
+                        self.client_send(DebugStatus::BlockProductionTimings).await?.rpc_into()
+                    }
                     "/debug/api/peer_store" => self
                         .peer_manager_send(near_network::debug::GetDebugStatus::PeerStore)
                         .await?
@@ -1020,6 +1033,39 @@ impl JsonRpcHandler {
         Ok(near_jsonrpc_primitives::types::gas_price::RpcGasPriceResponse { gas_price_view })
     }
 
+    /// Returns the gas price at which the prepaid gas of a transaction with
+    /// the given attached gas would be purchased.
+    async fn pessimistic_gas_price(
+        &self,
+        request_data: near_jsonrpc_primitives::types::gas_price::RpcPessimisticGasPriceRequest,
+    ) -> Result<
+        near_jsonrpc_primitives::types::gas_price::RpcPessimisticGasPriceResponse,
+        near_jsonrpc_primitives::types::gas_price::RpcGasPriceError,
+    > {
+        let block_reference = match request_data.block_id.clone() {
+            Some(block_id) => BlockReference::BlockId(block_id),
+            None => BlockReference::latest(),
+        };
+        let gas_price =
+            self.view_client_send(GetGasPrice { block_id: request_data.block_id }).await?.gas_price;
+        let config_view = self.view_client_send(GetProtocolConfig(block_reference)).await?;
+        let pessimistic_gas_price = node_runtime::config::pessimistic_gas_price(
+            &config_view.runtime_config.transaction_costs,
+            gas_price,
+            request_data.prepaid_gas,
+            request_data.signer_is_receiver,
+        )
+        .map_err(|err| {
+            near_jsonrpc_primitives::types::gas_price::RpcGasPriceError::InternalError {
+                error_message: err.to_string(),
+            }
+        })?;
+        Ok(near_jsonrpc_primitives::types::gas_price::RpcPessimisticGasPriceResponse {
+            gas_price,
+            pessimistic_gas_price,
+        })
+    }
+
     async fn validators(
         &self,
         request_data: near_jsonrpc_primitives::types::validator::RpcValidatorRequest,
@@ -1427,6 +1473,7 @@ async fn display_debug_html(
         "chain_n_chunk_info" => Some(debug_page_string!("chain_n_chunk_info.html", handler)),
         "sync" => Some(debug_page_string!("sync.html", handler)),
         "validator" => Some(debug_page_string!("validator.html", handler)),
+        "trie_cache" => Some(debug_page_string!("trie_cache.html", handler)),
         _ => None,
     };
 
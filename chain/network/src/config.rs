@@ -75,6 +75,17 @@ pub struct Tier1 {
     pub advertise_proxies_interval: time::Duration,
 }
 
+/// Limits on serving state sync requests of other nodes, so that serving
+/// state parts doesn't slow down block processing.
+#[derive(Clone, Default)]
+pub struct StateSyncServing {
+    /// Maximal number of bytes of state parts served per second, on average.
+    /// Requests exceeding the budget are dropped. No limit if None.
+    pub max_bytes_per_second: Option<u64>,
+    /// If not empty, state sync requests are served only to these peers.
+    pub allowed_peers: Vec<PeerId>,
+}
+
 /// Validated configuration for the peer-to-peer manager.
 #[derive(Clone)]
 pub struct NetworkConfig {
@@ -137,6 +148,8 @@ pub struct NetworkConfig {
     pub routing_table_update_rate_limit: demux::RateLimit,
     /// Config of the TIER1 network.
     pub tier1: Option<Tier1>,
+    /// Limits on serving state sync requests.
+    pub state_sync_serving: StateSyncServing,
 
     // Whether to ignore tombstones some time after startup.
     //
@@ -257,6 +270,15 @@ impl NetworkConfig {
             accounts_data_broadcast_rate_limit: demux::RateLimit { qps: 0.1, burst: 1 },
             routing_table_update_rate_limit: demux::RateLimit { qps: 0.5, burst: 1 },
             tier1: Some(Tier1 { advertise_proxies_interval: time::Duration::minutes(15) }),
+            state_sync_serving: StateSyncServing {
+                max_bytes_per_second: cfg.state_sync_serve_max_bytes_per_second,
+                allowed_peers: cfg
+                    .state_sync_serve_allowed_peers
+                    .iter()
+                    .map(|peer_id| Ok(PeerId::new(peer_id.parse()?)))
+                    .collect::<anyhow::Result<_>>()
+                    .context("state_sync_serve_allowed_peers")?,
+            },
             inbound_disabled: cfg.experimental.inbound_disabled,
             skip_tombstones: if cfg.experimental.skip_sending_tombstones_seconds > 0 {
                 Some(time::Duration::seconds(cfg.experimental.skip_sending_tombstones_seconds))
@@ -326,6 +348,7 @@ impl NetworkConfig {
                 // It should rather be triggered manually in tests.
                 advertise_proxies_interval: time::Duration::hours(1000),
             }),
+            state_sync_serving: StateSyncServing::default(),
             skip_tombstones: None,
             event_sink: Sink::null(),
         }
@@ -364,6 +387,9 @@ impl NetworkConfig {
         self.accounts_data_broadcast_rate_limit
             .validate()
             .context("accounts_Data_broadcast_rate_limit")?;
+        if self.state_sync_serving.max_bytes_per_second == Some(0) {
+            anyhow::bail!("state_sync_serve_max_bytes_per_second must be positive.");
+        }
         Ok(VerifiedConfig { node_id: self.node_id(), inner: self })
     }
 }
@@ -114,6 +114,17 @@ pub struct Config {
     /// It can be IP:Port or IP (to blacklist all connections coming from this address).
     #[serde(default)]
     pub blacklist: Vec<String>,
+    /// Maximal number of bytes of state parts served to other nodes per second, on average.
+    /// State sync requests exceeding it are dropped, to keep serving them from slowing down
+    /// block processing. No limit if not set.
+    #[serde(default)]
+    pub state_sync_serve_max_bytes_per_second: Option<u64>,
+    /// List of PeerIds of the nodes which are allowed to request state parts from this node.
+    /// If empty, state sync requests of all nodes are served.
+    /// Example:
+    ///   ["ed25519:86EtEy7epneKyrcJwSWP7zsisTkfDRH5CFVszt4qiQYw"]
+    #[serde(default)]
+    pub state_sync_serve_allowed_peers: Vec<String>,
     /// Time to persist Accounts Id in the router without removing them in seconds.
     #[serde(default = "default_ttl_account_id_router")]
     pub ttl_account_id_router: Duration,
@@ -221,6 +232,8 @@ impl Default for Config {
             skip_sync_wait: false,
             ban_window: Duration::from_secs(3 * 60 * 60),
             blacklist: vec![],
+            state_sync_serve_max_bytes_per_second: None,
+            state_sync_serve_allowed_peers: vec![],
             ttl_account_id_router: default_ttl_account_id_router(),
             peer_stats_period: default_peer_stats_period(),
             monitor_peers_max_period: default_monitor_peers_max_period(),
@@ -99,6 +99,17 @@ pub struct AccountData {
     pub timestamp: time::Utc,
 }
 
+/// Payload signable by an account key.
+///
+/// Intermediate representation of `proto::AccountKeyPayload`, so that new
+/// variants can be added without changing the conversions of the existing
+/// ones. A binary that doesn't know a variant fails to parse it with
+/// `ParseAccountDataError::BadPayloadType`.
+#[derive(PartialEq, Eq, Debug)]
+pub(crate) enum AccountKeyPayload {
+    AccountData(AccountData),
+}
+
 // Limit on the size of the serialized AccountData message.
 // It is important to have such a constraint on the serialized proto,
 // because it may contain many unknown fields (which are dropped during parsing).
@@ -329,6 +340,69 @@ impl PeerMessage {
             _ => self.into(),
         }
     }
+
+    /// Priority class of the message in the outbound queue of a connection.
+    pub(crate) fn priority(&self) -> MessagePriority {
+        match self {
+            PeerMessage::Handshake(_)
+            | PeerMessage::HandshakeFailure(_, _)
+            | PeerMessage::LastEdge(_)
+            | PeerMessage::Disconnect
+            | PeerMessage::BlockRequest(_)
+            | PeerMessage::Block(_)
+            | PeerMessage::Challenge(_) => MessagePriority::Consensus,
+            PeerMessage::BlockHeadersRequest(_) | PeerMessage::BlockHeaders(_) => {
+                MessagePriority::StateSync
+            }
+            PeerMessage::Routed(routed_msg) => routed_msg.body.priority(),
+            PeerMessage::SyncRoutingTable(_)
+            | PeerMessage::RequestUpdateNonce(_)
+            | PeerMessage::SyncAccountsData(_)
+            | PeerMessage::PeersRequest
+            | PeerMessage::PeersResponse(_)
+            | PeerMessage::Transaction(_) => MessagePriority::Gossip,
+        }
+    }
+}
+
+/// Priority classes of the outbound queue of a connection, from the highest to
+/// the lowest priority. A message is only sent once all messages of higher
+/// classes have been sent, unless the higher classes ran out of budget, see
+/// [`MessagePriority::budget_bytes`].
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug, Hash, strum::IntoStaticStr)]
+pub enum MessagePriority {
+    /// Block approvals, blocks and connection management.
+    Consensus,
+    /// Chunk parts, requests and forwards.
+    Chunks,
+    /// State sync and header sync.
+    StateSync,
+    /// Everything else, e.g. transactions, routing table and peer exchange.
+    Gossip,
+}
+
+impl MessagePriority {
+    /// All classes, ordered from the highest to the lowest priority.
+    pub const ALL: [MessagePriority; 4] = [
+        MessagePriority::Consensus,
+        MessagePriority::Chunks,
+        MessagePriority::StateSync,
+        MessagePriority::Gossip,
+    ];
+
+    /// Number of bytes this class may send in a row while messages of lower
+    /// classes are waiting. Once exhausted, one lower priority message is sent
+    /// and the budget is refilled. This keeps lower classes from starving on
+    /// saturated links.
+    pub(crate) fn budget_bytes(self) -> usize {
+        match self {
+            MessagePriority::Consensus => 16 * bytesize::MIB as usize,
+            MessagePriority::Chunks => 8 * bytesize::MIB as usize,
+            MessagePriority::StateSync => 4 * bytesize::MIB as usize,
+            // Lowest class, nothing to yield to.
+            MessagePriority::Gossip => usize::MAX,
+        }
+    }
 }
 
 // TODO(#1313): Use Box
@@ -373,6 +447,22 @@ pub enum RoutedMessageBody {
 }
 
 impl RoutedMessageBody {
+    /// Priority class of the message in the outbound queue of a connection.
+    pub(crate) fn priority(&self) -> MessagePriority {
+        match self {
+            RoutedMessageBody::BlockApproval(_) => MessagePriority::Consensus,
+            RoutedMessageBody::PartialEncodedChunkRequest(_)
+            | RoutedMessageBody::PartialEncodedChunkResponse(_)
+            | RoutedMessageBody::VersionedPartialEncodedChunk(_)
+            | RoutedMessageBody::PartialEncodedChunkForward(_) => MessagePriority::Chunks,
+            RoutedMessageBody::StateRequestHeader(_, _)
+            | RoutedMessageBody::StateRequestPart(_, _, _)
+            | RoutedMessageBody::StateResponse(_)
+            | RoutedMessageBody::VersionedStateResponse(_) => MessagePriority::StateSync,
+            _ => MessagePriority::Gossip,
+        }
+    }
+
     // Return whether this message is important.
     // In routing logics, we send important messages multiple times to minimize the risk that they are
     // lost
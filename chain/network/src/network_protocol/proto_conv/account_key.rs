@@ -3,7 +3,9 @@ use super::*;
 
 use crate::network_protocol::proto;
 use crate::network_protocol::proto::account_key_payload::Payload_type as ProtoPT;
-use crate::network_protocol::{AccountData, AccountKeySignedPayload, SignedAccountData};
+use crate::network_protocol::{
+    AccountData, AccountKeyPayload, AccountKeySignedPayload, SignedAccountData,
+};
 use protobuf::{Message as _, MessageField as MF};
 
 #[derive(thiserror::Error, Debug)]
@@ -20,33 +22,22 @@ pub enum ParseAccountDataError {
     Timestamp(ParseRequiredError<ParseTimestampError>),
 }
 
-// TODO: currently a direct conversion Validator <-> proto::AccountKeyPayload is implemented.
-// When more variants are available, consider whether to introduce an intermediate
-// AccountKeyPayload enum.
-impl From<&AccountData> for proto::AccountKeyPayload {
+impl From<&AccountData> for proto::AccountData {
     fn from(x: &AccountData) -> Self {
         Self {
-            payload_type: Some(ProtoPT::AccountData(proto::AccountData {
-                peer_id: MF::some((&x.peer_id).into()),
-                account_key: MF::some((&x.account_key).into()),
-                proxies: x.proxies.iter().map(Into::into).collect(),
-                version: x.version,
-                timestamp: MF::some(utc_to_proto(&x.timestamp)),
-                ..Default::default()
-            })),
-            ..Self::default()
+            peer_id: MF::some((&x.peer_id).into()),
+            account_key: MF::some((&x.account_key).into()),
+            proxies: x.proxies.iter().map(Into::into).collect(),
+            version: x.version,
+            timestamp: MF::some(utc_to_proto(&x.timestamp)),
+            ..Default::default()
         }
     }
 }
 
-impl TryFrom<&proto::AccountKeyPayload> for AccountData {
+impl TryFrom<&proto::AccountData> for AccountData {
     type Error = ParseAccountDataError;
-    fn try_from(x: &proto::AccountKeyPayload) -> Result<Self, Self::Error> {
-        let x = match x.payload_type.as_ref().ok_or(Self::Error::BadPayloadType)? {
-            ProtoPT::AccountData(a) => a,
-            #[allow(unreachable_patterns)]
-            _ => return Err(Self::Error::BadPayloadType),
-        };
+    fn try_from(x: &proto::AccountData) -> Result<Self, Self::Error> {
         Ok(Self {
             peer_id: try_from_required(&x.peer_id).map_err(Self::Error::PeerId)?,
             account_key: try_from_required(&x.account_key).map_err(Self::Error::AccountKey)?,
@@ -58,6 +49,47 @@ impl TryFrom<&proto::AccountKeyPayload> for AccountData {
     }
 }
 
+impl From<&AccountKeyPayload> for proto::AccountKeyPayload {
+    fn from(x: &AccountKeyPayload) -> Self {
+        Self {
+            payload_type: Some(match x {
+                AccountKeyPayload::AccountData(a) => ProtoPT::AccountData(a.into()),
+            }),
+            ..Self::default()
+        }
+    }
+}
+
+impl TryFrom<&proto::AccountKeyPayload> for AccountKeyPayload {
+    type Error = ParseAccountDataError;
+    fn try_from(x: &proto::AccountKeyPayload) -> Result<Self, Self::Error> {
+        // A variant added in a newer version of the protocol is an unknown
+        // field to this binary, so `payload_type` is not set.
+        Ok(match x.payload_type.as_ref().ok_or(Self::Error::BadPayloadType)? {
+            ProtoPT::AccountData(a) => AccountKeyPayload::AccountData(a.try_into()?),
+            #[allow(unreachable_patterns)]
+            _ => return Err(Self::Error::BadPayloadType),
+        })
+    }
+}
+
+impl From<&AccountData> for proto::AccountKeyPayload {
+    fn from(x: &AccountData) -> Self {
+        Self { payload_type: Some(ProtoPT::AccountData(x.into())), ..Self::default() }
+    }
+}
+
+impl TryFrom<&proto::AccountKeyPayload> for AccountData {
+    type Error = ParseAccountDataError;
+    fn try_from(x: &proto::AccountKeyPayload) -> Result<Self, Self::Error> {
+        match AccountKeyPayload::try_from(x)? {
+            AccountKeyPayload::AccountData(a) => Ok(a),
+            #[allow(unreachable_patterns)]
+            _ => Err(Self::Error::BadPayloadType),
+        }
+    }
+}
+
 //////////////////////////////////////////
 
 #[derive(thiserror::Error, Debug)]
@@ -70,6 +102,15 @@ pub enum ParseSignedAccountDataError {
     Signature(ParseRequiredError<ParseSignatureError>),
 }
 
+impl ParseSignedAccountDataError {
+    /// Whether the payload is well-formed, but of a type this binary doesn't
+    /// know about. Such payloads were most likely produced by a newer version
+    /// and can be skipped instead of treating the sender as malicious.
+    pub fn is_unknown_payload_type(&self) -> bool {
+        matches!(self, Self::AccountData(ParseAccountDataError::BadPayloadType))
+    }
+}
+
 impl From<&SignedAccountData> for proto::AccountKeySignedPayload {
     fn from(x: &SignedAccountData) -> Self {
         Self {
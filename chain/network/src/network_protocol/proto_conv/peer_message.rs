@@ -218,11 +218,16 @@ impl TryFrom<&proto::PeerMessage> for PeerMessage {
                     .map_err(Self::Error::UpdateNonceRequest)?,
             ),
             ProtoMT::SyncAccountsData(msg) => PeerMessage::SyncAccountsData(SyncAccountsData {
-                accounts_data: try_from_slice(&msg.accounts_data)
-                    .map_err(Self::Error::SyncAccountsData)?
-                    .into_iter()
-                    .map(Arc::new)
-                    .collect(),
+                // Account data of a payload type added in a newer version is
+                // skipped, so that new variants don't break this parser.
+                accounts_data: try_from_slice_skipping(
+                    &msg.accounts_data,
+                    ParseSignedAccountDataError::is_unknown_payload_type,
+                )
+                .map_err(Self::Error::SyncAccountsData)?
+                .into_iter()
+                .map(Arc::new)
+                .collect(),
                 incremental: msg.incremental,
                 requesting_full_sync: msg.requesting_full_sync,
             }),
@@ -243,7 +248,7 @@ impl TryFrom<&proto::PeerMessage> for PeerMessage {
                 try_from_required(&br.block).map_err(Self::Error::BlockResponse)?,
             ),
             ProtoMT::Transaction(t) => PeerMessage::Transaction(
-                SignedTransaction::try_from_slice(&t.borsh).map_err(Self::Error::Transaction)?,
+                SignedTransaction::from_borsh_bytes(&t.borsh).map_err(Self::Error::Transaction)?,
             ),
             ProtoMT::Routed(r) => PeerMessage::Routed(Box::new(RoutedMessageV2 {
                 msg: RoutedMessage::try_from_slice(&r.borsh).map_err(Self::Error::Routed)?,
@@ -19,6 +19,23 @@ pub fn try_from_slice<'a, X, Y: TryFrom<&'a X>>(
     Ok(ys)
 }
 
+/// Like `try_from_slice`, but drops the elements which fail to convert with
+/// an error for which `skip` returns true.
+pub fn try_from_slice_skipping<'a, X, Y: TryFrom<&'a X>>(
+    xs: &'a [X],
+    skip: impl Fn(&Y::Error) -> bool,
+) -> Result<Vec<Y>, ParseVecError<Y::Error>> {
+    let mut ys = vec![];
+    for (idx, x) in xs.iter().enumerate() {
+        match x.try_into() {
+            Ok(y) => ys.push(y),
+            Err(source) if skip(&source) => {}
+            Err(source) => return Err(ParseVecError { idx, source }),
+        }
+    }
+    Ok(ys)
+}
+
 #[derive(thiserror::Error, Debug)]
 pub enum ParseRequiredError<E> {
     #[error("missing, while required")]
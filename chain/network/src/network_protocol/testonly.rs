@@ -20,6 +20,7 @@ use near_primitives::transaction::SignedTransaction;
 use near_primitives::types::{AccountId, BlockHeight, EpochId, StateRoot};
 use near_primitives::validator_signer::{InMemoryValidatorSigner, ValidatorSigner};
 use near_primitives::version;
+use protobuf::Message as _;
 use rand::distributions::Standard;
 use rand::Rng;
 use std::collections::HashMap;
@@ -405,6 +406,22 @@ pub fn make_signed_account_data(rng: &mut impl Rng, clock: &time::Clock) -> Sign
     make_account_data(rng, 1, clock.now_utc(), signer.public_key(), peer_id).sign(&signer).unwrap()
 }
 
+/// Signs an arbitrary `proto::AccountKeyPayload`. Use it to construct
+/// payloads that only a newer version would produce, for example ones with
+/// unknown fields or unknown payload types.
+pub fn make_signed_account_key_payload(
+    signer: &dyn ValidatorSigner,
+    payload: &proto::AccountKeyPayload,
+) -> proto::AccountKeySignedPayload {
+    let payload = payload.write_to_bytes().unwrap();
+    let signature = signer.sign_account_key_payload(&payload);
+    proto::AccountKeySignedPayload {
+        payload,
+        signature: protobuf::MessageField::some((&signature).into()),
+        ..Default::default()
+    }
+}
+
 // Accessors for creating malformed SignedAccountData
 impl SignedAccountData {
     pub(crate) fn payload_mut(&mut self) -> &mut Vec<u8> {
@@ -7,6 +7,7 @@ use crate::types::{HandshakeFailureReason, PeerMessage};
 use crate::types::{PartialEncodedChunkRequestMsg, PartialEncodedChunkResponseMsg};
 use anyhow::{bail, Context as _};
 use itertools::Itertools as _;
+use protobuf::Message as _;
 use rand::Rng as _;
 
 #[test]
@@ -54,6 +55,75 @@ fn bad_account_data_size() {
     assert!(ad.sign(&signer).is_err());
 }
 
+#[test]
+fn account_data_unknown_fields() {
+    let mut rng = make_rng(2804728104);
+    let clock = time::FakeClock::default();
+    let signer = data::make_validator_signer(&mut rng);
+    let peer_id = data::make_peer_id(&mut rng);
+    let ad = data::make_account_data(&mut rng, 1, clock.now_utc(), signer.public_key(), peer_id);
+
+    // Fields added in a newer version, both to the payload and to the account data.
+    let mut payload = proto::AccountKeyPayload::from(&ad);
+    payload.mut_unknown_fields().add_length_delimited(100, b"new field".to_vec());
+    if let Some(proto::account_key_payload::Payload_type::AccountData(a)) =
+        &mut payload.payload_type
+    {
+        a.mut_unknown_fields().add_varint(101, 7);
+    }
+    let signed = data::make_signed_account_key_payload(&signer, &payload);
+
+    let got = SignedAccountData::try_from(&signed).unwrap();
+    assert_eq!(*got, ad);
+    // The signed bytes are kept as received, including the unknown fields,
+    // so the signature still verifies and the payload is forwarded unchanged.
+    got.payload().verify(&ad.account_key).unwrap();
+    assert_eq!(proto::AccountKeySignedPayload::from(&got), signed);
+    // Encoding the parsed data again drops the unknown fields, so it cannot be
+    // used to check the original signature.
+    let reencoded = proto::AccountKeyPayload::from(&*got).write_to_bytes().unwrap();
+    assert_ne!(reencoded, signed.payload);
+}
+
+#[test]
+fn account_key_payload_unknown_type() {
+    let mut rng = make_rng(6729351028);
+    let clock = time::FakeClock::default();
+    let signer = data::make_validator_signer(&mut rng);
+
+    // A payload type added in a newer version.
+    let mut payload = proto::AccountKeyPayload::new();
+    payload.mut_unknown_fields().add_length_delimited(3, b"new payload type".to_vec());
+    let unknown = data::make_signed_account_key_payload(&signer, &payload);
+    let err = SignedAccountData::try_from(&unknown).unwrap_err();
+    assert!(err.is_unknown_payload_type(), "{err}");
+
+    // Malformed payloads are still rejected as such.
+    let malformed = proto::AccountKeySignedPayload { payload: vec![0xff; 4], ..unknown.clone() };
+    let err = SignedAccountData::try_from(&malformed).unwrap_err();
+    assert!(!err.is_unknown_payload_type(), "{err}");
+
+    // Old parsers skip the unknown payload type instead of rejecting the whole message.
+    let known = data::make_signed_account_data(&mut rng, &clock.clock());
+    let msg = proto::PeerMessage {
+        message_type: Some(proto::peer_message::Message_type::SyncAccountsData(
+            proto::SyncAccountsData {
+                accounts_data: vec![unknown, (&known).into()],
+                incremental: true,
+                ..Default::default()
+            },
+        )),
+        ..Default::default()
+    };
+    let got = PeerMessage::try_from(&msg).unwrap();
+    let want = PeerMessage::SyncAccountsData(SyncAccountsData {
+        accounts_data: vec![Arc::new(known)],
+        incremental: true,
+        requesting_full_sync: false,
+    });
+    assert_eq!(got, want);
+}
+
 #[test]
 fn serialize_deserialize_protobuf_only() {
     let mut rng = make_rng(39521947542);
@@ -304,7 +304,7 @@ impl PeerActor {
         self.tracker.lock().increment_sent(&self.clock, bytes.len() as u64);
         let bytes_len = bytes.len();
         tracing::trace!(target: "network", msg_len = bytes_len);
-        self.framed.send(stream::Frame(bytes));
+        self.framed.send(msg.priority(), stream::Frame(bytes));
         metrics::PEER_DATA_SENT_BYTES.inc_by(bytes_len as u64);
         metrics::PEER_MESSAGE_SENT_BY_TYPE_TOTAL.with_label_values(&[msg_type]).inc();
         metrics::PEER_MESSAGE_SENT_BY_TYPE_BYTES
@@ -748,6 +748,7 @@ impl PeerActor {
         clock: &time::Clock,
         network_state: &NetworkState,
         peer_id: PeerId,
+        author: PeerId,
         msg_hash: CryptoHash,
         body: RoutedMessageBody,
     ) -> Result<Option<RoutedMessageBody>, ReasonForBan> {
@@ -762,16 +763,28 @@ impl PeerActor {
                 network_state.client.tx_status_response(tx_result).await;
                 None
             }
-            RoutedMessageBody::StateRequestHeader(shard_id, sync_hash) => network_state
-                .client
-                .state_request_header(shard_id, sync_hash)
-                .await?
-                .map(RoutedMessageBody::VersionedStateResponse),
-            RoutedMessageBody::StateRequestPart(shard_id, sync_hash, part_id) => network_state
-                .client
-                .state_request_part(shard_id, sync_hash, part_id)
-                .await?
-                .map(RoutedMessageBody::VersionedStateResponse),
+            RoutedMessageBody::StateRequestHeader(shard_id, sync_hash) => {
+                if !network_state.state_sync_throttle.allow_header(&author) {
+                    return Ok(None);
+                }
+                network_state
+                    .client
+                    .state_request_header(shard_id, sync_hash)
+                    .await?
+                    .map(RoutedMessageBody::VersionedStateResponse)
+            }
+            RoutedMessageBody::StateRequestPart(shard_id, sync_hash, part_id) => {
+                if !network_state.state_sync_throttle.allow_part(clock, &author) {
+                    tracing::debug!(target: "network", ?author, shard_id, part_id, "Dropping state part request");
+                    return Ok(None);
+                }
+                let response =
+                    network_state.client.state_request_part(shard_id, sync_hash, part_id).await?;
+                if let Some(response) = &response {
+                    network_state.state_sync_throttle.record_served(response);
+                }
+                response.map(RoutedMessageBody::VersionedStateResponse)
+            }
             RoutedMessageBody::VersionedStateResponse(info) => {
                 network_state.client.state_response(info).await;
                 None
@@ -796,8 +809,10 @@ impl PeerActor {
                 network_state.client.partial_encoded_chunk(chunk).await;
                 None
             }
-            RoutedMessageBody::PartialEncodedChunkForward(msg) => {
-                network_state.client.partial_encoded_chunk_forward(msg).await;
+            RoutedMessageBody::PartialEncodedChunkForward(mut msg) => {
+                if network_state.chunk_forward_cache.remove_duplicates(&author, &mut msg) {
+                    network_state.client.partial_encoded_chunk_forward(msg).await;
+                }
                 None
             }
             RoutedMessageBody::ReceiptOutcomeRequest(_) => {
@@ -852,7 +867,8 @@ impl PeerActor {
             Ok(match msg {
                 PeerMessage::Routed(msg) => {
                     let msg_hash = msg.hash();
-                    Self::receive_routed_message(&clock, &network_state, peer_id, msg_hash, msg.msg.body).await?.map(
+                    let author = msg.msg.author.clone();
+                    Self::receive_routed_message(&clock, &network_state, peer_id, author, msg_hash, msg.msg.body).await?.map(
                         |body| {
                             PeerMessage::Routed(network_state.sign_message(
                                 &clock,
@@ -1,3 +1,4 @@
+use crate::network_protocol::MessagePriority;
 use crate::peer_manager::connection;
 use crate::stats::metrics;
 use crate::tcp;
@@ -56,8 +57,79 @@ pub(crate) enum Error {
     Recv(#[source] RecvError),
 }
 
+/// Sending halves of the send queues, one per `MessagePriority`.
+type QueueSenders = Vec<tokio::sync::mpsc::UnboundedSender<Frame>>;
+
+/// Receiving side of the per-priority send queues of a connection.
+///
+/// Frames are returned highest priority first. A class that sent more than
+/// its budget in a row while lower classes were waiting yields to the next
+/// waiting class for one frame, see `MessagePriority::budget_bytes`.
+pub(crate) struct SendQueue {
+    receivers: Vec<tokio::sync::mpsc::UnboundedReceiver<Frame>>,
+    /// Next frame of each class, already taken out of its channel.
+    heads: Vec<Option<Frame>>,
+    /// Bytes sent by each class in a row while lower classes were waiting.
+    spent: Vec<usize>,
+}
+
+impl SendQueue {
+    pub(crate) fn new() -> (QueueSenders, Self) {
+        let (senders, receivers) =
+            MessagePriority::ALL.iter().map(|_| tokio::sync::mpsc::unbounded_channel()).unzip();
+        let n = MessagePriority::ALL.len();
+        (senders, Self { receivers, heads: vec![None; n], spent: vec![0; n] })
+    }
+
+    /// Returns the next frame to send, if any is queued, without waiting.
+    pub(crate) fn try_next(&mut self) -> Option<(MessagePriority, Frame)> {
+        for (head, receiver) in self.heads.iter_mut().zip(&mut self.receivers) {
+            if head.is_none() {
+                *head = receiver.try_recv().ok();
+            }
+        }
+        let heads = &self.heads;
+        let mut waiting = MessagePriority::ALL.into_iter().filter(|p| heads[*p as usize].is_some());
+        let highest = waiting.next()?;
+        let chosen = std::iter::once(highest)
+            .chain(waiting)
+            .find(|p| self.spent[*p as usize] < p.budget_bytes())
+            .unwrap_or_else(|| {
+                self.spent.fill(0);
+                highest
+            });
+        if chosen != highest {
+            metrics::PEER_SEND_QUEUE_BUDGET_EXHAUSTED.with_label_values(&[highest.into()]).inc();
+        }
+        // Classes above the chosen one yielded, so their budgets are refilled.
+        self.spent[..chosen as usize].fill(0);
+        let frame = self.heads[chosen as usize].take().unwrap();
+        let lower_waiting = self.heads[chosen as usize + 1..].iter().any(Option::is_some);
+        self.spent[chosen as usize] =
+            if lower_waiting { self.spent[chosen as usize] + frame.0.len() } else { 0 };
+        Some((chosen, frame))
+    }
+
+    /// Waits for the next frame to send. Returns `None` once all senders have
+    /// been dropped and all queues are drained.
+    pub(crate) async fn next(&mut self) -> Option<(MessagePriority, Frame)> {
+        if let Some(next) = self.try_next() {
+            return Some(next);
+        }
+        let recvs = self.receivers.iter_mut().map(|receiver| Box::pin(receiver.recv()));
+        let (frame, i, _) = futures::future::select_all(recvs).await;
+        match frame {
+            Some(frame) => self.heads[i] = Some(frame),
+            // The connection is closing, but other queues might still hold
+            // frames that arrived while waiting.
+            None => return self.try_next(),
+        }
+        self.try_next()
+    }
+}
+
 pub(crate) struct FramedStream<Actor: actix::Actor> {
-    queue_send: tokio::sync::mpsc::UnboundedSender<Frame>,
+    queue_send: QueueSenders,
     stats: Arc<connection::Stats>,
     send_buf_size_metric: Arc<metrics::IntGaugeGuard>,
     addr: actix::Addr<Actor>,
@@ -75,7 +147,7 @@ where
         stats: Arc<connection::Stats>,
     ) -> Self {
         let (tcp_recv, tcp_send) = tokio::io::split(stream.stream);
-        let (queue_send, queue_recv) = tokio::sync::mpsc::unbounded_channel();
+        let (queue_send, queue_recv) = SendQueue::new();
         let send_buf_size_metric = Arc::new(metrics::MetricGuard::new(
             &*metrics::PEER_DATA_WRITE_BUFFER_SIZE,
             vec![stream.peer_addr.to_string()],
@@ -104,17 +176,20 @@ where
         Self { queue_send, stats, send_buf_size_metric, addr: ctx.address() }
     }
 
-    /// Pushes `msg` to the send queue.
+    /// Pushes `msg` to the send queue of the given priority class.
     /// Silently drops message if the connection has been closed.
     /// If the message is too large, it will be silently dropped inside run_send_loop.
     /// Emits a critical error to Actor if send queue is full.
-    pub fn send(&self, frame: Frame) {
+    pub fn send(&self, priority: MessagePriority, frame: Frame) {
         let msg = &frame.0;
         let mut buf_size =
             self.stats.bytes_to_send.fetch_add(msg.len() as u64, Ordering::Acquire) as usize;
         buf_size += msg.len();
         self.stats.messages_to_send.fetch_add(1, Ordering::Acquire);
         self.send_buf_size_metric.add(msg.len() as i64);
+        metrics::PEER_SEND_QUEUE_SIZE_BY_PRIORITY
+            .with_label_values(&[priority.into()])
+            .add(msg.len() as i64);
         // Exceeding buffer capacity is a critical error and Actor should call ctx.stop()
         // when receiving one. It is not like we do any extra allocations, so we can affort
         // pushing the message to the queue anyway.
@@ -125,7 +200,7 @@ where
                 want_max_bytes: MAX_WRITE_BUFFER_CAPACITY_BYTES,
             }));
         }
-        let _ = self.queue_send.send(frame);
+        let _ = self.queue_send[priority as usize].send(frame);
     }
 
     /// Event loop receiving and processing messages.
@@ -177,13 +252,13 @@ where
     }
     async fn run_send_loop(
         tcp_send: WriteHalf,
-        mut queue_recv: tokio::sync::mpsc::UnboundedReceiver<Frame>,
+        mut queue_recv: SendQueue,
         stats: Arc<connection::Stats>,
         buf_size_metric: Arc<metrics::IntGaugeGuard>,
     ) -> io::Result<()> {
         const WRITE_BUFFER_CAPACITY: usize = 8 * 1024;
         let mut writer = tokio::io::BufWriter::with_capacity(WRITE_BUFFER_CAPACITY, tcp_send);
-        while let Some(Frame(mut msg)) = queue_recv.recv().await {
+        while let Some((mut priority, Frame(mut msg))) = queue_recv.next().await {
             // Try writing a batch of messages and flush once at the end.
             loop {
                 // TODO(gprusak): sending a too large message should probably be treated as a bug,
@@ -197,9 +272,16 @@ where
                 stats.messages_to_send.fetch_sub(1, Ordering::Release);
                 stats.bytes_to_send.fetch_sub(msg.len() as u64, Ordering::Release);
                 buf_size_metric.sub(msg.len() as i64);
-                msg = match queue_recv.try_recv() {
-                    Ok(Frame(it)) => it,
-                    Err(_) => break,
+                let priority_label: &str = priority.into();
+                metrics::PEER_SEND_QUEUE_SIZE_BY_PRIORITY
+                    .with_label_values(&[priority_label])
+                    .sub(msg.len() as i64);
+                metrics::PEER_MESSAGE_SENT_BY_PRIORITY_BYTES
+                    .with_label_values(&[priority_label])
+                    .inc_by(msg.len() as u64);
+                (priority, msg) = match queue_recv.try_next() {
+                    Some((priority, Frame(it))) => (priority, it),
+                    None => break,
                 };
             }
             // This is an unconditional flush, which means that even if new messages
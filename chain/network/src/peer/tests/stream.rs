@@ -1,5 +1,6 @@
 use crate::actix::ActixSystem;
 use crate::network_protocol::testonly as data;
+use crate::network_protocol::MessagePriority;
 use crate::peer::stream;
 use crate::tcp;
 use crate::testonly::make_rng;
@@ -25,7 +26,7 @@ struct SendFrame(stream::Frame);
 impl actix::Handler<SendFrame> for Actor {
     type Result = ();
     fn handle(&mut self, SendFrame(frame): SendFrame, _ctx: &mut Self::Context) {
-        self.stream.send(frame);
+        self.stream.send(MessagePriority::Gossip, frame);
     }
 }
 
@@ -90,3 +91,47 @@ async fn send_recv() {
         }
     }
 }
+
+#[tokio::test]
+async fn send_queue_priorities() {
+    let (senders, mut queue) = stream::SendQueue::new();
+    let frame = |tag: u8, size: usize| stream::Frame(vec![tag; size]);
+    let send = |p: MessagePriority, f: stream::Frame| senders[p as usize].send(f).unwrap();
+
+    send(MessagePriority::Gossip, frame(0, 10));
+    send(MessagePriority::StateSync, frame(1, 10));
+    send(MessagePriority::Consensus, frame(2, 10));
+    send(MessagePriority::Chunks, frame(3, 10));
+    send(MessagePriority::Consensus, frame(4, 10));
+    let order: Vec<_> = std::iter::from_fn(|| queue.try_next()).map(|(p, f)| (p, f.0[0])).collect();
+    assert_eq!(
+        order,
+        vec![
+            (MessagePriority::Consensus, 2),
+            (MessagePriority::Consensus, 4),
+            (MessagePriority::Chunks, 3),
+            (MessagePriority::StateSync, 1),
+            (MessagePriority::Gossip, 0),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn send_queue_budget() {
+    let (senders, mut queue) = stream::SendQueue::new();
+    let budget = MessagePriority::Consensus.budget_bytes();
+    // Two consensus frames exceeding the budget together, a waiting gossip
+    // frame gets sent in between.
+    senders[MessagePriority::Consensus as usize].send(stream::Frame(vec![0; budget])).unwrap();
+    senders[MessagePriority::Consensus as usize].send(stream::Frame(vec![0; 1])).unwrap();
+    senders[MessagePriority::Gossip as usize].send(stream::Frame(vec![1; 1])).unwrap();
+    let order: Vec<_> = std::iter::from_fn(|| queue.try_next()).map(|(p, _)| p).collect();
+    assert_eq!(
+        order,
+        vec![MessagePriority::Consensus, MessagePriority::Gossip, MessagePriority::Consensus]
+    );
+
+    // Dropping the senders closes the queue.
+    drop(senders);
+    assert!(queue.next().await.is_none());
+}
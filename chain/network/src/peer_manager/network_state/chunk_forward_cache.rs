@@ -0,0 +1,121 @@
+use crate::network_protocol::PartialEncodedChunkForwardMsg;
+use crate::stats::metrics;
+use lru::LruCache;
+use near_primitives::network::PeerId;
+use near_primitives::sharding::ChunkHash;
+use parking_lot::Mutex;
+
+/// Number of forwarded chunk parts remembered. A part is a few KB at most, but
+/// a block contains up to `num_shards * num_total_parts` parts, which are
+/// forwarded to every validator tracking the shard.
+const CHUNK_FORWARD_CACHE_SIZE: usize = 100_000;
+
+/// Cache of chunk parts recently forwarded to this node, used to drop
+/// duplicated parts before they reach the client and get validated again.
+///
+/// Entries are keyed by the author of the forward message rather than the
+/// peer which routed it to us, so that duplicates arriving over different
+/// routes are dropped as well. Since routed messages are signed by their
+/// author, a peer can only suppress parts it forwards itself.
+pub(crate) struct ChunkForwardCache(Mutex<LruCache<(ChunkHash, u64, PeerId), ()>>);
+
+impl ChunkForwardCache {
+    pub fn new() -> Self {
+        Self::with_capacity(CHUNK_FORWARD_CACHE_SIZE)
+    }
+
+    fn with_capacity(capacity: usize) -> Self {
+        Self(Mutex::new(LruCache::new(capacity)))
+    }
+
+    /// Removes the parts of `forward` which have already been received from
+    /// `author` and remembers the remaining ones. Returns false if no parts
+    /// are left, in which case the message should be dropped.
+    pub fn remove_duplicates(
+        &self,
+        author: &PeerId,
+        forward: &mut PartialEncodedChunkForwardMsg,
+    ) -> bool {
+        let mut cache = self.0.lock();
+        let num_parts = forward.parts.len();
+        forward.parts.retain(|part| {
+            let key = (forward.chunk_hash.clone(), part.part_ord, author.clone());
+            cache.put(key, ()).is_none()
+        });
+        let num_duplicates = num_parts - forward.parts.len();
+        metrics::PARTIAL_ENCODED_CHUNK_FORWARD_DUPLICATE_PARTS.inc_by(num_duplicates as u64);
+        if forward.parts.is_empty() && num_parts > 0 {
+            metrics::PARTIAL_ENCODED_CHUNK_FORWARD_DUPLICATES.inc();
+            return false;
+        }
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ChunkForwardCache;
+    use crate::network_protocol::testonly as data;
+    use crate::network_protocol::PartialEncodedChunkForwardMsg;
+    use crate::testonly::make_rng;
+    use near_crypto::Signature;
+    use near_primitives::hash::CryptoHash;
+    use near_primitives::sharding::{ChunkHash, PartialEncodedChunkPart};
+
+    fn make_forward(chunk_hash: ChunkHash, part_ords: &[u64]) -> PartialEncodedChunkForwardMsg {
+        PartialEncodedChunkForwardMsg {
+            chunk_hash,
+            inner_header_hash: CryptoHash::default(),
+            merkle_root: CryptoHash::default(),
+            signature: Signature::default(),
+            prev_block_hash: CryptoHash::default(),
+            height_created: 1,
+            shard_id: 0,
+            parts: part_ords
+                .iter()
+                .map(|&part_ord| PartialEncodedChunkPart {
+                    part_ord,
+                    part: vec![].into_boxed_slice(),
+                    merkle_proof: vec![],
+                })
+                .collect(),
+        }
+    }
+
+    fn part_ords(forward: &PartialEncodedChunkForwardMsg) -> Vec<u64> {
+        forward.parts.iter().map(|part| part.part_ord).collect()
+    }
+
+    #[test]
+    fn remove_duplicates() {
+        let mut rng = make_rng(921853233);
+        let rng = &mut rng;
+        let cache = ChunkForwardCache::with_capacity(3);
+        let alice = data::make_peer_id(rng);
+        let bob = data::make_peer_id(rng);
+        let chunk = ChunkHash(CryptoHash::hash_bytes(b"chunk"));
+
+        let mut forward = make_forward(chunk.clone(), &[0, 1]);
+        assert!(cache.remove_duplicates(&alice, &mut forward));
+        assert_eq!(part_ords(&forward), vec![0, 1]);
+
+        // Part 1 was already received from alice.
+        let mut forward = make_forward(chunk.clone(), &[1, 2]);
+        assert!(cache.remove_duplicates(&alice, &mut forward));
+        assert_eq!(part_ords(&forward), vec![2]);
+
+        // Parts are deduplicated per author.
+        let mut forward = make_forward(chunk.clone(), &[1]);
+        assert!(cache.remove_duplicates(&bob, &mut forward));
+        assert_eq!(part_ords(&forward), vec![1]);
+
+        let mut forward = make_forward(chunk.clone(), &[1, 2]);
+        assert!(!cache.remove_duplicates(&alice, &mut forward));
+        assert!(forward.parts.is_empty());
+
+        // Part 0 of alice has been evicted.
+        let mut forward = make_forward(chunk, &[0]);
+        assert!(cache.remove_duplicates(&alice, &mut forward));
+        assert_eq!(part_ords(&forward), vec![0]);
+    }
+}
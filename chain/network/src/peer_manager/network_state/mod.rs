@@ -31,8 +31,13 @@ use std::sync::atomic::{AtomicU32, AtomicUsize, Ordering};
 use std::sync::Arc;
 use tracing::{debug, trace, Instrument};
 
+mod chunk_forward_cache;
+mod state_sync_throttle;
 mod tier1;
 
+use chunk_forward_cache::ChunkForwardCache;
+use state_sync_throttle::StateSyncThrottle;
+
 /// Limit number of pending Peer actors to avoid OOM.
 pub(crate) const LIMIT_PENDING_PEERS: usize = 60;
 
@@ -135,6 +140,11 @@ pub(crate) struct NetworkState {
     /// Shared counter across all PeerActors, which counts number of `RoutedMessageBody::ForwardTx`
     /// messages sincce last block.
     pub txns_since_last_block: AtomicUsize,
+    /// Chunk parts recently forwarded to this node, to drop duplicates before
+    /// they are validated by the client.
+    pub chunk_forward_cache: ChunkForwardCache,
+    /// Decides which state sync requests of other nodes are served.
+    pub state_sync_throttle: StateSyncThrottle,
 
     /// Whitelisted nodes, which are allowed to connect even if the connection limit has been
     /// reached.
@@ -171,6 +181,8 @@ impl NetworkState {
             accounts_data: Arc::new(accounts_data::Cache::new()),
             routing_table_view: RoutingTableView::new(store, config.node_id()),
             txns_since_last_block: AtomicUsize::new(0),
+            chunk_forward_cache: ChunkForwardCache::new(),
+            state_sync_throttle: StateSyncThrottle::new(clock, &config.state_sync_serving),
             whitelist_nodes,
             max_num_peers: AtomicU32::new(config.max_num_peers),
             config,
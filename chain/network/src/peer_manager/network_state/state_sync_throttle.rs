@@ -0,0 +1,179 @@
+use crate::config;
+use crate::network_protocol::StateResponseInfo;
+use crate::stats::metrics;
+use crate::time;
+use near_primitives::network::PeerId;
+use parking_lot::Mutex;
+use std::collections::HashSet;
+
+/// Bytes which may be served per second, refilled continuously. Sending a
+/// part may overdraw the budget, the next part is served once it is positive
+/// again. At most one second worth of bytes is accumulated while idle.
+struct Budget {
+    max_bytes_per_second: u64,
+    available: i64,
+    updated_at: time::Instant,
+}
+
+impl Budget {
+    fn refill(&mut self, now: time::Instant) {
+        let elapsed = (now - self.updated_at).as_seconds_f64().max(0.);
+        let refill = (elapsed * self.max_bytes_per_second as f64) as i64;
+        self.available = self
+            .available
+            .saturating_add(refill)
+            .min(self.max_bytes_per_second.try_into().unwrap_or(i64::MAX));
+        self.updated_at = now;
+    }
+}
+
+/// Decides which state sync requests of other nodes are served, according to
+/// `config::StateSyncServing`.
+///
+/// Peers are identified by the author of the routed request, so that a node
+/// on the allow list may reach us over any route.
+pub(crate) struct StateSyncThrottle {
+    allowed_peers: HashSet<PeerId>,
+    budget: Option<Mutex<Budget>>,
+}
+
+impl StateSyncThrottle {
+    pub fn new(clock: &time::Clock, cfg: &config::StateSyncServing) -> Self {
+        Self {
+            allowed_peers: cfg.allowed_peers.iter().cloned().collect(),
+            budget: cfg.max_bytes_per_second.map(|max_bytes_per_second| {
+                Mutex::new(Budget {
+                    max_bytes_per_second,
+                    available: max_bytes_per_second.try_into().unwrap_or(i64::MAX),
+                    updated_at: clock.now(),
+                })
+            }),
+        }
+    }
+
+    fn is_allowed(&self, author: &PeerId) -> bool {
+        if self.allowed_peers.is_empty() || self.allowed_peers.contains(author) {
+            return true;
+        }
+        metrics::STATE_SYNC_REQUESTS_DROPPED.with_label_values(&["not_allowed"]).inc();
+        false
+    }
+
+    /// Whether a state header request of `author` should be served. Headers
+    /// are not counted against the budget.
+    pub fn allow_header(&self, author: &PeerId) -> bool {
+        self.is_allowed(author)
+    }
+
+    /// Whether a state part request of `author` should be served. The size
+    /// of the served part has to be reported with `record_served`.
+    pub fn allow_part(&self, clock: &time::Clock, author: &PeerId) -> bool {
+        if !self.is_allowed(author) {
+            return false;
+        }
+        let budget = match &self.budget {
+            Some(budget) => budget,
+            None => return true,
+        };
+        let mut budget = budget.lock();
+        budget.refill(clock.now());
+        if budget.available <= 0 {
+            metrics::STATE_SYNC_REQUESTS_DROPPED.with_label_values(&["throttled"]).inc();
+            return false;
+        }
+        true
+    }
+
+    pub fn record_served(&self, response: &StateResponseInfo) {
+        let bytes = part_size(response);
+        metrics::STATE_SYNC_SERVED_BYTES.inc_by(bytes as u64);
+        if let Some(budget) = &self.budget {
+            let mut budget = budget.lock();
+            budget.available =
+                budget.available.saturating_sub(bytes.try_into().unwrap_or(i64::MAX));
+        }
+    }
+}
+
+fn part_size(response: &StateResponseInfo) -> usize {
+    let part = match response {
+        StateResponseInfo::V1(info) => &info.state_response.part,
+        StateResponseInfo::V2(info) => info.state_response.part(),
+    };
+    part.as_ref().map_or(0, |(_, data)| data.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::StateSyncThrottle;
+    use crate::config;
+    use crate::network_protocol::testonly as data;
+    use crate::network_protocol::{StateResponseInfo, StateResponseInfoV2};
+    use crate::testonly::make_rng;
+    use crate::time;
+    use near_primitives::hash::CryptoHash;
+    use near_primitives::syncing::{ShardStateSyncResponse, ShardStateSyncResponseV2};
+
+    fn make_response(part_size: usize) -> StateResponseInfo {
+        StateResponseInfo::V2(StateResponseInfoV2 {
+            shard_id: 0,
+            sync_hash: CryptoHash::default(),
+            state_response: ShardStateSyncResponse::V2(ShardStateSyncResponseV2 {
+                header: None,
+                part: Some((0, vec![0; part_size])),
+            }),
+        })
+    }
+
+    #[test]
+    fn allowed_peers() {
+        let mut rng = make_rng(921853233);
+        let rng = &mut rng;
+        let clock = time::FakeClock::default();
+        let alice = data::make_peer_id(rng);
+        let bob = data::make_peer_id(rng);
+
+        let throttle = StateSyncThrottle::new(&clock.clock(), &config::StateSyncServing::default());
+        assert!(throttle.allow_header(&alice));
+        assert!(throttle.allow_part(&clock.clock(), &bob));
+
+        let cfg = config::StateSyncServing {
+            max_bytes_per_second: None,
+            allowed_peers: vec![alice.clone()],
+        };
+        let throttle = StateSyncThrottle::new(&clock.clock(), &cfg);
+        assert!(throttle.allow_header(&alice));
+        assert!(throttle.allow_part(&clock.clock(), &alice));
+        assert!(!throttle.allow_header(&bob));
+        assert!(!throttle.allow_part(&clock.clock(), &bob));
+    }
+
+    #[test]
+    fn bandwidth_budget() {
+        let mut rng = make_rng(921853233);
+        let alice = data::make_peer_id(&mut rng);
+        let clock = time::FakeClock::default();
+        let cfg =
+            config::StateSyncServing { max_bytes_per_second: Some(1000), allowed_peers: vec![] };
+        let throttle = StateSyncThrottle::new(&clock.clock(), &cfg);
+
+        // The initial budget is one second worth of bytes, a part may overdraw it.
+        assert!(throttle.allow_part(&clock.clock(), &alice));
+        throttle.record_served(&make_response(1500));
+        assert!(!throttle.allow_part(&clock.clock(), &alice));
+
+        // 500 bytes of debt are paid back after half a second.
+        clock.advance(time::Duration::milliseconds(500));
+        assert!(!throttle.allow_part(&clock.clock(), &alice));
+        clock.advance(time::Duration::milliseconds(100));
+        assert!(throttle.allow_part(&clock.clock(), &alice));
+        throttle.record_served(&make_response(100));
+        assert!(!throttle.allow_part(&clock.clock(), &alice));
+
+        // Idle time accumulates at most one second worth of bytes.
+        clock.advance(time::Duration::seconds(10));
+        assert!(throttle.allow_part(&clock.clock(), &alice));
+        throttle.record_served(&make_response(1000));
+        assert!(!throttle.allow_part(&clock.clock(), &alice));
+    }
+}
@@ -159,6 +159,30 @@ pub(crate) static PEER_DATA_WRITE_BUFFER_SIZE: Lazy<IntGaugeVec> = Lazy::new(||
     )
     .unwrap()
 });
+pub(crate) static PEER_SEND_QUEUE_SIZE_BY_PRIORITY: Lazy<IntGaugeVec> = Lazy::new(|| {
+    try_create_int_gauge_vec(
+        "near_peer_send_queue_size_by_priority",
+        "Total size of outgoing messages waiting to be sent to peers, by priority class",
+        &["priority"],
+    )
+    .unwrap()
+});
+pub(crate) static PEER_MESSAGE_SENT_BY_PRIORITY_BYTES: Lazy<IntCounterVec> = Lazy::new(|| {
+    try_create_int_counter_vec(
+        "near_peer_message_sent_by_priority_bytes",
+        "Total data sent to peers by priority class",
+        &["priority"],
+    )
+    .unwrap()
+});
+pub(crate) static PEER_SEND_QUEUE_BUDGET_EXHAUSTED: Lazy<IntCounterVec> = Lazy::new(|| {
+    try_create_int_counter_vec(
+        "near_peer_send_queue_budget_exhausted_total",
+        "Number of times a priority class used up its budget and yielded to a lower class",
+        &["priority"],
+    )
+    .unwrap()
+});
 pub(crate) static PEER_MESSAGE_RECEIVED_BY_TYPE_BYTES: Lazy<IntCounterVec> = Lazy::new(|| {
     try_create_int_counter_vec(
         "near_peer_message_received_by_type_bytes",
@@ -318,6 +342,37 @@ pub(crate) static PARTIAL_ENCODED_CHUNK_REQUEST_DELAY: Lazy<Histogram> = Lazy::n
     )
         .unwrap()
 });
+pub(crate) static PARTIAL_ENCODED_CHUNK_FORWARD_DUPLICATE_PARTS: Lazy<IntCounter> =
+    Lazy::new(|| {
+        try_create_int_counter(
+            "near_partial_encoded_chunk_forward_duplicate_parts",
+            "Forwarded chunk parts dropped as already received from the same author",
+        )
+        .unwrap()
+    });
+pub(crate) static PARTIAL_ENCODED_CHUNK_FORWARD_DUPLICATES: Lazy<IntCounter> = Lazy::new(|| {
+    try_create_int_counter(
+        "near_partial_encoded_chunk_forward_duplicates",
+        "Number of chunk forward messages dropped because all their parts were duplicates",
+    )
+    .unwrap()
+});
+
+pub(crate) static STATE_SYNC_SERVED_BYTES: Lazy<IntCounter> = Lazy::new(|| {
+    try_create_int_counter(
+        "near_state_sync_served_bytes_total",
+        "Total size of state parts served to other nodes",
+    )
+    .unwrap()
+});
+pub(crate) static STATE_SYNC_REQUESTS_DROPPED: Lazy<IntCounterVec> = Lazy::new(|| {
+    try_create_int_counter_vec(
+        "near_state_sync_requests_dropped_total",
+        "Number of state sync requests of other nodes which were not served",
+        &["reason"],
+    )
+    .unwrap()
+});
 
 pub(crate) static BROADCAST_MESSAGES: Lazy<IntCounterVec> = Lazy::new(|| {
     try_create_int_counter_vec("near_broadcast_msg", "Broadcasted messages", &["type"]).unwrap()
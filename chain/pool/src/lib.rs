@@ -6,7 +6,8 @@ use near_crypto::PublicKey;
 use near_primitives::epoch_manager::RngSeed;
 use near_primitives::hash::{hash, CryptoHash};
 use near_primitives::transaction::SignedTransaction;
-use near_primitives::types::AccountId;
+use near_primitives::types::{AccountId, Balance, Nonce};
+use std::cmp::Reverse;
 use std::ops::Bound;
 
 mod metrics;
@@ -56,8 +57,8 @@ impl TransactionPool {
         }
         metrics::TRANSACTION_POOL_TOTAL.inc();
 
-        let signer_id = &signed_transaction.transaction.signer_id;
-        let signer_public_key = &signed_transaction.transaction.public_key;
+        let signer_id = signed_transaction.transaction.signer_id();
+        let signer_public_key = signed_transaction.transaction.public_key();
         self.transactions
             .entry(self.key(signer_id, signer_public_key))
             .or_insert_with(Vec::new)
@@ -78,8 +79,8 @@ impl TransactionPool {
         let mut grouped_transactions = HashMap::new();
         for tx in transactions {
             if self.unique_transactions.contains(&tx.get_hash()) {
-                let signer_id = &tx.transaction.signer_id;
-                let signer_public_key = &tx.transaction.public_key;
+                let signer_id = tx.transaction.signer_id();
+                let signer_public_key = tx.transaction.public_key();
                 grouped_transactions
                     .entry(self.key(signer_id, signer_public_key))
                     .or_insert_with(HashSet::new)
@@ -113,6 +114,11 @@ impl TransactionPool {
     pub fn len(&self) -> usize {
         self.unique_transactions.len()
     }
+
+    /// Iterates over all transactions in the pool, in no particular order.
+    pub fn transactions(&self) -> impl Iterator<Item = &SignedTransaction> {
+        self.transactions.values().flatten()
+    }
 }
 
 /// PoolIterator is a structure to pull transactions from the pool.
@@ -122,20 +128,47 @@ pub struct PoolIteratorWrapper<'a> {
     /// Mutable reference to the pool, to avoid exposing it while the iterator exists.
     pool: &'a mut TransactionPool,
 
+    /// Keys of the groups of which the next transaction pays a priority fee,
+    /// ordered by decreasing fee. They are pulled from the pool first.
+    priority_keys: VecDeque<PoolKey>,
+
     /// Queue of transaction groups. Each group there is sorted by nonce.
     sorted_groups: VecDeque<TransactionGroup>,
 }
 
 impl<'a> PoolIteratorWrapper<'a> {
     pub fn new(pool: &'a mut TransactionPool) -> Self {
-        Self { pool, sorted_groups: Default::default() }
+        let mut priority_keys: Vec<_> = pool
+            .transactions
+            .iter()
+            .filter_map(|(key, transactions)| {
+                let next_tx = transactions.iter().max_by_key(|tx| tx_order(tx))?;
+                let priority_fee = next_tx.transaction.priority_fee().filter(|fee| *fee > 0)?;
+                Some((Reverse(priority_fee), *key))
+            })
+            .collect();
+        priority_keys.sort();
+        Self {
+            pool,
+            priority_keys: priority_keys.into_iter().map(|(_, key)| key).collect(),
+            sorted_groups: Default::default(),
+        }
     }
 }
 
+/// Transactions of a group are taken in increasing order of this key: by
+/// nonce, and of the transactions with the same nonce, the one with the
+/// highest priority fee first.
+fn tx_order(tx: &SignedTransaction) -> (Reverse<Nonce>, Balance) {
+    (Reverse(tx.transaction.nonce()), tx.transaction.priority_fee().unwrap_or(0))
+}
+
 /// The iterator works with the following algorithm:
 /// On next(), the iterator tries to get a transaction group from the pool, sorts transactions in
 /// it, and add it to the back of the sorted groups queue.
-/// Remembers the last used key, so it can continue from the next key.
+/// Groups of which the next transaction pays a priority fee are taken first, in order of
+/// decreasing fee. Then the groups are taken in the order of their keys. Remembers the last used
+/// key, so it can continue from the next key.
 ///
 /// If the pool is empty, the iterator gets the group from the front of the sorted groups queue.
 ///
@@ -152,23 +185,29 @@ impl<'a> PoolIteratorWrapper<'a> {
 impl<'a> PoolIterator for PoolIteratorWrapper<'a> {
     fn next(&mut self) -> Option<&mut TransactionGroup> {
         if !self.pool.transactions.is_empty() {
-            let key = *self
-                .pool
-                .transactions
-                .range((Bound::Excluded(self.pool.last_used_key), Bound::Unbounded))
-                .next()
-                .map(|(k, _v)| k)
-                .unwrap_or_else(|| {
-                    self.pool
+            let key = match self.priority_keys.pop_front() {
+                Some(key) => key,
+                None => {
+                    let key = *self
+                        .pool
                         .transactions
-                        .keys()
+                        .range((Bound::Excluded(self.pool.last_used_key), Bound::Unbounded))
                         .next()
-                        .expect("we've just checked that the map is not empty")
-                });
-            self.pool.last_used_key = key;
+                        .map(|(k, _v)| k)
+                        .unwrap_or_else(|| {
+                            self.pool
+                                .transactions
+                                .keys()
+                                .next()
+                                .expect("we've just checked that the map is not empty")
+                        });
+                    self.pool.last_used_key = key;
+                    key
+                }
+            };
             let mut transactions =
                 self.pool.transactions.remove(&key).expect("just checked existence");
-            transactions.sort_by_key(|st| std::cmp::Reverse(st.transaction.nonce));
+            transactions.sort_by_key(tx_order);
             self.sorted_groups.push_back(TransactionGroup {
                 key,
                 transactions,
@@ -262,7 +301,7 @@ mod tests {
         (
             prepare_transactions(&mut pool, expected_weight)
                 .iter()
-                .map(|tx| tx.transaction.nonce)
+                .map(|tx| tx.transaction.nonce())
                 .collect(),
             pool,
         )
@@ -337,7 +376,7 @@ mod tests {
         sort_pairs(&mut nonces[..6]);
         assert_eq!(nonces, vec![1, 21, 2, 22, 3, 23, 24, 25, 26, 27]);
         let nonces: Vec<u64> =
-            prepare_transactions(&mut pool, 10).iter().map(|tx| tx.transaction.nonce).collect();
+            prepare_transactions(&mut pool, 10).iter().map(|tx| tx.transaction.nonce()).collect();
         assert_eq!(nonces, vec![28, 29, 30, 31]);
     }
 
@@ -380,9 +419,9 @@ mod tests {
         assert_eq!(pool.len(), txs_to_check.len());
 
         let mut pool_txs = prepare_transactions(&mut pool, txs_to_check.len() as u32);
-        pool_txs.sort_by_key(|tx| tx.transaction.nonce);
+        pool_txs.sort_by_key(|tx| tx.transaction.nonce());
         let mut expected_txs = txs_to_check.to_vec();
-        expected_txs.sort_by_key(|tx| tx.transaction.nonce);
+        expected_txs.sort_by_key(|tx| tx.transaction.nonce());
 
         assert_eq!(pool_txs, expected_txs);
     }
@@ -400,13 +439,13 @@ mod tests {
         let mut pool_iter = pool.pool_iterator();
         while let Some(iter) = pool_iter.next() {
             while let Some(tx) = iter.next() {
-                if tx.transaction.nonce & 1 == 1 {
+                if tx.transaction.nonce() & 1 == 1 {
                     res.push(tx);
                     break;
                 }
             }
         }
-        let mut nonces: Vec<_> = res.into_iter().map(|tx| tx.transaction.nonce).collect();
+        let mut nonces: Vec<_> = res.into_iter().map(|tx| tx.transaction.nonce()).collect();
         sort_pairs(&mut nonces[..4]);
         assert_eq!(nonces, vec![1, 21, 3, 23, 25, 27, 29, 31]);
     }
@@ -428,6 +467,57 @@ mod tests {
         assert_eq!(txs.len(), 10);
     }
 
+    #[cfg(feature = "protocol_feature_transaction_priority_fee")]
+    fn priority_transaction(
+        signer_id: &str,
+        nonce: u64,
+        priority_fee: Balance,
+    ) -> SignedTransaction {
+        use near_primitives::transaction::{Action, Transaction, TransactionV1, TransferAction};
+        let signer_id: AccountId = signer_id.parse().unwrap();
+        let signer =
+            InMemorySigner::from_seed(signer_id.clone(), KeyType::ED25519, signer_id.as_ref());
+        Transaction::V1(TransactionV1 {
+            signer_id,
+            public_key: signer.public_key.clone(),
+            nonce,
+            receiver_id: "bob.near".parse().unwrap(),
+            block_hash: CryptoHash::default(),
+            actions: vec![Action::Transfer(TransferAction { deposit: 1 })],
+            priority_fee,
+        })
+        .sign(&signer)
+    }
+
+    /// Groups of which the next transaction pays a priority fee are pulled first, by decreasing
+    /// fee. Of transactions with the same nonce, the one with the higher fee is pulled first.
+    #[test]
+    #[cfg(feature = "protocol_feature_transaction_priority_fee")]
+    fn test_priority_fee_order() {
+        let mut transactions = generate_transactions("alice.near", "alice.near", 1, 2);
+        transactions.push(priority_transaction("bob.near", 1, 10));
+        transactions.push(priority_transaction("carol.near", 1, 20));
+        transactions.push(priority_transaction("carol.near", 2, 5));
+        transactions.push(priority_transaction("carol.near", 2, 30));
+        // The fee of a later transaction doesn't move the group forward.
+        transactions.push(priority_transaction("dave.near", 2, 100));
+        transactions.extend(generate_transactions("dave.near", "dave.near", 1, 1));
+
+        let mut pool = TransactionPool::new(TEST_SEED);
+        for tx in transactions {
+            pool.insert_transaction(tx);
+        }
+        let txs = prepare_transactions(&mut pool, 2);
+        let signers: Vec<_> = txs.iter().map(|tx| tx.transaction.signer_id().as_str()).collect();
+        assert_eq!(signers, vec!["carol.near", "bob.near"]);
+
+        // Now carol's next transaction has the highest fee.
+        let txs = prepare_transactions(&mut pool, 1);
+        assert_eq!(txs[0].transaction.signer_id().as_str(), "carol.near");
+        assert_eq!(txs[0].transaction.priority_fee(), Some(30));
+        assert_eq!(pool.len(), 5);
+    }
+
     /// Test pool iterator remembers the last key.
     #[test]
     fn test_pool_iterator_remembers_the_last_key() {
@@ -461,8 +551,23 @@ mod tests {
         let txs = prepare_transactions(&mut pool, 5);
         assert_eq!(txs.len(), 5);
         nonces.sort();
-        let mut new_nonces = txs.iter().map(|tx| tx.transaction.nonce).collect::<Vec<_>>();
+        let mut new_nonces = txs.iter().map(|tx| tx.transaction.nonce()).collect::<Vec<_>>();
         new_nonces.sort();
         assert_ne!(nonces, new_nonces);
     }
+
+    #[test]
+    fn test_transactions() {
+        let mut transactions = generate_transactions("alice.near", "alice.near", 1, 5);
+        transactions.extend(generate_transactions("bob.near", "bob.near", 1, 5));
+        let (_, mut pool) = process_txs_to_nonces(transactions.clone(), 3);
+        assert_eq!(pool.transactions().count(), 7);
+
+        pool.reintroduce_transactions(transactions.clone());
+        let mut hashes: Vec<_> = pool.transactions().map(SignedTransaction::get_hash).collect();
+        hashes.sort();
+        let mut expected: Vec<_> = transactions.iter().map(SignedTransaction::get_hash).collect();
+        expected.sort();
+        assert_eq!(hashes, expected);
+    }
 }
@@ -146,28 +146,37 @@ pub(crate) async fn convert_block_to_transactions(
     let prev_block_id = near_primitives::types::BlockReference::from(
         near_primitives::types::BlockId::Hash(block.header.prev_hash),
     );
+    // The remaining queries are independent of each other, so they are sent
+    // all at once instead of waiting for each response in turn.
     let accounts_previous_state =
-        crate::utils::query_accounts(&prev_block_id, touched_account_ids.iter(), &view_client_addr)
-            .await?;
-
-    let accounts_changes = view_client_addr
-        .send(
-            near_client::GetStateChanges {
-                block_hash: block.header.hash,
-                state_changes_request:
-                    near_primitives::views::StateChangesRequestView::AccountChanges {
-                        account_ids: touched_account_ids,
-                    },
-            }
-            .with_span_context(),
-        )
-        .await??;
-
-    let runtime_config = crate::utils::query_protocol_config(block.header.hash, &view_client_addr)
-        .await?
-        .runtime_config;
-    let exec_to_rx =
-        transactions::ExecutionToReceipts::for_block(&view_client_addr, block.header.hash).await?;
+        crate::utils::query_accounts(&prev_block_id, touched_account_ids.iter(), &view_client_addr);
+    let accounts_changes = async {
+        let changes = view_client_addr
+            .send(
+                near_client::GetStateChanges {
+                    block_hash: block.header.hash,
+                    state_changes_request:
+                        near_primitives::views::StateChangesRequestView::AccountChanges {
+                            account_ids: touched_account_ids.clone(),
+                        },
+                }
+                .with_span_context(),
+            )
+            .await??;
+        crate::errors::Result::Ok(changes)
+    };
+    let runtime_config = async {
+        let config =
+            crate::utils::query_protocol_config(block.header.hash, &view_client_addr).await?;
+        crate::errors::Result::Ok(config.runtime_config)
+    };
+    let exec_to_rx = transactions::ExecutionToReceipts::for_block(&view_client_addr, block);
+    let (accounts_previous_state, accounts_changes, runtime_config, exec_to_rx): (
+        std::collections::HashMap<_, _>,
+        _,
+        _,
+        _,
+    ) = futures::try_join!(accounts_previous_state, accounts_changes, runtime_config, exec_to_rx)?;
     transactions::convert_block_changes_to_transactions(
         &view_client_addr,
         &runtime_config,
@@ -240,6 +249,50 @@ impl From<NearActions> for Vec<crate::models::Operation> {
                     );
                 }
 
+                // Rosetta has no notion of contract state, so the action is
+                // shown as an account creation followed by a deployment.
+                #[cfg(feature = "protocol_feature_create_account_with_contract")]
+                near_primitives::transaction::Action::CreateAccountWithContract(action) => {
+                    let initiate_create_account_operation_id =
+                        crate::models::OperationIdentifier::new(&operations);
+                    operations.push(
+                        validated_operations::InitiateCreateAccountOperation {
+                            sender_account: sender_account_identifier.clone(),
+                        }
+                        .into_operation(initiate_create_account_operation_id.clone()),
+                    );
+
+                    operations.push(
+                        validated_operations::CreateAccountOperation {
+                            account: receiver_account_identifier.clone(),
+                        }
+                        .into_related_operation(
+                            crate::models::OperationIdentifier::new(&operations),
+                            vec![initiate_create_account_operation_id],
+                        ),
+                    );
+
+                    let initiate_deploy_contract_operation_id =
+                        crate::models::OperationIdentifier::new(&operations);
+                    operations.push(
+                        validated_operations::InitiateDeployContractOperation {
+                            sender_account: sender_account_identifier.clone(),
+                        }
+                        .into_operation(initiate_deploy_contract_operation_id.clone()),
+                    );
+
+                    operations.push(
+                        validated_operations::DeployContractOperation {
+                            account: receiver_account_identifier.clone(),
+                            code: action.code,
+                        }
+                        .into_related_operation(
+                            crate::models::OperationIdentifier::new(&operations),
+                            vec![initiate_deploy_contract_operation_id],
+                        ),
+                    );
+                }
+
                 near_primitives::transaction::Action::DeleteAccount(action) => {
                     let initiate_delete_account_operation_id =
                         crate::models::OperationIdentifier::new(&operations);
@@ -1,5 +1,6 @@
 use crate::models::AccountIdentifier;
 use actix::Addr;
+use futures::StreamExt;
 use near_account_id::AccountId;
 use near_o11y::WithSpanContextExt;
 use near_primitives::hash::CryptoHash;
@@ -20,42 +21,54 @@ pub(crate) struct ExecutionToReceipts {
     receipts: HashMap<CryptoHash, AccountId>,
 }
 impl ExecutionToReceipts {
-    /// Fetches execution outcomes for given block and constructs a mapping from
-    /// transaction or receipt causing the execution to list of created
-    /// receipts’ hashes.
+    /// Fetches the chunks and execution outcomes of given block and constructs
+    /// a mapping from transaction or receipt causing the execution to list of
+    /// created receipts’ hashes.
+    ///
+    /// All chunks and the outcomes are requested at once rather than one
+    /// after another.
     pub(crate) async fn for_block(
         view_client_addr: &Addr<near_client::ViewClientActor>,
-        block_hash: CryptoHash,
+        block: &near_primitives::views::BlockView,
     ) -> crate::errors::Result<Self> {
-        let block = view_client_addr
-            .send(
-                near_client::GetBlock(near_primitives::types::BlockId::Hash(block_hash).into())
-                    .with_span_context(),
-            )
-            .await?
-            .map_err(|e| crate::errors::ErrorKind::InternalError(e.to_string()))?;
+        let chunks = futures::future::try_join_all(
+            block
+                .chunks
+                .iter()
+                .zip(block.header.chunk_mask.iter())
+                .filter(|(_, contained)| **contained)
+                .map(|(chunk, _)| async move {
+                    view_client_addr
+                        .send(
+                            near_client::GetChunk::ChunkHash(near_primitives::sharding::ChunkHash(
+                                chunk.chunk_hash,
+                            ))
+                            .with_span_context(),
+                        )
+                        .await?
+                        .map_err(|e| {
+                            crate::errors::ErrorKind::InternalInvariantError(e.to_string())
+                        })
+                }),
+        );
+        let outcomes = async {
+            view_client_addr
+                .send(
+                    near_client::GetExecutionOutcomesForBlock { block_hash: block.header.hash }
+                        .with_span_context(),
+                )
+                .await?
+                .map_err(crate::errors::ErrorKind::InternalInvariantError)
+        };
+        let (chunks, outcomes) = futures::try_join!(chunks, outcomes)?;
+
         let mut transactions = HashMap::new();
         let mut receipts = HashMap::new();
-        for (shard_id, contained) in block.header.chunk_mask.iter().enumerate() {
-            if *contained {
-                let chunk = view_client_addr
-                    .send(
-                        near_client::GetChunk::ChunkHash(near_primitives::sharding::ChunkHash(
-                            block.chunks[shard_id].chunk_hash,
-                        ))
-                        .with_span_context(),
-                    )
-                    .await?
-                    .map_err(|e| crate::errors::ErrorKind::InternalInvariantError(e.to_string()))?;
-                transactions.extend(chunk.transactions.into_iter().map(|t| (t.hash, t)));
-                receipts
-                    .extend(chunk.receipts.into_iter().map(|t| (t.receipt_id, t.predecessor_id)));
-            }
+        for chunk in chunks {
+            transactions.extend(chunk.transactions.into_iter().map(|t| (t.hash, t)));
+            receipts.extend(chunk.receipts.into_iter().map(|t| (t.receipt_id, t.predecessor_id)));
         }
-        let map = view_client_addr
-            .send(near_client::GetExecutionOutcomesForBlock { block_hash }.with_span_context())
-            .await?
-            .map_err(crate::errors::ErrorKind::InternalInvariantError)?
+        let map = outcomes
             .into_values()
             .flat_map(|outcomes| outcomes)
             .filter(|exec| !exec.outcome.receipt_ids.is_empty())
@@ -64,6 +77,34 @@ impl ExecutionToReceipts {
         Ok(Self { map, transactions, receipts })
     }
 
+    /// Looks up the predecessors of the receipts causing given state changes
+    /// which were not included in the block’s chunks, so that converting the
+    /// changes doesn’t need a query per change.  The receipts are requested
+    /// concurrently and each of them only once.  Receipts which can’t be
+    /// found are skipped.
+    async fn fetch_missing_receipts(
+        &mut self,
+        view_client_addr: &Addr<near_client::ViewClientActor>,
+        changes: &near_primitives::views::StateChangesView,
+    ) {
+        let missing: std::collections::HashSet<CryptoHash> = changes
+            .iter()
+            .filter_map(|change| receipt_hash_of_cause(&change.cause))
+            .filter(|receipt_hash| !self.receipts.contains_key(receipt_hash))
+            .collect();
+        let fetched = futures::stream::iter(missing)
+            .map(|receipt_id| async move {
+                let predecessor_id =
+                    get_predecessor_id_from_receipt_hash(view_client_addr, receipt_id).await?;
+                Some((receipt_id, predecessor_id))
+            })
+            .buffer_unordered(10)
+            .filter_map(futures::future::ready)
+            .collect::<Vec<_>>()
+            .await;
+        self.receipts.extend(fetched);
+    }
+
     /// Creates an empty mapping.  This is useful for tests.
     #[cfg(test)]
     pub(crate) fn empty() -> Self {
@@ -141,8 +182,25 @@ fn convert_cause_to_transaction_id(
     }
 }
 
-async fn get_predecessor_id_from_receipt_or_transaction(
-    view_client: &Addr<near_client::ViewClientActor>,
+/// Returns hash of the receipt which caused a state change, if any.
+fn receipt_hash_of_cause(
+    cause: &near_primitives::views::StateChangeCauseView,
+) -> Option<CryptoHash> {
+    use near_primitives::views::StateChangeCauseView;
+
+    match cause {
+        StateChangeCauseView::ReceiptProcessing { receipt_hash }
+        | StateChangeCauseView::PostponedReceipt { receipt_hash }
+        | StateChangeCauseView::ActionReceiptProcessingStarted { receipt_hash }
+        | StateChangeCauseView::ActionReceiptGasReward { receipt_hash } => Some(*receipt_hash),
+        _ => None,
+    }
+}
+
+/// Returns the account which initiated the transaction or receipt causing a
+/// state change.  Receipts not included in the block have to be fetched with
+/// `ExecutionToReceipts::fetch_missing_receipts` beforehand.
+fn get_predecessor_id_from_receipt_or_transaction(
     cause: &near_primitives::views::StateChangeCauseView,
     transactions_in_block: &HashMap<CryptoHash, SignedTransactionView>,
     receipts_in_block: &HashMap<CryptoHash, AccountId>,
@@ -151,31 +209,7 @@ async fn get_predecessor_id_from_receipt_or_transaction(
         near_primitives::views::StateChangeCauseView::TransactionProcessing { tx_hash } => {
             transactions_in_block.get(tx_hash)?.signer_id.clone()
         }
-        near_primitives::views::StateChangeCauseView::ReceiptProcessing { receipt_hash } => {
-            match receipts_in_block.get(receipt_hash) {
-                Some(t) => t.clone(),
-                None => get_predecessor_id_from_receipt_hash(view_client, *receipt_hash).await?,
-            }
-        }
-        near_primitives::views::StateChangeCauseView::PostponedReceipt { receipt_hash } => {
-            match receipts_in_block.get(receipt_hash) {
-                Some(t) => t.clone(),
-                None => get_predecessor_id_from_receipt_hash(view_client, *receipt_hash).await?,
-            }
-        }
-        near_primitives::views::StateChangeCauseView::ActionReceiptProcessingStarted {
-            receipt_hash,
-        } => match receipts_in_block.get(receipt_hash) {
-            Some(t) => t.clone(),
-            None => get_predecessor_id_from_receipt_hash(view_client, *receipt_hash).await?,
-        },
-        near_primitives::views::StateChangeCauseView::ActionReceiptGasReward { receipt_hash } => {
-            match receipts_in_block.get(receipt_hash) {
-                Some(t) => t.clone(),
-                None => get_predecessor_id_from_receipt_hash(view_client, *receipt_hash).await?,
-            }
-        }
-        _ => return None,
+        cause => receipts_in_block.get(&receipt_hash_of_cause(cause)?)?.clone(),
     };
     Some(crate::models::AccountIdentifier::from(predecessor_id))
 }
@@ -242,8 +276,9 @@ pub(crate) async fn convert_block_changes_to_transactions(
         near_primitives::types::AccountId,
         near_primitives::views::AccountView,
     >,
-    exec_to_rx: ExecutionToReceipts,
+    mut exec_to_rx: ExecutionToReceipts,
 ) -> crate::errors::Result<RosettaTransactionsMap> {
+    exec_to_rx.fetch_missing_receipts(view_client_addr, &accounts_changes).await;
     let mut transactions = RosettaTransactions::new(exec_to_rx, block_hash);
     for account_change in accounts_changes {
         let transactions_in_block = &transactions.exec_to_rx.transactions;
@@ -276,12 +311,10 @@ pub(crate) async fn convert_block_changes_to_transactions(
                     _ => None,
                 };
                 let predecessor_id = get_predecessor_id_from_receipt_or_transaction(
-                    view_client_addr,
                     &account_change.cause,
                     &transactions_in_block,
                     &receipts_in_block,
-                )
-                .await;
+                );
                 let previous_account_state = accounts_previous_state.get(&account_id);
                 convert_account_update_to_operations(
                     runtime_config,
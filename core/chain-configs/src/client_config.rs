@@ -154,6 +154,12 @@ pub struct ClientConfig {
     /// genesis file.  The value only affects the RPCs without influencing the
     /// protocol thus changing it per-node doesn’t affect the blockchain.
     pub max_gas_burnt_view: Option<Gas>,
+    /// Number of chunk apply results kept in memory to avoid executing the
+    /// same chunk twice. Zero disables the cache.
+    pub apply_chunk_cache_size: usize,
+    /// Save the transaction pool to the database on shutdown and restore the
+    /// transactions which are still valid on startup.
+    pub persist_tx_pool: bool,
     /// Re-export storage layer statistics as prometheus metrics.
     pub enable_statistics_export: bool,
     /// Number of threads to execute background migration work in client.
@@ -216,6 +222,8 @@ impl ClientConfig {
             view_client_throttle_period: Duration::from_secs(1),
             trie_viewer_state_size_limit: None,
             max_gas_burnt_view: None,
+            apply_chunk_cache_size: 0,
+            persist_tx_pool: false,
             enable_statistics_export: true,
             client_background_migration_threads: 1,
         }
@@ -0,0 +1,274 @@
+#![cfg(feature = "io_trace")]
+//! `tracing` layer that exports a sample of the IO trace to OpenTelemetry.
+//!
+//! The spans are the same ones [`IoTraceLayer`] writes to a file, but they are
+//! sent to the OTLP collector instead, where they can be inspected next to the
+//! other traces of the node. Like with [`SampledIoTraceLayer`], only a
+//! fraction of the top-level spans are exported, each with all its children.
+//!
+//! IO operations become events of the span they happen in and counters become
+//! attributes of the span. The block, shard and receipt that a span belongs to
+//! are copied from its ancestors, so that the IO of a specific receipt or shard
+//! can be searched for.
+//!
+//! [`IoTraceLayer`]: crate::io_tracer::IoTraceLayer
+//! [`SampledIoTraceLayer`]: crate::sampled_io_tracer::SampledIoTraceLayer
+
+use crate::sampled_io_tracer::RootSampler;
+use opentelemetry::trace::{Span as _, TraceContextExt, Tracer};
+use opentelemetry::{Context, KeyValue};
+use std::collections::HashMap;
+use tracing::{span, Subscriber};
+use tracing_subscriber::registry::LookupSpan;
+use tracing_subscriber::Layer;
+
+/// Span fields that are added to all descendants of the span.
+const INHERITED_FIELDS: [&str; 4] = ["block_height", "block_hash", "shard_id", "receipt_id"];
+
+/// Fields that are too verbose to be exported.
+const IGNORED_FIELDS: [&str; 2] = ["message", "node_counter"];
+
+/// Tracing layer that exports a sample of the IO trace as OpenTelemetry spans.
+pub struct IoTraceExportLayer<T> {
+    tracer: T,
+    sampler: RootSampler,
+}
+
+/// OpenTelemetry span of a sampled tracing span.
+///
+/// Note: Type used as key in `AnyMap` inside span extensions.
+struct ExportedSpan<S> {
+    span: S,
+    /// Values of `INHERITED_FIELDS`, from this span or the closest ancestor.
+    inherited: Vec<KeyValue>,
+    /// Counts of `io_tracer_count` events within the span.
+    counts: HashMap<String, i64>,
+}
+
+impl<S, T> Layer<S> for IoTraceExportLayer<T>
+where
+    S: Subscriber + for<'span> LookupSpan<'span>,
+    T: Tracer + 'static,
+    T::Span: Send + Sync + 'static,
+{
+    fn on_new_span(
+        &self,
+        attrs: &span::Attributes<'_>,
+        id: &span::Id,
+        ctx: tracing_subscriber::layer::Context<'_, S>,
+    ) {
+        let span = ctx.span(id).unwrap();
+        let (parent_cx, parent_inherited) = match span.parent() {
+            Some(parent) => match parent.extensions().get::<ExportedSpan<T::Span>>() {
+                Some(exported) => (
+                    Context::new().with_remote_span_context(exported.span.span_context().clone()),
+                    exported.inherited.clone(),
+                ),
+                // Only whole trees of spans are sampled.
+                None => return,
+            },
+            None if self.sampler.sample() => (Context::new(), vec![]),
+            None => return,
+        };
+
+        let mut fields = FieldVisitor::default();
+        attrs.record(&mut fields);
+        let mut attributes = fields.0;
+        let inherited = inherit(&parent_inherited, &attributes);
+        for kv in &inherited {
+            if !attributes.iter().any(|attribute| attribute.key == kv.key) {
+                attributes.push(kv.clone());
+            }
+        }
+
+        let otel_span = self
+            .tracer
+            .span_builder(attrs.metadata().name())
+            .with_attributes(attributes)
+            .start_with_context(&self.tracer, &parent_cx);
+        span.extensions_mut().insert(ExportedSpan {
+            span: otel_span,
+            inherited,
+            counts: HashMap::new(),
+        });
+    }
+
+    fn on_event(&self, event: &tracing::Event<'_>, ctx: tracing_subscriber::layer::Context<'_, S>) {
+        let span = match ctx.event_span(event) {
+            Some(span) => span,
+            // Events outside of any span cannot be attributed to a sample.
+            None => return,
+        };
+        let mut extensions = span.extensions_mut();
+        let exported = match extensions.get_mut::<ExportedSpan<T::Span>>() {
+            Some(exported) => exported,
+            None => return,
+        };
+
+        let mut fields = FieldVisitor::default();
+        event.record(&mut fields);
+        if event.metadata().target() == "io_tracer_count" {
+            if let Some(counter) = fields.get("counter") {
+                *exported.counts.entry(counter.as_str().into_owned()).or_default() += 1;
+            }
+            return;
+        }
+        // Same as in the IO trace, only DB and storage operations are of
+        // interest, other events are used for logging.
+        let op = match fields.get("db_op").or_else(|| fields.get("storage_op")) {
+            Some(op) => op.as_str().into_owned(),
+            None => return,
+        };
+        exported.span.add_event(op, fields.0);
+    }
+
+    fn on_record(
+        &self,
+        id: &span::Id,
+        values: &span::Record<'_>,
+        ctx: tracing_subscriber::layer::Context<'_, S>,
+    ) {
+        let span = ctx.span(id).unwrap();
+        let mut extensions = span.extensions_mut();
+        if let Some(exported) = extensions.get_mut::<ExportedSpan<T::Span>>() {
+            let mut fields = FieldVisitor::default();
+            values.record(&mut fields);
+            for kv in fields.0 {
+                exported.span.set_attribute(kv);
+            }
+        }
+    }
+
+    fn on_close(&self, id: span::Id, ctx: tracing_subscriber::layer::Context<'_, S>) {
+        let span = ctx.span(&id).unwrap();
+        let exported = span.extensions_mut().remove::<ExportedSpan<T::Span>>();
+        if let Some(ExportedSpan { span: mut otel_span, counts, .. }) = exported {
+            for (counter, count) in counts {
+                otel_span.set_attribute(KeyValue::new(counter, count));
+            }
+            otel_span.end();
+        }
+    }
+}
+
+impl<T> IoTraceExportLayer<T> {
+    pub(crate) fn new(tracer: T, sample_rate: f64) -> Self {
+        Self { tracer, sampler: RootSampler::new(sample_rate) }
+    }
+}
+
+/// Values of `INHERITED_FIELDS` for a span with the given attributes.
+fn inherit(parent_inherited: &[KeyValue], attributes: &[KeyValue]) -> Vec<KeyValue> {
+    let own = attributes.iter().filter(|kv| INHERITED_FIELDS.contains(&kv.key.as_str()));
+    parent_inherited
+        .iter()
+        .filter(|kv| !attributes.iter().any(|attribute| attribute.key == kv.key))
+        .chain(own)
+        .cloned()
+        .collect()
+}
+
+/// Collects the fields of spans and events as OpenTelemetry attributes.
+#[derive(Default)]
+struct FieldVisitor(Vec<KeyValue>);
+
+impl FieldVisitor {
+    fn get(&self, name: &str) -> Option<&opentelemetry::Value> {
+        self.0.iter().find(|kv| kv.key.as_str() == name).map(|kv| &kv.value)
+    }
+
+    fn push(&mut self, field: &tracing::field::Field, value: impl Into<opentelemetry::Value>) {
+        if !IGNORED_FIELDS.contains(&field.name()) {
+            self.0.push(KeyValue::new(field.name(), value));
+        }
+    }
+}
+
+impl tracing::field::Visit for FieldVisitor {
+    fn record_i64(&mut self, field: &tracing::field::Field, value: i64) {
+        self.push(field, value);
+    }
+    fn record_u64(&mut self, field: &tracing::field::Field, value: u64) {
+        match i64::try_from(value) {
+            Ok(value) => self.push(field, value),
+            Err(_) => self.push(field, value.to_string()),
+        }
+    }
+    fn record_bool(&mut self, field: &tracing::field::Field, value: bool) {
+        self.push(field, value);
+    }
+    fn record_f64(&mut self, field: &tracing::field::Field, value: f64) {
+        self.push(field, value);
+    }
+    fn record_str(&mut self, field: &tracing::field::Field, value: &str) {
+        self.push(field, value.to_owned());
+    }
+    fn record_debug(&mut self, field: &tracing::field::Field, value: &dyn std::fmt::Debug) {
+        self.push(field, format!("{value:?}"));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::IoTraceExportLayer;
+    use opentelemetry::sdk::export::trace::SpanData;
+    use opentelemetry::sdk::trace::{Span, SpanProcessor, TracerProvider};
+    use opentelemetry::trace::{TraceResult, TracerProvider as _};
+    use opentelemetry::{Context, Key, Value};
+    use std::sync::{Arc, Mutex};
+    use tracing_subscriber::layer::SubscriberExt;
+
+    /// Collects the spans that would be exported.
+    #[derive(Clone, Debug, Default)]
+    struct Collector(Arc<Mutex<Vec<SpanData>>>);
+
+    impl SpanProcessor for Collector {
+        fn on_start(&self, _span: &mut Span, _cx: &Context) {}
+        fn on_end(&self, span: SpanData) {
+            self.0.lock().unwrap().push(span);
+        }
+        fn force_flush(&self) -> TraceResult<()> {
+            Ok(())
+        }
+        fn shutdown(&mut self) -> TraceResult<()> {
+            Ok(())
+        }
+    }
+
+    fn attribute(span: &SpanData, key: &'static str) -> Option<Value> {
+        span.attributes.get(&Key::new(key)).cloned()
+    }
+
+    #[test]
+    fn test_io_trace_export() {
+        let collector = Collector::default();
+        let provider = TracerProvider::builder().with_span_processor(collector.clone()).build();
+        let layer = IoTraceExportLayer::new(provider.tracer("io_trace"), 0.5);
+        let subscriber = tracing_subscriber::registry().with(layer);
+        tracing::subscriber::with_default(subscriber, || {
+            for block_height in 0..2u64 {
+                let _root = tracing::info_span!("apply", block_height).entered();
+                let _receipt = tracing::info_span!("process_receipt", receipt_id = "r").entered();
+                tracing::trace!(target: "io_tracer", storage_op = "read", key = "k", size = 3u64);
+                tracing::trace!(target: "io_tracer_count", counter = "get_ref");
+                tracing::trace!(target: "io_tracer_count", counter = "get_ref");
+            }
+        });
+
+        let spans = collector.0.lock().unwrap();
+        let names: Vec<&str> = spans.iter().map(|span| span.name.as_ref()).collect();
+        assert_eq!(names, vec!["process_receipt", "apply"]);
+        let (receipt, root) = (&spans[0], &spans[1]);
+        assert_eq!(receipt.parent_span_id, root.span_context.span_id());
+        assert_eq!(receipt.span_context.trace_id(), root.span_context.trace_id());
+        assert_eq!(attribute(root, "block_height"), Some(Value::I64(1)));
+        assert_eq!(attribute(receipt, "block_height"), Some(Value::I64(1)));
+        assert_eq!(attribute(receipt, "receipt_id"), Some(Value::from("r")));
+        assert_eq!(attribute(receipt, "get_ref"), Some(Value::I64(2)));
+
+        let events: Vec<_> = receipt.events.iter().collect();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].name, "read");
+        assert!(events[0].attributes.iter().any(|kv| kv.key.as_str() == "key"));
+    }
+}
@@ -177,6 +177,11 @@ impl IoTraceLayer {
         (Self { make_writer }, guard)
     }
 
+    /// Writer for output that is not buffered in spans.
+    pub(crate) fn writer(&self) -> impl Write + '_ {
+        self.make_writer.make_writer()
+    }
+
     /// Print or buffer formatted tracing events that look like an IO event.
     ///
     /// IO events are:
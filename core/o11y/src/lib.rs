@@ -26,10 +26,12 @@ use tracing_subscriber::{fmt, reload, EnvFilter, Layer, Registry};
 
 /// Custom tracing subscriber implementation that produces IO traces.
 pub mod context;
+mod io_trace_export;
 mod io_tracer;
 pub mod macros;
 pub mod metrics;
 pub mod pretty;
+mod sampled_io_tracer;
 pub mod testonly;
 
 /// Produce a tracing-event for target "io_tracer" that will be consumed by the
@@ -109,6 +111,8 @@ pub struct DefaultSubscriberGuard<S> {
     writer_guard: Option<tracing_appender::non_blocking::WorkerGuard>,
     #[allow(dead_code)] // This field is never read, but has semantic purpose as a drop guard.
     io_trace_guard: Option<tracing_appender::non_blocking::WorkerGuard>,
+    #[allow(dead_code)] // This field is never read, but has semantic purpose as a drop guard.
+    io_trace_export_guard: Option<trace::TracerProvider>,
 }
 
 // Doesn't define WARN and ERROR, because the highest verbosity of spans is INFO.
@@ -146,6 +150,38 @@ pub struct Options {
     /// Enable JSON output of IO events, written to a file.
     #[clap(long)]
     record_io_trace: Option<PathBuf>,
+
+    /// Only record the IO trace for this fraction of top-level spans, in
+    /// `(0, 1]`. All spans are still counted exactly.
+    #[clap(long, requires("record-io-trace"))]
+    record_io_trace_sample_rate: Option<f64>,
+
+    /// Export the IO trace of this fraction of top-level spans, in `(0, 1]`,
+    /// to the OpenTelemetry collector. IO operations are exported as span
+    /// events.
+    #[clap(long)]
+    export_io_trace_sample_rate: Option<f64>,
+
+    /// Hide byte payloads, like function call arguments and storage keys and
+    /// values, in logs and IO traces. They are replaced by their length and a
+    /// prefix of their hash.
+    #[clap(long)]
+    log_redact_payloads: bool,
+
+    /// Approximate maximum length of abbreviated byte payloads in logs, 128
+    /// by default.
+    #[clap(long)]
+    log_max_payload_len: Option<usize>,
+}
+
+impl Options {
+    /// Applies the options for formatting payloads, see [`pretty::Config`].
+    fn set_pretty_config(&self) {
+        pretty::set_config(pretty::Config {
+            max_len: self.log_max_payload_len.unwrap_or(pretty::Config::default().max_len),
+            redact: self.log_redact_payloads,
+        });
+    }
 }
 
 impl<S: tracing::Subscriber + Send + Sync> DefaultSubscriberGuard<S> {
@@ -246,9 +282,7 @@ where
 // register timers and channels and whatnot.
 async fn add_opentelemetry_layer<S>(
     opentelemetry_level: OpenTelemetryLevel,
-    chain_id: String,
-    node_public_key: PublicKey,
-    account_id: Option<AccountId>,
+    resource: Resource,
     subscriber: S,
 ) -> (TracingLayer<S>, reload::Handle<LevelFilter, S>)
 where
@@ -257,6 +291,27 @@ where
     let filter = get_opentelemetry_filter(opentelemetry_level);
     let (filter, handle) = reload::Layer::<LevelFilter, S>::new(filter);
 
+    let tracer = opentelemetry_otlp::new_pipeline()
+        .tracing()
+        .with_exporter(opentelemetry_otlp::new_exporter().tonic())
+        .with_trace_config(
+            trace::config()
+                .with_sampler(Sampler::AlwaysOn)
+                .with_id_generator(IdGenerator::default())
+                .with_resource(resource),
+        )
+        .install_batch(opentelemetry::runtime::Tokio)
+        .unwrap();
+    let layer = tracing_opentelemetry::layer().with_tracer(tracer).with_filter(filter);
+    (subscriber.with(layer), handle)
+}
+
+/// Describes this node to the OpenTelemetry collector.
+fn otlp_resource(
+    chain_id: String,
+    node_public_key: PublicKey,
+    account_id: Option<AccountId>,
+) -> Resource {
     let mut resource = vec![
         KeyValue::new("chain_id", chain_id),
         KeyValue::new("node_id", node_public_key.to_string()),
@@ -270,20 +325,7 @@ where
         format!("neard:{}", node_public_key)
     };
     resource.push(KeyValue::new(SERVICE_NAME, service_name));
-
-    let tracer = opentelemetry_otlp::new_pipeline()
-        .tracing()
-        .with_exporter(opentelemetry_otlp::new_exporter().tonic())
-        .with_trace_config(
-            trace::config()
-                .with_sampler(Sampler::AlwaysOn)
-                .with_id_generator(IdGenerator::default())
-                .with_resource(Resource::new(resource)),
-        )
-        .install_batch(opentelemetry::runtime::Tokio)
-        .unwrap();
-    let layer = tracing_opentelemetry::layer().with_tracer(tracer).with_filter(filter);
-    (subscriber.with(layer), handle)
+    Resource::new(resource)
 }
 
 pub fn get_opentelemetry_filter(opentelemetry_level: OpenTelemetryLevel) -> LevelFilter {
@@ -315,6 +357,72 @@ where
     (io_layer, guard)
 }
 
+/// Like [`make_io_tracing_layer`] but only records a `sample_rate` fraction of
+/// the top-level spans, while counting all spans exactly.
+///
+/// Use this to observe a node for a long time, where a full IO trace would be
+/// too heavy.
+#[cfg(feature = "io_trace")]
+pub fn make_sampled_io_tracing_layer<S>(
+    file: std::fs::File,
+    sample_rate: f64,
+) -> (
+    Filtered<sampled_io_tracer::SampledIoTraceLayer, EnvFilter, S>,
+    tracing_appender::non_blocking::WorkerGuard,
+)
+where
+    S: tracing::Subscriber + for<'span> LookupSpan<'span>,
+{
+    use std::io::BufWriter;
+    let (base_io_layer, guard) =
+        sampled_io_tracer::SampledIoTraceLayer::new(BufWriter::new(file), sample_rate);
+    let io_layer = base_io_layer.with_filter(tracing_subscriber::filter::EnvFilter::new(
+        "store=trace,vm_logic=trace,host-function=trace,runtime=debug,io_tracer=trace,io_tracer_count=trace",
+    ));
+    (io_layer, guard)
+}
+
+/// The constructed layer exports a `sample_rate` fraction of the top-level
+/// spans of the IO trace to the OpenTelemetry collector.
+///
+/// Unlike [`make_io_tracing_layer`], the output can be inspected in standard
+/// tracing UIs, next to the other traces of the node. Spans are exported by
+/// their own tracer provider, which flushes the remaining spans when dropped.
+///
+/// Needs an async runtime to start the batch exporter.
+#[cfg(feature = "io_trace")]
+pub fn make_io_trace_export_layer<S>(
+    resource: Resource,
+    sample_rate: f64,
+) -> (Filtered<io_trace_export::IoTraceExportLayer<Tracer>, EnvFilter, S>, trace::TracerProvider)
+where
+    S: tracing::Subscriber + for<'span> LookupSpan<'span>,
+{
+    use opentelemetry::trace::TracerProvider as _;
+    let exporter =
+        opentelemetry_otlp::SpanExporterBuilder::from(opentelemetry_otlp::new_exporter().tonic())
+            .build_span_exporter()
+            .unwrap();
+    // Sampling is done by the layer, such that whole trees of spans are
+    // exported.
+    let provider = trace::TracerProvider::builder()
+        .with_batch_exporter(exporter, opentelemetry::runtime::Tokio)
+        .with_config(
+            trace::config()
+                .with_sampler(Sampler::AlwaysOn)
+                .with_id_generator(IdGenerator::default())
+                .with_resource(resource),
+        )
+        .build();
+    let tracer = provider.tracer("io_trace");
+    let io_layer = io_trace_export::IoTraceExportLayer::new(tracer, sample_rate).with_filter(
+        tracing_subscriber::filter::EnvFilter::new(
+            "store=trace,vm_logic=trace,host-function=trace,runtime=debug,io_tracer=trace,io_tracer_count=trace",
+        ),
+    );
+    (io_layer, provider)
+}
+
 fn use_color_output(options: &Options) -> bool {
     match options.color {
         ColorOutput::Always => true,
@@ -342,6 +450,7 @@ pub fn default_subscriber(
     options: &Options,
 ) -> DefaultSubscriberGuard<impl tracing::Subscriber + for<'span> LookupSpan<'span> + Send + Sync> {
     let color_output = use_color_output(options);
+    options.set_pretty_config();
 
     let make_writer = || {
         let stderr = std::io::stderr();
@@ -356,6 +465,7 @@ pub fn default_subscriber(
         local_subscriber_guard: None,
         writer_guard: None,
         io_trace_guard: None,
+        io_trace_export_guard: None,
     }
 }
 
@@ -371,6 +481,7 @@ pub async fn default_subscriber_with_opentelemetry(
     account_id: Option<AccountId>,
 ) -> DefaultSubscriberGuard<impl tracing::Subscriber + Send + Sync> {
     let color_output = use_color_output(options);
+    options.set_pretty_config();
 
     // Do not lock the `stderr` here to allow for things like `dbg!()` work during development.
     let stderr = std::io::stderr();
@@ -394,35 +505,52 @@ pub async fn default_subscriber_with_opentelemetry(
         .set(handle)
         .unwrap_or_else(|_| panic!("Failed to set Log Layer Filter"));
 
-    let (subscriber, handle) = add_opentelemetry_layer(
-        options.opentelemetry,
-        chain_id,
-        node_public_key,
-        account_id,
-        subscriber,
-    )
-    .await;
+    let resource = otlp_resource(chain_id, node_public_key, account_id);
+    let (subscriber, handle) =
+        add_opentelemetry_layer(options.opentelemetry, resource.clone(), subscriber).await;
     OTLP_LAYER_RELOAD_HANDLE
         .set(handle)
         .unwrap_or_else(|_| panic!("Failed to set OTLP Layer Filter"));
 
     #[allow(unused_mut)]
     let mut io_trace_guard = None;
+    #[allow(unused_mut)]
+    let mut io_trace_export_guard = None;
     #[cfg(feature = "io_trace")]
-    let subscriber = subscriber.with(options.record_io_trace.as_ref().map(|output_path| {
-        let (sub, guard) = make_io_tracing_layer(
+    let subscriber = {
+        let sample_rate = options.record_io_trace_sample_rate;
+        let output_file = options.record_io_trace.as_ref().map(|output_path| {
             std::fs::File::create(output_path)
-                .expect("unable to create or truncate IO trace output file"),
-        );
-        io_trace_guard = Some(guard);
-        sub
-    }));
+                .expect("unable to create or truncate IO trace output file")
+        });
+        let (full_file, sampled_file) = match sample_rate {
+            Some(_) => (None, output_file),
+            None => (output_file, None),
+        };
+        subscriber
+            .with(full_file.map(|file| {
+                let (sub, guard) = make_io_tracing_layer(file);
+                io_trace_guard = Some(guard);
+                sub
+            }))
+            .with(sampled_file.map(|file| {
+                let (sub, guard) = make_sampled_io_tracing_layer(file, sample_rate.unwrap());
+                io_trace_guard = Some(guard);
+                sub
+            }))
+            .with(options.export_io_trace_sample_rate.map(|sample_rate| {
+                let (sub, provider) = make_io_trace_export_layer(resource, sample_rate);
+                io_trace_export_guard = Some(provider);
+                sub
+            }))
+    };
 
     DefaultSubscriberGuard {
         subscriber: Some(subscriber),
         local_subscriber_guard: None,
         writer_guard: Some(writer_guard),
         io_trace_guard,
+        io_trace_export_guard,
     }
 }
 
@@ -1,5 +1,43 @@
 use near_primitives_core::hash::CryptoHash;
 use near_primitives_core::serialize::base64_display;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+/// Configuration of the formatters in this module.
+///
+/// The configuration is global so that it applies to `Debug` implementations
+/// and traces alike.  It’s set once by the node from its logging options, see
+/// [`set_config`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Config {
+    /// Approximate maximum length of the strings formatted by [`AbbrBytes`].
+    /// Values below 16 are treated as 16.
+    pub max_len: usize,
+    /// Whether [`Bytes`], [`AbbrBytes`] and [`StorageKey`] should hide the
+    /// formatted data.  Redacted data is formatted as its length and a prefix
+    /// of its hash, so that formatting is still deterministic and equal values
+    /// can be recognised across runs without leaking user payloads.
+    pub redact: bool,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self { max_len: 128, redact: false }
+    }
+}
+
+static MAX_LEN: AtomicUsize = AtomicUsize::new(128);
+static REDACT: AtomicBool = AtomicBool::new(false);
+
+/// Sets the configuration used by all formatters in this module.
+pub fn set_config(config: Config) {
+    MAX_LEN.store(config.max_len, Ordering::Relaxed);
+    REDACT.store(config.redact, Ordering::Relaxed);
+}
+
+/// Returns the configuration currently used by formatters in this module.
+pub fn config() -> Config {
+    Config { max_len: MAX_LEN.load(Ordering::Relaxed), redact: REDACT.load(Ordering::Relaxed) }
+}
 
 /// A wrapper for bytes slice which tries to guess best way to format it.
 ///
@@ -26,12 +64,12 @@ use near_primitives_core::serialize::base64_display;
 /// ```
 ///
 /// See also [`StorageKey`] which tries to guess if the data is not a crypto
-/// hash.
+/// hash.  If redaction is enabled in [`Config`], the data is not shown.
 pub struct Bytes<'a>(pub &'a [u8]);
 
 impl<'a> std::fmt::Display for Bytes<'a> {
     fn fmt(&self, fmt: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        bytes_format(self.0, fmt, false)
+        bytes_format(self.0, fmt, false, &config())
     }
 }
 
@@ -39,14 +77,14 @@ impl<'a> std::fmt::Display for Bytes<'a> {
 /// truncating the value if it’s too long.
 ///
 /// Behaves like [`Bytes`] but truncates the formatted string to around 128
-/// characters.  If the value is longer then that, the length of the value in
-/// bytes is included at the beginning and ellipsis is included at the end of
-/// the value.
+/// characters, or [`Config::max_len`] if configured.  If the value is longer
+/// then that, the length of the value in bytes is included at the beginning
+/// and ellipsis is included at the end of the value.
 pub struct AbbrBytes<T>(pub T);
 
 impl<'a> std::fmt::Display for AbbrBytes<&'a [u8]> {
     fn fmt(&self, fmt: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        truncated_bytes_format(self.0, fmt)
+        truncated_bytes_format(self.0, fmt, &config())
     }
 }
 
@@ -60,7 +98,7 @@ impl<'a> std::fmt::Display for AbbrBytes<Option<&'a [u8]>> {
     fn fmt(&self, fmt: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self.0 {
             None => fmt.write_str("None"),
-            Some(bytes) => truncated_bytes_format(bytes, fmt),
+            Some(bytes) => truncated_bytes_format(bytes, fmt, &config()),
         }
     }
 }
@@ -70,7 +108,8 @@ impl<'a> std::fmt::Display for AbbrBytes<Option<&'a [u8]>> {
 /// If the slice is exactly 32-byte long, it’s assumed to be a hash and is
 /// converted into base58 and printed surrounded by backtics.  Otherwise,
 /// behaves like [`Bytes`] representing the data as string if it contains ASCII
-/// printable bytes only or base64 otherwise.
+/// printable bytes only or base64 otherwise.  Hashes are shown even if
+/// redaction is enabled in [`Config`].
 ///
 /// The motivation for such choices is that we only ever use base58 to format
 /// hashes which are 32-byte long.  It’s therefore not useful to use it for any
@@ -97,7 +136,7 @@ pub struct StorageKey<'a>(pub &'a [u8]);
 
 impl<'a> std::fmt::Display for StorageKey<'a> {
     fn fmt(&self, fmt: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        bytes_format(self.0, fmt, true)
+        bytes_format(self.0, fmt, true, &config())
     }
 }
 
@@ -143,9 +182,12 @@ fn bytes_format(
     bytes: &[u8],
     fmt: &mut std::fmt::Formatter<'_>,
     consider_hash: bool,
+    config: &Config,
 ) -> std::fmt::Result {
     if consider_hash && bytes.len() == 32 {
         write!(fmt, "`{}`", CryptoHash(bytes.try_into().unwrap()))
+    } else if config.redact {
+        redacted_bytes_format(bytes, fmt)
     } else if bytes.iter().all(|ch| 0x20 <= *ch && *ch <= 0x7E) {
         // SAFETY: We’ve just checked that the value contains ASCII
         // characters only.
@@ -157,31 +199,45 @@ fn bytes_format(
 }
 
 /// Implementation of [`AbbrBytes`].
-fn truncated_bytes_format(bytes: &[u8], fmt: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-    const LIMIT: usize = 128;
+fn truncated_bytes_format(
+    bytes: &[u8],
+    fmt: &mut std::fmt::Formatter<'_>,
+    config: &Config,
+) -> std::fmt::Result {
+    if config.redact {
+        return redacted_bytes_format(bytes, fmt);
+    }
+    let limit = config.max_len.max(16);
     let len = bytes.len();
-    if bytes.iter().take(LIMIT - 2).all(|ch| 0x20 <= *ch && *ch <= 0x7E) {
-        if len <= LIMIT - 2 {
+    if bytes.iter().take(limit - 2).all(|ch| 0x20 <= *ch && *ch <= 0x7E) {
+        if len <= limit - 2 {
             // SAFETY: We’ve just checked that the value contains ASCII
             // characters only.
             let value = unsafe { std::str::from_utf8_unchecked(bytes) };
             write!(fmt, "'{value}'")
         } else {
-            let bytes = &bytes[..LIMIT - 9];
+            let bytes = &bytes[..limit - 9];
             let value = unsafe { std::str::from_utf8_unchecked(bytes) };
             write!(fmt, "({len})'{value}'…")
         }
     } else {
-        if bytes.len() <= LIMIT / 4 * 3 {
+        if bytes.len() <= limit / 4 * 3 {
             std::fmt::Display::fmt(&base64_display(bytes), fmt)
         } else {
-            let bytes = &bytes[..(LIMIT - 8) / 4 * 3];
+            let bytes = &bytes[..(limit - 8) / 4 * 3];
             let value = base64_display(bytes);
             write!(fmt, "({len}){value}…")
         }
     }
 }
 
+/// Formats bytes hidden by redaction as their length and the first eight
+/// characters of their hash.
+fn redacted_bytes_format(bytes: &[u8], fmt: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    let hash = CryptoHash::hash_bytes(bytes).to_string();
+    write!(fmt, "<redacted ({}) {}>", bytes.len(), &hash[..8])
+}
+
 #[cfg(test)]
 macro_rules! do_test_bytes_formatting {
     ($type:ident, $consider_hash:expr, $truncate:expr) => {{
@@ -247,6 +303,62 @@ fn test_storage_key() {
     do_test_bytes_formatting!(StorageKey, true, false);
 }
 
+#[cfg(test)]
+struct WithConfig<'a>(
+    &'a [u8],
+    Config,
+    fn(&[u8], &mut std::fmt::Formatter<'_>, &Config) -> std::fmt::Result,
+);
+
+#[cfg(test)]
+impl<'a> std::fmt::Display for WithConfig<'a> {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        (self.2)(self.0, fmt, &self.1)
+    }
+}
+
+#[test]
+fn test_max_len() {
+    let config = Config { max_len: 32, redact: false };
+    let abbr = |bytes| WithConfig(bytes, config, truncated_bytes_format).to_string();
+    assert_eq!("'foo'", abbr(b"foo"));
+    assert_eq!(
+        format!("(128)'{}'…", &"rabarbar".repeat(3)[..23]),
+        abbr("rabarbar".repeat(16).as_bytes())
+    );
+    // Too small limits are raised to 16.
+    let config = Config { max_len: 0, redact: false };
+    assert_eq!(
+        "(20)'aaaaaaa'…",
+        WithConfig(&[b'a'; 20], config, truncated_bytes_format).to_string()
+    );
+}
+
+#[test]
+fn test_redacted() {
+    let config = Config { max_len: 128, redact: true };
+    let bytes = |bytes| {
+        WithConfig(bytes, config, |bytes, fmt, config| bytes_format(bytes, fmt, false, config))
+            .to_string()
+    };
+    let storage_key = |bytes| {
+        WithConfig(bytes, config, |bytes, fmt, config| bytes_format(bytes, fmt, true, config))
+            .to_string()
+    };
+    let abbr = |bytes| WithConfig(bytes, config, truncated_bytes_format).to_string();
+
+    assert_eq!("<redacted (3) 3yMApqCu>", bytes(b"foo"));
+    assert_eq!("<redacted (3) 3yMApqCu>", abbr(b"foo"));
+    assert_eq!("<redacted (3) 3yMApqCu>", storage_key(b"foo"));
+    assert_eq!(
+        format!("<redacted (128) {}>", &CryptoHash::hash_bytes(&[7; 128]).to_string()[..8]),
+        abbr(&[7; 128])
+    );
+    // Hashes are not redacted in storage keys.
+    let hash = CryptoHash::hash_bytes(b"foo");
+    assert_eq!("`3yMApqCuCjXDWPrbjfR5mjCPTHqFG8Pux1TxQrEM35jj`", storage_key(hash.as_bytes()));
+}
+
 #[test]
 fn test_slice() {
     macro_rules! test {
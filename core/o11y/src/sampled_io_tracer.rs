@@ -0,0 +1,238 @@
+#![cfg(feature = "io_trace")]
+//! `tracing` layer that records a sample of the IO trace.
+//!
+//! A full IO trace of a node following the chain is very heavy, while plain
+//! metrics lose the structure of what happened. This layer sits in between:
+//! only a fraction of the top-level spans are recorded with all details, in
+//! the same format as [`IoTraceLayer`], but every span is counted.
+//!
+//! When a recorded top-level span is closed, a `span_counts` line is printed
+//! with the exact number of spans by name since the previous such line,
+//! including the spans that were not sampled. The replay command of the
+//! estimator uses these lines to extrapolate from the sample to the full
+//! workload.
+
+use crate::io_tracer::IoTraceLayer;
+use std::collections::BTreeMap;
+use std::io::Write;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use tracing::{span, Subscriber};
+use tracing_appender::non_blocking::WorkerGuard;
+use tracing_subscriber::registry::{LookupSpan, SpanRef};
+use tracing_subscriber::Layer;
+
+/// Tracing layer that produces an IO trace for a sample of top-level spans
+/// and exact span counts for all of them.
+pub struct SampledIoTraceLayer {
+    inner: IoTraceLayer,
+    sampler: RootSampler,
+    counts: Mutex<SpanCounts>,
+}
+
+/// Deterministic sampling of top-level spans.
+pub(crate) struct RootSampler {
+    /// Fraction of top-level spans to record, in `(0, 1]`.
+    sample_rate: f64,
+    /// Number of top-level spans seen so far.
+    roots: AtomicU64,
+}
+
+/// Exact number of spans since the last `span_counts` line.
+#[derive(Default)]
+struct SpanCounts {
+    roots: u64,
+    sampled_roots: u64,
+    by_name: BTreeMap<&'static str, u64>,
+}
+
+/// Whether a span and all of its children are recorded.
+///
+/// Note: Type used as key in `AnyMap` inside span extensions.
+struct Sampled(bool);
+
+impl<S: Subscriber + for<'span> LookupSpan<'span>> Layer<S> for SampledIoTraceLayer {
+    fn on_new_span(
+        &self,
+        attrs: &span::Attributes<'_>,
+        id: &span::Id,
+        ctx: tracing_subscriber::layer::Context<'_, S>,
+    ) {
+        let (sampled, is_root) = {
+            let span = ctx.span(id).unwrap();
+            let sampled = match span.parent() {
+                Some(parent) => is_sampled(&parent),
+                None => self.sampler.sample(),
+            };
+            span.extensions_mut().insert(Sampled(sampled));
+            (sampled, span.parent().is_none())
+        };
+        {
+            let mut counts = self.counts.lock().unwrap();
+            *counts.by_name.entry(attrs.metadata().name()).or_default() += 1;
+            if is_root {
+                counts.roots += 1;
+                counts.sampled_roots += sampled as u64;
+            }
+        }
+        if sampled {
+            self.inner.on_new_span(attrs, id, ctx);
+        }
+    }
+
+    fn on_event(&self, event: &tracing::Event<'_>, ctx: tracing_subscriber::layer::Context<'_, S>) {
+        // Events outside of any span cannot be attributed to a sample and are
+        // dropped.
+        if ctx.event_span(event).map_or(false, |span| is_sampled(&span)) {
+            self.inner.on_event(event, ctx);
+        }
+    }
+
+    fn on_exit(&self, id: &span::Id, ctx: tracing_subscriber::layer::Context<'_, S>) {
+        if ctx.span(id).map_or(false, |span| is_sampled(&span)) {
+            self.inner.on_exit(id, ctx);
+        }
+    }
+
+    fn on_close(&self, id: span::Id, ctx: tracing_subscriber::layer::Context<'_, S>) {
+        // A span can be entered and exited many times, e.g. when it
+        // instruments a future, but it is closed only once.
+        let span = ctx.span(&id).unwrap();
+        if span.parent().is_none() && is_sampled(&span) {
+            let counts = std::mem::take(&mut *self.counts.lock().unwrap());
+            writeln!(self.inner.writer(), "{}", counts.output_line()).unwrap();
+        }
+    }
+
+    fn on_record(
+        &self,
+        id: &span::Id,
+        values: &span::Record<'_>,
+        ctx: tracing_subscriber::layer::Context<'_, S>,
+    ) {
+        if ctx.span(id).map_or(false, |span| is_sampled(&span)) {
+            self.inner.on_record(id, values, ctx);
+        }
+    }
+}
+
+fn is_sampled<'a, R: LookupSpan<'a>>(span: &SpanRef<'a, R>) -> bool {
+    span.extensions().get::<Sampled>().map_or(false, |s| s.0)
+}
+
+impl SampledIoTraceLayer {
+    pub(crate) fn new<W: 'static + Write + Send + Sync>(
+        out: W,
+        sample_rate: f64,
+    ) -> (Self, WorkerGuard) {
+        let sampler = RootSampler::new(sample_rate);
+        let (inner, guard) = IoTraceLayer::new(out);
+        let layer = Self { inner, sampler, counts: Mutex::new(SpanCounts::default()) };
+        (layer, guard)
+    }
+}
+
+impl RootSampler {
+    pub(crate) fn new(sample_rate: f64) -> Self {
+        assert!(
+            sample_rate > 0.0 && sample_rate <= 1.0,
+            "IO trace sample rate must be in (0, 1], got {sample_rate}"
+        );
+        Self { sample_rate, roots: AtomicU64::new(0) }
+    }
+
+    /// Decides whether the next top-level span is recorded.
+    ///
+    /// Sampling is deterministic: out of `n` top-level spans, exactly
+    /// `floor(n * sample_rate)` are recorded, spread evenly.
+    pub(crate) fn sample(&self) -> bool {
+        let n = self.roots.fetch_add(1, Ordering::Relaxed);
+        ((n + 1) as f64 * self.sample_rate).floor() > (n as f64 * self.sample_rate).floor()
+    }
+}
+
+impl SpanCounts {
+    fn output_line(&self) -> String {
+        let mut line =
+            format!("span_counts roots={} sampled_roots={}", self.roots, self.sampled_roots);
+        for (name, count) in &self.by_name {
+            line.push_str(&format!(" {name}={count}"));
+        }
+        line
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SampledIoTraceLayer;
+    use std::io::Write;
+    use std::sync::{Arc, Mutex};
+    use tracing_subscriber::layer::SubscriberExt;
+
+    #[derive(Clone, Default)]
+    struct SharedBuffer(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for SharedBuffer {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_sampled_io_trace() {
+        let buffer = SharedBuffer::default();
+        let (layer, guard) = SampledIoTraceLayer::new(buffer.clone(), 0.5);
+        let subscriber = tracing_subscriber::registry().with(layer);
+        tracing::subscriber::with_default(subscriber, || {
+            for i in 0..4 {
+                let _root = tracing::info_span!("apply", i).entered();
+                for _ in 0..2 {
+                    let _receipt = tracing::info_span!("process_receipt").entered();
+                }
+            }
+        });
+        drop(guard);
+
+        let output = String::from_utf8(buffer.0.lock().unwrap().clone()).unwrap();
+        let lines: Vec<&str> = output.lines().collect();
+        assert_eq!(
+            lines,
+            vec![
+                "apply i=1",
+                "  process_receipt ",
+                "  process_receipt ",
+                "span_counts roots=2 sampled_roots=1 apply=2 process_receipt=4",
+                "apply i=3",
+                "  process_receipt ",
+                "  process_receipt ",
+                "span_counts roots=2 sampled_roots=1 apply=2 process_receipt=4",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_span_counts_of_reentered_span() {
+        let buffer = SharedBuffer::default();
+        let (layer, guard) = SampledIoTraceLayer::new(buffer.clone(), 1.0);
+        let subscriber = tracing_subscriber::registry().with(layer);
+        tracing::subscriber::with_default(subscriber, || {
+            let root = tracing::info_span!("apply");
+            for _ in 0..3 {
+                let _entered = root.enter();
+                let _receipt = tracing::info_span!("process_receipt").entered();
+            }
+        });
+        drop(guard);
+
+        let output = String::from_utf8(buffer.0.lock().unwrap().clone()).unwrap();
+        let span_counts: Vec<&str> =
+            output.lines().filter(|line| line.starts_with("span_counts")).collect();
+        assert_eq!(
+            span_counts,
+            vec!["span_counts roots=1 sampled_roots=1 apply=1 process_receipt=3"]
+        );
+    }
+}
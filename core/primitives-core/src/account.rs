@@ -1,11 +1,12 @@
 use borsh::{BorshDeserialize, BorshSerialize};
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::io;
 
 pub use near_account_id as id;
 
 use crate::hash::CryptoHash;
-use crate::serialize::dec_format;
+use crate::serialize::{dec_format, dec_format_map};
 use crate::types::{Balance, Nonce, StorageUsage};
 #[derive(
     BorshSerialize, BorshDeserialize, Serialize, Deserialize, PartialEq, Eq, Debug, Clone, Copy,
@@ -145,7 +146,16 @@ impl BorshSerialize for Account {
 /// that can be issued.
 /// `account_id,public_key` is a key in the state
 #[derive(
-    BorshSerialize, BorshDeserialize, Serialize, Deserialize, PartialEq, Eq, Hash, Clone, Debug,
+    BorshSerialize,
+    BorshDeserialize,
+    Serialize,
+    Deserialize,
+    PartialEq,
+    Eq,
+    Hash,
+    Clone,
+    Debug,
+    arbitrary::Arbitrary,
 )]
 pub struct AccessKey {
     /// Nonce for this access key, used for tx nonce generation. When access key is created, nonce
@@ -167,7 +177,16 @@ impl AccessKey {
 
 /// Defines permissions for AccessKey
 #[derive(
-    BorshSerialize, BorshDeserialize, Serialize, Deserialize, PartialEq, Eq, Hash, Clone, Debug,
+    BorshSerialize,
+    BorshDeserialize,
+    Serialize,
+    Deserialize,
+    PartialEq,
+    Eq,
+    Hash,
+    Clone,
+    Debug,
+    arbitrary::Arbitrary,
 )]
 pub enum AccessKeyPermission {
     FunctionCall(FunctionCallPermission),
@@ -181,9 +200,7 @@ pub enum AccessKeyPermission {
 /// The permission can limit the allowed balance to be spent on the prepaid gas.
 /// It also restrict the account ID of the receiver for this function call.
 /// It also can restrict the method name for the allowed function calls.
-#[derive(
-    BorshSerialize, BorshDeserialize, Serialize, Deserialize, PartialEq, Eq, Hash, Clone, Debug,
-)]
+#[derive(Serialize, Deserialize, PartialEq, Eq, Hash, Clone, Debug, arbitrary::Arbitrary)]
 pub struct FunctionCallPermission {
     /// Allowance is a balance limit to use by this access key to pay for function call gas and
     /// transaction fees. When this access key is used, both account balance and the allowance is
@@ -204,6 +221,89 @@ pub struct FunctionCallPermission {
     /// function call of one of the given method names.
     /// Empty list means any method name can be used.
     pub method_names: Vec<String>,
+
+    /// Allowances of individual methods, from the method name to the balance which is left to
+    /// spend on calls of the method. A call of a method listed here decreases both its method
+    /// allowance and `allowance`, calls of other methods are only limited by `allowance`.
+    /// NOTE: Like `allowance`, method allowances can only be changed by re-creating the key.
+    #[serde(with = "dec_format_map", default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub method_allowances: BTreeMap<String, Balance>,
+}
+
+/// Tag which starts the Borsh encoding of a `FunctionCallPermission` with method allowances.
+///
+/// The encoding of a permission without method allowances is the same as before they existed and
+/// starts with the `Option` tag of `allowance`, which is 0 or 1. Binaries without support for
+/// method allowances reject the tag as an invalid `Option`.
+const FUNCTION_CALL_PERMISSION_V2_TAG: u8 = 2;
+
+impl BorshSerialize for FunctionCallPermission {
+    fn serialize<W: io::Write>(&self, writer: &mut W) -> io::Result<()> {
+        let has_method_allowances = !self.method_allowances.is_empty();
+        if has_method_allowances {
+            BorshSerialize::serialize(&FUNCTION_CALL_PERMISSION_V2_TAG, writer)?;
+        }
+        BorshSerialize::serialize(&self.allowance, writer)?;
+        BorshSerialize::serialize(&self.receiver_id, writer)?;
+        BorshSerialize::serialize(&self.method_names, writer)?;
+        if has_method_allowances {
+            BorshSerialize::serialize(&self.method_allowances, writer)?;
+        }
+        Ok(())
+    }
+}
+
+impl BorshDeserialize for FunctionCallPermission {
+    fn deserialize(buf: &mut &[u8]) -> Result<Self, io::Error> {
+        let has_method_allowances =
+            cfg!(feature = "protocol_feature_function_call_method_allowances")
+                && buf.first() == Some(&FUNCTION_CALL_PERMISSION_V2_TAG);
+        if has_method_allowances {
+            *buf = &buf[1..];
+        }
+        let allowance = BorshDeserialize::deserialize(buf)?;
+        let receiver_id = BorshDeserialize::deserialize(buf)?;
+        let method_names = BorshDeserialize::deserialize(buf)?;
+        let method_allowances = if has_method_allowances {
+            let method_allowances = deserialize_method_allowances(buf)?;
+            // Keep the encoding unique, permissions without method allowances are encoded
+            // without the tag.
+            if method_allowances.is_empty() {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "tagged function call permission without method allowances",
+                ));
+            }
+            method_allowances
+        } else {
+            BTreeMap::new()
+        };
+        Ok(Self { allowance, receiver_id, method_names, method_allowances })
+    }
+}
+
+/// Decodes method allowances like a Borsh `BTreeMap`, but only from its canonical encoding.
+///
+/// Borsh decodes maps with unsorted or duplicate keys, in which case many encodings decode to the
+/// same permission. Transaction hashes and state must not depend on which of them was used, so
+/// the keys have to be strictly increasing.
+fn deserialize_method_allowances(buf: &mut &[u8]) -> io::Result<BTreeMap<String, Balance>> {
+    let len = <u32 as BorshDeserialize>::deserialize(buf)?;
+    let mut method_allowances = BTreeMap::new();
+    let mut last_method_name: Option<String> = None;
+    for _ in 0..len {
+        let method_name = <String as BorshDeserialize>::deserialize(buf)?;
+        let allowance = <Balance as BorshDeserialize>::deserialize(buf)?;
+        if last_method_name.as_ref().map_or(false, |last| *last >= method_name) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "method allowances must be sorted by method name without duplicates",
+            ));
+        }
+        last_method_name = Some(method_name.clone());
+        method_allowances.insert(method_name, allowance);
+    }
+    Ok(method_allowances)
 }
 
 #[cfg(test)]
@@ -241,4 +341,83 @@ mod tests {
             <Account as BorshDeserialize>::deserialize(&mut new_bytes).unwrap();
         assert_eq!(deserialized_account, new_account);
     }
+
+    #[derive(BorshSerialize)]
+    struct LegacyFunctionCallPermission {
+        allowance: Option<Balance>,
+        receiver_id: String,
+        method_names: Vec<String>,
+    }
+
+    fn function_call_permission() -> (FunctionCallPermission, LegacyFunctionCallPermission) {
+        let permission = FunctionCallPermission {
+            allowance: Some(100),
+            receiver_id: "bob.near".to_string(),
+            method_names: vec!["foo".to_string(), "bar".to_string()],
+            method_allowances: BTreeMap::new(),
+        };
+        let legacy = LegacyFunctionCallPermission {
+            allowance: permission.allowance,
+            receiver_id: permission.receiver_id.clone(),
+            method_names: permission.method_names.clone(),
+        };
+        (permission, legacy)
+    }
+
+    #[test]
+    fn test_function_call_permission_serialization() {
+        // Without method allowances, the encoding didn't change.
+        let (permission, legacy) = function_call_permission();
+        let bytes = permission.try_to_vec().unwrap();
+        assert_eq!(bytes, legacy.try_to_vec().unwrap());
+        assert_eq!(FunctionCallPermission::try_from_slice(&bytes).unwrap(), permission);
+    }
+
+    #[test]
+    #[cfg(feature = "protocol_feature_function_call_method_allowances")]
+    fn test_function_call_permission_method_allowances_serialization() {
+        let (mut permission, legacy) = function_call_permission();
+        permission.method_allowances.insert("foo".to_string(), 10);
+        let bytes = permission.try_to_vec().unwrap();
+        assert_eq!(bytes[0], FUNCTION_CALL_PERMISSION_V2_TAG);
+        assert_eq!(FunctionCallPermission::try_from_slice(&bytes).unwrap(), permission);
+
+        // The permission is also decoded correctly inside of an access key.
+        let access_key =
+            AccessKey { nonce: 7, permission: AccessKeyPermission::FunctionCall(permission) };
+        let bytes = access_key.try_to_vec().unwrap();
+        assert_eq!(AccessKey::try_from_slice(&bytes).unwrap(), access_key);
+
+        let mut tagged_without_allowances = vec![FUNCTION_CALL_PERMISSION_V2_TAG];
+        tagged_without_allowances.extend(legacy.try_to_vec().unwrap());
+        tagged_without_allowances.extend(0u32.to_le_bytes());
+        FunctionCallPermission::try_from_slice(&tagged_without_allowances).unwrap_err();
+    }
+
+    #[test]
+    #[cfg(feature = "protocol_feature_function_call_method_allowances")]
+    fn test_function_call_permission_non_canonical_method_allowances() {
+        let (_, legacy) = function_call_permission();
+        let encode = |method_allowances: &[(&str, Balance)]| {
+            let mut bytes = vec![FUNCTION_CALL_PERMISSION_V2_TAG];
+            bytes.extend(legacy.try_to_vec().unwrap());
+            bytes.extend((method_allowances.len() as u32).to_le_bytes());
+            for (method_name, allowance) in method_allowances {
+                bytes.extend(method_name.to_string().try_to_vec().unwrap());
+                bytes.extend(allowance.to_le_bytes());
+            }
+            bytes
+        };
+
+        let sorted = encode(&[("bar", 1), ("foo", 2)]);
+        let permission = FunctionCallPermission::try_from_slice(&sorted).unwrap();
+        assert_eq!(permission.try_to_vec().unwrap(), sorted);
+
+        let unsorted = encode(&[("foo", 2), ("bar", 1)]);
+        let err = FunctionCallPermission::try_from_slice(&unsorted).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        let duplicate = encode(&[("bar", 1), ("bar", 1), ("foo", 2)]);
+        let err = FunctionCallPermission::try_from_slice(&duplicate).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
 }
@@ -0,0 +1,154 @@
+//! Amounts of gas and tokens with checked arithmetic.
+//!
+//! [`Gas`] and [`Balance`] are plain integers, so fee calculations can
+//! silently overflow in release builds unless every operation is written with
+//! `checked_*` or `saturating_*`. The newtypes here only offer arithmetic that
+//! makes overflow explicit. They serialize exactly like the wrapped integer,
+//! so they can replace the bare types in existing code one place at a time.
+
+use crate::types::{Balance, Gas};
+use borsh::{BorshDeserialize, BorshSerialize};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+macro_rules! checked_amount {
+    ($(#[$attr:meta])* $name:ident($inner:ty)) => {
+        $(#[$attr])*
+        #[derive(
+            BorshSerialize,
+            BorshDeserialize,
+            Serialize,
+            Deserialize,
+            Clone,
+            Copy,
+            Default,
+            PartialEq,
+            Eq,
+            PartialOrd,
+            Ord,
+            Hash,
+            Debug,
+        )]
+        #[serde(transparent)]
+        pub struct $name($inner);
+
+        impl $name {
+            pub const ZERO: Self = Self(0);
+            pub const MAX: Self = Self(<$inner>::MAX);
+
+            pub const fn new(amount: $inner) -> Self {
+                Self(amount)
+            }
+
+            /// Returns the wrapped integer.
+            pub const fn get(self) -> $inner {
+                self.0
+            }
+
+            pub fn checked_add(self, other: Self) -> Option<Self> {
+                self.0.checked_add(other.0).map(Self)
+            }
+
+            pub fn checked_sub(self, other: Self) -> Option<Self> {
+                self.0.checked_sub(other.0).map(Self)
+            }
+
+            pub fn checked_mul(self, factor: $inner) -> Option<Self> {
+                self.0.checked_mul(factor).map(Self)
+            }
+
+            pub fn checked_div(self, divisor: $inner) -> Option<Self> {
+                self.0.checked_div(divisor).map(Self)
+            }
+
+            pub fn saturating_add(self, other: Self) -> Self {
+                Self(self.0.saturating_add(other.0))
+            }
+
+            pub fn saturating_sub(self, other: Self) -> Self {
+                Self(self.0.saturating_sub(other.0))
+            }
+
+            pub fn saturating_mul(self, factor: $inner) -> Self {
+                Self(self.0.saturating_mul(factor))
+            }
+
+            /// Sum of all amounts, or `None` on overflow.
+            pub fn checked_sum(amounts: impl IntoIterator<Item = Self>) -> Option<Self> {
+                amounts.into_iter().try_fold(Self::ZERO, Self::checked_add)
+            }
+        }
+
+        impl From<$inner> for $name {
+            fn from(amount: $inner) -> Self {
+                Self(amount)
+            }
+        }
+
+        impl From<$name> for $inner {
+            fn from(amount: $name) -> Self {
+                amount.0
+            }
+        }
+
+        impl fmt::Display for $name {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                fmt::Display::fmt(&self.0, f)
+            }
+        }
+    };
+}
+
+checked_amount!(
+    /// Amount of gas, see [`Gas`].
+    NearGas(Gas)
+);
+
+checked_amount!(
+    /// Amount of tokens, see [`Balance`].
+    NearBalance(Balance)
+);
+
+impl NearGas {
+    /// Price of this amount of gas at `gas_price` per unit, or `None` on
+    /// overflow.
+    pub fn checked_cost(self, gas_price: NearBalance) -> Option<NearBalance> {
+        gas_price.checked_mul(Balance::from(self.0))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{NearBalance, NearGas};
+    use borsh::{BorshDeserialize, BorshSerialize};
+
+    #[test]
+    fn test_checked_arithmetic() {
+        let gas = NearGas::new(10);
+        assert_eq!(gas.checked_add(NearGas::new(5)), Some(NearGas::new(15)));
+        assert_eq!(NearGas::MAX.checked_add(gas), None);
+        assert_eq!(gas.checked_sub(NearGas::new(11)), None);
+        assert_eq!(gas.saturating_sub(NearGas::new(11)), NearGas::ZERO);
+        assert_eq!(gas.checked_mul(u64::MAX), None);
+        assert_eq!(gas.saturating_mul(u64::MAX), NearGas::MAX);
+        assert_eq!(gas.checked_div(0), None);
+        assert_eq!(NearGas::checked_sum([gas, gas, gas]), Some(NearGas::new(30)));
+        assert_eq!(NearGas::checked_sum([gas, NearGas::MAX]), None);
+
+        assert_eq!(gas.checked_cost(NearBalance::new(3)), Some(NearBalance::new(30)));
+        assert_eq!(NearGas::MAX.checked_cost(NearBalance::MAX), None);
+    }
+
+    #[test]
+    fn test_serialization_passthrough() {
+        let gas = NearGas::new(1_000);
+        assert_eq!(gas.try_to_vec().unwrap(), 1_000u64.try_to_vec().unwrap());
+        assert_eq!(NearGas::try_from_slice(&1_000u64.try_to_vec().unwrap()).unwrap(), gas);
+        assert_eq!(serde_json::to_string(&gas).unwrap(), "1000");
+        assert_eq!(serde_json::from_str::<NearGas>("1000").unwrap(), gas);
+
+        let balance = NearBalance::new(7);
+        assert_eq!(balance.try_to_vec().unwrap(), 7u128.try_to_vec().unwrap());
+        assert_eq!(balance.to_string(), "7");
+    }
+}
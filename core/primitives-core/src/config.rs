@@ -5,7 +5,7 @@ use std::collections::hash_map::DefaultHasher;
 use std::hash::{Hash, Hasher};
 use strum::{Display, EnumCount};
 
-#[derive(Clone, Debug, Hash, Serialize, Deserialize, PartialEq, Eq)]
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
 pub struct VMConfig {
     /// Costs for runtime externals
     pub ext_costs: ExtCostsConfig,
@@ -17,6 +17,36 @@ pub struct VMConfig {
 
     /// Describes limits for VM and Runtime.
     pub limit_config: VMLimitConfig,
+
+    /// Reuse the linear memory of a contract call for the next call to the
+    /// same method of the same contract on the same thread.
+    ///
+    /// This targets chunks with many consecutive calls to one contract, such
+    /// as dozens of `ft_transfer` calls to one token. Only the mapping of the
+    /// memory is reused: after a call, its pages are reset to copy-on-write
+    /// zero pages and the next instantiation initializes them like a new
+    /// memory, data segments included. Memories that were grown by the call
+    /// are not reused.
+    ///
+    /// Only the Wasmer2 VM implements this, and only on Linux. Other VMs
+    /// ignore it. This is a node-local option rather than a protocol
+    /// parameter, so it is neither serialized nor part of the hash.
+    #[serde(skip)]
+    pub reuse_memory: bool,
+}
+
+/// Leaves out `reuse_memory`, which does not change how contracts are
+/// compiled or executed, so that toggling it keeps compiled contract cache
+/// keys.
+impl Hash for VMConfig {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        let VMConfig { ext_costs, grow_mem_cost, regular_op_cost, limit_config, reuse_memory: _ } =
+            self;
+        ext_costs.hash(state);
+        grow_mem_cost.hash(state);
+        regular_op_cost.hash(state);
+        limit_config.hash(state);
+    }
 }
 
 /// Describes limits for VM and Runtime.
@@ -92,6 +122,17 @@ pub struct VMLimitConfig {
     /// If present, stores max number of locals declared globally in one contract
     #[serde(skip_serializing_if = "Option::is_none")]
     pub max_locals_per_contract: Option<u64>,
+    /// If present, stores max number of globals, defined or imported, in one
+    /// contract
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_globals_per_contract: Option<u64>,
+    /// If present, stores max total initial size of the tables in one contract
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_table_elements_per_contract: Option<u64>,
+    /// If present, stores max total size in bytes of the custom sections in one
+    /// contract, including their names
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_custom_sections_size_per_contract: Option<u64>,
     /// Whether to enforce account_id well-formedness where it wasn't enforced
     /// historically.
     #[serde(default = "AccountIdValidityRulesVersion::v0")]
@@ -164,6 +205,7 @@ impl VMConfig {
             grow_mem_cost: 1,
             regular_op_cost: (SAFETY_MULTIPLIER as u32) * 1285457,
             limit_config: VMLimitConfig::test(),
+            reuse_memory: false,
         }
     }
 
@@ -182,6 +224,7 @@ impl VMConfig {
             regular_op_cost: 0,
             // We shouldn't have any costs in the limit config.
             limit_config: VMLimitConfig { max_gas_burnt: u64::MAX, ..VMLimitConfig::test() },
+            reuse_memory: false,
         }
     }
 }
@@ -237,6 +280,12 @@ impl VMLimitConfig {
             // necessary (they only take constant operands indicating the local to access), which
             // is 4 bytes worth of code for each local.
             max_locals_per_contract: Some(max_contract_size / 4),
+            // Contracts compiled from Rust typically have only a handful of
+            // globals and tables of a few thousand entries, custom sections
+            // only carry debug info like function names.
+            max_globals_per_contract: Some(10_000),
+            max_table_elements_per_contract: Some(100_000),
+            max_custom_sections_size_per_contract: Some(max_contract_size / 4),
             account_id_validity_rules_version: AccountIdValidityRulesVersion::V1,
         }
     }
@@ -247,6 +296,9 @@ impl VMLimitConfig {
 pub struct ViewConfig {
     /// If specified, defines max burnt gas per view method.
     pub max_gas_burnt: Gas,
+    /// If specified, the execution of the method is aborted after this
+    /// wall-clock time.
+    pub max_duration: Option<std::time::Duration>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone, Hash, PartialEq, Eq)]
@@ -316,8 +368,35 @@ pub struct ExtCostsConfig {
     #[cfg(feature = "protocol_feature_ed25519_verify")]
     pub ed25519_verify_byte: Gas,
 
+    /// Cost of hashing a batch of values with sha256 base
+    #[cfg(feature = "protocol_feature_hash_batch")]
+    pub sha256_batch_base: Gas,
+    /// Cost of hashing a batch of values with sha256 per byte
+    #[cfg(feature = "protocol_feature_hash_batch")]
+    pub sha256_batch_byte: Gas,
+
+    /// Cost of hashing a batch of values with keccak256 base
+    #[cfg(feature = "protocol_feature_hash_batch")]
+    pub keccak256_batch_base: Gas,
+    /// Cost of hashing a batch of values with keccak256 per byte
+    #[cfg(feature = "protocol_feature_hash_batch")]
+    pub keccak256_batch_byte: Gas,
+
+    /// Cost of getting the gas price of the current block
+    #[cfg(feature = "protocol_feature_block_gas_info")]
+    pub block_gas_price_base: Gas,
+    /// Cost of getting the gas limit of the current chunk
+    #[cfg(feature = "protocol_feature_block_gas_info")]
+    pub block_gas_limit_base: Gas,
+
     /// Cost of calling ecrecover
     pub ecrecover_base: Gas,
+    /// Cost of recovering a batch of signatures with ecrecover base
+    #[cfg(feature = "protocol_feature_ecrecover_batch")]
+    pub ecrecover_batch_base: Gas,
+    /// Cost of recovering a batch of signatures with ecrecover per signature
+    #[cfg(feature = "protocol_feature_ecrecover_batch")]
+    pub ecrecover_batch_element: Gas,
 
     /// Cost for calling logging.
     pub log_base: Gas,
@@ -463,9 +542,26 @@ impl ExtCostsConfig {
             ed25519_verify_base: SAFETY_MULTIPLIER * 1513656750,
             #[cfg(feature = "protocol_feature_ed25519_verify")]
             ed25519_verify_byte: SAFETY_MULTIPLIER * 7157035,
+            #[cfg(feature = "protocol_feature_hash_batch")]
+            sha256_batch_base: SAFETY_MULTIPLIER * 1513656750,
+            #[cfg(feature = "protocol_feature_hash_batch")]
+            sha256_batch_byte: SAFETY_MULTIPLIER * 8039117,
+            #[cfg(feature = "protocol_feature_hash_batch")]
+            keccak256_batch_base: SAFETY_MULTIPLIER * 1959830425,
+            #[cfg(feature = "protocol_feature_hash_batch")]
+            keccak256_batch_byte: SAFETY_MULTIPLIER * 7157035,
+            #[cfg(feature = "protocol_feature_block_gas_info")]
+            block_gas_price_base: SAFETY_MULTIPLIER * 88256037,
+            #[cfg(feature = "protocol_feature_block_gas_info")]
+            block_gas_limit_base: SAFETY_MULTIPLIER * 88256037,
             // Cost per byte is 3542227. There are 64 bytes in a block.
             ripemd160_block: SAFETY_MULTIPLIER * 226702528,
             ecrecover_base: SAFETY_MULTIPLIER * 1121789875000,
+            #[cfg(feature = "protocol_feature_ecrecover_batch")]
+            ecrecover_batch_base: SAFETY_MULTIPLIER * 1513656750,
+            // Until estimated, charge each signature like a call to `ecrecover`.
+            #[cfg(feature = "protocol_feature_ecrecover_batch")]
+            ecrecover_batch_element: SAFETY_MULTIPLIER * 1121789875000,
             log_base: SAFETY_MULTIPLIER * 1181104350,
             log_byte: SAFETY_MULTIPLIER * 4399597,
             storage_write_base: SAFETY_MULTIPLIER * 21398912000,
@@ -535,7 +631,23 @@ impl ExtCostsConfig {
             ed25519_verify_base: 0,
             #[cfg(feature = "protocol_feature_ed25519_verify")]
             ed25519_verify_byte: 0,
+            #[cfg(feature = "protocol_feature_hash_batch")]
+            sha256_batch_base: 0,
+            #[cfg(feature = "protocol_feature_hash_batch")]
+            sha256_batch_byte: 0,
+            #[cfg(feature = "protocol_feature_hash_batch")]
+            keccak256_batch_base: 0,
+            #[cfg(feature = "protocol_feature_hash_batch")]
+            keccak256_batch_byte: 0,
+            #[cfg(feature = "protocol_feature_block_gas_info")]
+            block_gas_price_base: 0,
+            #[cfg(feature = "protocol_feature_block_gas_info")]
+            block_gas_limit_base: 0,
             ecrecover_base: 0,
+            #[cfg(feature = "protocol_feature_ecrecover_batch")]
+            ecrecover_batch_base: 0,
+            #[cfg(feature = "protocol_feature_ecrecover_batch")]
+            ecrecover_batch_element: 0,
             log_base: 0,
             log_byte: 0,
             storage_write_base: 0,
@@ -610,7 +722,23 @@ pub enum ExtCosts {
     ed25519_verify_base,
     #[cfg(feature = "protocol_feature_ed25519_verify")]
     ed25519_verify_byte,
+    #[cfg(feature = "protocol_feature_hash_batch")]
+    sha256_batch_base,
+    #[cfg(feature = "protocol_feature_hash_batch")]
+    sha256_batch_byte,
+    #[cfg(feature = "protocol_feature_hash_batch")]
+    keccak256_batch_base,
+    #[cfg(feature = "protocol_feature_hash_batch")]
+    keccak256_batch_byte,
+    #[cfg(feature = "protocol_feature_block_gas_info")]
+    block_gas_price_base,
+    #[cfg(feature = "protocol_feature_block_gas_info")]
+    block_gas_limit_base,
     ecrecover_base,
+    #[cfg(feature = "protocol_feature_ecrecover_batch")]
+    ecrecover_batch_base,
+    #[cfg(feature = "protocol_feature_ecrecover_batch")]
+    ecrecover_batch_element,
     log_base,
     log_byte,
     storage_write_base,
@@ -702,7 +830,23 @@ impl ExtCosts {
             ed25519_verify_base => config.ed25519_verify_base,
             #[cfg(feature = "protocol_feature_ed25519_verify")]
             ed25519_verify_byte => config.ed25519_verify_byte,
+            #[cfg(feature = "protocol_feature_hash_batch")]
+            sha256_batch_base => config.sha256_batch_base,
+            #[cfg(feature = "protocol_feature_hash_batch")]
+            sha256_batch_byte => config.sha256_batch_byte,
+            #[cfg(feature = "protocol_feature_hash_batch")]
+            keccak256_batch_base => config.keccak256_batch_base,
+            #[cfg(feature = "protocol_feature_hash_batch")]
+            keccak256_batch_byte => config.keccak256_batch_byte,
+            #[cfg(feature = "protocol_feature_block_gas_info")]
+            block_gas_price_base => config.block_gas_price_base,
+            #[cfg(feature = "protocol_feature_block_gas_info")]
+            block_gas_limit_base => config.block_gas_limit_base,
             ecrecover_base => config.ecrecover_base,
+            #[cfg(feature = "protocol_feature_ecrecover_batch")]
+            ecrecover_batch_base => config.ecrecover_batch_base,
+            #[cfg(feature = "protocol_feature_ecrecover_batch")]
+            ecrecover_batch_element => config.ecrecover_batch_element,
             log_base => config.log_base,
             log_byte => config.log_byte,
             storage_write_base => config.storage_write_base,
@@ -2,6 +2,7 @@ pub use borsh;
 pub use num_rational;
 
 pub mod account;
+pub mod amount;
 pub mod config;
 pub mod contract;
 pub mod hash;
@@ -79,6 +79,12 @@ pub enum Parameter {
     ActionDeleteKeySendSir,
     ActionDeleteKeySendNotSir,
     ActionDeleteKeyExecution,
+    ActionStateInitSendSir,
+    ActionStateInitSendNotSir,
+    ActionStateInitExecution,
+    ActionStateInitPerByteSendSir,
+    ActionStateInitPerByteSendNotSir,
+    ActionStateInitPerByteExecution,
 
     // Smart contract dynamic gas costs
     WasmRegularOpCost,
@@ -108,8 +114,16 @@ pub enum Parameter {
     WasmRipemd160Base,
     WasmRipemd160Block,
     WasmEcrecoverBase,
+    WasmEcrecoverBatchBase,
+    WasmEcrecoverBatchElement,
     WasmEd25519VerifyBase,
     WasmEd25519VerifyByte,
+    WasmSha256BatchBase,
+    WasmSha256BatchByte,
+    WasmKeccak256BatchBase,
+    WasmKeccak256BatchByte,
+    WasmBlockGasPriceBase,
+    WasmBlockGasLimitBase,
     WasmLogBase,
     WasmLogByte,
     WasmStorageWriteBase,
@@ -173,6 +187,9 @@ pub enum Parameter {
     MaxFunctionsNumberPerContract,
     Wasmer2StackLimit,
     MaxLocalsPerContract,
+    MaxGlobalsPerContract,
+    MaxTableElementsPerContract,
+    MaxCustomSectionsSizePerContract,
     AccountIdValidityRulesVersion,
 }
 
@@ -205,6 +222,8 @@ pub enum FeeParameter {
     ActionAddFunctionCallKey,
     ActionAddFunctionCallKeyPerByte,
     ActionDeleteKey,
+    ActionStateInit,
+    ActionStateInitPerByte,
 }
 
 impl Parameter {
@@ -238,8 +257,16 @@ impl Parameter {
             Parameter::WasmRipemd160Base,
             Parameter::WasmRipemd160Block,
             Parameter::WasmEcrecoverBase,
+            Parameter::WasmEcrecoverBatchBase,
+            Parameter::WasmEcrecoverBatchElement,
             Parameter::WasmEd25519VerifyBase,
             Parameter::WasmEd25519VerifyByte,
+            Parameter::WasmSha256BatchBase,
+            Parameter::WasmSha256BatchByte,
+            Parameter::WasmKeccak256BatchBase,
+            Parameter::WasmKeccak256BatchByte,
+            Parameter::WasmBlockGasPriceBase,
+            Parameter::WasmBlockGasLimitBase,
             Parameter::WasmLogBase,
             Parameter::WasmLogByte,
             Parameter::WasmStorageWriteBase,
@@ -308,6 +335,9 @@ impl Parameter {
             Parameter::MaxFunctionsNumberPerContract,
             Parameter::Wasmer2StackLimit,
             Parameter::MaxLocalsPerContract,
+            Parameter::MaxGlobalsPerContract,
+            Parameter::MaxTableElementsPerContract,
+            Parameter::MaxCustomSectionsSizePerContract,
             Parameter::AccountIdValidityRulesVersion,
         ]
         .iter()
@@ -8,11 +8,21 @@ use strum::IntoEnumIterator;
 ///
 /// This is not part of the protocol but archival nodes still rely on this not
 /// changing to answer old tx-status requests with a gas profile.
-#[derive(Clone, PartialEq, Eq)]
+#[derive(Clone, PartialEq, Eq, arbitrary::Arbitrary)]
 pub struct DataArray(Box<[u64; Self::LEN]>);
 
 impl DataArray {
-    pub const LEN: usize = if cfg!(feature = "protocol_feature_ed25519_verify") { 72 } else { 70 };
+    pub const LEN: usize = if cfg!(feature = "protocol_feature_ecrecover_batch") {
+        80
+    } else if cfg!(feature = "protocol_feature_block_gas_info") {
+        78
+    } else if cfg!(feature = "protocol_feature_hash_batch") {
+        76
+    } else if cfg!(feature = "protocol_feature_ed25519_verify") {
+        72
+    } else {
+        70
+    };
 }
 
 impl Index<usize> for DataArray {
@@ -48,7 +58,7 @@ impl BorshSerialize for DataArray {
 
 /// Profile of gas consumption.
 /// When add new cost, the new cost should also be append to profile_index
-#[derive(Clone, PartialEq, Eq, BorshSerialize, BorshDeserialize)]
+#[derive(Clone, PartialEq, Eq, BorshSerialize, BorshDeserialize, arbitrary::Arbitrary)]
 pub struct ProfileData {
     data: DataArray,
 }
@@ -260,6 +270,22 @@ impl Cost {
             Cost::ExtCost { ext_cost_kind: ExtCosts::ed25519_verify_base } => 70,
             #[cfg(feature = "protocol_feature_ed25519_verify")]
             Cost::ExtCost { ext_cost_kind: ExtCosts::ed25519_verify_byte } => 71,
+            #[cfg(feature = "protocol_feature_hash_batch")]
+            Cost::ExtCost { ext_cost_kind: ExtCosts::sha256_batch_base } => 72,
+            #[cfg(feature = "protocol_feature_hash_batch")]
+            Cost::ExtCost { ext_cost_kind: ExtCosts::sha256_batch_byte } => 73,
+            #[cfg(feature = "protocol_feature_hash_batch")]
+            Cost::ExtCost { ext_cost_kind: ExtCosts::keccak256_batch_base } => 74,
+            #[cfg(feature = "protocol_feature_hash_batch")]
+            Cost::ExtCost { ext_cost_kind: ExtCosts::keccak256_batch_byte } => 75,
+            #[cfg(feature = "protocol_feature_block_gas_info")]
+            Cost::ExtCost { ext_cost_kind: ExtCosts::block_gas_price_base } => 76,
+            #[cfg(feature = "protocol_feature_block_gas_info")]
+            Cost::ExtCost { ext_cost_kind: ExtCosts::block_gas_limit_base } => 77,
+            #[cfg(feature = "protocol_feature_ecrecover_batch")]
+            Cost::ExtCost { ext_cost_kind: ExtCosts::ecrecover_batch_base } => 78,
+            #[cfg(feature = "protocol_feature_ecrecover_batch")]
+            Cost::ExtCost { ext_cost_kind: ExtCosts::ecrecover_batch_element } => 79,
         }
     }
 }
@@ -24,6 +24,10 @@ pub struct Fee {
 }
 
 impl Fee {
+    pub fn free() -> Fee {
+        Fee { send_sir: 0, send_not_sir: 0, execution: 0 }
+    }
+
     #[inline]
     pub fn send_fee(&self, sir: bool) -> Gas {
         if sir {
@@ -113,6 +117,20 @@ pub struct ActionCreationConfig {
 
     /// Base cost of deleting an account.
     pub delete_account_cost: Fee,
+
+    /// Cost per key-value pair of the initial contract state written by
+    /// `CreateAccountWithContract`, in addition to the costs of creating an
+    /// account and deploying a contract.
+    ///
+    /// Configs of protocol versions before the action existed do not set
+    /// this, it is free there.
+    #[cfg(feature = "protocol_feature_create_account_with_contract")]
+    #[serde(default = "Fee::free")]
+    pub state_init_cost: Fee,
+    /// Cost per byte of keys and values of the initial contract state.
+    #[cfg(feature = "protocol_feature_create_account_with_contract")]
+    #[serde(default = "Fee::free")]
+    pub state_init_cost_per_byte: Fee,
 }
 
 /// Describes the cost of creating an access key.
@@ -219,6 +237,18 @@ impl RuntimeFeesConfig {
                     send_not_sir: 147489000000,
                     execution: 147489000000,
                 },
+                #[cfg(feature = "protocol_feature_create_account_with_contract")]
+                state_init_cost: Fee {
+                    send_sir: 64196736000,
+                    send_not_sir: 64196736000,
+                    execution: 64196736000,
+                },
+                #[cfg(feature = "protocol_feature_create_account_with_contract")]
+                state_init_cost_per_byte: Fee {
+                    send_sir: 70482867,
+                    send_not_sir: 70482867,
+                    execution: 70482867,
+                },
             },
             storage_usage_config: StorageUsageConfig {
                 // See Account in core/primitives/src/account.rs for the data structure.
@@ -232,7 +262,7 @@ impl RuntimeFeesConfig {
     }
 
     pub fn free() -> Self {
-        let free = Fee { send_sir: 0, send_not_sir: 0, execution: 0 };
+        let free = Fee::free();
         RuntimeFeesConfig {
             action_receipt_creation_config: free.clone(),
             data_receipt_creation_config: DataReceiptCreationConfig {
@@ -253,6 +283,10 @@ impl RuntimeFeesConfig {
                     function_call_cost_per_byte: free.clone(),
                 },
                 delete_key_cost: free.clone(),
+                #[cfg(feature = "protocol_feature_create_account_with_contract")]
+                state_init_cost: free.clone(),
+                #[cfg(feature = "protocol_feature_create_account_with_contract")]
+                state_init_cost_per_byte: free.clone(),
                 delete_account_cost: free,
             },
             storage_usage_config: StorageUsageConfig {
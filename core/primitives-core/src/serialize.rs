@@ -197,6 +197,31 @@ pub mod dec_format {
     }
 }
 
+/// Serialises a map from strings to `u128` with the values formatted as by
+/// [`dec_format`].
+pub mod dec_format_map {
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+    use std::collections::BTreeMap;
+
+    #[derive(Serialize, Deserialize)]
+    struct Dec(#[serde(with = "super::dec_format")] u128);
+
+    pub fn serialize<S>(map: &BTreeMap<String, u128>, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.collect_map(map.iter().map(|(key, value)| (key, Dec(*value))))
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<BTreeMap<String, u128>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let map = BTreeMap::<String, Dec>::deserialize(deserializer)?;
+        Ok(map.into_iter().map(|(key, Dec(value))| (key, value)).collect())
+    }
+}
+
 #[test]
 fn test_u64_dec_format() {
     #[derive(PartialEq, Debug, serde::Deserialize, serde::Serialize)]
@@ -251,6 +276,21 @@ fn test_option_u128_dec_format() {
     assert_de_error::<Test>("{\"field\":42.0}");
 }
 
+#[test]
+fn test_dec_format_map() {
+    #[derive(PartialEq, Debug, serde::Deserialize, serde::Serialize)]
+    struct Test {
+        #[serde(with = "dec_format_map")]
+        field: std::collections::BTreeMap<String, u128>,
+    }
+
+    let field = [("a".to_string(), 42), ("b".to_string(), 18446744073709551616)].into();
+    assert_round_trip("{\"field\":{}}", Test { field: Default::default() });
+    assert_round_trip("{\"field\":{\"a\":\"42\",\"b\":\"18446744073709551616\"}}", Test { field });
+    assert_deserialise("{\"field\":{\"a\":42}}", Test { field: [("a".to_string(), 42)].into() });
+    assert_de_error::<Test>("{\"field\":{\"a\":null}}");
+}
+
 #[cfg(test)]
 #[track_caller]
 fn assert_round_trip<'a, T>(serialised: &'a str, obj: T)
@@ -24,6 +24,9 @@ pub type ShardId = u64;
 pub type Balance = u128;
 /// Gas is a type for storing amount of gas.
 pub type Gas = u64;
+/// Amounts of gas and tokens with checked arithmetic, to be used instead of
+/// `Gas` and `Balance` in new code.
+pub use crate::amount::{NearBalance, NearGas};
 
 /// Weight of unused gas to distribute to scheduled function call actions.
 /// Used in `promise_batch_action_function_call_weight` host function.
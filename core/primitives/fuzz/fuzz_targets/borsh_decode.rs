@@ -0,0 +1,30 @@
+#![no_main]
+
+use borsh::{BorshDeserialize, BorshSerialize};
+use libfuzzer_sys::fuzz_target;
+use near_primitives::receipt::Receipt;
+use near_primitives::sharding::ShardChunk;
+use near_primitives::transaction::{Action, ExecutionOutcome, SignedTransaction, Transaction};
+
+/// Decodes `bytes` as `T` and checks that the encoding of the decoded value is
+/// stable.  The input itself is not compared, as decoding of some types is
+/// lenient, e.g. gas profiles of any length are accepted.
+fn check<T: BorshSerialize + BorshDeserialize>(bytes: &[u8]) {
+    if let Ok(value) = T::try_from_slice(bytes) {
+        let encoded = value.try_to_vec().unwrap();
+        let decoded = T::try_from_slice(&encoded).expect("failed to decode re-encoded value");
+        assert_eq!(encoded, decoded.try_to_vec().unwrap());
+    }
+}
+
+fuzz_target!(|input: (u8, &[u8])| {
+    let (kind, bytes) = input;
+    match kind % 6 {
+        0 => check::<Transaction>(bytes),
+        1 => check::<SignedTransaction>(bytes),
+        2 => check::<Action>(bytes),
+        3 => check::<Receipt>(bytes),
+        4 => check::<ExecutionOutcome>(bytes),
+        _ => check::<ShardChunk>(bytes),
+    }
+});
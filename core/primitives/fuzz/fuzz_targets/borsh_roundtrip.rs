@@ -0,0 +1,41 @@
+#![no_main]
+
+use std::fmt::Debug;
+
+use borsh::{BorshDeserialize, BorshSerialize};
+use libfuzzer_sys::{arbitrary, fuzz_target};
+use near_primitives::receipt::Receipt;
+use near_primitives::transaction::{Action, ExecutionOutcome, Transaction};
+use near_primitives::types::AccountId;
+
+#[derive(Debug, arbitrary::Arbitrary)]
+enum Input {
+    Transaction(Transaction),
+    Action(Action),
+    Receipt(Receipt),
+    ExecutionOutcome(ExecutionOutcome),
+}
+
+/// Checks that `value` decodes to itself.  Arbitrary account ids are not
+/// necessarily valid, so the value is allowed to be rejected by the decoder.
+fn check<T: BorshSerialize + BorshDeserialize + PartialEq + Debug>(value: &T) {
+    let bytes = value.try_to_vec().unwrap();
+    if let Ok(decoded) = T::try_from_slice(&bytes) {
+        assert_eq!(&decoded, value);
+    }
+}
+
+fuzz_target!(|input: Input| {
+    match input {
+        // `TransactionV0` is serialized without a version tag and only the
+        // length of a valid signer id tells it apart from later versions.
+        Input::Transaction(tx) => {
+            if AccountId::validate(tx.signer_id()).is_ok() {
+                check(&tx)
+            }
+        }
+        Input::Action(action) => check(&action),
+        Input::Receipt(receipt) => check(&receipt),
+        Input::ExecutionOutcome(outcome) => check(&outcome),
+    }
+});
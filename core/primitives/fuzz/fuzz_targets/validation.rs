@@ -0,0 +1,28 @@
+#![no_main]
+
+use libfuzzer_sys::{arbitrary, fuzz_target};
+use near_primitives::config::VMLimitConfig;
+use near_primitives::receipt::{Receipt, ReceiptEnum};
+use near_primitives::transaction::Transaction;
+use near_primitives::validation::validate_actions;
+
+#[derive(Debug, arbitrary::Arbitrary)]
+enum Input {
+    Transaction(Transaction),
+    Receipt(Receipt),
+}
+
+// Validation has to reject invalid input with an error, never with a panic.
+fuzz_target!(|input: Input| {
+    let limit_config = VMLimitConfig::test();
+    match input {
+        Input::Transaction(tx) => {
+            let _ = tx.validate(&limit_config);
+        }
+        Input::Receipt(receipt) => {
+            if let ReceiptEnum::Action(action_receipt) = &receipt.receipt {
+                let _ = validate_actions(&limit_config, &action_receipt.actions);
+            }
+        }
+    }
+});
@@ -10,7 +10,7 @@ use crate::types::{
     AccountId, Balance, BlockHeightDelta, EpochHeight, EpochId, NumSeats, ProtocolVersion,
     ValidatorId, ValidatorKickoutReason,
 };
-use crate::version::PROTOCOL_VERSION;
+use crate::version::{ProtocolFeature, PROTOCOL_VERSION};
 use near_primitives_core::hash::CryptoHash;
 use near_primitives_core::types::BlockHeight;
 use smart_default::SmartDefault;
@@ -99,7 +99,7 @@ impl AllEpochConfig {
         // the simple nightshade shard config
         let mut config = self.genesis_epoch_config.clone();
         if self.use_production_config {
-            if checked_feature!("stable", SimpleNightshade, protocol_version) {
+            if ProtocolFeature::SimpleNightshade.enabled(protocol_version) {
                 config.shard_layout = ShardLayout::get_simple_nightshade_layout();
                 config.num_block_producer_seats_per_shard = vec![
                     config.num_block_producer_seats;
@@ -109,7 +109,7 @@ impl AllEpochConfig {
                 config.avg_hidden_validator_seats_per_shard =
                     vec![0; config.shard_layout.num_shards() as usize];
             }
-            if checked_feature!("stable", ChunkOnlyProducers, protocol_version) {
+            if ProtocolFeature::ChunkOnlyProducers.enabled(protocol_version) {
                 // On testnet, genesis config set num_block_producer_seats to 200
                 // This is to bring it back to 100 to be the same as on mainnet
                 config.num_block_producer_seats = 100;
@@ -122,7 +122,7 @@ impl AllEpochConfig {
                 config.validator_selection_config.num_chunk_only_producer_seats = 200;
             }
 
-            if checked_feature!("stable", MaxKickoutStake, protocol_version) {
+            if ProtocolFeature::MaxKickoutStake.enabled(protocol_version) {
                 config.validator_max_kickout_stake_perc = 30;
             }
         }
@@ -430,7 +430,7 @@ pub mod epoch_info {
     use crate::epoch_manager::ValidatorWeight;
     use crate::types::validator_stake::{ValidatorStake, ValidatorStakeIter};
     use crate::types::{BlockChunkValidatorStats, ValidatorKickoutReason};
-    use crate::version::PROTOCOL_VERSION;
+    use crate::version::{ProtocolFeature, PROTOCOL_VERSION};
     use borsh::{BorshDeserialize, BorshSerialize};
     use near_primitives_core::hash::CryptoHash;
     use near_primitives_core::types::{
@@ -441,7 +441,7 @@ pub mod epoch_info {
     use std::collections::{BTreeMap, HashMap};
 
     use crate::types::validator_stake::ValidatorStakeV1;
-    use crate::{checked_feature, epoch_manager::RngSeed, rand::WeightedIndex};
+    use crate::{epoch_manager::RngSeed, rand::WeightedIndex};
     use near_primitives_core::{
         hash::hash,
         types::{BlockHeight, ShardId},
@@ -545,7 +545,7 @@ pub mod epoch_info {
             protocol_version: ProtocolVersion,
             rng_seed: RngSeed,
         ) -> Self {
-            if checked_feature!("stable", AliasValidatorSelectionAlgorithm, protocol_version) {
+            if ProtocolFeature::AliasValidatorSelectionAlgorithm.enabled(protocol_version) {
                 let stake_weights = |ids: &[ValidatorId]| -> WeightedIndex {
                     WeightedIndex::new(
                         ids.iter()
@@ -872,23 +872,20 @@ pub mod epoch_info {
                 }
                 Self::V3(v3) => {
                     let protocol_version = self.protocol_version();
-                    let seed =
-                        if checked_feature!(
-                            "stable",
-                            SynchronizeBlockChunkProduction,
-                            protocol_version
-                        ) && !checked_feature!("stable", ChunkOnlyProducers, protocol_version)
-                        {
-                            // This is same seed that used for determining block producer
-                            Self::block_produce_seed(height, &v3.rng_seed)
-                        } else {
-                            // 32 bytes from epoch_seed, 8 bytes from height, 8 bytes from shard_id
-                            let mut buffer = [0u8; 48];
-                            buffer[0..32].copy_from_slice(&v3.rng_seed);
-                            buffer[32..40].copy_from_slice(&height.to_le_bytes());
-                            buffer[40..48].copy_from_slice(&shard_id.to_le_bytes());
-                            hash(&buffer).0
-                        };
+                    let seed = if ProtocolFeature::SynchronizeBlockChunkProduction
+                        .enabled(protocol_version)
+                        && !ProtocolFeature::ChunkOnlyProducers.enabled(protocol_version)
+                    {
+                        // This is same seed that used for determining block producer
+                        Self::block_produce_seed(height, &v3.rng_seed)
+                    } else {
+                        // 32 bytes from epoch_seed, 8 bytes from height, 8 bytes from shard_id
+                        let mut buffer = [0u8; 48];
+                        buffer[0..32].copy_from_slice(&v3.rng_seed);
+                        buffer[32..40].copy_from_slice(&height.to_le_bytes());
+                        buffer[40..48].copy_from_slice(&shard_id.to_le_bytes());
+                        hash(&buffer).0
+                    };
                     let shard_id = shard_id as usize;
                     v3.chunk_producers_settlement[shard_id]
                         [v3.chunk_producers_sampler[shard_id].sample(seed)]
@@ -1,5 +1,6 @@
 use crate::serialize::dec_format;
 use crate::types::{AccountId, Balance, EpochId, Gas, Nonce};
+use crate::version::ProtocolVersion;
 use borsh::{BorshDeserialize, BorshSerialize};
 use near_crypto::PublicKey;
 use serde::{Deserialize, Serialize};
@@ -10,6 +11,7 @@ use near_rpc_error_macro::RpcError;
 use near_vm_errors::FunctionCallErrorSer;
 
 /// Error returned in the ExecutionOutcome in case of failure
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[derive(
     BorshSerialize, BorshDeserialize, Debug, Clone, PartialEq, Eq, Deserialize, Serialize, RpcError,
 )]
@@ -96,6 +98,7 @@ impl std::fmt::Display for StorageError {
 impl std::error::Error for StorageError {}
 
 /// An error happened during TX execution
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[derive(
     BorshSerialize, BorshDeserialize, Debug, Clone, PartialEq, Eq, Deserialize, Serialize, RpcError,
 )]
@@ -144,6 +147,7 @@ pub enum InvalidTxError {
 
 impl std::error::Error for InvalidTxError {}
 
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[derive(
     BorshSerialize, BorshDeserialize, Debug, Clone, PartialEq, Eq, Deserialize, Serialize, RpcError,
 )]
@@ -167,9 +171,20 @@ pub enum InvalidAccessKeyError {
     },
     /// Having a deposit with a function call action is not allowed with a function call access key.
     DepositWithFunctionCall,
+    /// Access Key does not have enough allowance for the called method to cover transaction cost
+    NotEnoughMethodAllowance {
+        account_id: AccountId,
+        public_key: PublicKey,
+        method_name: String,
+        #[serde(with = "dec_format")]
+        allowance: Balance,
+        #[serde(with = "dec_format")]
+        cost: Balance,
+    },
 }
 
 /// Describes the error for validating a list of actions.
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[derive(
     BorshSerialize, BorshDeserialize, Serialize, Deserialize, Debug, Clone, PartialEq, Eq, RpcError,
 )]
@@ -198,9 +213,19 @@ pub enum ActionsValidationError {
     UnsuitableStakingKey { public_key: PublicKey },
     /// The attached amount of gas in a FunctionCall action has to be a positive number.
     FunctionCallZeroAttachedGas,
+    /// The action is not supported by the current protocol version.
+    UnsupportedProtocolFeature { protocol_feature: String, version: ProtocolVersion },
+    /// The length of a key of the initial contract state exceeded the limit.
+    StateInitKeyLengthExceeded { length: u64, limit: u64 },
+    /// The length of a value of the initial contract state exceeded the limit.
+    StateInitValueLengthExceeded { length: u64, limit: u64 },
+    /// An allowance is given for a method which the access key doesn't allow to call in a Add Key
+    /// action.
+    AddKeyMethodAllowanceNotPermitted { method_name: String },
 }
 
 /// Describes the error for validating a receipt.
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[derive(
     BorshSerialize, BorshDeserialize, Serialize, Deserialize, Debug, Clone, PartialEq, Eq, RpcError,
 )]
@@ -314,6 +339,28 @@ impl Display for ActionsValidationError {
                 f,
                 "The attached amount of gas in a FunctionCall action has to be a positive number",
             ),
+            ActionsValidationError::UnsupportedProtocolFeature { protocol_feature, version } => {
+                write!(
+                    f,
+                    "Transaction requires protocol feature {} / version {} which is not supported by the current protocol version",
+                    protocol_feature, version,
+                )
+            }
+            ActionsValidationError::StateInitKeyLengthExceeded { length, limit } => write!(
+                f,
+                "The length of a key of the initial contract state {} exceeds the maximum allowed length {}",
+                length, limit
+            ),
+            ActionsValidationError::StateInitValueLengthExceeded { length, limit } => write!(
+                f,
+                "The length of a value of the initial contract state {} exceeds the maximum allowed length {}",
+                length, limit
+            ),
+            ActionsValidationError::AddKeyMethodAllowanceNotPermitted { method_name } => write!(
+                f,
+                "An allowance is given for method {:?} which is not in the allowed method names of a AddKey action",
+                method_name
+            ),
         }
     }
 }
@@ -321,6 +368,7 @@ impl Display for ActionsValidationError {
 impl std::error::Error for ActionsValidationError {}
 
 /// An error happened during Action execution
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[derive(
     BorshSerialize, BorshDeserialize, Debug, Clone, PartialEq, Eq, Deserialize, Serialize, RpcError,
 )]
@@ -334,6 +382,7 @@ pub struct ActionError {
 
 impl std::error::Error for ActionError {}
 
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[derive(
     BorshSerialize, BorshDeserialize, Debug, Clone, PartialEq, Eq, Deserialize, Serialize, RpcError,
 )]
@@ -397,6 +446,10 @@ pub enum ActionErrorKind {
     OnlyImplicitAccountCreationAllowed { account_id: AccountId },
     /// Delete account whose state is large is temporarily banned.
     DeleteAccountWithLargeState { account_id: AccountId },
+    /// The contract of a `DeployContract` action exceeds the limits on its
+    /// globals, tables or custom sections, which are checked on deployment.
+    #[cfg(feature = "protocol_feature_limit_contract_sections")]
+    ContractLimitExceeded { account_id: AccountId, error: near_vm_errors::PrepareError },
 }
 
 impl From<ActionErrorKind> for ActionError {
@@ -502,6 +555,17 @@ impl Display for InvalidAccessKeyError {
             InvalidAccessKeyError::DepositWithFunctionCall => {
                 write!(f, "Having a deposit with a function call action is not allowed with a function call access key.")
             }
+            InvalidAccessKeyError::NotEnoughMethodAllowance {
+                account_id,
+                public_key,
+                method_name,
+                allowance,
+                cost,
+            } => write!(
+                f,
+                "Access Key {:?}:{} does not have enough balance {} for method {:?} for transaction costing {}",
+                account_id, public_key, allowance, method_name, cost
+            ),
         }
     }
 }
@@ -707,6 +771,8 @@ impl Display for ActionErrorKind {
             ActionErrorKind::InsufficientStake { account_id, stake, minimum_stake } => write!(f, "Account {} tries to stake {} but minimum required stake is {}", account_id, stake, minimum_stake),
             ActionErrorKind::OnlyImplicitAccountCreationAllowed { account_id } => write!(f, "CreateAccount action is called on hex-characters account of length 64 {}", account_id),
             ActionErrorKind::DeleteAccountWithLargeState { account_id } => write!(f, "The state of account {} is too large and therefore cannot be deleted", account_id),
+            #[cfg(feature = "protocol_feature_limit_contract_sections")]
+            ActionErrorKind::ContractLimitExceeded { account_id, error } => write!(f, "The contract deployed to account {} is not allowed: {}", account_id, error),
         }
     }
 }
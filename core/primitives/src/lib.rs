@@ -32,6 +32,7 @@ pub mod trie_key;
 pub mod types;
 mod upgrade_schedule;
 pub mod utils;
+pub mod validation;
 pub mod validator_signer;
 pub mod version;
 pub mod views;
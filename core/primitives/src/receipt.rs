@@ -1,5 +1,6 @@
 use std::borrow::Borrow;
 use std::fmt;
+use std::io;
 
 use borsh::{BorshDeserialize, BorshSerialize};
 use serde::{Deserialize, Serialize};
@@ -8,13 +9,17 @@ use near_crypto::{KeyType, PublicKey};
 use near_o11y::pretty;
 
 use crate::borsh::maybestd::collections::HashMap;
+use crate::checked_feature;
 use crate::hash::CryptoHash;
 use crate::serialize::{dec_format, option_base64_format};
 use crate::transaction::{Action, TransferAction};
+use crate::trie_key::TrieKey;
 use crate::types::{AccountId, Balance, ShardId};
+use crate::version::ProtocolVersion;
 
 /// Receipts are used for a cross-shard communication.
 /// Receipts could be 2 types (determined by a `ReceiptEnum`): `ReceiptEnum::Action` of `ReceiptEnum::Data`.
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
 pub struct Receipt {
     /// An issuer account_id of a particular receipt.
@@ -40,6 +45,15 @@ impl Receipt {
         self.receipt_id
     }
 
+    /// Priority of an action receipt, see `ActionReceipt::priority`.  Data
+    /// receipts have no priority.
+    pub fn priority(&self) -> Balance {
+        match &self.receipt {
+            ReceiptEnum::Action(action_receipt) => action_receipt.priority,
+            ReceiptEnum::Data(_) => 0,
+        }
+    }
+
     /// Generates a receipt with a transfer from system for a given balance without a receipt_id.
     /// This should be used for token refunds instead of gas refunds. It doesn't refund the
     /// allowance of the access key. For gas refunds use `new_gas_refund`.
@@ -56,6 +70,7 @@ impl Receipt {
                 output_data_receivers: vec![],
                 input_data_ids: vec![],
                 actions: vec![Action::Transfer(TransferAction { deposit: refund })],
+                priority: 0,
             }),
         }
     }
@@ -83,12 +98,14 @@ impl Receipt {
                 output_data_receivers: vec![],
                 input_data_ids: vec![],
                 actions: vec![Action::Transfer(TransferAction { deposit: refund })],
+                priority: 0,
             }),
         }
     }
 }
 
 /// Receipt could be either ActionReceipt or DataReceipt
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
 pub enum ReceiptEnum {
     Action(ActionReceipt),
@@ -96,7 +113,8 @@ pub enum ReceiptEnum {
 }
 
 /// ActionReceipt is derived from an Action from `Transaction or from Receipt`
-#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
 pub struct ActionReceipt {
     /// A signer of the original transaction
     pub signer_id: AccountId,
@@ -115,10 +133,89 @@ pub struct ActionReceipt {
     pub input_data_ids: Vec<CryptoHash>,
     /// A list of actions to process when all input_data_ids are filled
     pub actions: Vec<Action>,
+    /// The priority fee of the original transaction, inherited by all the
+    /// receipts it spawns.  Delayed receipts with a higher priority are
+    /// processed first, see `DelayedReceiptQueue`.
+    #[serde(with = "dec_format", default, skip_serializing_if = "is_zero")]
+    pub priority: Balance,
+}
+
+impl ActionReceipt {
+    /// Priority of the receipt a transaction offering the given priority fee
+    /// is converted to.
+    pub fn priority_from_fee(
+        priority_fee: Option<Balance>,
+        protocol_version: ProtocolVersion,
+    ) -> Balance {
+        if checked_feature!("protocol_feature_receipt_priority", ReceiptPriority, protocol_version)
+        {
+            priority_fee.unwrap_or(0)
+        } else {
+            0
+        }
+    }
+}
+
+fn is_zero(balance: &Balance) -> bool {
+    *balance == 0
+}
+
+/// Borsh tag of an `ActionReceipt` with a priority.  Receipts without a
+/// priority are serialized without the tag and the priority, as before the
+/// field was added.  They start with the length of the signer id, which is at
+/// least 2, so the first byte tells the versions apart.
+const ACTION_RECEIPT_WITH_PRIORITY_TAG: u8 = 1;
+
+impl BorshSerialize for ActionReceipt {
+    fn serialize<W: io::Write>(&self, writer: &mut W) -> io::Result<()> {
+        if self.priority != 0 {
+            BorshSerialize::serialize(&ACTION_RECEIPT_WITH_PRIORITY_TAG, writer)?;
+        }
+        BorshSerialize::serialize(&self.signer_id, writer)?;
+        BorshSerialize::serialize(&self.signer_public_key, writer)?;
+        BorshSerialize::serialize(&self.gas_price, writer)?;
+        BorshSerialize::serialize(&self.output_data_receivers, writer)?;
+        BorshSerialize::serialize(&self.input_data_ids, writer)?;
+        BorshSerialize::serialize(&self.actions, writer)?;
+        if self.priority != 0 {
+            BorshSerialize::serialize(&self.priority, writer)?;
+        }
+        Ok(())
+    }
+}
+
+impl BorshDeserialize for ActionReceipt {
+    fn deserialize(buf: &mut &[u8]) -> io::Result<Self> {
+        let with_priority = buf.first() == Some(&ACTION_RECEIPT_WITH_PRIORITY_TAG);
+        if with_priority {
+            *buf = &buf[1..];
+        }
+        let mut receipt = Self {
+            signer_id: BorshDeserialize::deserialize(buf)?,
+            signer_public_key: BorshDeserialize::deserialize(buf)?,
+            gas_price: BorshDeserialize::deserialize(buf)?,
+            output_data_receivers: BorshDeserialize::deserialize(buf)?,
+            input_data_ids: BorshDeserialize::deserialize(buf)?,
+            actions: BorshDeserialize::deserialize(buf)?,
+            priority: 0,
+        };
+        if with_priority {
+            receipt.priority = BorshDeserialize::deserialize(buf)?;
+            // Keep the encoding unique, receipts without priority have no tag.
+            if receipt.priority == 0 {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "tagged action receipt with zero priority",
+                ));
+            }
+        }
+        Ok(receipt)
+    }
 }
 
 /// An incoming (ingress) `DataReceipt` which is going to a Receipt's `receiver` input_data_ids
 /// Which will be converted to `PromiseResult::Successful(value)` or `PromiseResult::Failed`
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Hash, PartialEq, Eq, Clone)]
 pub struct DataReceipt {
     pub data_id: CryptoHash,
@@ -137,6 +234,7 @@ impl fmt::Debug for DataReceipt {
 
 /// The outgoing (egress) data which will be transformed
 /// to a `DataReceipt` to be sent to a `receipt.receiver`
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[derive(
     BorshSerialize, BorshDeserialize, Serialize, Deserialize, Hash, Clone, Debug, PartialEq, Eq,
 )]
@@ -171,5 +269,179 @@ pub struct DelayedReceiptIndices {
     pub next_available_index: u64,
 }
 
+/// Lowest priority of the receipts delayed in each of the priority queues, see
+/// `DelayedReceiptQueue`.
+pub const DELAYED_RECEIPT_PRIORITY_THRESHOLDS: [Balance; 3] =
+    [1, 100_000_000_000_000_000_000, 10_000_000_000_000_000_000_000];
+
+/// Identifies one of the delayed receipt queues of a shard.
+///
+/// Receipts without priority are delayed in the default queue.  Since
+/// `ProtocolFeature::ReceiptPriority`, receipts with a priority are delayed
+/// in the queue of the highest priority class they reach according to
+/// `DELAYED_RECEIPT_PRIORITY_THRESHOLDS` instead.  The queues are drained
+/// from the highest class down to the default queue, so that a congested shard
+/// processes the receipts paying the most first.  Within a queue, receipts
+/// stay in the order in which they were delayed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum DelayedReceiptQueue {
+    Default,
+    Priority { class: u8 },
+}
+
+impl DelayedReceiptQueue {
+    /// All queues, in the order in which they are drained.
+    pub fn all() -> impl Iterator<Item = Self> {
+        (0..DELAYED_RECEIPT_PRIORITY_THRESHOLDS.len() as u8)
+            .rev()
+            .map(|class| Self::Priority { class })
+            .chain(std::iter::once(Self::Default))
+    }
+
+    /// The queues which exist at the given protocol version, in the order in
+    /// which they are drained.
+    pub fn enabled(protocol_version: ProtocolVersion) -> Vec<Self> {
+        if checked_feature!("protocol_feature_receipt_priority", ReceiptPriority, protocol_version)
+        {
+            Self::all().collect()
+        } else {
+            vec![Self::Default]
+        }
+    }
+
+    /// The queue the receipt is delayed in.  Only receipts created since
+    /// `ProtocolFeature::ReceiptPriority` have a priority, so all others go
+    /// to the default queue.
+    pub fn for_receipt(receipt: &Receipt) -> Self {
+        let priority = receipt.priority();
+        match DELAYED_RECEIPT_PRIORITY_THRESHOLDS.iter().rposition(|min| priority >= *min) {
+            Some(class) => Self::Priority { class: class as u8 },
+            None => Self::Default,
+        }
+    }
+
+    /// Key of the `DelayedReceiptIndices` of the queue.
+    pub fn indices_key(self) -> TrieKey {
+        match self {
+            Self::Default => TrieKey::DelayedReceiptIndices,
+            Self::Priority { class } => {
+                TrieKey::PriorityDelayedReceiptIndices { priority_class: class }
+            }
+        }
+    }
+
+    /// Key of the receipt at the given index of the queue.
+    pub fn receipt_key(self, index: u64) -> TrieKey {
+        match self {
+            Self::Default => TrieKey::DelayedReceipt { index },
+            Self::Priority { class } => {
+                TrieKey::PriorityDelayedReceipt { priority_class: class, index }
+            }
+        }
+    }
+}
+
 /// Map of shard to list of receipts to send to it.
 pub type ReceiptResult = HashMap<ShardId, Vec<Receipt>>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn action_receipt(priority: Balance) -> Receipt {
+        Receipt {
+            predecessor_id: "alice.near".parse().unwrap(),
+            receiver_id: "bob.near".parse().unwrap(),
+            receipt_id: CryptoHash::default(),
+            receipt: ReceiptEnum::Action(ActionReceipt {
+                signer_id: "alice.near".parse().unwrap(),
+                signer_public_key: PublicKey::empty(KeyType::ED25519),
+                gas_price: 100,
+                output_data_receivers: vec![],
+                input_data_ids: vec![CryptoHash::default()],
+                actions: vec![Action::Transfer(TransferAction { deposit: 1 })],
+                priority,
+            }),
+        }
+    }
+
+    /// Receipts without priority keep the encoding from before the field was
+    /// added, so that the receipts already in the state can still be read.
+    #[test]
+    fn test_action_receipt_borsh_without_priority() {
+        #[derive(BorshSerialize)]
+        struct OldActionReceipt {
+            signer_id: AccountId,
+            signer_public_key: PublicKey,
+            gas_price: Balance,
+            output_data_receivers: Vec<DataReceiver>,
+            input_data_ids: Vec<CryptoHash>,
+            actions: Vec<Action>,
+        }
+
+        let receipt = match action_receipt(0).receipt {
+            ReceiptEnum::Action(action_receipt) => action_receipt,
+            ReceiptEnum::Data(_) => unreachable!(),
+        };
+        let old_receipt = OldActionReceipt {
+            signer_id: receipt.signer_id.clone(),
+            signer_public_key: receipt.signer_public_key.clone(),
+            gas_price: receipt.gas_price,
+            output_data_receivers: receipt.output_data_receivers.clone(),
+            input_data_ids: receipt.input_data_ids.clone(),
+            actions: receipt.actions.clone(),
+        };
+        let bytes = receipt.try_to_vec().unwrap();
+        assert_eq!(bytes, old_receipt.try_to_vec().unwrap());
+        assert_eq!(ActionReceipt::try_from_slice(&bytes).unwrap(), receipt);
+    }
+
+    #[test]
+    fn test_action_receipt_borsh_with_priority() {
+        let receipt = action_receipt(1_000);
+        let bytes = receipt.try_to_vec().unwrap();
+        let decoded = Receipt::try_from_slice(&bytes).unwrap();
+        assert_eq!(decoded, receipt);
+        assert_eq!(decoded.priority(), 1_000);
+
+        // A zero priority must not be tagged, so that every receipt has a
+        // single encoding.
+        let mut bytes = ActionReceipt::try_to_vec(match &action_receipt(0).receipt {
+            ReceiptEnum::Action(action_receipt) => action_receipt,
+            ReceiptEnum::Data(_) => unreachable!(),
+        })
+        .unwrap();
+        bytes.insert(0, ACTION_RECEIPT_WITH_PRIORITY_TAG);
+        bytes.extend(0u128.to_le_bytes());
+        assert!(ActionReceipt::try_from_slice(&bytes).is_err());
+    }
+
+    #[test]
+    fn test_delayed_receipt_queue_for_receipt() {
+        let [low, medium, high] = DELAYED_RECEIPT_PRIORITY_THRESHOLDS;
+        let queue = |priority| DelayedReceiptQueue::for_receipt(&action_receipt(priority));
+        assert_eq!(queue(0), DelayedReceiptQueue::Default);
+        assert_eq!(queue(low), DelayedReceiptQueue::Priority { class: 0 });
+        assert_eq!(queue(medium - 1), DelayedReceiptQueue::Priority { class: 0 });
+        assert_eq!(queue(medium), DelayedReceiptQueue::Priority { class: 1 });
+        assert_eq!(queue(high), DelayedReceiptQueue::Priority { class: 2 });
+        assert_eq!(queue(Balance::MAX), DelayedReceiptQueue::Priority { class: 2 });
+
+        let data_receipt = Receipt {
+            receipt: ReceiptEnum::Data(DataReceipt { data_id: CryptoHash::default(), data: None }),
+            ..action_receipt(high)
+        };
+        assert_eq!(DelayedReceiptQueue::for_receipt(&data_receipt), DelayedReceiptQueue::Default);
+
+        let queues: Vec<_> = DelayedReceiptQueue::all().collect();
+        assert_eq!(
+            queues,
+            vec![
+                DelayedReceiptQueue::Priority { class: 2 },
+                DelayedReceiptQueue::Priority { class: 1 },
+                DelayedReceiptQueue::Priority { class: 0 },
+                DelayedReceiptQueue::Default,
+            ]
+        );
+    }
+}
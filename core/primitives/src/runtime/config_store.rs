@@ -1,7 +1,8 @@
+use crate::config::VMLimitConfig;
 use crate::runtime::config::RuntimeConfig;
 use crate::runtime::parameter_table::{ParameterTable, ParameterTableDiff};
 use crate::types::ProtocolVersion;
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, BTreeSet};
 use std::ops::Bound;
 use std::sync::Arc;
 
@@ -28,6 +29,15 @@ static CONFIG_DIFFS: &[(ProtocolVersion, &str)] = &[
     // set read_cached_trie_node cost, decrease storage key limit
     (53, include_config!("53.txt")),
     (57, include_config!("57.txt")),
+    // Fees of the CreateAccountWithContract action
+    #[cfg(feature = "protocol_feature_create_account_with_contract")]
+    (137, include_config!("137.txt")),
+    // Limit globals, table sizes and custom sections of contracts
+    #[cfg(feature = "protocol_feature_limit_contract_sections")]
+    (139, include_config!("139.txt")),
+    // Charge for the storage_iter_prefix_page host function
+    #[cfg(feature = "protocol_feature_storage_iter_prefix_page")]
+    (141, include_config!("141.txt")),
 ];
 
 /// Testnet parameters for versions <= 29, which (incorrectly) differed from mainnet parameters
@@ -101,6 +111,71 @@ impl RuntimeConfigStore {
             })
             .1
     }
+
+    /// Lists the limits which differ between the configs of two protocol
+    /// versions.
+    pub fn limit_config_diff(
+        &self,
+        from: ProtocolVersion,
+        to: ProtocolVersion,
+    ) -> Vec<LimitConfigChange> {
+        diff_limit_configs(
+            &self.get_config(from).wasm_config.limit_config,
+            &self.get_config(to).wasm_config.limit_config,
+        )
+    }
+
+    /// Lists the changed limits for every protocol version which changed the
+    /// `VMLimitConfig`.
+    pub fn limit_config_changes(&self) -> Vec<(ProtocolVersion, Vec<LimitConfigChange>)> {
+        self.store
+            .iter()
+            .zip(self.store.iter().skip(1))
+            .filter_map(|((_, prev), (version, config))| {
+                let changes = diff_limit_configs(
+                    &prev.wasm_config.limit_config,
+                    &config.wasm_config.limit_config,
+                );
+                (!changes.is_empty()).then(|| (*version, changes))
+            })
+            .collect()
+    }
+}
+
+/// A limit which differs between two `VMLimitConfig`s.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+pub struct LimitConfigChange {
+    /// Name of the field in `VMLimitConfig`.
+    pub name: String,
+    /// JSON value of the limit before the change, `None` if it was not set.
+    pub old: Option<serde_json::Value>,
+    /// JSON value of the limit after the change, `None` if it is not set.
+    pub new: Option<serde_json::Value>,
+}
+
+/// Compares the limits by their JSON representation, so that new fields of
+/// `VMLimitConfig` are covered without changes here.  Changes are sorted by
+/// name.
+pub fn diff_limit_configs(old: &VMLimitConfig, new: &VMLimitConfig) -> Vec<LimitConfigChange> {
+    let old = limit_config_to_json(old);
+    let new = limit_config_to_json(new);
+    let names: BTreeSet<&String> = old.keys().chain(new.keys()).collect();
+    names
+        .into_iter()
+        .filter(|name| old.get(*name) != new.get(*name))
+        .map(|name| LimitConfigChange {
+            name: name.clone(),
+            old: old.get(name).cloned(),
+            new: new.get(name).cloned(),
+        })
+        .collect()
+}
+
+fn limit_config_to_json(config: &VMLimitConfig) -> serde_json::Map<String, serde_json::Value> {
+    match serde_json::to_value(config).expect("VMLimitConfig is serializable") {
+        serde_json::Value::Object(map) => map,
+        value => unreachable!("VMLimitConfig serialized as {value}"),
+    }
 }
 
 #[cfg(test)]
@@ -222,6 +297,37 @@ mod tests {
         );
     }
 
+    /// Each signature in a batch is as much work as a call to `ecrecover`, so
+    /// it must never be cheaper.
+    #[test]
+    #[cfg(feature = "protocol_feature_ecrecover_batch")]
+    fn test_ecrecover_batch_element_cost() {
+        let store = RuntimeConfigStore::new(None);
+        let protocol_versions = CONFIG_DIFFS.iter().map(|(protocol_version, _)| *protocol_version);
+        for protocol_version in std::iter::once(0).chain(protocol_versions) {
+            let ext_costs = &store.get_config(protocol_version).wasm_config.ext_costs;
+            assert!(ext_costs.ecrecover_batch_element >= ext_costs.ecrecover_base);
+        }
+    }
+
+    /// The fees of `CreateAccountWithContract` are only set from the version
+    /// that introduced the action.
+    #[test]
+    #[cfg(feature = "protocol_feature_create_account_with_contract")]
+    fn test_state_init_cost() {
+        use crate::runtime::fees::Fee;
+        use crate::version::ProtocolFeature::CreateAccountWithContract;
+        let store = RuntimeConfigStore::new(None);
+        let version = CreateAccountWithContract.protocol_version();
+        let old_cfg = store.get_config(version - 1);
+        let new_cfg = store.get_config(version);
+        assert_eq!(old_cfg.transaction_costs.action_creation_config.state_init_cost, Fee::free());
+        assert_eq!(
+            new_cfg.transaction_costs.action_creation_config.state_init_cost.send_sir,
+            64_196_736_000
+        );
+    }
+
     #[test]
     fn test_lower_max_length_storage_key() {
         let store = RuntimeConfigStore::new(None);
@@ -257,4 +363,54 @@ mod tests {
             insta::assert_json_snapshot!(snapshot_name, store.get_config(*version));
         }
     }
+
+    #[test]
+    fn test_limit_config_diff() {
+        let store = RuntimeConfigStore::new(None);
+        let version = LowerStorageKeyLimit.protocol_version();
+        assert_eq!(store.limit_config_diff(version, version), vec![]);
+
+        let changes = store.limit_config_diff(version - 1, version);
+        let change = changes.iter().find(|change| change.name == "max_length_storage_key").unwrap();
+        assert_eq!(change.old, Some(serde_json::json!(4_194_304)));
+        assert_eq!(change.new, Some(serde_json::json!(2_048)));
+        assert!(store
+            .limit_config_changes()
+            .iter()
+            .any(|(changed_version, changes)| *changed_version == version
+                && changes.contains(change)));
+    }
+
+    /// Pins the effective `VMLimitConfig` of each version and the limits
+    /// changed by each version, so that a limit can't drift unnoticed.
+    /// If tests fail after an intended change, run `cargo insta review` and
+    /// accept the new snapshots if they look right.
+    #[test]
+    #[cfg(not(feature = "nightly"))]
+    fn test_limit_configs_unchanged() {
+        let store = RuntimeConfigStore::new(None);
+        let limit_configs: BTreeMap<_, _> = store
+            .store
+            .iter()
+            .map(|(version, config)| (*version, &config.wasm_config.limit_config))
+            .collect();
+        insta::assert_json_snapshot!("limit_configs.json", limit_configs);
+        insta::assert_json_snapshot!("limit_config_changes.json", store.limit_config_changes());
+    }
+
+    /// Limits disabled for tests, like the `u64::MAX` limits of the params
+    /// estimator, must never end up in a protocol version.
+    #[test]
+    fn test_no_disabled_limits() {
+        let store = RuntimeConfigStore::new(None);
+        for (version, config) in &store.store {
+            for (name, value) in limit_config_to_json(&config.wasm_config.limit_config) {
+                assert_ne!(
+                    value.as_u64(),
+                    Some(u64::MAX),
+                    "{name} is disabled in version {version}"
+                );
+            }
+        }
+    }
 }
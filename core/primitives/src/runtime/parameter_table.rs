@@ -104,7 +104,8 @@ impl ParameterTable {
     }
 
     fn transaction_costs_json(&self) -> serde_json::Value {
-        json!( {
+        #[allow(unused_mut)]
+        let mut json = json!( {
             "action_receipt_creation_config": self.fee_json(FeeParameter::ActionReceiptCreation),
             "data_receipt_creation_config": {
                 "base_cost": self.fee_json(FeeParameter::DataReceiptCreationBase),
@@ -138,7 +139,18 @@ impl ParameterTable {
                 self.get(Parameter::PessimisticGasPriceInflationNumerator),
                 self.get(Parameter::PessimisticGasPriceInflationDenominator)
             ]
-        })
+        });
+        // The fees of `CreateAccountWithContract` only exist from the
+        // version that introduced it, see `ActionCreationConfig`.
+        #[cfg(feature = "protocol_feature_create_account_with_contract")]
+        if self.get(Parameter::ActionStateInitSendSir).is_some() {
+            let action_creation_config = &mut json["action_creation_config"];
+            action_creation_config["state_init_cost"] =
+                self.fee_json(FeeParameter::ActionStateInit);
+            action_creation_config["state_init_cost_per_byte"] =
+                self.fee_json(FeeParameter::ActionStateInitPerByte);
+        }
+        json
     }
 
     fn json_map(
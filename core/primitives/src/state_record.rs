@@ -95,6 +95,11 @@ impl StateRecord {
                 Some(StateRecord::DelayedReceipt(Box::new(receipt)))
             }
             col::DELAYED_RECEIPT_INDICES => None,
+            col::PRIORITY_DELAYED_RECEIPT => {
+                let receipt = Receipt::try_from_slice(&value).unwrap();
+                Some(StateRecord::DelayedReceipt(Box::new(receipt)))
+            }
+            col::PRIORITY_DELAYED_RECEIPT_INDICES => None,
             _ => unreachable!(),
         }
     }
@@ -167,5 +172,11 @@ pub fn is_contract_code_key(key: &[u8]) -> bool {
 
 pub fn is_delayed_receipt_key(key: &[u8]) -> bool {
     debug_assert!(!key.is_empty());
-    key[0] == col::DELAYED_RECEIPT || key[0] == col::DELAYED_RECEIPT_INDICES
+    matches!(
+        key[0],
+        col::DELAYED_RECEIPT
+            | col::DELAYED_RECEIPT_INDICES
+            | col::PRIORITY_DELAYED_RECEIPT
+            | col::PRIORITY_DELAYED_RECEIPT_INDICES
+    )
 }
@@ -15,7 +15,7 @@ use crate::sharding::ShardChunkHeader;
 use crate::transaction::{
     Action, AddKeyAction, CreateAccountAction, DeleteAccountAction, DeleteKeyAction,
     DeployContractAction, FunctionCallAction, SignedTransaction, StakeAction, Transaction,
-    TransferAction,
+    TransactionV0, TransferAction,
 };
 use crate::types::{AccountId, Balance, BlockHeight, EpochId, EpochInfoProvider, Gas, Nonce};
 use crate::validator_signer::ValidatorSigner;
@@ -33,7 +33,22 @@ impl Transaction {
         nonce: Nonce,
         block_hash: CryptoHash,
     ) -> Self {
-        Self { signer_id, public_key, nonce, receiver_id, block_hash, actions: vec![] }
+        Self::V0(TransactionV0 {
+            signer_id,
+            public_key,
+            nonce,
+            receiver_id,
+            block_hash,
+            actions: vec![],
+        })
+    }
+
+    pub fn actions_mut(&mut self) -> &mut Vec<Action> {
+        match self {
+            Self::V0(tx) => &mut tx.actions,
+            #[cfg(feature = "protocol_feature_transaction_priority_fee")]
+            Self::V1(tx) => &mut tx.actions,
+        }
     }
 
     pub fn sign(self, signer: &dyn Signer) -> SignedTransaction {
@@ -42,12 +57,12 @@ impl Transaction {
     }
 
     pub fn create_account(mut self) -> Self {
-        self.actions.push(Action::CreateAccount(CreateAccountAction {}));
+        self.actions_mut().push(Action::CreateAccount(CreateAccountAction {}));
         self
     }
 
     pub fn deploy_contract(mut self, code: Vec<u8>) -> Self {
-        self.actions.push(Action::DeployContract(DeployContractAction { code }));
+        self.actions_mut().push(Action::DeployContract(DeployContractAction { code }));
         self
     }
 
@@ -58,7 +73,7 @@ impl Transaction {
         gas: Gas,
         deposit: Balance,
     ) -> Self {
-        self.actions.push(Action::FunctionCall(FunctionCallAction {
+        self.actions_mut().push(Action::FunctionCall(FunctionCallAction {
             method_name,
             args,
             gas,
@@ -68,26 +83,26 @@ impl Transaction {
     }
 
     pub fn transfer(mut self, deposit: Balance) -> Self {
-        self.actions.push(Action::Transfer(TransferAction { deposit }));
+        self.actions_mut().push(Action::Transfer(TransferAction { deposit }));
         self
     }
 
     pub fn stake(mut self, stake: Balance, public_key: PublicKey) -> Self {
-        self.actions.push(Action::Stake(StakeAction { stake, public_key }));
+        self.actions_mut().push(Action::Stake(StakeAction { stake, public_key }));
         self
     }
     pub fn add_key(mut self, public_key: PublicKey, access_key: AccessKey) -> Self {
-        self.actions.push(Action::AddKey(AddKeyAction { public_key, access_key }));
+        self.actions_mut().push(Action::AddKey(AddKeyAction { public_key, access_key }));
         self
     }
 
     pub fn delete_key(mut self, public_key: PublicKey) -> Self {
-        self.actions.push(Action::DeleteKey(DeleteKeyAction { public_key }));
+        self.actions_mut().push(Action::DeleteKey(DeleteKeyAction { public_key }));
         self
     }
 
     pub fn delete_account(mut self, beneficiary_id: AccountId) -> Self {
-        self.actions.push(Action::DeleteAccount(DeleteAccountAction { beneficiary_id }));
+        self.actions_mut().push(Action::DeleteAccount(DeleteAccountAction { beneficiary_id }));
         self
     }
 }
@@ -101,14 +116,14 @@ impl SignedTransaction {
         actions: Vec<Action>,
         block_hash: CryptoHash,
     ) -> Self {
-        Transaction {
+        Transaction::V0(TransactionV0 {
             nonce,
             signer_id,
             public_key: signer.public_key(),
             receiver_id,
             block_hash,
             actions,
-        }
+        })
         .sign(signer)
     }
 
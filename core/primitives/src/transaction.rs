@@ -1,25 +1,29 @@
 use std::borrow::Borrow;
 use std::fmt;
 use std::hash::{Hash, Hasher};
+use std::io;
 
 use borsh::{BorshDeserialize, BorshSerialize};
 use serde::{Deserialize, Serialize};
 
 use near_crypto::{PublicKey, Signature};
 use near_o11y::pretty;
-use near_primitives_core::profile::ProfileData;
+use near_primitives_core::profile::{Cost, ProfileData};
 
 use crate::account::AccessKey;
-use crate::errors::TxExecutionError;
+use crate::config::VMLimitConfig;
+use crate::errors::{InvalidTxError, TxExecutionError};
 use crate::hash::{hash, CryptoHash};
 use crate::merkle::MerklePath;
 use crate::serialize::{base64_format, dec_format};
-use crate::types::{AccountId, Balance, Gas, Nonce};
+use crate::types::{AccountId, Balance, Gas, Nonce, TrieNodesCount};
+use crate::validation::validate_actions;
 
 pub type LogEntry = String;
 
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, PartialEq, Eq, Debug, Clone)]
-pub struct Transaction {
+pub struct TransactionV0 {
     /// An account on which behalf transaction is signed
     pub signer_id: AccountId,
     /// A public key of the access key which was used to sign an account.
@@ -36,14 +40,187 @@ pub struct Transaction {
     pub actions: Vec<Action>,
 }
 
+/// Like `TransactionV0`, plus a priority fee offered for being included in a
+/// chunk before transactions which pay less.
+#[cfg(feature = "protocol_feature_transaction_priority_fee")]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, PartialEq, Eq, Debug, Clone)]
+pub struct TransactionV1 {
+    /// An account on which behalf transaction is signed
+    pub signer_id: AccountId,
+    /// A public key of the access key which was used to sign an account.
+    /// Access key holds permissions for calling certain kinds of actions.
+    pub public_key: PublicKey,
+    /// Nonce is used to determine order of transaction in the pool.
+    /// It increments for a combination of `signer_id` and `public_key`
+    pub nonce: Nonce,
+    /// Receiver account for this transaction
+    pub receiver_id: AccountId,
+    /// The hash of the block in the blockchain on top of which the given transaction is valid
+    pub block_hash: CryptoHash,
+    /// A list of actions to be applied
+    pub actions: Vec<Action>,
+    /// Balance charged to the signer and burnt when the transaction is
+    /// converted to a receipt, on top of the gas.
+    #[serde(with = "dec_format")]
+    pub priority_fee: Balance,
+}
+
+/// Borsh tag of `TransactionV1`. `TransactionV0` is serialized without a tag
+/// for backward compatibility. It starts with the length of the signer id,
+/// which is at least 2, so the first byte tells the versions apart.
+const TRANSACTION_V1_TAG: u8 = 1;
+
+/// `V1` is listed first, as untagged deserialization would otherwise parse a
+/// `V1` transaction as `V0` and drop the priority fee.
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[derive(Serialize, Deserialize, PartialEq, Eq, Debug, Clone)]
+#[serde(untagged)]
+pub enum Transaction {
+    #[cfg(feature = "protocol_feature_transaction_priority_fee")]
+    V1(TransactionV1),
+    V0(TransactionV0),
+}
+
 impl Transaction {
     /// Computes a hash of the transaction for signing and size of serialized transaction
     pub fn get_hash_and_size(&self) -> (CryptoHash, u64) {
         let bytes = self.try_to_vec().expect("Failed to deserialize");
         (hash(&bytes), bytes.len() as u64)
     }
+
+    pub fn signer_id(&self) -> &AccountId {
+        match self {
+            Self::V0(tx) => &tx.signer_id,
+            #[cfg(feature = "protocol_feature_transaction_priority_fee")]
+            Self::V1(tx) => &tx.signer_id,
+        }
+    }
+
+    pub fn public_key(&self) -> &PublicKey {
+        match self {
+            Self::V0(tx) => &tx.public_key,
+            #[cfg(feature = "protocol_feature_transaction_priority_fee")]
+            Self::V1(tx) => &tx.public_key,
+        }
+    }
+
+    pub fn nonce(&self) -> Nonce {
+        match self {
+            Self::V0(tx) => tx.nonce,
+            #[cfg(feature = "protocol_feature_transaction_priority_fee")]
+            Self::V1(tx) => tx.nonce,
+        }
+    }
+
+    pub fn receiver_id(&self) -> &AccountId {
+        match self {
+            Self::V0(tx) => &tx.receiver_id,
+            #[cfg(feature = "protocol_feature_transaction_priority_fee")]
+            Self::V1(tx) => &tx.receiver_id,
+        }
+    }
+
+    pub fn block_hash(&self) -> &CryptoHash {
+        match self {
+            Self::V0(tx) => &tx.block_hash,
+            #[cfg(feature = "protocol_feature_transaction_priority_fee")]
+            Self::V1(tx) => &tx.block_hash,
+        }
+    }
+
+    pub fn actions(&self) -> &[Action] {
+        match self {
+            Self::V0(tx) => &tx.actions,
+            #[cfg(feature = "protocol_feature_transaction_priority_fee")]
+            Self::V1(tx) => &tx.actions,
+        }
+    }
+
+    pub fn take_actions(self) -> Vec<Action> {
+        match self {
+            Self::V0(tx) => tx.actions,
+            #[cfg(feature = "protocol_feature_transaction_priority_fee")]
+            Self::V1(tx) => tx.actions,
+        }
+    }
+
+    /// The priority fee of a `V1` transaction, `None` for older versions.
+    pub fn priority_fee(&self) -> Option<Balance> {
+        match self {
+            Self::V0(_) => None,
+            #[cfg(feature = "protocol_feature_transaction_priority_fee")]
+            Self::V1(tx) => Some(tx.priority_fee),
+        }
+    }
+
+    /// Checks the serialized size and the actions of the transaction against
+    /// the limits, see `crate::validation` for the checks of the actions.
+    pub fn validate(&self, limit_config: &VMLimitConfig) -> Result<(), InvalidTxError> {
+        let size = self.try_to_vec().expect("Failed to serialize").len() as u64;
+        self.validate_with_size(size, limit_config)
+    }
+
+    fn validate_with_size(
+        &self,
+        size: u64,
+        limit_config: &VMLimitConfig,
+    ) -> Result<(), InvalidTxError> {
+        if size > limit_config.max_transaction_size {
+            return Err(InvalidTxError::TransactionSizeExceeded {
+                size,
+                limit: limit_config.max_transaction_size,
+            });
+        }
+        validate_actions(limit_config, self.actions()).map_err(InvalidTxError::ActionsValidation)
+    }
+}
+
+impl From<TransactionV0> for Transaction {
+    fn from(transaction: TransactionV0) -> Self {
+        Self::V0(transaction)
+    }
+}
+
+#[cfg(feature = "protocol_feature_transaction_priority_fee")]
+impl From<TransactionV1> for Transaction {
+    fn from(transaction: TransactionV1) -> Self {
+        Self::V1(transaction)
+    }
 }
 
+impl BorshSerialize for Transaction {
+    fn serialize<W: io::Write>(&self, writer: &mut W) -> io::Result<()> {
+        match self {
+            Self::V0(tx) => BorshSerialize::serialize(tx, writer),
+            #[cfg(feature = "protocol_feature_transaction_priority_fee")]
+            Self::V1(tx) => {
+                BorshSerialize::serialize(&TRANSACTION_V1_TAG, writer)?;
+                BorshSerialize::serialize(tx, writer)
+            }
+        }
+    }
+}
+
+impl BorshDeserialize for Transaction {
+    fn deserialize(buf: &mut &[u8]) -> io::Result<Self> {
+        if buf.first() == Some(&TRANSACTION_V1_TAG) {
+            #[cfg(feature = "protocol_feature_transaction_priority_fee")]
+            {
+                *buf = &buf[1..];
+                return Ok(Self::V1(<TransactionV1 as BorshDeserialize>::deserialize(buf)?));
+            }
+            #[cfg(not(feature = "protocol_feature_transaction_priority_fee"))]
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "transaction version 1 is not supported",
+            ));
+        }
+        Ok(Self::V0(<TransactionV0 as BorshDeserialize>::deserialize(buf)?))
+    }
+}
+
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[derive(
     BorshSerialize,
     BorshDeserialize,
@@ -68,6 +245,10 @@ pub enum Action {
     AddKey(AddKeyAction),
     DeleteKey(DeleteKeyAction),
     DeleteAccount(DeleteAccountAction),
+    /// Creates an (sub)account like `CreateAccount`, deploys a contract to it
+    /// and writes the initial contract state, all in one action.
+    #[cfg(feature = "protocol_feature_create_account_with_contract")]
+    CreateAccountWithContract(CreateAccountWithContractAction),
 }
 
 impl Action {
@@ -87,6 +268,7 @@ impl Action {
 }
 
 /// Create account action
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, PartialEq, Eq, Clone, Debug)]
 pub struct CreateAccountAction {}
 
@@ -97,6 +279,7 @@ impl From<CreateAccountAction> for Action {
 }
 
 /// Deploy contract action
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, PartialEq, Eq, Clone)]
 pub struct DeployContractAction {
     /// WebAssembly binary
@@ -118,6 +301,66 @@ impl fmt::Debug for DeployContractAction {
     }
 }
 
+/// Create account, deploy contract and initialize its state action
+#[cfg(feature = "protocol_feature_create_account_with_contract")]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, PartialEq, Eq, Clone)]
+pub struct CreateAccountWithContractAction {
+    /// WebAssembly binary
+    #[serde(with = "base64_format")]
+    pub code: Vec<u8>,
+    /// Contract state written before the account becomes visible to other
+    /// receipts.
+    pub state: Vec<ContractStateEntry>,
+}
+
+#[cfg(feature = "protocol_feature_create_account_with_contract")]
+impl CreateAccountWithContractAction {
+    /// Total number of bytes of keys and values of the initial state.
+    pub fn state_num_bytes(&self) -> u64 {
+        self.state.iter().map(|entry| entry.key.len() as u64 + entry.value.len() as u64).sum()
+    }
+}
+
+#[cfg(feature = "protocol_feature_create_account_with_contract")]
+impl From<CreateAccountWithContractAction> for Action {
+    fn from(action: CreateAccountWithContractAction) -> Self {
+        Self::CreateAccountWithContract(action)
+    }
+}
+
+#[cfg(feature = "protocol_feature_create_account_with_contract")]
+impl fmt::Debug for CreateAccountWithContractAction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CreateAccountWithContractAction")
+            .field("code", &format_args!("{}", pretty::AbbrBytes(&self.code)))
+            .field("state", &self.state)
+            .finish()
+    }
+}
+
+/// Key-value pair of contract storage.
+#[cfg(feature = "protocol_feature_create_account_with_contract")]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, PartialEq, Eq, Clone)]
+pub struct ContractStateEntry {
+    #[serde(with = "base64_format")]
+    pub key: Vec<u8>,
+    #[serde(with = "base64_format")]
+    pub value: Vec<u8>,
+}
+
+#[cfg(feature = "protocol_feature_create_account_with_contract")]
+impl fmt::Debug for ContractStateEntry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ContractStateEntry")
+            .field("key", &format_args!("{}", pretty::AbbrBytes(&self.key)))
+            .field("value", &format_args!("{}", pretty::AbbrBytes(&self.value)))
+            .finish()
+    }
+}
+
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, PartialEq, Eq, Clone)]
 pub struct FunctionCallAction {
     pub method_name: String,
@@ -145,6 +388,7 @@ impl fmt::Debug for FunctionCallAction {
     }
 }
 
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, PartialEq, Eq, Clone, Debug)]
 pub struct TransferAction {
     #[serde(with = "dec_format")]
@@ -158,6 +402,7 @@ impl From<TransferAction> for Action {
 }
 
 /// An action which stakes signer_id tokens and setup's validator public key
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, PartialEq, Eq, Clone, Debug)]
 pub struct StakeAction {
     /// Amount of tokens to stake.
@@ -173,6 +418,7 @@ impl From<StakeAction> for Action {
     }
 }
 
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, PartialEq, Eq, Clone, Debug)]
 pub struct AddKeyAction {
     /// A public key which will be associated with an access_key
@@ -187,6 +433,7 @@ impl From<AddKeyAction> for Action {
     }
 }
 
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, PartialEq, Eq, Clone, Debug)]
 pub struct DeleteKeyAction {
     /// A public key associated with the access_key to be deleted.
@@ -199,6 +446,7 @@ impl From<DeleteKeyAction> for Action {
     }
 }
 
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, PartialEq, Eq, Clone, Debug)]
 pub struct DeleteAccountAction {
     pub beneficiary_id: AccountId,
@@ -210,8 +458,7 @@ impl From<DeleteAccountAction> for Action {
     }
 }
 
-#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Eq, Debug, Clone)]
-#[borsh_init(init)]
+#[derive(BorshSerialize, Serialize, Deserialize, Eq, Debug, Clone)]
 pub struct SignedTransaction {
     pub transaction: Transaction,
     pub signature: Signature,
@@ -229,6 +476,13 @@ impl SignedTransaction {
         signed_tx
     }
 
+    /// Deserializes a transaction, computing its hash and size from `bytes`
+    /// instead of serializing the transaction again.
+    pub fn from_borsh_bytes(bytes: &[u8]) -> io::Result<Self> {
+        Self::try_from_slice(bytes)
+    }
+
+    /// Recomputes the hash and size after the transaction was modified.
     pub fn init(&mut self) {
         let (hash, size) = self.transaction.get_hash_and_size();
         self.hash = hash;
@@ -242,6 +496,34 @@ impl SignedTransaction {
     pub fn get_size(&self) -> u64 {
         self.size
     }
+
+    /// Same as `Transaction::validate`, without serializing the transaction
+    /// again.
+    pub fn validate(&self, limit_config: &VMLimitConfig) -> Result<(), InvalidTxError> {
+        self.transaction.validate_with_size(self.size, limit_config)
+    }
+}
+
+/// The hash and size are computed from the bytes the transaction is read
+/// from, instead of serializing the transaction again.
+///
+/// This is only the same as hashing the serialized transaction because every
+/// type in a transaction decodes from its canonical encoding only.  Plain
+/// Borsh maps do not, they accept unsorted and duplicate keys, so maps in a
+/// transaction need a strict decoder, see `FunctionCallPermission`.
+impl BorshDeserialize for SignedTransaction {
+    fn deserialize(buf: &mut &[u8]) -> io::Result<Self> {
+        let start = *buf;
+        let transaction = <Transaction as BorshDeserialize>::deserialize(buf)?;
+        let transaction_bytes = &start[..start.len() - buf.len()];
+        let signature = <Signature as BorshDeserialize>::deserialize(buf)?;
+        Ok(Self {
+            transaction,
+            signature,
+            hash: hash(transaction_bytes),
+            size: transaction_bytes.len() as u64,
+        })
+    }
 }
 
 impl Hash for SignedTransaction {
@@ -263,6 +545,7 @@ impl Borrow<CryptoHash> for SignedTransaction {
 }
 
 /// The status of execution for a transaction or a receipt.
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[derive(BorshSerialize, BorshDeserialize, PartialEq, Eq, Clone)]
 pub enum ExecutionStatus {
     /// The execution is pending or unknown.
@@ -319,6 +602,16 @@ impl From<&ExecutionOutcome> for PartialExecutionOutcome {
     }
 }
 
+/// ExecutionOutcome for proof of outcomes with `ExecutionMetadata::V7`.
+/// Like `PartialExecutionOutcome`, plus a commitment to the metadata so that
+/// the gas profile can be proven as well.
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Clone)]
+pub struct PartialExecutionOutcomeV2 {
+    pub outcome: PartialExecutionOutcome,
+    /// Hash of the metadata, see `ExecutionMetadata::proof_hash`.
+    pub metadata_hash: CryptoHash,
+}
+
 /// ExecutionStatus for proof. Excludes failure debug info.
 #[derive(BorshSerialize, BorshDeserialize, PartialEq, Clone)]
 pub enum PartialExecutionStatus {
@@ -340,6 +633,7 @@ impl From<ExecutionStatus> for PartialExecutionStatus {
 }
 
 /// Execution outcome for one signed transaction or one receipt.
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[derive(BorshSerialize, BorshDeserialize, PartialEq, Clone, smart_default::SmartDefault, Eq)]
 pub struct ExecutionOutcome {
     /// Logs from this transaction or receipt.
@@ -364,6 +658,7 @@ pub struct ExecutionOutcome {
     pub metadata: ExecutionMetadata,
 }
 
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[derive(BorshSerialize, BorshDeserialize, PartialEq, Clone, Eq, Debug)]
 pub enum ExecutionMetadata {
     // V1: Empty Metadata
@@ -371,6 +666,150 @@ pub enum ExecutionMetadata {
 
     // V2: With ProfileData
     V2(ProfileData),
+
+    // V3: With ProfileData and the trie nodes read while executing the receipt
+    V3 {
+        profile: ProfileData,
+        trie_nodes_count: TrieNodesCount,
+    },
+
+    // V4: Like V3, plus the code of the function call error if the receipt
+    // failed in a contract, see `FunctionCallError::code`
+    V4 {
+        profile: ProfileData,
+        trie_nodes_count: TrieNodesCount,
+        error_code: Option<u32>,
+    },
+
+    // V5: Like V4, plus the gas attached to the receipt and the part of it
+    // that was refunded to the signer
+    V5 {
+        profile: ProfileData,
+        trie_nodes_count: TrieNodesCount,
+        error_code: Option<u32>,
+        gas_refund: GasRefund,
+    },
+
+    // V6: Like V5, plus the events emitted by the contract with `emit_event`
+    V6 {
+        profile: ProfileData,
+        trie_nodes_count: TrieNodesCount,
+        error_code: Option<u32>,
+        gas_refund: GasRefund,
+        events: Vec<Event>,
+    },
+
+    // V7: Same fields as V6, but the metadata is committed to in the outcome
+    // proof, see `PartialExecutionOutcomeV2`
+    V7 {
+        profile: ProfileData,
+        trie_nodes_count: TrieNodesCount,
+        error_code: Option<u32>,
+        gas_refund: GasRefund,
+        events: Vec<Event>,
+    },
+}
+
+impl ExecutionMetadata {
+    /// Events emitted while executing the receipt, empty before `V6`.
+    pub fn events(&self) -> &[Event] {
+        match self {
+            ExecutionMetadata::V6 { events, .. } | ExecutionMetadata::V7 { events, .. } => events,
+            _ => &[],
+        }
+    }
+
+    /// Hash of the metadata if it is part of the outcome proof, which is the
+    /// case since `V7`.
+    ///
+    /// The hash is computed over `ExecutionMetadataCommitment` rather than the
+    /// borsh encoding of the metadata, which depends on the compiled features.
+    pub fn proof_hash(&self) -> Option<CryptoHash> {
+        match self {
+            ExecutionMetadata::V7 { profile, trie_nodes_count, error_code, gas_refund, events } => {
+                Some(CryptoHash::hash_borsh(ExecutionMetadataCommitment::V1 {
+                    gas_profile: gas_profile_commitment(profile),
+                    trie_nodes_count,
+                    error_code,
+                    gas_refund,
+                    events,
+                }))
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Encoding of `ExecutionMetadata` committed to in the outcome proof.
+///
+/// The borsh encoding of `ProfileData` has one entry per cost known to the
+/// binary, so it changes with the enabled protocol features. The commitment
+/// lists the costs explicitly instead, see `gas_profile_commitment`. Changes
+/// to this encoding require a new version.
+#[derive(BorshSerialize)]
+enum ExecutionMetadataCommitment<'a> {
+    V1 {
+        gas_profile: Vec<(String, Gas)>,
+        trie_nodes_count: &'a TrieNodesCount,
+        error_code: &'a Option<u32>,
+        gas_refund: &'a GasRefund,
+        events: &'a [Event],
+    },
+}
+
+/// Non-zero costs of the profile by name, sorted by name.
+///
+/// Costs that share an entry in the profile, like `deploy_contract_base` and
+/// `deploy_contract_byte`, are listed once under the name of the first one.
+fn gas_profile_commitment(profile: &ProfileData) -> Vec<(String, Gas)> {
+    let mut seen_entries = std::collections::HashSet::new();
+    let mut costs: Vec<_> = Cost::iter()
+        .filter(|cost| seen_entries.insert(cost.profile_index()))
+        .filter(|&cost| profile[cost] > 0)
+        .map(|cost| {
+            let name = match cost {
+                Cost::ActionCost { action_cost_kind } => format!("action/{:?}", action_cost_kind),
+                Cost::ExtCost { ext_cost_kind } => format!("ext/{:?}", ext_cost_kind),
+                Cost::WasmInstruction => "wasm_instruction".to_string(),
+            };
+            (name, profile[cost])
+        })
+        .collect();
+    costs.sort();
+    costs
+}
+
+/// Event emitted by a contract with the `emit_event` host function.
+///
+/// Unlike events logged as `EVENT_JSON:` strings, the fields are typed and
+/// every event is part of the outcome proof, see
+/// `ExecutionOutcomeWithId::to_hashes`.
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Clone, Eq, Debug)]
+pub struct Event {
+    /// Name of the standard the event belongs to, e.g. `nep171`.
+    pub standard: String,
+    /// Version of the standard, e.g. `1.0.0`.
+    pub version: String,
+    /// Name of the event, e.g. `nft_mint`.
+    pub event: String,
+    /// Payload of the event, its encoding is defined by the standard.
+    pub data: Vec<u8>,
+}
+
+/// Gas attached to an action receipt and the unused part of it that is
+/// refunded to the signer.
+///
+/// The rest of the attached gas was either burnt by the receipt or passed on
+/// to the receipts it created.
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Clone, Copy, Default, Eq, Debug)]
+pub struct GasRefund {
+    /// Gas prepaid for the function calls of the receipt plus the execution
+    /// fees of its actions.
+    pub attached: Gas,
+    /// Gas converted back to tokens in a refund receipt.
+    pub refunded: Gas,
 }
 
 impl Default for ExecutionMetadata {
@@ -405,10 +844,18 @@ pub struct ExecutionOutcomeWithId {
 
 impl ExecutionOutcomeWithId {
     pub fn to_hashes(&self) -> Vec<CryptoHash> {
-        let mut result = Vec::with_capacity(2 + self.outcome.logs.len());
+        let events = self.outcome.metadata.events();
+        let mut result = Vec::with_capacity(2 + self.outcome.logs.len() + events.len());
         result.push(self.id);
-        result.push(CryptoHash::hash_borsh(PartialExecutionOutcome::from(&self.outcome)));
+        let outcome = PartialExecutionOutcome::from(&self.outcome);
+        result.push(match self.outcome.metadata.proof_hash() {
+            Some(metadata_hash) => {
+                CryptoHash::hash_borsh(PartialExecutionOutcomeV2 { outcome, metadata_hash })
+            }
+            None => CryptoHash::hash_borsh(outcome),
+        });
         result.extend(self.outcome.logs.iter().map(|log| hash(log.as_bytes())));
+        result.extend(events.iter().map(CryptoHash::hash_borsh));
         result
     }
 }
@@ -450,20 +897,21 @@ mod tests {
     use near_crypto::{InMemorySigner, KeyType, Signature, Signer};
 
     use crate::account::{AccessKeyPermission, FunctionCallPermission};
+    use crate::errors::ActionsValidationError;
 
     use super::*;
 
     #[test]
     fn test_verify_transaction() {
         let signer = InMemorySigner::from_random("test".parse().unwrap(), KeyType::ED25519);
-        let transaction = Transaction {
+        let transaction = Transaction::V0(TransactionV0 {
             signer_id: "test".parse().unwrap(),
             public_key: signer.public_key(),
             nonce: 0,
             receiver_id: "test".parse().unwrap(),
             block_hash: Default::default(),
             actions: vec![],
-        }
+        })
         .sign(&signer);
         let wrong_public_key = PublicKey::from_seed(KeyType::ED25519, "wrong");
         let valid_keys = vec![signer.public_key(), wrong_public_key.clone()];
@@ -482,7 +930,7 @@ mod tests {
     #[test]
     fn test_serialize_transaction() {
         let public_key: PublicKey = "22skMptHjFWNyuEWY22ftn2AbLPSYpmYwGJRGwpNHbTV".parse().unwrap();
-        let transaction = Transaction {
+        let transaction = Transaction::V0(TransactionV0 {
             signer_id: "test.near".parse().unwrap(),
             public_key: public_key.clone(),
             nonce: 1,
@@ -507,6 +955,7 @@ mod tests {
                             allowance: None,
                             receiver_id: "zzz".parse().unwrap(),
                             method_names: vec!["www".to_string()],
+                            method_allowances: Default::default(),
                         }),
                     },
                 }),
@@ -515,7 +964,7 @@ mod tests {
                     beneficiary_id: "123".parse().unwrap(),
                 }),
             ],
-        };
+        });
         let signed_tx = SignedTransaction::new(Signature::empty(KeyType::ED25519), transaction);
         let new_signed_tx =
             SignedTransaction::try_from_slice(&signed_tx.try_to_vec().unwrap()).unwrap();
@@ -526,6 +975,174 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_from_borsh_bytes() {
+        let signer = InMemorySigner::from_seed("test.near".parse().unwrap(), KeyType::ED25519, "a");
+        let signed_tx = SignedTransaction::send_money(
+            1,
+            "test.near".parse().unwrap(),
+            "123".parse().unwrap(),
+            &signer,
+            1_000,
+            CryptoHash::default(),
+        );
+        let bytes = signed_tx.try_to_vec().unwrap();
+
+        let decoded_tx = SignedTransaction::from_borsh_bytes(&bytes).unwrap();
+        assert_eq!(decoded_tx, signed_tx);
+        assert_eq!(decoded_tx.get_size(), signed_tx.get_size());
+        assert_eq!(decoded_tx.try_to_vec().unwrap(), bytes);
+        assert!(verify_transaction_signature(&decoded_tx, &[signer.public_key()]));
+
+        let mut trailing = bytes.clone();
+        trailing.push(0);
+        assert!(SignedTransaction::from_borsh_bytes(&trailing).is_err());
+        assert!(SignedTransaction::from_borsh_bytes(&bytes[..bytes.len() - 1]).is_err());
+
+        // Modified transactions are serialized with their new content, even
+        // when they were decoded from bytes.
+        let mut modified_tx = decoded_tx;
+        modified_tx.signature = Signature::empty(KeyType::ED25519);
+        let modified_bytes = modified_tx.try_to_vec().unwrap();
+        assert_ne!(modified_bytes, bytes);
+        assert_eq!(SignedTransaction::try_from_slice(&modified_bytes).unwrap(), modified_tx);
+    }
+
+    #[test]
+    fn test_from_borsh_bytes_bad_tag() {
+        let signer = InMemorySigner::from_seed("test.near".parse().unwrap(), KeyType::ED25519, "a");
+        let signed_tx = SignedTransaction::send_money(
+            1,
+            "test.near".parse().unwrap(),
+            "123".parse().unwrap(),
+            &signer,
+            1_000,
+            CryptoHash::default(),
+        );
+        let bytes = signed_tx.try_to_vec().unwrap();
+        let size = signed_tx.get_size() as usize;
+
+        // The transaction ends with the tag of its only action and the
+        // deposit of the transfer, the signature starts with its key type.
+        let action_tag = size - 1 - std::mem::size_of::<Balance>();
+        let signature_tag = size;
+        for tag_index in [action_tag, signature_tag] {
+            let mut bad_tag = bytes.clone();
+            bad_tag[tag_index] = 0xff;
+            assert!(SignedTransaction::from_borsh_bytes(&bad_tag).is_err());
+        }
+    }
+
+    /// The hash is computed from the received bytes, so transactions must
+    /// not decode from a non-canonical encoding.
+    #[test]
+    #[cfg(feature = "protocol_feature_function_call_method_allowances")]
+    fn test_from_borsh_bytes_non_canonical() {
+        let signer = InMemorySigner::from_seed("test.near".parse().unwrap(), KeyType::ED25519, "a");
+        let permission = FunctionCallPermission {
+            allowance: None,
+            receiver_id: "123".to_string(),
+            method_names: vec![],
+            method_allowances: [("bar".to_string(), 1), ("foo".to_string(), 2)]
+                .into_iter()
+                .collect(),
+        };
+        let signed_tx = SignedTransaction::from_actions(
+            1,
+            "test.near".parse().unwrap(),
+            "test.near".parse().unwrap(),
+            &signer,
+            vec![Action::AddKey(AddKeyAction {
+                public_key: signer.public_key(),
+                access_key: AccessKey {
+                    nonce: 0,
+                    permission: AccessKeyPermission::FunctionCall(permission),
+                },
+            })],
+            CryptoHash::default(),
+        );
+        let bytes = signed_tx.try_to_vec().unwrap();
+        assert_eq!(SignedTransaction::from_borsh_bytes(&bytes).unwrap(), signed_tx);
+
+        // Swap the encoded method allowances.
+        let entry = |method_name: &str, allowance: Balance| {
+            let mut entry = method_name.to_string().try_to_vec().unwrap();
+            entry.extend(allowance.to_le_bytes());
+            entry
+        };
+        let sorted = [entry("bar", 1), entry("foo", 2)].concat();
+        let unsorted = [entry("foo", 2), entry("bar", 1)].concat();
+        let start = bytes.windows(sorted.len()).position(|window| window == sorted).unwrap();
+        let mut non_canonical = bytes.clone();
+        non_canonical[start..start + sorted.len()].copy_from_slice(&unsorted);
+        assert!(SignedTransaction::from_borsh_bytes(&non_canonical).is_err());
+    }
+
+    #[test]
+    #[cfg(feature = "protocol_feature_transaction_priority_fee")]
+    fn test_serialize_transaction_v1() {
+        let signer = InMemorySigner::from_seed("test.near".parse().unwrap(), KeyType::ED25519, "a");
+        let transaction = TransactionV1 {
+            signer_id: "test.near".parse().unwrap(),
+            public_key: signer.public_key(),
+            nonce: 1,
+            receiver_id: "123".parse().unwrap(),
+            block_hash: Default::default(),
+            actions: vec![Action::Transfer(TransferAction { deposit: 123 })],
+            priority_fee: 1_000,
+        };
+        let bytes = Transaction::V1(transaction.clone()).try_to_vec().unwrap();
+        assert_eq!(bytes[0], TRANSACTION_V1_TAG);
+        assert_eq!(&bytes[1..], transaction.try_to_vec().unwrap());
+
+        let signed_tx = Transaction::V1(transaction).sign(&signer);
+        let new_signed_tx =
+            SignedTransaction::try_from_slice(&signed_tx.try_to_vec().unwrap()).unwrap();
+        assert_eq!(new_signed_tx, signed_tx);
+        assert_eq!(new_signed_tx.transaction.priority_fee(), Some(1_000));
+        assert!(verify_transaction_signature(&new_signed_tx, &[signer.public_key()]));
+
+        let json = serde_json::to_string(&signed_tx).unwrap();
+        let new_signed_tx: SignedTransaction = serde_json::from_str(&json).unwrap();
+        assert_eq!(new_signed_tx.transaction, signed_tx.transaction);
+    }
+
+    #[test]
+    fn test_validate_transaction() {
+        let signer = InMemorySigner::from_random("test".parse().unwrap(), KeyType::ED25519);
+        let transaction = Transaction::V0(TransactionV0 {
+            signer_id: "test".parse().unwrap(),
+            public_key: signer.public_key(),
+            nonce: 0,
+            receiver_id: "test".parse().unwrap(),
+            block_hash: Default::default(),
+            actions: vec![Action::Transfer(TransferAction { deposit: 1 }); 2],
+        });
+        let signed_tx = transaction.clone().sign(&signer);
+        let mut limit_config = VMLimitConfig::test();
+        assert_eq!(transaction.validate(&limit_config), Ok(()));
+        assert_eq!(signed_tx.validate(&limit_config), Ok(()));
+
+        limit_config.max_actions_per_receipt = 1;
+        let expected = Err(InvalidTxError::ActionsValidation(
+            ActionsValidationError::TotalNumberOfActionsExceeded {
+                total_number_of_actions: 2,
+                limit: 1,
+            },
+        ));
+        assert_eq!(transaction.validate(&limit_config), expected);
+        assert_eq!(signed_tx.validate(&limit_config), expected);
+
+        let size = signed_tx.get_size();
+        limit_config.max_transaction_size = size - 1;
+        let expected = Err(InvalidTxError::TransactionSizeExceeded {
+            size,
+            limit: limit_config.max_transaction_size,
+        });
+        assert_eq!(transaction.validate(&limit_config), expected);
+        assert_eq!(signed_tx.validate(&limit_config), expected);
+    }
+
     #[test]
     fn test_outcome_to_hashes() {
         let outcome = ExecutionOutcome {
@@ -549,4 +1166,113 @@ mod tests {
             outcome.to_hashes()
         );
     }
+
+    #[test]
+    fn test_outcome_to_hashes_with_events() {
+        let event = Event {
+            standard: "nep171".to_string(),
+            version: "1.0.0".to_string(),
+            event: "nft_mint".to_string(),
+            data: vec![1, 2, 3],
+        };
+        let outcome = ExecutionOutcome {
+            logs: vec!["123".to_string()],
+            metadata: ExecutionMetadata::V6 {
+                profile: Default::default(),
+                trie_nodes_count: TrieNodesCount { db_reads: 0, mem_reads: 0 },
+                error_code: None,
+                gas_refund: Default::default(),
+                events: vec![event.clone()],
+            },
+            ..Default::default()
+        };
+        let id = CryptoHash([42u8; 32]);
+        let outcome = ExecutionOutcomeWithId { id, outcome };
+        // Metadata is not part of the partial outcome, but every event is
+        // hashed after the logs.
+        let hashes = outcome.to_hashes();
+        assert_eq!(hashes.len(), 4);
+        assert_eq!(hashes[2], hash("123".as_bytes()));
+        assert_eq!(hashes[3], CryptoHash::hash_borsh(&event));
+
+        let view = crate::views::ExecutionOutcomeWithIdView {
+            proof: vec![],
+            block_hash: CryptoHash::default(),
+            id,
+            outcome: outcome.outcome.into(),
+        };
+        assert_eq!(view.to_hashes(), hashes);
+    }
+
+    #[test]
+    fn test_outcome_to_hashes_with_metadata_commitment() {
+        let metadata = |gas_refunded| ExecutionMetadata::V7 {
+            profile: Default::default(),
+            trie_nodes_count: TrieNodesCount { db_reads: 0, mem_reads: 0 },
+            error_code: None,
+            gas_refund: GasRefund { attached: 10, refunded: gas_refunded },
+            events: vec![],
+        };
+        let outcome = |metadata| ExecutionOutcomeWithId {
+            id: CryptoHash([42u8; 32]),
+            outcome: ExecutionOutcome { metadata, ..Default::default() },
+        };
+        let v6 = ExecutionMetadata::V6 {
+            profile: Default::default(),
+            trie_nodes_count: TrieNodesCount { db_reads: 0, mem_reads: 0 },
+            error_code: None,
+            gas_refund: GasRefund { attached: 10, refunded: 1 },
+            events: vec![],
+        };
+        // Metadata before V7 is not part of the proof.
+        assert_eq!(outcome(v6.clone()).to_hashes(), outcome(ExecutionMetadata::V1).to_hashes());
+        let hashes = outcome(metadata(1)).to_hashes();
+        assert_ne!(hashes, outcome(v6).to_hashes());
+        assert_ne!(hashes, outcome(metadata(2)).to_hashes());
+        assert_eq!(
+            hashes[1],
+            CryptoHash::hash_borsh(PartialExecutionOutcomeV2 {
+                outcome: PartialExecutionOutcome::from(&outcome(metadata(1)).outcome),
+                metadata_hash: metadata(1).proof_hash().unwrap(),
+            })
+        );
+
+        let view = crate::views::ExecutionOutcomeWithIdView {
+            proof: vec![],
+            block_hash: CryptoHash::default(),
+            id: CryptoHash([42u8; 32]),
+            outcome: outcome(metadata(1)).outcome.into(),
+        };
+        assert_eq!(view.outcome.metadata.version, 7);
+        assert_eq!(view.to_hashes(), hashes);
+    }
+
+    /// The metadata hash is part of the protocol, it must not change with
+    /// the compiled protocol features or new costs in the profile.
+    #[test]
+    fn test_metadata_proof_hash_unchanged() {
+        use near_primitives_core::config::{ActionCosts, ExtCosts};
+
+        let mut profile = ProfileData::new();
+        profile.add_action_cost(ActionCosts::deploy_contract_base, 100);
+        profile.add_action_cost(ActionCosts::deploy_contract_byte, 20);
+        profile.add_ext_cost(ExtCosts::base, 3);
+        profile.compute_wasm_instruction_cost(1_000);
+        let metadata = ExecutionMetadata::V7 {
+            profile,
+            trie_nodes_count: TrieNodesCount { db_reads: 1, mem_reads: 2 },
+            error_code: Some(1),
+            gas_refund: GasRefund { attached: 10, refunded: 1 },
+            events: vec![Event {
+                standard: "nep171".to_string(),
+                version: "1.0.0".to_string(),
+                event: "nft_mint".to_string(),
+                data: vec![1, 2, 3],
+            }],
+        };
+        assert_eq!(
+            metadata.proof_hash().unwrap().to_string(),
+            "GczuzK38jL5XwWywLdopiZgGVjbw4bkPcpcGMC9E3GHN"
+        );
+    }
 }
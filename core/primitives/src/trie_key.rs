@@ -35,6 +35,12 @@ pub(crate) mod col {
     pub const DELAYED_RECEIPT: u8 = 8;
     /// This column id is used when storing Key-Value data from a contract on an `account_id`.
     pub const CONTRACT_DATA: u8 = 9;
+    /// This column id is used when storing the indices of the delayed receipts
+    /// queue of a priority class, see `primitives::receipt::DelayedReceiptQueue`.
+    pub const PRIORITY_DELAYED_RECEIPT_INDICES: u8 = 10;
+    /// This column id is used when storing delayed receipts in the queue of a
+    /// priority class.
+    pub const PRIORITY_DELAYED_RECEIPT: u8 = 11;
     /// All columns
     pub const NON_DELAYED_RECEIPT_COLUMNS: [(u8, &str); 8] = [
         (ACCOUNT, "Account"),
@@ -83,6 +89,13 @@ pub enum TrieKey {
     /// Used to store a key-value record `Vec<u8>` within a contract deployed on a given `AccountId`
     /// and a given key.
     ContractData { account_id: AccountId, key: Vec<u8> },
+    /// Used to store indices of the delayed receipts queue of a priority class
+    /// (`primitives::receipt::DelayedReceiptQueue`).
+    /// NOTE: It is a singleton per shard and priority class.
+    PriorityDelayedReceiptIndices { priority_class: u8 },
+    /// Used to store a delayed receipt `primitives::receipt::Receipt` for a given index `u64`
+    /// in the delayed receipt queue of a priority class.
+    PriorityDelayedReceipt { priority_class: u8, index: u64 },
 }
 
 /// Provides `len` function.
@@ -140,6 +153,12 @@ impl TrieKey {
                     + ACCOUNT_DATA_SEPARATOR.len()
                     + key.len()
             }
+            TrieKey::PriorityDelayedReceiptIndices { .. } => {
+                col::PRIORITY_DELAYED_RECEIPT_INDICES.len() + size_of::<u8>()
+            }
+            TrieKey::PriorityDelayedReceipt { .. } => {
+                col::PRIORITY_DELAYED_RECEIPT.len() + size_of::<u8>() + size_of::<u64>()
+            }
         }
     }
 
@@ -199,6 +218,15 @@ impl TrieKey {
                 buf.push(ACCOUNT_DATA_SEPARATOR);
                 buf.extend(key);
             }
+            TrieKey::PriorityDelayedReceiptIndices { priority_class } => {
+                buf.push(col::PRIORITY_DELAYED_RECEIPT_INDICES);
+                buf.push(*priority_class);
+            }
+            TrieKey::PriorityDelayedReceipt { priority_class, index } => {
+                buf.push(col::PRIORITY_DELAYED_RECEIPT);
+                buf.push(*priority_class);
+                buf.extend(&index.to_le_bytes());
+            }
         };
         debug_assert_eq!(expected_len, buf.len() - start_len);
     }
@@ -337,6 +365,24 @@ pub mod trie_key_parsers {
         Ok(TrieKey::AccessKey { account_id, public_key })
     }
 
+    /// Returns the name of the `TrieKey` variant a raw key belongs to, based
+    /// on its column byte, or `None` if the column is unknown.
+    pub fn parse_trie_key_type_name(raw_key: &[u8]) -> Option<&'static str> {
+        let key_col = *raw_key.first()?;
+        match key_col {
+            // Delayed receipts are stored under the indices column followed by
+            // the receipt index, see `TrieKey::to_vec`.
+            col::DELAYED_RECEIPT_INDICES if raw_key.len() == 1 => Some("DelayedReceiptIndices"),
+            col::DELAYED_RECEIPT_INDICES | col::DELAYED_RECEIPT => Some("DelayedReceipt"),
+            col::PRIORITY_DELAYED_RECEIPT_INDICES => Some("PriorityDelayedReceiptIndices"),
+            col::PRIORITY_DELAYED_RECEIPT => Some("PriorityDelayedReceipt"),
+            _ => col::NON_DELAYED_RECEIPT_COLUMNS
+                .iter()
+                .find(|(col, _)| *col == key_col)
+                .map(|(_, col_name)| *col_name),
+        }
+    }
+
     #[allow(unused)]
     pub fn parse_account_id_from_raw_key(
         raw_key: &[u8],
@@ -623,5 +669,38 @@ mod tests {
         let key = TrieKey::DelayedReceipt { index: 0 };
         let raw_key = key.to_vec();
         assert!(trie_key_parsers::parse_account_id_from_raw_key(&raw_key).unwrap().is_none());
+        let key = TrieKey::PriorityDelayedReceiptIndices { priority_class: 1 };
+        let raw_key = key.to_vec();
+        assert_eq!(raw_key.len(), key.len());
+        assert!(trie_key_parsers::parse_account_id_from_raw_key(&raw_key).unwrap().is_none());
+        let key = TrieKey::PriorityDelayedReceipt { priority_class: 1, index: 0 };
+        let raw_key = key.to_vec();
+        assert_eq!(raw_key.len(), key.len());
+        assert!(trie_key_parsers::parse_account_id_from_raw_key(&raw_key).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_trie_key_type_name() {
+        let account_id: AccountId = "alice.near".parse().unwrap();
+        let cases = [
+            (TrieKey::Account { account_id: account_id.clone() }, "Account"),
+            (TrieKey::ContractCode { account_id: account_id.clone() }, "ContractCode"),
+            (TrieKey::ContractData { account_id, key: vec![1, 2] }, "ContractData"),
+            (TrieKey::DelayedReceiptIndices, "DelayedReceiptIndices"),
+            (TrieKey::DelayedReceipt { index: 7 }, "DelayedReceipt"),
+            (
+                TrieKey::PriorityDelayedReceiptIndices { priority_class: 2 },
+                "PriorityDelayedReceiptIndices",
+            ),
+            (
+                TrieKey::PriorityDelayedReceipt { priority_class: 2, index: 7 },
+                "PriorityDelayedReceipt",
+            ),
+        ];
+        for (key, name) in cases {
+            assert_eq!(trie_key_parsers::parse_trie_key_type_name(&key.to_vec()), Some(name));
+        }
+        assert_eq!(trie_key_parsers::parse_trie_key_type_name(&[]), None);
+        assert_eq!(trie_key_parsers::parse_trie_key_type_name(&[200]), None);
     }
 }
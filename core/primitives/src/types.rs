@@ -345,6 +345,8 @@ impl StateChanges {
                 TrieKey::PostponedReceipt { .. } => {}
                 TrieKey::DelayedReceiptIndices => {}
                 TrieKey::DelayedReceipt { .. } => {}
+                TrieKey::PriorityDelayedReceiptIndices { .. } => {}
+                TrieKey::PriorityDelayedReceipt { .. } => {}
             }
         }
 
@@ -947,7 +949,8 @@ pub enum TrieCacheMode {
 }
 
 /// Counts trie nodes reads during tx/receipt execution for proper storage costs charging.
-#[derive(Debug, PartialEq)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, Copy, PartialEq, Eq)]
 pub struct TrieNodesCount {
     /// Potentially expensive trie node reads which are served from disk in the worst case.
     pub db_reads: u64,
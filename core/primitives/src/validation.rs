@@ -0,0 +1,246 @@
+//! Checks of transactions and actions against the limits of `VMLimitConfig`
+//! which don't depend on the state or the protocol version.  They are shared
+//! by everything that has to reject invalid transactions, e.g. the runtime
+//! and the transaction pool.
+
+use crate::account::AccessKeyPermission;
+use crate::config::{AccountIdValidityRulesVersion, VMLimitConfig};
+use crate::errors::ActionsValidationError;
+#[cfg(feature = "protocol_feature_create_account_with_contract")]
+use crate::transaction::CreateAccountWithContractAction;
+use crate::transaction::{
+    Action, AddKeyAction, DeployContractAction, FunctionCallAction, StakeAction,
+};
+use crate::types::{AccountId, Gas};
+use near_crypto::key_conversion::is_valid_staking_key;
+
+/// Validates given actions:
+///
+/// - Checks limits if applicable.
+/// - Checks that the total number of actions doesn't exceed the limit.
+/// - Validates each individual action.
+/// - Checks that the total prepaid gas doesn't exceed the limit.
+pub fn validate_actions(
+    limit_config: &VMLimitConfig,
+    actions: &[Action],
+) -> Result<(), ActionsValidationError> {
+    if actions.len() as u64 > limit_config.max_actions_per_receipt {
+        return Err(ActionsValidationError::TotalNumberOfActionsExceeded {
+            total_number_of_actions: actions.len() as u64,
+            limit: limit_config.max_actions_per_receipt,
+        });
+    }
+
+    let mut iter = actions.iter().peekable();
+    while let Some(action) = iter.next() {
+        if let Action::DeleteAccount(_) = action {
+            if iter.peek().is_some() {
+                return Err(ActionsValidationError::DeleteActionMustBeFinal);
+            }
+        }
+        validate_action(limit_config, action)?;
+    }
+
+    let total_prepaid_gas = actions
+        .iter()
+        .try_fold(0, |acc: Gas, action| acc.checked_add(action.get_prepaid_gas()))
+        .ok_or(ActionsValidationError::IntegerOverflow)?;
+    if total_prepaid_gas > limit_config.max_total_prepaid_gas {
+        return Err(ActionsValidationError::TotalPrepaidGasExceeded {
+            total_prepaid_gas,
+            limit: limit_config.max_total_prepaid_gas,
+        });
+    }
+
+    Ok(())
+}
+
+/// Validates a single given action. Checks limits if applicable.
+pub fn validate_action(
+    limit_config: &VMLimitConfig,
+    action: &Action,
+) -> Result<(), ActionsValidationError> {
+    match action {
+        Action::CreateAccount(_) => Ok(()),
+        Action::DeployContract(a) => validate_deploy_contract_action(limit_config, a),
+        Action::FunctionCall(a) => validate_function_call_action(limit_config, a),
+        Action::Transfer(_) => Ok(()),
+        Action::Stake(a) => validate_stake_action(a),
+        Action::AddKey(a) => validate_add_key_action(limit_config, a),
+        Action::DeleteKey(_) => Ok(()),
+        Action::DeleteAccount(_) => Ok(()),
+        #[cfg(feature = "protocol_feature_create_account_with_contract")]
+        Action::CreateAccountWithContract(a) => {
+            validate_create_account_with_contract_action(limit_config, a)
+        }
+    }
+}
+
+/// Validates `DeployContractAction`. Checks that the given contract size doesn't exceed the limit.
+fn validate_deploy_contract_action(
+    limit_config: &VMLimitConfig,
+    action: &DeployContractAction,
+) -> Result<(), ActionsValidationError> {
+    if action.code.len() as u64 > limit_config.max_contract_size {
+        return Err(ActionsValidationError::ContractSizeExceeded {
+            size: action.code.len() as u64,
+            limit: limit_config.max_contract_size,
+        });
+    }
+
+    Ok(())
+}
+
+/// Validates `CreateAccountWithContractAction`. Checks that the contract size and the lengths of
+/// the keys and values of the initial state don't exceed the limits.
+#[cfg(feature = "protocol_feature_create_account_with_contract")]
+fn validate_create_account_with_contract_action(
+    limit_config: &VMLimitConfig,
+    action: &CreateAccountWithContractAction,
+) -> Result<(), ActionsValidationError> {
+    if action.code.len() as u64 > limit_config.max_contract_size {
+        return Err(ActionsValidationError::ContractSizeExceeded {
+            size: action.code.len() as u64,
+            limit: limit_config.max_contract_size,
+        });
+    }
+
+    for entry in &action.state {
+        if entry.key.len() as u64 > limit_config.max_length_storage_key {
+            return Err(ActionsValidationError::StateInitKeyLengthExceeded {
+                length: entry.key.len() as u64,
+                limit: limit_config.max_length_storage_key,
+            });
+        }
+        if entry.value.len() as u64 > limit_config.max_length_storage_value {
+            return Err(ActionsValidationError::StateInitValueLengthExceeded {
+                length: entry.value.len() as u64,
+                limit: limit_config.max_length_storage_value,
+            });
+        }
+    }
+
+    Ok(())
+}
+
+/// Validates `FunctionCallAction`. Checks that the method name length doesn't exceed the limit and
+/// the length of the arguments doesn't exceed the limit.
+fn validate_function_call_action(
+    limit_config: &VMLimitConfig,
+    action: &FunctionCallAction,
+) -> Result<(), ActionsValidationError> {
+    if action.gas == 0 {
+        return Err(ActionsValidationError::FunctionCallZeroAttachedGas);
+    }
+
+    if action.method_name.len() as u64 > limit_config.max_length_method_name {
+        return Err(ActionsValidationError::FunctionCallMethodNameLengthExceeded {
+            length: action.method_name.len() as u64,
+            limit: limit_config.max_length_method_name,
+        });
+    }
+
+    if action.args.len() as u64 > limit_config.max_arguments_length {
+        return Err(ActionsValidationError::FunctionCallArgumentsLengthExceeded {
+            length: action.args.len() as u64,
+            limit: limit_config.max_arguments_length,
+        });
+    }
+
+    Ok(())
+}
+
+/// Validates `StakeAction`. Checks that the `public_key` is a valid staking key.
+fn validate_stake_action(action: &StakeAction) -> Result<(), ActionsValidationError> {
+    if !is_valid_staking_key(&action.public_key) {
+        return Err(ActionsValidationError::UnsuitableStakingKey {
+            public_key: action.public_key.clone(),
+        });
+    }
+
+    Ok(())
+}
+
+/// Validates `AddKeyAction`. If the access key permission is `FunctionCall`, checks that the
+/// total number of bytes of the method names, including the names of methods with an allowance,
+/// doesn't exceed the limit and every method name length doesn't exceed the limit. Allowances can
+/// only be given for methods the key can call.
+fn validate_add_key_action(
+    limit_config: &VMLimitConfig,
+    action: &AddKeyAction,
+) -> Result<(), ActionsValidationError> {
+    if let AccessKeyPermission::FunctionCall(fc) = &action.access_key.permission {
+        // Check whether `receiver_id` is a valid account_id. Historically, we
+        // allowed arbitrary strings there!
+        match limit_config.account_id_validity_rules_version {
+            AccountIdValidityRulesVersion::V0 => (),
+            AccountIdValidityRulesVersion::V1 => {
+                if let Err(_) = fc.receiver_id.parse::<AccountId>() {
+                    return Err(ActionsValidationError::InvalidAccountId {
+                        account_id: truncate_string(&fc.receiver_id, AccountId::MAX_LEN * 2),
+                    });
+                }
+            }
+        }
+
+        for method_name in fc.method_allowances.keys() {
+            if !fc.method_names.is_empty() && !fc.method_names.contains(method_name) {
+                return Err(ActionsValidationError::AddKeyMethodAllowanceNotPermitted {
+                    method_name: method_name.clone(),
+                });
+            }
+        }
+
+        // Checking method name length limits
+        let mut total_number_of_bytes = 0;
+        for method_name in fc.method_names.iter().chain(fc.method_allowances.keys()) {
+            let length = method_name.len() as u64;
+            if length > limit_config.max_length_method_name {
+                return Err(ActionsValidationError::AddKeyMethodNameLengthExceeded {
+                    length,
+                    limit: limit_config.max_length_method_name,
+                });
+            }
+            // Adding terminating character to the total number of bytes
+            total_number_of_bytes += length + 1;
+        }
+        if total_number_of_bytes > limit_config.max_number_bytes_method_names {
+            return Err(ActionsValidationError::AddKeyMethodNamesNumberOfBytesExceeded {
+                total_number_of_bytes,
+                limit: limit_config.max_number_bytes_method_names,
+            });
+        }
+    }
+
+    Ok(())
+}
+
+fn truncate_string(s: &str, limit: usize) -> String {
+    for i in (0..=limit).rev() {
+        if let Some(s) = s.get(..i) {
+            return s.to_string();
+        }
+    }
+    unreachable!()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::truncate_string;
+
+    #[test]
+    fn test_truncate_string() {
+        fn check(input: &str, limit: usize, want: &str) {
+            let got = truncate_string(input, limit);
+            assert_eq!(got, want)
+        }
+        check("", 10, "");
+        check("hello", 0, "");
+        check("hello", 2, "he");
+        check("hello", 4, "hell");
+        check("hello", 5, "hello");
+        check("hello", 6, "hello");
+        check("hello", 10, "hello");
+        check("привет", 3, "п");
+    }
+}
@@ -60,7 +60,22 @@ pub fn is_implicit_account_creation_enabled(protocol_version: ProtocolVersion) -
 /// #[cfg(feature = "protocol_feature_evm")]
 /// EVM code
 ///
-#[derive(Hash, PartialEq, Eq, Clone, Copy, Debug)]
+/// Code outside of nearcore, e.g. indexers and tools, should query features with
+/// [`ProtocolFeature::enabled`] instead of the `checked_feature!` macro. The
+/// serialized name of a feature is the name of its variant and never changes.
+#[derive(
+    Hash,
+    PartialEq,
+    Eq,
+    Clone,
+    Copy,
+    Debug,
+    Serialize,
+    Deserialize,
+    strum::Display,
+    strum::EnumString,
+    strum::EnumIter,
+)]
 pub enum ProtocolFeature {
     // stable features
     RectifyInflation,
@@ -147,6 +162,65 @@ pub enum ProtocolFeature {
     FixContractLoadingCost,
     #[cfg(feature = "protocol_feature_ed25519_verify")]
     Ed25519Verify,
+    /// `ExecutionMetadata::V3` with the number of trie nodes read while
+    /// executing the receipt.
+    #[cfg(feature = "protocol_feature_trie_nodes_count_metadata")]
+    TrieNodesCountMetadata,
+    /// `sha256_batch` and `keccak256_batch` host functions hashing many values
+    /// in one call.
+    #[cfg(feature = "protocol_feature_hash_batch")]
+    HashBatch,
+    /// `block_gas_price` and `block_gas_limit` host functions.
+    #[cfg(feature = "protocol_feature_block_gas_info")]
+    BlockGasInfo,
+    /// `ExecutionMetadata::V4` with the code of the function call error, see
+    /// `FunctionCallError::code`.
+    #[cfg(feature = "protocol_feature_function_call_error_code")]
+    FunctionCallErrorCode,
+    /// `CreateAccountWithContract` action creating an account with a contract
+    /// and its initial state in one step.
+    #[cfg(feature = "protocol_feature_create_account_with_contract")]
+    CreateAccountWithContract,
+    /// `register_count`, `register_len_total` and `max_number_registers` host
+    /// functions to inspect the register usage of a contract.
+    #[cfg(feature = "protocol_feature_register_introspection")]
+    RegisterIntrospection,
+    /// Limits on the number of globals, the size of tables and the size of
+    /// custom sections of a contract. Deploying a contract that exceeds them
+    /// fails with `ActionErrorKind::ContractLimitExceeded`.
+    #[cfg(feature = "protocol_feature_limit_contract_sections")]
+    LimitContractSections,
+    /// `ExecutionMetadata::V5` with the attached and refunded gas of the
+    /// receipt.
+    #[cfg(feature = "protocol_feature_gas_refund_metadata")]
+    GasRefundMetadata,
+    /// `storage_iter_prefix_page` host function to read the storage of a
+    /// contract under a key prefix, one page at a time.
+    #[cfg(feature = "protocol_feature_storage_iter_prefix_page")]
+    StorageIterPrefixPage,
+    /// `ecrecover_batch` host function recovering the signers of many ECDSA
+    /// signatures in one call.
+    #[cfg(feature = "protocol_feature_ecrecover_batch")]
+    EcrecoverBatch,
+    /// `TransactionV1` with a priority fee paid for earlier inclusion.
+    #[cfg(feature = "protocol_feature_transaction_priority_fee")]
+    TransactionPriorityFee,
+    /// `emit_event` host function and typed events in the execution outcome,
+    /// included in the outcome proofs.
+    #[cfg(feature = "protocol_feature_structured_events")]
+    StructuredEvents,
+    /// Execution outcomes commit to the hash of their metadata, so that the
+    /// gas profile is covered by the outcome proofs.
+    #[cfg(feature = "protocol_feature_outcome_metadata_commitment")]
+    OutcomeMetadataCommitment,
+    /// Action receipts inherit the priority fee of their transaction and
+    /// delayed receipts are processed in the order of their priority.
+    #[cfg(feature = "protocol_feature_receipt_priority")]
+    ReceiptPriority,
+    /// Function call access keys can limit the balance spent on individual
+    /// methods, see `FunctionCallPermission::method_allowances`.
+    #[cfg(feature = "protocol_feature_function_call_method_allowances")]
+    FunctionCallMethodAllowances,
     #[cfg(feature = "protocol_feature_reject_blocks_with_outdated_protocol_version")]
     RejectBlocksWithOutdatedProtocolVersions,
     #[cfg(feature = "shardnet")]
@@ -166,7 +240,7 @@ const STABLE_PROTOCOL_VERSION: ProtocolVersion = 57;
 /// Largest protocol version supported by the current binary.
 pub const PROTOCOL_VERSION: ProtocolVersion = if cfg!(feature = "nightly_protocol") {
     // On nightly, pick big enough version to support all features.
-    132
+    147
 } else if cfg!(feature = "shardnet") {
     102
 } else {
@@ -252,21 +326,66 @@ impl ProtocolFeature {
                     132
                 }
             }
+            #[cfg(feature = "protocol_feature_trie_nodes_count_metadata")]
+            ProtocolFeature::TrieNodesCountMetadata => 133,
+            #[cfg(feature = "protocol_feature_hash_batch")]
+            ProtocolFeature::HashBatch => 134,
+            #[cfg(feature = "protocol_feature_block_gas_info")]
+            ProtocolFeature::BlockGasInfo => 135,
+            #[cfg(feature = "protocol_feature_function_call_error_code")]
+            ProtocolFeature::FunctionCallErrorCode => 136,
+            #[cfg(feature = "protocol_feature_create_account_with_contract")]
+            ProtocolFeature::CreateAccountWithContract => 137,
+            #[cfg(feature = "protocol_feature_register_introspection")]
+            ProtocolFeature::RegisterIntrospection => 138,
+            #[cfg(feature = "protocol_feature_limit_contract_sections")]
+            ProtocolFeature::LimitContractSections => 139,
+            #[cfg(feature = "protocol_feature_gas_refund_metadata")]
+            ProtocolFeature::GasRefundMetadata => 140,
+            #[cfg(feature = "protocol_feature_storage_iter_prefix_page")]
+            ProtocolFeature::StorageIterPrefixPage => 141,
+            #[cfg(feature = "protocol_feature_ecrecover_batch")]
+            ProtocolFeature::EcrecoverBatch => 142,
+            #[cfg(feature = "protocol_feature_transaction_priority_fee")]
+            ProtocolFeature::TransactionPriorityFee => 143,
+            #[cfg(feature = "protocol_feature_structured_events")]
+            ProtocolFeature::StructuredEvents => 144,
+            #[cfg(feature = "protocol_feature_outcome_metadata_commitment")]
+            ProtocolFeature::OutcomeMetadataCommitment => 145,
+            #[cfg(feature = "protocol_feature_receipt_priority")]
+            ProtocolFeature::ReceiptPriority => 146,
+            #[cfg(feature = "protocol_feature_function_call_method_allowances")]
+            ProtocolFeature::FunctionCallMethodAllowances => 147,
             #[cfg(feature = "shardnet")]
             ProtocolFeature::ShardnetShardLayoutUpgrade => 102,
         }
     }
+
+    /// Whether the feature is enabled in the given protocol version.
+    ///
+    /// Features behind a cargo feature flag only exist if the flag is enabled,
+    /// so this never reports a feature the binary does not support.
+    pub const fn enabled(self, protocol_version: ProtocolVersion) -> bool {
+        self.protocol_version() <= protocol_version
+    }
+
+    /// All features supported by this binary which are enabled in the given
+    /// protocol version.
+    pub fn enabled_features(protocol_version: ProtocolVersion) -> Vec<ProtocolFeature> {
+        use strum::IntoEnumIterator;
+        Self::iter().filter(|feature| feature.enabled(protocol_version)).collect()
+    }
 }
 
 #[macro_export]
 macro_rules! checked_feature {
     ("stable", $feature:ident, $current_protocol_version:expr) => {{
-        $crate::version::ProtocolFeature::$feature.protocol_version() <= $current_protocol_version
+        $crate::version::ProtocolFeature::$feature.enabled($current_protocol_version)
     }};
     ($feature_name:tt, $feature:ident, $current_protocol_version:expr) => {{
         #[cfg(feature = $feature_name)]
-        let is_feature_enabled = $crate::version::ProtocolFeature::$feature.protocol_version()
-            <= $current_protocol_version;
+        let is_feature_enabled =
+            $crate::version::ProtocolFeature::$feature.enabled($current_protocol_version);
         #[cfg(not(feature = $feature_name))]
         let is_feature_enabled = {
             // Workaround unused variable warning
@@ -298,3 +417,32 @@ macro_rules! checked_feature {
         }
     }};
 }
+
+#[cfg(test)]
+mod tests {
+    use super::ProtocolFeature;
+    use std::str::FromStr;
+
+    #[test]
+    fn test_protocol_feature_enabled() {
+        let feature = ProtocolFeature::AltBn128;
+        assert!(!feature.enabled(feature.protocol_version() - 1));
+        assert!(feature.enabled(feature.protocol_version()));
+        assert!(ProtocolFeature::enabled_features(feature.protocol_version()).contains(&feature));
+        assert!(
+            !ProtocolFeature::enabled_features(feature.protocol_version() - 1).contains(&feature)
+        );
+    }
+
+    #[test]
+    fn test_protocol_feature_serialization() {
+        let feature = ProtocolFeature::ChunkNodesCache;
+        assert_eq!(serde_json::to_string(&feature).unwrap(), "\"ChunkNodesCache\"");
+        assert_eq!(
+            serde_json::from_str::<ProtocolFeature>("\"ChunkNodesCache\"").unwrap(),
+            feature
+        );
+        assert_eq!(feature.to_string(), "ChunkNodesCache");
+        assert_eq!(ProtocolFeature::from_str("ChunkNodesCache").unwrap(), feature);
+    }
+}
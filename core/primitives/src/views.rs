@@ -3,7 +3,7 @@
 //! These types should only change when we cannot avoid this. Thus, when the counterpart internal
 //! type gets changed, the view should preserve the old shape and only re-map the necessary bits
 //! from the source structure in the relevant `From<SourceStruct>` impl.
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::fmt;
 use std::ops::Range;
 use std::sync::Arc;
@@ -30,22 +30,25 @@ use crate::merkle::{combine_hash, MerklePath};
 use crate::network::PeerId;
 use crate::profile::Cost;
 use crate::receipt::{ActionReceipt, DataReceipt, DataReceiver, Receipt, ReceiptEnum};
-use crate::serialize::{base64_format, dec_format, option_base64_format};
+use crate::serialize::{base64_format, dec_format, dec_format_map, option_base64_format};
 use crate::sharding::{
     ChunkHash, ShardChunk, ShardChunkHeader, ShardChunkHeaderInner, ShardChunkHeaderInnerV2,
     ShardChunkHeaderV3,
 };
 use crate::transaction::{
     Action, AddKeyAction, CreateAccountAction, DeleteAccountAction, DeleteKeyAction,
-    DeployContractAction, ExecutionMetadata, ExecutionOutcome, ExecutionOutcomeWithIdAndProof,
-    ExecutionStatus, FunctionCallAction, PartialExecutionOutcome, PartialExecutionStatus,
-    SignedTransaction, StakeAction, TransferAction,
+    DeployContractAction, Event, ExecutionMetadata, ExecutionOutcome,
+    ExecutionOutcomeWithIdAndProof, ExecutionStatus, FunctionCallAction, GasRefund,
+    PartialExecutionOutcome, PartialExecutionOutcomeV2, PartialExecutionStatus, SignedTransaction,
+    StakeAction, TransferAction,
 };
+#[cfg(feature = "protocol_feature_create_account_with_contract")]
+use crate::transaction::{ContractStateEntry, CreateAccountWithContractAction};
 use crate::types::{
     AccountId, AccountWithPublicKey, Balance, BlockHeight, CompiledContractCache, EpochHeight,
     EpochId, FunctionArgs, Gas, Nonce, NumBlocks, ShardId, StateChangeCause, StateChangeKind,
     StateChangeValue, StateChangeWithCause, StateChangesRequest, StateRoot, StorageUsage, StoreKey,
-    StoreValue, ValidatorKickoutReason,
+    StoreValue, TrieNodesCount, ValidatorKickoutReason,
 };
 use crate::version::{ProtocolVersion, Version};
 use validator_stake_view::ValidatorStakeView;
@@ -144,6 +147,8 @@ pub enum AccessKeyPermissionView {
         allowance: Option<Balance>,
         receiver_id: String,
         method_names: Vec<String>,
+        #[serde(with = "dec_format_map", default, skip_serializing_if = "BTreeMap::is_empty")]
+        method_allowances: BTreeMap<String, Balance>,
     },
     FullAccess,
 }
@@ -155,6 +160,7 @@ impl From<AccessKeyPermission> for AccessKeyPermissionView {
                 allowance: func_call.allowance,
                 receiver_id: func_call.receiver_id,
                 method_names: func_call.method_names,
+                method_allowances: func_call.method_allowances,
             },
             AccessKeyPermission::FullAccess => AccessKeyPermissionView::FullAccess,
         }
@@ -164,13 +170,17 @@ impl From<AccessKeyPermission> for AccessKeyPermissionView {
 impl From<AccessKeyPermissionView> for AccessKeyPermission {
     fn from(view: AccessKeyPermissionView) -> Self {
         match view {
-            AccessKeyPermissionView::FunctionCall { allowance, receiver_id, method_names } => {
-                AccessKeyPermission::FunctionCall(FunctionCallPermission {
-                    allowance,
-                    receiver_id,
-                    method_names,
-                })
-            }
+            AccessKeyPermissionView::FunctionCall {
+                allowance,
+                receiver_id,
+                method_names,
+                method_allowances,
+            } => AccessKeyPermission::FunctionCall(FunctionCallPermission {
+                allowance,
+                receiver_id,
+                method_names,
+                method_allowances,
+            }),
             AccessKeyPermissionView::FullAccess => AccessKeyPermission::FullAccess,
         }
     }
@@ -303,6 +313,10 @@ fn is_false(v: &bool) -> bool {
     !*v
 }
 
+fn is_zero(v: &Balance) -> bool {
+    *v == 0
+}
+
 #[derive(Debug, PartialEq, Eq, Clone)]
 pub struct QueryResponse {
     pub kind: QueryResponseKind,
@@ -1051,6 +1065,12 @@ pub enum ActionView {
     DeleteAccount {
         beneficiary_id: AccountId,
     },
+    #[cfg(feature = "protocol_feature_create_account_with_contract")]
+    CreateAccountWithContract {
+        #[serde(with = "base64_format")]
+        code: Vec<u8>,
+        state: Vec<ContractStateEntry>,
+    },
 }
 
 impl From<Action> for ActionView {
@@ -1079,6 +1099,11 @@ impl From<Action> for ActionView {
             Action::DeleteAccount(action) => {
                 ActionView::DeleteAccount { beneficiary_id: action.beneficiary_id }
             }
+            #[cfg(feature = "protocol_feature_create_account_with_contract")]
+            Action::CreateAccountWithContract(action) => {
+                let code = hash(&action.code).as_ref().to_vec();
+                ActionView::CreateAccountWithContract { code, state: action.state }
+            }
         }
     }
 }
@@ -1108,6 +1133,10 @@ impl TryFrom<ActionView> for Action {
             ActionView::DeleteAccount { beneficiary_id } => {
                 Action::DeleteAccount(DeleteAccountAction { beneficiary_id })
             }
+            #[cfg(feature = "protocol_feature_create_account_with_contract")]
+            ActionView::CreateAccountWithContract { code, state } => {
+                Action::CreateAccountWithContract(CreateAccountWithContractAction { code, state })
+            }
         })
     }
 }
@@ -1119,6 +1148,9 @@ pub struct SignedTransactionView {
     pub nonce: Nonce,
     pub receiver_id: AccountId,
     pub actions: Vec<ActionView>,
+    /// Only set for transactions which pay a priority fee.
+    #[serde(default, skip_serializing_if = "Option::is_none", with = "dec_format")]
+    pub priority_fee: Option<Balance>,
     pub signature: Signature,
     pub hash: CryptoHash,
 }
@@ -1126,17 +1158,14 @@ pub struct SignedTransactionView {
 impl From<SignedTransaction> for SignedTransactionView {
     fn from(signed_tx: SignedTransaction) -> Self {
         let hash = signed_tx.get_hash();
+        let transaction = signed_tx.transaction;
         SignedTransactionView {
-            signer_id: signed_tx.transaction.signer_id,
-            public_key: signed_tx.transaction.public_key,
-            nonce: signed_tx.transaction.nonce,
-            receiver_id: signed_tx.transaction.receiver_id,
-            actions: signed_tx
-                .transaction
-                .actions
-                .into_iter()
-                .map(|action| action.into())
-                .collect(),
+            signer_id: transaction.signer_id().clone(),
+            public_key: transaction.public_key().clone(),
+            nonce: transaction.nonce(),
+            receiver_id: transaction.receiver_id().clone(),
+            priority_fee: transaction.priority_fee(),
+            actions: transaction.take_actions().into_iter().map(|action| action.into()).collect(),
             signature: signed_tx.signature,
             hash,
         }
@@ -1234,6 +1263,78 @@ pub struct CostGasUsed {
 pub struct ExecutionMetadataView {
     pub version: u32,
     pub gas_profile: Option<Vec<CostGasUsed>>,
+    /// Trie nodes read while executing the receipt, present since version 3.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub trie_nodes_count: Option<TrieNodesCountView>,
+    /// Numeric code of the function call error the receipt failed with,
+    /// present since version 4. The thousands give the category of the error,
+    /// see `near_vm_errors::FunctionCallErrorCategory`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error_code: Option<u32>,
+    /// Gas attached to the receipt and the part of it that was refunded,
+    /// present since version 5.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub gas_refund: Option<GasRefundView>,
+    /// Events emitted by the contract, present since version 6.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub events: Option<Vec<EventView>>,
+    /// Hash of the metadata committed to in the outcome proof, present since
+    /// version 7. It is passed on as computed by the node: the gas profile of
+    /// the view is lossy, so the hash can't be recomputed from the view and
+    /// the other metadata fields are not verified against it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hash: Option<CryptoHash>,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, PartialEq, Clone, Eq, Debug)]
+pub struct TrieNodesCountView {
+    /// Trie nodes read from the database or the shard cache.
+    pub db_reads: u64,
+    /// Trie nodes read from the chunk cache.
+    pub mem_reads: u64,
+}
+
+impl From<TrieNodesCount> for TrieNodesCountView {
+    fn from(count: TrieNodesCount) -> Self {
+        Self { db_reads: count.db_reads, mem_reads: count.mem_reads }
+    }
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, PartialEq, Clone, Eq, Debug)]
+pub struct GasRefundView {
+    /// Gas prepaid for the function calls of the receipt plus the execution
+    /// fees of its actions.
+    #[serde(with = "dec_format")]
+    pub attached: Gas,
+    /// Gas refunded to the signer.
+    #[serde(with = "dec_format")]
+    pub refunded: Gas,
+}
+
+impl From<GasRefund> for GasRefundView {
+    fn from(refund: GasRefund) -> Self {
+        Self { attached: refund.attached, refunded: refund.refunded }
+    }
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, PartialEq, Clone, Eq, Debug)]
+pub struct EventView {
+    pub standard: String,
+    pub version: String,
+    pub event: String,
+    #[serde(with = "base64_format")]
+    pub data: Vec<u8>,
+}
+
+impl From<Event> for EventView {
+    fn from(event: Event) -> Self {
+        Self {
+            standard: event.standard,
+            version: event.version,
+            event: event.event,
+            data: event.data,
+        }
+    }
 }
 
 impl Default for ExecutionMetadataView {
@@ -1244,73 +1345,128 @@ impl Default for ExecutionMetadataView {
 
 impl From<ExecutionMetadata> for ExecutionMetadataView {
     fn from(metadata: ExecutionMetadata) -> Self {
-        let gas_profile = match metadata {
-            ExecutionMetadata::V1 => None,
-            ExecutionMetadata::V2(profile_data) => {
-                let mut costs: Vec<_> =
-                    Cost::iter()
-                        .filter(|&cost| profile_data[cost] > 0)
-                        .map(|cost| CostGasUsed {
-                            cost_category: match cost {
-                                Cost::ActionCost { .. } => "ACTION_COST",
-                                Cost::ExtCost { .. } => "WASM_HOST_COST",
-                                Cost::WasmInstruction => "WASM_HOST_COST",
+        let hash = metadata.proof_hash();
+        let (version, profile_data, trie_nodes_count, error_code, gas_refund, events) =
+            match metadata {
+                ExecutionMetadata::V1 => (1, None, None, None, None, None),
+                ExecutionMetadata::V2(profile_data) => {
+                    (1, Some(profile_data), None, None, None, None)
+                }
+                ExecutionMetadata::V3 { profile, trie_nodes_count } => {
+                    (3, Some(profile), Some(trie_nodes_count.into()), None, None, None)
+                }
+                ExecutionMetadata::V4 { profile, trie_nodes_count, error_code } => {
+                    (4, Some(profile), Some(trie_nodes_count.into()), error_code, None, None)
+                }
+                ExecutionMetadata::V5 { profile, trie_nodes_count, error_code, gas_refund } => (
+                    5,
+                    Some(profile),
+                    Some(trie_nodes_count.into()),
+                    error_code,
+                    Some(gas_refund.into()),
+                    None,
+                ),
+                ExecutionMetadata::V6 {
+                    profile,
+                    trie_nodes_count,
+                    error_code,
+                    gas_refund,
+                    events,
+                } => (
+                    6,
+                    Some(profile),
+                    Some(trie_nodes_count.into()),
+                    error_code,
+                    Some(gas_refund.into()),
+                    Some(events.into_iter().map(Into::into).collect()),
+                ),
+                ExecutionMetadata::V7 {
+                    profile,
+                    trie_nodes_count,
+                    error_code,
+                    gas_refund,
+                    events,
+                } => (
+                    7,
+                    Some(profile),
+                    Some(trie_nodes_count.into()),
+                    error_code,
+                    Some(gas_refund.into()),
+                    Some(events.into_iter().map(Into::into).collect()),
+                ),
+            };
+        let gas_profile = profile_data.map(|profile_data| {
+            let mut costs: Vec<_> =
+                Cost::iter()
+                    .filter(|&cost| profile_data[cost] > 0)
+                    .map(|cost| CostGasUsed {
+                        cost_category: match cost {
+                            Cost::ActionCost { .. } => "ACTION_COST",
+                            Cost::ExtCost { .. } => "WASM_HOST_COST",
+                            Cost::WasmInstruction => "WASM_HOST_COST",
+                        }
+                        .to_string(),
+                        cost: match cost {
+                            // preserve old behavior that conflated some action
+                            // costs for profile (duplicates are removed afterwards)
+                            Cost::ActionCost {
+                                action_cost_kind:
+                                    ActionCosts::deploy_contract_base
+                                    | ActionCosts::deploy_contract_byte,
+                            } => "DEPLOY_CONTRACT".to_owned(),
+                            Cost::ActionCost {
+                                action_cost_kind:
+                                    ActionCosts::function_call_base
+                                    | ActionCosts::function_call_byte,
+                            } => "FUNCTION_CALL".to_owned(),
+                            Cost::ActionCost {
+                                action_cost_kind:
+                                    ActionCosts::add_full_access_key
+                                    | ActionCosts::add_function_call_key_base
+                                    | ActionCosts::add_function_call_key_byte,
+                            } => "ADD_KEY".to_owned(),
+                            Cost::ActionCost {
+                                action_cost_kind:
+                                    ActionCosts::new_action_receipt
+                                    | ActionCosts::new_data_receipt_base,
+                            } => "NEW_RECEIPT".to_owned(),
+                            // other costs have always been mapped one-to-one
+                            Cost::ActionCost { action_cost_kind: action_cost } => {
+                                format!("{:?}", action_cost).to_ascii_uppercase()
                             }
-                            .to_string(),
-                            cost: match cost {
-                                // preserve old behavior that conflated some action
-                                // costs for profile (duplicates are removed afterwards)
-                                Cost::ActionCost {
-                                    action_cost_kind:
-                                        ActionCosts::deploy_contract_base
-                                        | ActionCosts::deploy_contract_byte,
-                                } => "DEPLOY_CONTRACT".to_owned(),
-                                Cost::ActionCost {
-                                    action_cost_kind:
-                                        ActionCosts::function_call_base
-                                        | ActionCosts::function_call_byte,
-                                } => "FUNCTION_CALL".to_owned(),
-                                Cost::ActionCost {
-                                    action_cost_kind:
-                                        ActionCosts::add_full_access_key
-                                        | ActionCosts::add_function_call_key_base
-                                        | ActionCosts::add_function_call_key_byte,
-                                } => "ADD_KEY".to_owned(),
-                                Cost::ActionCost {
-                                    action_cost_kind:
-                                        ActionCosts::new_action_receipt
-                                        | ActionCosts::new_data_receipt_base,
-                                } => "NEW_RECEIPT".to_owned(),
-                                // other costs have always been mapped one-to-one
-                                Cost::ActionCost { action_cost_kind: action_cost } => {
-                                    format!("{:?}", action_cost).to_ascii_uppercase()
-                                }
-                                Cost::ExtCost { ext_cost_kind: ext_cost } => {
-                                    format!("{:?}", ext_cost).to_ascii_uppercase()
-                                }
-                                Cost::WasmInstruction => "WASM_INSTRUCTION".to_string(),
-                            },
-                            gas_used: profile_data[cost],
-                        })
-                        .collect();
-
-                // The order doesn't really matter, but the default one is just
-                // historical, which is especially unintuitive, so let's sort
-                // lexicographically.
-                //
-                // Can't `sort_by_key` here because lifetime inference in
-                // closures is limited.
-                costs.sort_by(|lhs, rhs| {
-                    lhs.cost_category.cmp(&rhs.cost_category).then(lhs.cost.cmp(&rhs.cost))
-                });
-
-                // need to remove duplicate entries due to cost conflation
-                costs.dedup();
-
-                Some(costs)
-            }
-        };
-        ExecutionMetadataView { version: 1, gas_profile }
+                            Cost::ExtCost { ext_cost_kind: ext_cost } => {
+                                format!("{:?}", ext_cost).to_ascii_uppercase()
+                            }
+                            Cost::WasmInstruction => "WASM_INSTRUCTION".to_string(),
+                        },
+                        gas_used: profile_data[cost],
+                    })
+                    .collect();
+
+            // The order doesn't really matter, but the default one is just
+            // historical, which is especially unintuitive, so let's sort
+            // lexicographically.
+            //
+            // Can't `sort_by_key` here because lifetime inference in
+            // closures is limited.
+            costs.sort_by(|lhs, rhs| {
+                lhs.cost_category.cmp(&rhs.cost_category).then(lhs.cost.cmp(&rhs.cost))
+            });
+
+            // need to remove duplicate entries due to cost conflation
+            costs.dedup();
+
+            costs
+        });
+        ExecutionMetadataView {
+            version,
+            gas_profile,
+            trie_nodes_count,
+            error_code,
+            gas_refund,
+            events,
+            hash,
+        }
     }
 }
 
@@ -1378,10 +1534,22 @@ impl From<ExecutionStatusView> for PartialExecutionStatus {
 impl ExecutionOutcomeView {
     // Same behavior as ExecutionOutcomeWithId's to_hashes.
     pub fn to_hashes(&self, id: CryptoHash) -> Vec<CryptoHash> {
-        let mut result = Vec::with_capacity(2 + self.logs.len());
+        let events = self.metadata.events.as_deref().unwrap_or_default();
+        let mut result = Vec::with_capacity(2 + self.logs.len() + events.len());
         result.push(id);
-        result.push(CryptoHash::hash_borsh(&PartialExecutionOutcome::from(self)));
+        let outcome = PartialExecutionOutcome::from(self);
+        // The gas profile of the view is lossy, so the metadata hash is taken
+        // from the view instead of recomputing it. A proof checked with these
+        // hashes covers the metadata hash, not the metadata of the view.
+        result.push(match self.metadata.hash {
+            Some(metadata_hash) => {
+                CryptoHash::hash_borsh(PartialExecutionOutcomeV2 { outcome, metadata_hash })
+            }
+            None => CryptoHash::hash_borsh(outcome),
+        });
         result.extend(self.logs.iter().map(|log| hash(log.as_bytes())));
+        // `EventView` has the same borsh serialization as `Event`.
+        result.extend(events.iter().map(CryptoHash::hash_borsh));
         result
     }
 }
@@ -1568,6 +1736,8 @@ pub enum ReceiptEnumView {
         output_data_receivers: Vec<DataReceiverView>,
         input_data_ids: Vec<CryptoHash>,
         actions: Vec<ActionView>,
+        #[serde(with = "dec_format", default, skip_serializing_if = "is_zero")]
+        priority: Balance,
     },
     Data {
         data_id: CryptoHash,
@@ -1601,6 +1771,7 @@ impl From<Receipt> for ReceiptView {
                         .map(Into::into)
                         .collect(),
                     actions: action_receipt.actions.into_iter().map(Into::into).collect(),
+                    priority: action_receipt.priority,
                 },
                 ReceiptEnum::Data(data_receipt) => {
                     ReceiptEnumView::Data { data_id: data_receipt.data_id, data: data_receipt.data }
@@ -1626,6 +1797,7 @@ impl TryFrom<ReceiptView> for Receipt {
                     output_data_receivers,
                     input_data_ids,
                     actions,
+                    priority,
                 } => ReceiptEnum::Action(ActionReceipt {
                     signer_id,
                     signer_public_key,
@@ -1642,6 +1814,7 @@ impl TryFrom<ReceiptView> for Receipt {
                         .into_iter()
                         .map(TryInto::try_into)
                         .collect::<Result<Vec<_>, _>>()?,
+                    priority,
                 }),
                 ReceiptEnumView::Data { data_id, data } => {
                     ReceiptEnum::Data(DataReceipt { data_id, data })
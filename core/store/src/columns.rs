@@ -237,11 +237,30 @@ pub enum DBCol {
     /// - *Rows*: BlockShardId (BlockHash || ShardId) - 40 bytes
     /// - *Column type*: StateChangesForSplitStates
     StateChangesForSplitStates,
+    /// `ShardUId` prefix under which the trie nodes and values of a shard are
+    /// stored in `DBCol::State`.  Shards without an entry use their own
+    /// `ShardUId`.  Set when a shard is split, so that the new shards can
+    /// share the storage of the parent instead of duplicating it.
+    /// - *Rows*: ShardUId
+    /// - *Column type*: ShardUId
+    StateShardUIdMapping,
     /// Transaction or receipt outcome, by outcome ID (transaction or receipt hash) and block
     /// hash. Multiple outcomes may be stored for the same outcome ID in case of forks.
     /// *Rows*: OutcomeId (CryptoHash) || BlockHash (CryptoHash)
     /// *Column type*: ExecutionOutcomeWithProof
     TransactionResultForBlock,
+    /// Hashes of the trie nodes most recently used by the shard cache of a
+    /// shard.  Saved periodically so that the cache can be warmed up after a
+    /// restart.
+    /// - *Rows*: ShardUId
+    /// - *Column type*: Vec<CryptoHash>, most recently used first
+    TrieCacheWarmup,
+    /// Transactions which were in the transaction pool when the node was
+    /// stopped.  Only written if `persist_tx_pool` is enabled and removed
+    /// again when the transactions are restored on startup.
+    /// - *Rows*: ShardId
+    /// - *Column type*: Vec<SignedTransaction>
+    TransactionPool,
     /// Flat state contents. Used to get `ValueRef` by trie key faster than doing a trie lookup.
     /// - *Rows*: trie key (Vec<u8>)
     /// - *Column type*: ValueRef
@@ -455,7 +474,10 @@ impl DBCol {
             DBCol::EpochValidatorInfo => &[DBKeyType::EpochId],
             DBCol::HeaderHashesByHeight => &[DBKeyType::BlockHeight],
             DBCol::StateChangesForSplitStates => &[DBKeyType::BlockHash, DBKeyType::ShardId],
+            DBCol::StateShardUIdMapping => &[DBKeyType::ShardUId],
             DBCol::TransactionResultForBlock => &[DBKeyType::OutcomeId, DBKeyType::BlockHash],
+            DBCol::TrieCacheWarmup => &[DBKeyType::ShardUId],
+            DBCol::TransactionPool => &[DBKeyType::ShardId],
             #[cfg(feature = "protocol_feature_flat_state")]
             DBCol::FlatState => &[DBKeyType::TrieKey],
             #[cfg(feature = "protocol_feature_flat_state")]
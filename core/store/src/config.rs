@@ -1,7 +1,12 @@
 use near_primitives::shard_layout::ShardUId;
+use std::time::Duration;
 use std::{collections::HashMap, iter::FromIterator};
 
-use crate::trie::DEFAULT_SHARD_CACHE_TOTAL_SIZE_LIMIT;
+use crate::trie::{
+    DEFAULT_CACHE_WARMUP_MAX_NODES, DEFAULT_CHUNK_CACHE_SIZE_LIMIT, DEFAULT_PREFETCH_MIN_HIT_RATE,
+    DEFAULT_SHARD_CACHE_TOTAL_SIZE_LIMIT, DEFAULT_VIEW_TRIE_LIMIT_CACHED_VALUE_SIZE,
+    TRIE_LIMIT_CACHED_VALUE_SIZE,
+};
 
 #[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
 #[serde(default)]
@@ -59,6 +64,45 @@ pub struct StoreConfig {
     /// This config option is temporary and will be removed once flat storage is implemented.
     pub sweat_prefetch_senders: Vec<String>,
 
+    /// Memory limit for prefetched values waiting to be consumed, per shard.
+    /// Default value: 200MiB.
+    /// Once the limit is reached, no new prefetch requests are started until
+    /// the main thread takes values out of the staging area.
+    pub prefetch_staging_max_memory: bytesize::ByteSize,
+
+    /// Number of IO threads prefetching data, per shard.
+    /// Default value: 8.
+    /// Because the storage driver is blocking, each thread has at most one
+    /// request in flight. Fast disks can serve more requests in parallel.
+    pub num_prefetch_threads: usize,
+
+    /// Share of prefetched values used by the main thread below which
+    /// prefetching is paused for a while, per shard.
+    /// Default value: 0.1.
+    /// Prefetching values that are never read only adds IO load, which
+    /// happens if the prefetched accounts are misconfigured.  Set to 0 to
+    /// never pause prefetching.
+    pub prefetch_min_hit_rate: f64,
+
+    /// Number of trie nodes per shard to load into the shard cache on startup.
+    /// Default value: 100000.
+    /// The hashes of the most recently used nodes are saved periodically
+    /// while the node runs and the nodes are read back before the first
+    /// chunks are applied.  Set to 0 to disable the warm-up.
+    pub trie_cache_warmup_max_nodes: usize,
+
+    /// Limit for the total size of the trie nodes and values cached while
+    /// applying a single chunk.
+    /// Default value: 128MiB.
+    /// Gas costs keep the chunk cache well below the default, so exceeding
+    /// the limit points at mischarged storage operations.  It is logged as an
+    /// error and fails the chunk application on nightly builds.
+    pub chunk_cache_size_limit: bytesize::ByteSize,
+
+    /// Detection of a degraded disk from the latency of database operations
+    /// and shedding of non-essential load while it lasts.
+    pub disk_latency: DiskLatencyConfig,
+
     /// Path where to create RocksDB checkpoints during database migrations or
     /// `false` to disable that feature.
     ///
@@ -101,6 +145,10 @@ pub enum MigrationSnapshot {
 pub enum Mode {
     /// Open an existing database in read-only mode.  Fail if it doesn’t exist.
     ReadOnly,
+    /// Open an existing database as a read-only RocksDB secondary instance.
+    /// Unlike [`Mode::ReadOnly`], the database can be kept up to date with a
+    /// node writing to it, see [`crate::Store::catch_up_with_primary`].
+    ReadOnlySecondary,
     /// Open an existing database in read-write mode.  Fail if it doesn’t exist.
     ReadWriteExisting,
     /// Open a database in read-write mode.  create if it doesn’t exist.
@@ -111,7 +159,7 @@ pub enum Mode {
 
 impl Mode {
     pub const fn read_only(self) -> bool {
-        matches!(self, Mode::ReadOnly)
+        matches!(self, Mode::ReadOnly | Mode::ReadOnlySecondary)
     }
     pub const fn read_write(self) -> bool {
         !self.read_only()
@@ -127,7 +175,7 @@ impl Mode {
     /// `None` if the mode requires creation of a new database.
     pub const fn but_cannot_create(self) -> Option<Self> {
         match self {
-            Self::ReadOnly | Self::ReadWriteExisting => Some(self),
+            Self::ReadOnly | Self::ReadOnlySecondary | Self::ReadWriteExisting => Some(self),
             Self::ReadWrite => Some(Self::ReadWriteExisting),
             Self::Create => None,
         }
@@ -201,8 +249,9 @@ impl Default for StoreConfig {
                     ShardUId { version: 1, shard_id: 3 },
                     3_000_000_000,
                 )]),
+                max_cached_value_size: TRIE_LIMIT_CACHED_VALUE_SIZE,
             },
-            view_trie_cache: TrieCacheConfig::default(),
+            view_trie_cache: TrieCacheConfig::default_view(),
 
             enable_receipt_prefetching: true,
             sweat_prefetch_receivers: vec![
@@ -213,6 +262,12 @@ impl Default for StoreConfig {
                 "oracle.sweat".to_owned(),
                 "sweat_the_oracle.testnet".to_owned(),
             ],
+            prefetch_staging_max_memory: bytesize::ByteSize::mib(200),
+            num_prefetch_threads: 8,
+            prefetch_min_hit_rate: DEFAULT_PREFETCH_MIN_HIT_RATE,
+            trie_cache_warmup_max_nodes: DEFAULT_CACHE_WARMUP_MAX_NODES,
+            chunk_cache_size_limit: bytesize::ByteSize::b(DEFAULT_CHUNK_CACHE_SIZE_LIMIT),
+            disk_latency: DiskLatencyConfig::default(),
 
             migration_snapshot: Default::default(),
 
@@ -269,6 +324,8 @@ pub struct TrieCacheConfig {
     pub default_max_bytes: u64,
     /// Overwrites `default_max_bytes` for specific shards.
     pub per_shard_max_bytes: HashMap<ShardUId, u64>,
+    /// Values of this size (in bytes) or larger are not put in the cache.
+    pub max_cached_value_size: usize,
 }
 
 impl Default for TrieCacheConfig {
@@ -276,6 +333,47 @@ impl Default for TrieCacheConfig {
         Self {
             default_max_bytes: DEFAULT_SHARD_CACHE_TOTAL_SIZE_LIMIT,
             per_shard_max_bytes: Default::default(),
+            max_cached_value_size: TRIE_LIMIT_CACHED_VALUE_SIZE,
+        }
+    }
+}
+
+impl TrieCacheConfig {
+    /// Default configuration of the caches used for view calls, which allow
+    /// larger values than the caches used for chunk application.
+    pub fn default_view() -> Self {
+        Self { max_cached_value_size: DEFAULT_VIEW_TRIE_LIMIT_CACHED_VALUE_SIZE, ..Self::default() }
+    }
+}
+
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct DiskLatencyConfig {
+    /// 99th percentile of read latencies above which the disk is considered
+    /// degraded.
+    /// Default value: 50ms.
+    pub read_p99_threshold: Duration,
+    /// 99th percentile of write latencies above which the disk is considered
+    /// degraded.
+    /// Default value: 500ms.
+    pub write_p99_threshold: Duration,
+    /// Length of the windows over which the percentiles are computed.
+    /// Default value: 10s.
+    pub window: Duration,
+    /// Whether to reject view calls, stop serving state sync and pause
+    /// prefetching while the disk is degraded.
+    /// Default value: false, in which case a degraded disk is only reported
+    /// in logs and metrics.
+    pub shed_load: bool,
+}
+
+impl Default for DiskLatencyConfig {
+    fn default() -> Self {
+        Self {
+            read_p99_threshold: Duration::from_millis(50),
+            write_p99_threshold: Duration::from_millis(500),
+            window: Duration::from_secs(10),
+            shed_load: false,
         }
     }
 }
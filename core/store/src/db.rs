@@ -100,6 +100,35 @@ pub trait Database: Sync + Send {
         Ok(self.get_raw_bytes(col, key)?.and_then(DBSlice::strip_refcount))
     }
 
+    /// Returns raw bytes for each of the given `keys`, in the same order.
+    ///
+    /// This is the batched version of [`Self::get_raw_bytes`].  The default
+    /// implementation looks up the keys one by one, databases which support
+    /// batched reads should override it.
+    fn multi_get_raw_bytes(
+        &self,
+        col: DBCol,
+        keys: &[&[u8]],
+    ) -> io::Result<Vec<Option<DBSlice<'_>>>> {
+        keys.iter().map(|key| self.get_raw_bytes(col, key)).collect()
+    }
+
+    /// Returns values for each of the given `keys`, in the same order,
+    /// forcing a reference count decoding.
+    ///
+    /// This is the batched version of [`Self::get_with_rc_stripped`].
+    ///
+    /// **Panics** if the column is not reference counted.
+    fn multi_get_with_rc_stripped(
+        &self,
+        col: DBCol,
+        keys: &[&[u8]],
+    ) -> io::Result<Vec<Option<DBSlice<'_>>>> {
+        assert!(col.is_rc());
+        let values = self.multi_get_raw_bytes(col, keys)?;
+        Ok(values.into_iter().map(|value| value.and_then(DBSlice::strip_refcount)).collect())
+    }
+
     /// Iterate over all items in given column in lexicographical order sorted
     /// by the key.
     ///
@@ -144,8 +173,23 @@ pub trait Database: Sync + Send {
     /// is blocking until compaction finishes. Otherwise, this is a no-op.
     fn compact(&self) -> io::Result<()>;
 
+    /// Catches up with the changes the primary instance made to the database
+    /// since it was opened or last caught up.
+    ///
+    /// This is a no-op for databases which are not opened as a secondary
+    /// instance.
+    fn catch_up_with_primary(&self) -> io::Result<()>;
+
     /// Returns statistics about the database if available.
     fn get_store_statistics(&self) -> Option<StoreStatistics>;
+
+    /// Returns size of the SST files of a column, if the database stores its
+    /// data in SST files.
+    fn get_sst_stats(&self, col: DBCol) -> Option<SstStats>;
+
+    /// Returns the monitor of the latency of this database's operations, if
+    /// it is tracked.
+    fn disk_latency_monitor(&self) -> Option<&crate::DiskLatencyMonitor>;
 }
 
 fn assert_no_overwrite(col: DBCol, key: &[u8], value: &[u8], old_value: &[u8]) {
@@ -171,3 +215,13 @@ pub enum StatsValue {
 pub struct StoreStatistics {
     pub data: Vec<(String, Vec<StatsValue>)>,
 }
+
+/// Size of the SST files of a single column.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+pub struct SstStats {
+    /// Total size of the live SST files in bytes.
+    pub live_files_size: u64,
+    /// Number of SST files on each level of the LSM tree, starting with
+    /// level 0.
+    pub num_files_per_level: Vec<u64>,
+}
@@ -118,6 +118,30 @@ impl<D: Database> super::Database for ColdDB<D> {
         }
     }
 
+    fn multi_get_raw_bytes(
+        &self,
+        col: DBCol,
+        keys: &[&[u8]],
+    ) -> std::io::Result<Vec<Option<DBSlice<'_>>>> {
+        if Self::is_hot_column(col) {
+            return self.hot.multi_get_raw_bytes(col, keys);
+        }
+        keys.iter().map(|key| self.get_raw_bytes(col, key)).collect()
+    }
+
+    fn multi_get_with_rc_stripped(
+        &self,
+        col: DBCol,
+        keys: &[&[u8]],
+    ) -> std::io::Result<Vec<Option<DBSlice<'_>>>> {
+        assert!(col.is_rc());
+        if Self::is_hot_column(col) {
+            self.hot.multi_get_with_rc_stripped(col, keys)
+        } else {
+            keys.iter().map(|key| self.get_cold_impl(col, key)).collect()
+        }
+    }
+
     /// Iterates over all values in a column.
     ///
     /// This is implemented only for a few columns.  Specifically for Block,
@@ -205,9 +229,21 @@ impl<D: Database> super::Database for ColdDB<D> {
         self.cold.flush()
     }
 
+    fn catch_up_with_primary(&self) -> std::io::Result<()> {
+        self.cold.catch_up_with_primary()
+    }
+
     fn get_store_statistics(&self) -> Option<crate::StoreStatistics> {
         self.cold.get_store_statistics()
     }
+
+    fn get_sst_stats(&self, col: DBCol) -> Option<super::SstStats> {
+        self.cold.get_sst_stats(col)
+    }
+
+    fn disk_latency_monitor(&self) -> Option<&crate::DiskLatencyMonitor> {
+        self.cold.disk_latency_monitor()
+    }
 }
 
 /// Returns key as used in cold database for given column in hot database.
@@ -8,8 +8,13 @@ use strum::IntoEnumIterator;
 use tracing::warn;
 
 use crate::config::Mode;
-use crate::db::{refcount, DBIterator, DBOp, DBSlice, DBTransaction, Database, StatsValue};
-use crate::{metadata, metrics, DBCol, StoreConfig, StoreStatistics, Temperature};
+use crate::db::{
+    refcount, DBIterator, DBOp, DBSlice, DBTransaction, Database, SstStats, StatsValue,
+};
+use crate::disk_latency::DiskOp;
+use crate::{
+    metadata, metrics, DBCol, DiskLatencyMonitor, StoreConfig, StoreStatistics, Temperature,
+};
 
 mod instance_tracker;
 pub(crate) mod snapshot;
@@ -20,6 +25,10 @@ pub(crate) mod snapshot;
 const CF_PROPERTY_NAMES: [&'static std::ffi::CStr; 1] =
     [::rocksdb::properties::LIVE_SST_FILES_SIZE];
 
+/// Number of levels of the LSM tree.  We don’t configure it so it’s the
+/// RocksDB default.
+const NUM_LEVELS: usize = 7;
+
 pub struct RocksDB {
     db: DB,
     db_opt: Options,
@@ -31,6 +40,15 @@ pub struct RocksDB {
     /// want.
     cf_handles: enum_map::EnumMap<DBCol, Option<std::ptr::NonNull<ColumnFamily>>>,
 
+    /// Directory for the info logs of a secondary instance, `None` unless
+    /// opened in [`Mode::ReadOnlySecondary`].  It’s removed once the database
+    /// is closed.
+    secondary_dir: Option<tempfile::TempDir>,
+
+    /// Monitor of the latency of reads and writes, `None` for cold databases
+    /// which are expected to live on slow disks.
+    latency_monitor: Option<DiskLatencyMonitor>,
+
     // RAII-style of keeping track of the number of instances of RocksDB and
     // counting total sum of max_open_files.
     _instance_tracker: instance_tracker::InstanceTracker,
@@ -54,7 +72,8 @@ impl RocksDB {
     /// `mode` specifies whether to open the database in read/write or read-only
     /// mode.  In the latter case, the database will not be created if it
     /// doesn’t exist nor any migrations will be performed if the database has
-    /// database version different than expected.
+    /// database version different than expected.  In secondary mode the
+    /// database can be opened while a node is writing to it.
     ///
     /// `temp` specifies whether the database is cold or hot which affects
     /// whether refcount merge operator is configured on reference counted
@@ -94,14 +113,37 @@ impl RocksDB {
     ) -> io::Result<Self> {
         let counter = instance_tracker::InstanceTracker::try_new(store_config.max_open_files)
             .map_err(other_error)?;
-        let (db, db_opt) = Self::open_db(path, store_config, mode, temp, columns)?;
+        let secondary_dir = match mode {
+            Mode::ReadOnlySecondary => {
+                Some(tempfile::Builder::new().prefix("secondary").tempdir()?)
+            }
+            _ => None,
+        };
+        let secondary_path = secondary_dir.as_ref().map(|dir| dir.path());
+        let (db, db_opt) = Self::open_db(path, secondary_path, store_config, mode, temp, columns)?;
         let cf_handles = Self::get_cf_handles(&db, columns);
-        Ok(Self { db, db_opt, cf_handles, _instance_tracker: counter })
+        let latency_monitor = match temp {
+            Temperature::Hot => Some(DiskLatencyMonitor::new(store_config.disk_latency.clone())),
+            #[cfg(feature = "cold_store")]
+            Temperature::Cold => None,
+        };
+        Ok(Self {
+            db,
+            db_opt,
+            cf_handles,
+            secondary_dir,
+            latency_monitor,
+            _instance_tracker: counter,
+        })
     }
 
     /// Opens the database with given column families configured.
+    ///
+    /// If `secondary_path` is given, the database is opened as a secondary
+    /// instance which writes its info logs to that directory.
     fn open_db(
         path: &Path,
+        secondary_path: Option<&Path>,
         store_config: &StoreConfig,
         mode: Mode,
         temp: Temperature,
@@ -118,7 +160,9 @@ impl RocksDB {
                 )
             })
             .collect::<Vec<_>>();
-        let db = if mode.read_only() {
+        let db = if let Some(secondary_path) = secondary_path {
+            DB::open_cf_descriptors_as_secondary(&options, path, secondary_path, cf_descriptors)
+        } else if mode.read_only() {
             DB::open_cf_descriptors_read_only(&options, path, cf_descriptors, false)
         } else {
             DB::open_cf_descriptors(&options, path, cf_descriptors)
@@ -256,6 +300,13 @@ impl RocksDB {
             _ => unreachable!(),
         }
     }
+
+    /// Reports latency of an operation, in seconds, to the latency monitor.
+    fn record_latency(&self, op: DiskOp, seconds: f64) {
+        if let Some(monitor) = &self.latency_monitor {
+            monitor.record(op, std::time::Duration::from_secs_f64(seconds));
+        }
+    }
 }
 
 impl Database for RocksDB {
@@ -268,10 +319,31 @@ impl Database for RocksDB {
             .get_pinned_cf_opt(self.cf_handle(col)?, key, &read_options)
             .map_err(into_other)?
             .map(DBSlice::from_rocksdb_slice);
-        timer.observe_duration();
+        self.record_latency(DiskOp::Read, timer.stop_and_record());
         Ok(result)
     }
 
+    fn multi_get_raw_bytes(
+        &self,
+        col: DBCol,
+        keys: &[&[u8]],
+    ) -> io::Result<Vec<Option<DBSlice<'_>>>> {
+        let timer = metrics::DATABASE_OP_LATENCY_HIST
+            .with_label_values(&["multi_get", col.into()])
+            .start_timer();
+        let read_options = rocksdb_read_options();
+        let sorted_input = false;
+        let result = self
+            .db
+            .batched_multi_get_cf_opt(self.cf_handle(col)?, keys, sorted_input, &read_options)
+            .into_iter()
+            .map(|value| value.map(|value| value.map(DBSlice::from_rocksdb_slice)))
+            .collect::<Result<_, _>>()
+            .map_err(into_other);
+        self.record_latency(DiskOp::Read, timer.stop_and_record());
+        result
+    }
+
     fn iter_raw_bytes<'a>(&'a self, col: DBCol) -> DBIterator<'a> {
         Box::new(self.iter_raw_bytes_prefix(col, &[]))
     }
@@ -317,7 +389,10 @@ impl Database for RocksDB {
                 }
             }
         }
-        self.db.write(batch).map_err(into_other)
+        let start = std::time::Instant::now();
+        let result = self.db.write(batch).map_err(into_other);
+        self.record_latency(DiskOp::Write, start.elapsed().as_secs_f64());
+        result
     }
 
     fn compact(&self) -> io::Result<()> {
@@ -328,6 +403,13 @@ impl Database for RocksDB {
         Ok(())
     }
 
+    fn catch_up_with_primary(&self) -> io::Result<()> {
+        if self.secondary_dir.is_none() {
+            return Ok(());
+        }
+        self.db.try_catch_up_with_primary().map_err(into_other)
+    }
+
     fn flush(&self) -> io::Result<()> {
         // Need to iterator over all CFs because the normal `flush()` only
         // flushes the default column family.
@@ -354,6 +436,21 @@ impl Database for RocksDB {
             Some(result)
         }
     }
+
+    fn get_sst_stats(&self, col: DBCol) -> Option<SstStats> {
+        let cf_handle = self.cf_handle(col).ok()?;
+        let get_property =
+            |name: &std::ffi::CStr| self.db.property_int_value_cf(cf_handle, name).ok().flatten();
+        let live_files_size = get_property(::rocksdb::properties::LIVE_SST_FILES_SIZE)?;
+        let num_files_per_level = (0..NUM_LEVELS)
+            .map(|level| get_property(&::rocksdb::properties::num_files_at_level(level)))
+            .collect::<Option<Vec<u64>>>()?;
+        Some(SstStats { live_files_size, num_files_per_level })
+    }
+
+    fn disk_latency_monitor(&self) -> Option<&DiskLatencyMonitor> {
+        self.latency_monitor.as_ref()
+    }
 }
 
 /// DB level options
@@ -707,6 +804,28 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_secondary_catch_up() {
+        let (_tmp_dir, opener) = NodeStorage::test_opener();
+        let primary = opener.open().unwrap().get_store(crate::Temperature::Hot);
+        let mut store_update = primary.store_update();
+        store_update.set(DBCol::BlockMisc, &[1], &[1]);
+        store_update.commit().unwrap();
+
+        let secondary = opener
+            .open_in_mode(Mode::ReadOnlySecondary)
+            .unwrap()
+            .get_store(crate::Temperature::Hot);
+        assert_eq!(secondary.get(DBCol::BlockMisc, &[1]).unwrap().as_deref(), Some(&[1][..]));
+
+        let mut store_update = primary.store_update();
+        store_update.set(DBCol::BlockMisc, &[2], &[2]);
+        store_update.commit().unwrap();
+        assert_eq!(secondary.get(DBCol::BlockMisc, &[2]).unwrap(), None);
+        secondary.catch_up_with_primary().unwrap();
+        assert_eq!(secondary.get(DBCol::BlockMisc, &[2]).unwrap().as_deref(), Some(&[2][..]));
+    }
+
     #[test]
     fn test_parse_statistics() {
         let statistics = "rocksdb.cold.file.read.count COUNT : 999\n\
@@ -2,7 +2,7 @@ use std::collections::BTreeMap;
 use std::io;
 use std::sync::{Arc, RwLock};
 
-use crate::db::{refcount, DBIterator, DBOp, DBSlice, DBTransaction, Database};
+use crate::db::{refcount, DBIterator, DBOp, DBSlice, DBTransaction, Database, SstStats};
 use crate::{DBCol, StoreStatistics};
 
 /// An in-memory database intended for tests and IO-agnostic estimations.
@@ -93,7 +93,19 @@ impl Database for TestDB {
         Ok(())
     }
 
+    fn catch_up_with_primary(&self) -> io::Result<()> {
+        Ok(())
+    }
+
     fn get_store_statistics(&self) -> Option<StoreStatistics> {
         None
     }
+
+    fn get_sst_stats(&self, _col: DBCol) -> Option<SstStats> {
+        None
+    }
+
+    fn disk_latency_monitor(&self) -> Option<&crate::DiskLatencyMonitor> {
+        None
+    }
 }
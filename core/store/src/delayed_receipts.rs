@@ -0,0 +1,170 @@
+use std::collections::BTreeMap;
+
+use near_primitives::errors::StorageError;
+use near_primitives::receipt::{DelayedReceiptIndices, DelayedReceiptQueue, Receipt};
+
+use crate::{get, set, TrieAccess, TrieUpdate};
+
+/// Indices of the delayed receipt queues of a shard.
+///
+/// The indices are read once and kept here while receipts are added to and
+/// removed from the queues, then written back with [`Self::save`].  Only the
+/// queues the object was loaded with can be used, so that callers decide which
+/// queues exist at their protocol version.
+#[derive(Clone, Debug, PartialEq)]
+pub struct DelayedReceiptQueues {
+    indices: BTreeMap<DelayedReceiptQueue, DelayedReceiptIndices>,
+    initial_indices: BTreeMap<DelayedReceiptQueue, DelayedReceiptIndices>,
+}
+
+impl DelayedReceiptQueues {
+    pub fn load(
+        trie: &dyn TrieAccess,
+        queues: impl IntoIterator<Item = DelayedReceiptQueue>,
+    ) -> Result<Self, StorageError> {
+        let indices = queues
+            .into_iter()
+            .map(|queue| Ok((queue, get(trie, &queue.indices_key())?.unwrap_or_default())))
+            .collect::<Result<BTreeMap<_, _>, StorageError>>()?;
+        Ok(Self { initial_indices: indices.clone(), indices })
+    }
+
+    /// Current indices of every queue, from the highest priority down.
+    pub fn indices(&self) -> impl Iterator<Item = (DelayedReceiptQueue, &DelayedReceiptIndices)> {
+        self.indices.iter().rev().map(|(queue, indices)| (*queue, indices))
+    }
+
+    /// Current indices of the given queue, `None` if it was not loaded.
+    pub fn get(&self, queue: DelayedReceiptQueue) -> Option<&DelayedReceiptIndices> {
+        self.indices.get(&queue)
+    }
+
+    /// Adds the receipt to the end of its queue, see
+    /// [`DelayedReceiptQueue::for_receipt`].
+    pub fn push(
+        &mut self,
+        state_update: &mut TrieUpdate,
+        receipt: &Receipt,
+    ) -> Result<(), StorageError> {
+        let queue = DelayedReceiptQueue::for_receipt(receipt);
+        let indices = self.indices.get_mut(&queue).ok_or_else(|| {
+            StorageError::StorageInconsistentState(format!(
+                "Delayed receipt queue {:?} is not available",
+                queue
+            ))
+        })?;
+        set(state_update, queue.receipt_key(indices.next_available_index), receipt);
+        indices.next_available_index =
+            indices.next_available_index.checked_add(1).ok_or_else(|| {
+                StorageError::StorageInconsistentState(
+                    "Next available index for delayed receipt exceeded the integer limit"
+                        .to_string(),
+                )
+            })?;
+        Ok(())
+    }
+
+    /// Removes and returns the first receipt of the queue with the highest
+    /// priority which is not empty.
+    pub fn pop(&mut self, state_update: &mut TrieUpdate) -> Result<Option<Receipt>, StorageError> {
+        let queue = self
+            .indices
+            .iter()
+            .rev()
+            .find(|(_, indices)| indices.first_index < indices.next_available_index)
+            .map(|(queue, _)| *queue);
+        match queue {
+            Some(queue) => self.pop_from(state_update, queue),
+            None => Ok(None),
+        }
+    }
+
+    /// Removes and returns the first receipt of the given queue.
+    pub fn pop_from(
+        &mut self,
+        state_update: &mut TrieUpdate,
+        queue: DelayedReceiptQueue,
+    ) -> Result<Option<Receipt>, StorageError> {
+        let indices = match self.indices.get_mut(&queue) {
+            Some(indices) if indices.first_index < indices.next_available_index => indices,
+            _ => return Ok(None),
+        };
+        let key = queue.receipt_key(indices.first_index);
+        let receipt = get(state_update, &key)?.ok_or_else(|| {
+            StorageError::StorageInconsistentState(format!(
+                "Delayed receipt #{} of queue {:?} should be in the state",
+                indices.first_index, queue
+            ))
+        })?;
+        state_update.remove(key);
+        // Math checked above: first_index is less than next_available_index
+        indices.first_index += 1;
+        Ok(Some(receipt))
+    }
+
+    /// Whether any of the queues changed since they were loaded.
+    pub fn is_modified(&self) -> bool {
+        self.indices != self.initial_indices
+    }
+
+    /// Writes the indices of the queues which changed since they were loaded.
+    pub fn save(&self, state_update: &mut TrieUpdate) {
+        for (queue, indices) in &self.indices {
+            if self.initial_indices.get(queue) != Some(indices) {
+                set(state_update, queue.indices_key(), indices);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::DelayedReceiptQueues;
+    use crate::test_utils::create_tries;
+    use crate::Trie;
+    use near_primitives::hash::CryptoHash;
+    use near_primitives::receipt::{ActionReceipt, DelayedReceiptQueue, Receipt, ReceiptEnum};
+    use near_primitives::shard_layout::ShardUId;
+    use near_primitives::types::Balance;
+
+    fn receipt(index: u8, priority: Balance) -> Receipt {
+        let mut receipt = Receipt::new_balance_refund(&"alice.near".parse().unwrap(), 1);
+        receipt.receipt_id = CryptoHash::hash_bytes(&[index]);
+        if let ReceiptEnum::Action(ActionReceipt { priority: receipt_priority, .. }) =
+            &mut receipt.receipt
+        {
+            *receipt_priority = priority;
+        }
+        receipt
+    }
+
+    #[test]
+    fn test_delayed_receipt_queues_order() {
+        let tries = create_tries();
+        let mut state_update = tries.new_trie_update(ShardUId::single_shard(), Trie::EMPTY_ROOT);
+        let mut queues =
+            DelayedReceiptQueues::load(&state_update, DelayedReceiptQueue::all()).unwrap();
+        let high = 10u128.pow(24);
+        let receipts = [receipt(0, 0), receipt(1, 1), receipt(2, high), receipt(3, 0)];
+        for receipt in &receipts {
+            queues.push(&mut state_update, receipt).unwrap();
+        }
+        queues.save(&mut state_update);
+
+        let mut queues =
+            DelayedReceiptQueues::load(&state_update, DelayedReceiptQueue::all()).unwrap();
+        let mut popped = vec![];
+        while let Some(receipt) = queues.pop(&mut state_update).unwrap() {
+            popped.push(receipt);
+        }
+        let expected = [&receipts[2], &receipts[1], &receipts[0], &receipts[3]];
+        assert_eq!(popped.iter().collect::<Vec<_>>(), expected);
+
+        // Without the priority queues, only receipts without priority can be
+        // delayed.
+        let mut queues =
+            DelayedReceiptQueues::load(&state_update, [DelayedReceiptQueue::Default]).unwrap();
+        queues.push(&mut state_update, &receipts[0]).unwrap();
+        assert!(queues.push(&mut state_update, &receipts[1]).is_err());
+    }
+}
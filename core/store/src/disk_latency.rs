@@ -0,0 +1,230 @@
+//! Detection of a degraded disk from the latency of database operations.
+//!
+//! The hot database reports the latency of every read and write to a
+//! [`DiskLatencyMonitor`].  At the end of every window, the 99th percentile of
+//! the latencies in the window is compared with the thresholds configured in
+//! [`DiskLatencyConfig`].  While one of them is exceeded, the disk counts as
+//! degraded.  This is exported as a metric to alert the operator and, if
+//! enabled, non-essential work like view calls, serving state sync and
+//! prefetching is shed so that the node keeps up with producing chunks.
+
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+use tracing::{info, warn};
+
+use crate::config::DiskLatencyConfig;
+use crate::metrics;
+
+/// Latencies are counted in buckets of powers of two microseconds, the last
+/// bucket holds everything from about 4 seconds on.
+const NUM_BUCKETS: usize = 24;
+
+/// Windows with fewer operations of a kind tell nothing about the disk.
+const MIN_SAMPLES: u64 = 100;
+
+#[derive(Clone, Copy, Debug)]
+pub(crate) enum DiskOp {
+    Read,
+    Write,
+}
+
+impl DiskOp {
+    fn as_str(self) -> &'static str {
+        match self {
+            DiskOp::Read => "read",
+            DiskOp::Write => "write",
+        }
+    }
+}
+
+/// Histogram of latencies which can be updated from many threads at once.
+struct LatencyHistogram {
+    buckets: [AtomicU64; NUM_BUCKETS],
+}
+
+impl LatencyHistogram {
+    fn new() -> Self {
+        Self { buckets: std::array::from_fn(|_| AtomicU64::new(0)) }
+    }
+
+    fn record(&self, latency: Duration) {
+        let micros = u64::try_from(latency.as_micros()).unwrap_or(u64::MAX);
+        let bucket = (u64::BITS - micros.leading_zeros()) as usize;
+        self.buckets[bucket.min(NUM_BUCKETS - 1)].fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Resets the histogram and returns the upper bound of the bucket holding
+    /// the 99th percentile of the recorded latencies, or `None` if there were
+    /// too few of them.
+    fn take_p99(&self) -> Option<Duration> {
+        let counts: Vec<u64> =
+            self.buckets.iter().map(|bucket| bucket.swap(0, Ordering::Relaxed)).collect();
+        let total: u64 = counts.iter().sum();
+        if total < MIN_SAMPLES {
+            return None;
+        }
+        let rank = total - total / 100;
+        let mut seen = 0;
+        counts
+            .iter()
+            .position(|count| {
+                seen += count;
+                seen >= rank
+            })
+            .map(|bucket| Duration::from_micros(1 << bucket))
+    }
+}
+
+/// Tracks the latency of database operations in consecutive windows and
+/// decides whether the disk is degraded, see the module documentation.
+pub struct DiskLatencyMonitor {
+    config: DiskLatencyConfig,
+    reads: LatencyHistogram,
+    writes: LatencyHistogram,
+    /// Reference point for `window_end`.
+    created: Instant,
+    /// End of the current window in nanoseconds since `created`.
+    window_end: AtomicU64,
+    degraded: AtomicBool,
+}
+
+impl DiskLatencyMonitor {
+    pub(crate) fn new(config: DiskLatencyConfig) -> Self {
+        let window_end = AtomicU64::new(config.window.as_nanos() as u64);
+        Self {
+            config,
+            reads: LatencyHistogram::new(),
+            writes: LatencyHistogram::new(),
+            created: Instant::now(),
+            window_end,
+            degraded: AtomicBool::new(false),
+        }
+    }
+
+    pub(crate) fn record(&self, op: DiskOp, latency: Duration) {
+        match op {
+            DiskOp::Read => self.reads.record(latency),
+            DiskOp::Write => self.writes.record(latency),
+        }
+        self.maybe_end_window();
+    }
+
+    /// Whether the disk was degraded in the last complete window.
+    pub fn is_degraded(&self) -> bool {
+        self.maybe_end_window();
+        self.degraded.load(Ordering::Relaxed)
+    }
+
+    /// Whether non-essential work should be skipped because the disk is
+    /// degraded.  Always `false` unless `shed_load` is configured.
+    pub fn should_shed_load(&self) -> bool {
+        self.config.shed_load && self.is_degraded()
+    }
+
+    fn maybe_end_window(&self) {
+        let now = self.created.elapsed().as_nanos() as u64;
+        let window_end = self.window_end.load(Ordering::Relaxed);
+        if now < window_end {
+            return;
+        }
+        let next_window_end = now + self.config.window.as_nanos() as u64;
+        // Only one of the threads which see the window ending evaluates it.
+        if self
+            .window_end
+            .compare_exchange(window_end, next_window_end, Ordering::Relaxed, Ordering::Relaxed)
+            .is_ok()
+        {
+            self.end_window();
+        }
+    }
+
+    fn end_window(&self) {
+        let read_p99 = self.reads.take_p99();
+        let write_p99 = self.writes.take_p99();
+        let exceeds =
+            |p99: Option<Duration>, threshold| matches!(p99, Some(p99) if p99 > threshold);
+        let degraded = exceeds(read_p99, self.config.read_p99_threshold)
+            || exceeds(write_p99, self.config.write_p99_threshold);
+        for (op, p99) in [(DiskOp::Read, read_p99), (DiskOp::Write, write_p99)] {
+            if let Some(p99) = p99 {
+                metrics::DISK_LATENCY_P99.with_label_values(&[op.as_str()]).set(p99.as_secs_f64());
+            }
+        }
+        metrics::DISK_DEGRADED.set(degraded as i64);
+        let was_degraded = self.degraded.swap(degraded, Ordering::Relaxed);
+        if degraded && !was_degraded {
+            warn!(target: "store", ?read_p99, ?write_p99, shed_load = self.config.shed_load, "Disk latency is degraded");
+        } else if !degraded && was_degraded {
+            info!(target: "store", ?read_p99, ?write_p99, "Disk latency recovered");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{DiskLatencyMonitor, DiskOp, LatencyHistogram, MIN_SAMPLES};
+    use crate::config::DiskLatencyConfig;
+    use std::time::Duration;
+
+    #[test]
+    fn test_latency_histogram_p99() {
+        let histogram = LatencyHistogram::new();
+        for _ in 0..MIN_SAMPLES - 1 {
+            histogram.record(Duration::from_micros(100));
+        }
+        assert_eq!(histogram.take_p99(), None);
+
+        for _ in 0..990 {
+            histogram.record(Duration::from_micros(100));
+        }
+        for _ in 0..10 {
+            histogram.record(Duration::from_millis(50));
+        }
+        // 100µs fall into the bucket up to 128µs.
+        assert_eq!(histogram.take_p99(), Some(Duration::from_micros(128)));
+
+        for _ in 0..980 {
+            histogram.record(Duration::from_micros(100));
+        }
+        for _ in 0..20 {
+            histogram.record(Duration::from_millis(50));
+        }
+        assert_eq!(histogram.take_p99(), Some(Duration::from_micros(65536)));
+        // Taking the percentile resets the histogram.
+        assert_eq!(histogram.take_p99(), None);
+    }
+
+    #[test]
+    fn test_disk_latency_monitor() {
+        let config = DiskLatencyConfig {
+            read_p99_threshold: Duration::from_millis(10),
+            write_p99_threshold: Duration::from_millis(100),
+            window: Duration::ZERO,
+            shed_load: true,
+        };
+        let monitor = DiskLatencyMonitor::new(config);
+        let record = |op, latency, count| {
+            for _ in 0..count {
+                match op {
+                    DiskOp::Read => monitor.reads.record(latency),
+                    DiskOp::Write => monitor.writes.record(latency),
+                }
+            }
+        };
+
+        record(DiskOp::Read, Duration::from_millis(1), MIN_SAMPLES);
+        record(DiskOp::Write, Duration::from_millis(50), MIN_SAMPLES);
+        assert!(!monitor.should_shed_load());
+
+        record(DiskOp::Write, Duration::from_millis(200), MIN_SAMPLES);
+        assert!(monitor.should_shed_load());
+
+        record(DiskOp::Read, Duration::from_millis(20), MIN_SAMPLES);
+        assert!(monitor.should_shed_load());
+
+        // A window without enough operations says nothing about the disk.
+        record(DiskOp::Read, Duration::from_millis(20), 10);
+        assert!(!monitor.is_degraded());
+    }
+}
@@ -25,7 +25,7 @@ use near_primitives::trie_key::{trie_key_parsers, TrieKey};
 use near_primitives::types::{AccountId, CompiledContract, CompiledContractCache, StateRoot};
 
 use crate::db::{
-    refcount, DBIterator, DBOp, DBSlice, DBTransaction, Database, StoreStatistics,
+    refcount, DBIterator, DBOp, DBSlice, DBTransaction, Database, SstStats, StoreStatistics,
     GENESIS_JSON_HASH_KEY, GENESIS_STATE_ROOTS_KEY,
 };
 pub use crate::trie::iterator::{TrieIterator, TrieTraversalItem};
@@ -33,8 +33,8 @@ pub use crate::trie::update::{TrieUpdate, TrieUpdateIterator, TrieUpdateValuePtr
 pub use crate::trie::{
     estimator, split_state, ApplyStatePartResult, KeyForStateChanges, KeyLookupMode, NibbleSlice,
     PartialStorage, PrefetchApi, RawTrieNode, RawTrieNodeWithSize, ShardTries, Trie, TrieAccess,
-    TrieCache, TrieCachingStorage, TrieChanges, TrieConfig, TrieDBStorage, TrieStorage,
-    WrappedTrieChanges,
+    TrieCache, TrieCacheStats, TrieCachingStorage, TrieChanges, TrieConfig, TrieDBStorage,
+    TrieStorage, WrappedTrieChanges,
 };
 pub use flat_state::FlatStateDelta;
 
@@ -43,6 +43,8 @@ pub mod cold_storage;
 mod columns;
 pub mod config;
 pub mod db;
+mod delayed_receipts;
+mod disk_latency;
 pub mod flat_state;
 pub mod metadata;
 mod metrics;
@@ -52,6 +54,8 @@ pub mod test_utils;
 mod trie;
 
 pub use crate::config::{Mode, StoreConfig};
+pub use crate::delayed_receipts::DelayedReceiptQueues;
+pub use crate::disk_latency::DiskLatencyMonitor;
 pub use crate::opener::{StoreMigrator, StoreOpener, StoreOpenerError};
 
 /// Specifies temperature of a storage.
@@ -262,6 +266,27 @@ impl Store {
         Ok(value)
     }
 
+    /// Reads values of all `keys` in one batch, which is faster than reading
+    /// them one by one with [`Self::get`].  Values are returned in the same
+    /// order as the keys.
+    pub fn multi_get(&self, column: DBCol, keys: &[&[u8]]) -> io::Result<Vec<Option<DBSlice<'_>>>> {
+        let values = if column.is_rc() {
+            self.storage.multi_get_with_rc_stripped(column, keys)
+        } else {
+            self.storage.multi_get_raw_bytes(column, keys)
+        }?;
+        for (key, value) in keys.iter().zip(&values) {
+            tracing::trace!(
+                target: "store",
+                db_op = "get",
+                col = %column,
+                key = %pretty::StorageKey(key),
+                size = value.as_deref().map(<[u8]>::len)
+            );
+        }
+        Ok(values)
+    }
+
     pub fn get_ser<T: BorshDeserialize>(&self, column: DBCol, key: &[u8]) -> io::Result<Option<T>> {
         self.get(column, key)?.as_deref().map(T::try_from_slice).transpose()
     }
@@ -347,9 +372,69 @@ impl Store {
         self.storage.compact()
     }
 
+    /// Makes changes a node wrote to the database since it was opened visible,
+    /// if the storage was opened in [`Mode::ReadOnlySecondary`].  Caches on
+    /// top of the store, e.g. trie caches, are not invalidated.
+    pub fn catch_up_with_primary(&self) -> io::Result<()> {
+        self.storage.catch_up_with_primary()
+    }
+
     pub fn get_store_statistics(&self) -> Option<StoreStatistics> {
         self.storage.get_store_statistics()
     }
+
+    /// Whether non-essential work of the given `kind`, e.g. view calls,
+    /// should be skipped because the disk latency is degraded.  See
+    /// [`config::DiskLatencyConfig`].
+    pub fn should_shed_load(&self, kind: &str) -> bool {
+        let shed =
+            self.storage.disk_latency_monitor().map_or(false, |monitor| monitor.should_shed_load());
+        if shed {
+            metrics::DISK_LOAD_SHED.with_label_values(&[kind]).inc();
+        }
+        shed
+    }
+
+    /// Returns number and size of the entries of every column.
+    ///
+    /// This reads the whole database, which can take hours on a mainnet node.
+    pub fn column_stats(&self) -> io::Result<Vec<(DBCol, ColumnStats)>> {
+        use strum::IntoEnumIterator;
+        DBCol::iter().map(|col| Ok((col, self.column_stats_for(col)?))).collect()
+    }
+
+    /// Returns number and size of the entries of a single column.
+    ///
+    /// Keys and values are counted by scanning the column.
+    pub fn column_stats_for(&self, column: DBCol) -> io::Result<ColumnStats> {
+        let mut stats = ColumnStats {
+            num_keys: 0,
+            key_bytes: 0,
+            value_bytes: 0,
+            sst: self.storage.get_sst_stats(column),
+        };
+        for item in self.storage.iter(column) {
+            let (key, value) = item?;
+            stats.num_keys += 1;
+            stats.key_bytes += key.len() as u64;
+            stats.value_bytes += value.len() as u64;
+        }
+        Ok(stats)
+    }
+}
+
+/// Number and size of the entries of a column, see [`Store::column_stats`].
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+pub struct ColumnStats {
+    pub num_keys: u64,
+    /// Total size of all keys in bytes.
+    pub key_bytes: u64,
+    /// Total size of all values in bytes.  Reference counts of values in rc
+    /// columns are not included.
+    pub value_bytes: u64,
+    /// Size of the SST files of the column, if the database stores its data
+    /// in SST files.
+    pub sst: Option<SstStats>,
 }
 
 /// Keeps track of current changes to the database and can commit all of them to the database.
@@ -902,6 +987,66 @@ mod tests {
         test_clear_column(crate::test_utils::create_test_store());
     }
 
+    fn test_column_stats(store: Store, want_sst: bool) {
+        let mut store_update = store.store_update();
+        store_update.set(DBCol::Peers, b"key", b"value");
+        store_update.set(DBCol::Peers, b"other key", b"");
+        store_update.increment_refcount(DBCol::State, b"node", b"data");
+        store_update.increment_refcount(DBCol::State, b"node", b"data");
+        store_update.commit().unwrap();
+        store.flush().unwrap();
+
+        let stats = store.column_stats_for(DBCol::Peers).unwrap();
+        assert_eq!((stats.num_keys, stats.key_bytes, stats.value_bytes), (2, 12, 5));
+        assert_eq!(stats.sst.is_some(), want_sst);
+        // Reference counts are not included.
+        let stats = store.column_stats_for(DBCol::State).unwrap();
+        assert_eq!((stats.num_keys, stats.key_bytes, stats.value_bytes), (1, 4, 4));
+        let stats = store.column_stats_for(DBCol::Block).unwrap();
+        assert_eq!((stats.num_keys, stats.key_bytes, stats.value_bytes), (0, 0, 0));
+    }
+
+    #[test]
+    fn column_stats_rocksdb() {
+        let (_tmp_dir, opener) = NodeStorage::test_opener();
+        test_column_stats(opener.open().unwrap().get_store(Temperature::Hot), true);
+    }
+
+    #[test]
+    fn column_stats_testdb() {
+        test_column_stats(crate::test_utils::create_test_store(), false);
+    }
+
+    fn test_multi_get(store: Store) {
+        let mut store_update = store.store_update();
+        store_update.set(DBCol::Peers, b"a", b"1");
+        store_update.set(DBCol::Peers, b"c", b"3");
+        store_update.increment_refcount(DBCol::State, b"node", b"data");
+        store_update.increment_refcount(DBCol::State, b"released", b"data");
+        store_update.decrement_refcount(DBCol::State, b"released");
+        store_update.commit().unwrap();
+
+        let values = store.multi_get(DBCol::Peers, &[b"c".as_slice(), b"b", b"a"]).unwrap();
+        let values: Vec<_> = values.iter().map(|value| value.as_deref()).collect();
+        assert_eq!(values, [Some(&b"3"[..]), None, Some(&b"1"[..])]);
+        // Reference counts are stripped and released values are missing.
+        let values = store.multi_get(DBCol::State, &[b"node".as_slice(), b"released"]).unwrap();
+        let values: Vec<_> = values.iter().map(|value| value.as_deref()).collect();
+        assert_eq!(values, [Some(&b"data"[..]), None]);
+        assert!(store.multi_get(DBCol::State, &[]).unwrap().is_empty());
+    }
+
+    #[test]
+    fn multi_get_rocksdb() {
+        let (_tmp_dir, opener) = NodeStorage::test_opener();
+        test_multi_get(opener.open().unwrap().get_store(Temperature::Hot));
+    }
+
+    #[test]
+    fn multi_get_testdb() {
+        test_multi_get(crate::test_utils::create_test_store());
+    }
+
     /// Asserts that elements in the vector are sorted.
     #[track_caller]
     fn assert_sorted(want_count: usize, keys: Vec<Box<[u8]>>) {
@@ -5,7 +5,7 @@ pub type DbVersion = u32;
 
 /// Current version of the database.
 pub const DB_VERSION: DbVersion =
-    if cfg!(feature = "protocol_feature_flat_state") { 35 } else { 34 };
+    if cfg!(feature = "protocol_feature_flat_state") { 38 } else { 37 };
 
 /// Database version at which point DbKind was introduced.
 const DB_VERSION_WITH_KIND: DbVersion = 34;
@@ -1,6 +1,7 @@
 use near_o11y::metrics::{
-    try_create_histogram_vec, try_create_int_counter_vec, try_create_int_gauge_vec, HistogramVec,
-    IntCounterVec, IntGaugeVec,
+    try_create_gauge_vec, try_create_histogram_vec, try_create_int_counter_vec,
+    try_create_int_gauge, try_create_int_gauge_vec, GaugeVec, HistogramVec, IntCounterVec,
+    IntGauge, IntGaugeVec,
 };
 use once_cell::sync::Lazy;
 
@@ -69,6 +70,15 @@ pub static CHUNK_CACHE_SIZE: Lazy<IntGaugeVec> = Lazy::new(|| {
         .unwrap()
 });
 
+pub static CHUNK_CACHE_CURRENT_TOTAL_SIZE: Lazy<IntGaugeVec> = Lazy::new(|| {
+    try_create_int_gauge_vec(
+        "near_chunk_cache_current_total_size",
+        "Total size of the values in the chunk cache, in bytes",
+        &["shard_id", "is_view"],
+    )
+    .unwrap()
+});
+
 pub static SHARD_CACHE_CURRENT_TOTAL_SIZE: Lazy<IntGaugeVec> = Lazy::new(|| {
     try_create_int_gauge_vec(
         "near_shard_cache_current_total_size",
@@ -110,6 +120,15 @@ pub static SHARD_CACHE_GC_POP_MISSES: Lazy<IntCounterVec> = Lazy::new(|| {
     )
     .unwrap()
 });
+pub static SHARD_CACHE_EVICTIONS: Lazy<IntCounterVec> = Lazy::new(|| {
+    try_create_int_counter_vec(
+        "near_shard_cache_evictions",
+        "Values evicted from the shard cache, by cause: `capacity` if the cache was full, \
+         `deletion` if the node was deleted or explicitly evicted",
+        &["shard_id", "is_view", "cause"],
+    )
+    .unwrap()
+});
 pub static SHARD_CACHE_DELETIONS_SIZE: Lazy<IntGaugeVec> = Lazy::new(|| {
     try_create_int_gauge_vec(
         "near_shard_cache_deletions_size",
@@ -165,6 +184,14 @@ pub static PREFETCH_FAIL: Lazy<IntCounterVec> = Lazy::new(|| {
     )
     .unwrap()
 });
+pub static PREFETCH_CANCELLED: Lazy<IntCounterVec> = Lazy::new(|| {
+    try_create_int_counter_vec(
+        "near_prefetch_cancelled",
+        "Prefetch requests dropped because the chunk they were made for had been applied",
+        &["shard_id"],
+    )
+    .unwrap()
+});
 pub static PREFETCH_NOT_REQUESTED: Lazy<IntCounterVec> = Lazy::new(|| {
     try_create_int_counter_vec(
         "near_prefetch_not_requested",
@@ -189,6 +216,42 @@ pub static PREFETCH_RETRY: Lazy<IntCounterVec> = Lazy::new(|| {
     )
     .unwrap()
 });
+pub static PREFETCH_PAUSED: Lazy<IntGaugeVec> = Lazy::new(|| {
+    try_create_int_gauge_vec(
+        "near_prefetch_paused",
+        "Whether prefetching is paused because most prefetched values were not used",
+        &["shard_id"],
+    )
+    .unwrap()
+});
+pub static PREFETCH_WAIT_TIME: Lazy<HistogramVec> = Lazy::new(|| {
+    try_create_histogram_vec(
+        "near_prefetch_wait_time",
+        "Time the main thread spent waiting for values that were still being prefetched.",
+        &["shard_id"],
+        Some(vec![0.00001, 0.00005, 0.0001, 0.0002, 0.0005, 0.001, 0.002, 0.005, 0.01, 0.1]),
+    )
+    .unwrap()
+});
+pub static COLD_STATE_READS: Lazy<IntCounterVec> = Lazy::new(|| {
+    try_create_int_counter_vec(
+        "near_cold_state_reads",
+        "Trie node reads that missed in the hot store and were retried on the cold store.",
+        &["shard_id"],
+    )
+    .unwrap()
+});
+pub static COLD_STATE_READ_LATENCY: Lazy<HistogramVec> = Lazy::new(|| {
+    try_create_histogram_vec(
+        "near_cold_state_read_latency",
+        "Latency of trie node reads from the cold store.",
+        &["shard_id"],
+        // Cold storage is expected to be on slower disks, hence the buckets
+        // reach much further than for hot reads.
+        Some(vec![0.0001, 0.0005, 0.001, 0.005, 0.01, 0.05, 0.1, 0.5, 1., 5.]),
+    )
+    .unwrap()
+});
 pub static PREFETCH_STAGED_BYTES: Lazy<IntGaugeVec> = Lazy::new(|| {
     try_create_int_gauge_vec(
         "near_prefetch_staged_bytes",
@@ -205,6 +268,29 @@ pub static PREFETCH_STAGED_SLOTS: Lazy<IntGaugeVec> = Lazy::new(|| {
     )
     .unwrap()
 });
+pub(crate) static DISK_DEGRADED: Lazy<IntGauge> = Lazy::new(|| {
+    try_create_int_gauge(
+        "near_disk_degraded",
+        "Whether the latency of the hot database exceeded the configured thresholds in the last window.",
+    )
+    .unwrap()
+});
+pub(crate) static DISK_LATENCY_P99: Lazy<GaugeVec> = Lazy::new(|| {
+    try_create_gauge_vec(
+        "near_disk_latency_p99_seconds",
+        "Approximate 99th percentile of the hot database latency in the last window.",
+        &["op"],
+    )
+    .unwrap()
+});
+pub(crate) static DISK_LOAD_SHED: Lazy<IntCounterVec> = Lazy::new(|| {
+    try_create_int_counter_vec(
+        "near_disk_load_shed_total",
+        "Number of requests rejected or skipped because of degraded disk latency.",
+        &["kind"],
+    )
+    .unwrap()
+});
 #[cfg(feature = "cold_store")]
 pub static COLD_MIGRATION_READS: Lazy<IntCounterVec> = Lazy::new(|| {
     try_create_int_counter_vec(
@@ -16,12 +16,36 @@ pub(crate) const DEFAULT_SHARD_CACHE_TOTAL_SIZE_LIMIT: u64 =
 const DEFAULT_SHARD_CACHE_DELETIONS_QUEUE_CAPACITY: usize =
     if cfg!(feature = "no_cache") { 1 } else { 100_000 };
 
-/// Values above this size (in bytes) are never cached.
+/// Default limit for the prefetch staging area, per shard.
+const DEFAULT_PREFETCH_STAGING_MAX_BYTES: usize = 200 * 1024 * 1024;
+
+/// Default number of prefetching IO threads, per shard.
+const DEFAULT_NUM_PREFETCH_THREADS: usize = 8;
+
+/// Default share of prefetched values that must be used by the main thread
+/// for prefetching to stay enabled.
+pub(crate) const DEFAULT_PREFETCH_MIN_HIT_RATE: f64 = 0.1;
+
+/// Default number of trie nodes per shard to load into the shard cache on
+/// startup. Around the number of nodes in a full default-sized shard cache.
+pub(crate) const DEFAULT_CACHE_WARMUP_MAX_NODES: usize = 100_000;
+
+/// Default limit for the total size of the values in the chunk cache.
+/// Gas costs bound the chunk cache to roughly 31_250 nodes and 85 MB of keys
+/// and values per receipt, see `TrieCachingStorage::retrieve_raw_bytes`.
+pub(crate) const DEFAULT_CHUNK_CACHE_SIZE_LIMIT: u64 = 128 * 1024 * 1024;
+
+/// Default size limit for values in the shard cache. Values of this size (in
+/// bytes) or larger are not cached.
 /// Note that most of Trie inner nodes are smaller than this - e.g. branches use around 32 * 16 = 512 bytes.
-const TRIE_LIMIT_CACHED_VALUE_SIZE: usize = 1000;
+pub(crate) const TRIE_LIMIT_CACHED_VALUE_SIZE: usize = 1000;
+
+/// Default size limit for values in the view caches. View calls of some
+/// contracts read many values slightly larger than `TRIE_LIMIT_CACHED_VALUE_SIZE`
+/// and benefit greatly from caching them.
+pub(crate) const DEFAULT_VIEW_TRIE_LIMIT_CACHED_VALUE_SIZE: usize = 4 * 1024;
 
 /// Stores necessary configuration for the creation of tries.
-#[derive(Default)]
 pub struct TrieConfig {
     pub shard_cache_config: TrieCacheConfig,
     pub view_shard_cache_config: TrieCacheConfig,
@@ -31,6 +55,36 @@ pub struct TrieConfig {
     pub sweat_prefetch_receivers: Vec<AccountId>,
     /// List of allowed predecessor accounts for SWEAT prefetching.
     pub sweat_prefetch_senders: Vec<AccountId>,
+    /// Memory limit for prefetched values waiting in the staging area.
+    pub prefetch_staging_max_bytes: usize,
+    /// How many threads will be prefetching data, without the scheduler thread.
+    pub num_prefetch_threads: usize,
+    /// Prefetching is paused while the share of prefetched values used by the
+    /// main thread is below this.
+    pub prefetch_min_hit_rate: f64,
+    /// Maximum number of trie nodes per shard to load into the shard cache on
+    /// startup, 0 disables the warm-up.
+    pub cache_warmup_max_nodes: usize,
+    /// Total size of values in the chunk cache above which mischarged storage
+    /// operations are reported.
+    pub chunk_cache_size_limit: u64,
+}
+
+impl Default for TrieConfig {
+    fn default() -> Self {
+        Self {
+            shard_cache_config: Default::default(),
+            view_shard_cache_config: TrieCacheConfig::default_view(),
+            enable_receipt_prefetching: false,
+            sweat_prefetch_receivers: vec![],
+            sweat_prefetch_senders: vec![],
+            prefetch_staging_max_bytes: DEFAULT_PREFETCH_STAGING_MAX_BYTES,
+            num_prefetch_threads: DEFAULT_NUM_PREFETCH_THREADS,
+            prefetch_min_hit_rate: DEFAULT_PREFETCH_MIN_HIT_RATE,
+            cache_warmup_max_nodes: DEFAULT_CACHE_WARMUP_MAX_NODES,
+            chunk_cache_size_limit: DEFAULT_CHUNK_CACHE_SIZE_LIMIT,
+        }
+    }
 }
 
 impl TrieConfig {
@@ -38,6 +92,11 @@ impl TrieConfig {
     pub fn from_store_config(config: &StoreConfig) -> Self {
         let mut this = TrieConfig::default();
 
+        this.shard_cache_config = config.trie_cache.clone();
+        this.view_shard_cache_config = config.view_trie_cache.clone();
+
+        // Applied after `trie_cache`, so that per-shard overrides in the
+        // deprecated format are not lost.
         if !config.trie_cache_capacities.is_empty() {
             warn!(target: "store", "`trie_cache_capacities` is deprecated, use `trie_cache` and `view_trie_cache` instead");
             for (shard_uid, capacity) in &config.trie_cache_capacities {
@@ -46,10 +105,12 @@ impl TrieConfig {
             }
         }
 
-        this.shard_cache_config = config.trie_cache.clone();
-        this.view_shard_cache_config = config.view_trie_cache.clone();
-
         this.enable_receipt_prefetching = config.enable_receipt_prefetching;
+        this.prefetch_staging_max_bytes = config.prefetch_staging_max_memory.as_u64() as usize;
+        this.num_prefetch_threads = config.num_prefetch_threads;
+        this.prefetch_min_hit_rate = config.prefetch_min_hit_rate;
+        this.cache_warmup_max_nodes = config.trie_cache_warmup_max_nodes;
+        this.chunk_cache_size_limit = config.chunk_cache_size_limit.as_u64();
         for account in &config.sweat_prefetch_receivers {
             match AccountId::from_str(account) {
                 Ok(account_id) => this.sweat_prefetch_receivers.push(account_id),
@@ -66,11 +127,6 @@ impl TrieConfig {
         this
     }
 
-    /// Size limit in bytes per single value for caching in shard caches.
-    pub fn max_cached_value_size() -> usize {
-        TRIE_LIMIT_CACHED_VALUE_SIZE
-    }
-
     /// Capacity for deletion queue in which nodes are after unforced eviction.
     ///
     /// The shard cache uses LRU eviction policy for forced evictions. But when a
@@ -92,12 +148,11 @@ impl TrieConfig {
     /// We no longer commit to a hard limit on this. But we make sure that the old
     /// worst-case assumption of how much memory would be consumed still works.
     /// Specifically, the old calculation ignored `PER_ENTRY_OVERHEAD` and used
-    /// `max_cached_value_size()` only to figure out a good value for how many
+    /// `TRIE_LIMIT_CACHED_VALUE_SIZE` only to figure out a good value for how many
     /// nodes we want in the cache at most.
     /// This implicit limit should result in the same min number of nodes and
     /// same max memory consumption as the old config.
     pub(crate) fn deprecated_num_entry_to_memory_limit(max_num_entries: u64) -> u64 {
-        max_num_entries
-            * (TrieCacheInner::PER_ENTRY_OVERHEAD + TrieConfig::max_cached_value_size() as u64)
+        max_num_entries * (TrieCacheInner::PER_ENTRY_OVERHEAD + TRIE_LIMIT_CACHED_VALUE_SIZE as u64)
     }
 }
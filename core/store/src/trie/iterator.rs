@@ -4,6 +4,10 @@ use crate::trie::nibble_slice::NibbleSlice;
 use crate::trie::{TrieNode, TrieNodeWithSize, ValueHandle};
 use crate::{StorageError, Trie};
 
+/// How many values `visit_nodes_interval` collects before reading them from
+/// the storage in one batch.
+const VALUE_BATCH_SIZE: usize = 256;
+
 #[derive(Debug)]
 struct Crumb {
     node: TrieNodeWithSize,
@@ -44,6 +48,9 @@ pub struct TrieIterator<'a> {
 
     /// If not `None`, a list of all nodes that the iterator has visited.
     visited_nodes: Option<Vec<std::sync::Arc<[u8]>>>,
+
+    /// If not `None`, nibbles of the key at which the iteration stops.
+    end_nibbles: Option<Vec<u8>>,
 }
 
 pub type TrieItem = (Vec<u8>, Vec<u8>);
@@ -65,6 +72,7 @@ impl<'a> TrieIterator<'a> {
             trail: Vec::with_capacity(8),
             key_nibbles: Vec::with_capacity(64),
             visited_nodes: None,
+            end_nibbles: None,
         };
         r.descend_into_node(&trie.root)?;
         Ok(r)
@@ -75,6 +83,28 @@ impl<'a> TrieIterator<'a> {
         self.seek_nibble_slice(NibbleSlice::new(key.as_ref()), true).map(drop)
     }
 
+    /// Position the iterator on the first element with key >= `key`.
+    pub fn seek<K: AsRef<[u8]>>(&mut self, key: K) -> Result<(), StorageError> {
+        self.seek_nibble_slice(NibbleSlice::new(key.as_ref()), false).map(drop)
+    }
+
+    /// Stops the iteration before the first element with key >= `end`.
+    ///
+    /// Nodes which only contain keys past `end` are not read from storage.
+    pub fn set_end<K: AsRef<[u8]>>(&mut self, end: K) {
+        self.end_nibbles = Some(NibbleSlice::new(end.as_ref()).iter().collect());
+    }
+
+    /// Whether all keys under the current position are past the end of the
+    /// iteration.
+    ///
+    /// Keys in the subtree of the current position all start with
+    /// `key_nibbles`, so they are all >= `end_nibbles` if `key_nibbles` is.
+    /// Since the iteration visits keys in ascending order, it can stop there.
+    fn is_past_end(&self) -> bool {
+        matches!(&self.end_nibbles, Some(end_nibbles) if self.key_nibbles >= *end_nibbles)
+    }
+
     /// Configures whether the iterator should remember all the nodes its
     /// visiting.
     ///
@@ -220,14 +250,18 @@ impl<'a> TrieIterator<'a> {
                 }
                 Some(IterStep::PopTrail)
             }
-            (CrumbStatus::At, TrieNode::Branch(_, Some(value))) => {
-                let hash = match value {
-                    ValueHandle::HashAndSize(_, hash) => *hash,
-                    ValueHandle::InMemory(_node) => unreachable!(),
-                };
-                Some(IterStep::Value(hash))
+            (CrumbStatus::At, TrieNode::Branch(children, value)) => {
+                // The children are visited next, unless the iteration stops
+                // before reaching them.
+                let child_hashes: Vec<CryptoHash> =
+                    children.iter().flatten().map(|child| *child.unwrap_hash()).collect();
+                self.trie.storage.read_ahead(&child_hashes);
+                match value {
+                    Some(ValueHandle::HashAndSize(_, hash)) => Some(IterStep::Value(*hash)),
+                    Some(ValueHandle::InMemory(_node)) => unreachable!(),
+                    None => Some(IterStep::Continue),
+                }
             }
-            (CrumbStatus::At, TrieNode::Branch(_, None)) => Some(IterStep::Continue),
             (CrumbStatus::At, TrieNode::Leaf(key, value)) => {
                 let hash = match value {
                     ValueHandle::HashAndSize(_, hash) => *hash,
@@ -313,6 +347,9 @@ impl<'a> TrieIterator<'a> {
             return Ok(vec![]);
         }
         let mut nodes_list = Vec::new();
+        // Values are not needed to continue the traversal, so they are read
+        // in batches.
+        let mut value_hashes = Vec::with_capacity(VALUE_BATCH_SIZE);
 
         // Actually (self.key_nibbles[..] == path_begin) always because path_begin always ends in a node
         if &self.key_nibbles[..] >= path_begin {
@@ -342,7 +379,11 @@ impl<'a> TrieIterator<'a> {
                 }
                 IterStep::Continue => {}
                 IterStep::Value(hash) => {
-                    self.trie.storage.retrieve_raw_bytes(&hash)?;
+                    value_hashes.push(hash);
+                    if value_hashes.len() == VALUE_BATCH_SIZE {
+                        self.trie.storage.retrieve_raw_bytes_batch(&value_hashes)?;
+                        value_hashes.clear();
+                    }
                     nodes_list.push(TrieTraversalItem {
                         hash,
                         key: self.has_value().then(|| self.key()),
@@ -350,6 +391,7 @@ impl<'a> TrieIterator<'a> {
                 }
             }
         }
+        self.trie.storage.retrieve_raw_bytes_batch(&value_hashes)?;
         Ok(nodes_list)
     }
 }
@@ -367,6 +409,11 @@ impl<'a> Iterator for TrieIterator<'a> {
     fn next(&mut self) -> Option<Self::Item> {
         loop {
             let iter_step = self.iter_step()?;
+            if matches!(iter_step, IterStep::Descend(_) | IterStep::Value(_)) && self.is_past_end()
+            {
+                self.trail.clear();
+                return None;
+            }
             match iter_step {
                 IterStep::PopTrail => {
                     self.trail.pop();
@@ -435,10 +482,13 @@ mod tests {
             let min_key = map.keys().min().unwrap_or(&empty_vec);
             test_get_trie_items(&trie, &map, &[], &[]);
             test_get_trie_items(&trie, &map, min_key, max_key);
+            test_iter_range(&trie, &map, min_key, max_key);
             for (seek_key, _) in trie_changes.iter() {
                 test_seek_prefix(&trie, &map, seek_key);
                 test_get_trie_items(&trie, &map, min_key, seek_key);
                 test_get_trie_items(&trie, &map, seek_key, max_key);
+                test_iter_range(&trie, &map, min_key, seek_key);
+                test_iter_range(&trie, &map, seek_key, max_key);
             }
             for _ in 0..20 {
                 let alphabet = &b"abcdefgh"[0..rng.gen_range(2..8)];
@@ -452,6 +502,7 @@ mod tests {
                 let path_begin = seek_key.clone().min(seek_key2.clone());
                 let path_end = seek_key.clone().max(seek_key2.clone());
                 test_get_trie_items(&trie, &map, &path_begin, &path_end);
+                test_iter_range(&trie, &map, &path_begin, &path_end);
             }
         }
     }
@@ -479,6 +530,13 @@ mod tests {
         assert_eq!(result1, result2);
     }
 
+    fn test_iter_range(trie: &Trie, map: &BTreeMap<Vec<u8>, Vec<u8>>, start: &[u8], end: &[u8]) {
+        let result1: Vec<_> = trie.iter_range(start, end).unwrap().map(Result::unwrap).collect();
+        let result2: Vec<_> =
+            map.range(start.to_vec()..end.to_vec()).map(|(k, v)| (k.clone(), v.clone())).collect();
+        assert_eq!(result1, result2);
+    }
+
     fn test_seek_prefix(trie: &Trie, map: &BTreeMap<Vec<u8>, Vec<u8>>, seek_key: &[u8]) {
         let mut iterator = trie.iter().unwrap();
         iterator.seek_prefix(&seek_key).unwrap();
@@ -497,6 +555,14 @@ mod tests {
             .filter(|(x, _)| x.starts_with(seek_key))
             .collect();
         assert_eq!(got, want);
+
+        let got: Vec<_> = trie.iter_prefix(seek_key).unwrap().map(Result::unwrap).collect();
+        let want: Vec<_> = map
+            .range(seek_key.to_vec()..)
+            .take_while(|(k, _)| k.starts_with(seek_key))
+            .map(|(k, v)| (k.clone(), v.clone()))
+            .collect();
+        assert_eq!(got, want);
     }
 
     #[test]
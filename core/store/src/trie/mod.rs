@@ -1,4 +1,4 @@
-use std::cell::RefCell;
+use std::cell::{Cell, RefCell};
 use std::collections::HashMap;
 use std::io::Read;
 
@@ -17,13 +17,19 @@ use near_primitives::types::{StateRoot, StateRootNode};
 
 use crate::flat_state::FlatState;
 pub use crate::trie::config::TrieConfig;
-pub(crate) use crate::trie::config::DEFAULT_SHARD_CACHE_TOTAL_SIZE_LIMIT;
+pub(crate) use crate::trie::config::{
+    DEFAULT_CACHE_WARMUP_MAX_NODES, DEFAULT_CHUNK_CACHE_SIZE_LIMIT, DEFAULT_PREFETCH_MIN_HIT_RATE,
+    DEFAULT_SHARD_CACHE_TOTAL_SIZE_LIMIT, DEFAULT_VIEW_TRIE_LIMIT_CACHED_VALUE_SIZE,
+    TRIE_LIMIT_CACHED_VALUE_SIZE,
+};
 use crate::trie::insert_delete::NodesStorage;
 use crate::trie::iterator::TrieIterator;
 pub use crate::trie::nibble_slice::NibbleSlice;
 pub use crate::trie::prefetching_trie_storage::PrefetchApi;
 pub use crate::trie::shard_tries::{KeyForStateChanges, ShardTries, WrappedTrieChanges};
-pub use crate::trie::trie_storage::{TrieCache, TrieCachingStorage, TrieDBStorage, TrieStorage};
+pub use crate::trie::trie_storage::{
+    TrieCache, TrieCacheStats, TrieCachingStorage, TrieDBStorage, TrieStorage,
+};
 use crate::trie::trie_storage::{TrieMemoryPartialStorage, TrieRecordingStorage};
 use crate::StorageError;
 pub use near_primitives::types::TrieNodesCount;
@@ -572,6 +578,10 @@ impl Trie {
             store: storage.store.clone(),
             shard_uid: storage.shard_uid,
             recorded: RefCell::new(Default::default()),
+            db_read_nodes: Cell::new(0),
+            mem_read_nodes: Cell::new(0),
+            read_ahead_prefetcher: storage.read_ahead_prefetcher().cloned(),
+            cold_store: storage.cold_store.clone(),
         };
         Trie { storage: Box::new(storage), root: self.root.clone(), flat_state: None }
     }
@@ -590,6 +600,8 @@ impl Trie {
         let storage = Box::new(TrieMemoryPartialStorage {
             recorded_storage,
             visited_nodes: Default::default(),
+            db_read_nodes: Cell::new(0),
+            mem_read_nodes: Cell::new(0),
         });
         Self::new(storage, root, None)
     }
@@ -986,6 +998,25 @@ impl Trie {
         TrieIterator::new(self)
     }
 
+    /// Iterates over all items with keys starting with `prefix`, in key order.
+    pub fn iter_prefix<'a>(&'a self, prefix: &[u8]) -> Result<TrieIterator<'a>, StorageError> {
+        let mut iter = TrieIterator::new(self)?;
+        iter.seek_prefix(prefix)?;
+        Ok(iter)
+    }
+
+    /// Iterates over all items with keys in `[start, end)`, in key order.
+    pub fn iter_range<'a>(
+        &'a self,
+        start: &[u8],
+        end: &[u8],
+    ) -> Result<TrieIterator<'a>, StorageError> {
+        let mut iter = TrieIterator::new(self)?;
+        iter.seek(start)?;
+        iter.set_end(end);
+        Ok(iter)
+    }
+
     pub fn get_trie_nodes_count(&self) -> TrieNodesCount {
         self.storage.get_trie_nodes_count()
     }
@@ -1,4 +1,4 @@
-use crate::trie::POISONED_LOCK_ERR;
+use crate::trie::{KeyLookupMode, POISONED_LOCK_ERR};
 use crate::{
     metrics, DBCol, StorageError, Store, Trie, TrieCache, TrieCachingStorage, TrieConfig,
     TrieStorage,
@@ -6,25 +6,31 @@ use crate::{
 use crossbeam::select;
 use near_o11y::metrics::prometheus;
 use near_o11y::metrics::prometheus::core::GenericGauge;
-use near_o11y::tracing::error;
+use near_o11y::tracing::{error, info, warn};
 use near_primitives::hash::CryptoHash;
 use near_primitives::shard_layout::ShardUId;
 use near_primitives::trie_key::TrieKey;
 use near_primitives::types::{AccountId, ShardId, StateRoot, TrieNodesCount};
-use std::collections::HashMap;
-use std::sync::{Arc, Mutex};
+use std::collections::{HashMap, VecDeque};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Condvar, Mutex};
 use std::thread;
 
 const MAX_QUEUED_WORK_ITEMS: usize = 16 * 1024;
-const MAX_PREFETCH_STAGING_MEMORY: usize = 200 * 1024 * 1024;
+/// How many queued work items an IO thread takes at once. The values of all
+/// keys in such a batch are read from the DB together.
+const MAX_WORK_ITEMS_PER_BATCH: usize = 8;
 /// How much memory capacity is reserved for each prefetch request before
 /// sending it. Once the value is fetched, the actual size is used instead.
 /// Set to 4MiB, the same as `max_length_storage_value`.
 const PREFETCH_RESERVED_BYTES_PER_SLOT: usize = 4 * 1024 * 1024;
-/// How many threads will be prefetching data, without the scheduler thread.
-/// Because the storage driver is blocking, there is only one request per thread
-/// at a time.
-const NUM_IO_THREADS: usize = 8;
+/// Number of most recent chunks the prefetch hit rate is computed over.
+const HIT_RATE_WINDOW_CHUNKS: usize = 50;
+/// Prefetching is only paused if at least this many values were prefetched
+/// in the window, a handful of unused values are not worth pausing for.
+const HIT_RATE_MIN_FETCHED: u64 = 1000;
+/// Number of chunks applied without prefetching once it has been paused.
+const HIT_RATE_PAUSE_CHUNKS: u64 = 500;
 
 /// Storage used by I/O threads to prefetch data.
 ///
@@ -49,6 +55,11 @@ struct TriePrefetchingStorage {
     shard_cache: TrieCache,
     /// Shared with parent `TrieCachingStorage`.
     prefetching: PrefetchStagingArea,
+    /// Current generation of prefetch requests, shared with `PrefetchApi`.
+    generation: Arc<AtomicU64>,
+    /// Generation of the request currently served by this storage. Once the
+    /// shared generation moves past it, no more DB reads are started.
+    request_generation: u64,
 }
 
 /// This type is shared between runtime crate and store crate.
@@ -60,13 +71,19 @@ struct TriePrefetchingStorage {
 pub struct PrefetchApi {
     /// Bounded, shared queue for all IO threads to take work from.
     ///
-    /// Work items are defined as `TrieKey` because currently the only
-    /// work is to prefetch a trie key. If other IO work is added, consider
-    /// changing the queue to an enum.
-    /// The state root is also included because multiple chunks could be applied
-    /// at the same time.
-    work_queue_tx: crossbeam::channel::Sender<(StateRoot, TrieKey)>,
-    work_queue_rx: crossbeam::channel::Receiver<(StateRoot, TrieKey)>,
+    /// Each item is tagged with the generation it was queued in, see
+    /// `generation`.
+    work_queue_tx: crossbeam::channel::Sender<(u64, PrefetchWorkItem)>,
+    work_queue_rx: crossbeam::channel::Receiver<(u64, PrefetchWorkItem)>,
+    /// Incremented every time queued requests are cancelled with
+    /// `clear_queue`. Requests from an older generation are dropped by the IO
+    /// threads before they hit the DB.
+    generation: Arc<AtomicU64>,
+    /// Decides whether prefetching is used for the next chunk, see
+    /// `begin_chunk`.
+    hit_rate_monitor: Arc<Mutex<PrefetchHitRateMonitor>>,
+    /// Used to pause prefetching while the disk latency is degraded.
+    store: Store,
     /// Prefetching IO threads will insert fetched data here. This is also used
     /// to mark what is already being fetched, to avoid fetching the same data
     /// multiple times.
@@ -81,6 +98,16 @@ pub struct PrefetchApi {
     pub shard_uid: ShardUId,
 }
 
+/// Work for the prefetching IO threads.
+enum PrefetchWorkItem {
+    /// Look up a trie key, reading all nodes on its path and the value.
+    /// The state root is included because multiple chunks could be applied
+    /// at the same time.
+    TrieKey(StateRoot, TrieKey),
+    /// Read the given trie nodes, see `TrieCachingStorage::set_read_ahead`.
+    TrieNodes(Vec<CryptoHash>),
+}
+
 /// Staging area for in-flight prefetch requests and a buffer for prefetched data.
 ///
 /// Before starting a pre-fetch, a slot is reserved for it. Once the data is
@@ -94,11 +121,22 @@ pub struct PrefetchApi {
 /// This design also ensures the shard cache works exactly the same with or
 /// without the prefetcher, because the order in which it sees accesses is
 /// independent of the prefetcher.
+///
+/// The condition variable is notified whenever a slot is filled or removed,
+/// to wake up threads waiting in `blocking_get`.
 #[derive(Clone)]
-pub(crate) struct PrefetchStagingArea(Arc<Mutex<InnerPrefetchStagingArea>>);
+pub(crate) struct PrefetchStagingArea(Arc<Mutex<InnerPrefetchStagingArea>>, Arc<Condvar>);
 
 struct InnerPrefetchStagingArea {
     slots: SizeTrackedHashMap,
+    /// No new slots are reserved while the staging area uses more memory
+    /// than this.
+    max_bytes: usize,
+    /// Values inserted by the IO threads since the last `take_usage`.
+    fetched: u64,
+    /// Prefetched values released by the main thread since the last
+    /// `take_usage`.
+    used: u64,
 }
 
 /// Result when atomically accessing the prefetch staging area.
@@ -189,8 +227,10 @@ impl TrieStorage for TriePrefetchingStorage {
     // Note: This is the tricky bit of the implementation.
     // We have to retrieve data only once in many threads, so all IO threads
     // have to go though the staging area and check for inflight requests.
-    // The shard cache mutex plus the prefetch staging area mutex are used for
-    // that in combination. Let's call the first lock S and the second P.
+    // The mutex of the shard cache segment containing the value plus the
+    // prefetch staging area mutex are used for that in combination. Let's call
+    // the first lock S and the second P. Values in different segments do not
+    // race with each other, so S only needs to be the lock of the segment.
     // The rules for S and P are:
     // 1. To avoid deadlocks, S must always be requested before P, if they are
     //    held at the same time.
@@ -201,8 +241,16 @@ impl TrieStorage for TriePrefetchingStorage {
     // 3. IO threads should release S and P as soon as possible, as they can
     //    block the main thread otherwise.
     fn retrieve_raw_bytes(&self, hash: &CryptoHash) -> Result<Arc<[u8]>, StorageError> {
+        // The chunk this request was made for has been applied already, the
+        // data is not needed anymore.
+        if self.is_cancelled() {
+            return Err(StorageError::StorageInconsistentState(format!(
+                "Prefetch request cancelled on hash {hash}"
+            )));
+        }
+
         // Try to get value from shard cache containing most recently touched nodes.
-        let mut shard_cache_guard = self.shard_cache.0.lock().expect(POISONED_LOCK_ERR);
+        let mut shard_cache_guard = self.shard_cache.lock(hash);
         if let Some(val) = shard_cache_guard.get(hash) {
             return Ok(val);
         }
@@ -241,15 +289,12 @@ impl TrieStorage for TriePrefetchingStorage {
             }
             PrefetcherResult::Prefetched(value) => Ok(value),
             PrefetcherResult::Pending => {
-                // yield once before calling `block_get` that will check for data to be present again.
-                thread::yield_now();
                 self.prefetching
                     .blocking_get(hash.clone())
                     .or_else(|| {
                         // `blocking_get` will return None if the prefetch slot has been removed
                         // by the main thread and the value inserted into the shard cache.
-                        let mut guard = self.shard_cache.0.lock().expect(POISONED_LOCK_ERR);
-                        guard.get(hash)
+                        self.shard_cache.get(hash)
                     })
                     .ok_or_else(|| {
                         // This could only happen if this thread started prefetching a value
@@ -271,6 +316,58 @@ impl TrieStorage for TriePrefetchingStorage {
         }
     }
 
+    fn retrieve_raw_bytes_batch(
+        &self,
+        hashes: &[CryptoHash],
+    ) -> Result<Vec<Arc<[u8]>>, StorageError> {
+        if self.is_cancelled() {
+            return Err(StorageError::StorageInconsistentState(
+                "Prefetch request cancelled on batch".to_string(),
+            ));
+        }
+
+        // Reserve slots for everything that is neither in the shard cache nor
+        // in the staging area, holding S while P is updated as in
+        // `retrieve_raw_bytes`.
+        let mut reserved = Vec::new();
+        for hash in hashes {
+            let mut shard_cache_guard = self.shard_cache.lock(hash);
+            if shard_cache_guard.get(hash).is_some() {
+                continue;
+            }
+            if let PrefetcherResult::SlotReserved =
+                self.prefetching.get_and_set_if_empty(*hash, PrefetchSlot::PendingPrefetch)
+            {
+                reserved.push(*hash);
+            }
+        }
+
+        // Fill all reserved slots with one DB read. Slots that cannot be
+        // filled are released, the lookup below then reports the error.
+        let keys: Vec<_> = reserved
+            .iter()
+            .map(|hash| TrieCachingStorage::get_key_from_shard_uid_and_hash(self.shard_uid, hash))
+            .collect();
+        let keys: Vec<&[u8]> = keys.iter().map(|key| key.as_ref()).collect();
+        match self.store.multi_get(DBCol::State, &keys) {
+            Ok(values) => {
+                for (hash, value) in reserved.iter().zip(values) {
+                    match value {
+                        Some(value) => self.prefetching.insert_fetched(*hash, value.into()),
+                        None => self.prefetching.release(hash),
+                    }
+                }
+            }
+            Err(_) => {
+                for hash in &reserved {
+                    self.prefetching.release(hash);
+                }
+            }
+        }
+
+        hashes.iter().map(|hash| self.retrieve_raw_bytes(hash)).collect()
+    }
+
     fn get_trie_nodes_count(&self) -> TrieNodesCount {
         unimplemented!()
     }
@@ -282,22 +379,36 @@ impl TriePrefetchingStorage {
         shard_uid: ShardUId,
         shard_cache: TrieCache,
         prefetching: PrefetchStagingArea,
+        generation: Arc<AtomicU64>,
     ) -> Self {
-        Self { store, shard_uid, shard_cache, prefetching }
+        let request_generation = generation.load(Ordering::Acquire);
+        Self { store, shard_uid, shard_cache, prefetching, generation, request_generation }
+    }
+
+    /// Returns a copy of this storage that serves a request queued in the given generation.
+    fn for_generation(&self, request_generation: u64) -> Self {
+        Self { request_generation, ..self.clone() }
+    }
+
+    fn is_cancelled(&self) -> bool {
+        self.request_generation != self.generation.load(Ordering::Acquire)
     }
 }
 
 impl PrefetchStagingArea {
-    fn new(shard_id: ShardId) -> Self {
+    fn new(shard_id: ShardId, max_bytes: usize) -> Self {
         let inner = InnerPrefetchStagingArea {
             slots: SizeTrackedHashMap {
                 map: Default::default(),
                 size_bytes: 0,
                 metrics: StagedMetrics::new(shard_id),
             },
+            max_bytes,
+            fetched: 0,
+            used: 0,
         };
         inner.slots.update_metrics();
-        Self(Arc::new(Mutex::new(inner)))
+        Self(Arc::new(Mutex::new(inner)), Arc::new(Condvar::new()))
     }
 
     /// Release a slot in the prefetcher staging area.
@@ -310,6 +421,9 @@ impl PrefetchStagingArea {
     pub(crate) fn release(&self, key: &CryptoHash) {
         let mut guard = self.0.lock().expect(POISONED_LOCK_ERR);
         let dropped = guard.slots.remove(key);
+        if let Some(PrefetchSlot::Done(_)) = dropped {
+            guard.used += 1;
+        }
         // `Done` is the result after a successful prefetch.
         // `PendingFetch` means the value has been read without a prefetch.
         // `None` means prefetching was stopped due to memory limits.
@@ -321,23 +435,28 @@ impl PrefetchStagingArea {
                 )
                 || prefetch_state_matches(PrefetchSlot::PendingFetch, dropped.as_ref().unwrap()),
         );
+        std::mem::drop(guard);
+        self.1.notify_all();
     }
 
     /// Block until value is prefetched and then return it.
     ///
+    /// Returns `None` if the slot is removed before the value arrives.
+    ///
     /// Note: This function could return a future and become async.
     /// DB requests are all blocking, unfortunately, so the benefit seems small.
     /// The main benefit would be if many IO threads end up prefetching the
     /// same data and thus are waiting on each other rather than the DB.
     /// Of course, that would require prefetching to be moved into an async environment,
     pub(crate) fn blocking_get(&self, key: CryptoHash) -> Option<Arc<[u8]>> {
+        let mut guard = self.0.lock().expect(POISONED_LOCK_ERR);
         loop {
-            match self.0.lock().expect(POISONED_LOCK_ERR).slots.get(&key) {
+            match guard.slots.get(&key) {
                 Some(PrefetchSlot::Done(value)) => return Some(value.clone()),
                 Some(_) => (),
                 None => return None,
             }
-            thread::sleep(std::time::Duration::from_micros(1));
+            guard = self.1.wait(guard).expect(POISONED_LOCK_ERR);
         }
     }
 
@@ -348,7 +467,18 @@ impl PrefetchStagingArea {
     }
 
     fn insert_fetched(&self, key: CryptoHash, value: Arc<[u8]>) {
-        self.0.lock().expect(POISONED_LOCK_ERR).slots.insert(key, PrefetchSlot::Done(value));
+        let mut guard = self.0.lock().expect(POISONED_LOCK_ERR);
+        guard.slots.insert(key, PrefetchSlot::Done(value));
+        guard.fetched += 1;
+        std::mem::drop(guard);
+        self.1.notify_all();
+    }
+
+    /// Returns the number of prefetched values and how many of them were used
+    /// by the main thread since the last call.
+    fn take_usage(&self) -> (u64, u64) {
+        let mut guard = self.0.lock().expect(POISONED_LOCK_ERR);
+        (std::mem::take(&mut guard.fetched), std::mem::take(&mut guard.used))
     }
 
     /// Get prefetched value if available and otherwise atomically insert the
@@ -359,8 +489,8 @@ impl PrefetchStagingArea {
         set_if_empty: PrefetchSlot,
     ) -> PrefetcherResult {
         let mut guard = self.0.lock().expect(POISONED_LOCK_ERR);
-        let full =
-            guard.slots.size_bytes > MAX_PREFETCH_STAGING_MEMORY - PREFETCH_RESERVED_BYTES_PER_SLOT;
+        let full = guard.slots.size_bytes
+            > guard.max_bytes.saturating_sub(PREFETCH_RESERVED_BYTES_PER_SLOT);
         match guard.slots.map.get(&key) {
             Some(value) => match value {
                 PrefetchSlot::Done(value) => PrefetcherResult::Prefetched(value.clone()),
@@ -379,11 +509,80 @@ impl PrefetchStagingArea {
     }
 }
 
+/// Outcome of `PrefetchHitRateMonitor::next_chunk`.
+#[derive(Debug, PartialEq)]
+enum PrefetchDecision {
+    Prefetch,
+    /// The hit rate over the window dropped below the threshold.
+    Pause {
+        hit_rate: f64,
+    },
+    Paused,
+    Resume,
+}
+
+/// Tracks which share of the prefetched values is used by the main thread
+/// over a sliding window of chunks.
+///
+/// Prefetching values that are never read only adds IO load, for example if
+/// prefetching is configured for the wrong accounts.  When the hit rate drops
+/// below the threshold, prefetching is paused for `HIT_RATE_PAUSE_CHUNKS`
+/// chunks and then tried again.
+struct PrefetchHitRateMonitor {
+    min_hit_rate: f64,
+    /// Prefetched and used values of the most recent chunks.
+    window: VecDeque<(u64, u64)>,
+    /// Number of chunks left to apply without prefetching.
+    paused_chunks_left: u64,
+}
+
+impl PrefetchHitRateMonitor {
+    fn new(min_hit_rate: f64) -> Self {
+        Self {
+            min_hit_rate,
+            window: VecDeque::with_capacity(HIT_RATE_WINDOW_CHUNKS + 1),
+            paused_chunks_left: 0,
+        }
+    }
+
+    /// Records the values prefetched and used for the previous chunk and
+    /// decides about prefetching for the next one.
+    fn next_chunk(&mut self, fetched: u64, used: u64) -> PrefetchDecision {
+        if self.paused_chunks_left > 0 {
+            self.paused_chunks_left -= 1;
+            return if self.paused_chunks_left == 0 {
+                PrefetchDecision::Resume
+            } else {
+                PrefetchDecision::Paused
+            };
+        }
+        self.window.push_back((fetched, used));
+        if self.window.len() > HIT_RATE_WINDOW_CHUNKS {
+            self.window.pop_front();
+        }
+        if self.window.len() < HIT_RATE_WINDOW_CHUNKS {
+            return PrefetchDecision::Prefetch;
+        }
+        let (fetched, used) = self
+            .window
+            .iter()
+            .fold((0, 0), |(fetched, used), chunk| (fetched + chunk.0, used + chunk.1));
+        let hit_rate = used as f64 / fetched.max(1) as f64;
+        if fetched >= HIT_RATE_MIN_FETCHED && hit_rate < self.min_hit_rate {
+            self.window.clear();
+            self.paused_chunks_left = HIT_RATE_PAUSE_CHUNKS;
+            return PrefetchDecision::Pause { hit_rate };
+        }
+        PrefetchDecision::Prefetch
+    }
+}
+
 impl PrefetchApi {
     pub(crate) fn new(
         store: Store,
         shard_cache: TrieCache,
         shard_uid: ShardUId,
+        state_shard_uid: ShardUId,
         trie_config: &TrieConfig,
     ) -> (Self, PrefetchingThreadsHandle) {
         let (work_queue_tx, work_queue_rx) = crossbeam::channel::bounded(MAX_QUEUED_WORK_ITEMS);
@@ -394,19 +593,29 @@ impl PrefetchApi {
         let this = Self {
             work_queue_tx,
             work_queue_rx,
-            prefetching: PrefetchStagingArea::new(shard_uid.shard_id()),
+            prefetching: PrefetchStagingArea::new(
+                shard_uid.shard_id(),
+                trie_config.prefetch_staging_max_bytes,
+            ),
+            generation: Arc::new(AtomicU64::new(0)),
+            hit_rate_monitor: Arc::new(Mutex::new(PrefetchHitRateMonitor::new(
+                trie_config.prefetch_min_hit_rate,
+            ))),
+            store: store.clone(),
             enable_receipt_prefetching,
             sweat_prefetch_receivers,
             sweat_prefetch_senders,
             shard_uid,
         };
         let (shutdown_tx, shutdown_rx) = crossbeam::channel::bounded(1);
-        let handles = (0..NUM_IO_THREADS)
+        // Because the storage driver is blocking, there is only one request per
+        // thread at a time. All threads take work from the same queue.
+        let handles = (0..trie_config.num_prefetch_threads)
             .map(|_| {
                 this.start_io_thread(
                     store.clone(),
                     shard_cache.clone(),
-                    shard_uid.clone(),
+                    state_shard_uid,
                     shutdown_rx.clone(),
                 )
             })
@@ -415,29 +624,84 @@ impl PrefetchApi {
         (this, handle)
     }
 
+    /// Called before a chunk is applied.  Pauses prefetching for a while if
+    /// the main thread used too few of the values prefetched for the previous
+    /// chunks.  Prefetching is also skipped while the disk latency is
+    /// degraded, as speculative reads only add to the load.
+    ///
+    /// Returns whether prefetch requests should be sent for the chunk.
+    pub fn begin_chunk(&self) -> bool {
+        let (fetched, used) = self.prefetching.take_usage();
+        let decision =
+            self.hit_rate_monitor.lock().expect(POISONED_LOCK_ERR).next_chunk(fetched, used);
+        let shard_id = self.shard_uid.shard_id;
+        let paused = metrics::PREFETCH_PAUSED.with_label_values(&[&shard_id.to_string()]);
+        let prefetch = match decision {
+            PrefetchDecision::Prefetch => true,
+            PrefetchDecision::Pause { hit_rate } => {
+                warn!(target: "prefetcher", shard_id, hit_rate, chunks = HIT_RATE_PAUSE_CHUNKS, "Pausing prefetching, most prefetched values are not used");
+                paused.set(1);
+                false
+            }
+            PrefetchDecision::Paused => false,
+            PrefetchDecision::Resume => {
+                info!(target: "prefetcher", shard_id, "Resuming prefetching");
+                paused.set(0);
+                true
+            }
+        };
+        prefetch && !self.store.should_shed_load("prefetch")
+    }
+
     /// Returns the argument back if queue is full.
     pub fn prefetch_trie_key(
         &self,
         root: StateRoot,
         trie_key: TrieKey,
     ) -> Result<(), (StateRoot, TrieKey)> {
-        self.work_queue_tx.try_send((root, trie_key)).map_err(|e| e.into_inner())
+        let generation = self.generation.load(Ordering::Acquire);
+        let work_item = PrefetchWorkItem::TrieKey(root, trie_key);
+        self.work_queue_tx.try_send((generation, work_item)).map_err(|e| match e.into_inner() {
+            (_generation, PrefetchWorkItem::TrieKey(root, trie_key)) => (root, trie_key),
+            (_generation, PrefetchWorkItem::TrieNodes(_)) => unreachable!(),
+        })
+    }
+
+    /// Requests trie nodes by their hashes, without looking up any keys.
+    ///
+    /// Returns the argument back if queue is full.
+    pub(crate) fn prefetch_trie_nodes(
+        &self,
+        node_hashes: Vec<CryptoHash>,
+    ) -> Result<(), Vec<CryptoHash>> {
+        let generation = self.generation.load(Ordering::Acquire);
+        let work_item = PrefetchWorkItem::TrieNodes(node_hashes);
+        self.work_queue_tx.try_send((generation, work_item)).map_err(|e| match e.into_inner() {
+            (_generation, PrefetchWorkItem::TrieNodes(node_hashes)) => node_hashes,
+            (_generation, PrefetchWorkItem::TrieKey(..)) => unreachable!(),
+        })
     }
 
     pub fn start_io_thread(
         &self,
         store: Store,
         shard_cache: TrieCache,
-        shard_uid: ShardUId,
+        state_shard_uid: ShardUId,
         shutdown_rx: crossbeam::channel::Receiver<()>,
     ) -> thread::JoinHandle<()> {
-        let prefetcher_storage =
-            TriePrefetchingStorage::new(store, shard_uid, shard_cache, self.prefetching.clone());
+        let prefetcher_storage = TriePrefetchingStorage::new(
+            store,
+            state_shard_uid,
+            shard_cache,
+            self.prefetching.clone(),
+            self.generation.clone(),
+        );
         let work_queue = self.work_queue_rx.clone();
-        let metric_prefetch_sent =
-            metrics::PREFETCH_SENT.with_label_values(&[&shard_uid.shard_id.to_string()]);
-        let metric_prefetch_fail =
-            metrics::PREFETCH_FAIL.with_label_values(&[&shard_uid.shard_id.to_string()]);
+        let current_generation = self.generation.clone();
+        let shard_id = self.shard_uid.shard_id.to_string();
+        let metric_prefetch_sent = metrics::PREFETCH_SENT.with_label_values(&[&shard_id]);
+        let metric_prefetch_fail = metrics::PREFETCH_FAIL.with_label_values(&[&shard_id]);
+        let metric_prefetch_cancelled = metrics::PREFETCH_CANCELLED.with_label_values(&[&shard_id]);
         thread::spawn(move || {
             loop {
                 let selected = select! {
@@ -445,28 +709,69 @@ impl PrefetchApi {
                     recv(work_queue) -> maybe_work_item => maybe_work_item.ok(),
                 };
 
-                match selected {
+                let first_item = match selected {
                     None => return,
-                    Some((trie_root, trie_key)) => {
-                        // Since the trie root can change,and since the root is
-                        // not known at the time when the IO threads starts,
-                        // we need to redefine the trie before each request.
-                        // Note that the constructor of `Trie` is trivial, and
-                        // the clone only clones a few `Arc`s, so the performance
-                        // hit is small.
-                        let prefetcher_trie =
-                            Trie::new(Box::new(prefetcher_storage.clone()), trie_root, None);
-                        let storage_key = trie_key.to_vec();
-                        metric_prefetch_sent.inc();
-                        if let Ok(_maybe_value) = prefetcher_trie.get(&storage_key) {
+                    Some(work_item) => work_item,
+                };
+                // Take more work if there is any, so that the values of all
+                // keys can be read from the DB in one batch.
+                let mut work_items = vec![first_item];
+                work_items.extend(work_queue.try_iter().take(MAX_WORK_ITEMS_PER_BATCH - 1));
+
+                let mut batch_storage = None;
+                // Values of the looked up keys and requested nodes, they are
+                // read from the DB together.
+                let mut batch_hashes = Vec::with_capacity(work_items.len());
+                for (generation, work_item) in work_items {
+                    let storage = prefetcher_storage.for_generation(generation);
+                    if storage.is_cancelled() {
+                        // Queued for a chunk that has been applied in the meantime.
+                        metric_prefetch_cancelled.inc();
+                        continue;
+                    }
+                    metric_prefetch_sent.inc();
+                    let (trie_root, trie_key) = match work_item {
+                        PrefetchWorkItem::TrieKey(trie_root, trie_key) => (trie_root, trie_key),
+                        PrefetchWorkItem::TrieNodes(node_hashes) => {
+                            batch_hashes.extend(node_hashes);
+                            batch_storage = Some(storage);
+                            continue;
+                        }
+                    };
+                    // Since the trie root can change,and since the root is
+                    // not known at the time when the IO threads starts,
+                    // we need to redefine the trie before each request.
+                    // Note that the constructor of `Trie` is trivial, and
+                    // the clone only clones a few `Arc`s, so the performance
+                    // hit is small.
+                    let prefetcher_trie = Trie::new(Box::new(storage.clone()), trie_root, None);
+                    let storage_key = trie_key.to_vec();
+                    match prefetcher_trie.get_ref(&storage_key, KeyLookupMode::Trie) {
+                        Ok(maybe_value_ref) => {
                             near_o11y::io_trace!(count: "prefetch");
-                        } else {
+                            batch_hashes.extend(maybe_value_ref.map(|value_ref| value_ref.hash));
+                        }
+                        Err(_) if generation != current_generation.load(Ordering::Acquire) => {
+                            // Cancelled while the trie was traversed.
+                            metric_prefetch_cancelled.inc();
+                        }
+                        Err(_) => {
                             // This may happen in rare occasions and can be ignored safely.
                             // See comments in `TriePrefetchingStorage::retrieve_raw_bytes`.
                             near_o11y::io_trace!(count: "prefetch_failure");
                             metric_prefetch_fail.inc();
                         }
                     }
+                    batch_storage = Some(storage);
+                }
+
+                if let Some(storage) = batch_storage.filter(|_| !batch_hashes.is_empty()) {
+                    if storage.retrieve_raw_bytes_batch(&batch_hashes).is_err()
+                        && !storage.is_cancelled()
+                    {
+                        near_o11y::io_trace!(count: "prefetch_failure");
+                        metric_prefetch_fail.inc();
+                    }
                 }
             }
         })
@@ -474,15 +779,19 @@ impl PrefetchApi {
 
     /// Remove queued up requests so IO threads will be paused after they finish their current task.
     ///
-    /// Queued up work will not be finished. But trie keys that are already
-    /// being fetched will finish.
+    /// Queued up work will not be finished. Trie keys that are already being
+    /// fetched stop before the next DB read, DB reads in flight still finish.
     pub fn clear_queue(&self) {
+        // Bump the generation first, so that requests taken from the queue
+        // concurrently with draining it are dropped, too.
+        self.generation.fetch_add(1, Ordering::AcqRel);
         while let Ok(_dropped) = self.work_queue_rx.try_recv() {}
     }
 
     /// Clear prefetched staging area from data that has not been picked up by the main thread.
     pub fn clear_data(&self) {
         self.prefetching.0.lock().expect(POISONED_LOCK_ERR).slots.clear();
+        self.prefetching.1.notify_all();
     }
 }
 
@@ -519,6 +828,153 @@ impl Drop for PrefetchingThreadsHandle {
     }
 }
 
+#[cfg(test)]
+mod prefetch_staging_area_tests {
+    use super::{
+        PrefetchApi, PrefetchDecision, PrefetchHitRateMonitor, PrefetchStagingArea,
+        PrefetcherResult, TriePrefetchingStorage, HIT_RATE_PAUSE_CHUNKS, HIT_RATE_WINDOW_CHUNKS,
+        PREFETCH_RESERVED_BYTES_PER_SLOT,
+    };
+    use crate::test_utils::create_test_store;
+    use crate::{DBCol, Trie, TrieCache, TrieCachingStorage, TrieConfig, TrieStorage};
+    use near_primitives::hash::hash;
+    use near_primitives::shard_layout::ShardUId;
+    use near_primitives::trie_key::TrieKey;
+    use std::sync::atomic::{AtomicU64, Ordering};
+    use std::sync::Arc;
+
+    #[test]
+    fn test_staging_area_memory_limit() {
+        let staging = PrefetchStagingArea::new(0, 2 * PREFETCH_RESERVED_BYTES_PER_SLOT);
+        let (a, b, c) = (hash(b"a"), hash(b"b"), hash(b"c"));
+        assert!(matches!(staging.get_or_set_fetching(a), PrefetcherResult::SlotReserved));
+        assert!(matches!(staging.get_or_set_fetching(b), PrefetcherResult::SlotReserved));
+        assert!(matches!(staging.get_or_set_fetching(c), PrefetcherResult::MemoryLimitReached));
+
+        // Done slots only account for the actual value size.
+        staging.insert_fetched(a, Arc::from(vec![0u8; 100]));
+        staging.release(&b);
+        assert!(matches!(staging.get_or_set_fetching(c), PrefetcherResult::SlotReserved));
+        assert_eq!(
+            staging.0.lock().unwrap().slots.size_bytes,
+            100 + PREFETCH_RESERVED_BYTES_PER_SLOT
+        );
+    }
+
+    #[test]
+    fn test_blocking_get_wakes_up() {
+        let staging = PrefetchStagingArea::new(0, 2 * PREFETCH_RESERVED_BYTES_PER_SLOT);
+        let (a, b) = (hash(b"a"), hash(b"b"));
+        assert!(matches!(staging.get_or_set_fetching(a), PrefetcherResult::SlotReserved));
+        assert!(matches!(staging.get_or_set_fetching(b), PrefetcherResult::SlotReserved));
+
+        let waiter = {
+            let staging = staging.clone();
+            std::thread::spawn(move || (staging.blocking_get(a), staging.blocking_get(b)))
+        };
+        staging.insert_fetched(a, Arc::from(vec![1u8]));
+        staging.release(&b);
+        let (value_a, value_b) = waiter.join().unwrap();
+        assert_eq!(value_a.as_deref(), Some(&[1u8][..]));
+        assert_eq!(value_b, None);
+    }
+
+    #[test]
+    fn test_staging_area_usage() {
+        let staging = PrefetchStagingArea::new(0, 4 * PREFETCH_RESERVED_BYTES_PER_SLOT);
+        let (a, b, c) = (hash(b"a"), hash(b"b"), hash(b"c"));
+        staging.insert_fetched(a, Arc::from(vec![1u8]));
+        staging.insert_fetched(b, Arc::from(vec![2u8]));
+        assert!(matches!(staging.get_or_set_fetching(c), PrefetcherResult::SlotReserved));
+        staging.release(&a);
+        // Values read by the main thread itself are not prefetched.
+        staging.release(&c);
+        assert_eq!(staging.take_usage(), (2, 1));
+        assert_eq!(staging.take_usage(), (0, 0));
+    }
+
+    #[test]
+    fn test_hit_rate_monitor() {
+        let mut monitor = PrefetchHitRateMonitor::new(0.5);
+        for _ in 1..HIT_RATE_WINDOW_CHUNKS {
+            assert_eq!(monitor.next_chunk(100, 10), PrefetchDecision::Prefetch);
+        }
+        assert_eq!(monitor.next_chunk(100, 10), PrefetchDecision::Pause { hit_rate: 0.1 });
+        for _ in 1..HIT_RATE_PAUSE_CHUNKS {
+            assert_eq!(monitor.next_chunk(0, 0), PrefetchDecision::Paused);
+        }
+        assert_eq!(monitor.next_chunk(0, 0), PrefetchDecision::Resume);
+
+        // The window starts over after resuming and chunks with good hit
+        // rates make up for bad ones.
+        for _ in 0..HIT_RATE_WINDOW_CHUNKS {
+            assert_eq!(monitor.next_chunk(100, 10), PrefetchDecision::Prefetch);
+            assert_eq!(monitor.next_chunk(100, 100), PrefetchDecision::Prefetch);
+        }
+
+        // Too few prefetched values are not worth pausing for.
+        let mut monitor = PrefetchHitRateMonitor::new(0.5);
+        for _ in 0..2 * HIT_RATE_WINDOW_CHUNKS {
+            assert_eq!(monitor.next_chunk(1, 0), PrefetchDecision::Prefetch);
+        }
+
+        let mut monitor = PrefetchHitRateMonitor::new(0.0);
+        for _ in 0..2 * HIT_RATE_WINDOW_CHUNKS {
+            assert_eq!(monitor.next_chunk(100, 0), PrefetchDecision::Prefetch);
+        }
+    }
+
+    #[test]
+    fn test_cancelled_request_skips_db() {
+        let shard_uid = ShardUId::single_shard();
+        let store = create_test_store();
+        let value = b"trie node".to_vec();
+        let node_hash = hash(&value);
+        let mut store_update = store.store_update();
+        let key = TrieCachingStorage::get_key_from_shard_uid_and_hash(shard_uid, &node_hash);
+        store_update.increment_refcount(DBCol::State, key.as_ref(), &value);
+        store_update.commit().unwrap();
+
+        let staging = PrefetchStagingArea::new(0, 2 * PREFETCH_RESERVED_BYTES_PER_SLOT);
+        let generation = Arc::new(AtomicU64::new(0));
+        let storage = TriePrefetchingStorage::new(
+            store,
+            shard_uid,
+            TrieCache::new(&TrieConfig::default(), shard_uid, false),
+            staging.clone(),
+            generation.clone(),
+        );
+        generation.fetch_add(1, Ordering::AcqRel);
+        assert!(storage.retrieve_raw_bytes(&node_hash).is_err());
+        assert!(staging.0.lock().unwrap().slots.map.is_empty());
+
+        let storage = storage.for_generation(1);
+        assert_eq!(storage.retrieve_raw_bytes(&node_hash).unwrap().as_ref(), value.as_slice());
+    }
+
+    #[test]
+    fn test_clear_queue_starts_new_generation() {
+        let shard_uid = ShardUId::single_shard();
+        // Without IO threads, requests stay in the queue until cleared.
+        let trie_config = TrieConfig { num_prefetch_threads: 0, ..TrieConfig::default() };
+        let (api, _handle) = PrefetchApi::new(
+            create_test_store(),
+            TrieCache::new(&trie_config, shard_uid, false),
+            shard_uid,
+            shard_uid,
+            &trie_config,
+        );
+        let trie_key = TrieKey::Account { account_id: "alice.near".parse().unwrap() };
+        api.prefetch_trie_key(Trie::EMPTY_ROOT, trie_key.clone()).unwrap();
+        api.clear_queue();
+        assert!(api.work_queue_rx.is_empty());
+
+        api.prefetch_trie_key(Trie::EMPTY_ROOT, trie_key).unwrap();
+        let (generation, _work_item) = api.work_queue_rx.try_recv().unwrap();
+        assert_eq!(generation, 1);
+    }
+}
+
 /// Implementation to make testing from runtime possible.
 ///
 /// Prefetching by design has no visible side-effects.
@@ -11,14 +11,19 @@ use near_primitives::types::{
     NumShards, RawStateChange, RawStateChangesWithTrieKey, StateChangeCause, StateRoot,
 };
 
+use crate::db::refcount::decode_value_with_rc;
 use crate::flat_state::FlatStateFactory;
 use crate::trie::config::TrieConfig;
 use crate::trie::prefetching_trie_storage::PrefetchingThreadsHandle;
-use crate::trie::trie_storage::{TrieCache, TrieCachingStorage};
+use crate::trie::trie_storage::{ColdStateStore, TrieCache, TrieCacheStats, TrieCachingStorage};
 use crate::trie::{TrieRefcountChange, POISONED_LOCK_ERR};
 use crate::{metrics, DBCol, DBOp, DBTransaction, PrefetchApi};
 use crate::{Store, StoreUpdate, Trie, TrieChanges, TrieUpdate};
 
+/// Number of trie nodes read from the database at once while warming up the
+/// shard caches.
+const CACHE_WARMUP_BATCH_SIZE: usize = 1024;
+
 struct ShardTriesInner {
     store: Store,
     trie_config: TrieConfig,
@@ -29,6 +34,15 @@ struct ShardTriesInner {
     flat_state_factory: FlatStateFactory,
     /// Prefetcher state, such as IO threads, per shard.
     prefetchers: RwLock<HashMap<ShardUId, (PrefetchApi, PrefetchingThreadsHandle)>>,
+    /// Prefetchers of view tries, only used to read ahead. See
+    /// `get_view_trie_with_read_ahead`.
+    view_prefetchers: RwLock<HashMap<ShardUId, (PrefetchApi, PrefetchingThreadsHandle)>>,
+    /// `ShardUId` prefix in `DBCol::State` for shards that don't use their
+    /// own. Mirrors `DBCol::StateShardUIdMapping`.
+    state_shard_uids: RwLock<HashMap<ShardUId, ShardUId>>,
+    /// Cold store of archival nodes with split storage. Trie nodes missing in
+    /// `store` are read from it.
+    cold_store: Option<Store>,
 }
 
 #[derive(Clone)]
@@ -40,9 +54,11 @@ impl ShardTries {
         trie_config: TrieConfig,
         shard_uids: &[ShardUId],
         flat_state_factory: FlatStateFactory,
+        cold_store: Option<Store>,
     ) -> Self {
         let caches = Self::create_initial_caches(&trie_config, &shard_uids, false);
         let view_caches = Self::create_initial_caches(&trie_config, &shard_uids, true);
+        let state_shard_uids = Self::load_state_shard_uids(&store);
         ShardTries(Arc::new(ShardTriesInner {
             store: store.clone(),
             trie_config,
@@ -50,6 +66,9 @@ impl ShardTries {
             view_caches: RwLock::new(view_caches),
             flat_state_factory,
             prefetchers: Default::default(),
+            view_prefetchers: Default::default(),
+            state_shard_uids: RwLock::new(state_shard_uids),
+            cold_store,
         }))
     }
 
@@ -71,6 +90,7 @@ impl ShardTries {
             trie_config,
             &shard_uids,
             FlatStateFactory::new(store.clone()),
+            None,
         )
     }
 
@@ -86,6 +106,53 @@ impl ShardTries {
             .collect()
     }
 
+    fn load_state_shard_uids(store: &Store) -> HashMap<ShardUId, ShardUId> {
+        store
+            .iter(DBCol::StateShardUIdMapping)
+            .map(|item| {
+                let (key, value) = item.expect("failed to read DBCol::StateShardUIdMapping");
+                let shard_uid = ShardUId::try_from(&key[..]).unwrap();
+                let state_shard_uid = ShardUId::try_from(&value[..]).unwrap();
+                (shard_uid, state_shard_uid)
+            })
+            .collect()
+    }
+
+    /// Returns the `ShardUId` prefix under which trie nodes and values of the
+    /// given shard are stored in `DBCol::State`.
+    ///
+    /// This is the shard itself, unless the shard was created by splitting
+    /// another shard. Then it shares the storage of that shard, so that nodes
+    /// and values identical to the parent's are stored only once. They are
+    /// still written when the new tries are built, which increments their
+    /// refcount.
+    pub fn get_state_shard_uid(&self, shard_uid: ShardUId) -> ShardUId {
+        let state_shard_uids = self.0.state_shard_uids.read().expect(POISONED_LOCK_ERR);
+        state_shard_uids.get(&shard_uid).copied().unwrap_or(shard_uid)
+    }
+
+    /// Makes `shard_uid` use the storage of `parent_shard_uid` once
+    /// `store_update` is committed.
+    ///
+    /// Must be called before any trie changes for `shard_uid` are applied,
+    /// nodes already written under its own prefix are not moved.
+    pub fn set_state_shard_uid(
+        &self,
+        store_update: &mut StoreUpdate,
+        shard_uid: ShardUId,
+        parent_shard_uid: ShardUId,
+    ) {
+        // Resolve the parent right away, so that lookups never need to follow
+        // a chain of splits.
+        let state_shard_uid = self.get_state_shard_uid(parent_shard_uid);
+        store_update.set_shard_tries(self);
+        store_update.set(
+            DBCol::StateShardUIdMapping,
+            &shard_uid.to_bytes(),
+            &state_shard_uid.to_bytes(),
+        );
+    }
+
     pub(crate) fn is_same(&self, other: &Self) -> bool {
         Arc::ptr_eq(&self.0, &other.0)
     }
@@ -104,8 +171,10 @@ impl ShardTries {
         shard_uid: ShardUId,
         state_root: StateRoot,
         is_view: bool,
+        read_ahead: bool,
         block_hash: Option<CryptoHash>,
     ) -> Trie {
+        let state_shard_uid = self.get_state_shard_uid(shard_uid);
         let caches_to_use = if is_view { &self.0.view_caches } else { &self.0.caches };
         let cache = {
             let mut caches = caches_to_use.write().expect(POISONED_LOCK_ERR);
@@ -114,18 +183,23 @@ impl ShardTries {
                 .or_insert_with(|| TrieCache::new(&self.0.trie_config, shard_uid, is_view))
                 .clone()
         };
-        // Do not enable prefetching on view caches.
+        // Do not enable prefetching on view caches, except for reading ahead.
         // 1) Performance of view calls is not crucial.
         // 2) A lot of the prefetcher code assumes there is only one "main-thread" per shard active.
         //    If you want to enable it for view calls, at least make sure they don't share
         //    the `PrefetchApi` instances with the normal calls.
-        let prefetch_enabled = !is_view
-            && (self.0.trie_config.enable_receipt_prefetching
+        // Reading ahead copes with several threads at once, at worst a node is
+        // read twice.
+        let prefetch_enabled = if is_view {
+            read_ahead
+        } else {
+            self.0.trie_config.enable_receipt_prefetching
                 || (!self.0.trie_config.sweat_prefetch_receivers.is_empty()
-                    && !self.0.trie_config.sweat_prefetch_senders.is_empty()));
+                    && !self.0.trie_config.sweat_prefetch_senders.is_empty())
+        };
+        let prefetchers = if is_view { &self.0.view_prefetchers } else { &self.0.prefetchers };
         let prefetch_api = prefetch_enabled.then(|| {
-            self.0
-                .prefetchers
+            prefetchers
                 .write()
                 .expect(POISONED_LOCK_ERR)
                 .entry(shard_uid)
@@ -134,6 +208,7 @@ impl ShardTries {
                         self.0.store.clone(),
                         cache.clone(),
                         shard_uid.clone(),
+                        state_shard_uid,
                         &self.0.trie_config,
                     )
                 })
@@ -141,13 +216,15 @@ impl ShardTries {
                 .clone()
         });
 
-        let storage = Box::new(TrieCachingStorage::new(
-            self.0.store.clone(),
-            cache,
-            shard_uid,
-            is_view,
-            prefetch_api,
-        ));
+        let mut storage =
+            TrieCachingStorage::new(self.0.store.clone(), cache, shard_uid, is_view, prefetch_api)
+                .with_state_shard_uid(state_shard_uid)
+                .with_chunk_cache_size_limit(self.0.trie_config.chunk_cache_size_limit);
+        if let Some(cold_store) = &self.0.cold_store {
+            storage = storage.with_cold_store(ColdStateStore::new(cold_store.clone(), shard_uid));
+        }
+        let storage = Box::new(storage);
+        storage.set_read_ahead(read_ahead);
         let flat_state = self.0.flat_state_factory.new_flat_state_for_shard(
             shard_uid.shard_id(),
             block_hash,
@@ -158,7 +235,7 @@ impl ShardTries {
     }
 
     pub fn get_trie_for_shard(&self, shard_uid: ShardUId, state_root: StateRoot) -> Trie {
-        self.get_trie_for_shard_internal(shard_uid, state_root, false, None)
+        self.get_trie_for_shard_internal(shard_uid, state_root, false, false, None)
     }
 
     pub fn get_trie_with_block_hash_for_shard(
@@ -167,11 +244,31 @@ impl ShardTries {
         state_root: StateRoot,
         block_hash: &CryptoHash,
     ) -> Trie {
-        self.get_trie_for_shard_internal(shard_uid, state_root, false, Some(block_hash.clone()))
+        self.get_trie_for_shard_internal(
+            shard_uid,
+            state_root,
+            false,
+            false,
+            Some(block_hash.clone()),
+        )
     }
 
     pub fn get_view_trie_for_shard(&self, shard_uid: ShardUId, state_root: StateRoot) -> Trie {
-        self.get_trie_for_shard_internal(shard_uid, state_root, true, None)
+        self.get_trie_for_shard_internal(shard_uid, state_root, true, false, None)
+    }
+
+    /// Like `get_view_trie_for_shard`, but the trie iterator reads ahead the
+    /// children of the branch nodes it visits, see
+    /// `TrieCachingStorage::set_read_ahead`.
+    ///
+    /// Meant for traversals of large parts of the trie, like generating state
+    /// parts.  The IO threads for reading ahead are started on first use.
+    pub fn get_view_trie_with_read_ahead(
+        &self,
+        shard_uid: ShardUId,
+        state_root: StateRoot,
+    ) -> Trie {
+        self.get_trie_for_shard_internal(shard_uid, state_root, true, true, None)
     }
 
     pub fn store_update(&self) -> StoreUpdate {
@@ -188,6 +285,7 @@ impl ShardTries {
 
     pub(crate) fn update_cache(&self, transaction: &DBTransaction) -> std::io::Result<()> {
         let mut caches = self.0.caches.write().expect(POISONED_LOCK_ERR);
+        let mut state_shard_uids = self.0.state_shard_uids.write().expect(POISONED_LOCK_ERR);
         let mut shards = HashMap::new();
         for op in &transaction.ops {
             match op {
@@ -209,17 +307,170 @@ impl ShardTries {
                         }
                     }
                 }
+                DBOp::Set { col: DBCol::StateShardUIdMapping, key, value } => {
+                    let shard_uid = ShardUId::try_from(key.as_slice())
+                        .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+                    let state_shard_uid = ShardUId::try_from(value.as_slice())
+                        .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+                    state_shard_uids.insert(shard_uid, state_shard_uid);
+                }
                 DBOp::Set { col, .. } | DBOp::Insert { col, .. } | DBOp::Delete { col, .. } => {
                     assert_ne!(*col, DBCol::State);
                 }
             }
         }
-        for (shard_uid, ops) in shards {
-            let cache = caches
-                .entry(shard_uid)
-                .or_insert_with(|| TrieCache::new(&self.0.trie_config, shard_uid, false))
-                .clone();
-            cache.update_cache(ops);
+        for (state_shard_uid, ops) in shards {
+            for shard_uid in Self::shards_sharing_state(&caches, &state_shard_uids, state_shard_uid)
+            {
+                let cache = caches
+                    .entry(shard_uid)
+                    .or_insert_with(|| TrieCache::new(&self.0.trie_config, shard_uid, false))
+                    .clone();
+                cache.update_cache(ops.clone());
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the shards whose caches hold nodes stored under the `State`
+    /// prefix of `state_shard_uid`.
+    ///
+    /// Nodes under a shared prefix belong to all shards using it. The cache of
+    /// a split parent is only included if it still exists.
+    fn shards_sharing_state(
+        caches: &HashMap<ShardUId, TrieCache>,
+        state_shard_uids: &HashMap<ShardUId, ShardUId>,
+        state_shard_uid: ShardUId,
+    ) -> Vec<ShardUId> {
+        let mut shard_uids: Vec<ShardUId> = state_shard_uids
+            .iter()
+            .filter(|(_, prefix)| **prefix == state_shard_uid)
+            .map(|(shard_uid, _)| *shard_uid)
+            .collect();
+        if shard_uids.is_empty() || caches.contains_key(&state_shard_uid) {
+            shard_uids.push(state_shard_uid);
+        }
+        shard_uids
+    }
+
+    /// Snapshots of the shard caches and view caches, sorted by shard id.
+    pub fn cache_stats(&self) -> Vec<TrieCacheStats> {
+        let mut stats: Vec<_> = [&self.0.caches, &self.0.view_caches]
+            .into_iter()
+            .flat_map(|caches| {
+                let caches = caches.read().expect(POISONED_LOCK_ERR);
+                caches.values().map(TrieCache::stats).collect::<Vec<_>>()
+            })
+            .collect();
+        stats.sort_by_key(|stats| (stats.shard_id, stats.is_view));
+        stats
+    }
+
+    /// Saves the keys of the nodes most recently used by the shard caches, so
+    /// that [`Self::warm_up_caches`] can load them again after a restart.
+    ///
+    /// View caches are not saved, they are only used for view calls.
+    pub fn save_cache_warmup(&self, store_update: &mut StoreUpdate) -> io::Result<()> {
+        let max_nodes = self.0.trie_config.cache_warmup_max_nodes;
+        if max_nodes == 0 {
+            return Ok(());
+        }
+        let caches = self.0.caches.read().expect(POISONED_LOCK_ERR);
+        for (shard_uid, cache) in caches.iter() {
+            let keys = cache.recent_keys(max_nodes);
+            store_update.set_ser(DBCol::TrieCacheWarmup, &shard_uid.to_bytes(), &keys)?;
+        }
+        Ok(())
+    }
+
+    /// Loads the nodes saved by [`Self::save_cache_warmup`] into the shard
+    /// caches.  Returns the number of loaded nodes.
+    ///
+    /// Nodes that were garbage collected since they were saved are skipped.
+    /// Meant to be called on startup, before any chunks are applied.
+    pub fn warm_up_caches(&self) -> io::Result<usize> {
+        let max_nodes = self.0.trie_config.cache_warmup_max_nodes;
+        if max_nodes == 0 {
+            return Ok(0);
+        }
+        let shard_uids: Vec<ShardUId> =
+            self.0.caches.read().expect(POISONED_LOCK_ERR).keys().copied().collect();
+        let mut num_loaded = 0;
+        for shard_uid in shard_uids {
+            let mut hashes: Vec<CryptoHash> =
+                match self.0.store.get_ser(DBCol::TrieCacheWarmup, &shard_uid.to_bytes())? {
+                    Some(hashes) => hashes,
+                    None => continue,
+                };
+            hashes.truncate(max_nodes);
+            // Insert the least recently used nodes first, so that the order
+            // in the cache is the same as before the restart.
+            hashes.reverse();
+            let state_shard_uid = self.get_state_shard_uid(shard_uid);
+            let cache = self.get_cache(shard_uid);
+            for batch in hashes.chunks(CACHE_WARMUP_BATCH_SIZE) {
+                let keys: Vec<_> = batch
+                    .iter()
+                    .map(|hash| {
+                        TrieCachingStorage::get_key_from_shard_uid_and_hash(state_shard_uid, hash)
+                    })
+                    .collect();
+                let keys: Vec<&[u8]> = keys.iter().map(|key| key.as_ref()).collect();
+                let values = self.0.store.multi_get(DBCol::State, &keys)?;
+                let nodes: Vec<(CryptoHash, Arc<[u8]>)> = batch
+                    .iter()
+                    .zip(values)
+                    .filter_map(|(hash, value)| Some((*hash, Arc::from(value?))))
+                    .collect();
+                num_loaded += nodes.len();
+                cache.put_all(nodes);
+            }
+        }
+        Ok(num_loaded)
+    }
+
+    fn get_cache(&self, shard_uid: ShardUId) -> TrieCache {
+        let mut caches = self.0.caches.write().expect(POISONED_LOCK_ERR);
+        caches
+            .entry(shard_uid)
+            .or_insert_with(|| TrieCache::new(&self.0.trie_config, shard_uid, false))
+            .clone()
+    }
+
+    /// Evicts nodes inserted by `trie_changes` from the shard caches, for
+    /// changes of a block that is no longer on the canonical chain.
+    ///
+    /// Only nodes whose refcount would drop to zero by reverting the
+    /// insertions are evicted, nodes shared with other blocks stay cached.
+    /// Refcounts in the DB are not modified, this happens once the block is
+    /// garbage collected with `revert_insertions`.
+    pub fn evict_reverted_insertions(
+        &self,
+        trie_changes: &TrieChanges,
+        shard_uid: ShardUId,
+    ) -> io::Result<()> {
+        let state_shard_uid = self.get_state_shard_uid(shard_uid);
+        let mut evicted = vec![];
+        for TrieRefcountChange { trie_node_or_value_hash, rc, .. } in &trie_changes.insertions {
+            let key = TrieCachingStorage::get_key_from_shard_uid_and_hash(
+                state_shard_uid,
+                trie_node_or_value_hash,
+            );
+            let refcount = match self.get_db().get_raw_bytes(DBCol::State, key.as_ref())? {
+                Some(value) => decode_value_with_rc(&value).1,
+                None => 0,
+            };
+            if refcount <= i64::from(rc.get()) {
+                evicted.push(*trie_node_or_value_hash);
+            }
+        }
+
+        let caches = self.0.caches.read().expect(POISONED_LOCK_ERR);
+        let state_shard_uids = self.0.state_shard_uids.read().expect(POISONED_LOCK_ERR);
+        for shard_uid in Self::shards_sharing_state(&caches, &state_shard_uids, state_shard_uid) {
+            if let Some(cache) = caches.get(&shard_uid) {
+                cache.evict(&evicted);
+            }
         }
         Ok(())
     }
@@ -231,9 +482,10 @@ impl ShardTries {
         store_update: &mut StoreUpdate,
     ) {
         store_update.set_shard_tries(self);
+        let state_shard_uid = self.get_state_shard_uid(shard_uid);
         for TrieRefcountChange { trie_node_or_value_hash, rc, .. } in deletions.iter() {
             let key = TrieCachingStorage::get_key_from_shard_uid_and_hash(
-                shard_uid,
+                state_shard_uid,
                 trie_node_or_value_hash,
             );
             store_update.decrement_refcount_by(DBCol::State, key.as_ref(), *rc);
@@ -247,11 +499,12 @@ impl ShardTries {
         store_update: &mut StoreUpdate,
     ) {
         store_update.set_shard_tries(self);
+        let state_shard_uid = self.get_state_shard_uid(shard_uid);
         for TrieRefcountChange { trie_node_or_value_hash, trie_node_or_value, rc } in
             insertions.iter()
         {
             let key = TrieCachingStorage::get_key_from_shard_uid_and_hash(
-                shard_uid,
+                state_shard_uid,
                 trie_node_or_value_hash,
             );
             store_update.increment_refcount_by(DBCol::State, key.as_ref(), trie_node_or_value, *rc);
@@ -330,6 +583,7 @@ impl ShardTries {
     }
 }
 
+#[derive(Clone)]
 pub struct WrappedTrieChanges {
     tries: ShardTries,
     shard_uid: ShardUId,
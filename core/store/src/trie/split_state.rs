@@ -1,12 +1,12 @@
 use crate::trie::iterator::TrieItem;
 use crate::{
-    get, get_delayed_receipt_indices, set, ShardTries, StoreUpdate, Trie, TrieChanges, TrieUpdate,
+    get, set, DelayedReceiptQueues, ShardTries, StoreUpdate, Trie, TrieChanges, TrieUpdate,
 };
 use borsh::BorshDeserialize;
 use bytesize::ByteSize;
 use near_primitives::account::id::AccountId;
 use near_primitives::errors::StorageError;
-use near_primitives::receipt::Receipt;
+use near_primitives::receipt::{DelayedReceiptIndices, DelayedReceiptQueue, Receipt};
 use near_primitives::shard_layout::ShardUId;
 use near_primitives::state_part::PartId;
 use near_primitives::trie_key::trie_key_parsers::parse_account_id_from_raw_key;
@@ -51,20 +51,20 @@ impl ShardTries {
         let mut insert_receipts = Vec::new();
         for ConsolidatedStateChange { trie_key, value } in changes.changes {
             match &trie_key {
-                TrieKey::DelayedReceiptIndices => {}
-                TrieKey::DelayedReceipt { index } => match value {
-                    Some(value) => {
-                        let receipt = Receipt::try_from_slice(&value).map_err(|err| {
-                            StorageError::StorageInconsistentState(format!(
-                                "invalid delayed receipt {:?}, err: {}",
-                                value,
-                                err.to_string(),
-                            ))
-                        })?;
-                        insert_receipts.push((*index, receipt));
+                TrieKey::DelayedReceiptIndices | TrieKey::PriorityDelayedReceiptIndices { .. } => {}
+                TrieKey::DelayedReceipt { index } => {
+                    if let Some(value) = value {
+                        let receipt = parse_delayed_receipt(&value)?;
+                        insert_receipts.push(((DelayedReceiptQueue::Default, *index), receipt));
                     }
-                    None => {}
-                },
+                }
+                TrieKey::PriorityDelayedReceipt { priority_class, index } => {
+                    if let Some(value) = value {
+                        let receipt = parse_delayed_receipt(&value)?;
+                        let queue = DelayedReceiptQueue::Priority { class: *priority_class };
+                        insert_receipts.push(((queue, *index), receipt));
+                    }
+                }
                 TrieKey::Account { account_id }
                 | TrieKey::ContractCode { account_id }
                 | TrieKey::AccessKey { account_id, .. }
@@ -203,15 +203,26 @@ impl ShardTries {
     }
 }
 
+fn parse_delayed_receipt(value: &[u8]) -> Result<Receipt, StorageError> {
+    Receipt::try_from_slice(value).map_err(|err| {
+        StorageError::StorageInconsistentState(format!(
+            "invalid delayed receipt {:?}, err: {}",
+            value,
+            err.to_string(),
+        ))
+    })
+}
+
 fn apply_delayed_receipts_to_split_states_impl(
     trie_updates: &mut HashMap<ShardUId, TrieUpdate>,
     insert_receipts: &[Receipt],
     delete_receipts: &[Receipt],
     account_id_to_shard_id: &dyn Fn(&AccountId) -> ShardUId,
 ) -> Result<(), StorageError> {
-    let mut delayed_receipts_indices_by_shard = HashMap::new();
+    let mut delayed_receipts_by_shard = HashMap::new();
     for (shard_uid, update) in trie_updates.iter() {
-        delayed_receipts_indices_by_shard.insert(*shard_uid, get_delayed_receipt_indices(update)?);
+        delayed_receipts_by_shard
+            .insert(*shard_uid, DelayedReceiptQueues::load(update, DelayedReceiptQueue::all())?);
     }
 
     for receipt in insert_receipts {
@@ -225,22 +236,10 @@ fn apply_delayed_receipts_to_split_states_impl(
             );
             return Err(StorageError::StorageInconsistentState(err));
         }
-        // we already checked that new_shard_uid is in trie_updates and delayed_receipts_indices
+        // we already checked that new_shard_uid is in trie_updates and delayed_receipts_by_shard
         // so we can safely unwrap here
-        let mut delayed_receipts_indices =
-            delayed_receipts_indices_by_shard.get_mut(&new_shard_uid).unwrap();
-        set(
-            trie_updates.get_mut(&new_shard_uid).unwrap(),
-            TrieKey::DelayedReceipt { index: delayed_receipts_indices.next_available_index },
-            receipt,
-        );
-        delayed_receipts_indices.next_available_index =
-            delayed_receipts_indices.next_available_index.checked_add(1).ok_or_else(|| {
-                StorageError::StorageInconsistentState(
-                    "Next available index for delayed receipt exceeded the integer limit"
-                        .to_string(),
-                )
-            })?;
+        let delayed_receipts = delayed_receipts_by_shard.get_mut(&new_shard_uid).unwrap();
+        delayed_receipts.push(trie_updates.get_mut(&new_shard_uid).unwrap(), receipt)?;
     }
 
     for receipt in delete_receipts {
@@ -254,40 +253,42 @@ fn apply_delayed_receipts_to_split_states_impl(
             );
             return Err(StorageError::StorageInconsistentState(err));
         }
-        let mut delayed_receipts_indices =
-            delayed_receipts_indices_by_shard.get_mut(&new_shard_uid).unwrap();
-
+        let delayed_receipts = delayed_receipts_by_shard.get_mut(&new_shard_uid).unwrap();
         let trie_update = trie_updates.get_mut(&new_shard_uid).unwrap();
-        let trie_key = TrieKey::DelayedReceipt { index: delayed_receipts_indices.first_index };
 
-        let stored_receipt = get::<Receipt>(trie_update, &trie_key)?
+        let stored_receipt = delayed_receipts
+            .pop_from(trie_update, DelayedReceiptQueue::for_receipt(receipt))?
             .expect("removed receipt does not exist in new state");
-        // check that the receipt to remove is at the first of delayed receipt queue
+        // check that the receipt to remove is at the first of its delayed receipt queue
         assert_eq!(&stored_receipt, receipt);
-        trie_update.remove(trie_key);
-        delayed_receipts_indices.first_index += 1;
     }
 
     // commit the trie_updates and update state_roots
     for (shard_uid, trie_update) in trie_updates {
+        let delayed_receipts = delayed_receipts_by_shard.get(shard_uid).unwrap();
+        delayed_receipts.save(trie_update);
+        // The indices of the default queue are always written, as they were
+        // before there were other queues.
         set(
             trie_update,
             TrieKey::DelayedReceiptIndices,
-            delayed_receipts_indices_by_shard.get(shard_uid).unwrap(),
+            delayed_receipts.get(DelayedReceiptQueue::Default).unwrap(),
         );
         trie_update.commit(StateChangeCause::Resharding);
     }
     Ok(())
 }
 
-/// Retrieve delayed receipts starting with `start_index` until `memory_limit` is hit
+/// Retrieve delayed receipts of `queue` starting with `start_index` until `memory_limit` is hit
 /// return None if there is no delayed receipts with index >= start_index
 pub fn get_delayed_receipts(
     state_update: &TrieUpdate,
+    queue: DelayedReceiptQueue,
     start_index: Option<u64>,
     memory_limit: ByteSize,
 ) -> Result<Option<(u64, Vec<Receipt>)>, StorageError> {
-    let mut delayed_receipt_indices = get_delayed_receipt_indices(state_update)?;
+    let mut delayed_receipt_indices: DelayedReceiptIndices =
+        get(state_update, &queue.indices_key())?.unwrap_or_default();
     if let Some(start_index) = start_index {
         if start_index >= delayed_receipt_indices.next_available_index {
             return Ok(None);
@@ -300,11 +301,11 @@ pub fn get_delayed_receipts(
     while used_memory < memory_limit.as_u64()
         && delayed_receipt_indices.first_index < delayed_receipt_indices.next_available_index
     {
-        let key = TrieKey::DelayedReceipt { index: delayed_receipt_indices.first_index };
+        let key = queue.receipt_key(delayed_receipt_indices.first_index);
         let data = state_update.get(&key)?.ok_or_else(|| {
             StorageError::StorageInconsistentState(format!(
-                "Delayed receipt #{} should be in the state",
-                delayed_receipt_indices.first_index
+                "Delayed receipt #{} of queue {:?} should be in the state",
+                delayed_receipt_indices.first_index, queue
             ))
         })?;
         used_memory += data.len() as u64;
@@ -326,12 +327,14 @@ mod tests {
         simplify_changes, test_populate_trie,
     };
 
-    use crate::{get, get_delayed_receipt_indices, set, set_account, ShardTries, ShardUId, Trie};
+    use crate::{
+        get, get_delayed_receipt_indices, set, set_account, DBCol, ShardTries, ShardUId, Trie,
+    };
     use near_primitives::account::id::AccountId;
     use near_primitives::account::Account;
     use near_primitives::borsh::BorshSerialize;
     use near_primitives::hash::{hash, CryptoHash};
-    use near_primitives::receipt::{DelayedReceiptIndices, Receipt};
+    use near_primitives::receipt::{DelayedReceiptIndices, DelayedReceiptQueue, Receipt};
     use near_primitives::state_part::PartId;
     use near_primitives::trie_key::trie_key_parsers::parse_account_id_from_raw_key;
     use near_primitives::trie_key::TrieKey;
@@ -502,6 +505,51 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_split_states_share_parent_storage() {
+        let mut rng = rand::thread_rng();
+        let tries = create_tries();
+        let parent_shard_uid = ShardUId::single_shard();
+        let changes = gen_changes(&mut rng, 100);
+        let state_root =
+            test_populate_trie(&tries, &Trie::EMPTY_ROOT, parent_shard_uid, changes.clone());
+
+        let num_shards = 2;
+        let mut store_update = tries.store_update();
+        let mut state_roots = HashMap::new();
+        for shard_id in 0..num_shards {
+            let shard_uid = ShardUId { version: 1, shard_id };
+            tries.set_state_shard_uid(&mut store_update, shard_uid, parent_shard_uid);
+            state_roots.insert(shard_uid, Trie::EMPTY_ROOT);
+        }
+        store_update.commit().unwrap();
+
+        let (store_update, state_roots) = tries
+            .add_values_to_split_states_impl(&state_roots, changes, &|raw_key| {
+                Ok(Some(ShardUId { version: 1, shard_id: hash(raw_key).0[0] as u32 % num_shards }))
+            })
+            .unwrap();
+        store_update.commit().unwrap();
+
+        // All nodes, including the ones of the new shards, are stored under the
+        // prefix of the parent.
+        for item in tries.get_store().iter(DBCol::State) {
+            let (key, _) = item.unwrap();
+            assert_eq!(&key[..8], &parent_shard_uid.to_bytes());
+        }
+        let trie = tries.get_trie_for_shard(parent_shard_uid, state_root);
+        let trie_items: HashMap<_, _> = trie.iter().unwrap().map(Result::unwrap).collect();
+        let mut combined_trie_items = HashMap::new();
+        // The mapping is loaded from the store when tries are created.
+        let new_tries = ShardTries::test(tries.get_store(), 1);
+        for (shard_uid, state_root) in state_roots {
+            assert_eq!(new_tries.get_state_shard_uid(shard_uid), parent_shard_uid);
+            let trie = new_tries.get_view_trie_for_shard(shard_uid, state_root);
+            combined_trie_items.extend(trie.iter().unwrap().map(Result::unwrap));
+        }
+        assert_eq!(trie_items, combined_trie_items);
+    }
+
     #[test]
     fn test_get_delayed_receipts() {
         let mut rng = rand::thread_rng();
@@ -533,8 +581,13 @@ mod tests {
             let mut start_index = 0;
 
             let trie_update = tries.new_trie_update(ShardUId::single_shard(), state_root);
-            while let Some((next_index, receipts)) =
-                get_delayed_receipts(&trie_update, Some(start_index), memory_limit).unwrap()
+            while let Some((next_index, receipts)) = get_delayed_receipts(
+                &trie_update,
+                DelayedReceiptQueue::Default,
+                Some(start_index),
+                memory_limit,
+            )
+            .unwrap()
             {
                 assert_eq!(receipts, all_receipts[start_index as usize..next_index as usize]);
                 start_index = next_index;
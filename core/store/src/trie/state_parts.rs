@@ -24,10 +24,12 @@ impl Trie {
     /// # Errors
     /// StorageError if the storage is corrupted
     pub fn get_trie_nodes_for_part(&self, part_id: PartId) -> Result<PartialState, StorageError> {
-        assert!(self.storage.as_caching_storage().is_some());
+        let storage = self.storage.as_caching_storage().unwrap();
 
         let with_recording = self.recording_reads();
-        with_recording.visit_nodes_for_state_part(part_id)?;
+        let result = with_recording.visit_nodes_for_state_part(part_id);
+        storage.stop_read_ahead();
+        result?;
         let recorded = with_recording.recorded_storage().unwrap();
 
         let trie_nodes = recorded.nodes;
@@ -716,4 +718,26 @@ mod tests {
             }
         }
     }
+
+    /// Reading ahead must not change the nodes of a state part.
+    #[test]
+    fn test_get_trie_nodes_for_part_with_read_ahead() {
+        let mut rng = rand::thread_rng();
+        let shard_uid = ShardUId::single_shard();
+        let tries = create_tries();
+        let trie_changes = gen_changes(&mut rng, 50);
+        let state_root = test_populate_trie(&tries, &Trie::EMPTY_ROOT, shard_uid, trie_changes);
+        let trie = tries.get_view_trie_for_shard(shard_uid, state_root);
+        let read_ahead_trie = tries.get_view_trie_with_read_ahead(shard_uid, state_root);
+
+        for num_parts in 1..5 {
+            for part_id in 0..num_parts {
+                let part_id = PartId::new(part_id, num_parts);
+                assert_eq!(
+                    read_ahead_trie.get_trie_nodes_for_part(part_id).unwrap(),
+                    trie.get_trie_nodes_for_part(part_id).unwrap()
+                );
+            }
+        }
+    }
 }
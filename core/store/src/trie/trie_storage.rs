@@ -1,5 +1,5 @@
 use crate::db::refcount::decode_value_with_rc;
-use crate::trie::config::TrieConfig;
+use crate::trie::config::{TrieConfig, DEFAULT_CHUNK_CACHE_SIZE_LIMIT};
 use crate::trie::prefetching_trie_storage::PrefetcherResult;
 use crate::trie::POISONED_LOCK_ERR;
 use crate::{metrics, DBCol, PrefetchApi, StorageError, Store};
@@ -14,7 +14,8 @@ use std::borrow::Borrow;
 use std::cell::{Cell, RefCell};
 use std::collections::{HashMap, HashSet, VecDeque};
 use std::io::ErrorKind;
-use std::sync::{Arc, Mutex};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::Instant;
 
 pub(crate) struct BoundedQueue<T> {
     queue: VecDeque<T>,
@@ -51,11 +52,11 @@ impl<T> BoundedQueue<T> {
 }
 
 /// In-memory cache for trie items - nodes and values. All nodes are stored in the LRU cache with three modifications.
-/// 1) Size of each value must not exceed `TRIE_LIMIT_CACHED_VALUE_SIZE`.
+/// 1) Size of each value must not exceed `max_value_size`.
 /// Needed to avoid caching large values like contract codes.
 /// 2) If we put new value to LRU cache and total size of existing values exceeds `total_sizes_capacity`, we evict
 /// values from it until that is no longer the case. So the actual total size should never exceed
-/// `total_size_limit` + `TRIE_LIMIT_CACHED_VALUE_SIZE`.
+/// `total_size_limit` + `max_value_size`.
 /// Needed because value sizes generally vary from 1 B to 500 B and we want to count cache size precisely.
 /// 3) If value is popped, it is put to the `deletions` queue with `deletions_queue_capacity` first. If popped value
 /// doesn't fit in the queue, the last value is removed from the queue and LRU cache, and newly popped value is inserted
@@ -71,10 +72,21 @@ pub struct TrieCacheInner {
     total_size: u64,
     /// Upper bound for the total size.
     total_size_limit: u64,
+    /// Values of this size or larger are not cached.
+    max_value_size: usize,
     /// Shard id of the nodes being cached.
     shard_id: ShardId,
     /// Whether cache is used for view calls execution.
     is_view: bool,
+    /// Number of values evicted because the cache was full.
+    capacity_evictions: u64,
+    /// Number of values evicted because their nodes were deleted, once they
+    /// leave the deletions queue, or because they were explicitly evicted.
+    deletion_evictions: u64,
+    /// Number of values that were not cached because of `max_value_size`.
+    too_large: u64,
+    /// When a value was last inserted or found in the cache.
+    last_used: Instant,
     // Counters tracking operations happening inside the shard cache.
     // Stored here to avoid overhead of looking them up on hot paths.
     metrics: TrieCacheMetrics,
@@ -87,6 +99,34 @@ struct TrieCacheMetrics {
     shard_cache_pop_lru: GenericCounter<prometheus::core::AtomicU64>,
     shard_cache_gc_pop_misses: GenericCounter<prometheus::core::AtomicU64>,
     shard_cache_deletions_size: GenericGauge<prometheus::core::AtomicI64>,
+    // The gauges are shared by all segments of a `TrieCache`, so they are
+    // only ever changed by deltas.
+    shard_cache_size: GenericGauge<prometheus::core::AtomicI64>,
+    shard_cache_current_total_size: GenericGauge<prometheus::core::AtomicI64>,
+    shard_cache_capacity_evictions: GenericCounter<prometheus::core::AtomicU64>,
+    shard_cache_deletion_evictions: GenericCounter<prometheus::core::AtomicU64>,
+}
+
+/// Snapshot of the state and the counters of a shard cache, see
+/// [`TrieCache::stats`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TrieCacheStats {
+    pub shard_id: ShardId,
+    pub is_view: bool,
+    /// Number of cached values.
+    pub len: usize,
+    /// Approximate memory consumption of the cached values.
+    pub total_size: u64,
+    pub total_size_limit: u64,
+    /// Number of keys in the deletions queue.
+    pub deletions_queue_len: usize,
+    /// Values evicted because the cache was full, since the node started.
+    pub capacity_evictions: u64,
+    /// Values evicted because their nodes were deleted, since the node
+    /// started.
+    pub deletion_evictions: u64,
+    /// Values not cached because they are too large, since the node started.
+    pub too_large: u64,
 }
 
 impl TrieCacheInner {
@@ -98,6 +138,7 @@ impl TrieCacheInner {
     pub(crate) fn new(
         deletions_queue_capacity: usize,
         total_size_limit: u64,
+        max_value_size: usize,
         shard_id: ShardId,
         is_view: bool,
     ) -> Self {
@@ -107,6 +148,7 @@ impl TrieCacheInner {
         let shard_id_str = buffer.format(shard_id);
 
         let metrics_labels: [&str; 2] = [&shard_id_str, if is_view { "1" } else { "0" }];
+        let eviction_labels = |cause: &'static str| [metrics_labels[0], metrics_labels[1], cause];
         let metrics = TrieCacheMetrics {
             shard_cache_too_large: metrics::SHARD_CACHE_TOO_LARGE
                 .with_label_values(&metrics_labels),
@@ -118,23 +160,42 @@ impl TrieCacheInner {
                 .with_label_values(&metrics_labels),
             shard_cache_deletions_size: metrics::SHARD_CACHE_DELETIONS_SIZE
                 .with_label_values(&metrics_labels),
+            shard_cache_size: metrics::SHARD_CACHE_SIZE.with_label_values(&metrics_labels),
+            shard_cache_current_total_size: metrics::SHARD_CACHE_CURRENT_TOTAL_SIZE
+                .with_label_values(&metrics_labels),
+            shard_cache_capacity_evictions: metrics::SHARD_CACHE_EVICTIONS
+                .with_label_values(&eviction_labels("capacity")),
+            shard_cache_deletion_evictions: metrics::SHARD_CACHE_EVICTIONS
+                .with_label_values(&eviction_labels("deletion")),
         };
         Self {
             cache: LruCache::unbounded(),
             deletions: BoundedQueue::new(deletions_queue_capacity),
             total_size: 0,
             total_size_limit,
+            max_value_size,
             shard_id,
             is_view,
+            capacity_evictions: 0,
+            deletion_evictions: 0,
+            too_large: 0,
+            last_used: Instant::now(),
             metrics,
         }
     }
 
     pub(crate) fn get(&mut self, key: &CryptoHash) -> Option<Arc<[u8]>> {
-        self.cache.get(key).cloned()
+        let value = self.cache.get(key).cloned();
+        if value.is_some() {
+            self.last_used = Instant::now();
+        }
+        value
     }
 
     pub(crate) fn clear(&mut self) {
+        self.metrics.shard_cache_size.sub(self.cache.len() as i64);
+        self.metrics.shard_cache_current_total_size.sub(self.total_size as i64);
+        self.metrics.shard_cache_deletions_size.sub(self.deletions.len() as i64);
         self.total_size = 0;
         self.deletions.clear();
         self.cache.clear();
@@ -147,7 +208,7 @@ impl TrieCacheInner {
                 Some(key) => match self.cache.pop(&key) {
                     Some(value) => {
                         self.metrics.shard_cache_pop_hits.inc();
-                        self.remove_value_of_size(value.len());
+                        self.remove_evicted_for_capacity(value.len());
                         continue;
                     }
                     None => {
@@ -161,10 +222,11 @@ impl TrieCacheInner {
             self.metrics.shard_cache_pop_lru.inc();
             let (_, value) =
                 self.cache.pop_lru().expect("Cannot fail because total size capacity is > 0");
-            self.remove_value_of_size(value.len());
+            self.remove_evicted_for_capacity(value.len());
         }
 
         // Add value to the cache.
+        self.last_used = Instant::now();
         self.add_value_of_size(value.len());
         match self.cache.push(key, value) {
             Some((evicted_key, evicted_value)) => {
@@ -175,10 +237,30 @@ impl TrieCacheInner {
         };
     }
 
+    /// Puts the value in the cache unless it is too large, returns whether it
+    /// was put.
+    pub(crate) fn put_if_small(&mut self, key: CryptoHash, value: Arc<[u8]>) -> bool {
+        if value.len() < self.max_value_size {
+            self.put(key, value);
+            true
+        } else {
+            self.skip_too_large();
+            false
+        }
+    }
+
     // Adds key to the deletions queue if it is present in cache.
     // Returns key-value pair which are popped if deletions queue is full.
     pub(crate) fn pop(&mut self, key: &CryptoHash) -> Option<(CryptoHash, Arc<[u8]>)> {
-        self.metrics.shard_cache_deletions_size.set(self.deletions.len() as i64);
+        let deletions_len = self.deletions.len();
+        let popped = self.pop_into_deletions(key);
+        self.metrics
+            .shard_cache_deletions_size
+            .add(self.deletions.len() as i64 - deletions_len as i64);
+        popped
+    }
+
+    fn pop_into_deletions(&mut self, key: &CryptoHash) -> Option<(CryptoHash, Arc<[u8]>)> {
         // Do nothing if key was removed before.
         if self.cache.contains(key) {
             // Put key to the queue of deletions and possibly remove another key we have to delete.
@@ -186,7 +268,7 @@ impl TrieCacheInner {
                 Some(key_to_delete) => match self.cache.pop(&key_to_delete) {
                     Some(evicted_value) => {
                         self.metrics.shard_cache_pop_hits.inc();
-                        self.remove_value_of_size(evicted_value.len());
+                        self.remove_evicted_for_deletion(evicted_value.len());
                         Some((key_to_delete, evicted_value))
                     }
                     None => {
@@ -202,19 +284,74 @@ impl TrieCacheInner {
         }
     }
 
+    /// Removes the key from the cache right away, without going through the
+    /// deletions queue.
+    pub(crate) fn remove(&mut self, key: &CryptoHash) {
+        if let Some(value) = self.cache.pop(key) {
+            self.remove_evicted_for_deletion(value.len());
+        }
+    }
+
+    /// Counts a value that is not put in the cache because it is at least
+    /// `max_value_size` bytes large.
+    pub(crate) fn skip_too_large(&mut self) {
+        self.too_large += 1;
+        self.metrics.shard_cache_too_large.inc();
+    }
+
+    pub(crate) fn stats(&self) -> TrieCacheStats {
+        TrieCacheStats {
+            shard_id: self.shard_id,
+            is_view: self.is_view,
+            len: self.cache.len(),
+            total_size: self.total_size,
+            total_size_limit: self.total_size_limit,
+            deletions_queue_len: self.deletions.len(),
+            capacity_evictions: self.capacity_evictions,
+            deletion_evictions: self.deletion_evictions,
+            too_large: self.too_large,
+        }
+    }
+
     /// Number of currently cached entries.
     pub fn len(&self) -> usize {
         self.cache.len()
     }
 
+    /// Whether the key is cached, without marking it as recently used.
+    pub(crate) fn contains(&self, key: &CryptoHash) -> bool {
+        self.cache.contains(key)
+    }
+
+    /// Keys of at most `limit` entries, most recently used first.
+    pub(crate) fn recent_keys(&self, limit: usize) -> Vec<CryptoHash> {
+        self.cache.iter().take(limit).map(|(key, _)| *key).collect()
+    }
+
     /// Account consumed memory for a new entry in the cache.
     pub(crate) fn add_value_of_size(&mut self, len: usize) {
         self.total_size += Self::entry_size(len);
+        self.metrics.shard_cache_size.inc();
+        self.metrics.shard_cache_current_total_size.add(Self::entry_size(len) as i64);
     }
 
     /// Remove consumed memory for an entry in the cache.
     pub(crate) fn remove_value_of_size(&mut self, len: usize) {
         self.total_size -= Self::entry_size(len);
+        self.metrics.shard_cache_size.dec();
+        self.metrics.shard_cache_current_total_size.sub(Self::entry_size(len) as i64);
+    }
+
+    fn remove_evicted_for_capacity(&mut self, len: usize) {
+        self.capacity_evictions += 1;
+        self.metrics.shard_cache_capacity_evictions.inc();
+        self.remove_value_of_size(len);
+    }
+
+    fn remove_evicted_for_deletion(&mut self, len: usize) {
+        self.deletion_evictions += 1;
+        self.metrics.shard_cache_deletion_evictions.inc();
+        self.remove_value_of_size(len);
     }
 
     /// Approximate memory consumption of LRU cache.
@@ -228,10 +365,28 @@ impl TrieCacheInner {
 }
 
 /// Wrapper over LruCache to handle concurrent access.
+///
+/// To keep threads reading different nodes from waiting for each other, the
+/// cache is split into up to [`TrieCache::MAX_SEGMENTS`] segments, each
+/// behind its own lock.  A node is kept in the segment given by the first
+/// byte of its hash, the size limit and the deletions queue are split evenly
+/// between the segments.  Values are evicted as described for
+/// [`TrieCacheInner`] within each segment, so the total size can exceed the
+/// limit by `max_value_size` per segment.
 #[derive(Clone)]
-pub struct TrieCache(pub(crate) Arc<Mutex<TrieCacheInner>>);
+pub struct TrieCache(pub(crate) Arc<TrieCacheSegments>);
+
+pub(crate) struct TrieCacheSegments {
+    segments: Box<[Mutex<TrieCacheInner>]>,
+}
 
 impl TrieCache {
+    /// Upper bound for the number of segments.
+    pub(crate) const MAX_SEGMENTS: usize = 16;
+    /// Smallest size limit of a segment.  Smaller caches have fewer segments,
+    /// so that they evict values almost exactly in LRU order.
+    pub(crate) const MIN_SEGMENT_SIZE_LIMIT: u64 = 1024 * 1024;
+
     pub fn new(config: &TrieConfig, shard_uid: ShardUId, is_view: bool) -> Self {
         let cache_config =
             if is_view { &config.view_shard_cache_config } else { &config.shard_cache_config };
@@ -241,46 +396,179 @@ impl TrieCache {
             .copied()
             .unwrap_or(cache_config.default_max_bytes);
         let queue_capacity = config.deletions_queue_capacity();
-        Self(Arc::new(Mutex::new(TrieCacheInner::new(
-            queue_capacity,
-            total_size_limit,
-            shard_uid.shard_id(),
-            is_view,
-        ))))
+        let num_segments = (total_size_limit / Self::MIN_SEGMENT_SIZE_LIMIT)
+            .clamp(1, Self::MAX_SEGMENTS as u64) as usize;
+        let segments = (0..num_segments)
+            .map(|index| {
+                Mutex::new(TrieCacheInner::new(
+                    split_evenly(queue_capacity as u64, num_segments, index) as usize,
+                    split_evenly(total_size_limit, num_segments, index),
+                    cache_config.max_cached_value_size,
+                    shard_uid.shard_id(),
+                    is_view,
+                ))
+            })
+            .collect();
+        Self(Arc::new(TrieCacheSegments { segments }))
+    }
+
+    fn segment_index(&self, key: &CryptoHash) -> usize {
+        key.as_bytes()[0] as usize % self.0.segments.len()
+    }
+
+    /// Locks the segment containing the key.
+    pub(crate) fn lock(&self, key: &CryptoHash) -> MutexGuard<'_, TrieCacheInner> {
+        self.0.segments[self.segment_index(key)].lock().expect(POISONED_LOCK_ERR)
+    }
+
+    fn lock_all(&self) -> impl Iterator<Item = MutexGuard<'_, TrieCacheInner>> {
+        self.0.segments.iter().map(|segment| segment.lock().expect(POISONED_LOCK_ERR))
+    }
+
+    /// Splits the items by the segment of their key, keeping their order, so
+    /// that each segment is only locked once.
+    fn group_by_segment<T>(
+        &self,
+        items: impl IntoIterator<Item = T>,
+        key: impl Fn(&T) -> &CryptoHash,
+    ) -> Vec<Vec<T>> {
+        let mut groups: Vec<Vec<T>> = (0..self.0.segments.len()).map(|_| Vec::new()).collect();
+        for item in items {
+            let index = self.segment_index(key(&item));
+            groups[index].push(item);
+        }
+        groups
     }
 
     pub fn get(&self, key: &CryptoHash) -> Option<Arc<[u8]>> {
-        self.0.lock().expect(POISONED_LOCK_ERR).get(key)
+        self.lock(key).get(key)
+    }
+
+    /// Whether the key is cached, without marking it as recently used.
+    pub(crate) fn contains(&self, key: &CryptoHash) -> bool {
+        self.lock(key).contains(key)
+    }
+
+    /// Puts the value in the cache unless it is too large, returns whether it
+    /// was put.
+    pub(crate) fn put(&self, key: CryptoHash, value: Arc<[u8]>) -> bool {
+        self.lock(&key).put_if_small(key, value)
     }
 
     pub fn clear(&self) {
-        self.0.lock().expect(POISONED_LOCK_ERR).clear()
+        for mut guard in self.lock_all() {
+            guard.clear();
+        }
     }
 
     pub fn update_cache(&self, ops: Vec<(CryptoHash, Option<&[u8]>)>) {
-        let mut guard = self.0.lock().expect(POISONED_LOCK_ERR);
-        for (hash, opt_value_rc) in ops {
-            if let Some(value_rc) = opt_value_rc {
-                if let (Some(value), _rc) = decode_value_with_rc(&value_rc) {
-                    if value.len() < TrieConfig::max_cached_value_size() {
-                        guard.put(hash, value.into());
+        let groups = self.group_by_segment(ops, |(hash, _)| hash);
+        for (segment, ops) in self.0.segments.iter().zip(groups) {
+            if ops.is_empty() {
+                continue;
+            }
+            let mut guard = segment.lock().expect(POISONED_LOCK_ERR);
+            for (hash, opt_value_rc) in ops {
+                if let Some(value_rc) = opt_value_rc {
+                    if let (Some(value), _rc) = decode_value_with_rc(&value_rc) {
+                        guard.put_if_small(hash, value.into());
                     } else {
-                        guard.metrics.shard_cache_too_large.inc();
+                        guard.pop(&hash);
                     }
                 } else {
                     guard.pop(&hash);
                 }
-            } else {
-                guard.pop(&hash);
             }
         }
     }
 
+    /// Keys of at most `limit` cached nodes, most recently used first.
+    ///
+    /// The order within each segment is exact.  Segments are interleaved,
+    /// starting with the most recently used one, so the first key is always
+    /// the most recently used one overall.
+    pub(crate) fn recent_keys(&self, limit: usize) -> Vec<CryptoHash> {
+        let mut segments: Vec<(Instant, std::vec::IntoIter<CryptoHash>)> = self
+            .lock_all()
+            .map(|guard| (guard.last_used, guard.recent_keys(limit).into_iter()))
+            .collect();
+        segments.sort_by(|(a, _), (b, _)| b.cmp(a));
+        let mut keys = Vec::new();
+        while keys.len() < limit {
+            let round: Vec<CryptoHash> =
+                segments.iter_mut().filter_map(|(_, segment_keys)| segment_keys.next()).collect();
+            if round.is_empty() {
+                break;
+            }
+            keys.extend(round);
+        }
+        keys.truncate(limit);
+        keys
+    }
+
+    /// Puts nodes read from the database into the cache.  The last node ends
+    /// up as the most recently used one.
+    pub(crate) fn put_all(&self, nodes: impl IntoIterator<Item = (CryptoHash, Arc<[u8]>)>) {
+        let groups = self.group_by_segment(nodes, |(hash, _)| hash);
+        for (segment, nodes) in self.0.segments.iter().zip(groups) {
+            if nodes.is_empty() {
+                continue;
+            }
+            let mut guard = segment.lock().expect(POISONED_LOCK_ERR);
+            for (hash, value) in nodes {
+                guard.put_if_small(hash, value);
+            }
+        }
+    }
+
+    /// Removes values that are known to be no longer needed from the cache.
+    pub(crate) fn evict(&self, keys: &[CryptoHash]) {
+        let groups = self.group_by_segment(keys, |key| *key);
+        for (segment, keys) in self.0.segments.iter().zip(groups) {
+            if keys.is_empty() {
+                continue;
+            }
+            let mut guard = segment.lock().expect(POISONED_LOCK_ERR);
+            for key in keys {
+                guard.remove(key);
+            }
+        }
+    }
+
+    /// Snapshot of the size and eviction counters of the cache, summed over
+    /// all segments.
+    pub fn stats(&self) -> TrieCacheStats {
+        let mut guards = self.lock_all();
+        let mut stats = guards.next().expect("cache has at least one segment").stats();
+        for guard in guards {
+            let segment = guard.stats();
+            stats.len += segment.len;
+            stats.total_size += segment.total_size;
+            stats.total_size_limit += segment.total_size_limit;
+            stats.deletions_queue_len += segment.deletions_queue_len;
+            stats.capacity_evictions += segment.capacity_evictions;
+            stats.deletion_evictions += segment.deletion_evictions;
+            stats.too_large += segment.too_large;
+        }
+        stats
+    }
+
     #[cfg(test)]
     pub(crate) fn len(&self) -> usize {
-        let guard = self.0.lock().expect(POISONED_LOCK_ERR);
-        guard.len()
+        self.lock_all().map(|guard| guard.len()).sum()
     }
+
+    #[cfg(test)]
+    pub(crate) fn num_segments(&self) -> usize {
+        self.0.segments.len()
+    }
+}
+
+/// Part `index` of `total` split into `parts` parts that differ by at most
+/// one and add up to `total`.
+fn split_evenly(total: u64, parts: usize, index: usize) -> u64 {
+    let parts = parts as u64;
+    total / parts + u64::from((index as u64) < total % parts)
 }
 
 pub trait TrieStorage {
@@ -289,6 +577,26 @@ pub trait TrieStorage {
     /// StorageError if the storage fails internally or the hash is not present.
     fn retrieve_raw_bytes(&self, hash: &CryptoHash) -> Result<Arc<[u8]>, StorageError>;
 
+    /// Get bytes of multiple serialized TrieNodes or values, in the order of
+    /// `hashes`.
+    ///
+    /// Storages backed by the DB override this to read all nodes that are
+    /// not cached in one batch, which is much cheaper than reading them one
+    /// by one.
+    /// # Errors
+    /// StorageError if the storage fails internally or any hash is not present.
+    fn retrieve_raw_bytes_batch(
+        &self,
+        hashes: &[CryptoHash],
+    ) -> Result<Vec<Arc<[u8]>>, StorageError> {
+        hashes.iter().map(|hash| self.retrieve_raw_bytes(hash)).collect()
+    }
+
+    /// Hints that the nodes with the given hashes are likely to be retrieved
+    /// soon.  Storages that support reading ahead start reading them in the
+    /// background, others ignore the hint.
+    fn read_ahead(&self, _hashes: &[CryptoHash]) {}
+
     fn as_caching_storage(&self) -> Option<&TrieCachingStorage> {
         None
     }
@@ -306,53 +614,133 @@ pub trait TrieStorage {
 
 /// Records every value read by retrieve_raw_bytes.
 /// Used for obtaining state parts (and challenges in the future).
-/// TODO (#6316): implement proper nodes counting logic as in TrieCachingStorage
+///
+/// Nodes are counted as if the recorded nodes were the chunk cache: the first
+/// read of a node is a DB read, repeated reads are memory reads.
 pub struct TrieRecordingStorage {
     pub(crate) store: Store,
     pub(crate) shard_uid: ShardUId,
     pub(crate) recorded: RefCell<HashMap<CryptoHash, Arc<[u8]>>>,
+    pub(crate) db_read_nodes: Cell<u64>,
+    pub(crate) mem_read_nodes: Cell<u64>,
+    /// Set if the recorded `TrieCachingStorage` reads ahead, see
+    /// `TrieCachingStorage::set_read_ahead`.
+    pub(crate) read_ahead_prefetcher: Option<PrefetchApi>,
+    /// Cold store of the recorded `TrieCachingStorage`, see
+    /// `TrieCachingStorage::with_cold_store`.
+    pub(crate) cold_store: Option<ColdStateStore>,
+}
+
+impl TrieRecordingStorage {
+    /// Takes the node from the prefetch staging area if it was read ahead and
+    /// otherwise reads it from the DB.
+    fn read_through_staging_area(
+        &self,
+        prefetcher: &PrefetchApi,
+        hash: &CryptoHash,
+    ) -> Result<Arc<[u8]>, StorageError> {
+        let result = match prefetcher.prefetching.get_or_set_fetching(*hash) {
+            PrefetcherResult::Prefetched(value) => Ok(value),
+            PrefetcherResult::Pending => match prefetcher.prefetching.blocking_get(*hash) {
+                Some(value) => Ok(value),
+                None => self.read_from_db(hash),
+            },
+            PrefetcherResult::SlotReserved => self.read_from_db(hash),
+            // No slot was reserved, so there is nothing to release.
+            PrefetcherResult::MemoryLimitReached => return self.read_from_db(hash),
+        };
+        prefetcher.prefetching.release(hash);
+        result
+    }
+
+    fn read_from_db(&self, hash: &CryptoHash) -> Result<Arc<[u8]>, StorageError> {
+        read_node_from_db(&self.store, self.cold_store.as_ref(), self.shard_uid, hash)
+    }
 }
 
 impl TrieStorage for TrieRecordingStorage {
     fn retrieve_raw_bytes(&self, hash: &CryptoHash) -> Result<Arc<[u8]>, StorageError> {
         if let Some(val) = self.recorded.borrow().get(hash).cloned() {
+            self.mem_read_nodes.set(self.mem_read_nodes.get() + 1);
             return Ok(val);
         }
-        let key = TrieCachingStorage::get_key_from_shard_uid_and_hash(self.shard_uid, hash);
-        let val = self
-            .store
-            .get(DBCol::State, key.as_ref())
-            .map_err(|_| StorageError::StorageInternalError)?;
-        if let Some(val) = val {
-            let val = Arc::from(val);
-            self.recorded.borrow_mut().insert(*hash, Arc::clone(&val));
-            Ok(val)
-        } else {
-            Err(StorageError::StorageInconsistentState("Trie node missing".to_string()))
+        self.db_read_nodes.set(self.db_read_nodes.get() + 1);
+        let val = match &self.read_ahead_prefetcher {
+            Some(prefetcher) => self.read_through_staging_area(prefetcher, hash)?,
+            None => self.read_from_db(hash)?,
+        };
+        self.recorded.borrow_mut().insert(*hash, Arc::clone(&val));
+        Ok(val)
+    }
+
+    fn read_ahead(&self, hashes: &[CryptoHash]) {
+        let prefetcher = match &self.read_ahead_prefetcher {
+            Some(prefetcher) => prefetcher,
+            None => return,
+        };
+        let missing: Vec<CryptoHash> = {
+            let recorded = self.recorded.borrow();
+            hashes.iter().filter(|hash| !recorded.contains_key(*hash)).copied().collect()
+        };
+        if !missing.is_empty() {
+            // Reading ahead is best effort, nothing is lost if the queue is full.
+            let _ = prefetcher.prefetch_trie_nodes(missing);
         }
     }
 
+    fn retrieve_raw_bytes_batch(
+        &self,
+        hashes: &[CryptoHash],
+    ) -> Result<Vec<Arc<[u8]>>, StorageError> {
+        let missing = {
+            let recorded = self.recorded.borrow();
+            let mut seen = HashSet::new();
+            hashes
+                .iter()
+                .filter(|hash| !recorded.contains_key(*hash) && seen.insert(*hash))
+                .copied()
+                .collect::<Vec<_>>()
+        };
+        let values =
+            read_nodes_from_db(&self.store, self.cold_store.as_ref(), self.shard_uid, &missing)?;
+        self.db_read_nodes.set(self.db_read_nodes.get() + missing.len() as u64);
+        self.mem_read_nodes.set(self.mem_read_nodes.get() + (hashes.len() - missing.len()) as u64);
+        let mut recorded = self.recorded.borrow_mut();
+        recorded.extend(missing.into_iter().zip(values));
+        Ok(hashes.iter().map(|hash| recorded[hash].clone()).collect())
+    }
+
     fn as_recording_storage(&self) -> Option<&TrieRecordingStorage> {
         Some(self)
     }
 
     fn get_trie_nodes_count(&self) -> TrieNodesCount {
-        unimplemented!();
+        TrieNodesCount { db_reads: self.db_read_nodes.get(), mem_reads: self.mem_read_nodes.get() }
     }
 }
 
 /// Storage for validating recorded partial storage.
 /// visited_nodes are to validate that partial storage doesn't contain unnecessary nodes.
+///
+/// Nodes are counted the same way as by `TrieRecordingStorage` when the
+/// partial storage was recorded: the first access to a node is a DB read,
+/// repeated accesses are memory reads.
 pub struct TrieMemoryPartialStorage {
     pub(crate) recorded_storage: HashMap<CryptoHash, Arc<[u8]>>,
     pub(crate) visited_nodes: RefCell<HashSet<CryptoHash>>,
+    pub(crate) db_read_nodes: Cell<u64>,
+    pub(crate) mem_read_nodes: Cell<u64>,
 }
 
 impl TrieStorage for TrieMemoryPartialStorage {
     fn retrieve_raw_bytes(&self, hash: &CryptoHash) -> Result<Arc<[u8]>, StorageError> {
         let result = self.recorded_storage.get(hash).cloned().ok_or(StorageError::TrieNodeMissing);
         if result.is_ok() {
-            self.visited_nodes.borrow_mut().insert(*hash);
+            if self.visited_nodes.borrow_mut().insert(*hash) {
+                self.db_read_nodes.set(self.db_read_nodes.get() + 1);
+            } else {
+                self.mem_read_nodes.set(self.mem_read_nodes.get() + 1);
+            }
         }
         result
     }
@@ -362,13 +750,48 @@ impl TrieStorage for TrieMemoryPartialStorage {
     }
 
     fn get_trie_nodes_count(&self) -> TrieNodesCount {
-        unimplemented!();
+        TrieNodesCount { db_reads: self.db_read_nodes.get(), mem_reads: self.mem_read_nodes.get() }
+    }
+}
+
+/// Nodes and values read while applying a chunk, see
+/// [`TrieCachingStorage::chunk_cache`].
+#[derive(Default)]
+pub(crate) struct ChunkCache {
+    values: HashMap<CryptoHash, Arc<[u8]>>,
+    /// Total size of the cached values, in bytes.
+    total_size: u64,
+}
+
+impl ChunkCache {
+    pub(crate) fn get(&self, key: &CryptoHash) -> Option<&Arc<[u8]>> {
+        self.values.get(key)
+    }
+
+    pub(crate) fn contains_key(&self, key: &CryptoHash) -> bool {
+        self.values.contains_key(key)
+    }
+
+    pub(crate) fn insert(&mut self, key: CryptoHash, value: Arc<[u8]>) {
+        self.total_size += value.len() as u64;
+        if let Some(old_value) = self.values.insert(key, value) {
+            self.total_size -= old_value.len() as u64;
+        }
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    pub(crate) fn total_size(&self) -> u64 {
+        self.total_size
     }
 }
 
 /// Storage for reading State nodes and values from DB which caches reads.
 pub struct TrieCachingStorage {
     pub(crate) store: Store,
+    /// Prefix of the keys in `DBCol::State`, see `ShardTries::get_state_shard_uid`.
     pub(crate) shard_uid: ShardUId,
 
     /// Caches ever requested items for the shard `shard_uid`. Used to speed up DB operations, presence of any item is
@@ -380,11 +803,20 @@ pub struct TrieCachingStorage {
     /// txs/receipts ends. Then cache is removed automatically in `apply_transactions_with_optional_storage_proof` when
     /// `TrieCachingStorage` is removed.
     /// Note that for both caches key is the hash of value, so for the fixed key the value is unique.
-    pub(crate) chunk_cache: RefCell<HashMap<CryptoHash, Arc<[u8]>>>,
+    pub(crate) chunk_cache: RefCell<ChunkCache>,
     pub(crate) cache_mode: Cell<TrieCacheMode>,
+    /// Total size of the chunk cache above which mischarged storage operations
+    /// are reported, see `TrieConfig::chunk_cache_size_limit`.
+    chunk_cache_size_limit: u64,
 
     /// The entry point for the runtime to submit prefetch requests.
     pub(crate) prefetch_api: Option<PrefetchApi>,
+    /// Whether nodes hinted with `read_ahead` are requested from the
+    /// prefetcher.
+    read_ahead: Cell<bool>,
+    /// Where nodes missing in `store` are looked up, on archival nodes with
+    /// split storage.
+    pub(crate) cold_store: Option<ColdStateStore>,
 
     /// Counts potentially expensive trie node reads which are served from disk in the worst case. Here we count reads
     /// from DB or shard cache.
@@ -401,15 +833,14 @@ struct TrieCacheInnerMetrics {
     chunk_cache_misses: GenericCounter<prometheus::core::AtomicU64>,
     shard_cache_hits: GenericCounter<prometheus::core::AtomicU64>,
     shard_cache_misses: GenericCounter<prometheus::core::AtomicU64>,
-    shard_cache_too_large: GenericCounter<prometheus::core::AtomicU64>,
-    shard_cache_size: GenericGauge<prometheus::core::AtomicI64>,
     chunk_cache_size: GenericGauge<prometheus::core::AtomicI64>,
-    shard_cache_current_total_size: GenericGauge<prometheus::core::AtomicI64>,
+    chunk_cache_current_total_size: GenericGauge<prometheus::core::AtomicI64>,
     prefetch_hits: GenericCounter<prometheus::core::AtomicU64>,
     prefetch_pending: GenericCounter<prometheus::core::AtomicU64>,
     prefetch_not_requested: GenericCounter<prometheus::core::AtomicU64>,
     prefetch_memory_limit_reached: GenericCounter<prometheus::core::AtomicU64>,
     prefetch_retry: GenericCounter<prometheus::core::AtomicU64>,
+    prefetch_wait_time: prometheus::Histogram,
 }
 
 impl TrieCachingStorage {
@@ -430,11 +861,8 @@ impl TrieCachingStorage {
             chunk_cache_misses: metrics::CHUNK_CACHE_MISSES.with_label_values(&metrics_labels),
             shard_cache_hits: metrics::SHARD_CACHE_HITS.with_label_values(&metrics_labels),
             shard_cache_misses: metrics::SHARD_CACHE_MISSES.with_label_values(&metrics_labels),
-            shard_cache_too_large: metrics::SHARD_CACHE_TOO_LARGE
-                .with_label_values(&metrics_labels),
-            shard_cache_size: metrics::SHARD_CACHE_SIZE.with_label_values(&metrics_labels),
             chunk_cache_size: metrics::CHUNK_CACHE_SIZE.with_label_values(&metrics_labels),
-            shard_cache_current_total_size: metrics::SHARD_CACHE_CURRENT_TOTAL_SIZE
+            chunk_cache_current_total_size: metrics::CHUNK_CACHE_CURRENT_TOTAL_SIZE
                 .with_label_values(&metrics_labels),
             prefetch_hits: metrics::PREFETCH_HITS.with_label_values(&metrics_labels[..1]),
             prefetch_pending: metrics::PREFETCH_PENDING.with_label_values(&metrics_labels[..1]),
@@ -443,6 +871,7 @@ impl TrieCachingStorage {
             prefetch_memory_limit_reached: metrics::PREFETCH_MEMORY_LIMIT_REACHED
                 .with_label_values(&metrics_labels[..1]),
             prefetch_retry: metrics::PREFETCH_RETRY.with_label_values(&metrics_labels[..1]),
+            prefetch_wait_time: metrics::PREFETCH_WAIT_TIME.with_label_values(&metrics_labels[..1]),
         };
         TrieCachingStorage {
             store,
@@ -450,13 +879,60 @@ impl TrieCachingStorage {
             shard_cache,
             cache_mode: Cell::new(TrieCacheMode::CachingShard),
             prefetch_api,
+            read_ahead: Cell::new(false),
+            cold_store: None,
             chunk_cache: RefCell::new(Default::default()),
+            chunk_cache_size_limit: DEFAULT_CHUNK_CACHE_SIZE_LIMIT,
             db_read_nodes: Cell::new(0),
             mem_read_nodes: Cell::new(0),
             metrics,
         }
     }
 
+    /// Reads nodes stored under the prefix of another shard, for shards that
+    /// share the storage of the shard they were split from.
+    pub(crate) fn with_state_shard_uid(mut self, state_shard_uid: ShardUId) -> Self {
+        self.shard_uid = state_shard_uid;
+        self
+    }
+
+    /// Retries reads of nodes that are missing in the hot store on the cold
+    /// store, so that tries of old blocks can be read on archival nodes with
+    /// split storage.
+    pub(crate) fn with_cold_store(mut self, cold_store: ColdStateStore) -> Self {
+        self.cold_store = Some(cold_store);
+        self
+    }
+
+    pub(crate) fn with_chunk_cache_size_limit(mut self, limit: u64) -> Self {
+        self.chunk_cache_size_limit = limit;
+        self
+    }
+
+    /// Puts a value read in the `CachingChunk` mode in the chunk cache.
+    ///
+    /// Gas costs bound the size of the chunk cache, so exceeding the limit
+    /// means that storage operations are undercharged.  This is reported once
+    /// per chunk, as an error on nightly builds.
+    fn put_in_chunk_cache(&self, hash: CryptoHash, value: Arc<[u8]>) -> Result<(), StorageError> {
+        let mut chunk_cache = self.chunk_cache.borrow_mut();
+        let was_within_limit = chunk_cache.total_size() <= self.chunk_cache_size_limit;
+        chunk_cache.insert(hash, value);
+        let total_size = chunk_cache.total_size();
+        self.metrics.chunk_cache_current_total_size.set(total_size as i64);
+        if was_within_limit && total_size > self.chunk_cache_size_limit {
+            let message = format!(
+                "chunk cache of shard {} holds {} bytes, over the limit of {} bytes",
+                self.shard_uid, total_size, self.chunk_cache_size_limit
+            );
+            if cfg!(feature = "nightly") {
+                return Err(StorageError::StorageInconsistentState(message));
+            }
+            log_assert!(false, "{}", message);
+        }
+        Ok(())
+    }
+
     pub(crate) fn get_shard_uid_and_hash_from_key(
         key: &[u8],
     ) -> Result<(ShardUId, CryptoHash), std::io::Error> {
@@ -486,10 +962,50 @@ impl TrieCachingStorage {
         self.mem_read_nodes.set(self.mem_read_nodes.get() + 1);
     }
 
+    /// Counts nodes as read from the chunk cache without retrieving them.
+    ///
+    /// Only valid for nodes that are known to be in the chunk cache.
+    pub(crate) fn inc_mem_read_nodes_by(&self, num_nodes: u64) {
+        self.mem_read_nodes.set(self.mem_read_nodes.get() + num_nodes);
+    }
+
     /// Set cache mode.
     pub fn set_mode(&self, state: TrieCacheMode) {
         self.cache_mode.set(state);
     }
+
+    pub fn get_mode(&self) -> TrieCacheMode {
+        self.cache_mode.get()
+    }
+
+    /// Enables or disables reading ahead.
+    ///
+    /// When reading ahead, the children of the branch nodes the trie iterator
+    /// visits are requested from the prefetcher as soon as the branch is
+    /// reached, so that they are read from the DB in parallel instead of one
+    /// by one.  Has no effect on storages without a prefetcher.
+    pub fn set_read_ahead(&self, enabled: bool) {
+        self.read_ahead.set(enabled);
+    }
+
+    /// The prefetcher used for reading ahead, if reading ahead is enabled.
+    pub(crate) fn read_ahead_prefetcher(&self) -> Option<&PrefetchApi> {
+        self.prefetch_api.as_ref().filter(|_| self.read_ahead.get())
+    }
+
+    /// Cancels queued read-ahead requests and drops nodes that were read
+    /// ahead but never retrieved, e.g. because the iteration stopped before
+    /// reaching them.  Reads already in flight may still stage their nodes,
+    /// these are dropped by the next call.
+    ///
+    /// This affects all users of the prefetcher, so it should only be called
+    /// on storages dedicated to reading ahead.
+    pub(crate) fn stop_read_ahead(&self) {
+        if let Some(prefetcher) = self.read_ahead_prefetcher() {
+            prefetcher.clear_queue();
+            prefetcher.clear_data();
+        }
+    }
 }
 
 impl TrieStorage for TrieCachingStorage {
@@ -505,9 +1021,7 @@ impl TrieStorage for TrieCachingStorage {
         self.metrics.chunk_cache_misses.inc();
 
         // Try to get value from shard cache containing most recently touched nodes.
-        let mut guard = self.shard_cache.0.lock().expect(POISONED_LOCK_ERR);
-        self.metrics.shard_cache_size.set(guard.len() as i64);
-        self.metrics.shard_cache_current_total_size.set(guard.current_total_size() as i64);
+        let mut guard = self.shard_cache.lock(hash);
         let val = match guard.get(hash) {
             Some(val) => {
                 self.metrics.shard_cache_hits.inc();
@@ -548,9 +1062,11 @@ impl TrieStorage for TrieCachingStorage {
                         PrefetcherResult::Pending => {
                             near_o11y::io_trace!(count: "prefetch_pending");
                             self.metrics.prefetch_pending.inc();
-                            std::thread::yield_now();
                             // If data is already being prefetched, wait for that instead of sending a new request.
-                            match prefetcher.prefetching.blocking_get(hash.clone()) {
+                            let wait_timer = self.metrics.prefetch_wait_time.start_timer();
+                            let prefetched = prefetcher.prefetching.blocking_get(hash.clone());
+                            wait_timer.observe_duration();
+                            match prefetched {
                                 Some(value) => value,
                                 // Only main thread (this one) removes values from staging area,
                                 // therefore blocking read will usually not return empty unless there
@@ -573,11 +1089,7 @@ impl TrieStorage for TrieCachingStorage {
                 // It is fine to have a size limit for shard cache and **not** have a limit for chunk cache, because key
                 // is always a value hash, so for each key there could be only one value, and it is impossible to have
                 // **different** values for the given key in shard and chunk caches.
-                if val.len() < TrieConfig::max_cached_value_size() {
-                    let mut guard = self.shard_cache.0.lock().expect(POISONED_LOCK_ERR);
-                    guard.put(*hash, val.clone());
-                } else {
-                    self.metrics.shard_cache_too_large.inc();
+                if !self.shard_cache.put(*hash, val.clone()) {
                     near_o11y::io_trace!(count: "shard_cache_too_large");
                 }
 
@@ -592,22 +1104,89 @@ impl TrieStorage for TrieCachingStorage {
 
         // Because node is not present in chunk cache, increment the nodes counter and optionally insert it into the
         // chunk cache.
-        // Note that we don't have a size limit for values in the chunk cache. There are two reasons:
+        // Note that we don't have a hard size limit for values in the chunk cache. There are two reasons:
         // - for nodes, value size is an implementation detail. If we change internal representation of a node (e.g.
         // change `memory_usage` field from `RawTrieNodeWithSize`), this would have to be a protocol upgrade.
         // - total size of all values is limited by the runtime fees. More thoroughly:
         // - - number of nodes is limited by receipt gas limit / touching trie node fee ~= 500 Tgas / 16 Ggas = 31_250;
         // - - size of trie keys and values is limited by receipt gas limit / lowest per byte fee
         // (`storage_read_value_byte`) ~= (500 * 10**12 / 5611005) / 2**20 ~= 85 MB.
-        // All values are given as of 16/03/2022. We may consider more precise limit for the chunk cache as well.
+        // All values are given as of 16/03/2022. `put_in_chunk_cache` reports caches growing beyond these bounds.
         self.inc_db_read_nodes();
         if let TrieCacheMode::CachingChunk = self.cache_mode.borrow().get() {
-            self.chunk_cache.borrow_mut().insert(*hash, val.clone());
+            self.put_in_chunk_cache(*hash, val.clone())?;
         };
 
         Ok(val)
     }
 
+    fn retrieve_raw_bytes_batch(
+        &self,
+        hashes: &[CryptoHash],
+    ) -> Result<Vec<Arc<[u8]>>, StorageError> {
+        // With a prefetcher, every DB read has to reserve a slot in the
+        // staging area first, see `retrieve_raw_bytes`.
+        if self.prefetch_api.is_some() {
+            return hashes.iter().map(|hash| self.retrieve_raw_bytes(hash)).collect();
+        }
+
+        // Read all nodes that are in neither of the caches in one batch and
+        // put them in the shard cache.
+        let missing = {
+            let chunk_cache = self.chunk_cache.borrow();
+            let mut seen = HashSet::new();
+            hashes
+                .iter()
+                .filter(|hash| {
+                    !chunk_cache.contains_key(*hash)
+                        && self.shard_cache.get(hash).is_none()
+                        && seen.insert(**hash)
+                })
+                .copied()
+                .collect::<Vec<_>>()
+        };
+        self.metrics.shard_cache_misses.inc_by(missing.len() as u64);
+        let values =
+            read_nodes_from_db(&self.store, self.cold_store.as_ref(), self.shard_uid, &missing)?;
+        let fetched: HashMap<CryptoHash, Arc<[u8]>> = missing.into_iter().zip(values).collect();
+        self.shard_cache.put_all(fetched.iter().map(|(hash, val)| (*hash, val.clone())));
+
+        // Count the nodes and fill the chunk cache exactly as if they were
+        // retrieved one by one.
+        hashes
+            .iter()
+            .map(|hash| match fetched.get(hash) {
+                Some(val) if !self.chunk_cache.borrow().contains_key(hash) => {
+                    self.inc_db_read_nodes();
+                    if let TrieCacheMode::CachingChunk = self.cache_mode.borrow().get() {
+                        self.put_in_chunk_cache(*hash, val.clone())?;
+                    }
+                    Ok(val.clone())
+                }
+                _ => self.retrieve_raw_bytes(hash),
+            })
+            .collect()
+    }
+
+    fn read_ahead(&self, hashes: &[CryptoHash]) {
+        let prefetcher = match self.read_ahead_prefetcher() {
+            Some(prefetcher) => prefetcher,
+            None => return,
+        };
+        let missing: Vec<CryptoHash> = {
+            let chunk_cache = self.chunk_cache.borrow();
+            hashes
+                .iter()
+                .filter(|hash| !chunk_cache.contains_key(*hash) && !self.shard_cache.contains(hash))
+                .copied()
+                .collect()
+        };
+        if !missing.is_empty() {
+            // Reading ahead is best effort, nothing is lost if the queue is full.
+            let _ = prefetcher.prefetch_trie_nodes(missing);
+        }
+    }
+
     fn as_caching_storage(&self) -> Option<&TrieCachingStorage> {
         Some(self)
     }
@@ -617,22 +1196,90 @@ impl TrieStorage for TrieCachingStorage {
     }
 }
 
+/// Cold store of an archival node with split storage.  Trie nodes of old
+/// blocks are only kept there, so reads that miss in the hot store are
+/// retried on it.
+#[derive(Clone)]
+pub(crate) struct ColdStateStore {
+    store: Store,
+    reads: GenericCounter<prometheus::core::AtomicU64>,
+    read_latency: prometheus::Histogram,
+}
+
+impl ColdStateStore {
+    pub(crate) fn new(store: Store, shard_uid: ShardUId) -> Self {
+        let mut buffer = itoa::Buffer::new();
+        let shard_id = buffer.format(shard_uid.shard_id);
+        Self {
+            store,
+            reads: metrics::COLD_STATE_READS.with_label_values(&[shard_id]),
+            read_latency: metrics::COLD_STATE_READ_LATENCY.with_label_values(&[shard_id]),
+        }
+    }
+
+    /// Reads a node by its key in the hot store.  The cold store drops the
+    /// `ShardUId` prefix of the key on its own.
+    fn read_node(&self, key: &[u8]) -> Result<Arc<[u8]>, StorageError> {
+        self.reads.inc();
+        let _timer = self.read_latency.start_timer();
+        self.store
+            .get(DBCol::State, key)
+            .map_err(|_| StorageError::StorageInternalError)?
+            .map(Arc::from)
+            .ok_or_else(trie_node_missing)
+    }
+}
+
+fn trie_node_missing() -> StorageError {
+    StorageError::StorageInconsistentState("Trie node missing".to_string())
+}
+
+/// Reads a node from `store`, or from `cold_store` if `store` doesn't have it.
 fn read_node_from_db(
     store: &Store,
+    cold_store: Option<&ColdStateStore>,
     shard_uid: ShardUId,
     hash: &CryptoHash,
 ) -> Result<Arc<[u8]>, StorageError> {
     let key = TrieCachingStorage::get_key_from_shard_uid_and_hash(shard_uid, hash);
-    let val = store
-        .get(DBCol::State, key.as_ref())
+    let val =
+        store.get(DBCol::State, key.as_ref()).map_err(|_| StorageError::StorageInternalError)?;
+    match (val, cold_store) {
+        (Some(val), _) => Ok(val.into()),
+        (None, Some(cold_store)) => cold_store.read_node(key.as_ref()),
+        (None, None) => Err(trie_node_missing()),
+    }
+}
+
+/// Batched version of [`read_node_from_db`], values are returned in the order
+/// of `hashes`.
+pub(crate) fn read_nodes_from_db(
+    store: &Store,
+    cold_store: Option<&ColdStateStore>,
+    shard_uid: ShardUId,
+    hashes: &[CryptoHash],
+) -> Result<Vec<Arc<[u8]>>, StorageError> {
+    let keys: Vec<_> = hashes
+        .iter()
+        .map(|hash| TrieCachingStorage::get_key_from_shard_uid_and_hash(shard_uid, hash))
+        .collect();
+    let keys: Vec<&[u8]> = keys.iter().map(|key| key.as_ref()).collect();
+    store
+        .multi_get(DBCol::State, &keys)
         .map_err(|_| StorageError::StorageInternalError)?
-        .ok_or_else(|| StorageError::StorageInconsistentState("Trie node missing".to_string()))?;
-    Ok(val.into())
+        .into_iter()
+        .zip(&keys)
+        .map(|(val, key)| match (val, cold_store) {
+            (Some(val), _) => Ok(Arc::from(val)),
+            (None, Some(cold_store)) => cold_store.read_node(key),
+            (None, None) => Err(trie_node_missing()),
+        })
+        .collect()
 }
 
 impl TrieCachingStorage {
     fn read_from_db(&self, hash: &CryptoHash) -> Result<Arc<[u8]>, StorageError> {
-        read_node_from_db(&self.store, self.shard_uid, hash)
+        read_node_from_db(&self.store, self.cold_store.as_ref(), self.shard_uid, hash)
     }
 
     pub fn prefetch_api(&self) -> &Option<PrefetchApi> {
@@ -658,7 +1305,7 @@ impl TrieDBStorage {
 
 impl TrieStorage for TrieDBStorage {
     fn retrieve_raw_bytes(&self, hash: &CryptoHash) -> Result<Arc<[u8]>, StorageError> {
-        read_node_from_db(&self.store, self.shard_uid, hash)
+        read_node_from_db(&self.store, None, self.shard_uid, hash)
     }
 
     fn get_trie_nodes_count(&self) -> TrieNodesCount {
@@ -701,12 +1348,15 @@ mod bounded_queue_tests {
 
 #[cfg(test)]
 mod trie_cache_tests {
+    use crate::db::refcount::add_positive_refcount;
     use crate::trie::trie_storage::TrieCacheInner;
     use crate::{StoreConfig, TrieCache, TrieConfig};
     use near_primitives::hash::hash;
     use near_primitives::shard_layout::ShardUId;
     use near_primitives::types::ShardId;
 
+    const TEST_MAX_VALUE_SIZE: usize = 1000;
+
     fn put_value(cache: &mut TrieCacheInner, value: &[u8]) {
         cache.put(hash(value), value.into());
     }
@@ -715,7 +1365,13 @@ mod trie_cache_tests {
     fn test_size_limit() {
         let value_size_sum = 5;
         let memory_overhead = 2 * TrieCacheInner::PER_ENTRY_OVERHEAD;
-        let mut cache = TrieCacheInner::new(100, value_size_sum + memory_overhead, 0, false);
+        let mut cache = TrieCacheInner::new(
+            100,
+            value_size_sum + memory_overhead,
+            TEST_MAX_VALUE_SIZE,
+            0,
+            false,
+        );
         // Add three values. Before each put, condition on total size should not be triggered.
         put_value(&mut cache, &[1, 1]);
         assert_eq!(cache.current_total_size(), 2 + TrieCacheInner::PER_ENTRY_OVERHEAD);
@@ -733,7 +1389,7 @@ mod trie_cache_tests {
 
     #[test]
     fn test_deletions_queue() {
-        let mut cache = TrieCacheInner::new(2, 1000, 0, false);
+        let mut cache = TrieCacheInner::new(2, 1000, TEST_MAX_VALUE_SIZE, 0, false);
         // Add two values to the cache.
         put_value(&mut cache, &[1]);
         put_value(&mut cache, &[1, 1]);
@@ -747,12 +1403,34 @@ mod trie_cache_tests {
         assert_eq!(cache.pop(&hash(&[1])), Some((hash(&[1]), vec![1].into())));
     }
 
+    #[test]
+    fn test_eviction_stats() {
+        // Small enough that the third put has to evict the first value.
+        let total_size_limit = TrieCacheInner::PER_ENTRY_OVERHEAD + 1;
+        let mut cache = TrieCacheInner::new(1, total_size_limit, 2, 0, false);
+        put_value(&mut cache, &[1]);
+        put_value(&mut cache, &[2]);
+        put_value(&mut cache, &[3]);
+        cache.skip_too_large();
+        assert_eq!(cache.pop(&hash(&[2])), None);
+        assert_eq!(cache.pop(&hash(&[3])), Some((hash(&[2]), vec![2].into())));
+        cache.remove(&hash(&[3]));
+
+        let stats = cache.stats();
+        assert_eq!(stats.len, 0);
+        assert_eq!(stats.total_size, 0);
+        assert_eq!(stats.deletions_queue_len, 1);
+        assert_eq!(stats.capacity_evictions, 1);
+        assert_eq!(stats.deletion_evictions, 2);
+        assert_eq!(stats.too_large, 1);
+    }
+
     /// test implicit capacity limit imposed by memory limit
     #[test]
     fn test_cache_capacity() {
         let capacity = 2;
         let total_size_limit = TrieCacheInner::PER_ENTRY_OVERHEAD * capacity;
-        let mut cache = TrieCacheInner::new(100, total_size_limit, 0, false);
+        let mut cache = TrieCacheInner::new(100, total_size_limit, TEST_MAX_VALUE_SIZE, 0, false);
         put_value(&mut cache, &[1]);
         put_value(&mut cache, &[2]);
         put_value(&mut cache, &[3]);
@@ -765,7 +1443,7 @@ mod trie_cache_tests {
     #[test]
     fn test_small_memory_limit() {
         let total_size_limit = 1;
-        let mut cache = TrieCacheInner::new(100, total_size_limit, 0, false);
+        let mut cache = TrieCacheInner::new(100, total_size_limit, TEST_MAX_VALUE_SIZE, 0, false);
         put_value(&mut cache, &[1, 2, 3]);
         put_value(&mut cache, &[2, 3, 4]);
         put_value(&mut cache, &[3, 4, 5]);
@@ -798,6 +1476,79 @@ mod trie_cache_tests {
         check_cache_size(&trie_config, 0, true, S0_VIEW_SIZE);
     }
 
+    /// Check that large caches are split into segments which together respect
+    /// the configured limit, and that small caches are not split.
+    #[test]
+    fn test_cache_segments() {
+        let shard_uid = ShardUId::single_shard();
+        let mut trie_config = TrieConfig::default();
+        let total_size_limit = 100 * TrieCache::MIN_SEGMENT_SIZE_LIMIT + 5;
+        trie_config.shard_cache_config.per_shard_max_bytes.insert(shard_uid, total_size_limit);
+        let cache = TrieCache::new(&trie_config, shard_uid, false);
+        assert_eq!(cache.num_segments(), TrieCache::MAX_SEGMENTS);
+        assert_eq!(cache.stats().total_size_limit, total_size_limit);
+
+        let values: Vec<Vec<u8>> = (0..=255u8).map(|i| vec![i]).collect();
+        cache.put_all(values.iter().map(|value| (hash(value), value.as_slice().into())));
+        assert_eq!(cache.len(), values.len());
+        assert_eq!(cache.get(&hash(&[7])).as_deref(), Some(&[7u8][..]));
+        let recent_keys = cache.recent_keys(values.len());
+        assert_eq!(recent_keys.len(), values.len());
+        assert_eq!(recent_keys[0], hash(&[7]));
+
+        cache.evict(&[hash(&[7]), hash(&[8])]);
+        let stats = cache.stats();
+        assert_eq!(stats.len, values.len() - 2);
+        assert_eq!(stats.deletion_evictions, 2);
+
+        trie_config.shard_cache_config.per_shard_max_bytes.insert(shard_uid, 5);
+        let cache = TrieCache::new(&trie_config, shard_uid, false);
+        assert_eq!(cache.num_segments(), 1);
+    }
+
+    /// Check that the value size limits of shard and view caches are applied
+    /// separately.
+    #[test]
+    fn test_max_cached_value_size() {
+        let mut store_config = StoreConfig::default();
+        store_config.trie_cache.max_cached_value_size = 10;
+        store_config.view_trie_cache.max_cached_value_size = 20;
+        let trie_config = TrieConfig::from_store_config(&store_config);
+        let shard_uid = ShardUId::single_shard();
+
+        for (is_view, max_value_size) in [(false, 10), (true, 20)] {
+            let trie_cache = TrieCache::new(&trie_config, shard_uid, is_view);
+            assert_eq!(trie_cache.lock(&hash(&[])).max_value_size, max_value_size);
+            let small_value = vec![0u8; max_value_size - 1];
+            let large_value = vec![1u8; max_value_size];
+            let rc = std::num::NonZeroU32::new(1).unwrap();
+            let small_value_rc = add_positive_refcount(&small_value, rc);
+            let large_value_rc = add_positive_refcount(&large_value, rc);
+            trie_cache.update_cache(vec![
+                (hash(&small_value), Some(&small_value_rc)),
+                (hash(&large_value), Some(&large_value_rc)),
+            ]);
+            assert!(trie_cache.get(&hash(&small_value)).is_some());
+            assert!(trie_cache.get(&hash(&large_value)).is_none());
+        }
+    }
+
+    /// Check that per-shard capacities in the deprecated format are still
+    /// applied to the non-view caches.
+    #[test]
+    fn test_deprecated_trie_cache_capacities() {
+        let mut store_config = StoreConfig::default();
+        let s0 = ShardUId { version: 0, shard_id: 0 };
+        store_config.trie_cache.default_max_bytes = 1;
+        store_config.trie_cache_capacities = vec![(s0, 10)];
+        let trie_config = TrieConfig::from_store_config(&store_config);
+
+        let expected_size = TrieConfig::deprecated_num_entry_to_memory_limit(10);
+        check_cache_size(&trie_config, 0, false, expected_size);
+        check_cache_size(&trie_config, 1, false, 1);
+        check_cache_size(&trie_config, 0, true, store_config.view_trie_cache.default_max_bytes);
+    }
+
     #[track_caller]
     fn check_cache_size(
         trie_config: &TrieConfig,
@@ -807,7 +1558,8 @@ mod trie_cache_tests {
     ) {
         let shard_uid = ShardUId { version: 0, shard_id: shard_id as u32 };
         let trie_cache = TrieCache::new(&trie_config, shard_uid, is_view);
-        assert_eq!(expected_size, trie_cache.0.lock().unwrap().total_size_limit,);
-        assert_eq!(is_view, trie_cache.0.lock().unwrap().is_view,);
+        let stats = trie_cache.stats();
+        assert_eq!(expected_size, stats.total_size_limit);
+        assert_eq!(is_view, stats.is_view);
     }
 }
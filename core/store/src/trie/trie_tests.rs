@@ -179,6 +179,31 @@ mod nodes_counter_tests {
         assert_eq!(storage.shard_cache.len(), 9);
     }
 
+    // Check that nodes read from recorded partial storage are counted as db reads on first access and as
+    // mem reads afterwards.
+    #[test]
+    fn test_count_partial_storage() {
+        let trie_items = vec![
+            (create_trie_key(&[0, 0, 0]), Some(vec![0])),
+            (create_trie_key(&[0, 1, 1]), Some(vec![1])),
+            (create_trie_key(&[1, 0, 0]), Some(vec![2])),
+        ];
+        let trie = create_trie(&trie_items);
+        let recording_trie = trie.recording_reads();
+        for (key, _) in &trie_items {
+            recording_trie.get(key).unwrap();
+        }
+        let partial_storage = recording_trie.recorded_storage().unwrap();
+        let trie = Trie::from_recorded_storage(partial_storage, trie.get_root().clone());
+
+        let (key, value) = &trie_items[0];
+        assert_eq!(trie.get(key).unwrap(), *value);
+        assert_eq!(trie.get_trie_nodes_count(), TrieNodesCount { db_reads: 5, mem_reads: 0 });
+        assert_eq!(trie.get(key).unwrap(), *value);
+        assert_eq!(trie.get_trie_nodes_count(), TrieNodesCount { db_reads: 5, mem_reads: 5 });
+        assert_eq!(get_touched_nodes_numbers(Rc::new(trie), &trie_items[1..]), vec![2, 2]);
+    }
+
     // Check that same values are stored in the same trie node.
     #[test]
     fn test_repeated_values_count() {
@@ -200,10 +225,11 @@ mod nodes_counter_tests {
 #[cfg(test)]
 mod trie_storage_tests {
     use super::*;
+    use crate::flat_state::FlatStateFactory;
     use crate::test_utils::{create_test_store, create_tries};
     use crate::trie::trie_storage::{TrieCache, TrieCachingStorage, TrieDBStorage};
     use crate::trie::TrieRefcountChange;
-    use crate::{Store, TrieChanges, TrieConfig};
+    use crate::{ShardTries, Store, TrieChanges, TrieConfig};
     use assert_matches::assert_matches;
     use near_primitives::hash::hash;
     use near_primitives::types::TrieCacheMode;
@@ -282,10 +308,136 @@ mod trie_storage_tests {
         assert_matches!(result, Err(StorageError::StorageInconsistentState(_)));
     }
 
+    /// Check that values retrieved in a batch are the same as when retrieved
+    /// one by one, and that they are cached and counted in the same way.
+    #[test]
+    fn test_retrieve_batch() {
+        let values = vec![vec![1u8], vec![2u8], vec![3u8]];
+        let shard_uid = ShardUId::single_shard();
+        let store = create_store_with_values(&values, shard_uid);
+        let trie_cache = TrieCache::new(&TrieConfig::default(), shard_uid, false);
+        let trie_caching_storage =
+            TrieCachingStorage::new(store, trie_cache.clone(), shard_uid, false, None);
+        trie_caching_storage.set_mode(TrieCacheMode::CachingChunk);
+        let keys: Vec<_> = values.iter().map(|value| hash(value)).collect();
+        trie_caching_storage.retrieve_raw_bytes(&keys[0]).unwrap();
+
+        // The first value is in the chunk cache already, the last one is
+        // requested twice.
+        let indices = [0, 1, 2, 2];
+        let batch: Vec<_> = indices.iter().map(|&i| keys[i]).collect();
+        let count_before = trie_caching_storage.get_trie_nodes_count();
+        let result = trie_caching_storage.retrieve_raw_bytes_batch(&batch).unwrap();
+        let count_delta = trie_caching_storage.get_trie_nodes_count() - count_before;
+        let result: Vec<_> = result.iter().map(|value| value.to_vec()).collect();
+        let expected: Vec<_> = indices.iter().map(|&i| values[i].clone()).collect();
+        assert_eq!(result, expected);
+        assert_eq!(count_delta.db_reads, 2);
+        assert_eq!(count_delta.mem_reads, 2);
+        for key in &keys {
+            assert!(trie_cache.get(key).is_some());
+        }
+
+        let wrong_key = hash(&vec![4]);
+        assert_matches!(
+            trie_caching_storage.retrieve_raw_bytes_batch(&[keys[0], wrong_key]),
+            Err(StorageError::StorageInconsistentState(_))
+        );
+    }
+
+    /// Check that nodes only inserted by an abandoned fork are evicted from
+    /// the shard cache, while nodes shared with the canonical chain stay.
+    #[test]
+    fn test_evict_reverted_insertions() {
+        let tries = create_tries();
+        let shard_uid = ShardUId::single_shard();
+        let shared = (b"shared".to_vec(), Some(b"value".to_vec()));
+        let trie = tries.get_trie_for_shard(shard_uid, Trie::EMPTY_ROOT);
+        let canonical = trie.update(vec![shared.clone()]).unwrap();
+        let fork = trie
+            .update(vec![shared.clone(), (b"fork".to_vec(), Some(b"fork value".to_vec()))])
+            .unwrap();
+        for trie_changes in [&canonical, &fork] {
+            let mut store_update = tries.store_update();
+            tries.apply_insertions(trie_changes, shard_uid, &mut store_update);
+            store_update.commit().unwrap();
+        }
+
+        let trie = tries.get_trie_for_shard(shard_uid, fork.new_root);
+        let shard_cache = &trie.storage.as_caching_storage().unwrap().shard_cache;
+        let cached = |hash: &CryptoHash| shard_cache.get(hash).is_some();
+        assert!(cached(&hash(b"value")));
+        assert!(cached(&hash(b"fork value")));
+
+        tries.evict_reverted_insertions(&fork, shard_uid).unwrap();
+        assert!(cached(&hash(b"value")));
+        assert!(!cached(&hash(b"fork value")));
+        assert!(!cached(&fork.new_root));
+        assert!(cached(&canonical.new_root));
+    }
+
+    /// Check that the shard cache of a restarted node is warmed up with the
+    /// saved nodes, in the same LRU order.
+    #[test]
+    fn test_cache_warmup() {
+        let values: Vec<Vec<u8>> = vec![b"a".to_vec(), b"b".to_vec(), b"c".to_vec()];
+        let shard_uid = ShardUId::single_shard();
+        let store = create_store_with_values(&values, shard_uid);
+        let tries = ShardTries::test(store.clone(), 1);
+        let trie = tries.get_trie_for_shard(shard_uid, Trie::EMPTY_ROOT);
+        let cache = &trie.storage.as_caching_storage().unwrap().shard_cache;
+        cache.put_all(values.iter().map(|value| (hash(value), Arc::from(value.as_slice()))));
+        cache.get(&hash(b"a"));
+        let recent_keys = cache.recent_keys(values.len());
+        assert_eq!(recent_keys[0], hash(b"a"));
+
+        let mut store_update = tries.store_update();
+        tries.save_cache_warmup(&mut store_update).unwrap();
+        store_update.commit().unwrap();
+
+        let restarted = ShardTries::test(store, 1);
+        assert_eq!(restarted.warm_up_caches().unwrap(), values.len());
+        let trie = restarted.get_trie_for_shard(shard_uid, Trie::EMPTY_ROOT);
+        let cache = &trie.storage.as_caching_storage().unwrap().shard_cache;
+        assert_eq!(cache.recent_keys(values.len()), recent_keys);
+    }
+
+    /// Check that nodes missing in the hot store are read from the cold store.
+    #[test]
+    fn test_cold_store_fallback() {
+        let values: Vec<Vec<u8>> = vec![b"a".to_vec(), b"b".to_vec()];
+        let shard_uid = ShardUId::single_shard();
+        let cold_store = create_store_with_values(&values, shard_uid);
+        let hot_store = create_store_with_values(&values[..1], shard_uid);
+        let tries = ShardTries::new(
+            hot_store.clone(),
+            TrieConfig::default(),
+            &[shard_uid],
+            FlatStateFactory::new(hot_store),
+            Some(cold_store),
+        );
+        let trie = tries.get_trie_for_shard(shard_uid, Trie::EMPTY_ROOT);
+        for value in &values {
+            assert_eq!(trie.storage.retrieve_raw_bytes(&hash(value)).unwrap().as_ref(), value);
+        }
+        let hashes: Vec<_> = values.iter().map(|value| hash(value)).collect();
+        let trie = tries.get_view_trie_for_shard(shard_uid, Trie::EMPTY_ROOT);
+        let batch = trie.storage.retrieve_raw_bytes_batch(&hashes).unwrap();
+        assert_eq!(batch.iter().map(|value| value.to_vec()).collect::<Vec<_>>(), values);
+
+        let trie = ShardTries::test(create_test_store(), 1)
+            .get_trie_for_shard(shard_uid, Trie::EMPTY_ROOT);
+        assert_matches!(
+            trie.storage.retrieve_raw_bytes(&hash(b"b")),
+            Err(StorageError::StorageInconsistentState(_))
+        );
+    }
+
     /// Check that large values does not fall into shard cache, but fall into chunk cache.
     #[test]
     fn test_large_value() {
-        let value = vec![1u8].repeat(TrieConfig::max_cached_value_size() + 1);
+        let max_value_size = TrieConfig::default().shard_cache_config.max_cached_value_size;
+        let value = vec![1u8].repeat(max_value_size + 1);
         let values = vec![value.clone()];
         let shard_uid = ShardUId::single_shard();
         let store = create_store_with_values(&values, shard_uid);
@@ -389,4 +541,42 @@ mod trie_storage_tests {
         assert_eq!(count_delta.db_reads, 0);
         assert_eq!(count_delta.mem_reads, 1);
     }
+
+    #[test]
+    fn test_chunk_cache_total_size() {
+        let values: Vec<Vec<u8>> = vec![vec![1], vec![2, 2], vec![3, 3, 3]];
+        let shard_uid = ShardUId::single_shard();
+        let store = create_store_with_values(&values, shard_uid);
+        let trie_cache = TrieCache::new(&TrieConfig::default(), shard_uid, false);
+        let trie_caching_storage =
+            TrieCachingStorage::new(store, trie_cache, shard_uid, false, None);
+
+        trie_caching_storage.set_mode(TrieCacheMode::CachingChunk);
+        for value in values.iter().chain(values.iter()) {
+            let result = trie_caching_storage.retrieve_raw_bytes(&hash(value));
+            assert_eq!(result.unwrap().as_ref(), value);
+        }
+        assert_eq!(trie_caching_storage.chunk_cache.borrow().total_size(), 6);
+    }
+
+    /// Check that nightly builds fail to read nodes once the chunk cache is
+    /// over its limit.
+    #[cfg(feature = "nightly")]
+    #[test]
+    fn test_chunk_cache_size_limit() {
+        let values: Vec<Vec<u8>> = vec![vec![1], vec![2]];
+        let shard_uid = ShardUId::single_shard();
+        let store = create_store_with_values(&values, shard_uid);
+        let trie_cache = TrieCache::new(&TrieConfig::default(), shard_uid, false);
+        let trie_caching_storage =
+            TrieCachingStorage::new(store, trie_cache, shard_uid, false, None)
+                .with_chunk_cache_size_limit(1);
+
+        trie_caching_storage.set_mode(TrieCacheMode::CachingChunk);
+        assert!(trie_caching_storage.retrieve_raw_bytes(&hash(&values[0])).is_ok());
+        assert_matches!(
+            trie_caching_storage.retrieve_raw_bytes(&hash(&values[1])),
+            Err(StorageError::StorageInconsistentState(_))
+        );
+    }
 }
@@ -1,4 +1,5 @@
-use std::collections::BTreeMap;
+use std::cell::RefCell;
+use std::collections::{BTreeMap, HashMap};
 
 use near_primitives::hash::CryptoHash;
 use near_primitives::types::{
@@ -32,6 +33,10 @@ pub struct TrieUpdate {
     pub trie: Rc<Trie>,
     committed: RawStateChanges,
     prospective: TrieUpdates,
+    /// Trie lookups done since the trie cache mode was last set, if the mode
+    /// is `CachingChunk`. Stores the result and the number of trie nodes on
+    /// the path to the key. See `TrieUpdate::trie_get_ref`.
+    trie_lookups: RefCell<HashMap<Vec<u8>, (Option<ValueRef>, u64)>>,
 }
 
 pub enum TrieUpdateValuePtr<'a> {
@@ -59,7 +64,12 @@ impl<'a> TrieUpdateValuePtr<'a> {
 
 impl TrieUpdate {
     pub fn new(trie: Rc<Trie>) -> Self {
-        TrieUpdate { trie, committed: Default::default(), prospective: Default::default() }
+        TrieUpdate {
+            trie,
+            committed: Default::default(),
+            prospective: Default::default(),
+            trie_lookups: Default::default(),
+        }
     }
 
     pub fn trie(&self) -> &Rc<Trie> {
@@ -80,13 +90,43 @@ impl TrieUpdate {
             }
         }
 
-        self.trie.get_ref(&key, mode).map(|option| {
+        self.trie_get_ref(&key, mode).map(|option| {
             option.map(|ValueRef { length, hash }| {
                 TrieUpdateValuePtr::HashAndSize(&self.trie, length, hash)
             })
         })
     }
 
+    /// Looks up the key in the trie, remembering the result while the trie
+    /// cache is in `CachingChunk` mode.
+    ///
+    /// In this mode, all nodes on the path to a key are in the chunk cache
+    /// after the first lookup. Walking the trie again would count each of them
+    /// as a memory read, so a remembered lookup counts the same nodes without
+    /// retrieving them. This keeps the touched trie node counts, and thus the
+    /// gas charged, exactly the same.
+    fn trie_get_ref(
+        &self,
+        key: &[u8],
+        mode: KeyLookupMode,
+    ) -> Result<Option<ValueRef>, StorageError> {
+        let storage = match self.trie.storage.as_caching_storage() {
+            Some(storage) if matches!(storage.get_mode(), TrieCacheMode::CachingChunk) => storage,
+            _ => return self.trie.get_ref(key, mode),
+        };
+        if let Some((value_ref, num_nodes)) = self.trie_lookups.borrow().get(key) {
+            storage.inc_mem_read_nodes_by(*num_nodes);
+            return Ok(value_ref.clone());
+        }
+        let nodes_before = storage.get_trie_nodes_count();
+        let value_ref = self.trie.get_ref(key, mode)?;
+        let nodes_after = storage.get_trie_nodes_count();
+        let num_nodes = (nodes_after.db_reads - nodes_before.db_reads)
+            + (nodes_after.mem_reads - nodes_before.mem_reads);
+        self.trie_lookups.borrow_mut().insert(key.to_vec(), (value_ref.clone(), num_nodes));
+        Ok(value_ref)
+    }
+
     pub fn get(&self, key: &TrieKey) -> Result<Option<Vec<u8>>, StorageError> {
         let key = key.to_vec();
         if let Some(key_value) = self.prospective.get(&key) {
@@ -104,11 +144,14 @@ impl TrieUpdate {
         // - Using `Vec<u8>` for sorting `BTreeMap` in the same order as a `Trie` and
         //   avoid recomputing `Vec<u8>` every time. It helps for merging iterators.
         // - Using `TrieKey` later for `RawStateChangesWithTrieKey` for State changes RPCs.
-        self.prospective
-            .insert(trie_key.to_vec(), TrieKeyValueUpdate { trie_key, value: Some(value) });
+        let key = trie_key.to_vec();
+        self.trie_lookups.get_mut().remove(&key);
+        self.prospective.insert(key, TrieKeyValueUpdate { trie_key, value: Some(value) });
     }
     pub fn remove(&mut self, trie_key: TrieKey) {
-        self.prospective.insert(trie_key.to_vec(), TrieKeyValueUpdate { trie_key, value: None });
+        let key = trie_key.to_vec();
+        self.trie_lookups.get_mut().remove(&key);
+        self.prospective.insert(key, TrieKeyValueUpdate { trie_key, value: None });
     }
 
     pub fn commit(&mut self, event: StateChangeCause) {
@@ -149,6 +192,15 @@ impl TrieUpdate {
         TrieUpdateIterator::new(self, key_prefix)
     }
 
+    /// Like [`Self::iter`], but skips the keys smaller than `start`.
+    pub fn iter_from(
+        &self,
+        key_prefix: &[u8],
+        start: &[u8],
+    ) -> Result<TrieUpdateIterator<'_>, StorageError> {
+        TrieUpdateIterator::new_from(self, key_prefix, start)
+    }
+
     pub fn get_root(&self) -> &StateRoot {
         self.trie.get_root()
     }
@@ -157,6 +209,7 @@ impl TrieUpdate {
         if let Some(storage) = self.trie.storage.as_caching_storage() {
             storage.set_mode(state);
         }
+        self.trie_lookups.borrow_mut().clear();
     }
 }
 
@@ -206,6 +259,47 @@ mod tests {
         );
     }
 
+    /// Repeated lookups in `CachingChunk` mode are served from memory but
+    /// count the same trie nodes as walking the trie again.
+    #[test]
+    fn trie_repeated_lookups() {
+        let tries = create_tries();
+        let shard_uid = ShardUId::single_shard();
+        let mut trie_update = tries.new_trie_update(shard_uid, Trie::EMPTY_ROOT);
+        for key in [b"dog".to_vec(), b"dog2".to_vec(), b"xxx".to_vec()] {
+            trie_update.set(test_key(key), b"puppy".to_vec());
+        }
+        trie_update
+            .commit(StateChangeCause::TransactionProcessing { tx_hash: CryptoHash::default() });
+        let trie_changes = trie_update.finalize().unwrap().0;
+        let mut store_update = tries.store_update();
+        let root = tries.apply_all(&trie_changes, shard_uid, &mut store_update);
+        store_update.commit().unwrap();
+        let key = test_key(b"dog".to_vec());
+
+        let trie = tries.get_trie_for_shard(shard_uid, root);
+        trie.storage.as_caching_storage().unwrap().set_mode(TrieCacheMode::CachingChunk);
+        let want_value_ref = trie.get_ref(&key.to_vec(), KeyLookupMode::Trie).unwrap();
+        trie.get_ref(&key.to_vec(), KeyLookupMode::Trie).unwrap();
+        let want_nodes_count = trie.get_trie_nodes_count();
+
+        let mut trie_update = tries.new_trie_update(shard_uid, root);
+        trie_update.set_trie_cache_mode(TrieCacheMode::CachingChunk);
+        for _ in 0..2 {
+            let value_ref = trie_update.trie_get_ref(&key.to_vec(), KeyLookupMode::Trie).unwrap();
+            assert_eq!(value_ref, want_value_ref);
+        }
+        assert_eq!(trie_update.trie().get_trie_nodes_count(), want_nodes_count);
+        assert_eq!(trie_update.trie_lookups.borrow().len(), 1);
+
+        // Writes and changing the cache mode forget remembered lookups.
+        trie_update.set(key.clone(), b"kitten".to_vec());
+        assert!(trie_update.trie_lookups.borrow().is_empty());
+        trie_update.trie_get_ref(&key.to_vec(), KeyLookupMode::Trie).unwrap();
+        trie_update.set_trie_cache_mode(TrieCacheMode::CachingShard);
+        assert!(trie_update.trie_lookups.borrow().is_empty());
+    }
+
     #[test]
     fn trie_remove() {
         let tries = create_tries_complex(SHARD_VERSION, 2);
@@ -317,4 +411,45 @@ mod tests {
             ]
         );
     }
+
+    #[test]
+    fn trie_iter_from() {
+        let tries = create_tries();
+        let mut trie_update = tries.new_trie_update(ShardUId::single_shard(), Trie::EMPTY_ROOT);
+        for key in [&b"dog"[..], b"dog1", b"dog3", b"doh"] {
+            trie_update.set(test_key(key.to_vec()), b"puppy".to_vec());
+        }
+        trie_update
+            .commit(StateChangeCause::TransactionProcessing { tx_hash: CryptoHash::default() });
+        let trie_changes = trie_update.finalize().unwrap().0;
+        let mut store_update = tries.store_update();
+        let new_root = tries.apply_all(&trie_changes, ShardUId::single_shard(), &mut store_update);
+        store_update.commit().unwrap();
+
+        let mut trie_update = tries.new_trie_update(ShardUId::single_shard(), new_root);
+        trie_update.set(test_key(b"dog2".to_vec()), b"puppy".to_vec());
+        trie_update.remove(test_key(b"dog3".to_vec()));
+        trie_update.set(test_key(b"dog4".to_vec()), b"puppy".to_vec());
+
+        let prefix = test_key(b"dog".to_vec()).to_vec();
+        let iter_from = |start: &[u8]| -> Vec<Vec<u8>> {
+            let start = test_key(start.to_vec()).to_vec();
+            trie_update.iter_from(&prefix, &start).unwrap().map(Result::unwrap).collect()
+        };
+        let keys = |keys: &[&[u8]]| -> Vec<Vec<u8>> {
+            keys.iter().map(|key| test_key(key.to_vec()).to_vec()).collect()
+        };
+
+        assert_eq!(iter_from(b"dog"), keys(&[b"dog", b"dog1", b"dog2", b"dog4"]));
+        assert_eq!(iter_from(b"do"), keys(&[b"dog", b"dog1", b"dog2", b"dog4"]));
+        assert_eq!(iter_from(b"dog1"), keys(&[b"dog1", b"dog2", b"dog4"]));
+        assert_eq!(iter_from(b"dog1\0"), keys(&[b"dog2", b"dog4"]));
+        assert_eq!(iter_from(b"dog3"), keys(&[b"dog4"]));
+        assert_eq!(iter_from(b"dog5"), keys(&[]));
+        assert_eq!(iter_from(b"doh"), keys(&[]));
+        // Start past the end of the prefix range, with changes in both the
+        // committed and the prospective overlay.
+        assert_eq!(iter_from(b"e"), keys(&[]));
+        assert_eq!(iter_from(b"dp"), keys(&[]));
+    }
 }
@@ -40,16 +40,40 @@ pub struct TrieUpdateIterator<'a>(Option<(Peekable<TrieIterator<'a>>, Peekable<M
 impl<'a> TrieUpdateIterator<'a> {
     #![allow(clippy::new_ret_no_self)]
     pub fn new(state_update: &'a TrieUpdate, prefix: &[u8]) -> Result<Self, StorageError> {
-        let mut trie_iter = state_update.trie.iter()?;
-        trie_iter.seek_prefix(prefix)?;
+        Self::new_from(state_update, prefix, prefix)
+    }
 
+    /// Iterates over the keys starting with `prefix` which are not smaller
+    /// than `start`.
+    pub fn new_from(
+        state_update: &'a TrieUpdate,
+        prefix: &[u8],
+        start: &[u8],
+    ) -> Result<Self, StorageError> {
+        let start = std::cmp::max(prefix, start);
         let end_bound = make_prefix_range_end_bound(prefix);
+        if end_bound.as_deref().map_or(false, |end_bound| start >= end_bound) {
+            // `start` is past all keys with the prefix.  This must not reach
+            // `BTreeMap::range` below, which panics on an inverted range.
+            return Ok(TrieUpdateIterator(None));
+        }
+
+        let mut trie_iter = state_update.trie.iter()?;
+        if start == prefix {
+            trie_iter.seek_prefix(prefix)?;
+        } else {
+            trie_iter.seek(start)?;
+            if let Some(end_bound) = &end_bound {
+                trie_iter.set_end(end_bound);
+            }
+        }
+
         let end_bound = if let Some(end_bound) = &end_bound {
             Bound::Excluded(end_bound.as_slice())
         } else {
             Bound::Unbounded
         };
-        let range = (Bound::Included(prefix), end_bound);
+        let range = (Bound::Included(start), end_bound);
 
         let committed_iter = state_update.committed.range::<[u8], _>(range).map(
             |(raw_key, changes_with_trie_key)| {
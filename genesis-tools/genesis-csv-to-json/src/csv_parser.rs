@@ -209,6 +209,7 @@ fn account_records(row: &Row, gas_price: Balance) -> Vec<StateRecord> {
                         allowance: None,
                         receiver_id: row.account_id.to_string(),
                         method_names: method_names.iter().map(|x| (*x).to_string()).collect(),
+                        method_allowances: Default::default(),
                     }),
                 },
             })
@@ -267,6 +268,7 @@ fn account_records(row: &Row, gas_price: Balance) -> Vec<StateRecord> {
                     gas: INIT_GAS,
                     deposit: 0,
                 })],
+                priority: 0,
             }),
         };
         res.push(StateRecord::PostponedReceipt(Box::new(receipt)));
@@ -7,7 +7,7 @@ use near_primitives::account::{AccessKey, AccessKeyPermission, FunctionCallPermi
 use near_primitives::errors::{ActionsValidationError, InvalidTxError};
 use near_primitives::hash::CryptoHash;
 use near_primitives::runtime::config_store::RuntimeConfigStore;
-use near_primitives::transaction::{Action, AddKeyAction, Transaction};
+use near_primitives::transaction::{Action, AddKeyAction, Transaction, TransactionV0};
 use near_store::test_utils::create_test_store;
 use nearcore::config::GenesisExt;
 use nearcore::TrackedConfig;
@@ -44,7 +44,7 @@ fn test_account_id_in_function_call_permission_upgrade() {
     };
 
     let signer = InMemorySigner::from_seed("test0".parse().unwrap(), KeyType::ED25519, "test0");
-    let tx = Transaction {
+    let tx = TransactionV0 {
         signer_id: "test0".parse().unwrap(),
         receiver_id: "test0".parse().unwrap(),
         public_key: signer.public_key(),
@@ -56,6 +56,7 @@ fn test_account_id_in_function_call_permission_upgrade() {
                     allowance: None,
                     receiver_id: "#".to_string(),
                     method_names: vec![],
+                    method_allowances: Default::default(),
                 }),
             },
         })],
@@ -66,8 +67,12 @@ fn test_account_id_in_function_call_permission_upgrade() {
     // Run the transaction, it should pass as we don't do validation at this protocol version.
     {
         let tip = env.clients[0].chain.head().unwrap();
-        let signed_transaction =
-            Transaction { nonce: 10, block_hash: tip.last_block_hash, ..tx.clone() }.sign(&signer);
+        let signed_transaction = Transaction::V0(TransactionV0 {
+            nonce: 10,
+            block_hash: tip.last_block_hash,
+            ..tx.clone()
+        })
+        .sign(&signer);
         let res = env.clients[0].process_tx(signed_transaction, false, false);
         assert_eq!(res, ProcessTxResponse::ValidTx);
         for i in 0..3 {
@@ -81,7 +86,8 @@ fn test_account_id_in_function_call_permission_upgrade() {
     {
         let tip = env.clients[0].chain.head().unwrap();
         let signed_transaction =
-            Transaction { nonce: 11, block_hash: tip.last_block_hash, ..tx }.sign(&signer);
+            Transaction::V0(TransactionV0 { nonce: 11, block_hash: tip.last_block_hash, ..tx })
+                .sign(&signer);
         let res = env.clients[0].process_tx(signed_transaction, false, false);
         assert_eq!(
             res,
@@ -112,7 +118,7 @@ fn test_very_long_account_id() {
 
     let tip = env.clients[0].chain.head().unwrap();
     let signer = InMemorySigner::from_seed("test0".parse().unwrap(), KeyType::ED25519, "test0");
-    let tx = Transaction {
+    let tx = Transaction::V0(TransactionV0 {
         signer_id: "test0".parse().unwrap(),
         receiver_id: "test0".parse().unwrap(),
         public_key: signer.public_key(),
@@ -124,12 +130,13 @@ fn test_very_long_account_id() {
                     allowance: None,
                     receiver_id: "A".repeat(1024),
                     method_names: vec![],
+                    method_allowances: Default::default(),
                 }),
             },
         })],
         nonce: 0,
         block_hash: tip.last_block_hash,
-    }
+    })
     .sign(&signer);
 
     let res = env.clients[0].process_tx(tx, false, false);
@@ -133,7 +133,12 @@ fn compare_node_counts() {
             let metadata = receipt_execution_outcome.outcome_with_id.outcome.metadata;
             match metadata {
                 ExecutionMetadata::V1 => panic!("ExecutionMetadata cannot be empty"),
-                ExecutionMetadata::V2(profile_data) => TrieNodesCount {
+                ExecutionMetadata::V2(profile_data)
+                | ExecutionMetadata::V3 { profile: profile_data, .. }
+                | ExecutionMetadata::V4 { profile: profile_data, .. }
+                | ExecutionMetadata::V5 { profile: profile_data, .. }
+                | ExecutionMetadata::V6 { profile: profile_data, .. }
+                | ExecutionMetadata::V7 { profile: profile_data, .. } => TrieNodesCount {
                     db_reads: {
                         let cost = profile_data.get_ext_cost(ExtCosts::touching_trie_node);
                         assert_eq!(cost % touching_trie_node_cost, 0);
@@ -4,7 +4,7 @@ use near_chain_configs::Genesis;
 use near_client::test_utils::TestEnv;
 use near_crypto::{InMemorySigner, KeyType, Signer};
 use near_primitives::hash::CryptoHash;
-use near_primitives::transaction::{Action, FunctionCallAction, Transaction};
+use near_primitives::transaction::{Action, FunctionCallAction, Transaction, TransactionV0};
 use nearcore::config::GenesisExt;
 
 #[test]
@@ -38,7 +38,7 @@ fn test_wasmer2_upgrade() {
     };
 
     let signer = InMemorySigner::from_seed("test0".parse().unwrap(), KeyType::ED25519, "test0");
-    let tx = Transaction {
+    let tx = TransactionV0 {
         signer_id: "test0".parse().unwrap(),
         receiver_id: "test0".parse().unwrap(),
         public_key: signer.public_key(),
@@ -56,8 +56,12 @@ fn test_wasmer2_upgrade() {
     // Run the transaction & collect the logs.
     let logs_at_old_version = {
         let tip = env.clients[0].chain.head().unwrap();
-        let signed_transaction =
-            Transaction { nonce: 10, block_hash: tip.last_block_hash, ..tx.clone() }.sign(&signer);
+        let signed_transaction = Transaction::V0(TransactionV0 {
+            nonce: 10,
+            block_hash: tip.last_block_hash,
+            ..tx.clone()
+        })
+        .sign(&signer);
         env.clients[0].process_tx(signed_transaction, false, false);
         for i in 0..3 {
             env.produce_block(0, tip.height + i + 1);
@@ -71,7 +75,8 @@ fn test_wasmer2_upgrade() {
     let logs_at_new_version = {
         let tip = env.clients[0].chain.head().unwrap();
         let signed_transaction =
-            Transaction { nonce: 11, block_hash: tip.last_block_hash, ..tx }.sign(&signer);
+            Transaction::V0(TransactionV0 { nonce: 11, block_hash: tip.last_block_hash, ..tx })
+                .sign(&signer);
         env.clients[0].process_tx(signed_transaction, false, false);
         for i in 0..3 {
             env.produce_block(0, tip.height + i + 1);
@@ -57,7 +57,7 @@ use near_primitives::state_part::PartId;
 use near_primitives::syncing::{get_num_state_parts, ShardStateSyncResponseHeader, StatePartKey};
 use near_primitives::transaction::{
     Action, DeployContractAction, ExecutionStatus, FunctionCallAction, SignedTransaction,
-    Transaction,
+    Transaction, TransactionV0,
 };
 use near_primitives::trie_key::TrieKey;
 use near_primitives::types::validator_stake::ValidatorStake;
@@ -1069,14 +1069,14 @@ fn test_process_invalid_tx() {
     let signer = InMemorySigner::from_seed("test1".parse().unwrap(), KeyType::ED25519, "test0");
     let tx = SignedTransaction::new(
         Signature::empty(KeyType::ED25519),
-        Transaction {
+        Transaction::V0(TransactionV0 {
             signer_id: "test".parse().unwrap(),
             public_key: signer.public_key(),
             nonce: 0,
             receiver_id: "test".parse().unwrap(),
             block_hash: *env.clients[0].chain.genesis().hash(),
             actions: vec![],
-        },
+        }),
     );
     for i in 1..12 {
         env.produce_block(0, i);
@@ -1087,14 +1087,14 @@ fn test_process_invalid_tx() {
     );
     let tx2 = SignedTransaction::new(
         Signature::empty(KeyType::ED25519),
-        Transaction {
+        Transaction::V0(TransactionV0 {
             signer_id: "test".parse().unwrap(),
             public_key: signer.public_key(),
             nonce: 0,
             receiver_id: "test".parse().unwrap(),
             block_hash: hash(&[1]),
             actions: vec![],
-        },
+        }),
     );
     assert_eq!(
         env.clients[0].process_tx(tx2, false, false),
@@ -1102,6 +1102,47 @@ fn test_process_invalid_tx() {
     );
 }
 
+/// Transactions saved on shutdown are restored into the pool on startup if
+/// they are still valid at the new head.
+#[test]
+fn test_tx_pool_persistence() {
+    init_test_logger();
+    let mut genesis = Genesis::test(vec!["test0".parse().unwrap()], 1);
+    genesis.config.transaction_validity_period = 10;
+    let chain_genesis = ChainGenesis::new(&genesis);
+    let mut env = TestEnv::builder(chain_genesis)
+        .runtime_adapters(create_nightshade_runtimes(&genesis, 1))
+        .build();
+    let signer = InMemorySigner::from_seed("test0".parse().unwrap(), KeyType::ED25519, "test0");
+    let tx = SignedTransaction::send_money(
+        1,
+        "test0".parse().unwrap(),
+        "test0".parse().unwrap(),
+        &signer,
+        100,
+        *env.clients[0].chain.genesis().hash(),
+    );
+    env.produce_block(0, 1);
+    assert_eq!(env.clients[0].process_tx(tx.clone(), false, false), ProcessTxResponse::ValidTx);
+
+    // Simulate a restart by emptying the pool after saving it.
+    assert_eq!(env.clients[0].save_tx_pool().unwrap(), 1);
+    env.clients[0].sharded_tx_pool.remove_transactions(0, &[tx.clone()]);
+    assert_eq!(env.clients[0].restore_tx_pool().unwrap(), 1);
+    let restored: Vec<_> = env.clients[0].sharded_tx_pool.transactions_by_shard().collect();
+    assert_eq!(restored, vec![(0, vec![tx.clone()])]);
+    // Restored transactions are removed from the database.
+    assert_eq!(env.clients[0].restore_tx_pool().unwrap(), 0);
+
+    // Expired transactions are not restored.
+    assert_eq!(env.clients[0].save_tx_pool().unwrap(), 1);
+    env.clients[0].sharded_tx_pool.remove_transactions(0, &[tx]);
+    for i in 2..12 {
+        env.produce_block(0, i);
+    }
+    assert_eq!(env.clients[0].restore_tx_pool().unwrap(), 0);
+}
+
 /// If someone produce a block with Utc::now() + 1 min, we should produce a block with valid timestamp
 #[test]
 fn test_time_attack() {
@@ -295,7 +295,7 @@ impl TestShardUpgradeEnv {
         let mut successful_txs = Vec::new();
         for tx in txs_to_check {
             let id = &tx.get_hash();
-            let account_id = &tx.transaction.signer_id;
+            let account_id = tx.transaction.signer_id();
             let shard_uid = account_id_to_shard_uid(account_id, &shard_layout);
             for (i, account_id) in env.validators.iter().enumerate() {
                 let cares_about_shard = env.clients[i].runtime_adapter.cares_about_shard(
@@ -80,13 +80,17 @@ fn outcome_view_to_hashes(outcome: &ExecutionOutcomeView) -> Vec<CryptoHash> {
         ExecutionStatusView::Failure(_) => PartialExecutionStatus::Failure,
         ExecutionStatusView::SuccessReceiptId(id) => PartialExecutionStatus::SuccessReceiptId(*id),
     };
-    let mut result = vec![CryptoHash::hash_borsh((
+    let partial_outcome = (
         outcome.receipt_ids.clone(),
         outcome.gas_burnt,
         outcome.tokens_burnt,
         outcome.executor_id.clone(),
         status,
-    ))];
+    );
+    let mut result = vec![match outcome.metadata.hash {
+        Some(metadata_hash) => CryptoHash::hash_borsh((partial_outcome, metadata_hash)),
+        None => CryptoHash::hash_borsh(partial_outcome),
+    }];
     for log in outcome.logs.iter() {
         result.push(hash(log.as_bytes()));
     }
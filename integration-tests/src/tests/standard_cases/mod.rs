@@ -29,6 +29,7 @@ use testlib::fees_utils::FeeHelper;
 use testlib::runtime_utils::{
     alice_account, bob_account, eve_dot_alice_account, x_dot_y_dot_alice_account,
 };
+use testlib::workload::{Workload, WorkloadConfig};
 
 /// The amount to send with function call.
 const FUNCTION_CALL_AMOUNT: Balance = TESTING_INIT_BALANCE / 10;
@@ -326,6 +327,25 @@ pub fn test_send_money(node: impl Node) {
     );
 }
 
+/// Runs a generated workload and checks that all transactions succeed and
+/// that the same seed produces the same transactions.
+pub fn test_workload(node: impl Node) {
+    let node_user = node.user();
+    let block_hash = node_user.get_best_block_hash().unwrap_or_default();
+    let config =
+        WorkloadConfig::new(vec![alice_account(), bob_account(), "carol.near".parse().unwrap()]);
+    let transactions = Workload::new(config.clone(), 0).transactions(50, block_hash);
+    let other_transactions = Workload::new(config, 0).transactions(50, block_hash);
+    assert_eq!(
+        transactions.iter().map(|tx| tx.get_hash()).collect::<Vec<_>>(),
+        other_transactions.iter().map(|tx| tx.get_hash()).collect::<Vec<_>>()
+    );
+    for transaction in transactions {
+        let outcome = node_user.commit_transaction(transaction).unwrap();
+        assert_matches!(outcome.status, FinalExecutionStatus::SuccessValue(_));
+    }
+}
+
 pub fn transfer_tokens_implicit_account(node: impl Node) {
     let account_id = &node.account_id().unwrap();
     let node_user = node.user();
@@ -863,6 +883,7 @@ pub fn test_add_access_key_function_call(node: impl Node) {
             allowance: None,
             receiver_id: account_id.to_string(),
             method_names: vec![],
+            method_allowances: Default::default(),
         }),
     };
     let signer2 = InMemorySigner::from_random("test".parse().unwrap(), KeyType::ED25519);
@@ -883,6 +904,7 @@ pub fn test_delete_access_key(node: impl Node) {
             allowance: None,
             receiver_id: account_id.to_string(),
             method_names: vec![],
+            method_allowances: Default::default(),
         }),
     };
     let signer2 = InMemorySigner::from_random("test".parse().unwrap(), KeyType::ED25519);
@@ -911,6 +933,7 @@ pub fn test_add_access_key_with_allowance(node: impl Node) {
             allowance: Some(10),
             receiver_id: account_id.to_string(),
             method_names: vec![],
+            method_allowances: Default::default(),
         }),
     };
     let node_user = node.user();
@@ -937,6 +960,7 @@ pub fn test_delete_access_key_with_allowance(node: impl Node) {
             allowance: Some(10),
             receiver_id: account_id.to_string(),
             method_names: vec![],
+            method_allowances: Default::default(),
         }),
     };
     let node_user = node.user();
@@ -973,6 +997,7 @@ pub fn test_access_key_smart_contract(node: impl Node) {
             allowance: Some(FUNCTION_CALL_AMOUNT),
             receiver_id: bob_account().into(),
             method_names: vec![],
+            method_allowances: Default::default(),
         }),
     };
     let mut node_user = node.user();
@@ -1012,6 +1037,7 @@ pub fn test_access_key_smart_contract(node: impl Node) {
                 allowance: Some(FUNCTION_CALL_AMOUNT - function_call_cost + gas_refund),
                 receiver_id: bob_account().into(),
                 method_names: vec![],
+                method_allowances: Default::default(),
             }),
         }
         .into()
@@ -1025,6 +1051,7 @@ pub fn test_access_key_smart_contract_reject_method_name(node: impl Node) {
             allowance: Some(FUNCTION_CALL_AMOUNT),
             receiver_id: bob_account().into(),
             method_names: vec!["log_something".to_string()],
+            method_allowances: Default::default(),
         }),
     };
     let mut node_user = node.user();
@@ -1053,6 +1080,7 @@ pub fn test_access_key_smart_contract_reject_contract_id(node: impl Node) {
             allowance: Some(FUNCTION_CALL_AMOUNT),
             receiver_id: bob_account().into(),
             method_names: vec![],
+            method_allowances: Default::default(),
         }),
     };
     let mut node_user = node.user();
@@ -1090,6 +1118,7 @@ pub fn test_access_key_reject_non_function_call(node: impl Node) {
             allowance: Some(FUNCTION_CALL_AMOUNT),
             receiver_id: account_id.to_string(),
             method_names: vec![],
+            method_allowances: Default::default(),
         }),
     };
     let mut node_user = node.user();
@@ -1411,6 +1440,7 @@ fn make_receipt(node: &impl Node, actions: Vec<Action>, receiver_id: AccountId)
         output_data_receivers: vec![],
         input_data_ids: vec![],
         actions,
+        priority: 0,
     });
     Receipt {
         predecessor_id: alice_account(),
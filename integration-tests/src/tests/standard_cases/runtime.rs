@@ -113,6 +113,12 @@ fn test_send_money_runtime() {
     test_send_money(node);
 }
 
+#[test]
+fn test_workload_runtime() {
+    let node = create_runtime_node();
+    test_workload(node);
+}
+
 #[test]
 fn test_transfer_tokens_implicit_account_runtime() {
     let node = create_runtime_node();
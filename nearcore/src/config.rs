@@ -300,6 +300,9 @@ pub struct Config {
     #[cfg(feature = "rosetta_rpc")]
     #[serde(skip_serializing_if = "Option::is_none")]
     pub rosetta_rpc: Option<RosettaRpcConfig>,
+    #[cfg(feature = "eth_rpc")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub eth_rpc: Option<near_jsonrpc::EthRpcConfig>,
     pub telemetry: TelemetryConfig,
     pub network: near_network::config_json::Config,
     pub consensus: Consensus,
@@ -321,6 +324,15 @@ pub struct Config {
     /// If set, overrides value in genesis configuration.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub max_gas_burnt_view: Option<Gas>,
+    /// Number of chunk apply results kept in memory, so that a chunk applied
+    /// again on top of the same state is not re-executed. Zero disables the
+    /// cache.
+    #[serde(default)]
+    pub apply_chunk_cache_size: usize,
+    /// Save the transaction pool to the database on shutdown and restore the
+    /// transactions which are still valid against the new head on startup.
+    #[serde(default)]
+    pub persist_tx_pool: bool,
     /// Different parameters to configure underlying storage.
     pub store: near_store::StoreConfig,
     /// Different parameters to configure underlying cold storage.
@@ -356,6 +368,8 @@ impl Default for Config {
             rpc: Some(RpcConfig::default()),
             #[cfg(feature = "rosetta_rpc")]
             rosetta_rpc: None,
+            #[cfg(feature = "eth_rpc")]
+            eth_rpc: None,
             telemetry: TelemetryConfig::default(),
             network: Default::default(),
             consensus: Consensus::default(),
@@ -369,6 +383,8 @@ impl Default for Config {
             view_client_throttle_period: default_view_client_throttle_period(),
             trie_viewer_state_size_limit: default_trie_viewer_state_size_limit(),
             max_gas_burnt_view: None,
+            apply_chunk_cache_size: 0,
+            persist_tx_pool: false,
             db_migration_snapshot_path: None,
             use_db_migration_snapshot: None,
             store: near_store::StoreConfig::default(),
@@ -546,6 +562,8 @@ pub struct NearConfig {
     pub rpc_config: Option<RpcConfig>,
     #[cfg(feature = "rosetta_rpc")]
     pub rosetta_rpc_config: Option<RosettaRpcConfig>,
+    #[cfg(feature = "eth_rpc")]
+    pub eth_rpc_config: Option<near_jsonrpc::EthRpcConfig>,
     pub telemetry_config: TelemetryConfig,
     pub genesis: Genesis,
     pub validator_signer: Option<Arc<dyn ValidatorSigner>>,
@@ -604,6 +622,8 @@ impl NearConfig {
                 view_client_throttle_period: config.view_client_throttle_period,
                 trie_viewer_state_size_limit: config.trie_viewer_state_size_limit,
                 max_gas_burnt_view: config.max_gas_burnt_view,
+                apply_chunk_cache_size: config.apply_chunk_cache_size,
+                persist_tx_pool: config.persist_tx_pool,
                 enable_statistics_export: config.store.enable_statistics_export,
                 client_background_migration_threads: config.store.background_migration_threads,
             },
@@ -618,6 +638,8 @@ impl NearConfig {
             rpc_config: config.rpc,
             #[cfg(feature = "rosetta_rpc")]
             rosetta_rpc_config: config.rosetta_rpc,
+            #[cfg(feature = "eth_rpc")]
+            eth_rpc_config: config.eth_rpc,
             genesis,
             validator_signer,
         })
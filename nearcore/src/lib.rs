@@ -5,7 +5,7 @@ use actix::{Actor, Addr};
 use actix_rt::ArbiterHandle;
 use actix_web;
 use anyhow::Context;
-use near_chain::{Chain, ChainGenesis};
+use near_chain::{Chain, ChainGenesis, RuntimeAdapter};
 use near_client::{start_client, start_view_client, ClientActor, ViewClientActor};
 use near_network::time;
 use near_network::types::NetworkRecipient;
@@ -18,7 +18,7 @@ use near_telemetry::TelemetryActor;
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use tokio::sync::oneshot;
-use tracing::{info, trace};
+use tracing::{info, trace, warn};
 
 pub mod append_only_map;
 pub mod config;
@@ -154,6 +154,19 @@ pub struct NearNode {
     pub rpc_servers: Vec<(&'static str, actix_web::dev::ServerHandle)>,
 }
 
+/// Loads the trie nodes used most recently before the node was stopped into
+/// the shard caches, so that the first chunks after a restart are applied at
+/// the usual speed.
+fn warm_up_trie_caches(runtime: &NightshadeRuntime) {
+    let start = std::time::Instant::now();
+    match runtime.get_tries().warm_up_caches() {
+        Ok(num_nodes) => {
+            info!(target: "neard", num_nodes, elapsed = ?start.elapsed(), "Warmed up trie caches")
+        }
+        Err(err) => warn!(target: "neard", ?err, "Failed to warm up trie caches"),
+    }
+}
+
 pub fn start_with_config(home_dir: &Path, config: NearConfig) -> anyhow::Result<NearNode> {
     start_with_config_and_synchronization(home_dir, config, None)
 }
@@ -167,11 +180,17 @@ pub fn start_with_config_and_synchronization(
 ) -> anyhow::Result<NearNode> {
     let store = open_storage(home_dir, &mut config)?;
 
-    let runtime = Arc::new(NightshadeRuntime::from_config(
+    #[cfg(feature = "cold_store")]
+    let cold_store = store.has_cold().then(|| store.get_store(Temperature::Cold));
+    #[cfg(not(feature = "cold_store"))]
+    let cold_store = None;
+    let runtime = Arc::new(NightshadeRuntime::from_config_with_cold_store(
         home_dir,
         store.get_store(Temperature::Hot),
+        cold_store,
         &config,
     ));
+    warm_up_trie_caches(&runtime);
 
     let telemetry = TelemetryActor::new(config.telemetry_config.clone()).start();
     let chain_genesis = ChainGenesis::new(&config.genesis);
@@ -242,6 +261,14 @@ pub fn start_with_config_and_synchronization(
         ));
     }
 
+    #[cfg(feature = "eth_rpc")]
+    if let Some(eth_rpc_config) = config.eth_rpc_config {
+        rpc_servers.push((
+            "Ethereum JSON RPC",
+            near_jsonrpc::start_eth_rpc(eth_rpc_config, view_client.clone()),
+        ));
+    }
+
     rpc_servers.shrink_to_fit();
 
     trace!(target: "diagnostic", key="log", "Starting NEAR node with diagnostic activated");
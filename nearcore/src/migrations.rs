@@ -163,6 +163,34 @@ impl<'a> near_store::StoreMigrator for Migrator<'a> {
                 tracing::info!(target: "migrations", "It will happen in parallel with regular block processing. ETA is 5h for RPC node and 10h for archival node.");
                 Ok(())
             }
+            // version 34 => 35 (35 => 36 with flat state): add
+            // DBCol::StateShardUIdMapping
+            //
+            // Like the migration from 27 to 28, this only adds a column which
+            // gets created on open.  Existing shards have no entry and keep
+            // using their own ShardUId prefix.
+            #[cfg(not(feature = "protocol_feature_flat_state"))]
+            34 => Ok(()),
+            #[cfg(feature = "protocol_feature_flat_state")]
+            35 => Ok(()),
+            // version 35 => 36 (36 => 37 with flat state): add
+            // DBCol::TrieCacheWarmup
+            //
+            // The column gets created on open.  Until it is first written, the
+            // shard caches start cold as before.
+            #[cfg(not(feature = "protocol_feature_flat_state"))]
+            35 => Ok(()),
+            #[cfg(feature = "protocol_feature_flat_state")]
+            36 => Ok(()),
+            // version 36 => 37 (37 => 38 with flat state): add
+            // DBCol::TransactionPool
+            //
+            // The column gets created on open and is only written on shutdown
+            // if the transaction pool is persisted.
+            #[cfg(not(feature = "protocol_feature_flat_state"))]
+            36 => Ok(()),
+            #[cfg(feature = "protocol_feature_flat_state")]
+            37 => Ok(()),
             DB_VERSION.. => unreachable!(),
         }
     }
@@ -23,7 +23,7 @@ use near_primitives::epoch_manager::block_info::BlockInfo;
 use near_primitives::epoch_manager::EpochConfig;
 use near_primitives::errors::{InvalidTxError, RuntimeError, StorageError};
 use near_primitives::hash::{hash, CryptoHash};
-use near_primitives::receipt::Receipt;
+use near_primitives::receipt::{DelayedReceiptQueue, Receipt};
 use near_primitives::runtime::config_store::RuntimeConfigStore;
 use near_primitives::runtime::migration_data::{MigrationData, MigrationFlags};
 use near_primitives::sandbox::state_patch::SandboxStatePatch;
@@ -36,9 +36,8 @@ use near_primitives::syncing::{get_num_state_parts, STATE_PART_MEMORY_LIMIT};
 use near_primitives::transaction::SignedTransaction;
 use near_primitives::types::validator_stake::ValidatorStakeIter;
 use near_primitives::types::{
-    AccountId, Balance, BlockHeight, CompiledContractCache, EpochHeight, EpochId,
-    EpochInfoProvider, Gas, MerkleHash, NumShards, ShardId, StateChangeCause,
-    StateChangesForSplitStates, StateRoot, StateRootNode,
+    AccountId, Balance, BlockHeight, EpochHeight, EpochId, EpochInfoProvider, Gas, MerkleHash,
+    NumShards, ShardId, StateChangeCause, StateChangesForSplitStates, StateRoot, StateRootNode,
 };
 use near_primitives::version::ProtocolVersion;
 use near_primitives::views::{
@@ -55,7 +54,7 @@ use near_store::{
     ApplyStatePartResult, DBCol, PartialStorage, ShardTries, Store, StoreCompiledContractCache,
     StoreUpdate, Trie, TrieConfig, WrappedTrieChanges,
 };
-use near_vm_runner::precompile_contract;
+use near_vm_runner::precompile_contracts;
 use node_runtime::adapter::ViewRuntimeAdapter;
 use node_runtime::config::RuntimeConfig;
 use node_runtime::state_viewer::TrieViewer;
@@ -95,9 +94,21 @@ pub struct NightshadeRuntime {
 
 impl NightshadeRuntime {
     pub fn from_config(home_dir: &Path, store: Store, config: &NearConfig) -> Self {
+        Self::from_config_with_cold_store(home_dir, store, None, config)
+    }
+
+    /// Like `from_config`, for archival nodes with split storage.  Trie nodes
+    /// missing in the hot `store` are read from `cold_store`.
+    pub fn from_config_with_cold_store(
+        home_dir: &Path,
+        store: Store,
+        cold_store: Option<Store>,
+        config: &NearConfig,
+    ) -> Self {
         Self::new(
             home_dir,
             store,
+            cold_store,
             &config.genesis,
             TrackedConfig::from_config(&config.client_config),
             config.client_config.trie_viewer_state_size_limit,
@@ -111,6 +122,7 @@ impl NightshadeRuntime {
     fn new(
         home_dir: &Path,
         store: Store,
+        cold_store: Option<Store>,
         genesis: &Genesis,
         tracked_config: TrackedConfig,
         trie_viewer_state_size_limit: Option<u64>,
@@ -142,6 +154,7 @@ impl NightshadeRuntime {
             trie_config,
             &genesis_config.shard_layout.get_shard_uids(),
             flat_state_factory.clone(),
+            cold_store,
         );
         let epoch_manager = EpochManager::new_from_genesis_config(store.clone(), &genesis_config)
             .expect("Failed to start Epoch Manager")
@@ -173,6 +186,7 @@ impl NightshadeRuntime {
         Self::new(
             home_dir,
             store,
+            None,
             genesis,
             tracked_config,
             None,
@@ -255,6 +269,7 @@ impl NightshadeRuntime {
             TrieConfig::default(),
             &genesis.config.shard_layout.get_shard_uids(),
             FlatStateFactory::new(store.clone()),
+            None,
         );
         let runtime = Runtime::new();
         let runtime_config_store =
@@ -581,35 +596,13 @@ impl NightshadeRuntime {
         .entered();
         let protocol_version = self.get_epoch_protocol_version(epoch_id)?;
         let runtime_config = self.runtime_config_store.get_config(protocol_version);
-        let compiled_contract_cache: Option<Box<dyn CompiledContractCache>> =
-            Some(Box::new(StoreCompiledContractCache::new(&self.store)));
-        // Execute precompile_contract in parallel but prevent it from using more than half of all
-        // threads so that node will still function normally.
-        rayon::scope(|scope| {
-            let (slot_sender, slot_receiver) = std::sync::mpsc::channel();
-            // Use up-to half of the threads for the compilation.
-            let max_threads = std::cmp::max(rayon::current_num_threads() / 2, 1);
-            for _ in 0..max_threads {
-                slot_sender.send(()).expect("both sender and receiver are owned here");
-            }
-            for code in contract_codes {
-                slot_receiver.recv().expect("could not receive a slot to compile contract");
-                let contract_cache = compiled_contract_cache.as_deref();
-                let slot_sender = slot_sender.clone();
-                scope.spawn(move |_| {
-                    precompile_contract(
-                        &code,
-                        &runtime_config.wasm_config,
-                        protocol_version,
-                        contract_cache,
-                    )
-                    .ok();
-                    // If this fails, it just means there won't be any more attempts to recv the
-                    // slots
-                    let _ = slot_sender.send(());
-                });
-            }
-        });
+        let stats = precompile_contracts(
+            contract_codes.into_iter(),
+            &StoreCompiledContractCache::new(&self.store),
+            &runtime_config.wasm_config,
+            protocol_version,
+        );
+        tracing::debug!(target: "runtime", ?stats, "precompiled contracts");
         Ok(())
     }
 }
@@ -629,19 +622,22 @@ fn apply_delayed_receipts<'a>(
 ) -> Result<HashMap<ShardUId, StateRoot>, Error> {
     let orig_trie_update = tries.new_trie_update_view(orig_shard_uid, orig_state_root);
 
-    let mut start_index = None;
     let mut new_state_roots = state_roots;
-    while let Some((next_index, receipts)) =
-        get_delayed_receipts(&orig_trie_update, start_index, STATE_PART_MEMORY_LIMIT)?
-    {
-        let (store_update, updated_state_roots) = tries.apply_delayed_receipts_to_split_states(
-            &new_state_roots,
-            &receipts,
-            account_id_to_shard_id,
-        )?;
-        new_state_roots = updated_state_roots;
-        start_index = Some(next_index);
-        store_update.commit()?;
+    for queue in DelayedReceiptQueue::all() {
+        let mut start_index = None;
+        while let Some((next_index, receipts)) =
+            get_delayed_receipts(&orig_trie_update, queue, start_index, STATE_PART_MEMORY_LIMIT)?
+        {
+            let (store_update, updated_state_roots) = tries
+                .apply_delayed_receipts_to_split_states(
+                    &new_state_roots,
+                    &receipts,
+                    account_id_to_shard_id,
+                )?;
+            new_state_roots = updated_state_roots;
+            start_index = Some(next_index);
+            store_update.commit()?;
+        }
     }
 
     Ok(new_state_roots)
@@ -755,7 +751,7 @@ impl RuntimeAdapter for NightshadeRuntime {
 
         if let Some(state_root) = state_root {
             let shard_uid =
-                self.account_id_to_shard_uid(&transaction.transaction.signer_id, epoch_id)?;
+                self.account_id_to_shard_uid(transaction.transaction.signer_id(), epoch_id)?;
             let mut state_update = self.tries.new_trie_update(shard_uid, state_root);
 
             match verify_and_charge_transaction(
@@ -1212,7 +1208,7 @@ impl RuntimeAdapter for NightshadeRuntime {
         .entered();
         let epoch_id = self.get_epoch_id(block_hash)?;
         let shard_uid = self.get_shard_uid_from_epoch_id(shard_id, &epoch_id)?;
-        let trie = self.tries.get_view_trie_for_shard(shard_uid, state_root.clone());
+        let trie = self.tries.get_view_trie_with_read_ahead(shard_uid, state_root.clone());
         let result = match trie.get_trie_nodes_for_part(part_id) {
             Ok(partial_state) => partial_state,
             Err(e) => {
@@ -1286,6 +1282,17 @@ impl RuntimeAdapter for NightshadeRuntime {
         let mut state_roots: HashMap<_, _> =
             new_shards.iter().map(|shard_uid| (*shard_uid, Trie::EMPTY_ROOT)).collect();
         let split_shard_ids: HashSet<_> = new_shards.into_iter().collect();
+        // New shards store their nodes under the prefix of the parent shard, so
+        // that values and nodes identical to the parent's are stored once
+        // instead of being duplicated under the new prefixes.  Every node of
+        // the new tries is still written, as a refcount increment of the
+        // existing entry: the deferred deletions of the parent's trie changes
+        // would otherwise remove nodes the new shards still reference.
+        let mut store_update = self.tries.store_update();
+        for new_shard_uid in &split_shard_ids {
+            self.tries.set_state_shard_uid(&mut store_update, *new_shard_uid, shard_uid);
+        }
+        store_update.commit()?;
         let checked_account_id_to_shard_id = |account_id: &AccountId| {
             let new_shard_uid = account_id_to_shard_uid(account_id, next_epoch_shard_layout);
             // check that all accounts in the shard are mapped the shards that this shard will split
@@ -1759,6 +1766,7 @@ mod test {
             let runtime = NightshadeRuntime::new(
                 dir.path(),
                 store,
+                None,
                 &genesis,
                 tracked_config,
                 None,
@@ -92,7 +92,13 @@ impl NeardCmd {
             ),
 
             NeardSubCommand::StateViewer(cmd) => {
-                let mode = if cmd.readwrite { Mode::ReadWrite } else { Mode::ReadOnly };
+                let mode = if cmd.readwrite {
+                    Mode::ReadWrite
+                } else if cmd.secondary {
+                    Mode::ReadOnlySecondary
+                } else {
+                    Mode::ReadOnly
+                };
                 cmd.subcmd.run(&home_dir, genesis_validation, mode);
             }
 
@@ -127,6 +133,11 @@ pub(super) struct StateViewerCommand {
     /// In case an operation needs to write to caches, a read-write mode may be needed.
     #[clap(long, short = 'w')]
     readwrite: bool,
+    /// Opens rocks DB as a secondary instance, which is meant to be used while
+    /// a node is running on the same database, e.g. to analyse the state of a
+    /// live validator without stopping it or copying the database first.
+    #[clap(long, conflicts_with = "readwrite")]
+    secondary: bool,
     #[clap(subcommand)]
     subcmd: StateViewerSubCommand,
 }
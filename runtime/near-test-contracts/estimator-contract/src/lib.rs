@@ -33,6 +33,10 @@ extern "C" {
     fn attached_deposit(balance_ptr: u64);
     fn prepaid_gas() -> u64;
     fn used_gas() -> u64;
+    #[cfg(feature = "protocol_feature_block_gas_info")]
+    fn block_gas_price(balance_ptr: u64);
+    #[cfg(feature = "protocol_feature_block_gas_info")]
+    fn block_gas_limit() -> u64;
     // ############
     // # Math API #
     // ############
@@ -43,6 +47,10 @@ extern "C" {
     fn sha256(value_len: u64, value_ptr: u64, register_id: u64);
     fn keccak256(value_len: u64, value_ptr: u64, register_id: u64);
     fn keccak512(value_len: u64, value_ptr: u64, register_id: u64);
+    #[cfg(feature = "protocol_feature_hash_batch")]
+    fn sha256_batch(inputs_len: u64, inputs_ptr: u64, register_id: u64);
+    #[cfg(feature = "protocol_feature_hash_batch")]
+    fn keccak256_batch(inputs_len: u64, inputs_ptr: u64, register_id: u64);
     fn ripemd160(value_len: u64, value_ptr: u64, register_id: u64);
     fn ecrecover(
         hash_len: u64,
@@ -53,6 +61,8 @@ extern "C" {
         malleability_flag: u64,
         register_id: u64,
     ) -> u64;
+    #[cfg(feature = "protocol_feature_ecrecover_batch")]
+    fn ecrecover_batch(inputs_len: u64, inputs_ptr: u64, register_id: u64) -> u64;
     #[cfg(feature = "protocol_feature_ed25519_verify")]
     fn ed25519_verify(
         sig_len: u64,
@@ -163,6 +173,15 @@ extern "C" {
     fn storage_read(key_len: u64, key_ptr: u64, register_id: u64) -> u64;
     fn storage_remove(key_len: u64, key_ptr: u64, register_id: u64) -> u64;
     fn storage_has_key(key_len: u64, key_ptr: u64) -> u64;
+    #[cfg(feature = "protocol_feature_storage_iter_prefix_page")]
+    fn storage_iter_prefix_page(
+        prefix_len: u64,
+        prefix_ptr: u64,
+        start_key_len: u64,
+        start_key_ptr: u64,
+        limit: u64,
+        register_id: u64,
+    ) -> u64;
 }
 
 // Function that does not do anything at all.
@@ -177,6 +196,26 @@ pub unsafe fn base_1M() {
     }
 }
 
+// Function to measure `block_gas_price_base`. Also measures `write_memory_base` and
+// `write_memory_byte`.
+#[no_mangle]
+#[cfg(feature = "protocol_feature_block_gas_info")]
+pub unsafe fn block_gas_price_1M() {
+    let buffer = [0u8; 16];
+    for _ in 0..1_000_000 {
+        block_gas_price(buffer.as_ptr() as u64);
+    }
+}
+
+// Function to measure `block_gas_limit_base`.
+#[no_mangle]
+#[cfg(feature = "protocol_feature_block_gas_info")]
+pub unsafe fn block_gas_limit_1M() {
+    for _ in 0..1_000_000 {
+        block_gas_limit();
+    }
+}
+
 // Function to measure `read_memory_base` and `read_memory_byte` many times.
 // Reads 10b 10k times from memory.
 #[no_mangle]
@@ -428,6 +467,60 @@ pub unsafe fn keccak512_10kib_10k() {
     }
 }
 
+/// Builds a batch of `N` inputs that all point to `buffer`.
+#[cfg(feature = "protocol_feature_hash_batch")]
+fn batch_inputs<const N: usize>(buffer: &[u8]) -> [[u64; 2]; N] {
+    [[buffer.len() as u64, buffer.as_ptr() as u64]; N]
+}
+
+// Function to measure `sha256_batch_base`. Also measures `base`, `read_memory_base`,
+// `write_register_base` and `write_register_byte`, as well as the per value overhead.
+// Compute sha256_batch on 10 values of 10b 1k times.
+#[no_mangle]
+#[cfg(feature = "protocol_feature_hash_batch")]
+pub unsafe fn sha256_batch_10x10b_1k() {
+    let buffer = [65u8; 10];
+    let inputs = batch_inputs::<10>(&buffer);
+    for _ in 0..1_000 {
+        sha256_batch(inputs.len() as u64, inputs.as_ptr() as *const u64 as u64, 0);
+    }
+}
+// Function to measure `sha256_batch_byte`.
+// Compute sha256_batch on 10 values of 10kib 1k times.
+#[no_mangle]
+#[cfg(feature = "protocol_feature_hash_batch")]
+pub unsafe fn sha256_batch_10x10kib_1k() {
+    let buffer = [65u8; 10240];
+    let inputs = batch_inputs::<10>(&buffer);
+    for _ in 0..1_000 {
+        sha256_batch(inputs.len() as u64, inputs.as_ptr() as *const u64 as u64, 0);
+    }
+}
+
+// Function to measure `keccak256_batch_base`. Also measures `base`, `read_memory_base`,
+// `write_register_base` and `write_register_byte`, as well as the per value overhead.
+// Compute keccak256_batch on 10 values of 10b 1k times.
+#[no_mangle]
+#[cfg(feature = "protocol_feature_hash_batch")]
+pub unsafe fn keccak256_batch_10x10b_1k() {
+    let buffer = [65u8; 10];
+    let inputs = batch_inputs::<10>(&buffer);
+    for _ in 0..1_000 {
+        keccak256_batch(inputs.len() as u64, inputs.as_ptr() as *const u64 as u64, 0);
+    }
+}
+// Function to measure `keccak256_batch_byte`.
+// Compute keccak256_batch on 10 values of 10kib 1k times.
+#[no_mangle]
+#[cfg(feature = "protocol_feature_hash_batch")]
+pub unsafe fn keccak256_batch_10x10kib_1k() {
+    let buffer = [65u8; 10240];
+    let inputs = batch_inputs::<10>(&buffer);
+    for _ in 0..1_000 {
+        keccak256_batch(inputs.len() as u64, inputs.as_ptr() as *const u64 as u64, 0);
+    }
+}
+
 // Function to measure `ripemd160_base` and `ripemd160_block`. Also measures `base`, `write_register_base`,
 // and `write_register_byte`. However `ripemd160` computation is more expensive than register writing
 // so we are okay overcharging it.
@@ -451,27 +544,59 @@ pub unsafe fn ripemd160_10kib_10k() {
     }
 }
 
+/// Hash and signature used to measure `ecrecover` costs.
+const ECRECOVER_HASH: [u8; 32] = [
+    0x7d, 0xba, 0xf5, 0x58, 0xb0, 0xa1, 0xa5, 0xdc, 0x7a, 0x67, 0x20, 0x21, 0x17, 0xab, 0x14, 0x3c,
+    0x1d, 0x86, 0x05, 0xa9, 0x83, 0xe4, 0xa7, 0x43, 0xbc, 0x06, 0xfc, 0xc0, 0x31, 0x62, 0xdc, 0x0d,
+];
+const ECRECOVER_SIG: [u8; 64] = [
+    0x5d, 0x99, 0xb6, 0xf7, 0xf6, 0xd1, 0xf7, 0x3d, 0x1a, 0x26, 0x49, 0x7f, 0x2b, 0x1c, 0x89, 0xb2,
+    0x4c, 0x09, 0x93, 0x91, 0x3f, 0x86, 0xe9, 0xa2, 0xd0, 0x2c, 0xd6, 0x98, 0x87, 0xd9, 0xc9, 0x4f,
+    0x3c, 0x88, 0x03, 0x58, 0x57, 0x9d, 0x81, 0x1b, 0x21, 0xdd, 0x1b, 0x7f, 0xd9, 0xbb, 0x01, 0xc1,
+    0xd8, 0x1d, 0x10, 0xe6, 0x9f, 0x03, 0x84, 0xe6, 0x75, 0xc3, 0x2b, 0x39, 0x64, 0x3b, 0xe8, 0x92,
+];
+
 // Function to measure `ecrecover_base`. Also measures `base`, `write_register_base`, and
 // `write_register_byte`. However `ecrecover` computation is more expensive than register writing
 // so we are okay overcharging it.
 // Compute ecrecover 10k times.
 #[no_mangle]
 pub unsafe fn ecrecover_10k() {
-    let hash_buffer: [u8; 32] = [
-        0x7d, 0xba, 0xf5, 0x58, 0xb0, 0xa1, 0xa5, 0xdc, 0x7a, 0x67, 0x20, 0x21, 0x17, 0xab, 0x14,
-        0x3c, 0x1d, 0x86, 0x05, 0xa9, 0x83, 0xe4, 0xa7, 0x43, 0xbc, 0x06, 0xfc, 0xc0, 0x31, 0x62,
-        0xdc, 0x0d,
-    ];
-    let sig_buffer: [u8; 64] = [
-        0x5d, 0x99, 0xb6, 0xf7, 0xf6, 0xd1, 0xf7, 0x3d, 0x1a, 0x26, 0x49, 0x7f, 0x2b, 0x1c, 0x89,
-        0xb2, 0x4c, 0x09, 0x93, 0x91, 0x3f, 0x86, 0xe9, 0xa2, 0xd0, 0x2c, 0xd6, 0x98, 0x87, 0xd9,
-        0xc9, 0x4f, 0x3c, 0x88, 0x03, 0x58, 0x57, 0x9d, 0x81, 0x1b, 0x21, 0xdd, 0x1b, 0x7f, 0xd9,
-        0xbb, 0x01, 0xc1, 0xd8, 0x1d, 0x10, 0xe6, 0x9f, 0x03, 0x84, 0xe6, 0x75, 0xc3, 0x2b, 0x39,
-        0x64, 0x3b, 0xe8, 0x92,
-    ];
+    for _ in 0..10_000 {
+        ecrecover(32, ECRECOVER_HASH.as_ptr() as _, 64, ECRECOVER_SIG.as_ptr() as _, 0, 0, 0);
+    }
+}
+
+/// Builds a batch of `N` `ecrecover_batch` records that all recover the same
+/// signature.
+#[cfg(feature = "protocol_feature_ecrecover_batch")]
+fn ecrecover_batch_records<const N: usize>() -> [[u8; 98]; N] {
+    let mut record = [0u8; 98];
+    record[0..32].copy_from_slice(&ECRECOVER_HASH);
+    record[32..96].copy_from_slice(&ECRECOVER_SIG);
+    [record; N]
+}
 
+// Function to measure `ecrecover_batch_base`. Also measures `base`, `read_memory_base` and
+// `write_register_base`.
+// Call ecrecover_batch on an empty batch 10k times.
+#[no_mangle]
+#[cfg(feature = "protocol_feature_ecrecover_batch")]
+pub unsafe fn ecrecover_batch_0x_10k() {
+    let records = ecrecover_batch_records::<0>();
     for _ in 0..10_000 {
-        ecrecover(32, hash_buffer.as_ptr() as _, 64, sig_buffer.as_ptr() as _, 0, 0, 0);
+        ecrecover_batch(0, records.as_ptr() as _, 0);
+    }
+}
+// Function to measure `ecrecover_batch_element`. Also measures `read_memory_byte` and
+// `write_register_byte`, which are negligible compared to the signature recovery.
+// Call ecrecover_batch on 10 signatures 1k times.
+#[no_mangle]
+#[cfg(feature = "protocol_feature_ecrecover_batch")]
+pub unsafe fn ecrecover_batch_10x_1k() {
+    let records = ecrecover_batch_records::<10>();
+    for _ in 0..1_000 {
+        ecrecover_batch(records.len() as u64, records.as_ptr() as _, 0);
     }
 }
 
@@ -826,6 +951,31 @@ storage_bench!(key, 10, value, 10240, 1000, storage_has_key_10b_key_10kib_value_
     storage_has_key(10, key.as_ptr() as _);
 });
 
+// Storage iteration.
+
+// Function to measure `storage_iter_create_prefix_base`.
+// Reads a page with a single key 1000 times, using the key as prefix.
+#[cfg(feature = "protocol_feature_storage_iter_prefix_page")]
+storage_bench!(key, 10, value, 10, 1000, storage_iter_prefix_page_10b_key_1k, {
+    storage_iter_prefix_page(10, key.as_ptr() as _, 0, 0, 1, 0);
+});
+
+// Function to measure `storage_iter_create_prefix_base + storage_iter_create_prefix_byte`.
+// Reads a page with a single key 1000 times, using the 10kib key as prefix.
+#[cfg(feature = "protocol_feature_storage_iter_prefix_page")]
+storage_bench!(key, 10240, value, 10, 1000, storage_iter_prefix_page_10kib_key_1k, {
+    storage_iter_prefix_page(10240, key.as_ptr() as _, 0, 0, 1, 0);
+});
+
+// Function to measure `storage_iter_next_base`, `storage_iter_next_key_byte`
+// and `storage_iter_next_value_byte`, depending on what was written before.
+// Reads all keys of the account in a single page of up to 1000 keys.
+#[no_mangle]
+#[cfg(feature = "protocol_feature_storage_iter_prefix_page")]
+pub unsafe fn storage_iter_prefix_page_1k() {
+    storage_iter_prefix_page(0, 0, 0, 0, 1000, 0);
+}
+
 // Function to measure `promise_and_base`.
 #[no_mangle]
 pub unsafe fn promise_and_100k() {
@@ -877,33 +1027,36 @@ pub unsafe fn data_producer_100kib() {
     value_return(data.len() as _, data.as_ptr() as _);
 }
 
-// Function to measure `data_receipt_creation_config`, but we are measure send and execution fee at the same time.
-// Produces 1000 10b data receipts.
-#[no_mangle]
-pub unsafe fn data_receipt_10b_1000() {
+// Creates `N` promises calling `producer` on the current account and joins
+// them with `promise_and`. With `callback`, a `noop` callback depends on the
+// joint promise, which turns the results of the `N` promises into data
+// receipts.
+unsafe fn data_receipts<const N: usize>(producer: &[u8], callback: bool) {
     let buf = [0u8; 1000];
     current_account_id(0);
     let buf_len = register_len(0);
     read_register(0, buf.as_ptr() as _);
 
-    let method_name = b"data_producer_10b";
     let args = b"";
-    let mut ids = [0u64; 1000];
+    let mut ids = [0u64; N];
     let amount = 0u128;
     let gas = prepaid_gas();
-    for i in 0..1000 {
+    for i in 0..N {
         ids[i] = promise_create(
             buf_len,
             buf.as_ptr() as _,
-            method_name.len() as _,
-            method_name.as_ptr() as _,
+            producer.len() as _,
+            producer.as_ptr() as _,
             args.len() as _,
             args.as_ptr() as _,
             &amount as *const u128 as *const u64 as u64,
-            gas / 2000,
+            gas / (2 * N as u64),
         );
     }
     let id = promise_and(ids.as_ptr() as _, ids.len() as _);
+    if !callback {
+        return;
+    }
     let method_name = b"noop";
     promise_then(
         id,
@@ -918,74 +1071,39 @@ pub unsafe fn data_receipt_10b_1000() {
     );
 }
 
+// Function to measure `data_receipt_creation_config`, but we are measure send and execution fee at the same time.
+// Produces 1000 10b data receipts.
+#[no_mangle]
+pub unsafe fn data_receipt_10b_1000() {
+    data_receipts::<1000>(b"data_producer_10b", true);
+}
+
 // Function to subtract the base from the `data_receipt_creation_config`. This method doesn't
 // have a callback on created promises so there is no data dependency.
 #[no_mangle]
 pub unsafe fn data_receipt_base_10b_1000() {
-    let buf = [0u8; 1000];
-    current_account_id(0);
-    let buf_len = register_len(0);
-    read_register(0, buf.as_ptr() as _);
+    data_receipts::<1000>(b"data_producer_10b", false);
+}
 
-    let method_name = b"data_producer_10b";
-    let args = b"";
-    let mut ids = [0u64; 1000];
-    let amount = 0u128;
-    let gas = prepaid_gas();
-    for i in 0..1000 {
-        ids[i] = promise_create(
-            buf_len,
-            buf.as_ptr() as _,
-            method_name.len() as _,
-            method_name.as_ptr() as _,
-            args.len() as _,
-            args.as_ptr() as _,
-            &amount as *const u128 as *const u64 as u64,
-            gas / 2000,
-        );
-    }
-    let _id = promise_and(ids.as_ptr() as _, ids.len() as _);
+// Function to measure the cost of a callback with only 10 input data
+// dependencies, to compare with `data_receipt_10b_1000`.
+// Produces 10 10b data receipts.
+#[no_mangle]
+pub unsafe fn data_receipt_10b_10() {
+    data_receipts::<10>(b"data_producer_10b", true);
+}
+
+// Function to subtract the base from `data_receipt_10b_10`.
+#[no_mangle]
+pub unsafe fn data_receipt_base_10b_10() {
+    data_receipts::<10>(b"data_producer_10b", false);
 }
 
 // Function to measure `data_receipt_creation_config`, but we are measure send and execution fee at the same time.
 // Produces 1000 10kib data receipts.
 #[no_mangle]
 pub unsafe fn data_receipt_100kib_1000() {
-    let buf = [0u8; 1000];
-    current_account_id(0);
-    let buf_len = register_len(0);
-    read_register(0, buf.as_ptr() as _);
-
-    let method_name = b"data_producer_100kib";
-    let args = b"";
-    let mut ids = [0u64; 1000];
-    let amount = 0u128;
-    let gas = prepaid_gas();
-    for i in 0..1000 {
-        ids[i] = promise_create(
-            buf_len,
-            buf.as_ptr() as _,
-            method_name.len() as _,
-            method_name.as_ptr() as _,
-            args.len() as _,
-            args.as_ptr() as _,
-            &amount as *const u128 as *const u64 as u64,
-            gas / 2000,
-        );
-    }
-    let id = promise_and(ids.as_ptr() as _, ids.len() as _);
-    let method_name = b"noop";
-    promise_then(
-        id,
-        buf_len,
-        buf.as_ptr() as _,
-        method_name.len() as _,
-        method_name.as_ptr() as _,
-        args.len() as _,
-        args.as_ptr() as _,
-        &amount as *const u128 as *const u64 as u64,
-        gas / 3,
-    );
+    data_receipts::<1000>(b"data_producer_100kib", true);
 }
 
 #[no_mangle]
@@ -139,11 +139,24 @@ pub struct LargeContract {
     pub functions: u32,
     pub locals_per_function: u32,
     pub panic_imports: u32, // How many times to import `env.panic`
+    pub globals: u32,
+    /// Initial size of the table, no table is declared if zero.
+    pub table_elements: u32,
+    /// Size of the payload of a custom section, no custom section is added if
+    /// zero.
+    pub custom_section_bytes: u32,
 }
 
 impl Default for LargeContract {
     fn default() -> Self {
-        Self { functions: 1, locals_per_function: 0, panic_imports: 0 }
+        Self {
+            functions: 1,
+            locals_per_function: 0,
+            panic_imports: 0,
+            globals: 0,
+            table_elements: 0,
+            custom_section_bytes: 0,
+        }
     }
 }
 
@@ -151,13 +164,15 @@ impl LargeContract {
     /// Construct a contract with many entitites.
     ///
     /// Currently supports constructing contracts that contain a specified number of functions with the
-    /// specified number of locals each.
+    /// specified number of locals each, globals, a table of the given size and a custom section of the
+    /// given size.
     ///
     /// Exports a function called `main` that does nothing.
     pub fn make(&self) -> Vec<u8> {
         use wasm_encoder::{
-            CodeSection, EntityType, Export, ExportSection, Function, FunctionSection,
-            ImportSection, Instruction, Module, TypeSection, ValType,
+            CodeSection, CustomSection, EntityType, Export, ExportSection, Function,
+            FunctionSection, GlobalSection, GlobalType, ImportSection, Instruction, Module,
+            TableSection, TableType, TypeSection, ValType,
         };
 
         // Won't generate a valid WASM without functions.
@@ -181,6 +196,27 @@ impl LargeContract {
         }
         module.section(&functions_section);
 
+        if self.table_elements != 0 {
+            let mut table_section = TableSection::new();
+            table_section.table(TableType {
+                element_type: ValType::FuncRef,
+                minimum: self.table_elements,
+                maximum: None,
+            });
+            module.section(&table_section);
+        }
+
+        if self.globals != 0 {
+            let mut global_section = GlobalSection::new();
+            for _ in 0..self.globals {
+                global_section.global(
+                    GlobalType { val_type: ValType::I32, mutable: false },
+                    &Instruction::I32Const(0),
+                );
+            }
+            module.section(&global_section);
+        }
+
         let mut exports_section = ExportSection::new();
         exports_section.export("main", Export::Function(0));
         module.section(&exports_section);
@@ -193,6 +229,11 @@ impl LargeContract {
         }
         module.section(&code_section);
 
+        if self.custom_section_bytes != 0 {
+            let data = vec![0; self.custom_section_bytes as usize];
+            module.section(&CustomSection { name: "large", data: &data });
+        }
+
         module.finish()
     }
 }
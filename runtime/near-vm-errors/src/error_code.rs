@@ -0,0 +1,152 @@
+use crate::{
+    CompilationError, FunctionCallError, HostError, MethodResolveError, PrepareError, WasmTrap,
+};
+
+/// Category of a [`FunctionCallError`], given by the thousands of its
+/// [code](FunctionCallError::code).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, strum::IntoStaticStr)]
+pub enum FunctionCallErrorCategory {
+    /// The contract could not be compiled or prepared for execution.
+    Compilation = 1,
+    /// An import or the called method could not be resolved.
+    Link = 2,
+    /// A host function was called with invalid arguments, a limit was
+    /// exceeded or the contract panicked.
+    Host = 3,
+    /// The contract ran out of gas.
+    Gas = 4,
+    /// The contract accessed memory out of bounds or overflowed its stack.
+    Memory = 5,
+    /// Any other trap during the execution of the contract.
+    Trap = 6,
+}
+
+impl FunctionCallErrorCategory {
+    /// Returns the category of an error code, `None` for unknown codes.
+    pub fn from_code(code: u32) -> Option<Self> {
+        match code / 1000 {
+            1 => Some(Self::Compilation),
+            2 => Some(Self::Link),
+            3 => Some(Self::Host),
+            4 => Some(Self::Gas),
+            5 => Some(Self::Memory),
+            6 => Some(Self::Trap),
+            _ => None,
+        }
+    }
+}
+
+impl FunctionCallError {
+    /// Returns the numeric code of the error, stored in execution outcomes so
+    /// that tooling can categorize failures without matching on error
+    /// messages.
+    ///
+    /// Codes are part of the public interface. They must never be changed or
+    /// reused, new errors get new codes in the range of their category.
+    pub fn code(&self) -> u32 {
+        match self {
+            FunctionCallError::CompilationError(err) => match err {
+                CompilationError::CodeDoesNotExist { .. } => 1000,
+                CompilationError::PrepareError(err) => match err {
+                    PrepareError::Serialization => 1100,
+                    PrepareError::Deserialization => 1101,
+                    PrepareError::InternalMemoryDeclared => 1102,
+                    PrepareError::GasInstrumentation => 1103,
+                    PrepareError::StackHeightInstrumentation => 1104,
+                    PrepareError::Instantiate => 1105,
+                    PrepareError::Memory => 1106,
+                    PrepareError::TooManyFunctions => 1107,
+                    PrepareError::TooManyLocals => 1108,
+                    PrepareError::TooManyGlobals => 1109,
+                    PrepareError::TableTooLarge => 1110,
+                    PrepareError::CustomSectionsTooLarge => 1111,
+                },
+                CompilationError::WasmerCompileError { .. } => 1200,
+            },
+            FunctionCallError::LinkError { .. } => 2000,
+            FunctionCallError::MethodResolveError(err) => match err {
+                MethodResolveError::MethodEmptyName => 2100,
+                MethodResolveError::MethodNotFound => 2101,
+                MethodResolveError::MethodInvalidSignature => 2102,
+            },
+            FunctionCallError::HostError(err) => match err {
+                HostError::BadUTF16 => 3000,
+                HostError::BadUTF8 => 3001,
+                HostError::BalanceExceeded => 3002,
+                HostError::EmptyMethodName => 3003,
+                HostError::GuestPanic { .. } => 3004,
+                HostError::IntegerOverflow => 3005,
+                HostError::InvalidPromiseIndex { .. } => 3006,
+                HostError::CannotAppendActionToJointPromise => 3007,
+                HostError::CannotReturnJointPromise => 3008,
+                HostError::InvalidPromiseResultIndex { .. } => 3009,
+                HostError::InvalidRegisterId { .. } => 3010,
+                HostError::IteratorWasInvalidated { .. } => 3011,
+                HostError::InvalidReceiptIndex { .. } => 3012,
+                HostError::InvalidIteratorIndex { .. } => 3013,
+                HostError::InvalidAccountId => 3014,
+                HostError::InvalidMethodName => 3015,
+                HostError::InvalidPublicKey => 3016,
+                HostError::ProhibitedInView { .. } => 3017,
+                HostError::NumberOfLogsExceeded { .. } => 3018,
+                HostError::KeyLengthExceeded { .. } => 3019,
+                HostError::ValueLengthExceeded { .. } => 3020,
+                HostError::TotalLogLengthExceeded { .. } => 3021,
+                HostError::NumberPromisesExceeded { .. } => 3022,
+                HostError::NumberInputDataDependenciesExceeded { .. } => 3023,
+                HostError::ReturnedValueLengthExceeded { .. } => 3024,
+                HostError::ContractSizeExceeded { .. } => 3025,
+                HostError::Deprecated { .. } => 3026,
+                HostError::ECRecoverError { .. } => 3027,
+                HostError::AltBn128InvalidInput { .. } => 3028,
+                HostError::Ed25519VerifyInvalidInput { .. } => 3029,
+                HostError::GasExceeded => 4000,
+                HostError::GasLimitExceeded => 4001,
+                HostError::MemoryAccessViolation => 5000,
+            },
+            FunctionCallError::WasmTrap(err) => match err {
+                WasmTrap::MemoryOutOfBounds => 5001,
+                WasmTrap::StackOverflow => 5002,
+                WasmTrap::MisalignedAtomicAccess => 5003,
+                WasmTrap::Unreachable => 6000,
+                WasmTrap::IncorrectCallIndirectSignature => 6001,
+                WasmTrap::CallIndirectOOB => 6002,
+                WasmTrap::IllegalArithmetic => 6003,
+                WasmTrap::IndirectCallToNull => 6004,
+                WasmTrap::GenericTrap => 6005,
+            },
+        }
+    }
+
+    /// Returns the category of the error, see [`FunctionCallError::code`].
+    pub fn category(&self) -> FunctionCallErrorCategory {
+        FunctionCallErrorCategory::from_code(self.code()).expect("all codes have a category")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        CompilationError, FunctionCallError, FunctionCallErrorCategory, HostError, PrepareError,
+        WasmTrap,
+    };
+
+    #[test]
+    fn test_error_code() {
+        let err = FunctionCallError::CompilationError(CompilationError::PrepareError(
+            PrepareError::TooManyLocals,
+        ));
+        assert_eq!((err.code(), err.category()), (1108, FunctionCallErrorCategory::Compilation));
+        let err = FunctionCallError::LinkError { msg: "missing import".to_owned() };
+        assert_eq!((err.code(), err.category()), (2000, FunctionCallErrorCategory::Link));
+        let err = FunctionCallError::HostError(HostError::GuestPanic { panic_msg: "!".to_owned() });
+        assert_eq!((err.code(), err.category()), (3004, FunctionCallErrorCategory::Host));
+        let err = FunctionCallError::HostError(HostError::GasExceeded);
+        assert_eq!((err.code(), err.category()), (4000, FunctionCallErrorCategory::Gas));
+        let err = FunctionCallError::WasmTrap(WasmTrap::StackOverflow);
+        assert_eq!((err.code(), err.category()), (5002, FunctionCallErrorCategory::Memory));
+        let err = FunctionCallError::WasmTrap(WasmTrap::Unreachable);
+        assert_eq!((err.code(), err.category()), (6000, FunctionCallErrorCategory::Trap));
+        assert_eq!(FunctionCallErrorCategory::from_code(7000), None);
+    }
+}
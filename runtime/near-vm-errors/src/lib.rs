@@ -8,6 +8,10 @@ use std::any::Any;
 use std::fmt::{self, Error, Formatter};
 use std::io;
 
+mod error_code;
+
+pub use error_code::FunctionCallErrorCategory;
+
 /// For bugs in the runtime itself, crash and die is the usual response.
 ///
 /// See the doc comment on `VMResult` for an explanation what the difference
@@ -61,6 +65,7 @@ pub enum FunctionCallError {
 /// Serializable version of `FunctionCallError`. Must never reorder/remove elements, can only
 /// add new variants at the end (but do that very carefully).
 /// It describes stable serialization format, and only used by serialization logic.
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[derive(Debug, Clone, PartialEq, Eq, BorshDeserialize, BorshSerialize, Serialize, Deserialize)]
 pub enum FunctionCallErrorSer {
     /// Wasm compilation error
@@ -98,6 +103,7 @@ pub enum CacheError {
     SerializationError { hash: [u8; 32] },
 }
 /// A kind of a trap happened during execution of a binary
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[derive(
     Debug,
     Clone,
@@ -131,6 +137,7 @@ pub enum WasmTrap {
     GenericTrap,
 }
 
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[derive(
     Debug,
     Clone,
@@ -149,6 +156,7 @@ pub enum MethodResolveError {
     MethodInvalidSignature,
 }
 
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[derive(
     Debug,
     Clone,
@@ -174,6 +182,7 @@ pub enum CompilationError {
     },
 }
 
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[derive(
     Debug, Clone, PartialEq, Eq, BorshDeserialize, BorshSerialize, Deserialize, Serialize, RpcError,
 )]
@@ -204,8 +213,15 @@ pub enum PrepareError {
     TooManyFunctions,
     /// Contract contains too many locals.
     TooManyLocals,
+    /// Contract contains too many globals.
+    TooManyGlobals,
+    /// Contract declares tables with too many elements.
+    TableTooLarge,
+    /// Custom sections of the contract are too large.
+    CustomSectionsTooLarge,
 }
 
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[derive(
     Debug,
     Clone,
@@ -388,6 +404,9 @@ impl fmt::Display for PrepareError {
             Memory => "Error creating memory.",
             TooManyFunctions => "Too many functions in contract.",
             TooManyLocals => "Too many locals declared in the contract.",
+            TooManyGlobals => "Too many globals declared in the contract.",
+            TableTooLarge => "Tables declared in the contract are too large.",
+            CustomSectionsTooLarge => "Custom sections of the contract are too large.",
         })
     }
 }
@@ -29,6 +29,10 @@ pub struct VMContext {
     pub block_timestamp: u64,
     /// The current epoch height.
     pub epoch_height: EpochHeight,
+    /// The gas price of the current block.
+    pub block_gas_price: Balance,
+    /// The gas limit of the current chunk, `Gas::MAX` if it is unlimited.
+    pub block_gas_limit: Gas,
 
     /// The balance attached to the given account. Excludes the `attached_deposit` that was
     /// attached to the transaction.
@@ -148,6 +148,50 @@ pub trait External {
     /// ```
     fn storage_remove_subtree(&mut self, prefix: &[u8]) -> Result<()>;
 
+    /// Visits the keys in the storage trie associated with the current account
+    /// which start with `prefix` and are not smaller than `start`, in ascending
+    /// lexicographic order.
+    ///
+    /// `visit` is called with each key and a pointer to its value. The
+    /// iteration stops as soon as it returns `Ok(false)` or an error, so that
+    /// the caller can charge gas for each key before the next one is read.
+    ///
+    /// # Arguments
+    ///
+    /// * `prefix` - a prefix for all visited keys
+    /// * `start` - the smallest key to visit
+    /// * `visit` - called for each key, returns whether to continue
+    ///
+    /// # Errors
+    ///
+    /// This function could return [`near_vm_errors::VMError`] or the error
+    /// returned by `visit`.
+    ///
+    /// # Example
+    /// ```
+    /// # use near_vm_logic::mocks::mock_external::MockedExternal;
+    /// # use near_vm_logic::External;
+    ///
+    /// # let mut external = MockedExternal::new();
+    /// external.storage_set(b"key1", b"value1").unwrap();
+    /// external.storage_set(b"key2", b"value2").unwrap();
+    /// external.storage_set(b"other", b"value3").unwrap();
+    /// let mut keys = vec![];
+    /// external
+    ///     .storage_iter_prefix(b"key", b"key2", &mut |key, _value| {
+    ///         keys.push(key.to_vec());
+    ///         Ok(true)
+    ///     })
+    ///     .unwrap();
+    /// assert_eq!(keys, vec![b"key2".to_vec()]);
+    /// ```
+    fn storage_iter_prefix(
+        &self,
+        prefix: &[u8],
+        start: &[u8],
+        visit: &mut dyn FnMut(&[u8], &dyn ValuePtr) -> Result<bool>,
+    ) -> Result<()>;
+
     /// Check whether the `key` is present in the storage trie associated with the current account.
     ///
     /// Returns `Ok(true)` if key is present, `Ok(false)` if the key is not present.
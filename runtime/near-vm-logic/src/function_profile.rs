@@ -0,0 +1,114 @@
+//! Per-function profile of a contract call, collected when the `profiling`
+//! feature is enabled.
+//!
+//! The runner instruments contracts so that the gas meter reports which WASM
+//! function the instructions of a metered block belong to.  Host function
+//! calls are attributed to the function that most recently reported gas,
+//! which is the calling function.
+
+use std::collections::BTreeMap;
+use std::fmt;
+
+/// Execution statistics of a single WASM function.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct FunctionStats {
+    /// Name of the function from the name section of the contract, if any.
+    pub name: Option<String>,
+    /// Number of WASM instructions executed in the function, as counted by
+    /// the gas meter.
+    pub instructions: u64,
+    /// Number of host functions called directly from the function.
+    pub host_calls: u64,
+}
+
+/// Execution statistics of a contract call by WASM function index.
+///
+/// Indices are those of the original contract, before instrumentation.
+/// Functions added by the instrumentation have indices past the original
+/// functions and no name.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct FunctionProfile {
+    functions: BTreeMap<u32, FunctionStats>,
+    current_function: Option<u32>,
+}
+
+impl FunctionProfile {
+    /// Records instructions of function `function_index`, which is executing
+    /// from now on.
+    pub fn record_instructions(&mut self, function_index: u32, instructions: u32) {
+        self.functions.entry(function_index).or_default().instructions += u64::from(instructions);
+        self.current_function = Some(function_index);
+    }
+
+    /// Records a host function call of the currently executing function.
+    pub fn record_host_call(&mut self) {
+        // Without gas metering no function is known to be executing.
+        if let Some(function_index) = self.current_function {
+            self.functions.entry(function_index).or_default().host_calls += 1;
+        }
+    }
+
+    /// Sets the names of the functions from the name section of the contract.
+    pub fn set_names(&mut self, name: impl Fn(u32) -> Option<String>) {
+        for (index, stats) in self.functions.iter_mut() {
+            stats.name = name(*index);
+        }
+    }
+
+    pub fn get(&self, function_index: u32) -> Option<&FunctionStats> {
+        self.functions.get(&function_index)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (u32, &FunctionStats)> {
+        self.functions.iter().map(|(index, stats)| (*index, stats))
+    }
+}
+
+/// Prints one function per line, most executed instructions first.
+impl fmt::Display for FunctionProfile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut functions: Vec<_> = self.iter().collect();
+        functions.sort_by_key(|(index, stats)| (std::cmp::Reverse(stats.instructions), *index));
+        writeln!(f, "{:>8} {:>14} {:>10}  name", "function", "instructions", "host calls")?;
+        for (index, stats) in functions {
+            let name = stats.name.as_deref().unwrap_or("<unknown>");
+            writeln!(f, "{index:>8} {:>14} {:>10}  {name}", stats.instructions, stats.host_calls)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::FunctionProfile;
+
+    #[test]
+    fn test_function_profile() {
+        let mut profile = FunctionProfile::default();
+        // Host calls before any instructions are not attributed.
+        profile.record_host_call();
+        profile.record_instructions(3, 10);
+        profile.record_host_call();
+        profile.record_instructions(5, 100);
+        profile.record_instructions(3, 0);
+        profile.record_host_call();
+        profile.record_host_call();
+        profile.set_names(|index| (index == 5).then(|| "main".to_string()));
+
+        let stats = profile.get(3).unwrap();
+        assert_eq!((stats.name.as_deref(), stats.instructions, stats.host_calls), (None, 10, 3));
+        let stats = profile.get(5).unwrap();
+        assert_eq!(
+            (stats.name.as_deref(), stats.instructions, stats.host_calls),
+            (Some("main"), 100, 0)
+        );
+        assert_eq!(
+            profile.to_string(),
+            "\
+function   instructions host calls  name
+       5            100          0  main
+       3             10          3  <unknown>
+"
+        );
+    }
+}
@@ -10,6 +10,9 @@ use near_primitives_core::{
 };
 use std::collections::HashMap;
 use std::fmt;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{mpsc, Arc};
+use std::time::Duration;
 
 #[inline]
 pub fn with_ext_cost_counter(f: impl FnOnce(&mut HashMap<ExtCosts, u64>)) {
@@ -28,8 +31,12 @@ pub fn with_ext_cost_counter(f: impl FnOnce(&mut HashMap<ExtCosts, u64>)) {
 type Result<T> = ::std::result::Result<T, VMLogicError>;
 
 /// Fast gas counter with very simple structure, could be exposed to compiled code in the VM.
+///
+/// The fields are atomics so that the counter can be shared with compiled code
+/// and with the [`ViewWatchdog`] thread.  They have the layout of `u64` and all
+/// accesses are relaxed, which compiles to plain loads and stores.
 #[repr(C)]
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug)]
 pub struct FastGasCounter {
     /// The following three fields must be put next to another to make sure
     /// generated gas counting code can use and adjust them.
@@ -38,17 +45,51 @@ pub struct FastGasCounter {
     /// and the host code.
 
     /// The amount of gas that was irreversibly used for contract execution.
-    pub burnt_gas: u64,
+    pub burnt_gas: AtomicU64,
     /// Hard gas limit for execution
-    pub gas_limit: u64,
+    pub gas_limit: AtomicU64,
     /// Single WASM opcode cost
-    pub opcode_cost: u64,
+    pub opcode_cost: AtomicU64,
+}
+
+/// Enforces the wall-clock budget of a view call.
+///
+/// Compiled contracts can't be interrupted from the outside, but they check
+/// the gas limit of the [`FastGasCounter`] on every metered block, and host
+/// functions check it on every charge.  Once the budget is used up, a
+/// background thread atomically sets the limit to zero, so that the execution
+/// stops with [`HostError::GasExceeded`] at the next block or host function
+/// call.  The thread shares ownership of the counter, so the watchdog doesn't
+/// borrow the [`GasCounter`].  Dropping the watchdog stops and joins the thread.
+pub struct ViewWatchdog {
+    stop_sender: Option<mpsc::Sender<()>>,
+    thread: Option<std::thread::JoinHandle<()>>,
+}
+
+impl Drop for ViewWatchdog {
+    fn drop(&mut self) {
+        drop(self.stop_sender.take());
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+impl ViewWatchdog {
+    /// Waits until the budget is used up, without stopping the watchdog.
+    #[cfg(test)]
+    fn wait(mut self) {
+        if let Some(thread) = self.thread.take() {
+            thread.join().unwrap();
+        }
+    }
 }
 
 /// Gas counter (a part of VMlogic)
 pub struct GasCounter {
-    /// Shared gas counter data.
-    fast_counter: FastGasCounter,
+    /// Shared gas counter data.  It is a separate allocation because compiled
+    /// code and the [`ViewWatchdog`] access it while the counter is borrowed.
+    fast_counter: Arc<FastGasCounter>,
     /// Gas that was attached to the promises.
     promises_gas: Gas,
     /// Hard gas limit for execution
@@ -81,11 +122,11 @@ impl GasCounter {
         let prepaid_gas = if is_view { Gas::MAX } else { prepaid_gas };
         Self {
             ext_costs_config,
-            fast_counter: FastGasCounter {
-                burnt_gas: 0,
-                gas_limit: min(max_gas_burnt, prepaid_gas),
-                opcode_cost: Gas::from(opcode_cost),
-            },
+            fast_counter: Arc::new(FastGasCounter {
+                burnt_gas: AtomicU64::new(0),
+                gas_limit: AtomicU64::new(min(max_gas_burnt, prepaid_gas)),
+                opcode_cost: AtomicU64::new(Gas::from(opcode_cost)),
+            }),
             max_gas_burnt: max_gas_burnt,
             promises_gas: 0,
             prepaid_gas,
@@ -94,6 +135,18 @@ impl GasCounter {
         }
     }
 
+    fn fast_burnt_gas(&self) -> Gas {
+        self.fast_counter.burnt_gas.load(Ordering::Relaxed)
+    }
+
+    fn set_fast_burnt_gas(&self, burnt_gas: Gas) {
+        self.fast_counter.burnt_gas.store(burnt_gas, Ordering::Relaxed)
+    }
+
+    fn fast_gas_limit(&self) -> Gas {
+        self.fast_counter.gas_limit.load(Ordering::Relaxed)
+    }
+
     /// Accounts for burnt and used gas; reports an error if max gas burnt or
     /// prepaid gas limit is crossed.  Panics when trying to burn more gas than
     /// being used, i.e. if `burn_gas > use_gas`.
@@ -103,16 +156,18 @@ impl GasCounter {
         let new_promises_gas =
             self.promises_gas.checked_add(promise_gas).ok_or(HostError::IntegerOverflow)?;
         let new_burnt_gas =
-            self.fast_counter.burnt_gas.checked_add(burn_gas).ok_or(HostError::IntegerOverflow)?;
+            self.fast_burnt_gas().checked_add(burn_gas).ok_or(HostError::IntegerOverflow)?;
         let new_used_gas =
             new_burnt_gas.checked_add(new_promises_gas).ok_or(HostError::IntegerOverflow)?;
         if new_burnt_gas <= self.max_gas_burnt && new_used_gas <= self.prepaid_gas {
             use std::cmp::min;
             if promise_gas != 0 && !self.is_view {
-                self.fast_counter.gas_limit =
-                    min(self.max_gas_burnt, self.prepaid_gas - new_promises_gas);
+                self.fast_counter.gas_limit.store(
+                    min(self.max_gas_burnt, self.prepaid_gas - new_promises_gas),
+                    Ordering::Relaxed,
+                );
             }
-            self.fast_counter.burnt_gas = new_burnt_gas;
+            self.set_fast_burnt_gas(new_burnt_gas);
             self.promises_gas = new_promises_gas;
             Ok(())
         } else {
@@ -123,9 +178,9 @@ impl GasCounter {
     // Optimized version of above function for cases where no promises involved.
     pub fn burn_gas(&mut self, value: Gas) -> Result<()> {
         let new_burnt_gas =
-            self.fast_counter.burnt_gas.checked_add(value).ok_or(HostError::IntegerOverflow)?;
-        if new_burnt_gas <= self.fast_counter.gas_limit {
-            self.fast_counter.burnt_gas = new_burnt_gas;
+            self.fast_burnt_gas().checked_add(value).ok_or(HostError::IntegerOverflow)?;
+        if new_burnt_gas <= self.fast_gas_limit() {
+            self.set_fast_burnt_gas(new_burnt_gas);
             Ok(())
         } else {
             Err(self.process_gas_limit(new_burnt_gas, new_burnt_gas + self.promises_gas).into())
@@ -136,16 +191,16 @@ impl GasCounter {
         use std::cmp::min;
         // Never burn more gas than what was paid for.
         let hard_burnt_limit = min(self.prepaid_gas, self.max_gas_burnt);
-        self.fast_counter.burnt_gas = min(new_burnt_gas, hard_burnt_limit);
-        debug_assert!(hard_burnt_limit >= self.fast_counter.burnt_gas);
+        let burnt_gas = min(new_burnt_gas, hard_burnt_limit);
+        self.set_fast_burnt_gas(burnt_gas);
 
         // Technically we shall do `self.promises_gas = 0;` or error paths, as in this case
         // no promises will be kept, but that would mean protocol change.
         // See https://github.com/near/nearcore/issues/5148.
         // TODO: consider making this change!
         let used_gas_limit = min(self.prepaid_gas, new_used_gas);
-        assert!(used_gas_limit >= self.fast_counter.burnt_gas);
-        self.promises_gas = used_gas_limit - self.fast_counter.burnt_gas;
+        assert!(used_gas_limit >= burnt_gas);
+        self.promises_gas = used_gas_limit - burnt_gas;
 
         // If we crossed both limits prefer reporting GasLimitExceeded.
         // Alternative would be to prefer reporting limit that is lower (or
@@ -159,7 +214,7 @@ impl GasCounter {
     }
 
     pub fn pay_wasm_gas(&mut self, opcodes: u32) -> Result<()> {
-        let value = Gas::from(opcodes) * self.fast_counter.opcode_cost;
+        let value = Gas::from(opcodes) * self.fast_counter.opcode_cost.load(Ordering::Relaxed);
         self.burn_gas(value)
     }
 
@@ -175,9 +230,24 @@ impl GasCounter {
     ///    cmp rax, [base + 8] ; unsigned compare with burnt limit
     ///    mov [base + 0], rax
     ///    ja emit_gas_exceeded
+    ///
+    /// The pointer stays valid for as long as the counter lives.  All fields
+    /// are atomics, so writing them through the pointer doesn't conflict with
+    /// the shared references of the host code.
     pub fn gas_counter_raw_ptr(&mut self) -> *mut FastGasCounter {
-        use std::ptr;
-        ptr::addr_of_mut!(self.fast_counter)
+        Arc::as_ptr(&self.fast_counter) as *mut FastGasCounter
+    }
+
+    /// Starts a [`ViewWatchdog`] which aborts the execution after `max_duration`.
+    pub fn start_watchdog(&mut self, max_duration: Duration) -> ViewWatchdog {
+        let fast_counter = Arc::clone(&self.fast_counter);
+        let (stop_sender, stop_receiver) = mpsc::channel::<()>();
+        let thread = std::thread::spawn(move || {
+            if let Err(mpsc::RecvTimeoutError::Timeout) = stop_receiver.recv_timeout(max_duration) {
+                fast_counter.gas_limit.store(0, Ordering::Relaxed);
+            }
+        });
+        ViewWatchdog { stop_sender: Some(stop_sender), thread: Some(thread) }
     }
 
     #[inline]
@@ -214,6 +284,39 @@ impl GasCounter {
         self.burn_gas(base_fee)
     }
 
+    /// Pays a base cost and a multiple of a per-unit cost in a single charge.
+    ///
+    /// Equivalent to `pay_base(base_cost)` followed by `pay_per(per_cost, num)`, but
+    /// checks the gas limit only once when both fit. If they don't, it falls
+    /// back to charging them one by one, so that the same charge fails and the
+    /// same amount of gas is burnt and profiled as with separate calls.
+    pub fn pay_base_and_per(
+        &mut self,
+        base_cost: ExtCosts,
+        per_cost: ExtCosts,
+        num: u64,
+    ) -> Result<()> {
+        let base_fee = base_cost.value(&self.ext_costs_config);
+        let per_fee = num.checked_mul(per_cost.value(&self.ext_costs_config));
+        let new_burnt_gas = per_fee
+            .and_then(|per_fee| per_fee.checked_add(base_fee))
+            .and_then(|total| total.checked_add(self.fast_burnt_gas()));
+        match (per_fee, new_burnt_gas) {
+            (Some(per_fee), Some(new_burnt_gas)) if new_burnt_gas <= self.fast_gas_limit() => {
+                self.inc_ext_costs_counter(base_cost, 1);
+                self.update_profile_host(base_cost, base_fee);
+                self.inc_ext_costs_counter(per_cost, num);
+                self.update_profile_host(per_cost, per_fee);
+                self.set_fast_burnt_gas(new_burnt_gas);
+                Ok(())
+            }
+            _ => {
+                self.pay_base(base_cost)?;
+                self.pay_per(per_cost, num)
+            }
+        }
+    }
+
     /// A helper function to pay per byte gas fee for batching an action.
     /// # Args:
     /// * `per_byte_fee`: the fee per byte;
@@ -282,12 +385,12 @@ impl GasCounter {
     }
 
     pub fn burnt_gas(&self) -> Gas {
-        self.fast_counter.burnt_gas
+        self.fast_burnt_gas()
     }
 
     /// Amount of gas used through promises and amount burned.
     pub fn used_gas(&self) -> Gas {
-        self.promises_gas + self.fast_counter.burnt_gas
+        self.promises_gas + self.fast_burnt_gas()
     }
 
     /// Remaining gas based on the amount of prepaid gas not yet used.
@@ -345,6 +448,17 @@ mod tests {
         test(7, 5, true, Err(HostError::GasLimitExceeded));
     }
 
+    #[test]
+    fn test_view_watchdog() {
+        let mut counter = make_test_counter(Gas::MAX, Gas::MAX, true);
+        let watchdog = counter.start_watchdog(std::time::Duration::from_secs(60));
+        drop(watchdog);
+        assert_eq!(counter.burn_gas(5), Ok(()));
+
+        counter.start_watchdog(std::time::Duration::ZERO).wait();
+        assert_eq!(counter.burn_gas(5), Err(HostError::GasExceeded.into()));
+    }
+
     #[test]
     fn test_deduct_too_much() {
         fn test(burn: Gas, prepaid: Gas, view: bool, want: Result<(), HostError>) {
@@ -365,4 +479,33 @@ mod tests {
         test(8, 5, false, Err(HostError::GasExceeded));
         test(8, 5, true, Ok(()));
     }
+
+    /// `pay_base_and_per` must behave exactly like `pay_base` followed by
+    /// `pay_per`, including which of the two charges hits the limit.
+    #[test]
+    fn test_pay_base_and_per_matches_separate_charges() {
+        use near_primitives_core::config::ExtCosts::{write_register_base, write_register_byte};
+
+        let config = ExtCostsConfig::test();
+        let base_fee = write_register_base.value(&config);
+        let byte_fee = write_register_byte.value(&config);
+        for num_bytes in [0, 1, 100, u64::MAX] {
+            let total = base_fee.saturating_add(byte_fee.saturating_mul(num_bytes));
+            for limit in
+                [0, base_fee - 1, base_fee, base_fee + 1, total - 1, total, total.saturating_add(1)]
+            {
+                let mut separate = make_test_counter(limit, limit, false);
+                let want = separate
+                    .pay_base(write_register_base)
+                    .and_then(|()| separate.pay_per(write_register_byte, num_bytes));
+                let mut batched = make_test_counter(limit, limit, false);
+                let got =
+                    batched.pay_base_and_per(write_register_base, write_register_byte, num_bytes);
+                assert_eq!(got, want, "num_bytes: {num_bytes}, limit: {limit}");
+                assert_eq!(batched.burnt_gas(), separate.burnt_gas());
+                assert_eq!(batched.used_gas(), separate.used_gas());
+                assert_eq!(batched.profile_data(), separate.profile_data());
+            }
+        }
+    }
 }
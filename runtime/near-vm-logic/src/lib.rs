@@ -4,6 +4,8 @@ mod alt_bn128;
 mod array_utils;
 mod context;
 mod dependencies;
+#[cfg(feature = "profiling")]
+mod function_profile;
 pub mod gas_counter;
 mod logic;
 pub mod mocks;
@@ -15,7 +17,9 @@ mod utils;
 
 pub use context::VMContext;
 pub use dependencies::{External, MemoryLike, StorageGetMode, ValuePtr};
-pub use logic::{VMLogic, VMOutcome};
+#[cfg(feature = "profiling")]
+pub use function_profile::{FunctionProfile, FunctionStats};
+pub use logic::{MemoryUsage, VMLogic, VMOutcome};
 pub use near_primitives_core::config::*;
 pub use near_primitives_core::profile;
 pub use near_primitives_core::types::ProtocolVersion;
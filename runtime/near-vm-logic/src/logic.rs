@@ -1,6 +1,6 @@
 use crate::context::VMContext;
 use crate::dependencies::{External, MemoryLike};
-use crate::gas_counter::{FastGasCounter, GasCounter};
+use crate::gas_counter::{FastGasCounter, GasCounter, ViewWatchdog};
 use crate::receipt_manager::ReceiptManager;
 use crate::types::{PromiseIndex, PromiseResult, ReceiptIndex, ReturnData};
 use crate::utils::split_method_names;
@@ -9,6 +9,7 @@ use byteorder::ByteOrder;
 use near_crypto::Secp256K1Signature;
 use near_primitives::checked_feature;
 use near_primitives::config::ViewConfig;
+use near_primitives::transaction::Event;
 use near_primitives::version::is_implicit_account_creation_enabled;
 use near_primitives_core::config::ExtCosts::*;
 use near_primitives_core::config::{ActionCosts, ExtCosts, VMConfig};
@@ -56,9 +57,15 @@ pub struct VMLogic<'a> {
     return_data: ReturnData,
     /// Logs written by the runtime.
     logs: Vec<String>,
+    /// Events emitted with `emit_event`. They count against the same limits
+    /// as the logs.
+    events: Vec<Event>,
     /// Registers can be used by the guest to store blobs of data without moving them across
     /// host-guest boundary.
     registers: HashMap<u64, Vec<u8>>,
+    /// Largest memory usage of the registers so far, as counted against
+    /// `registers_memory_limit`.
+    peak_registers_bytes: u64,
 
     /// The DAG of promises, indexed by promise id.
     promises: Vec<Promise>,
@@ -70,6 +77,10 @@ pub struct VMLogic<'a> {
 
     /// Handles the receipts generated through execution.
     receipt_manager: ReceiptManager,
+
+    /// Instruction and host call counts per WASM function.
+    #[cfg(feature = "profiling")]
+    function_profile: crate::FunctionProfile,
 }
 
 /// Promises API allows to create a DAG-structure that defines dependencies between smart contract
@@ -116,7 +127,7 @@ impl<'a> VMLogic<'a> {
         let current_account_balance = context.account_balance + context.attached_deposit;
         let current_storage_usage = context.storage_usage;
         let max_gas_burnt = match context.view_config {
-            Some(ViewConfig { max_gas_burnt: max_gas_burnt_view }) => max_gas_burnt_view,
+            Some(ViewConfig { max_gas_burnt: max_gas_burnt_view, .. }) => max_gas_burnt_view,
             None => config.limit_config.max_gas_burnt,
         };
 
@@ -141,11 +152,15 @@ impl<'a> VMLogic<'a> {
             gas_counter,
             return_data: ReturnData::None,
             logs: vec![],
+            events: vec![],
             registers: HashMap::new(),
+            peak_registers_bytes: 0,
             promises: vec![],
             total_log_length: 0,
             current_protocol_version,
             receipt_manager: ReceiptManager::default(),
+            #[cfg(feature = "profiling")]
+            function_profile: Default::default(),
         }
     }
 
@@ -171,6 +186,11 @@ impl<'a> VMLogic<'a> {
         &self.gas_counter
     }
 
+    #[cfg(test)]
+    pub(crate) fn memory(&mut self) -> &mut dyn MemoryLike {
+        &mut *self.memory
+    }
+
     // ###########################
     // # Memory helper functions #
     // ###########################
@@ -184,16 +204,14 @@ impl<'a> VMLogic<'a> {
     }
 
     fn memory_get_into(&mut self, offset: u64, buf: &mut [u8]) -> Result<()> {
-        self.gas_counter.pay_base(read_memory_base)?;
-        self.gas_counter.pay_per(read_memory_byte, buf.len() as _)?;
+        self.gas_counter.pay_base_and_per(read_memory_base, read_memory_byte, buf.len() as _)?;
         self.try_fit_mem(offset, buf.len() as _)?;
         self.memory.read_memory(offset, buf);
         Ok(())
     }
 
     fn memory_get_vec(&mut self, offset: u64, len: u64) -> Result<Vec<u8>> {
-        self.gas_counter.pay_base(read_memory_base)?;
-        self.gas_counter.pay_per(read_memory_byte, len)?;
+        self.gas_counter.pay_base_and_per(read_memory_base, read_memory_byte, len)?;
         self.try_fit_mem(offset, len)?;
         let mut buf = vec![0; len as usize];
         self.memory.read_memory(offset, &mut buf);
@@ -225,8 +243,7 @@ impl<'a> VMLogic<'a> {
     }
 
     fn memory_set_slice(&mut self, offset: u64, buf: &[u8]) -> Result<()> {
-        self.gas_counter.pay_base(write_memory_base)?;
-        self.gas_counter.pay_per(write_memory_byte, buf.len() as _)?;
+        self.gas_counter.pay_base_and_per(write_memory_base, write_memory_byte, buf.len() as _)?;
         self.try_fit_mem(offset, buf.len() as _)?;
         self.memory.write_memory(offset, buf);
         Ok(())
@@ -240,8 +257,11 @@ impl<'a> VMLogic<'a> {
 
     fn internal_read_register(&mut self, register_id: u64) -> Result<Vec<u8>> {
         if let Some(data) = self.registers.get(&register_id) {
-            self.gas_counter.pay_base(read_register_base)?;
-            self.gas_counter.pay_per(read_register_byte, data.len() as _)?;
+            self.gas_counter.pay_base_and_per(
+                read_register_base,
+                read_register_byte,
+                data.len() as _,
+            )?;
             Ok(data.clone())
         } else {
             Err(HostError::InvalidRegisterId { register_id }.into())
@@ -249,8 +269,11 @@ impl<'a> VMLogic<'a> {
     }
 
     fn internal_write_register(&mut self, register_id: u64, data: Vec<u8>) -> Result<()> {
-        self.gas_counter.pay_base(write_register_base)?;
-        self.gas_counter.pay_per(write_register_byte, data.len() as u64)?;
+        self.gas_counter.pay_base_and_per(
+            write_register_base,
+            write_register_byte,
+            data.len() as u64,
+        )?;
         if data.len() as u64 > self.config.limit_config.max_register_size
             || self.registers.len() as u64 >= self.config.limit_config.max_number_registers
         {
@@ -261,6 +284,7 @@ impl<'a> VMLogic<'a> {
         // Calculate the new memory usage.
         let usage: usize =
             self.registers.values().map(|v| size_of::<u64>() + v.len() * size_of::<u8>()).sum();
+        self.peak_registers_bytes = self.peak_registers_bytes.max(usage as u64);
         if usage as u64 > self.config.limit_config.registers_memory_limit {
             Err(HostError::MemoryAccessViolation.into())
         } else {
@@ -315,6 +339,41 @@ impl<'a> VMLogic<'a> {
         Ok(self.registers.get(&register_id).map(|r| r.len() as _).unwrap_or(u64::MAX))
     }
 
+    /// Returns the number of registers currently in use. Writing to a new
+    /// register fails once this reaches `max_number_registers`.
+    ///
+    /// # Cost
+    ///
+    /// `base`
+    #[cfg(feature = "protocol_feature_register_introspection")]
+    pub fn register_count(&mut self) -> Result<u64> {
+        self.gas_counter.pay_base(base)?;
+        Ok(self.registers.len() as u64)
+    }
+
+    /// Returns the total size of the blobs stored in all registers in use.
+    ///
+    /// # Cost
+    ///
+    /// `base`
+    #[cfg(feature = "protocol_feature_register_introspection")]
+    pub fn register_len_total(&mut self) -> Result<u64> {
+        self.gas_counter.pay_base(base)?;
+        Ok(self.registers.values().map(|r| r.len() as u64).sum())
+    }
+
+    /// Returns the maximum number of registers a contract can use at the same
+    /// time.
+    ///
+    /// # Cost
+    ///
+    /// `base`
+    #[cfg(feature = "protocol_feature_register_introspection")]
+    pub fn max_number_registers(&mut self) -> Result<u64> {
+        self.gas_counter.pay_base(base)?;
+        Ok(self.config.limit_config.max_number_registers)
+    }
+
     /// Copies `data` from the guest memory into the register. If register is unused will initialize
     /// it. If register has larger capacity than needed for `data` will not re-allocate it. The
     /// register will lose the pre-existing data if any.
@@ -471,8 +530,10 @@ impl<'a> VMLogic<'a> {
     // ####################################################
 
     /// Checks that the current log number didn't reach the limit yet, so we can add a new message.
+    /// Events count as log messages.
     fn check_can_add_a_log_message(&self) -> Result<()> {
-        if self.logs.len() as u64 >= self.config.limit_config.max_number_logs {
+        if (self.logs.len() + self.events.len()) as u64 >= self.config.limit_config.max_number_logs
+        {
             Err(HostError::NumberOfLogsExceeded { limit: self.config.limit_config.max_number_logs }
                 .into())
         } else {
@@ -790,6 +851,48 @@ impl<'a> VMLogic<'a> {
         Ok(self.gas_counter.used_gas())
     }
 
+    /// The gas price of the current block, i.e. the price in yoctoNEAR per
+    /// unit of gas that gas burnt in this block is converted with.
+    ///
+    /// # Errors
+    ///
+    /// If called as view function returns `ProhibitedInView`.
+    ///
+    /// # Cost
+    ///
+    /// `block_gas_price_base + memory_write_base + memory_write_size * 16`
+    #[cfg(feature = "protocol_feature_block_gas_info")]
+    pub fn block_gas_price(&mut self, balance_ptr: u64) -> Result<()> {
+        self.gas_counter.pay_base(block_gas_price_base)?;
+        if self.context.is_view() {
+            return Err(
+                HostError::ProhibitedInView { method_name: "block_gas_price".to_string() }.into()
+            );
+        }
+        self.memory_set_u128(balance_ptr, self.context.block_gas_price)
+    }
+
+    /// The gas limit of the chunk the contract is executed in. Returns
+    /// `u64::MAX` if the chunk has no gas limit.
+    ///
+    /// # Errors
+    ///
+    /// If called as view function returns `ProhibitedInView`.
+    ///
+    /// # Cost
+    ///
+    /// `block_gas_limit_base`
+    #[cfg(feature = "protocol_feature_block_gas_info")]
+    pub fn block_gas_limit(&mut self) -> Result<Gas> {
+        self.gas_counter.pay_base(block_gas_limit_base)?;
+        if self.context.is_view() {
+            return Err(
+                HostError::ProhibitedInView { method_name: "block_gas_limit".to_string() }.into()
+            );
+        }
+        Ok(self.context.block_gas_limit)
+    }
+
     // ############
     // # Math API #
     // ############
@@ -997,6 +1100,89 @@ impl<'a> VMLogic<'a> {
         self.internal_write_register(register_id, value_hash.as_slice().to_vec())
     }
 
+    /// Hashes each value of a batch with `hash` and returns the concatenated
+    /// hashes.  The batch is given as `inputs_len` pairs of little-endian
+    /// `(value_len: u64, value_ptr: u64)` starting at `inputs_ptr`.
+    #[cfg(feature = "protocol_feature_hash_batch")]
+    fn hash_batch(
+        &mut self,
+        inputs_len: u64,
+        inputs_ptr: u64,
+        byte_cost: ExtCosts,
+        hash: impl Fn(&[u8]) -> Vec<u8>,
+    ) -> Result<Vec<u8>> {
+        let num_elements = inputs_len.checked_mul(2).ok_or(HostError::MemoryAccessViolation)?;
+        let inputs = self.memory_get_vec_u64(inputs_ptr, num_elements)?;
+        let mut hashes = Vec::new();
+        for input in inputs.chunks_exact(2) {
+            let value = self.get_vec_from_memory_or_register(input[1], input[0])?;
+            self.gas_counter.pay_per(byte_cost, value.len() as u64)?;
+            hashes.extend(hash(&value));
+        }
+        Ok(hashes)
+    }
+
+    /// Hashes each value of a batch using sha256 and returns the concatenated
+    /// 32 byte hashes into `register_id`.
+    ///
+    /// The batch is given as `inputs_len` pairs of little-endian
+    /// `(value_len: u64, value_ptr: u64)` starting at `inputs_ptr`.  Like for
+    /// `sha256`, a value is read from the register `value_ptr` if its
+    /// `value_len` is `u64::MAX`.
+    ///
+    /// # Errors
+    ///
+    /// If the pairs or any of the values point outside the memory or the registers use more
+    /// memory than the limit with `MemoryAccessViolation`.
+    ///
+    /// # Cost
+    ///
+    /// `base + read_memory_base + read_memory_byte * 16 * inputs_len + input_cost(num_bytes) * inputs_len +
+    ///  write_register_base + write_register_byte * 32 * inputs_len + sha256_batch_base + sha256_batch_byte * num_bytes`
+    #[cfg(feature = "protocol_feature_hash_batch")]
+    pub fn sha256_batch(
+        &mut self,
+        inputs_len: u64,
+        inputs_ptr: u64,
+        register_id: u64,
+    ) -> Result<()> {
+        self.gas_counter.pay_base(sha256_batch_base)?;
+        let hashes = self.hash_batch(inputs_len, inputs_ptr, sha256_batch_byte, |value| {
+            use sha2::Digest;
+            sha2::Sha256::digest(value).to_vec()
+        })?;
+        self.internal_write_register(register_id, hashes)
+    }
+
+    /// Hashes each value of a batch using keccak256 and returns the
+    /// concatenated 32 byte hashes into `register_id`.
+    ///
+    /// The batch is given as for `sha256_batch`.
+    ///
+    /// # Errors
+    ///
+    /// If the pairs or any of the values point outside the memory or the registers use more
+    /// memory than the limit with `MemoryAccessViolation`.
+    ///
+    /// # Cost
+    ///
+    /// `base + read_memory_base + read_memory_byte * 16 * inputs_len + input_cost(num_bytes) * inputs_len +
+    ///  write_register_base + write_register_byte * 32 * inputs_len + keccak256_batch_base + keccak256_batch_byte * num_bytes`
+    #[cfg(feature = "protocol_feature_hash_batch")]
+    pub fn keccak256_batch(
+        &mut self,
+        inputs_len: u64,
+        inputs_ptr: u64,
+        register_id: u64,
+    ) -> Result<()> {
+        self.gas_counter.pay_base(keccak256_batch_base)?;
+        let hashes = self.hash_batch(inputs_len, inputs_ptr, keccak256_batch_byte, |value| {
+            use sha3::Digest;
+            sha3::Keccak256::digest(value).to_vec()
+        })?;
+        self.internal_write_register(register_id, hashes)
+    }
+
     /// Hashes the given value using RIPEMD-160 and returns it into `register_id`.
     ///
     /// # Errors
@@ -1121,6 +1307,86 @@ impl<'a> VMLogic<'a> {
         Ok(false as u64)
     }
 
+    /// Recovers the ECDSA signer addresses of a batch of signatures and
+    /// returns them into `register_id`.
+    ///
+    /// The batch is given as `inputs_len` records of 98 bytes starting at
+    /// `inputs_ptr`, each laid out as `hash (32) | signature (64) | v (1) |
+    /// malleability_flag (1)`.  For each record, the register receives 65
+    /// bytes: `1` followed by the recovered 64 byte public key, or `0`
+    /// followed by 64 zero bytes if the signature could not be recovered.
+    ///
+    /// Returns the number of successfully recovered signatures.
+    ///
+    /// # Errors
+    ///
+    /// * If the records point outside the memory, returns `MemoryAccessViolation`.
+    /// * If `v` of a record is not between 0 and 3 or its malleability flag is not 0 or 1,
+    ///   returns `ECRecoverError`.
+    ///
+    /// # Cost
+    ///
+    /// `base + read_memory_base + read_memory_byte * 98 * inputs_len + write_register_base +
+    ///  write_register_byte * 65 * inputs_len + ecrecover_batch_base + ecrecover_batch_element * inputs_len`
+    #[cfg(feature = "protocol_feature_ecrecover_batch")]
+    pub fn ecrecover_batch(
+        &mut self,
+        inputs_len: u64,
+        inputs_ptr: u64,
+        register_id: u64,
+    ) -> Result<u64> {
+        const RECORD_LEN: u64 = 98;
+        self.gas_counter.pay_base(ecrecover_batch_base)?;
+        let records_len =
+            inputs_len.checked_mul(RECORD_LEN).ok_or(HostError::MemoryAccessViolation)?;
+        let records = self.memory_get_vec(inputs_ptr, records_len)?;
+        self.gas_counter.pay_per(ecrecover_batch_element, inputs_len)?;
+
+        let mut output = Vec::with_capacity(records.len() / RECORD_LEN as usize * 65);
+        let mut recovered = 0;
+        for record in records.chunks_exact(RECORD_LEN as usize) {
+            let (v, malleability_flag) = (record[96], record[97]);
+            if v >= 4 {
+                return Err(VMLogicError::HostError(HostError::ECRecoverError {
+                    msg: format!("V recovery byte 0 through 3 are valid but was provided {}", v),
+                }));
+            }
+            if malleability_flag > 1 {
+                return Err(VMLogicError::HostError(HostError::ECRecoverError {
+                    msg: format!(
+                        "Malleability flag needs to be 0 or 1, but is instead {}",
+                        malleability_flag
+                    ),
+                }));
+            }
+
+            let mut hash = [0u8; 32];
+            hash.copy_from_slice(&record[0..32]);
+            let mut bytes = [0u8; 65];
+            bytes.copy_from_slice(&record[32..97]);
+            let signature = Secp256K1Signature::from(bytes);
+
+            let pk = if signature.check_signature_values(malleability_flag != 0) {
+                signature.recover(hash).ok()
+            } else {
+                None
+            };
+            match pk {
+                Some(pk) => {
+                    recovered += 1;
+                    output.push(1);
+                    output.extend_from_slice(pk.as_ref());
+                }
+                None => {
+                    output.push(0);
+                    output.extend_from_slice(&[0u8; 64]);
+                }
+            }
+        }
+        self.internal_write_register(register_id, output)?;
+        Ok(recovered)
+    }
+
     /// Verify an ED25519 signature given a message and a public key.
     ///
     /// Returns a bool indicating success (1) or failure (0) as a `u64`.
@@ -1205,6 +1471,25 @@ impl<'a> VMLogic<'a> {
         self.gas_counter.pay_wasm_gas(opcodes)
     }
 
+    /// Replaces `gas` in contracts instrumented for profiling, additionally
+    /// taking the index of the function the instructions belong to.
+    ///
+    /// # Cost
+    ///
+    /// Same as `gas`.
+    #[cfg(feature = "profiling")]
+    pub fn profiling_gas(&mut self, opcodes: u32, function_index: u32) -> Result<()> {
+        self.function_profile.record_instructions(function_index, opcodes);
+        self.gas(opcodes)
+    }
+
+    /// Counts a host function call for the profile of the calling function.
+    /// Called by the runner before every host function except the gas meter.
+    #[cfg(feature = "profiling")]
+    pub fn record_host_call(&mut self) {
+        self.function_profile.record_host_call();
+    }
+
     // ################
     // # Promises API #
     // ################
@@ -2301,6 +2586,62 @@ impl<'a> VMLogic<'a> {
         self.checked_push_log(message)
     }
 
+    /// Emits a typed event of a contract standard. Unlike `EVENT_JSON:` logs,
+    /// events are recorded with their fields in the execution outcome and
+    /// every event is part of the outcome proof.
+    ///
+    /// The strings are read like in `log_utf8`, the data is read from memory.
+    ///
+    /// # Errors
+    ///
+    /// * If a string or the data extends outside the memory of the guest returns
+    ///   `MemoryAccessViolation`;
+    /// * If a string is not UTF-8 returns `BadUtf8`;
+    /// * If the size of the strings and the data + `total_log_length` exceeds the
+    ///   `max_total_log_length` returns `TotalLogLengthExceeded`;
+    /// * If the total number of logs and events will exceed the `max_number_logs` returns
+    ///   `NumberOfLogsExceeded`.
+    ///
+    /// # Cost
+    ///
+    /// `base + log_base + log_byte * num_bytes + utf8 decoding cost + read_memory_base +
+    /// read_memory_byte * data_len`, where `num_bytes` is the size of the strings and the data.
+    #[cfg(feature = "protocol_feature_structured_events")]
+    pub fn emit_event(
+        &mut self,
+        standard_len: u64,
+        standard_ptr: u64,
+        version_len: u64,
+        version_ptr: u64,
+        event_len: u64,
+        event_ptr: u64,
+        data_len: u64,
+        data_ptr: u64,
+    ) -> Result<()> {
+        self.gas_counter.pay_base(base)?;
+        self.check_can_add_a_log_message()?;
+        let standard = self.get_utf8_string(standard_len, standard_ptr)?;
+        let version = self.get_utf8_string(version_len, version_ptr)?;
+        let event = self.get_utf8_string(event_len, event_ptr)?;
+        let length =
+            ((standard.len() + version.len() + event.len()) as u64).saturating_add(data_len);
+        let max_len =
+            self.config.limit_config.max_total_log_length.saturating_sub(self.total_log_length);
+        if length > max_len {
+            return Err(HostError::TotalLogLengthExceeded {
+                length: self.total_log_length.saturating_add(length),
+                limit: self.config.limit_config.max_total_log_length,
+            }
+            .into());
+        }
+        let data = self.memory_get_vec(data_ptr, data_len)?;
+        self.gas_counter.pay_base(log_base)?;
+        self.gas_counter.pay_per(log_byte, length)?;
+        self.total_log_length += length;
+        self.events.push(Event { standard, version, event, data });
+        Ok(())
+    }
+
     /// Special import kept for compatibility with AssemblyScript contracts. Not called by smart
     /// contracts directly, but instead called by the code generated by AssemblyScript.
     ///
@@ -2674,6 +3015,92 @@ impl<'a> VMLogic<'a> {
         Ok(())
     }
 
+    /// Reads up to `limit` key-value pairs whose keys start with the given
+    /// prefix and are not smaller than the given start key. The pairs are
+    /// written into the `register_id` in lexicographic order of the keys, as
+    /// a borsh-serialized `Vec<(Vec<u8>, Vec<u8>)>`. Returns the number of
+    /// pairs read.
+    ///
+    /// To read the next page, call it again with the last returned key
+    /// followed by a zero byte as the start key. Fewer than `limit` pairs
+    /// means that there are no more keys with the prefix.
+    ///
+    /// # Errors
+    ///
+    /// * If `prefix_len + prefix_ptr` or `start_key_len + start_key_ptr` exceeds the memory
+    ///   container or points to an unused register it returns `MemoryAccessViolation`;
+    /// * If the length of the prefix or the start key exceeds `max_length_storage_key` returns
+    ///   `KeyLengthExceeded`;
+    /// * If the registers exceed the memory limit returns `MemoryAccessViolation`.
+    ///
+    /// # Cost
+    ///
+    /// `base + storage_iter_create_prefix_base + storage_iter_create_prefix_byte * (num_prefix_bytes + num_start_key_bytes)
+    /// + (storage_iter_next_base + storage_iter_next_key_byte * num_key_bytes + storage_iter_next_value_byte * num_value_bytes) * num_pairs
+    /// + cost of reading the prefix and the start key + cost of touched trie nodes + cost to write the pairs`.
+    #[cfg(feature = "protocol_feature_storage_iter_prefix_page")]
+    pub fn storage_iter_prefix_page(
+        &mut self,
+        prefix_len: u64,
+        prefix_ptr: u64,
+        start_key_len: u64,
+        start_key_ptr: u64,
+        limit: u64,
+        register_id: u64,
+    ) -> Result<u64> {
+        self.gas_counter.pay_base(base)?;
+        self.gas_counter.pay_base(storage_iter_create_prefix_base)?;
+        let prefix = self.get_vec_from_memory_or_register(prefix_ptr, prefix_len)?;
+        let start_key = self.get_vec_from_memory_or_register(start_key_ptr, start_key_len)?;
+        for key in [&prefix, &start_key] {
+            if key.len() as u64 > self.config.limit_config.max_length_storage_key {
+                return Err(HostError::KeyLengthExceeded {
+                    length: key.len() as u64,
+                    limit: self.config.limit_config.max_length_storage_key,
+                }
+                .into());
+            }
+        }
+        self.gas_counter.pay_per(
+            storage_iter_create_prefix_byte,
+            prefix.len() as u64 + start_key.len() as u64,
+        )?;
+
+        let mut pairs: Vec<(Vec<u8>, Vec<u8>)> = vec![];
+        let nodes_before = self.ext.get_trie_nodes_count();
+        let result = if limit == 0 {
+            Ok(())
+        } else {
+            let gas_counter = &mut self.gas_counter;
+            // Each pair is paid for before its value is read, so a contract
+            // cannot make the node do more work than it can pay for.
+            self.ext.storage_iter_prefix(&prefix, &start_key, &mut |key, value_ptr| {
+                gas_counter.pay_base(storage_iter_next_base)?;
+                gas_counter.pay_per(storage_iter_next_key_byte, key.len() as u64)?;
+                gas_counter.pay_per(storage_iter_next_value_byte, value_ptr.len() as u64)?;
+                pairs.push((key.to_vec(), value_ptr.deref()?));
+                Ok((pairs.len() as u64) < limit)
+            })
+        };
+        let nodes_delta = self.ext.get_trie_nodes_count() - nodes_before;
+        self.gas_counter.add_trie_fees(&nodes_delta)?;
+        result?;
+
+        near_o11y::io_trace!(
+            storage_op = "iter_prefix_page",
+            prefix = %near_o11y::pretty::Bytes(&prefix),
+            count = pairs.len(),
+            size = pairs.iter().map(|(key, value)| key.len() + value.len()).sum::<usize>(),
+            tn_db_reads = nodes_delta.db_reads,
+            tn_mem_reads = nodes_delta.mem_reads,
+        );
+        let count = pairs.len() as u64;
+        let data = borsh::BorshSerialize::try_to_vec(&pairs)
+            .expect("borsh serialization into a vector cannot fail");
+        self.internal_write_register(register_id, data)?;
+        Ok(count)
+    }
+
     /// DEPRECATED
     /// Creates an iterator object inside the host. Returns the identifier that uniquely
     /// differentiates the given iterator from other iterators that can be simultaneously created.
@@ -2795,7 +3222,14 @@ impl<'a> VMLogic<'a> {
             burnt_gas,
             used_gas,
             logs: self.logs,
+            events: self.events,
             profile,
+            #[cfg(feature = "profiling")]
+            function_profile: Some(self.function_profile),
+            memory_usage: MemoryUsage {
+                linear_memory_pages: 0,
+                registers_bytes: self.peak_registers_bytes,
+            },
             action_receipts: self.receipt_manager.action_receipts,
             aborted: None,
         }
@@ -2818,6 +3252,13 @@ impl<'a> VMLogic<'a> {
         self.gas_counter.gas_counter_raw_ptr()
     }
 
+    /// Starts enforcing the wall-clock budget of a view call, if it has one,
+    /// see [`ViewWatchdog`].
+    pub fn start_view_watchdog(&mut self) -> Option<ViewWatchdog> {
+        let max_duration = self.context.view_config.as_ref()?.max_duration?;
+        Some(self.gas_counter.start_watchdog(max_duration))
+    }
+
     /// Properly handles gas limit exceeded error.
     pub fn process_gas_limit(&mut self) -> HostError {
         let new_burn_gas = self.gas_counter.burnt_gas();
@@ -2886,12 +3327,41 @@ pub struct VMOutcome {
     pub burnt_gas: Gas,
     pub used_gas: Gas,
     pub logs: Vec<String>,
+    /// Events emitted with `emit_event`.
+    pub events: Vec<Event>,
     /// Data collected from making a contract call
     pub profile: ProfileData,
+    /// Instruction and host call counts per WASM function, `None` if the
+    /// contract was not executed.
+    #[cfg(feature = "profiling")]
+    pub function_profile: Option<crate::FunctionProfile>,
+    /// Peak memory used by the call.
+    pub memory_usage: MemoryUsage,
     pub action_receipts: Vec<(AccountId, ReceiptMetadata)>,
     pub aborted: Option<FunctionCallError>,
 }
 
+/// Peak memory used during a contract call, both by the contract itself and
+/// on the host side on its behalf.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct MemoryUsage {
+    /// Largest size of the linear memory of the contract, in WASM pages of
+    /// 64 KiB. Linear memory never shrinks, so this is its size at the end of
+    /// the call. Set by the VM runner, 0 if the contract was not executed.
+    pub linear_memory_pages: u32,
+    /// Largest memory usage of the registers in bytes, as counted against
+    /// `registers_memory_limit`.
+    pub registers_bytes: u64,
+}
+
+impl MemoryUsage {
+    /// Keeps the larger value of each field.
+    pub fn merge_max(&mut self, other: MemoryUsage) {
+        self.linear_memory_pages = self.linear_memory_pages.max(other.linear_memory_pages);
+        self.registers_bytes = self.registers_bytes.max(other.registers_bytes);
+    }
+}
+
 impl VMOutcome {
     /// Consumes the `VMLogic` object and computes the final outcome with the
     /// given error that stopped execution from finishing successfully.
@@ -2919,7 +3389,11 @@ impl VMOutcome {
             burnt_gas: 0,
             used_gas: 0,
             logs: Vec::new(),
+            events: Vec::new(),
             profile: ProfileData::default(),
+            #[cfg(feature = "profiling")]
+            function_profile: None,
+            memory_usage: MemoryUsage::default(),
             action_receipts: Vec::new(),
             aborted: Some(error),
         }
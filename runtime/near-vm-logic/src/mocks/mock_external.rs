@@ -1,8 +1,8 @@
-use crate::{External, StorageGetMode, ValuePtr};
+use crate::{External, StorageGetMode, VMContext, ValuePtr};
 use near_primitives::hash::{hash, CryptoHash};
 use near_primitives::types::TrieNodesCount;
 use near_primitives_core::types::{AccountId, Balance};
-use std::collections::HashMap;
+use std::collections::{HashMap, VecDeque};
 
 #[derive(Default, Clone)]
 /// Emulates the trie and the mock handling code.
@@ -10,6 +10,17 @@ pub struct MockedExternal {
     pub fake_trie: HashMap<Vec<u8>, Vec<u8>>,
     pub validators: HashMap<AccountId, Balance>,
     data_count: u64,
+    calls: VecDeque<MockedCall>,
+}
+
+/// Values of the [`VMContext`] that differ between the calls of a test, see
+/// [`MockedExternal::push_call`].  Fields left as `None` keep the value of the
+/// context the test passes in.
+#[derive(Default, Clone, Debug)]
+pub struct MockedCall {
+    pub random_seed: Option<Vec<u8>>,
+    pub predecessor_account_id: Option<AccountId>,
+    pub signer_account_id: Option<AccountId>,
 }
 
 pub struct MockedValuePtr {
@@ -39,6 +50,46 @@ impl MockedExternal {
     pub fn new() -> Self {
         Self::default()
     }
+
+    /// Queues the values of the context for a future call.  Calls are applied
+    /// in the order they were pushed, one per [`Self::apply_next_call`].
+    pub fn push_call(&mut self, call: MockedCall) {
+        self.calls.push_back(call);
+    }
+
+    /// Queues one call per random seed, e.g. to test a contract that draws
+    /// randomness in every call.
+    pub fn push_random_seeds(&mut self, seeds: impl IntoIterator<Item = Vec<u8>>) {
+        self.calls.extend(
+            seeds
+                .into_iter()
+                .map(|seed| MockedCall { random_seed: Some(seed), ..Default::default() }),
+        );
+    }
+
+    /// Number of queued calls that have not been applied yet.
+    pub fn pending_calls(&self) -> usize {
+        self.calls.len()
+    }
+
+    /// Overrides the values of `context` with the next queued call, if any.
+    /// Returns whether a call was applied.
+    pub fn apply_next_call(&mut self, context: &mut VMContext) -> bool {
+        let call = match self.calls.pop_front() {
+            Some(call) => call,
+            None => return false,
+        };
+        if let Some(random_seed) = call.random_seed {
+            context.random_seed = random_seed;
+        }
+        if let Some(predecessor_account_id) = call.predecessor_account_id {
+            context.predecessor_account_id = predecessor_account_id;
+        }
+        if let Some(signer_account_id) = call.signer_account_id {
+            context.signer_account_id = signer_account_id;
+        }
+        true
+    }
 }
 
 use crate::dependencies::Result;
@@ -66,6 +117,27 @@ impl External for MockedExternal {
         Ok(())
     }
 
+    fn storage_iter_prefix(
+        &self,
+        prefix: &[u8],
+        start: &[u8],
+        visit: &mut dyn FnMut(&[u8], &dyn ValuePtr) -> Result<bool>,
+    ) -> Result<()> {
+        let mut keys: Vec<&Vec<u8>> = self
+            .fake_trie
+            .keys()
+            .filter(|key| key.starts_with(prefix) && key.as_slice() >= start)
+            .collect();
+        keys.sort();
+        for key in keys {
+            let value = MockedValuePtr { value: self.fake_trie[key].clone() };
+            if !visit(key, &value)? {
+                break;
+            }
+        }
+        Ok(())
+    }
+
     fn storage_has_key(&mut self, key: &[u8]) -> Result<bool> {
         Ok(self.fake_trie.contains_key(key))
     }
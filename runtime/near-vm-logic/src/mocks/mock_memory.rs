@@ -1,26 +1,86 @@
 use crate::MemoryLike;
 
-#[derive(Default)]
-pub struct MockedMemory {}
+use std::ops::Range;
+
+/// Guest memory for tests, backed by a buffer of fixed size.
+///
+/// Like the memory of a real VM, accesses outside of the buffer are reported
+/// by `fits_memory` and panic in the other `MemoryLike` methods.
+pub struct MockedMemory(Box<[u8]>);
+
+impl MockedMemory {
+    /// Size of the memory created by `default`, one Wasm page.
+    pub const DEFAULT_SIZE: u64 = 64 * 1024;
+
+    /// Creates a zeroed memory of `size` bytes.
+    pub fn new(size: u64) -> Self {
+        let size = usize::try_from(size).expect("memory size exceeds the address space");
+        Self(vec![0; size].into_boxed_slice())
+    }
+
+    /// Returns the size of the memory in bytes.
+    pub fn size(&self) -> u64 {
+        self.0.len() as u64
+    }
+
+    /// Returns the content of the given memory interval.
+    ///
+    /// # Panics
+    ///
+    /// If the memory interval is outside the memory.
+    pub fn get(&self, offset: u64, len: u64) -> &[u8] {
+        &self.0[self.range(offset, len)]
+    }
+
+    /// Overwrites the memory at `offset` with `data`, e.g. to preload
+    /// arguments of a host function call.
+    ///
+    /// # Panics
+    ///
+    /// If `offset + data.len()` is outside the memory.
+    pub fn set(&mut self, offset: u64, data: &[u8]) {
+        let range = self.range(offset, data.len() as u64);
+        self.0[range].copy_from_slice(data);
+    }
+
+    fn checked_range(&self, offset: u64, len: u64) -> Option<Range<usize>> {
+        let start = usize::try_from(offset).ok()?;
+        let end = start.checked_add(usize::try_from(len).ok()?)?;
+        (end <= self.0.len()).then(|| start..end)
+    }
+
+    fn range(&self, offset: u64, len: u64) -> Range<usize> {
+        self.checked_range(offset, len).unwrap_or_else(|| {
+            panic!(
+                "memory access out of bounds: offset {}, length {}, memory size {}",
+                offset,
+                len,
+                self.size()
+            )
+        })
+    }
+}
+
+impl Default for MockedMemory {
+    fn default() -> Self {
+        Self::new(Self::DEFAULT_SIZE)
+    }
+}
 
 impl MemoryLike for MockedMemory {
-    fn fits_memory(&self, _offset: u64, _len: u64) -> bool {
-        true
+    fn fits_memory(&self, offset: u64, len: u64) -> bool {
+        self.checked_range(offset, len).is_some()
     }
 
     fn read_memory(&self, offset: u64, buffer: &mut [u8]) {
-        let src = unsafe { std::slice::from_raw_parts(offset as *const u8, buffer.len() as usize) };
-        buffer.copy_from_slice(src);
+        buffer.copy_from_slice(self.get(offset, buffer.len() as u64));
     }
 
     fn read_memory_u8(&self, offset: u64) -> u8 {
-        let offset = offset as *const u8;
-        unsafe { *offset }
+        self.get(offset, 1)[0]
     }
 
     fn write_memory(&mut self, offset: u64, buffer: &[u8]) {
-        let dest =
-            unsafe { std::slice::from_raw_parts_mut(offset as *mut u8, buffer.len() as usize) };
-        dest.copy_from_slice(buffer);
+        self.set(offset, buffer);
     }
 }
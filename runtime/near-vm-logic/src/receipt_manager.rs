@@ -327,6 +327,7 @@ impl ReceiptManager {
                                     .map_err(|_| HostError::InvalidMethodName)
                             })
                             .collect::<std::result::Result<Vec<_>, _>>()?,
+                        method_allowances: Default::default(),
                     }),
                 },
             }),
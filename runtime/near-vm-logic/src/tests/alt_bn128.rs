@@ -91,11 +91,13 @@ fn test_alt_bn128_g1_multiexp() {
         let mut logic_builder = VMLogicBuilder::default();
         let mut logic = logic_builder.build(get_context(vec![], false));
 
-        let res = logic.alt_bn128_g1_multiexp(input.len() as _, input.as_ptr() as _, 0);
+        let input_ptr = logic.internal_mem_write(input);
+        let res = logic.alt_bn128_g1_multiexp(input.len() as _, input_ptr, 0);
         if let Some(((), expected)) = check_result(res, expected) {
             let len = logic.register_len(0).unwrap();
-            let mut res = vec![0u8; len as usize];
-            logic.read_register(0, res.as_mut_ptr() as _).unwrap();
+            let ptr = logic.internal_mem_write(&vec![0u8; len as usize]);
+            logic.read_register(0, ptr).unwrap();
+            let res = logic.internal_mem_read(ptr, len);
             assert_eq!(res, expected)
         }
     }
@@ -156,11 +158,13 @@ fn test_alt_bn128_g1_sum() {
         let mut logic_builder = VMLogicBuilder::default();
         let mut logic = logic_builder.build(get_context(vec![], false));
 
-        let res = logic.alt_bn128_g1_sum(input.len() as _, input.as_ptr() as _, 0);
+        let input_ptr = logic.internal_mem_write(input);
+        let res = logic.alt_bn128_g1_sum(input.len() as _, input_ptr, 0);
         if let Some(((), expected)) = check_result(res, expected) {
             let len = logic.register_len(0).unwrap();
-            let mut res = vec![0u8; len as usize];
-            logic.read_register(0, res.as_mut_ptr() as _).unwrap();
+            let ptr = logic.internal_mem_write(&vec![0u8; len as usize]);
+            logic.read_register(0, ptr).unwrap();
+            let res = logic.internal_mem_read(ptr, len);
             assert_eq_points(&res, expected)
         }
     }
@@ -221,7 +225,8 @@ fn test_alt_bn128_pairing_check() {
         let mut logic_builder = VMLogicBuilder::default();
         let mut logic = logic_builder.build(get_context(vec![], false));
 
-        let res = logic.alt_bn128_pairing_check(input.len() as _, input.as_ptr() as _);
+        let input_ptr = logic.internal_mem_write(input);
+        let res = logic.alt_bn128_pairing_check(input.len() as _, input_ptr);
         if let Some((res, expected)) = check_result(res, expected) {
             assert_eq!(res, expected)
         }
@@ -1,3 +1,4 @@
+use crate::mocks::mock_external::MockedCall;
 use crate::tests::vm_logic_builder::VMLogicBuilder;
 use crate::VMContext;
 
@@ -11,6 +12,8 @@ pub fn create_context() -> VMContext {
         block_height: 10,
         block_timestamp: 42,
         epoch_height: 1,
+        block_gas_price: 100_000_000,
+        block_gas_limit: 1_000 * 10u64.pow(12),
         account_balance: 2u128,
         account_locked_balance: 1u128,
         storage_usage: 12,
@@ -28,10 +31,8 @@ macro_rules! decl_test_bytes {
         fn $testname() {
             let mut logic_builder = VMLogicBuilder::default();
             let mut logic = logic_builder.build(create_context());
-            let res = vec![0u8; $input.len()];
             logic.$method(0).expect("read bytes into register from context should be ok");
-            logic.read_register(0, res.as_ptr() as _).expect("read register should be ok");
-            assert_eq!(res, $input);
+            logic.assert_read_register($input, 0);
         }
     };
 }
@@ -55,9 +56,11 @@ macro_rules! decl_test_u128 {
             let mut logic_builder = VMLogicBuilder::default();
             let mut logic = logic_builder.build(create_context());
             let buf = [0u8; std::mem::size_of::<u128>()];
+            let ptr = logic.internal_mem_write(&buf);
 
-            logic.$method(buf.as_ptr() as _).expect("read from context should be ok");
-            let res = u128::from_le_bytes(buf);
+            logic.$method(ptr).expect("read from context should be ok");
+            let buf = logic.internal_mem_read(ptr, buf.len() as u64);
+            let res = u128::from_le_bytes(buf.try_into().unwrap());
             assert_eq!(res, $input);
         }
     };
@@ -92,6 +95,8 @@ decl_test_u64!(test_block_index, block_index, create_context().block_height);
 decl_test_u64!(test_block_timestamp, block_timestamp, create_context().block_timestamp);
 decl_test_u64!(test_storage_usage, storage_usage, create_context().storage_usage);
 decl_test_u64!(test_prepaid_gas, prepaid_gas, create_context().prepaid_gas);
+#[cfg(feature = "protocol_feature_block_gas_info")]
+decl_test_u64!(test_block_gas_limit, block_gas_limit, create_context().block_gas_limit);
 
 decl_test_u128!(
     test_account_balance,
@@ -104,3 +109,39 @@ decl_test_u128!(
     create_context().account_locked_balance
 );
 decl_test_u128!(test_attached_deposit, attached_deposit, create_context().attached_deposit);
+#[cfg(feature = "protocol_feature_block_gas_info")]
+decl_test_u128!(test_block_gas_price, block_gas_price, create_context().block_gas_price);
+
+#[test]
+fn test_mocked_calls() {
+    let mut logic_builder = VMLogicBuilder::default();
+    logic_builder.ext.push_call(MockedCall {
+        predecessor_account_id: Some("dave".parse().unwrap()),
+        signer_account_id: Some("erin".parse().unwrap()),
+        ..Default::default()
+    });
+    logic_builder.ext.push_random_seeds([vec![1], vec![2]]);
+    assert_eq!(logic_builder.ext.pending_calls(), 3);
+
+    let mut logic = logic_builder.build(create_context());
+    logic.predecessor_account_id(0).unwrap();
+    logic.assert_read_register(b"dave", 0);
+    logic.signer_account_id(0).unwrap();
+    logic.assert_read_register(b"erin", 0);
+    logic.random_seed(0).unwrap();
+    logic.assert_read_register(&create_context().random_seed, 0);
+
+    for seed in [[1], [2]] {
+        let mut logic = logic_builder.build(create_context());
+        logic.random_seed(0).unwrap();
+        logic.assert_read_register(&seed, 0);
+        logic.predecessor_account_id(0).unwrap();
+        logic.assert_read_register(b"carol", 0);
+    }
+
+    // Without queued calls, the context is used as is.
+    assert_eq!(logic_builder.ext.pending_calls(), 0);
+    let mut logic = logic_builder.build(create_context());
+    logic.random_seed(0).unwrap();
+    logic.assert_read_register(&create_context().random_seed, 0);
+}
@@ -60,21 +60,21 @@ fn check_ed25519_verify(
         logic.wrapped_internal_write_register(1, &signature).unwrap();
         1
     } else {
-        signature.as_ptr() as u64
+        logic.internal_mem_write(signature)
     };
 
     let message_ptr = if message_len == u64::MAX {
         logic.wrapped_internal_write_register(2, &message).unwrap();
         2
     } else {
-        message.as_ptr() as u64
+        logic.internal_mem_write(message)
     };
 
     let public_key_ptr = if public_key_len == u64::MAX {
         logic.wrapped_internal_write_register(3, &public_key).unwrap();
         3
     } else {
-        public_key.as_ptr() as u64
+        logic.internal_mem_write(public_key)
     };
 
     let result = logic.ed25519_verify(
@@ -11,6 +11,8 @@ pub fn get_context(input: Vec<u8>, is_view: bool) -> VMContext {
         block_height: 0,
         block_timestamp: 0,
         epoch_height: 0,
+        block_gas_price: 100_000_000,
+        block_gas_limit: 1_000 * 10u64.pow(12),
         account_balance: 100,
         storage_usage: 0,
         account_locked_balance: 0,
@@ -18,7 +20,10 @@ pub fn get_context(input: Vec<u8>, is_view: bool) -> VMContext {
         prepaid_gas: 10_u64.pow(14),
         random_seed: vec![],
         view_config: match is_view {
-            true => Some(ViewConfig { max_gas_burnt: VMLimitConfig::test().max_gas_burnt }),
+            true => Some(ViewConfig {
+                max_gas_burnt: VMLimitConfig::test().max_gas_burnt,
+                max_duration: None,
+            }),
             false => None,
         },
         output_data_receivers: vec![],
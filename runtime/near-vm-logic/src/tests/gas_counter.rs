@@ -1,9 +1,9 @@
 use crate::receipt_manager::ReceiptMetadata;
 use crate::tests::fixtures::get_context;
 use crate::tests::helpers::*;
-use crate::tests::vm_logic_builder::VMLogicBuilder;
+use crate::tests::vm_logic_builder::{TestVMLogic, VMLogicBuilder};
 use crate::types::Gas;
-use crate::{VMConfig, VMLogic};
+use crate::VMConfig;
 use near_primitives::transaction::{Action, FunctionCallAction};
 
 #[test]
@@ -225,7 +225,7 @@ impl VMLogicBuilder {
         self
     }
 
-    fn build_with_prepaid_gas(&mut self, prepaid_gas: Gas) -> VMLogic<'_> {
+    fn build_with_prepaid_gas(&mut self, prepaid_gas: Gas) -> TestVMLogic<'_> {
         let mut context = get_context(vec![], false);
         context.prepaid_gas = prepaid_gas;
         self.build(context)
@@ -233,7 +233,7 @@ impl VMLogicBuilder {
 }
 
 /// Given the limit in gas, compute the corresponding limit in wasm ops for use
-/// with [`VMLogic::gas`] function.
+/// with [`crate::VMLogic::gas`] function.
 fn op_limit(gas_limit: Gas) -> u32 {
     (gas_limit / (VMConfig::test().regular_op_cost as u64)) as u32
 }
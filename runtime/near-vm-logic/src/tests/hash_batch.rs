@@ -0,0 +1,118 @@
+use crate::tests::fixtures::get_context;
+use crate::tests::helpers::*;
+use crate::tests::vm_logic_builder::{TestVMLogic, VMLogicBuilder};
+use crate::{map, ExtCosts};
+use near_vm_errors::HostError;
+use near_vm_errors::VMLogicError;
+
+fn read_hashes(logic: &mut TestVMLogic, num_hashes: usize) -> Vec<u8> {
+    let len = 32 * num_hashes;
+    let ptr = logic.internal_mem_write(&vec![0u8; len]);
+    logic.read_register(0, ptr).expect("OK");
+    logic.internal_mem_read(ptr, len as u64)
+}
+
+/// Writes the `(len, ptr)` pairs of `values` and returns a pointer to them.
+fn write_inputs(logic: &mut TestVMLogic, values: &[&[u8]]) -> u64 {
+    let mut inputs = Vec::new();
+    for value in values {
+        let ptr = logic.internal_mem_write(value);
+        inputs.extend_from_slice(&(value.len() as u64).to_le_bytes());
+        inputs.extend_from_slice(&ptr.to_le_bytes());
+    }
+    logic.internal_mem_write(&inputs)
+}
+
+#[test]
+fn test_sha256_batch() {
+    use sha2::Digest;
+
+    let mut logic_builder = VMLogicBuilder::default();
+    let mut logic = logic_builder.build(get_context(vec![], false));
+    let first = b"tesdsst";
+    let second = b"another value";
+    let inputs = write_inputs(&mut logic, &[first, second]);
+
+    logic.sha256_batch(2, inputs, 0).unwrap();
+    let expected = [sha2::Sha256::digest(first), sha2::Sha256::digest(second)].concat();
+    assert_eq!(read_hashes(&mut logic, 2), expected);
+    let len = (first.len() + second.len()) as u64;
+    assert_costs(map! {
+        ExtCosts::base: 1,
+        ExtCosts::read_memory_base: 3,
+        ExtCosts::read_memory_byte: 32 + len,
+        ExtCosts::write_memory_base: 1,
+        ExtCosts::write_memory_byte: 64,
+        ExtCosts::read_register_base: 1,
+        ExtCosts::read_register_byte: 64,
+        ExtCosts::write_register_base: 1,
+        ExtCosts::write_register_byte: 64,
+        ExtCosts::sha256_batch_base: 1,
+        ExtCosts::sha256_batch_byte: len,
+    });
+}
+
+#[test]
+fn test_keccak256_batch() {
+    use sha3::Digest;
+
+    let mut logic_builder = VMLogicBuilder::default();
+    let mut logic = logic_builder.build(get_context(vec![], false));
+    let first = b"tesdsst";
+    let second = b"another value";
+    let inputs = write_inputs(&mut logic, &[first, second]);
+
+    logic.keccak256_batch(2, inputs, 0).unwrap();
+    let expected = [sha3::Keccak256::digest(first), sha3::Keccak256::digest(second)].concat();
+    assert_eq!(read_hashes(&mut logic, 2), expected);
+    let len = (first.len() + second.len()) as u64;
+    assert_costs(map! {
+        ExtCosts::base: 1,
+        ExtCosts::read_memory_base: 3,
+        ExtCosts::read_memory_byte: 32 + len,
+        ExtCosts::write_memory_base: 1,
+        ExtCosts::write_memory_byte: 64,
+        ExtCosts::read_register_base: 1,
+        ExtCosts::read_register_byte: 64,
+        ExtCosts::write_register_base: 1,
+        ExtCosts::write_register_byte: 64,
+        ExtCosts::keccak256_batch_base: 1,
+        ExtCosts::keccak256_batch_byte: len,
+    });
+}
+
+#[test]
+fn test_sha256_batch_from_register() {
+    use sha2::Digest;
+
+    let mut logic_builder = VMLogicBuilder::default();
+    let mut logic = logic_builder.build(get_context(vec![], false));
+    let value = b"tesdsst";
+    logic.wrapped_internal_write_register(1, value).unwrap();
+    let inputs = [u64::MAX.to_le_bytes(), 1u64.to_le_bytes()].concat();
+    let inputs = logic.internal_mem_write(&inputs);
+
+    logic.sha256_batch(1, inputs, 0).unwrap();
+    assert_eq!(read_hashes(&mut logic, 1), sha2::Sha256::digest(value).to_vec());
+}
+
+#[test]
+fn test_sha256_batch_empty() {
+    let mut logic_builder = VMLogicBuilder::default();
+    let mut logic = logic_builder.build(get_context(vec![], false));
+
+    let inputs = logic.internal_mem_write(&[]);
+    logic.sha256_batch(0, inputs, 0).unwrap();
+    assert_eq!(logic.register_len(0), Ok(0));
+}
+
+#[test]
+fn test_sha256_batch_overflow() {
+    let mut logic_builder = VMLogicBuilder::default();
+    let mut logic = logic_builder.build(get_context(vec![], false));
+
+    assert_eq!(
+        logic.sha256_batch(u64::MAX, 0, 0),
+        Err(VMLogicError::HostError(HostError::MemoryAccessViolation))
+    );
+}
@@ -1,4 +1,5 @@
-use crate::{with_ext_cost_counter, VMLogic};
+use crate::tests::vm_logic_builder::TestVMLogic;
+use crate::with_ext_cost_counter;
 use near_primitives_core::{config::ExtCosts, types::Gas};
 use near_vm_errors::VMLogicError;
 use std::collections::HashMap;
@@ -6,69 +7,88 @@ use std::collections::HashMap;
 type Result<T> = ::std::result::Result<T, VMLogicError>;
 
 pub fn promise_create(
-    logic: &mut crate::VMLogic<'_>,
+    logic: &mut TestVMLogic<'_>,
     account_id: &[u8],
     amount: u128,
     gas: Gas,
 ) -> Result<u64> {
+    let account_id_ptr = logic.internal_mem_write(account_id);
     let method = b"promise_create";
+    let method_ptr = logic.internal_mem_write(method);
     let args = b"args";
+    let args_ptr = logic.internal_mem_write(args);
+    let amount_ptr = logic.internal_mem_write(&amount.to_le_bytes());
     logic.promise_create(
         account_id.len() as _,
-        account_id.as_ptr() as _,
+        account_id_ptr,
         method.len() as _,
-        method.as_ptr() as _,
+        method_ptr,
         args.len() as _,
-        args.as_ptr() as _,
-        amount.to_le_bytes().as_ptr() as _,
+        args_ptr,
+        amount_ptr,
         gas,
     )
 }
 
 #[allow(dead_code)]
-pub fn promise_batch_create(logic: &mut VMLogic, account_id: &str) -> Result<u64> {
-    logic.promise_batch_create(account_id.len() as _, account_id.as_ptr() as _)
+pub fn promise_batch_create(logic: &mut TestVMLogic, account_id: &str) -> Result<u64> {
+    let account_id_ptr = logic.internal_mem_write(account_id.as_bytes());
+    logic.promise_batch_create(account_id.len() as _, account_id_ptr)
+}
+
+#[allow(dead_code)]
+pub fn promise_and(logic: &mut TestVMLogic<'_>, promise_indices: &[u64]) -> Result<u64> {
+    let promise_indices_bytes: Vec<u8> =
+        promise_indices.iter().flat_map(|index| index.to_le_bytes()).collect();
+    let promise_indices_ptr = logic.internal_mem_write(&promise_indices_bytes);
+    logic.promise_and(promise_indices_ptr, promise_indices.len() as _)
 }
 
 #[allow(dead_code)]
 pub fn promise_batch_action_function_call(
-    logic: &mut VMLogic<'_>,
+    logic: &mut TestVMLogic<'_>,
     promise_index: u64,
     amount: u128,
     gas: Gas,
 ) -> Result<()> {
     let method_id = b"promise_batch_action";
+    let method_id_ptr = logic.internal_mem_write(method_id);
     let args = b"promise_batch_action_args";
+    let args_ptr = logic.internal_mem_write(args);
+    let amount_ptr = logic.internal_mem_write(&amount.to_le_bytes());
 
     logic.promise_batch_action_function_call(
         promise_index,
         method_id.len() as _,
-        method_id.as_ptr() as _,
+        method_id_ptr,
         args.len() as _,
-        args.as_ptr() as _,
-        amount.to_le_bytes().as_ptr() as _,
+        args_ptr,
+        amount_ptr,
         gas,
     )
 }
 
 #[allow(dead_code)]
 pub fn promise_batch_action_function_call_weight(
-    logic: &mut VMLogic<'_>,
+    logic: &mut TestVMLogic<'_>,
     promise_index: u64,
     amount: u128,
     gas: Gas,
     weight: u64,
 ) -> Result<()> {
     let method_id = b"promise_batch_action";
+    let method_id_ptr = logic.internal_mem_write(method_id);
     let args = b"promise_batch_action_args";
+    let args_ptr = logic.internal_mem_write(args);
+    let amount_ptr = logic.internal_mem_write(&amount.to_le_bytes());
 
     logic.promise_batch_action_function_call_weight(
         promise_index,
         method_id.len() as _,
-        method_id.as_ptr() as _,
+        method_id_ptr,
         args.len() as _,
-        args.as_ptr() as _,
-        amount.to_le_bytes().as_ptr() as _,
+        args_ptr,
+        amount_ptr,
         gas,
         weight,
     )
@@ -76,7 +96,7 @@ pub fn promise_batch_action_function_call_weight(
 
 #[allow(dead_code)]
 pub fn promise_batch_action_add_key_with_function_call(
-    logic: &mut VMLogic<'_>,
+    logic: &mut TestVMLogic<'_>,
     promise_index: u64,
     public_key: &[u8],
     nonce: u64,
@@ -84,16 +104,21 @@ pub fn promise_batch_action_add_key_with_function_call(
     receiver_id: &[u8],
     method_names: &[u8],
 ) -> Result<()> {
+    let public_key_ptr = logic.internal_mem_write(public_key);
+    let allowance_ptr = logic.internal_mem_write(&allowance.to_le_bytes());
+    let receiver_id_ptr = logic.internal_mem_write(receiver_id);
+    let method_names_ptr = logic.internal_mem_write(method_names);
+
     logic.promise_batch_action_add_key_with_function_call(
         promise_index,
         public_key.len() as _,
-        public_key.as_ptr() as _,
+        public_key_ptr,
         nonce,
-        allowance.to_le_bytes().as_ptr() as _,
+        allowance_ptr,
         receiver_id.len() as _,
-        receiver_id.as_ptr() as _,
+        receiver_id_ptr,
         method_names.len() as _,
-        method_names.as_ptr() as _,
+        method_names_ptr,
     )
 }
 
@@ -7,17 +7,19 @@ fn test_iterator_deprecated() {
     let context = get_context(vec![], false);
     let mut logic_builder = VMLogicBuilder::default();
     let mut logic = logic_builder.build(context);
+    let a = logic.internal_mem_write(b"a");
+    let b = logic.internal_mem_write(b"b");
     assert_eq!(
         Err(VMLogicError::HostError(HostError::Deprecated {
             method_name: "storage_iter_prefix".to_string()
         })),
-        logic.storage_iter_prefix(1, b"a".as_ptr() as _)
+        logic.storage_iter_prefix(1, a)
     );
     assert_eq!(
         Err(VMLogicError::HostError(HostError::Deprecated {
             method_name: "storage_iter_range".to_string()
         })),
-        logic.storage_iter_range(1, b"a".as_ptr() as _, 1, b"b".as_ptr() as _)
+        logic.storage_iter_range(1, a, 1, b)
     );
     assert_eq!(
         Err(VMLogicError::HostError(HostError::Deprecated {
@@ -26,3 +28,106 @@ fn test_iterator_deprecated() {
         logic.storage_iter_next(0, 0, 1)
     );
 }
+
+#[cfg(feature = "protocol_feature_storage_iter_prefix_page")]
+mod prefix_page {
+    use crate::tests::fixtures::get_context;
+    use crate::tests::helpers::*;
+    use crate::tests::vm_logic_builder::{TestVMLogic, VMLogicBuilder};
+    use crate::{map, ExtCosts, External};
+    use borsh::BorshDeserialize;
+    use near_vm_errors::{HostError, VMLogicError};
+
+    type Pairs = Vec<(Vec<u8>, Vec<u8>)>;
+
+    fn read_page(logic: &mut TestVMLogic, prefix: &[u8], start: &[u8], limit: u64) -> Pairs {
+        let prefix_ptr = logic.internal_mem_write(prefix);
+        let start_ptr = logic.internal_mem_write(start);
+        let count = logic
+            .storage_iter_prefix_page(
+                prefix.len() as _,
+                prefix_ptr,
+                start.len() as _,
+                start_ptr,
+                limit,
+                0,
+            )
+            .unwrap();
+        let len = logic.register_len(0).unwrap();
+        let ptr = logic.internal_mem_write(&vec![0u8; len as usize]);
+        logic.read_register(0, ptr).unwrap();
+        let pairs = Pairs::try_from_slice(&logic.internal_mem_read(ptr, len)).unwrap();
+        assert_eq!(count, pairs.len() as u64);
+        pairs
+    }
+
+    fn pairs(pairs: &[(&[u8], &[u8])]) -> Pairs {
+        pairs.iter().map(|(key, value)| (key.to_vec(), value.to_vec())).collect()
+    }
+
+    #[test]
+    fn test_storage_iter_prefix_page() {
+        let mut logic_builder = VMLogicBuilder::default();
+        for (key, value) in
+            [(&b"a"[..], &b"0"[..]), (b"k3", b"3"), (b"k1", b"1"), (b"k2", b""), (b"l", b"")]
+        {
+            logic_builder.ext.storage_set(key, value).unwrap();
+        }
+        let mut logic = logic_builder.build(get_context(vec![], false));
+
+        assert_eq!(
+            read_page(&mut logic, b"k", b"", 10),
+            pairs(&[(b"k1", b"1"), (b"k2", b""), (b"k3", b"3")])
+        );
+        assert_eq!(read_page(&mut logic, b"k", b"", 2), pairs(&[(b"k1", b"1"), (b"k2", b"")]));
+        assert_eq!(read_page(&mut logic, b"k", b"k2\0", 2), pairs(&[(b"k3", b"3")]));
+        assert_eq!(read_page(&mut logic, b"k", b"l", 2), pairs(&[]));
+        assert_eq!(read_page(&mut logic, b"k", b"", 0), pairs(&[]));
+        assert_eq!(read_page(&mut logic, b"", b"k3", 10), pairs(&[(b"k3", b"3"), (b"l", b"")]));
+    }
+
+    #[test]
+    fn test_storage_iter_prefix_page_costs() {
+        let mut logic_builder = VMLogicBuilder::default();
+        logic_builder.ext.storage_set(b"key1", b"value").unwrap();
+        logic_builder.ext.storage_set(b"key2", b"other value").unwrap();
+        logic_builder.ext.storage_set(b"key3", b"").unwrap();
+        let mut logic = logic_builder.build(get_context(vec![], false));
+        let prefix = logic.internal_mem_write(b"key");
+        let start = logic.internal_mem_write(b"key2");
+        reset_costs_counter();
+
+        assert_eq!(logic.storage_iter_prefix_page(3, prefix, 4, start, 1, 0), Ok(1));
+        // borsh: item count, then key and value with their lengths
+        let written = 4 + (4 + 4) + (4 + 11);
+        assert_costs(map! {
+            ExtCosts::base: 1,
+            ExtCosts::read_memory_base: 2,
+            ExtCosts::read_memory_byte: 7,
+            ExtCosts::storage_iter_create_prefix_base: 1,
+            ExtCosts::storage_iter_create_prefix_byte: 7,
+            ExtCosts::storage_iter_next_base: 1,
+            ExtCosts::storage_iter_next_key_byte: 4,
+            ExtCosts::storage_iter_next_value_byte: 11,
+            ExtCosts::touching_trie_node: 0,
+            ExtCosts::read_cached_trie_node: 0,
+            ExtCosts::write_register_base: 1,
+            ExtCosts::write_register_byte: written,
+        });
+    }
+
+    #[test]
+    fn test_storage_iter_prefix_page_key_too_long() {
+        let mut logic_builder = VMLogicBuilder::default();
+        let limit = 10;
+        logic_builder.config.limit_config.max_length_storage_key = limit;
+        let mut logic = logic_builder.build(get_context(vec![], false));
+        let key = vec![0u8; limit as usize + 1];
+        let ptr = logic.internal_mem_write(&key);
+
+        assert_eq!(
+            logic.storage_iter_prefix_page(0, ptr, key.len() as _, ptr, 1, 0),
+            Err(VMLogicError::HostError(HostError::KeyLengthExceeded { length: limit + 1, limit }))
+        );
+    }
+}
@@ -14,7 +14,8 @@ fn test_valid_utf8() {
     let mut logic = logic_builder.build(get_context(vec![], false));
     let string_bytes = "j ñ r'ø qò$`5 y'5 øò{%÷ `Võ%".as_bytes().to_vec();
     let len = string_bytes.len() as u64;
-    logic.log_utf8(len, string_bytes.as_ptr() as _).expect("Valid utf-8 string_bytes");
+    let string_bytes_ptr = logic.internal_mem_write(&string_bytes);
+    logic.log_utf8(len, string_bytes_ptr).expect("Valid utf-8 string_bytes");
     let outcome = logic.compute_outcome_and_distribute_gas();
     assert_eq!(outcome.logs[0], String::from_utf8(string_bytes).unwrap());
     assert_costs(map! {
@@ -34,7 +35,8 @@ fn test_invalid_utf8() {
     let mut logic = logic_builder.build(get_context(vec![], false));
     let string_bytes = [128].to_vec();
     let len = string_bytes.len() as u64;
-    assert_eq!(logic.log_utf8(len, string_bytes.as_ptr() as _), Err(HostError::BadUTF8.into()));
+    let string_bytes_ptr = logic.internal_mem_write(&string_bytes);
+    assert_eq!(logic.log_utf8(len, string_bytes_ptr), Err(HostError::BadUTF8.into()));
     let outcome = logic.compute_outcome_and_distribute_gas();
     assert_eq!(outcome.logs.len(), 0);
     assert_costs(map! {
@@ -55,9 +57,8 @@ fn test_valid_null_terminated_utf8() {
     let bytes_len = string_bytes.len();
     logic_builder.config.limit_config.max_total_log_length = string_bytes.len() as u64;
     let mut logic = logic_builder.build(get_context(vec![], false));
-    logic
-        .log_utf8(u64::MAX, string_bytes.as_ptr() as _)
-        .expect("Valid null-terminated utf-8 string_bytes");
+    let string_bytes_ptr = logic.internal_mem_write(&string_bytes);
+    logic.log_utf8(u64::MAX, string_bytes_ptr).expect("Valid null-terminated utf-8 string_bytes");
     string_bytes.pop();
     let outcome = logic.compute_outcome_and_distribute_gas();
     let len = bytes_len as u64;
@@ -81,8 +82,9 @@ fn test_log_max_limit() {
     logic_builder.config.limit_config.max_total_log_length = limit;
     let mut logic = logic_builder.build(get_context(vec![], false));
 
+    let string_bytes_ptr = logic.internal_mem_write(&string_bytes);
     assert_eq!(
-        logic.log_utf8(string_bytes.len() as _, string_bytes.as_ptr() as _),
+        logic.log_utf8(string_bytes.len() as _, string_bytes_ptr),
         Err(HostError::TotalLogLengthExceeded { length: string_bytes.len() as _, limit }.into())
     );
 
@@ -105,13 +107,14 @@ fn test_log_total_length_limit() {
     logic_builder.config.limit_config.max_number_logs = num_logs;
     let mut logic = logic_builder.build(get_context(vec![], false));
 
+    let string_bytes_ptr = logic.internal_mem_write(&string_bytes);
     for _ in 0..num_logs - 1 {
         logic
-            .log_utf8(string_bytes.len() as _, string_bytes.as_ptr() as _)
+            .log_utf8(string_bytes.len() as _, string_bytes_ptr)
             .expect("total is still under the limit");
     }
     assert_eq!(
-        logic.log_utf8(string_bytes.len() as _, string_bytes.as_ptr() as _),
+        logic.log_utf8(string_bytes.len() as _, string_bytes_ptr),
         Err(HostError::TotalLogLengthExceeded { length: limit + 1, limit }.into())
     );
 
@@ -129,13 +132,14 @@ fn test_log_number_limit() {
     logic_builder.config.limit_config.max_number_logs = max_number_logs;
     let mut logic = logic_builder.build(get_context(vec![], false));
     let len = string_bytes.len() as u64;
+    let string_bytes_ptr = logic.internal_mem_write(&string_bytes);
     for _ in 0..max_number_logs {
         logic
-            .log_utf8(len, string_bytes.as_ptr() as _)
+            .log_utf8(len, string_bytes_ptr)
             .expect("Valid utf-8 string_bytes under the log number limit");
     }
     assert_eq!(
-        logic.log_utf8(len, string_bytes.as_ptr() as _),
+        logic.log_utf8(len, string_bytes_ptr),
         Err(HostError::NumberOfLogsExceeded { limit: max_number_logs }.into())
     );
 
@@ -169,13 +173,14 @@ fn test_log_utf16_number_limit() {
 
     let mut logic = logic_builder.build(get_context(vec![], false));
     let len = string_bytes.len() as u64;
+    let string_bytes_ptr = logic.internal_mem_write(&string_bytes);
     for _ in 0..max_number_logs {
         logic
-            .log_utf16(len, string_bytes.as_ptr() as _)
+            .log_utf16(len, string_bytes_ptr)
             .expect("Valid utf-16 string_bytes under the log number limit");
     }
     assert_eq!(
-        logic.log_utf16(len, string_bytes.as_ptr() as _),
+        logic.log_utf16(len, string_bytes_ptr),
         Err(HostError::NumberOfLogsExceeded { limit: max_number_logs }.into())
     );
 
@@ -216,17 +221,20 @@ fn test_log_total_length_limit_mixed() {
     logic_builder.config.limit_config.max_number_logs = num_logs_each * 2 + 1;
     let mut logic = logic_builder.build(get_context(vec![], false));
 
+    let utf16_bytes_ptr = logic.internal_mem_write(&utf16_bytes);
+    let utf8_bytes_ptr = logic.internal_mem_write(&utf8_bytes);
     for _ in 0..num_logs_each {
         logic
-            .log_utf16(utf16_bytes.len() as _, utf16_bytes.as_ptr() as _)
+            .log_utf16(utf16_bytes.len() as _, utf16_bytes_ptr)
             .expect("total is still under the limit");
 
         logic
-            .log_utf8(utf8_bytes.len() as _, utf8_bytes.as_ptr() as _)
+            .log_utf8(utf8_bytes.len() as _, utf8_bytes_ptr)
             .expect("total is still under the limit");
     }
+    let final_bytes_ptr = logic.internal_mem_write(&final_bytes);
     assert_eq!(
-        logic.log_utf8(final_bytes.len() as _, final_bytes.as_ptr() as _),
+        logic.log_utf8(final_bytes.len() as _, final_bytes_ptr),
         Err(HostError::TotalLogLengthExceeded { length: limit + 1, limit }.into())
     );
 
@@ -243,8 +251,9 @@ fn test_log_utf8_max_limit_null_terminated() {
     let mut logic = logic_builder.build(get_context(vec![], false));
 
     string_bytes.push(0u8);
+    let string_bytes_ptr = logic.internal_mem_write(&string_bytes);
     assert_eq!(
-        logic.log_utf8(u64::MAX, string_bytes.as_ptr() as _),
+        logic.log_utf8(u64::MAX, string_bytes_ptr),
         Err(HostError::TotalLogLengthExceeded { length: limit + 1, limit }.into())
     );
 
@@ -270,9 +279,8 @@ fn test_valid_log_utf16() {
         utf16_bytes.push(u16_ as u8);
         utf16_bytes.push((u16_ >> 8) as u8);
     }
-    logic
-        .log_utf16(utf16_bytes.len() as _, utf16_bytes.as_ptr() as _)
-        .expect("Valid utf-16 string_bytes");
+    let utf16_bytes_ptr = logic.internal_mem_write(&utf16_bytes);
+    logic.log_utf16(utf16_bytes.len() as _, utf16_bytes_ptr).expect("Valid utf-16 string_bytes");
 
     let len = utf16_bytes.len() as u64;
     assert_costs(map! {
@@ -300,7 +308,8 @@ fn test_valid_log_utf16_max_log_len_not_even() {
         utf16_bytes.push((u16_ >> 8) as u8);
     }
     utf16_bytes.extend_from_slice(&[0, 0]);
-    logic.log_utf16(u64::MAX, utf16_bytes.as_ptr() as _).expect("Valid utf-16 string_bytes");
+    let utf16_bytes_ptr = logic.internal_mem_write(&utf16_bytes);
+    logic.log_utf16(u64::MAX, utf16_bytes_ptr).expect("Valid utf-16 string_bytes");
 
     let len = utf16_bytes.len() as u64;
     assert_costs(map! {
@@ -320,8 +329,9 @@ fn test_valid_log_utf16_max_log_len_not_even() {
         utf16_bytes.push((u16_ >> 8) as u8);
     }
     utf16_bytes.extend_from_slice(&[0, 0]);
+    let utf16_bytes_ptr = logic.internal_mem_write(&utf16_bytes);
     assert_eq!(
-        logic.log_utf16(u64::MAX, utf16_bytes.as_ptr() as _),
+        logic.log_utf16(u64::MAX, utf16_bytes_ptr),
         Err(HostError::TotalLogLengthExceeded {
             length: 6,
             limit: logic_builder.config.limit_config.max_total_log_length,
@@ -344,7 +354,8 @@ fn test_log_utf8_max_limit_null_terminated_fail() {
     string_bytes.push(0u8);
     logic_builder.config.limit_config.max_total_log_length = 3;
     let mut logic = logic_builder.build(get_context(vec![], false));
-    let res = logic.log_utf8(u64::MAX, string_bytes.as_ptr() as _);
+    let string_bytes_ptr = logic.internal_mem_write(&string_bytes);
+    let res = logic.log_utf8(u64::MAX, string_bytes_ptr);
     assert_eq!(res, Err(HostError::TotalLogLengthExceeded { length: 4, limit: 3 }.into()));
     assert_costs(map! {
         ExtCosts::base: 1,
@@ -366,7 +377,8 @@ fn test_valid_log_utf16_null_terminated() {
     }
     utf16_bytes.push(0);
     utf16_bytes.push(0);
-    logic.log_utf16(u64::MAX, utf16_bytes.as_ptr() as _).expect("Valid utf-16 string_bytes");
+    let utf16_bytes_ptr = logic.internal_mem_write(&utf16_bytes);
+    logic.log_utf16(u64::MAX, utf16_bytes_ptr).expect("Valid utf-16 string_bytes");
 
     let len = utf16_bytes.len() as u64;
     let outcome = logic.compute_outcome_and_distribute_gas();
@@ -392,7 +404,8 @@ fn test_invalid_log_utf16() {
         utf16_bytes.push(u16_ as u8);
         utf16_bytes.push((u16_ >> 8) as u8);
     }
-    let res = logic.log_utf16(utf16_bytes.len() as _, utf16_bytes.as_ptr() as _);
+    let utf16_bytes_ptr = logic.internal_mem_write(&utf16_bytes);
+    let res = logic.log_utf16(utf16_bytes.len() as _, utf16_bytes_ptr);
     let len = utf16_bytes.len() as u64;
     assert_eq!(res, Err(HostError::BadUTF16.into()));
     assert_costs(map! {
@@ -418,7 +431,8 @@ fn test_valid_log_utf16_null_terminated_fail() {
     utf16_bytes.push(0xD8u8); // Bad utf-16
     utf16_bytes.push(0);
     utf16_bytes.push(0);
-    let res = logic.log_utf16(u64::MAX, utf16_bytes.as_ptr() as _);
+    let utf16_bytes_ptr = logic.internal_mem_write(&utf16_bytes);
+    let res = logic.log_utf16(u64::MAX, utf16_bytes_ptr);
     let len = utf16_bytes.len() as u64;
     assert_eq!(res, Err(HostError::BadUTF16.into()));
     assert_costs(map! {
@@ -430,21 +444,87 @@ fn test_valid_log_utf16_null_terminated_fail() {
     });
 }
 
+#[test]
+#[cfg(feature = "protocol_feature_structured_events")]
+fn test_emit_event() {
+    let mut logic_builder = VMLogicBuilder::default();
+    let mut logic = logic_builder.build(get_context(vec![], false));
+    let standard_ptr = logic.internal_mem_write(b"nep171");
+    let version_ptr = logic.internal_mem_write(b"1.0.0");
+    let event_ptr = logic.internal_mem_write(b"nft_mint");
+    let data_ptr = logic.internal_mem_write(&[1, 2, 3]);
+    logic
+        .emit_event(6, standard_ptr, 5, version_ptr, 8, event_ptr, 3, data_ptr)
+        .expect("valid event");
+
+    let strings_len = 6 + 5 + 8;
+    assert_costs(map! {
+        ExtCosts::base: 1,
+        ExtCosts::log_base: 1,
+        ExtCosts::log_byte: strings_len + 3,
+        ExtCosts::read_memory_base: 4,
+        ExtCosts::read_memory_byte: strings_len + 3,
+        ExtCosts::utf8_decoding_base: 3,
+        ExtCosts::utf8_decoding_byte: strings_len,
+    });
+
+    let outcome = logic.compute_outcome_and_distribute_gas();
+    assert!(outcome.logs.is_empty());
+    assert_eq!(
+        outcome.events,
+        vec![near_primitives::transaction::Event {
+            standard: "nep171".to_string(),
+            version: "1.0.0".to_string(),
+            event: "nft_mint".to_string(),
+            data: vec![1, 2, 3],
+        }]
+    );
+}
+
+#[test]
+#[cfg(feature = "protocol_feature_structured_events")]
+fn test_emit_event_log_limits() {
+    let mut logic_builder = VMLogicBuilder::default();
+    let max_number_logs = 2;
+    logic_builder.config.limit_config.max_number_logs = max_number_logs;
+    logic_builder.config.limit_config.max_total_log_length = 20;
+    let mut logic = logic_builder.build(get_context(vec![], false));
+    let ptr = logic.internal_mem_write(b"blabla");
+
+    // Events count against the number of logs.
+    logic.log_utf8(6, ptr).expect("under the log number limit");
+    logic.emit_event(1, ptr, 1, ptr, 1, ptr, 0, ptr).expect("under the log number limit");
+    assert_eq!(
+        logic.emit_event(1, ptr, 1, ptr, 1, ptr, 0, ptr),
+        Err(HostError::NumberOfLogsExceeded { limit: max_number_logs }.into())
+    );
+
+    // The strings and the data count against the total log length.
+    logic_builder.config.limit_config.max_number_logs = 10;
+    let mut logic = logic_builder.build(get_context(vec![], false));
+    let ptr = logic.internal_mem_write(b"blabla");
+    assert_eq!(
+        logic.emit_event(6, ptr, 6, ptr, 6, ptr, 3, ptr),
+        Err(HostError::TotalLogLengthExceeded { length: 21, limit: 20 }.into())
+    );
+    let outcome = logic.compute_outcome_and_distribute_gas();
+    assert!(outcome.events.is_empty());
+}
+
 #[test]
 fn test_sha256() {
     let mut logic_builder = VMLogicBuilder::default();
     let mut logic = logic_builder.build(get_context(vec![], false));
     let data = b"tesdsst";
 
-    logic.sha256(data.len() as _, data.as_ptr() as _, 0).unwrap();
-    let mut res = [0u8; 32];
-    logic.read_register(0, (&mut res[..]).as_ptr() as _).expect("OK");
-    assert_eq!(
-        res,
-        [
+    let data_ptr = logic.internal_mem_write(data);
+    logic.sha256(data.len() as _, data_ptr, 0).unwrap();
+    logic.assert_read_register(
+        &[
             18, 176, 115, 156, 45, 100, 241, 132, 180, 134, 77, 42, 105, 111, 199, 127, 118, 112,
             92, 255, 88, 43, 83, 147, 122, 55, 26, 36, 42, 156, 160, 158,
-        ]
+        ],
+        0,
     );
     let len = data.len() as u64;
     assert_costs(map! {
@@ -468,15 +548,14 @@ fn test_keccak256() {
     let mut logic = logic_builder.build(get_context(vec![], false));
     let data = b"tesdsst";
 
-    logic.keccak256(data.len() as _, data.as_ptr() as _, 0).unwrap();
-    let mut res = [0u8; 32];
-    logic.read_register(0, (&mut res[..]).as_ptr() as _).expect("OK");
-    assert_eq!(
-        res.as_slice(),
+    let data_ptr = logic.internal_mem_write(data);
+    logic.keccak256(data.len() as _, data_ptr, 0).unwrap();
+    logic.assert_read_register(
         &[
             104, 110, 58, 122, 230, 181, 215, 145, 231, 229, 49, 162, 123, 167, 177, 58, 26, 142,
-            129, 173, 7, 37, 9, 26, 233, 115, 64, 102, 61, 85, 10, 159
-        ]
+            129, 173, 7, 37, 9, 26, 233, 115, 64, 102, 61, 85, 10, 159,
+        ],
+        0,
     );
     let len = data.len() as u64;
     assert_costs(map! {
@@ -500,17 +579,16 @@ fn test_keccak512() {
     let mut logic = logic_builder.build(get_context(vec![], false));
     let data = b"tesdsst";
 
-    logic.keccak512(data.len() as _, data.as_ptr() as _, 0).unwrap();
-    let mut res = [0u8; 64];
-    logic.read_register(0, (&mut res[..]).as_ptr() as _).expect("OK");
-    assert_eq!(
-        res,
-        [
+    let data_ptr = logic.internal_mem_write(data);
+    logic.keccak512(data.len() as _, data_ptr, 0).unwrap();
+    logic.assert_read_register(
+        &[
             55, 134, 96, 137, 168, 122, 187, 95, 67, 76, 18, 122, 146, 11, 225, 106, 117, 194, 154,
             157, 48, 160, 90, 146, 104, 209, 118, 126, 222, 230, 200, 125, 48, 73, 197, 236, 123,
             173, 192, 197, 90, 153, 167, 121, 100, 88, 209, 240, 137, 86, 239, 41, 87, 128, 219,
-            249, 136, 203, 220, 109, 46, 168, 234, 190
-        ]
+            249, 136, 203, 220, 109, 46, 168, 234, 190,
+        ],
+        0,
     );
     let len = data.len() as u64;
     assert_costs(map! {
@@ -534,12 +612,11 @@ fn test_ripemd160() {
     let mut logic = logic_builder.build(get_context(vec![], false));
 
     let data = b"tesdsst";
-    logic.ripemd160(data.len() as _, data.as_ptr() as _, 0).unwrap();
-    let mut res = [0u8; 20];
-    logic.read_register(0, (&mut res[..]).as_ptr() as _).expect("OK");
-    assert_eq!(
-        res,
-        [21, 102, 156, 115, 232, 3, 58, 215, 35, 84, 129, 30, 143, 86, 212, 104, 70, 97, 14, 225,]
+    let data_ptr = logic.internal_mem_write(data);
+    logic.ripemd160(data.len() as _, data_ptr, 0).unwrap();
+    logic.assert_read_register(
+        &[21, 102, 156, 115, 232, 3, 58, 215, 35, 84, 129, 30, 143, 86, 212, 104, 70, 97, 14, 225],
+        0,
     );
     let len = data.len() as u64;
     assert_costs(map! {
@@ -589,9 +666,9 @@ fn test_ecrecover() {
         let mut logic_builder = VMLogicBuilder::default();
         let mut logic = logic_builder.build(get_context(vec![], false));
 
-        let b = logic
-            .ecrecover(32, m.as_ptr() as _, 64, sig.as_ptr() as _, v as _, mc as _, 1)
-            .unwrap();
+        let m_ptr = logic.internal_mem_write(&m);
+        let sig_ptr = logic.internal_mem_write(&sig);
+        let b = logic.ecrecover(32, m_ptr, 64, sig_ptr, v as _, mc as _, 1).unwrap();
         assert_eq!(b, res.is_some() as u64);
 
         if let Some(res) = res {
@@ -602,9 +679,7 @@ fn test_ecrecover() {
                 ExtCosts::write_register_byte: 64,
                 ExtCosts::ecrecover_base: 1,
             });
-            let result = [0u8; 64];
-            logic.read_register(1, result.as_ptr() as _).unwrap();
-            assert_eq!(res, result);
+            logic.assert_read_register(&res, 1);
         } else {
             assert_costs(map! {
                 ExtCosts::read_memory_base: 2,
@@ -617,6 +692,92 @@ fn test_ecrecover() {
     }
 }
 
+#[test]
+#[cfg(feature = "protocol_feature_ecrecover_batch")]
+fn test_ecrecover_batch() {
+    // Only a part of the test vectors fits into the prepaid gas of one call.
+    let mut tests: Vec<EcrecoverTest> =
+        from_slice(fs::read("src/tests/ecrecover-tests.json").unwrap().as_slice()).unwrap();
+    tests.truncate(20);
+    let mut logic_builder = VMLogicBuilder::default();
+    let mut logic = logic_builder.build(get_context(vec![], false));
+
+    let mut records = Vec::new();
+    let mut expected = Vec::new();
+    for EcrecoverTest { m, v, sig, mc, res } in &tests {
+        records.extend_from_slice(m);
+        records.extend_from_slice(sig);
+        records.extend_from_slice(&[*v, *mc as u8]);
+        expected.push(res.is_some() as u8);
+        expected.extend_from_slice(&res.unwrap_or([0; 64]));
+    }
+    let records_ptr = logic.internal_mem_write(&records);
+    let n = tests.len() as u64;
+    let recovered = logic.ecrecover_batch(n, records_ptr, 1).unwrap();
+    assert_eq!(recovered, tests.iter().filter(|test| test.res.is_some()).count() as u64);
+    assert_costs(map! {
+        ExtCosts::read_memory_base: 1,
+        ExtCosts::read_memory_byte: 98 * n,
+        ExtCosts::write_register_base: 1,
+        ExtCosts::write_register_byte: 65 * n,
+        ExtCosts::ecrecover_batch_base: 1,
+        ExtCosts::ecrecover_batch_element: n,
+    });
+    logic.assert_read_register(&expected, 1);
+}
+
+/// Batching must not be a discount over calling `ecrecover` for each record.
+#[test]
+#[cfg(feature = "protocol_feature_ecrecover_batch")]
+fn test_ecrecover_batch_cost() {
+    let mut tests: Vec<EcrecoverTest> =
+        from_slice(fs::read("src/tests/ecrecover-tests.json").unwrap().as_slice()).unwrap();
+    tests.truncate(20);
+    let mut records = Vec::new();
+    for EcrecoverTest { m, v, sig, mc, .. } in &tests {
+        records.extend_from_slice(m);
+        records.extend_from_slice(sig);
+        records.extend_from_slice(&[*v, *mc as u8]);
+    }
+
+    let mut logic_builder = VMLogicBuilder::default();
+    let ecrecover_base = logic_builder.config.ext_costs.ecrecover_base;
+    let mut logic = logic_builder.build(get_context(vec![], false));
+    let records_ptr = logic.internal_mem_write(&records);
+    let n = tests.len() as u64;
+    logic.ecrecover_batch(n, records_ptr, 1).unwrap();
+    let outcome = logic.compute_outcome_and_distribute_gas();
+    assert!(outcome.burnt_gas >= n * ecrecover_base);
+}
+
+#[test]
+#[cfg(feature = "protocol_feature_ecrecover_batch")]
+fn test_ecrecover_batch_invalid_record() {
+    let mut logic_builder = VMLogicBuilder::default();
+    let mut logic = logic_builder.build(get_context(vec![], false));
+
+    let mut record = [0u8; 98];
+    record[96] = 4;
+    let records_ptr = logic.internal_mem_write(&record);
+    assert!(matches!(
+        logic.ecrecover_batch(1, records_ptr, 1),
+        Err(near_vm_errors::VMLogicError::HostError(HostError::ECRecoverError { .. }))
+    ));
+
+    record[96] = 0;
+    record[97] = 2;
+    let records_ptr = logic.internal_mem_write(&record);
+    assert!(matches!(
+        logic.ecrecover_batch(1, records_ptr, 1),
+        Err(near_vm_errors::VMLogicError::HostError(HostError::ECRecoverError { .. }))
+    ));
+
+    assert_eq!(
+        logic.ecrecover_batch(u64::MAX, records_ptr, 1),
+        Err(HostError::MemoryAccessViolation.into())
+    );
+}
+
 #[test]
 fn test_hash256_register() {
     let mut logic_builder = VMLogicBuilder::default();
@@ -625,14 +786,12 @@ fn test_hash256_register() {
     logic.wrapped_internal_write_register(1, data).unwrap();
 
     logic.sha256(u64::MAX, 1, 0).unwrap();
-    let mut res = [0u8; 32];
-    logic.read_register(0, (&mut res[..]).as_ptr() as _).unwrap();
-    assert_eq!(
-        res,
-        [
+    logic.assert_read_register(
+        &[
             18, 176, 115, 156, 45, 100, 241, 132, 180, 134, 77, 42, 105, 111, 199, 127, 118, 112,
             92, 255, 88, 43, 83, 147, 122, 55, 26, 36, 42, 156, 160, 158,
-        ]
+        ],
+        0,
     );
 
     let len = data.len() as u64;
@@ -658,40 +817,37 @@ fn test_key_length_limit() {
     logic_builder.config.limit_config.max_length_storage_key = limit;
     let mut logic = logic_builder.build(get_context(vec![], false));
     // Under the limit. Valid calls.
+    let key_ptr = logic.internal_mem_write(&key);
+    let val_ptr = logic.internal_mem_write(val);
     logic
-        .storage_has_key(key.len() as _, key.as_ptr() as _)
+        .storage_has_key(key.len() as _, key_ptr)
         .expect("storage_has_key: key length is under the limit");
     logic
-        .storage_write(key.len() as _, key.as_ptr() as _, val.len() as _, val.as_ptr() as _, 0)
+        .storage_write(key.len() as _, key_ptr, val.len() as _, val_ptr, 0)
         .expect("storage_read: key length is under the limit");
     logic
-        .storage_read(key.len() as _, key.as_ptr() as _, 0)
+        .storage_read(key.len() as _, key_ptr, 0)
         .expect("storage_read: key length is under the limit");
     logic
-        .storage_remove(key.len() as _, key.as_ptr() as _, 0)
+        .storage_remove(key.len() as _, key_ptr, 0)
         .expect("storage_remove: key length is under the limit");
     // Over the limit. Invalid calls.
     key.push(b'a');
+    let key_ptr = logic.internal_mem_write(&key);
     assert_eq!(
-        logic.storage_has_key(key.len() as _, key.as_ptr() as _),
+        logic.storage_has_key(key.len() as _, key_ptr),
         Err(HostError::KeyLengthExceeded { length: key.len() as _, limit }.into())
     );
     assert_eq!(
-        logic.storage_write(
-            key.len() as _,
-            key.as_ptr() as _,
-            val.len() as _,
-            val.as_ptr() as _,
-            0
-        ),
+        logic.storage_write(key.len() as _, key_ptr, val.len() as _, val_ptr, 0),
         Err(HostError::KeyLengthExceeded { length: key.len() as _, limit }.into())
     );
     assert_eq!(
-        logic.storage_read(key.len() as _, key.as_ptr() as _, 0),
+        logic.storage_read(key.len() as _, key_ptr, 0),
         Err(HostError::KeyLengthExceeded { length: key.len() as _, limit }.into())
     );
     assert_eq!(
-        logic.storage_remove(key.len() as _, key.as_ptr() as _, 0),
+        logic.storage_remove(key.len() as _, key_ptr, 0),
         Err(HostError::KeyLengthExceeded { length: key.len() as _, limit }.into())
     );
 }
@@ -703,18 +859,15 @@ fn test_value_length_limit() {
     logic_builder.config.limit_config.max_length_storage_value = val.len() as u64;
     let mut logic = logic_builder.build(get_context(vec![], false));
     let key = b"hello";
+    let key_ptr = logic.internal_mem_write(key);
+    let val_ptr = logic.internal_mem_write(&val);
     logic
-        .storage_write(key.len() as _, key.as_ptr() as _, val.len() as _, val.as_ptr() as _, 0)
+        .storage_write(key.len() as _, key_ptr, val.len() as _, val_ptr, 0)
         .expect("Value length is under the limit");
     val.push(b'a');
+    let val_ptr = logic.internal_mem_write(&val);
     assert_eq!(
-        logic.storage_write(
-            key.len() as _,
-            key.as_ptr() as _,
-            val.len() as _,
-            val.as_ptr() as _,
-            0
-        ),
+        logic.storage_write(key.len() as _, key_ptr, val.len() as _, val_ptr, 0),
         Err(HostError::ValueLengthExceeded {
             length: val.len() as u64,
             limit: logic_builder.config.limit_config.max_length_storage_value
@@ -730,13 +883,14 @@ fn test_num_promises() {
     logic_builder.config.limit_config.max_promises_per_function_call_action = num_promises;
     let mut logic = logic_builder.build(get_context(vec![], false));
     let account_id = b"alice";
+    let account_id_ptr = logic.internal_mem_write(account_id);
     for _ in 0..num_promises {
         logic
-            .promise_batch_create(account_id.len() as _, account_id.as_ptr() as _)
+            .promise_batch_create(account_id.len() as _, account_id_ptr)
             .expect("Number of promises is under the limit");
     }
     assert_eq!(
-        logic.promise_batch_create(account_id.len() as _, account_id.as_ptr() as _),
+        logic.promise_batch_create(account_id.len() as _, account_id_ptr),
         Err(HostError::NumberPromisesExceeded {
             number_of_promises: num_promises + 1,
             limit: logic_builder.config.limit_config.max_promises_per_function_call_action
@@ -752,18 +906,17 @@ fn test_num_joined_promises() {
     logic_builder.config.limit_config.max_number_input_data_dependencies = num_deps;
     let mut logic = logic_builder.build(get_context(vec![], false));
     let account_id = b"alice";
+    let account_id_ptr = logic.internal_mem_write(account_id);
     let promise_id = logic
-        .promise_batch_create(account_id.len() as _, account_id.as_ptr() as _)
+        .promise_batch_create(account_id.len() as _, account_id_ptr)
         .expect("Number of promises is under the limit");
     for num in 0..num_deps {
         let promises = vec![promise_id; num as usize];
-        logic
-            .promise_and(promises.as_ptr() as _, promises.len() as _)
-            .expect("Number of joined promises is under the limit");
+        promise_and(&mut logic, &promises).expect("Number of joined promises is under the limit");
     }
     let promises = vec![promise_id; (num_deps + 1) as usize];
     assert_eq!(
-        logic.promise_and(promises.as_ptr() as _, promises.len() as _),
+        promise_and(&mut logic, &promises),
         Err(HostError::NumberInputDataDependenciesExceeded {
             number_of_input_data_dependencies: promises.len() as u64,
             limit: logic_builder.config.limit_config.max_number_input_data_dependencies,
@@ -779,26 +932,24 @@ fn test_num_input_dependencies_recursive_join() {
     logic_builder.config.limit_config.max_number_input_data_dependencies = 1 << num_steps;
     let mut logic = logic_builder.build(get_context(vec![], false));
     let account_id = b"alice";
+    let account_id_ptr = logic.internal_mem_write(account_id);
     let original_promise_id = logic
-        .promise_batch_create(account_id.len() as _, account_id.as_ptr() as _)
+        .promise_batch_create(account_id.len() as _, account_id_ptr)
         .expect("Number of promises is under the limit");
     let mut promise_id = original_promise_id;
     for _ in 1..num_steps {
         let promises = vec![promise_id, promise_id];
-        promise_id = logic
-            .promise_and(promises.as_ptr() as _, promises.len() as _)
+        promise_id = promise_and(&mut logic, &promises)
             .expect("Number of joined promises is under the limit");
     }
     // The length of joined promises is exactly the limit (1024).
     let promises = vec![promise_id, promise_id];
-    logic
-        .promise_and(promises.as_ptr() as _, promises.len() as _)
-        .expect("Number of joined promises is under the limit");
+    promise_and(&mut logic, &promises).expect("Number of joined promises is under the limit");
 
     // The length of joined promises exceeding the limit by 1 (total 1025).
     let promises = vec![promise_id, promise_id, original_promise_id];
     assert_eq!(
-        logic.promise_and(promises.as_ptr() as _, promises.len() as _),
+        promise_and(&mut logic, &promises),
         Err(HostError::NumberInputDataDependenciesExceeded {
             number_of_input_data_dependencies: logic_builder
                 .config
@@ -817,12 +968,12 @@ fn test_return_value_limit() {
     let mut val = "a".repeat(1024).as_bytes().to_vec();
     logic_builder.config.limit_config.max_length_returned_data = val.len() as u64;
     let mut logic = logic_builder.build(get_context(vec![], false));
-    logic
-        .value_return(val.len() as _, val.as_ptr() as _)
-        .expect("Returned value length is under the limit");
+    let val_ptr = logic.internal_mem_write(&val);
+    logic.value_return(val.len() as _, val_ptr).expect("Returned value length is under the limit");
     val.push(b'a');
+    let val_ptr = logic.internal_mem_write(&val);
     assert_eq!(
-        logic.value_return(val.len() as _, val.as_ptr() as _),
+        logic.value_return(val.len() as _, val_ptr),
         Err(HostError::ReturnedValueLengthExceeded {
             length: val.len() as u64,
             limit: logic_builder.config.limit_config.max_length_returned_data
@@ -838,19 +989,18 @@ fn test_contract_size_limit() {
     logic_builder.config.limit_config.max_contract_size = code.len() as u64;
     let mut logic = logic_builder.build(get_context(vec![], false));
     let account_id = b"alice";
+    let account_id_ptr = logic.internal_mem_write(account_id);
     let promise_id = logic
-        .promise_batch_create(account_id.len() as _, account_id.as_ptr() as _)
+        .promise_batch_create(account_id.len() as _, account_id_ptr)
         .expect("Number of promises is under the limit");
+    let code_ptr = logic.internal_mem_write(&code);
     logic
-        .promise_batch_action_deploy_contract(promise_id, code.len() as u64, code.as_ptr() as _)
+        .promise_batch_action_deploy_contract(promise_id, code.len() as u64, code_ptr)
         .expect("The length of the contract code is under the limit");
     code.push(b'a');
+    let code_ptr = logic.internal_mem_write(&code);
     assert_eq!(
-        logic.promise_batch_action_deploy_contract(
-            promise_id,
-            code.len() as u64,
-            code.as_ptr() as _
-        ),
+        logic.promise_batch_action_deploy_contract(promise_id, code.len() as u64, code_ptr),
         Err(HostError::ContractSizeExceeded {
             size: code.len() as u64,
             limit: logic_builder.config.limit_config.max_contract_size
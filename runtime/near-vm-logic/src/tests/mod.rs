@@ -4,6 +4,8 @@ mod context;
 mod ed25519_verify;
 mod fixtures;
 mod gas_counter;
+#[cfg(feature = "protocol_feature_hash_batch")]
+mod hash_batch;
 mod helpers;
 mod iterators;
 mod miscs;
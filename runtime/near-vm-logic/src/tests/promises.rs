@@ -41,9 +41,8 @@ fn test_promise_results() {
     assert_eq!(logic.promise_result(1, 0), Ok(2), "Failed promise must return code 2");
     assert_eq!(logic.promise_result(2, 0), Ok(0), "Pending promise must return 3");
 
-    let buffer = [0u8; 4];
-    logic.read_register(0, buffer.as_ptr() as u64).unwrap();
-    assert_eq!(&buffer, b"test", "Only promise with result should write data into register");
+    // Only promise with result should write data into register.
+    logic.assert_read_register(b"test", 0);
 }
 
 #[test]
@@ -54,9 +53,8 @@ fn test_promise_batch_action_function_call() {
 
     promise_batch_action_function_call(&mut logic, 123, 0, 0)
         .expect_err("shouldn't accept not existent promise index");
-    let non_receipt = logic
-        .promise_and(index.to_le_bytes().as_ptr() as _, 1u64)
-        .expect("should create a non-receipt promise");
+    let non_receipt =
+        promise_and(&mut logic, &[index]).expect("should create a non-receipt promise");
     promise_batch_action_function_call(&mut logic, non_receipt, 0, 0)
         .expect_err("shouldn't accept non-receipt promise index");
 
@@ -87,9 +85,8 @@ fn test_promise_batch_action_create_account() {
     logic
         .promise_batch_action_create_account(123)
         .expect_err("shouldn't accept not existent promise index");
-    let non_receipt = logic
-        .promise_and(index.to_le_bytes().as_ptr() as _, 1u64)
-        .expect("should create a non-receipt promise");
+    let non_receipt =
+        promise_and(&mut logic, &[index]).expect("should create a non-receipt promise");
     logic
         .promise_batch_action_create_account(non_receipt)
         .expect_err("shouldn't accept non-receipt promise index");
@@ -122,19 +119,19 @@ fn test_promise_batch_action_deploy_contract() {
     let mut logic = logic_builder.build(get_context(vec![], false));
     let index = promise_create(&mut logic, b"rick.test", 0, 0).expect("should create a promise");
     let code = b"sample";
+    let code_ptr = logic.internal_mem_write(code);
 
     logic
-        .promise_batch_action_deploy_contract(123, code.len() as u64, code.as_ptr() as _)
+        .promise_batch_action_deploy_contract(123, code.len() as u64, code_ptr)
         .expect_err("shouldn't accept not existent promise index");
-    let non_receipt = logic
-        .promise_and(index.to_le_bytes().as_ptr() as _, 1u64)
-        .expect("should create a non-receipt promise");
+    let non_receipt =
+        promise_and(&mut logic, &[index]).expect("should create a non-receipt promise");
     logic
-        .promise_batch_action_deploy_contract(non_receipt, code.len() as u64, code.as_ptr() as _)
+        .promise_batch_action_deploy_contract(non_receipt, code.len() as u64, code_ptr)
         .expect_err("shouldn't accept non-receipt promise index");
 
     logic
-        .promise_batch_action_deploy_contract(index, code.len() as u64, code.as_ptr() as _)
+        .promise_batch_action_deploy_contract(index, code.len() as u64, code_ptr)
         .expect("should add an action to deploy contract");
     assert_eq!(logic.used_gas().unwrap(), 5255774958146);
     let expected = serde_json::json!(
@@ -171,22 +168,21 @@ fn test_promise_batch_action_transfer() {
     let mut logic = logic_builder.build(context);
     let index = promise_create(&mut logic, b"rick.test", 0, 0).expect("should create a promise");
 
+    let amount_ptr = logic.internal_mem_write(&110u128.to_le_bytes());
     logic
-        .promise_batch_action_transfer(123, 110u128.to_le_bytes().as_ptr() as _)
+        .promise_batch_action_transfer(123, amount_ptr)
         .expect_err("shouldn't accept not existent promise index");
-    let non_receipt = logic
-        .promise_and(index.to_le_bytes().as_ptr() as _, 1u64)
-        .expect("should create a non-receipt promise");
+    let non_receipt =
+        promise_and(&mut logic, &[index]).expect("should create a non-receipt promise");
     logic
-        .promise_batch_action_transfer(non_receipt, 110u128.to_le_bytes().as_ptr() as _)
+        .promise_batch_action_transfer(non_receipt, amount_ptr)
         .expect_err("shouldn't accept non-receipt promise index");
 
     logic
-        .promise_batch_action_transfer(index, 110u128.to_le_bytes().as_ptr() as _)
+        .promise_batch_action_transfer(index, amount_ptr)
         .expect("should add an action to transfer money");
-    logic
-        .promise_batch_action_transfer(index, 1u128.to_le_bytes().as_ptr() as _)
-        .expect_err("not enough money");
+    let amount_ptr = logic.internal_mem_write(&1u128.to_le_bytes());
+    logic.promise_batch_action_transfer(index, amount_ptr).expect_err("not enough money");
     assert_eq!(logic.used_gas().unwrap(), 5349703444787);
     let expected = serde_json::json!(
     [
@@ -226,34 +222,20 @@ fn test_promise_batch_action_stake() {
         .unwrap()
         .try_to_vec()
         .unwrap();
+    let key_ptr = logic.internal_mem_write(&key);
+    let amount_ptr = logic.internal_mem_write(&110u128.to_le_bytes());
 
     logic
-        .promise_batch_action_stake(
-            123,
-            110u128.to_le_bytes().as_ptr() as _,
-            key.len() as u64,
-            key.as_ptr() as _,
-        )
+        .promise_batch_action_stake(123, amount_ptr, key.len() as u64, key_ptr)
         .expect_err("shouldn't accept not existent promise index");
-    let non_receipt = logic
-        .promise_and(index.to_le_bytes().as_ptr() as _, 1u64)
-        .expect("should create a non-receipt promise");
+    let non_receipt =
+        promise_and(&mut logic, &[index]).expect("should create a non-receipt promise");
     logic
-        .promise_batch_action_stake(
-            non_receipt,
-            110u128.to_le_bytes().as_ptr() as _,
-            key.len() as u64,
-            key.as_ptr() as _,
-        )
+        .promise_batch_action_stake(non_receipt, amount_ptr, key.len() as u64, key_ptr)
         .expect_err("shouldn't accept non-receipt promise index");
 
     logic
-        .promise_batch_action_stake(
-            index,
-            110u128.to_le_bytes().as_ptr() as _,
-            key.len() as u64,
-            key.as_ptr() as _,
-        )
+        .promise_batch_action_stake(index, amount_ptr, key.len() as u64, key_ptr)
         .expect("should add an action to stake");
     assert_eq!(logic.used_gas().unwrap(), 5138414976215);
     let expected = serde_json::json!([
@@ -310,9 +292,8 @@ fn test_promise_batch_action_add_key_with_function_call() {
         method_names,
     )
     .expect_err("shouldn't accept non-existent promise index");
-    let non_receipt = logic
-        .promise_and(index.to_le_bytes().as_ptr() as _, 1u64)
-        .expect("should create a non-receipt promise");
+    let non_receipt =
+        promise_and(&mut logic, &[index]).expect("should create a non-receipt promise");
     promise_batch_action_add_key_with_function_call(
         &mut logic,
         non_receipt,
@@ -381,19 +362,19 @@ fn test_promise_batch_then() {
 
     let account_id = b"rick.test";
     let index = promise_create(&mut logic, account_id, 0, 0).expect("should create a promise");
+    let account_id_ptr = logic.internal_mem_write(account_id);
 
     logic
-        .promise_batch_then(123, account_id.len() as u64, account_id.as_ptr() as _)
+        .promise_batch_then(123, account_id.len() as u64, account_id_ptr)
         .expect_err("shouldn't accept non-existent promise index");
-    let non_receipt = logic
-        .promise_and(index.to_le_bytes().as_ptr() as _, 1u64)
-        .expect("should create a non-receipt promise");
+    let non_receipt =
+        promise_and(&mut logic, &[index]).expect("should create a non-receipt promise");
     logic
-        .promise_batch_then(non_receipt, account_id.len() as u64, account_id.as_ptr() as _)
+        .promise_batch_then(non_receipt, account_id.len() as u64, account_id_ptr)
         .expect("should accept non-receipt promise index");
 
     logic
-        .promise_batch_then(index, account_id.len() as u64, account_id.as_ptr() as _)
+        .promise_batch_then(index, account_id.len() as u64, account_id_ptr)
         .expect("promise batch should run ok");
     assert_eq!(logic.used_gas().unwrap(), 24124999601771);
     let expected = serde_json::json!([
@@ -1,3 +1,4 @@
+use crate::mocks::mock_memory::MockedMemory;
 use crate::tests::fixtures::get_context;
 use crate::tests::vm_logic_builder::VMLogicBuilder;
 use crate::VMConfig;
@@ -10,9 +11,7 @@ fn test_one_register() {
 
     logic.wrapped_internal_write_register(0, &[0, 1, 2]).unwrap();
     assert_eq!(logic.register_len(0).unwrap(), 3u64);
-    let buffer = [0u8; 3];
-    logic.read_register(0, buffer.as_ptr() as u64).unwrap();
-    assert_eq!(buffer, [0u8, 1, 2]);
+    logic.assert_read_register(&[0u8, 1, 2], 0);
 }
 
 #[test]
@@ -21,13 +20,30 @@ fn test_non_existent_register() {
     let mut logic = logic_builder.build(get_context(vec![], false));
 
     assert_eq!(logic.register_len(0), Ok(u64::MAX) as Result<u64, VMLogicError>);
-    let buffer = [0u8; 3];
+    let buffer = logic.internal_mem_write(&[0u8; 3]);
     assert_eq!(
-        logic.read_register(0, buffer.as_ptr() as u64),
+        logic.read_register(0, buffer),
         Err(HostError::InvalidRegisterId { register_id: 0 }.into())
     );
 }
 
+#[test]
+fn test_read_register_out_of_bounds() {
+    let mut logic_builder = VMLogicBuilder::default();
+    let mut logic = logic_builder.build(get_context(vec![], false));
+
+    logic.wrapped_internal_write_register(0, &[0, 1, 2]).unwrap();
+    assert_eq!(
+        logic.read_register(0, MockedMemory::DEFAULT_SIZE - 2),
+        Err(HostError::MemoryAccessViolation.into())
+    );
+    assert_eq!(logic.read_register(0, u64::MAX), Err(HostError::MemoryAccessViolation.into()));
+
+    let ptr = MockedMemory::DEFAULT_SIZE - 3;
+    logic.read_register(0, ptr).unwrap();
+    assert_eq!(logic.internal_mem_read(ptr, 3), [0, 1, 2]);
+}
+
 #[test]
 fn test_many_registers() {
     let mut logic_builder = VMLogicBuilder::default();
@@ -38,9 +54,7 @@ fn test_many_registers() {
         let value = (i * 10).to_le_bytes();
         logic.wrapped_internal_write_register(i, &value).unwrap();
 
-        let buffer = [0u8; std::mem::size_of::<u64>()];
-        logic.read_register(i, buffer.as_ptr() as u64).unwrap();
-        assert_eq!(i * 10, u64::from_le_bytes(buffer));
+        logic.assert_read_register(&value, i);
     }
 
     // One more register hits the boundary check.
@@ -91,3 +105,25 @@ fn test_register_is_not_used() {
     let mut logic = logic_builder.build(get_context(vec![], false));
     assert_eq!(logic.register_len(0), Ok(u64::MAX));
 }
+
+#[test]
+#[cfg(feature = "protocol_feature_register_introspection")]
+fn test_register_introspection() {
+    let mut logic_builder = VMLogicBuilder::default();
+    let max_registers = logic_builder.config.limit_config.max_number_registers;
+    let mut logic = logic_builder.build(get_context(vec![], false));
+
+    assert_eq!(logic.register_count(), Ok(0));
+    assert_eq!(logic.register_len_total(), Ok(0));
+    assert_eq!(logic.max_number_registers(), Ok(max_registers));
+
+    logic.wrapped_internal_write_register(0, &[0, 1, 2]).unwrap();
+    logic.wrapped_internal_write_register(7, &[0; 10]).unwrap();
+    assert_eq!(logic.register_count(), Ok(2));
+    assert_eq!(logic.register_len_total(), Ok(13));
+
+    // Overwriting a register doesn't use another one.
+    logic.wrapped_internal_write_register(0, &[]).unwrap();
+    assert_eq!(logic.register_count(), Ok(2));
+    assert_eq!(logic.register_len_total(), Ok(10));
+}
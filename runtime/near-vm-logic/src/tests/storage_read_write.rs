@@ -32,9 +32,7 @@ fn test_storage_read_with_register() {
     logic.wrapped_internal_write_register(1, key).unwrap();
 
     logic.storage_read(u64::MAX, 1 as _, 0).expect("storage read ok");
-    let res = [0u8; 3];
-    logic.read_register(0, res.as_ptr() as _).unwrap();
-    assert_eq!(&res, b"bar");
+    logic.assert_read_register(b"bar", 0);
 }
 
 #[test]
@@ -45,16 +43,16 @@ fn test_storage_remove_with_register() {
     let val: &[u8] = b"bar";
 
     let mut logic = logic_builder.build(get_context(vec![], false));
+    let key_ptr = logic.internal_mem_write(key);
+    let val_ptr = logic.internal_mem_write(val);
     logic
-        .storage_write(key.len() as _, key.as_ptr() as _, val.len() as _, val.as_ptr() as _, 0)
+        .storage_write(key.len() as _, key_ptr, val.len() as _, val_ptr, 0)
         .expect("storage write ok");
 
     logic.wrapped_internal_write_register(1, key).unwrap();
 
     logic.storage_remove(u64::MAX, 1 as _, 0).expect("storage remove ok");
-    let res = [0u8; 3];
-    logic.read_register(0, res.as_ptr() as _).unwrap();
-    assert_eq!(&res, b"bar");
+    logic.assert_read_register(b"bar", 0);
 }
 
 #[test]
@@ -9,8 +9,10 @@ fn test_storage_write_counter() {
     let key = b"foo";
     let val = b"bar";
 
+    let key_ptr = logic.internal_mem_write(key);
+    let val_ptr = logic.internal_mem_write(val);
     logic
-        .storage_write(key.len() as _, key.as_ptr() as _, val.len() as _, val.as_ptr() as _, 0)
+        .storage_write(key.len() as _, key_ptr, val.len() as _, val_ptr, 0)
         .expect("storage write ok");
 
     let cost_expected = (data_record_cost as usize + key.len() + val.len()) as u64;
@@ -20,8 +22,10 @@ fn test_storage_write_counter() {
     let key = b"foo";
     let val = b"bar";
 
+    let key_ptr = logic.internal_mem_write(key);
+    let val_ptr = logic.internal_mem_write(val);
     logic
-        .storage_write(key.len() as _, key.as_ptr() as _, val.len() as _, val.as_ptr() as _, 0)
+        .storage_write(key.len() as _, key_ptr, val.len() as _, val_ptr, 0)
         .expect("storage write ok");
 
     assert_eq!(logic.storage_usage().unwrap(), cost_expected);
@@ -35,11 +39,13 @@ fn test_storage_remove() {
     let key = b"foo";
     let val = b"bar";
 
+    let key_ptr = logic.internal_mem_write(key);
+    let val_ptr = logic.internal_mem_write(val);
     logic
-        .storage_write(key.len() as _, key.as_ptr() as _, val.len() as _, val.as_ptr() as _, 0)
+        .storage_write(key.len() as _, key_ptr, val.len() as _, val_ptr, 0)
         .expect("storage write ok");
 
-    logic.storage_remove(key.len() as _, key.as_ptr() as _, 0).expect("storage remove ok");
+    logic.storage_remove(key.len() as _, key_ptr, 0).expect("storage remove ok");
 
     assert_eq!(logic.storage_usage().unwrap(), 0u64);
 }
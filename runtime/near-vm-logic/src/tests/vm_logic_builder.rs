@@ -2,9 +2,10 @@ use crate::mocks::mock_external::MockedExternal;
 use crate::mocks::mock_memory::MockedMemory;
 use crate::types::PromiseResult;
 use crate::VMContext;
-use crate::{VMConfig, VMLogic};
+use crate::{VMConfig, VMLogic, VMOutcome};
 use near_primitives_core::runtime::fees::RuntimeFeesConfig;
 use near_primitives_core::types::ProtocolVersion;
+use std::ops::{Deref, DerefMut};
 
 pub(crate) const LATEST_PROTOCOL_VERSION: ProtocolVersion = ProtocolVersion::MAX;
 
@@ -31,8 +32,11 @@ impl Default for VMLogicBuilder {
 }
 
 impl VMLogicBuilder {
-    pub fn build(&mut self, context: VMContext) -> VMLogic<'_> {
-        VMLogic::new_with_protocol_version(
+    /// Creates the logic for one call.  The next call queued in `ext`, if any,
+    /// overrides the values of `context`, see [`MockedExternal::push_call`].
+    pub fn build(&mut self, mut context: VMContext) -> TestVMLogic<'_> {
+        self.ext.apply_next_call(&mut context);
+        let logic = VMLogic::new_with_protocol_version(
             &mut self.ext,
             context,
             &self.config,
@@ -40,7 +44,8 @@ impl VMLogicBuilder {
             &self.promise_results,
             &mut self.memory,
             self.current_protocol_version,
-        )
+        );
+        TestVMLogic { logic, mem_write_offset: 0 }
     }
     pub fn free() -> Self {
         VMLogicBuilder {
@@ -53,3 +58,61 @@ impl VMLogicBuilder {
         }
     }
 }
+
+/// `VMLogic` with helpers to access the guest memory, which tests use to pass
+/// arguments to host functions and to inspect their results.
+pub struct TestVMLogic<'a> {
+    logic: VMLogic<'a>,
+    /// Offset in guest memory where `internal_mem_write` puts the next data.
+    mem_write_offset: u64,
+}
+
+impl<'a> Deref for TestVMLogic<'a> {
+    type Target = VMLogic<'a>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.logic
+    }
+}
+
+impl DerefMut for TestVMLogic<'_> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.logic
+    }
+}
+
+impl TestVMLogic<'_> {
+    pub fn compute_outcome_and_distribute_gas(self) -> VMOutcome {
+        self.logic.compute_outcome_and_distribute_gas()
+    }
+
+    /// Writes `data` to guest memory after everything written by previous
+    /// calls and returns its offset. Gas is not charged.
+    pub fn internal_mem_write(&mut self, data: &[u8]) -> u64 {
+        let ptr = self.mem_write_offset;
+        self.internal_mem_write_at(ptr, data);
+        self.mem_write_offset += data.len() as u64;
+        ptr
+    }
+
+    /// Writes `data` to guest memory at `ptr`. Gas is not charged.
+    pub fn internal_mem_write_at(&mut self, ptr: u64, data: &[u8]) {
+        self.logic.memory().write_memory(ptr, data);
+    }
+
+    /// Reads `len` bytes of guest memory at `ptr`. Gas is not charged.
+    pub fn internal_mem_read(&mut self, ptr: u64, len: u64) -> Vec<u8> {
+        let mut buf = vec![0; len as usize];
+        self.logic.memory().read_memory(ptr, &mut buf);
+        buf
+    }
+
+    /// Copies the register into freshly written guest memory with
+    /// `read_register` and checks that its content is `want`.
+    #[track_caller]
+    pub fn assert_read_register(&mut self, want: &[u8], register_id: u64) {
+        let ptr = self.internal_mem_write(&vec![0; want.len()]);
+        self.read_register(register_id, ptr).unwrap();
+        assert_eq!(self.internal_mem_read(ptr, want.len() as u64), want);
+    }
+}
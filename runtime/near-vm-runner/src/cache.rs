@@ -50,7 +50,9 @@ pub fn get_contract_cache_key(
         code_hash: *code.hash(),
         vm_config_non_crypto_hash: config.non_crypto_hash(),
         vm_kind,
-        vm_hash: vm_hash(vm_kind),
+        // Contracts are instrumented differently with profiling, don't mix
+        // up their compiled code with the one of regular builds.
+        vm_hash: vm_hash(vm_kind) ^ u64::from(cfg!(feature = "profiling")),
     };
     CryptoHash::hash_borsh(key)
 }
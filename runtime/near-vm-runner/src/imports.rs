@@ -59,7 +59,7 @@ macro_rules! imports {
                 $(#[cfg(feature = $feature_name)])*
                 if true
                     $(&& near_primitives::checked_feature!($feature_name, $feature, $protocol_version))*
-                    $(&& near_primitives::checked_feature!("stable", $stable_feature, $protocol_version))?
+                    $(&& near_primitives::version::ProtocolFeature::$stable_feature.enabled($protocol_version))?
                 {
                     $M!($func < [ $( $arg_name : $arg_type ),* ] -> [ $( $returns ),* ] >);
                 }
@@ -74,6 +74,9 @@ imports! {
     // #############
     read_register<[register_id: u64, ptr: u64] -> []>,
     register_len<[register_id: u64] -> [u64]>,
+    #["protocol_feature_register_introspection", RegisterIntrospection] register_count<[] -> [u64]>,
+    #["protocol_feature_register_introspection", RegisterIntrospection] register_len_total<[] -> [u64]>,
+    #["protocol_feature_register_introspection", RegisterIntrospection] max_number_registers<[] -> [u64]>,
     write_register<[register_id: u64, data_len: u64, data_ptr: u64] -> []>,
     // ###############
     // # Context API #
@@ -95,6 +98,8 @@ imports! {
     attached_deposit<[balance_ptr: u64] -> []>,
     prepaid_gas<[] -> [u64]>,
     used_gas<[] -> [u64]>,
+    #["protocol_feature_block_gas_info", BlockGasInfo] block_gas_price<[balance_ptr: u64] -> []>,
+    #["protocol_feature_block_gas_info", BlockGasInfo] block_gas_limit<[] -> [u64]>,
     // ############
     // # Math API #
     // ############
@@ -102,6 +107,8 @@ imports! {
     sha256<[value_len: u64, value_ptr: u64, register_id: u64] -> []>,
     keccak256<[value_len: u64, value_ptr: u64, register_id: u64] -> []>,
     keccak512<[value_len: u64, value_ptr: u64, register_id: u64] -> []>,
+    #["protocol_feature_hash_batch", HashBatch] sha256_batch<[inputs_len: u64, inputs_ptr: u64, register_id: u64] -> []>,
+    #["protocol_feature_hash_batch", HashBatch] keccak256_batch<[inputs_len: u64, inputs_ptr: u64, register_id: u64] -> []>,
     #["protocol_feature_ed25519_verify", Ed25519Verify] ed25519_verify<[
         sig_len: u64,
         sig_ptr: u64,
@@ -112,6 +119,7 @@ imports! {
     ] -> [u64]>,
     #[MathExtension] ripemd160<[value_len: u64, value_ptr: u64, register_id: u64] -> []>,
     #[MathExtension] ecrecover<[hash_len: u64, hash_ptr: u64, sign_len: u64, sig_ptr: u64, v: u64, malleability_flag: u64, register_id: u64] -> [u64]>,
+    #["protocol_feature_ecrecover_batch", EcrecoverBatch] ecrecover_batch<[inputs_len: u64, inputs_ptr: u64, register_id: u64] -> [u64]>,
     // #####################
     // # Miscellaneous API #
     // #####################
@@ -119,6 +127,16 @@ imports! {
     panic<[] -> []>,
     panic_utf8<[len: u64, ptr: u64] -> []>,
     log_utf8<[len: u64, ptr: u64] -> []>,
+    #["protocol_feature_structured_events", StructuredEvents] emit_event<[
+        standard_len: u64,
+        standard_ptr: u64,
+        version_len: u64,
+        version_ptr: u64,
+        event_len: u64,
+        event_ptr: u64,
+        data_len: u64,
+        data_ptr: u64
+    ] -> []>,
     log_utf16<[len: u64, ptr: u64] -> []>,
     abort<[msg_ptr: u32, filename_ptr: u32, line: u32, col: u32] -> []>,
     // ################
@@ -222,8 +240,18 @@ imports! {
     storage_iter_prefix<[prefix_len: u64, prefix_ptr: u64] -> [u64]>,
     storage_iter_range<[start_len: u64, start_ptr: u64, end_len: u64, end_ptr: u64] -> [u64]>,
     storage_iter_next<[iterator_id: u64, key_register_id: u64, value_register_id: u64] -> [u64]>,
+    #["protocol_feature_storage_iter_prefix_page", StorageIterPrefixPage] storage_iter_prefix_page<[
+        prefix_len: u64,
+        prefix_ptr: u64,
+        start_key_len: u64,
+        start_key_ptr: u64,
+        limit: u64,
+        register_id: u64
+    ] -> [u64]>,
     // Function for the injected gas counter. Automatically called by the gas meter.
     gas<[gas_amount: u32] -> []>,
+    // Replaces `gas` in contracts instrumented for profiling.
+    ##["profiling"] profiling_gas<[gas_amount: u32, function_index: u32] -> []>,
     // ###############
     // # Validator API #
     // ###############
@@ -274,13 +302,18 @@ pub(crate) mod wasmer {
             ) => {
                 #[allow(unused_parens)]
                 fn $func( ctx: &mut wasmer_runtime::Ctx, $( $arg_name: $arg_type ),* ) -> Result<($( $returns ),*), VMLogicError> {
-                    const IS_GAS: bool = str_eq(stringify!($func), "gas");
+                    const IS_GAS: bool = str_eq(stringify!($func), "gas")
+                        || str_eq(stringify!($func), "profiling_gas");
                     let _span = if IS_GAS {
                         None
                     } else {
                         Some(tracing::trace_span!(target: "host-function", stringify!($func)).entered())
                     };
                     let logic: &mut VMLogic<'_> = unsafe { &mut *(ctx.data as *mut VMLogic<'_>) };
+                    #[cfg(feature = "profiling")]
+                    if !IS_GAS {
+                        logic.record_host_call();
+                    }
                     logic.$func( $( $arg_name, )* )
                 }
 
@@ -371,7 +404,8 @@ pub(crate) mod wasmer2 {
                     extern "C" fn $func(env: *mut VMLogic<'_>, $( $arg_name: $arg_type ),* )
                     -> Ret {
                         let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
-                            const IS_GAS: bool = str_eq(stringify!($func), "gas");
+                            const IS_GAS: bool = str_eq(stringify!($func), "gas")
+                                || str_eq(stringify!($func), "profiling_gas");
                             let _span = if IS_GAS {
                                 None
                             } else {
@@ -385,6 +419,10 @@ pub(crate) mod wasmer2 {
                             // lifetime and so it is safe to dereference the `env` pointer which is
                             // known to be derived from a valid `&'vmlogic mut VMLogic<'_>` in the
                             // first place.
+                            #[cfg(feature = "profiling")]
+                            if !IS_GAS {
+                                unsafe { (*env).record_host_call() }
+                            }
                             unsafe { (*env).$func( $( $arg_name, )* ) }
                         }));
                         // We want to ensure that the only kind of error that host function calls
@@ -486,7 +524,8 @@ pub(crate) mod wasmtime {
             ) => {
                 #[allow(unused_parens)]
                 fn $func(caller: wasmtime::Caller<'_, ()>, $( $arg_name: $arg_type ),* ) -> Result<($( $returns ),*), wasmtime::Trap> {
-                    const IS_GAS: bool = str_eq(stringify!($func), "gas");
+                    const IS_GAS: bool = str_eq(stringify!($func), "gas")
+                        || str_eq(stringify!($func), "profiling_gas");
                     let _span = if IS_GAS {
                         None
                     } else {
@@ -505,6 +544,10 @@ pub(crate) mod wasmtime {
                         crate::wasmtime_runner::CALLER.with(|runner_caller| *runner_caller.borrow_mut() = std::mem::transmute(caller));
                     }
                     let logic: &mut VMLogic<'_> = unsafe { &mut *(data as *mut VMLogic<'_>) };
+                    #[cfg(feature = "profiling")]
+                    if !IS_GAS {
+                        logic.record_host_call();
+                    }
                     match logic.$func( $( $arg_name as $arg_type, )* ) {
                         Ok(result) => Ok(result as ($( $returns ),* ) ),
                         Err(err) => {
@@ -15,5 +15,7 @@
 //! overall instrumentation story.
 
 pub(crate) mod gas;
+#[cfg(feature = "profiling")]
+pub(crate) mod profiling;
 pub(crate) mod rules;
 pub(crate) mod stack_height;
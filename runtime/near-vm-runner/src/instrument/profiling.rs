@@ -0,0 +1,136 @@
+//! Instrumentation attributing the instructions counted by the gas meter to
+//! the WASM function they were executed in.
+//!
+//! The `gas` import injected by [`super::gas::inject_gas_counter`] is turned
+//! into `profiling_gas`, which additionally takes the index of the calling
+//! function.  After every call of another function of the contract, the
+//! caller reports 0 instructions, so that host functions called afterwards are
+//! attributed to it again.
+
+use near_vm_logic::FunctionProfile;
+use parity_wasm::elements::{self, FunctionType, Instruction, Type, ValueType};
+
+/// Replaces the gas meter injected last by one which reports the index of the
+/// function in the original module, i.e. without the injected import.
+///
+/// Must be applied right after gas metering, before any other instrumentation
+/// adds imports.
+pub(crate) fn inject_profiling(
+    mut module: elements::Module,
+) -> Result<elements::Module, elements::Module> {
+    let function_imports = module.import_count(elements::ImportCountType::Function) as u32;
+    let gas_func = match function_imports.checked_sub(1) {
+        Some(gas_func) => gas_func,
+        None => return Err(module),
+    };
+
+    let type_section = match module.type_section_mut() {
+        Some(section) => section,
+        None => return Err(module),
+    };
+    let profiling_sig = type_section.types().len() as u32;
+    type_section
+        .types_mut()
+        .push(Type::Function(FunctionType::new(vec![ValueType::I32, ValueType::I32], vec![])));
+
+    let gas_import = module
+        .import_section_mut()
+        .and_then(|section| section.entries_mut().iter_mut().rev().find(is_function_import));
+    match gas_import {
+        Some(import) if import.field() == "gas" => {
+            *import.field_mut() = "profiling_gas".to_string();
+            *import.external_mut() = elements::External::Function(profiling_sig);
+        }
+        _ => return Err(module),
+    }
+
+    if let Some(code_section) = module.code_section_mut() {
+        for (index, func_body) in code_section.bodies_mut().iter_mut().enumerate() {
+            // Index of the function before the gas import was added.
+            let func = gas_func + index as u32;
+            let instructions = func_body.code_mut().elements_mut();
+            let mut profiled = Vec::with_capacity(instructions.len());
+            for instruction in instructions.drain(..) {
+                match instruction {
+                    Instruction::Call(callee) if callee == gas_func => {
+                        profiled.push(Instruction::I32Const(func as i32));
+                        profiled.push(instruction);
+                    }
+                    Instruction::Call(callee) if callee < gas_func => profiled.push(instruction),
+                    Instruction::Call(_) | Instruction::CallIndirect(..) => {
+                        profiled.push(instruction);
+                        profiled.push(Instruction::I32Const(0));
+                        profiled.push(Instruction::I32Const(func as i32));
+                        profiled.push(Instruction::Call(gas_func));
+                    }
+                    _ => profiled.push(instruction),
+                }
+            }
+            *instructions = profiled;
+        }
+    }
+    Ok(module)
+}
+
+fn is_function_import(import: &&mut elements::ImportEntry) -> bool {
+    matches!(import.external(), elements::External::Function(_))
+}
+
+/// Sets the function names of `profile` from the name section of `code`.
+pub(crate) fn set_function_names(profile: &mut FunctionProfile, code: &[u8]) {
+    let module = match parity_wasm::deserialize_buffer::<elements::Module>(code) {
+        Ok(module) => module,
+        Err(_) => return,
+    };
+    // Names are best effort, a malformed name section is ignored.
+    let module = module.parse_names().unwrap_or_else(|(_, module)| module);
+    let names = module.names_section().and_then(|section| section.functions());
+    profile.set_names(|index| names.and_then(|names| names.names().get(index).cloned()));
+}
+
+#[cfg(test)]
+mod tests {
+    use parity_wasm::elements::{self, Instruction::*};
+
+    #[test]
+    fn test_inject_profiling() {
+        let code = wat::parse_str(
+            r#"
+            (module
+              (import "env" "input" (func $input (param i64)))
+              (func $main
+                i64.const 0
+                call $input
+                call $helper)
+              (func $helper
+                nop))
+            "#,
+        )
+        .unwrap();
+        let module = parity_wasm::deserialize_buffer(&code).unwrap();
+        let rules = crate::instrument::rules::Set::new(1, Default::default());
+        let module = crate::instrument::gas::inject_gas_counter(module, &rules, "env").unwrap();
+        let module = super::inject_profiling(module).unwrap();
+
+        let import = &module.import_section().unwrap().entries()[1];
+        assert_eq!((import.module(), import.field()), ("env", "profiling_gas"));
+        let bodies = module.code_section().unwrap().bodies();
+        assert_eq!(
+            bodies[0].code().elements(),
+            &[
+                I32Const(3),
+                I32Const(1),
+                Call(1),
+                I64Const(0),
+                Call(0),
+                Call(3),
+                I32Const(0),
+                I32Const(1),
+                Call(1),
+                End
+            ][..]
+        );
+        assert_eq!(bodies[1].code().elements(), &[I32Const(1), I32Const(2), Call(1), Nop, End][..]);
+        elements::serialize(module).unwrap();
+    }
+}
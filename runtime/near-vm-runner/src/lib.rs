@@ -7,6 +7,7 @@ mod instrument;
 #[cfg(all(feature = "wasmer0_vm", target_arch = "x86_64"))]
 mod memory;
 pub mod prepare;
+mod replay;
 mod runner;
 #[cfg(test)]
 mod tests;
@@ -21,12 +22,16 @@ mod wasmtime_runner;
 pub use near_vm_logic::with_ext_cost_counter;
 
 pub use cache::{get_contract_cache_key, precompile_contract, MockCompiledContractCache};
-pub use runner::{run, VM};
+pub use replay::{replay_function_call, RecordedExtState};
+pub use runner::{
+    precompile_contracts, run, run_view, PrecompileStats, ViewBudget, ViewOutcome, VM,
+};
 
 /// This is public for internal experimentation use only, and should otherwise be considered an
 /// implementation detail of `near-vm-runner`.
 #[doc(hidden)]
 pub mod internal {
+    pub use crate::runner::run_with_vm_kind;
     pub use crate::vm_kind::VMKind;
     pub use wasmparser;
 }
@@ -23,6 +23,10 @@ impl WasmerMemory {
     pub fn clone(&self) -> Memory {
         self.0.clone()
     }
+
+    pub fn size_pages(&self) -> u32 {
+        self.0.size().0
+    }
 }
 
 impl MemoryLike for WasmerMemory {
@@ -21,38 +21,77 @@ pub(crate) const WASM_FEATURES: wasmparser::WasmFeatures = wasmparser::WasmFeatu
     memory64: false,
 };
 
+/// Sizes of the entities of a module that are subject to limits, see
+/// [`validate_contract`].  A counter that overflows is `None`.
+struct ModuleSizes {
+    /// Number of functions, defined or imported.
+    functions: Option<u64>,
+    /// Number of locals declared by all functions.
+    locals: Option<u64>,
+    /// Number of globals, defined or imported.
+    globals: Option<u64>,
+    /// Sum of the initial sizes of all tables, defined or imported.
+    table_elements: Option<u64>,
+    /// Sum of the sizes of all custom sections, including their names.
+    custom_sections_size: Option<u64>,
+}
+
+fn checked_add(counter: &mut Option<u64>, value: u64) {
+    *counter = counter.and_then(|c| c.checked_add(value));
+}
+
 /// Decode and validate the provided WebAssembly code with the `wasmparser` crate.
 ///
-/// This function will return the number of functions defined globally in the provided WebAssembly
-/// module as well as the number of locals declared by all functions. If either counter overflows,
-/// `None` is returned in its place.
-fn wasmparser_decode(
-    code: &[u8],
-) -> Result<(Option<u64>, Option<u64>), wasmparser::BinaryReaderError> {
+/// This function will return the sizes of the module that are limited by the
+/// `VMLimitConfig`, in particular the number of functions defined globally in
+/// the provided WebAssembly module and the number of locals declared by all
+/// functions.
+fn wasmparser_decode(code: &[u8]) -> Result<ModuleSizes, wasmparser::BinaryReaderError> {
     use wasmparser::{ImportSectionEntryType, ValidPayload};
     let mut validator = wasmparser::Validator::new();
     validator.wasm_features(WASM_FEATURES);
-    let mut function_count = Some(0u64);
-    let mut local_count = Some(0u64);
+    let mut sizes = ModuleSizes {
+        functions: Some(0),
+        locals: Some(0),
+        globals: Some(0),
+        table_elements: Some(0),
+        custom_sections_size: Some(0),
+    };
     for payload in wasmparser::Parser::new(0).parse_all(code) {
         let payload = payload?;
 
-        // The validator does not output `ValidPayload::Func` for imported functions.
-        if let wasmparser::Payload::ImportSection(ref import_section_reader) = payload {
-            let mut import_section_reader = import_section_reader.clone();
-            for _ in 0..import_section_reader.get_count() {
-                match import_section_reader.read()?.ty {
-                    ImportSectionEntryType::Function(_) => {
-                        function_count = function_count.and_then(|f| f.checked_add(1))
+        match payload {
+            // The validator does not output `ValidPayload::Func` for imported functions.
+            wasmparser::Payload::ImportSection(ref import_section_reader) => {
+                let mut import_section_reader = import_section_reader.clone();
+                for _ in 0..import_section_reader.get_count() {
+                    match import_section_reader.read()?.ty {
+                        ImportSectionEntryType::Function(_) => checked_add(&mut sizes.functions, 1),
+                        ImportSectionEntryType::Global(_) => checked_add(&mut sizes.globals, 1),
+                        ImportSectionEntryType::Table(table) => {
+                            checked_add(&mut sizes.table_elements, table.limits.initial.into())
+                        }
+                        ImportSectionEntryType::Memory(_)
+                        | ImportSectionEntryType::Event(_)
+                        | ImportSectionEntryType::Module(_)
+                        | ImportSectionEntryType::Instance(_) => {}
                     }
-                    ImportSectionEntryType::Table(_)
-                    | ImportSectionEntryType::Memory(_)
-                    | ImportSectionEntryType::Event(_)
-                    | ImportSectionEntryType::Global(_)
-                    | ImportSectionEntryType::Module(_)
-                    | ImportSectionEntryType::Instance(_) => {}
                 }
             }
+            wasmparser::Payload::GlobalSection(ref global_section_reader) => {
+                checked_add(&mut sizes.globals, global_section_reader.get_count().into())
+            }
+            wasmparser::Payload::TableSection(ref table_section_reader) => {
+                let mut table_section_reader = table_section_reader.clone();
+                for _ in 0..table_section_reader.get_count() {
+                    let table = table_section_reader.read()?;
+                    checked_add(&mut sizes.table_elements, table.limits.initial.into());
+                }
+            }
+            wasmparser::Payload::CustomSection { ref range, .. } => {
+                checked_add(&mut sizes.custom_sections_size, (range.end - range.start) as u64)
+            }
+            _ => {}
         }
 
         match validator.payload(&payload)? {
@@ -60,41 +99,79 @@ fn wasmparser_decode(
             ValidPayload::Submodule(_) => panic!("submodules are not reachable (not enabled)"),
             ValidPayload::Func(mut validator, body) => {
                 validator.validate(&body)?;
-                function_count = function_count.and_then(|f| f.checked_add(1));
+                checked_add(&mut sizes.functions, 1);
                 // Count the global number of local variables.
                 let mut local_reader = body.get_locals_reader()?;
                 for _ in 0..local_reader.get_count() {
                     let (count, _type) = local_reader.read()?;
-                    local_count = local_count.and_then(|l| l.checked_add(count.into()));
+                    checked_add(&mut sizes.locals, count.into());
                 }
             }
         }
     }
-    Ok((function_count, local_count))
+    Ok(sizes)
+}
+
+/// Checks that an optional limit is not exceeded by a size, where a size of
+/// `None` exceeds every limit.
+fn check_limit(
+    size: Option<u64>,
+    limit: Option<u64>,
+    err: PrepareError,
+) -> Result<(), PrepareError> {
+    match limit {
+        Some(limit) if size.map_or(true, |size| size > limit) => Err(err),
+        _ => Ok(()),
+    }
 }
 
 fn validate_contract(code: &[u8], config: &VMConfig) -> Result<(), PrepareError> {
-    let (function_count, local_count) = wasmparser_decode(code).map_err(|e| {
+    let sizes = wasmparser_decode(code).map_err(|e| {
         tracing::debug!(err=?e, "wasmparser failed decoding a contract");
         PrepareError::Deserialization
     })?;
+    let limits = &config.limit_config;
     // Verify the number of functions does not exceed the limit we imposed. Note that the ordering
     // of this check is important. In the past we first validated the entire module and only then
     // verified that the limit is not exceeded. While it would be more efficient to check for this
     // before validating the function bodies, it would change the results for malformed WebAssembly
     // modules.
-    if let Some(max_functions) = config.limit_config.max_functions_number_per_contract {
-        if function_count.ok_or(PrepareError::TooManyFunctions)? > max_functions {
-            return Err(PrepareError::TooManyFunctions);
-        }
-    }
-    // Similarly, do the same for the number of locals.
-    if let Some(max_locals) = config.limit_config.max_locals_per_contract {
-        if local_count.ok_or(PrepareError::TooManyLocals)? > max_locals {
-            return Err(PrepareError::TooManyLocals);
-        }
+    check_limit(
+        sizes.functions,
+        limits.max_functions_number_per_contract,
+        PrepareError::TooManyFunctions,
+    )?;
+    // Similarly, do the same for the number of locals and the other limits.
+    check_limit(sizes.locals, limits.max_locals_per_contract, PrepareError::TooManyLocals)?;
+    check_section_limits(&sizes, config)
+}
+
+fn check_section_limits(sizes: &ModuleSizes, config: &VMConfig) -> Result<(), PrepareError> {
+    let limits = &config.limit_config;
+    check_limit(sizes.globals, limits.max_globals_per_contract, PrepareError::TooManyGlobals)?;
+    check_limit(
+        sizes.table_elements,
+        limits.max_table_elements_per_contract,
+        PrepareError::TableTooLarge,
+    )?;
+    check_limit(
+        sizes.custom_sections_size,
+        limits.max_custom_sections_size_per_contract,
+        PrepareError::CustomSectionsTooLarge,
+    )
+}
+
+/// Checks the limits on the globals, tables and custom sections of a
+/// contract that is being deployed.
+///
+/// Unlike [`prepare_contract`], this accepts code that can't be decoded and
+/// ignores the other limits. Such contracts can still be deployed and only
+/// fail when they are called.
+pub fn check_contract_section_limits(code: &[u8], config: &VMConfig) -> Result<(), PrepareError> {
+    match wasmparser_decode(code) {
+        Ok(sizes) => check_section_limits(&sizes, config),
+        Err(_) => Ok(()),
     }
-    Ok(())
 }
 
 /// Loads the given module given in `original_code`, performs some checks on it and
@@ -106,6 +183,8 @@ fn validate_contract(code: &[u8], config: &VMConfig) -> Result<(), PrepareError>
 /// - imported memory (if any) doesn't reserve more memory than permitted by the `config`,
 /// - all imported functions from the external environment matches defined by `env` module,
 /// - functions number does not exceed limit specified in VMConfig,
+/// - neither do the numbers of locals and globals, the size of tables or the
+///   size of custom sections,
 ///
 /// The preprocessing includes injecting code for gas metering and metering the height of stack.
 pub fn prepare_contract(original_code: &[u8], config: &VMConfig) -> Result<Vec<u8>, PrepareError> {
@@ -120,6 +199,7 @@ pub fn prepare_contract(original_code: &[u8], config: &VMConfig) -> Result<Vec<u
             .standardize_mem()
             .ensure_no_internal_memory()?
             .inject_gas_metering()?
+            .inject_profiling()?
             .inject_stack_height_metering()?
             .scan_imports()?
             .into_wasm_code(),
@@ -188,6 +268,26 @@ impl<'a> ContractModule<'a> {
         Ok(Self { module, config })
     }
 
+    /// Makes the gas meter report the function the instructions belong to.
+    /// Done before stack height metering, so that it accounts for the values
+    /// this pushes on the stack.
+    #[cfg(feature = "profiling")]
+    fn inject_profiling(self) -> Result<Self, PrepareError> {
+        let Self { module, config } = self;
+        // Free config, no gas meter to extend.
+        if config.regular_op_cost == 0 {
+            return Ok(Self { module, config });
+        }
+        let module = crate::instrument::profiling::inject_profiling(module)
+            .map_err(|_| PrepareError::GasInstrumentation)?;
+        Ok(Self { module, config })
+    }
+
+    #[cfg(not(feature = "profiling"))]
+    fn inject_profiling(self) -> Result<Self, PrepareError> {
+        Ok(self)
+    }
+
     fn inject_stack_height_metering(self) -> Result<Self, PrepareError> {
         let Self { module, config } = self;
         let module = crate::instrument::stack_height::inject_limiter(
@@ -0,0 +1,140 @@
+use crate::runner::VMResult;
+use near_primitives::config::VMConfig;
+use near_primitives::contract::ContractCode;
+use near_primitives::hash::CryptoHash;
+use near_primitives::runtime::fees::RuntimeFeesConfig;
+use near_primitives::types::TrieNodesCount;
+use near_primitives::version::ProtocolVersion;
+use near_vm_logic::mocks::mock_external::MockedExternal;
+use near_vm_logic::types::{AccountId, Balance, PromiseResult};
+use near_vm_logic::{External, StorageGetMode, VMContext, VMLogicError, ValuePtr};
+use std::cell::Cell;
+use std::collections::HashMap;
+
+type Result<T> = std::result::Result<T, VMLogicError>;
+
+/// Host function responses recorded while a function call was executed on
+/// chain, enough to execute the call again without a node.
+#[derive(Debug, Default)]
+pub struct RecordedExtState {
+    /// Values of the storage keys used by the call, as they were before the
+    /// call.  Keys that are not listed read as absent.
+    pub storage: HashMap<Vec<u8>, Vec<u8>>,
+    /// Results of the promises the call is a callback for.
+    pub promise_results: Vec<PromiseResult>,
+    /// Stakes of the validators of the epoch the call was executed in.
+    pub validators: HashMap<AccountId, Balance>,
+    /// Trie node counters returned by successive `get_trie_nodes_count`
+    /// calls.  Storage operations are charged for the difference of the
+    /// counters before and after them, so these reproduce the gas of the
+    /// original execution.  The last counter is repeated once they are used
+    /// up, without counters no trie nodes are charged.
+    pub trie_nodes_counts: Vec<TrieNodesCount>,
+}
+
+/// Serves the host functions of a replayed call from a `RecordedExtState`.
+/// Storage writes only change the replay's copy of the storage.
+struct ReplayExternal<'a> {
+    storage: MockedExternal,
+    trie_nodes_counts: &'a [TrieNodesCount],
+    next_trie_nodes_count: Cell<usize>,
+}
+
+impl<'a> ReplayExternal<'a> {
+    fn new(recorded: &'a RecordedExtState) -> Self {
+        let mut storage = MockedExternal::new();
+        storage.fake_trie = recorded.storage.clone();
+        storage.validators = recorded.validators.clone();
+        Self {
+            storage,
+            trie_nodes_counts: &recorded.trie_nodes_counts,
+            next_trie_nodes_count: Cell::new(0),
+        }
+    }
+}
+
+impl External for ReplayExternal<'_> {
+    fn storage_set(&mut self, key: &[u8], value: &[u8]) -> Result<()> {
+        self.storage.storage_set(key, value)
+    }
+
+    fn storage_get<'a>(
+        &'a self,
+        key: &[u8],
+        mode: StorageGetMode,
+    ) -> Result<Option<Box<dyn ValuePtr + 'a>>> {
+        self.storage.storage_get(key, mode)
+    }
+
+    fn storage_remove(&mut self, key: &[u8]) -> Result<()> {
+        self.storage.storage_remove(key)
+    }
+
+    fn storage_remove_subtree(&mut self, prefix: &[u8]) -> Result<()> {
+        self.storage.storage_remove_subtree(prefix)
+    }
+
+    fn storage_iter_prefix(
+        &self,
+        prefix: &[u8],
+        start: &[u8],
+        visit: &mut dyn FnMut(&[u8], &dyn ValuePtr) -> Result<bool>,
+    ) -> Result<()> {
+        self.storage.storage_iter_prefix(prefix, start, visit)
+    }
+
+    fn storage_has_key(&mut self, key: &[u8]) -> Result<bool> {
+        self.storage.storage_has_key(key)
+    }
+
+    fn generate_data_id(&mut self) -> CryptoHash {
+        self.storage.generate_data_id()
+    }
+
+    fn get_trie_nodes_count(&self) -> TrieNodesCount {
+        let index = self.next_trie_nodes_count.get();
+        self.next_trie_nodes_count.set(index + 1);
+        match self.trie_nodes_counts.get(index).or_else(|| self.trie_nodes_counts.last()) {
+            Some(count) => TrieNodesCount { db_reads: count.db_reads, mem_reads: count.mem_reads },
+            None => TrieNodesCount { db_reads: 0, mem_reads: 0 },
+        }
+    }
+
+    fn validator_stake(&self, account_id: &AccountId) -> Result<Option<Balance>> {
+        self.storage.validator_stake(account_id)
+    }
+
+    fn validator_total_stake(&self) -> Result<Balance> {
+        self.storage.validator_total_stake()
+    }
+}
+
+/// Executes a function call again from the host function responses recorded
+/// while it was executed on chain, for debugging contract failures locally.
+///
+/// The call runs on the VM of `current_protocol_version` with the given
+/// configs.  Without a node there is no compiled contract cache, so the
+/// contract is compiled on every replay.  The returned outcome contains the
+/// gas profile of the call.
+pub fn replay_function_call(
+    code: &ContractCode,
+    method_name: &str,
+    recorded: &RecordedExtState,
+    context: VMContext,
+    wasm_config: &VMConfig,
+    fees_config: &RuntimeFeesConfig,
+    current_protocol_version: ProtocolVersion,
+) -> VMResult {
+    let mut ext = ReplayExternal::new(recorded);
+    crate::run(
+        code,
+        method_name,
+        &mut ext,
+        context,
+        wasm_config,
+        fees_config,
+        &recorded.promise_results,
+        current_protocol_version,
+        None,
+    )
+}
@@ -5,9 +5,10 @@ use near_primitives::contract::ContractCode;
 use near_primitives::runtime::fees::RuntimeFeesConfig;
 use near_primitives::types::CompiledContractCache;
 use near_primitives::version::ProtocolVersion;
-use near_vm_errors::{CacheError, CompilationError, VMRunnerError};
+use near_vm_errors::{CacheError, CompilationError, FunctionCallError, HostError, VMRunnerError};
 use near_vm_logic::types::PromiseResult;
-use near_vm_logic::{External, VMContext, VMOutcome};
+use near_vm_logic::{External, ReturnData, VMContext, VMOutcome, ViewConfig};
+use std::time::Duration;
 
 /// Returned by VM::run method.
 ///
@@ -53,7 +54,37 @@ pub fn run(
     current_protocol_version: ProtocolVersion,
     cache: Option<&dyn CompiledContractCache>,
 ) -> VMResult {
-    let vm_kind = VMKind::for_protocol_version(current_protocol_version);
+    run_with_vm_kind(
+        code,
+        method_name,
+        ext,
+        context,
+        wasm_config,
+        fees_config,
+        promise_results,
+        VMKind::for_protocol_version(current_protocol_version),
+        current_protocol_version,
+        cache,
+    )
+}
+
+/// Like [`run`], but executes the contract with the given VM instead of the
+/// default VM of the protocol version.
+///
+/// Panics if `vm_kind` has not been enabled at compile time.  This is meant
+/// for tests and tools comparing VMs, see also [`VMKind::runtime`].
+pub fn run_with_vm_kind(
+    code: &ContractCode,
+    method_name: &str,
+    ext: &mut dyn External,
+    context: VMContext,
+    wasm_config: &VMConfig,
+    fees_config: &RuntimeFeesConfig,
+    promise_results: &[PromiseResult],
+    vm_kind: VMKind,
+    current_protocol_version: ProtocolVersion,
+    cache: Option<&dyn CompiledContractCache>,
+) -> VMResult {
     let span = tracing::debug_span!(
         target: "vm",
         "run",
@@ -78,11 +109,171 @@ pub fn run(
         current_protocol_version,
         cache,
     )?;
+    #[cfg(feature = "profiling")]
+    let outcome = {
+        let mut outcome = outcome;
+        if let Some(profile) = &mut outcome.function_profile {
+            crate::instrument::profiling::set_function_names(profile, code.code());
+        }
+        outcome
+    };
 
     span.record("burnt_gas", &outcome.burnt_gas);
     Ok(outcome)
 }
 
+/// Limits of a view call executed with [`run_view`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ViewBudget {
+    /// Wall-clock time the execution of the method may take.  Loading and
+    /// compiling the contract is not included.
+    pub max_duration: Duration,
+    /// Number of WASM pages the memory of the contract may grow to.  The limit
+    /// is clamped to the `initial_memory_pages` and `max_memory_pages` of the
+    /// [`VMConfig`].
+    pub max_memory_pages: u32,
+}
+
+/// Result of a view call executed with [`run_view`].
+#[derive(Debug, PartialEq)]
+pub struct ViewOutcome {
+    pub logs: Vec<String>,
+    pub return_data: ReturnData,
+    /// Why the execution failed, if it did.  Running out of time is reported
+    /// as [`ViewOutcome::timed_out`] instead.
+    pub aborted: Option<FunctionCallError>,
+    /// Whether the execution was aborted because it exceeded
+    /// [`ViewBudget::max_duration`].
+    pub timed_out: bool,
+}
+
+/// Runs a view method of the specified contract.
+///
+/// Unlike [`run`], the execution is not limited by gas.  It is limited by the
+/// wall-clock time and memory of the `budget` instead, so that callers don't
+/// have to pick a gas limit for calls which are never charged.  As for any
+/// view call, host functions which modify state or create promises fail with
+/// [`HostError::ProhibitedInView`].  The `view_config` of the `context` is
+/// replaced.
+///
+/// Budgets with less memory than the `wasm_config` compile the contract with
+/// a separate entry in the `cache`.
+pub fn run_view(
+    code: &ContractCode,
+    method_name: &str,
+    ext: &mut dyn External,
+    mut context: VMContext,
+    wasm_config: &VMConfig,
+    fees_config: &RuntimeFeesConfig,
+    budget: ViewBudget,
+    current_protocol_version: ProtocolVersion,
+    cache: Option<&dyn CompiledContractCache>,
+) -> VMResult<ViewOutcome> {
+    let mut wasm_config = wasm_config.clone();
+    let limit_config = &mut wasm_config.limit_config;
+    limit_config.max_memory_pages = budget
+        .max_memory_pages
+        .clamp(limit_config.initial_memory_pages, limit_config.max_memory_pages);
+    // Gas is still counted, but with limits that can't be reached.  The
+    // deadline is enforced by lowering the gas limit, so running out of gas
+    // means running out of time.
+    context.view_config =
+        Some(ViewConfig { max_gas_burnt: u64::MAX, max_duration: Some(budget.max_duration) });
+
+    let outcome = run(
+        code,
+        method_name,
+        ext,
+        context,
+        &wasm_config,
+        fees_config,
+        &[],
+        current_protocol_version,
+        cache,
+    )?;
+    let timed_out = matches!(
+        outcome.aborted,
+        Some(FunctionCallError::HostError(HostError::GasExceeded | HostError::GasLimitExceeded))
+    );
+    Ok(ViewOutcome {
+        logs: outcome.logs,
+        return_data: outcome.return_data,
+        aborted: if timed_out { None } else { outcome.aborted },
+        timed_out,
+    })
+}
+
+/// Number of contracts by result of [`precompile_contracts`].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct PrecompileStats {
+    /// Contracts which were compiled and added to the cache.
+    pub compiled: u64,
+    /// Contracts which were already in the cache, including codes which
+    /// occurred more than once.
+    pub already_cached: u64,
+    /// Contracts which failed to compile or to be written to the cache.
+    pub failed: u64,
+}
+
+/// Compiles all given contracts with the VM of `current_protocol_version` and
+/// stores the results in `cache`.
+///
+/// This is meant to be called with all deployed contracts on node startup or
+/// ahead of a protocol upgrade which changes the VM, so that the first blocks
+/// don't have to compile every called contract.  Compilation runs on the
+/// rayon pool but uses at most half of its threads, so that the node keeps
+/// functioning normally.  Codes are deduplicated by hash.
+pub fn precompile_contracts(
+    contract_codes: impl Iterator<Item = ContractCode>,
+    cache: &dyn CompiledContractCache,
+    config: &VMConfig,
+    current_protocol_version: ProtocolVersion,
+) -> PrecompileStats {
+    let _span = tracing::debug_span!(target: "vm", "precompile_contracts").entered();
+    let stats = std::sync::Mutex::new(PrecompileStats::default());
+    let mut seen = std::collections::HashSet::new();
+    rayon::in_place_scope(|scope| {
+        let (slot_sender, slot_receiver) = std::sync::mpsc::channel();
+        let max_threads = std::cmp::max(rayon::current_num_threads() / 2, 1);
+        for _ in 0..max_threads {
+            slot_sender.send(()).expect("both sender and receiver are owned here");
+        }
+        for code in contract_codes {
+            if !seen.insert(*code.hash()) {
+                stats.lock().unwrap().already_cached += 1;
+                continue;
+            }
+            slot_receiver.recv().expect("could not receive a slot to compile contract");
+            let slot_sender = slot_sender.clone();
+            let stats = &stats;
+            scope.spawn(move |_| {
+                let result =
+                    crate::precompile_contract(&code, config, current_protocol_version, Some(cache));
+                let mut stats = stats.lock().unwrap();
+                match result {
+                    Ok(Ok(ContractPrecompilatonResult::ContractCompiled)) => stats.compiled += 1,
+                    Ok(Ok(ContractPrecompilatonResult::ContractAlreadyInCache)) => {
+                        stats.already_cached += 1
+                    }
+                    Ok(Ok(ContractPrecompilatonResult::CacheNotAvailable)) => {
+                        unreachable!("cache was given")
+                    }
+                    Ok(Err(_)) => stats.failed += 1,
+                    Err(err) => {
+                        tracing::warn!(target: "vm", code_hash = %code.hash(), ?err, "failed to cache compiled contract");
+                        stats.failed += 1;
+                    }
+                }
+                drop(stats);
+                // If this fails, it just means there won't be any more attempts
+                // to recv the slots.
+                let _ = slot_sender.send(());
+            });
+        }
+    });
+    stats.into_inner().unwrap()
+}
+
 pub trait VM {
     /// Validate and run the specified contract.
     ///
@@ -1,10 +1,17 @@
 mod cache;
+mod compare_vms;
 mod compile_errors;
 mod fuzzers;
+mod memory_reuse;
+mod memory_usage;
+#[cfg(feature = "profiling")]
+mod profiling;
+mod replay;
 mod rs_contract;
 mod runtime_errors;
 pub(crate) mod test_builder;
 mod ts_contract;
+mod view;
 mod wasm_validation;
 
 use crate::vm_kind::VMKind;
@@ -39,6 +46,8 @@ fn create_context(input: Vec<u8>) -> VMContext {
         block_height: 10,
         block_timestamp: 42,
         epoch_height: 1,
+        block_gas_price: 100_000_000,
+        block_gas_limit: 1_000 * 10u64.pow(12),
         account_balance: 2u128,
         account_locked_balance: 0,
         storage_usage: 12,
@@ -204,3 +204,26 @@ impl CompiledContractCache for FaultingCompiledContractCache {
         self.inner.get(key)
     }
 }
+
+#[test]
+fn test_precompile_contracts() {
+    let cache = MockCompiledContractCache::default();
+    let config = VMConfig::test();
+    let codes = || {
+        [
+            near_test_contracts::trivial_contract().to_vec(),
+            near_test_contracts::trivial_contract().to_vec(),
+            vec![42; 1000],
+        ]
+        .into_iter()
+        .map(|code| ContractCode::new(code, None))
+    };
+
+    let stats = crate::precompile_contracts(codes(), &cache, &config, LATEST_PROTOCOL_VERSION);
+    assert_eq!(stats, crate::PrecompileStats { compiled: 1, already_cached: 1, failed: 1 });
+    // Compilation errors are cached as well.
+    assert_eq!(cache.len(), 2);
+
+    let stats = crate::precompile_contracts(codes(), &cache, &config, LATEST_PROTOCOL_VERSION);
+    assert_eq!(stats, crate::PrecompileStats { compiled: 0, already_cached: 3, failed: 0 });
+}
@@ -0,0 +1,117 @@
+//! Differential testing of the VMs.
+//!
+//! Every VM has to produce exactly the same results, or validators running
+//! different VMs would disagree on the state.  [`compare_vms`] runs a call
+//! under all VMs compiled into this build and checks that.  It is meant to
+//! validate new VMs against the ones in use before switching over.
+
+use crate::runner::run_with_vm_kind;
+use crate::tests::{create_context, LATEST_PROTOCOL_VERSION};
+use crate::vm_kind::VMKind;
+use near_primitives::contract::ContractCode;
+use near_primitives::runtime::fees::RuntimeFeesConfig;
+use near_vm_errors::{CompilationError, FunctionCallError};
+use near_vm_logic::mocks::mock_external::MockedExternal;
+use near_vm_logic::{VMConfig, VMContext, VMOutcome};
+
+/// VMs compiled into this build.
+fn vm_kinds() -> Vec<VMKind> {
+    let mut vm_kinds = vec![];
+    #[cfg(all(feature = "wasmer0_vm", target_arch = "x86_64"))]
+    vm_kinds.push(VMKind::Wasmer0);
+    #[cfg(feature = "wasmtime_vm")]
+    vm_kinds.push(VMKind::Wasmtime);
+    #[cfg(all(feature = "wasmer2_vm", target_arch = "x86_64"))]
+    vm_kinds.push(VMKind::Wasmer2);
+    vm_kinds
+}
+
+/// Error messages produced by the VM implementations differ and are not part
+/// of the protocol, so they are not compared.
+fn without_vm_messages(mut outcome: VMOutcome) -> VMOutcome {
+    match &mut outcome.aborted {
+        Some(FunctionCallError::LinkError { msg })
+        | Some(FunctionCallError::CompilationError(CompilationError::WasmerCompileError { msg })) => {
+            *msg = String::new()
+        }
+        _ => {}
+    }
+    outcome
+}
+
+/// Runs `method` of `code` under every compiled-in VM on a fresh state and
+/// asserts that the outcomes, including logs, gas and profiles, and the
+/// resulting states are identical.  Returns the common outcome.
+pub(crate) fn compare_vms(code: &ContractCode, method: &str, context: VMContext) -> VMOutcome {
+    let config = VMConfig::test();
+    let fees = RuntimeFeesConfig::test();
+    let mut results = vm_kinds().into_iter().map(|vm_kind| {
+        let mut fake_external = MockedExternal::new();
+        let outcome = run_with_vm_kind(
+            code,
+            method,
+            &mut fake_external,
+            context.clone(),
+            &config,
+            &fees,
+            &[],
+            vm_kind,
+            LATEST_PROTOCOL_VERSION,
+            None,
+        )
+        .unwrap_or_else(|err| panic!("{vm_kind:?} failed to run `{method}`: {err:?}"));
+        (vm_kind, without_vm_messages(outcome), fake_external.fake_trie)
+    });
+    let (expected_vm_kind, expected_outcome, expected_trie) =
+        results.next().expect("no VM has been compiled in");
+    for (vm_kind, outcome, trie) in results {
+        assert_eq!(
+            outcome, expected_outcome,
+            "{vm_kind:?} and {expected_vm_kind:?} disagree on the outcome of `{method}`"
+        );
+        assert_eq!(
+            trie, expected_trie,
+            "{vm_kind:?} and {expected_vm_kind:?} disagree on the state after `{method}`"
+        );
+    }
+    expected_outcome
+}
+
+fn u64_input(values: &[u64]) -> Vec<u8> {
+    values.iter().flat_map(|value| value.to_le_bytes()).collect()
+}
+
+/// Calls of the test contracts which cover computation, memory, storage,
+/// logs, panics and running out of gas.
+fn corpus() -> Vec<(ContractCode, &'static str, Vec<u8>)> {
+    let rs_contract = || ContractCode::new(near_test_contracts::rs_contract().to_vec(), None);
+    let ts_contract = || ContractCode::new(near_test_contracts::ts_contract().to_vec(), None);
+    vec![
+        (rs_contract(), "ext_sha256", b"tesdsst".to_vec()),
+        (rs_contract(), "ext_used_gas", vec![]),
+        (rs_contract(), "ext_account_balance", vec![]),
+        (rs_contract(), "write_key_value", u64_input(&[10, 20])),
+        (rs_contract(), "write_random_value", vec![]),
+        (rs_contract(), "log_something", vec![]),
+        (rs_contract(), "sum_n", u64_input(&[1_000])),
+        (rs_contract(), "fibonacci", vec![16]),
+        (rs_contract(), "insert_strings", u64_input(&[0, 20])),
+        (rs_contract(), "recurse", u64_input(&[100])),
+        (rs_contract(), "panic_after_logging", vec![]),
+        (rs_contract(), "abort_with_zero", vec![]),
+        (rs_contract(), "loop_forever", vec![]),
+        (rs_contract(), "sanity_check_panic", vec![]),
+        (rs_contract(), "no_such_method", vec![]),
+        (ts_contract(), "try_panic", vec![]),
+        (ContractCode::new(b"not wasm".to_vec(), None), "main", vec![]),
+    ]
+}
+
+#[test]
+fn test_vms_agree_on_corpus() {
+    for (code, method, input) in corpus() {
+        let mut context = create_context(input);
+        context.prepaid_gas = 10u64.pow(13);
+        compare_vms(&code, method, context);
+    }
+}
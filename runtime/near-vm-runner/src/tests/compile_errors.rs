@@ -342,6 +342,68 @@ fn test_limit_locals_global() {
         "#]]);
 }
 
+#[test]
+#[cfg(feature = "protocol_feature_limit_contract_sections")]
+fn test_limit_contract_sections() {
+    let version = ProtocolFeature::LimitContractSections.protocol_version();
+
+    test_builder()
+        .wasm(
+            &near_test_contracts::LargeContract {
+                globals: 10_000,
+                table_elements: 100_000,
+                custom_section_bytes: 1_000_000,
+                ..Default::default()
+            }
+            .make(),
+        )
+        .protocol_versions(vec![version - 1, version])
+        .opaque_outcome()
+        .expects(&[expect![""], expect![""]]);
+
+    test_builder()
+        .wasm(&near_test_contracts::LargeContract { globals: 10_001, ..Default::default() }.make())
+        .protocol_versions(vec![version - 1, version])
+        .opaque_outcome()
+        .expects(&[
+            expect![""],
+            expect![[r#"
+                Err: PrepareError: Too many globals declared in the contract.
+            "#]],
+        ]);
+
+    test_builder()
+        .wasm(
+            &near_test_contracts::LargeContract { table_elements: 100_001, ..Default::default() }
+                .make(),
+        )
+        .protocol_versions(vec![version - 1, version])
+        .opaque_outcome()
+        .expects(&[
+            expect![""],
+            expect![[r#"
+                Err: PrepareError: Tables declared in the contract are too large.
+            "#]],
+        ]);
+
+    test_builder()
+        .wasm(
+            &near_test_contracts::LargeContract {
+                custom_section_bytes: 1_048_576,
+                ..Default::default()
+            }
+            .make(),
+        )
+        .protocol_versions(vec![version - 1, version])
+        .opaque_outcome()
+        .expects(&[
+            expect![""],
+            expect![[r#"
+                Err: PrepareError: Custom sections of the contract are too large.
+            "#]],
+        ]);
+}
+
 #[test]
 pub fn test_stablized_host_function() {
     test_builder()
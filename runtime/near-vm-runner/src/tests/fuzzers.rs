@@ -48,6 +48,8 @@ pub fn create_context(input: Vec<u8>) -> VMContext {
         block_height: 10,
         block_timestamp: 42,
         epoch_height: 1,
+        block_gas_price: 100_000_000,
+        block_gas_limit: 1_000 * 10u64.pow(12),
         account_balance: 2u128,
         account_locked_balance: 0,
         storage_usage: 12,
@@ -0,0 +1,62 @@
+use crate::tests::{create_context, with_vm_variants, LATEST_PROTOCOL_VERSION};
+use crate::vm_kind::VMKind;
+use near_primitives::contract::ContractCode;
+use near_primitives::runtime::fees::RuntimeFeesConfig;
+use near_vm_logic::mocks::mock_external::MockedExternal;
+use near_vm_logic::{VMConfig, VMOutcome};
+
+/// `main` returns the data segment, the last 8 bytes of the initial memory
+/// and then overwrites both, so leftovers of a previous call show up in the
+/// return data.  `grow` grows the memory, which must not be reused.
+const CONTRACT: &str = r#"
+(module
+  (import "env" "value_return" (func $value_return (param i64 i64)))
+  (memory 1)
+  (data (i32.const 0) "snapshot")
+  (func (export "main")
+    (i64.store (i32.const 16) (i64.load (i32.const 67108856)))
+    (call $value_return (i64.const 24) (i64.const 0))
+    (i64.store (i32.const 0) (i64.const -1))
+    (i64.store (i32.const 67108856) (i64.const -1))
+  )
+  (func (export "grow")
+    (drop (memory.grow (i32.const 1)))
+    (i64.store (i32.const 8) (i64.extend_i32_u (memory.size)))
+    (call $value_return (i64.const 16) (i64.const 0))
+  )
+)"#;
+
+fn run_calls(vm_kind: VMKind, reuse_memory: bool) -> Vec<VMOutcome> {
+    let code = ContractCode::new(wat::parse_str(CONTRACT).unwrap(), None);
+    let mut fake_external = MockedExternal::new();
+    let fees = RuntimeFeesConfig::test();
+    let config = VMConfig { reuse_memory, ..VMConfig::test() };
+    let runtime = vm_kind.runtime(config).expect("runtime has not been compiled");
+    ["main", "main", "main", "grow", "grow", "main", "main"]
+        .into_iter()
+        .map(|method| {
+            runtime
+                .run(
+                    &code,
+                    method,
+                    &mut fake_external,
+                    create_context(vec![]),
+                    &fees,
+                    &[],
+                    LATEST_PROTOCOL_VERSION,
+                    None,
+                )
+                .expect("execution failed")
+        })
+        .collect()
+}
+
+#[test]
+fn test_memory_reuse_determinism() {
+    with_vm_variants(|vm_kind: VMKind| {
+        let expected = run_calls(vm_kind, false);
+        assert!(expected.iter().all(|outcome| outcome.aborted.is_none()), "{expected:?}");
+        let outcomes = run_calls(vm_kind, true);
+        assert_eq!(outcomes, expected, "{vm_kind:?} outcomes depend on memory reuse");
+    });
+}
@@ -0,0 +1,47 @@
+use crate::tests::{create_context, with_vm_variants, LATEST_PROTOCOL_VERSION};
+use crate::vm_kind::VMKind;
+use near_primitives::contract::ContractCode;
+use near_primitives::runtime::fees::RuntimeFeesConfig;
+use near_vm_logic::mocks::mock_external::MockedExternal;
+use near_vm_logic::{MemoryUsage, VMConfig};
+
+/// Copies the input into a register, then grows the memory by two pages.
+const CONTRACT: &str = r#"
+(module
+  (import "env" "input" (func $input (param i64)))
+  (memory 1)
+  (func (export "main")
+    (call $input (i64.const 0))
+    (drop (memory.grow (i32.const 2)))
+  )
+)"#;
+
+#[test]
+fn test_memory_usage() {
+    with_vm_variants(|vm_kind: VMKind| {
+        let code = ContractCode::new(wat::parse_str(CONTRACT).unwrap(), None);
+        let mut fake_external = MockedExternal::new();
+        let fees = RuntimeFeesConfig::test();
+        let config = VMConfig::test();
+        let initial_memory_pages = config.limit_config.initial_memory_pages;
+        let runtime = vm_kind.runtime(config).expect("runtime has not been compiled");
+        let outcome = runtime
+            .run(
+                &code,
+                "main",
+                &mut fake_external,
+                create_context(vec![0; 100]),
+                &fees,
+                &[],
+                LATEST_PROTOCOL_VERSION,
+                None,
+            )
+            .expect("execution failed");
+        assert_eq!(outcome.aborted, None, "{vm_kind:?}");
+        assert_eq!(
+            outcome.memory_usage,
+            MemoryUsage { linear_memory_pages: initial_memory_pages + 2, registers_bytes: 108 },
+            "{vm_kind:?}"
+        );
+    });
+}
@@ -0,0 +1,49 @@
+use crate::tests::{create_context, with_vm_variants, LATEST_PROTOCOL_VERSION};
+use crate::vm_kind::VMKind;
+use near_primitives::contract::ContractCode;
+use near_primitives::runtime::fees::RuntimeFeesConfig;
+use near_vm_logic::mocks::mock_external::MockedExternal;
+use near_vm_logic::VMConfig;
+
+/// `main` calls a host function after `helper` returned, so that it has to
+/// be attributed to `main` again.
+const CONTRACT: &str = r#"
+(module
+  (import "env" "used_gas" (func $used_gas (result i64)))
+  (func $main (export "main")
+    (call $helper)
+    (drop (call $used_gas)))
+  (func $helper
+    (drop (call $used_gas))
+    (drop (call $used_gas)))
+)"#;
+
+#[test]
+fn test_function_profile() {
+    with_vm_variants(|vm_kind: VMKind| {
+        let code = ContractCode::new(wat::parse_str(CONTRACT).unwrap(), None);
+        let config = VMConfig::test();
+        let runtime = vm_kind.runtime(config).expect("runtime has not been compiled");
+        let outcome = runtime
+            .run(
+                &code,
+                "main",
+                &mut MockedExternal::new(),
+                create_context(vec![]),
+                &RuntimeFeesConfig::test(),
+                &[],
+                LATEST_PROTOCOL_VERSION,
+                None,
+            )
+            .expect("execution failed");
+        assert_eq!(outcome.aborted, None);
+        let mut profile = outcome.function_profile.expect("profile is missing");
+        crate::instrument::profiling::set_function_names(&mut profile, code.code());
+
+        let main = profile.get(1).unwrap();
+        assert_eq!((main.name.as_deref(), main.host_calls), (Some("main"), 1));
+        let helper = profile.get(2).unwrap();
+        assert_eq!((helper.name.as_deref(), helper.host_calls), (Some("helper"), 2));
+        assert!(main.instructions > 0 && helper.instructions > 0);
+    });
+}
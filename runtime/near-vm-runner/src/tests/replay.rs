@@ -0,0 +1,62 @@
+use crate::tests::{create_context, LATEST_PROTOCOL_VERSION};
+use crate::{replay_function_call, RecordedExtState};
+use near_primitives::contract::ContractCode;
+use near_primitives::runtime::fees::RuntimeFeesConfig;
+use near_primitives::types::TrieNodesCount;
+use near_vm_logic::types::PromiseResult;
+use near_vm_logic::{ExtCosts, ReturnData, VMConfig};
+
+/// `main` returns the value of "key" followed by the first promise result.
+const CONTRACT: &str = r#"
+(module
+  (import "env" "storage_read" (func $storage_read (param i64 i64 i64) (result i64)))
+  (import "env" "promise_result" (func $promise_result (param i64 i64) (result i64)))
+  (import "env" "register_len" (func $register_len (param i64) (result i64)))
+  (import "env" "read_register" (func $read_register (param i64 i64)))
+  (import "env" "value_return" (func $value_return (param i64 i64)))
+  (memory 1)
+  (data (i32.const 0) "key")
+  (func (export "main")
+    (drop (call $storage_read (i64.const 3) (i64.const 0) (i64.const 0)))
+    (call $read_register (i64.const 0) (i64.const 16))
+    (drop (call $promise_result (i64.const 0) (i64.const 1)))
+    (call $read_register (i64.const 1) (i64.add (i64.const 16) (call $register_len (i64.const 0))))
+    (call $value_return
+      (i64.add (call $register_len (i64.const 0)) (call $register_len (i64.const 1)))
+      (i64.const 16))
+  )
+)"#;
+
+#[test]
+fn test_replay_function_call() {
+    let code = ContractCode::new(wat::parse_str(CONTRACT).unwrap(), None);
+    let mut recorded = RecordedExtState::default();
+    recorded.storage.insert(b"key".to_vec(), b"value".to_vec());
+    recorded.promise_results.push(PromiseResult::Successful(b"result".to_vec()));
+    recorded.trie_nodes_counts = vec![
+        TrieNodesCount { db_reads: 10, mem_reads: 0 },
+        TrieNodesCount { db_reads: 12, mem_reads: 1 },
+    ];
+    let outcome = replay_function_call(
+        &code,
+        "main",
+        &recorded,
+        create_context(vec![]),
+        &VMConfig::test(),
+        &RuntimeFeesConfig::test(),
+        LATEST_PROTOCOL_VERSION,
+    )
+    .expect("execution failed");
+    assert_eq!(outcome.aborted, None);
+    assert_eq!(outcome.return_data, ReturnData::Value(b"valueresult".to_vec()));
+
+    let ext_costs = VMConfig::test().ext_costs;
+    assert_eq!(
+        outcome.profile.get_ext_cost(ExtCosts::touching_trie_node),
+        2 * ext_costs.touching_trie_node
+    );
+    assert_eq!(
+        outcome.profile.get_ext_cost(ExtCosts::read_cached_trie_node),
+        ext_costs.read_cached_trie_node
+    );
+}
@@ -0,0 +1,101 @@
+use crate::tests::{create_context, LATEST_PROTOCOL_VERSION};
+use crate::{ViewBudget, ViewOutcome};
+use near_primitives::contract::ContractCode;
+use near_primitives::runtime::fees::RuntimeFeesConfig;
+use near_vm_errors::{FunctionCallError, HostError};
+use near_vm_logic::mocks::mock_external::MockedExternal;
+use near_vm_logic::{ReturnData, VMConfig};
+use std::time::{Duration, Instant};
+
+const CONTRACT: &str = r#"
+(module
+  (import "env" "log_utf8" (func $log_utf8 (param i64 i64)))
+  (import "env" "value_return" (func $value_return (param i64 i64)))
+  (import "env" "storage_write" (func $storage_write (param i64 i64 i64 i64 i64) (result i64)))
+  (memory 1)
+  (data (i32.const 0) "hello")
+  (func (export "hello")
+    (call $log_utf8 (i64.const 5) (i64.const 0))
+    (call $value_return (i64.const 5) (i64.const 0))
+  )
+  (func (export "write")
+    (drop (call $storage_write (i64.const 5) (i64.const 0) (i64.const 5) (i64.const 0) (i64.const 0)))
+  )
+  (func (export "loop")
+    (loop $l (br $l))
+  )
+  (func (export "grow")
+    (i32.store (i32.const 0) (memory.grow (i32.const 4)))
+    (i32.store (i32.const 4) (memory.grow (i32.const 4)))
+    (call $value_return (i64.const 8) (i64.const 0))
+  )
+)"#;
+
+fn run_view(method_name: &str, budget: ViewBudget) -> ViewOutcome {
+    let code = ContractCode::new(wat::parse_str(CONTRACT).unwrap(), None);
+    let mut fake_external = MockedExternal::new();
+    crate::run_view(
+        &code,
+        method_name,
+        &mut fake_external,
+        create_context(vec![]),
+        &VMConfig::test(),
+        &RuntimeFeesConfig::test(),
+        budget,
+        LATEST_PROTOCOL_VERSION,
+        None,
+    )
+    .expect("execution failed")
+}
+
+fn budget() -> ViewBudget {
+    ViewBudget {
+        max_duration: Duration::from_secs(10),
+        max_memory_pages: VMConfig::test().limit_config.max_memory_pages,
+    }
+}
+
+#[test]
+fn test_view_returns_logs_and_data() {
+    let outcome = run_view("hello", budget());
+    assert_eq!(
+        outcome,
+        ViewOutcome {
+            logs: vec!["hello".to_string()],
+            return_data: ReturnData::Value(b"hello".to_vec()),
+            aborted: None,
+            timed_out: false,
+        }
+    );
+}
+
+#[test]
+fn test_view_prohibits_state_changes() {
+    let outcome = run_view("write", budget());
+    assert_eq!(
+        outcome.aborted,
+        Some(FunctionCallError::HostError(HostError::ProhibitedInView {
+            method_name: "storage_write".to_string()
+        }))
+    );
+}
+
+#[test]
+fn test_view_timeout() {
+    let start = Instant::now();
+    let outcome =
+        run_view("loop", ViewBudget { max_duration: Duration::from_millis(100), ..budget() });
+    assert!(outcome.timed_out);
+    assert_eq!(outcome.aborted, None);
+    assert!(start.elapsed() < Duration::from_secs(10));
+}
+
+#[test]
+fn test_view_memory_budget() {
+    let initial_memory_pages = VMConfig::test().limit_config.initial_memory_pages;
+    let outcome =
+        run_view("grow", ViewBudget { max_memory_pages: initial_memory_pages + 6, ..budget() });
+    let mut expected = initial_memory_pages.to_le_bytes().to_vec();
+    expected.extend((-1i32).to_le_bytes());
+    assert_eq!(outcome.return_data, ReturnData::Value(expected));
+}
@@ -1,5 +1,5 @@
 use borsh::BorshSerialize;
-use near_primitives::checked_feature;
+use near_primitives::version::ProtocolFeature;
 use near_vm_logic::ProtocolVersion;
 use std::hash::Hash;
 
@@ -40,7 +40,7 @@ impl VMKind {
         }
 
         if cfg!(target_arch = "x86_64") {
-            if checked_feature!("stable", Wasmer2, protocol_version) {
+            if ProtocolFeature::Wasmer2.enabled(protocol_version) {
                 VMKind::Wasmer2
             } else {
                 VMKind::Wasmer0
@@ -6,6 +6,7 @@ use crate::runner::VMResult;
 use crate::{get_contract_cache_key, imports};
 use memoffset::offset_of;
 use near_primitives::contract::ContractCode;
+use near_primitives::hash::CryptoHash;
 use near_primitives::runtime::fees::RuntimeFeesConfig;
 use near_primitives::types::{CompiledContract, CompiledContractCache};
 use near_stable_hasher::StableHasher;
@@ -15,6 +16,7 @@ use near_vm_errors::{
 use near_vm_logic::gas_counter::FastGasCounter;
 use near_vm_logic::types::{PromiseResult, ProtocolVersion};
 use near_vm_logic::{External, MemoryLike, VMConfig, VMContext, VMLogic, VMOutcome};
+use std::cell::RefCell;
 use std::hash::{Hash, Hasher};
 use std::mem::size_of;
 use std::sync::Arc;
@@ -59,6 +61,26 @@ impl Wasmer2Memory {
         )?)))
     }
 
+    /// Resets the memory to the state right after `new`, so that it can be
+    /// used for another call.  Returns `false` if this is not possible, e.g.
+    /// because the memory was grown.
+    fn reset(&mut self, initial_memory_pages: u32) -> bool {
+        if Arc::strong_count(&self.0) != 1 || self.0.size() != Pages(initial_memory_pages) {
+            return false;
+        }
+        #[cfg(target_os = "linux")]
+        unsafe {
+            let memory = self.0.vmmemory();
+            let memory = memory.as_ref();
+            // SAFETY: the memory is a private anonymous mapping that is not
+            // referenced by any instance.  Its pages read as zeros again after
+            // `MADV_DONTNEED` and are only copied when written.
+            libc::madvise(memory.base.cast(), memory.current_length, libc::MADV_DONTNEED) == 0
+        }
+        #[cfg(not(target_os = "linux"))]
+        false
+    }
+
     // Returns the pointer to memory at the specified offset and the size of the buffer starting at
     // the returned pointer.
     fn data_offset(&self, offset: u64) -> Option<(*mut u8, usize)> {
@@ -231,6 +253,19 @@ pub(crate) fn wasmer2_vm_hash() -> u64 {
 
 pub(crate) type VMArtifact = Arc<wasmer_engine_universal::UniversalArtifact>;
 
+/// Memory of the previous call on this thread, see `VMConfig::reuse_memory`.
+struct ReusableMemory {
+    /// Contract cache key of the call, which covers the code and the
+    /// `VMConfig`.
+    key: CryptoHash,
+    method_name: String,
+    memory: Wasmer2Memory,
+}
+
+thread_local! {
+    static REUSABLE_MEMORY: RefCell<Option<ReusableMemory>> = RefCell::new(None);
+}
+
 pub(crate) struct Wasmer2VM {
     pub(crate) config: VMConfig,
     pub(crate) engine: UniversalEngine,
@@ -447,8 +482,9 @@ impl Wasmer2VM {
                     Box::new(()),
                     // SAFETY: We have verified that the `FastGasCounter` layout matches the
                     // expected layout. `gas` remains dereferenceable throughout this function
-                    // by the virtue of it being contained within `import` which lives for the
-                    // entirety of this function.
+                    // by the virtue of the counter being owned by the `VMLogic` in `import`
+                    // which lives for the entirety of this function. Its fields are atomics, so
+                    // the writes of the compiled code and the view watchdog don't race.
                     InstanceConfig::default()
                         .with_counter(gas)
                         .with_stack_limit(self.config.limit_config.wasmer2_stack_limit),
@@ -590,12 +626,80 @@ impl crate::runner::VM for Wasmer2VM {
         current_protocol_version: ProtocolVersion,
         cache: Option<&dyn CompiledContractCache>,
     ) -> Result<VMOutcome, VMRunnerError> {
-        let mut memory = Wasmer2Memory::new(
-            self.config.limit_config.initial_memory_pages,
-            self.config.limit_config.max_memory_pages,
-        )
-        .expect("Cannot create memory for a contract call");
+        let new_memory = || {
+            Wasmer2Memory::new(
+                self.config.limit_config.initial_memory_pages,
+                self.config.limit_config.max_memory_pages,
+            )
+            .expect("Cannot create memory for a contract call")
+        };
+        if !self.config.reuse_memory {
+            return self.run_with_memory(
+                &mut new_memory(),
+                code,
+                method_name,
+                ext,
+                context,
+                fees_config,
+                promise_results,
+                current_protocol_version,
+                cache,
+            );
+        }
+
+        let key = get_contract_cache_key(code, VMKind::Wasmer2, &self.config);
+        let reused = REUSABLE_MEMORY.with(|slot| match slot.borrow_mut().take() {
+            Some(reusable) if reusable.key == key && reusable.method_name == method_name => {
+                Some(reusable.memory)
+            }
+            _ => None,
+        });
+        let mut memory = reused.unwrap_or_else(new_memory);
+        let result = self.run_with_memory(
+            &mut memory,
+            code,
+            method_name,
+            ext,
+            context,
+            fees_config,
+            promise_results,
+            current_protocol_version,
+            cache,
+        );
+        if memory.reset(self.config.limit_config.initial_memory_pages) {
+            let method_name = method_name.to_string();
+            REUSABLE_MEMORY.with(|slot| {
+                *slot.borrow_mut() = Some(ReusableMemory { key, method_name, memory })
+            });
+        }
+        result
+    }
+
+    fn precompile(
+        &self,
+        code: &ContractCode,
+        cache: &dyn CompiledContractCache,
+    ) -> Result<Result<ContractPrecompilatonResult, CompilationError>, near_vm_errors::CacheError>
+    {
+        Ok(self
+            .compile_and_cache(code, Some(cache))?
+            .map(|_| ContractPrecompilatonResult::ContractCompiled))
+    }
+}
 
+impl Wasmer2VM {
+    fn run_with_memory(
+        &self,
+        memory: &mut Wasmer2Memory,
+        code: &ContractCode,
+        method_name: &str,
+        ext: &mut dyn External,
+        context: VMContext,
+        fees_config: &RuntimeFeesConfig,
+        promise_results: &[PromiseResult],
+        current_protocol_version: ProtocolVersion,
+        cache: Option<&dyn CompiledContractCache>,
+    ) -> Result<VMOutcome, VMRunnerError> {
         // FIXME: this mostly duplicates the `run_module` method.
         // Note that we don't clone the actual backing memory, just increase the RC.
         let vmmemory = memory.vm();
@@ -605,7 +709,7 @@ impl crate::runner::VM for Wasmer2VM {
             &self.config,
             fees_config,
             promise_results,
-            &mut memory,
+            memory,
             current_protocol_version,
         );
 
@@ -630,6 +734,7 @@ impl crate::runner::VM for Wasmer2VM {
         if let Err(e) = result {
             return Ok(VMOutcome::abort(logic, e));
         }
+        let watchdog = logic.start_view_watchdog();
         let import = imports::wasmer2::build(
             vmmemory,
             &mut logic,
@@ -637,27 +742,21 @@ impl crate::runner::VM for Wasmer2VM {
             artifact.engine(),
         );
         if let Err(e) = get_entrypoint_index(&*artifact, method_name) {
+            drop(watchdog);
             return Ok(VMOutcome::abort_but_nop_outcome_in_old_protocol(
                 logic,
                 e,
                 current_protocol_version,
             ));
         }
-        match self.run_method(&artifact, import, method_name)? {
-            Ok(()) => Ok(VMOutcome::ok(logic)),
-            Err(err) => Ok(VMOutcome::abort(logic, err)),
-        }
-    }
-
-    fn precompile(
-        &self,
-        code: &ContractCode,
-        cache: &dyn CompiledContractCache,
-    ) -> Result<Result<ContractPrecompilatonResult, CompilationError>, near_vm_errors::CacheError>
-    {
-        Ok(self
-            .compile_and_cache(code, Some(cache))?
-            .map(|_| ContractPrecompilatonResult::ContractCompiled))
+        let result = self.run_method(&artifact, import, method_name);
+        drop(watchdog);
+        let mut outcome = match result? {
+            Ok(()) => VMOutcome::ok(logic),
+            Err(err) => VMOutcome::abort(logic, err),
+        };
+        outcome.memory_usage.linear_memory_pages = memory.0.size().0;
+        Ok(outcome)
     }
 }
 
@@ -666,6 +765,24 @@ mod tests {
     use assert_matches::assert_matches;
     use wasmer_types::WASM_PAGE_SIZE;
 
+    #[test]
+    fn memory_reset() {
+        use near_vm_logic::MemoryLike;
+        use wasmer_vm::Memory;
+        let mut memory = super::Wasmer2Memory::new(1, 2).unwrap();
+        memory.write_memory(0, &[1, 2, 3]);
+        if cfg!(target_os = "linux") {
+            assert!(memory.reset(1));
+            assert_eq!(memory.read_memory_u8(1), 0);
+        }
+
+        let vmmemory = memory.vm();
+        assert!(!memory.reset(1));
+        drop(vmmemory);
+        memory.0.grow(wasmer_types::Pages(1)).unwrap();
+        assert!(!memory.reset(1));
+    }
+
     #[test]
     fn get_memory_buffer() {
         let memory = super::Wasmer2Memory::new(1, 1).unwrap();
@@ -443,10 +443,15 @@ impl crate::runner::VM for Wasmer0VM {
             ));
         }
 
-        match run_method(&module, &import_object, method_name)? {
-            Ok(()) => Ok(VMOutcome::ok(logic)),
-            Err(err) => Ok(VMOutcome::abort(logic, err)),
-        }
+        let watchdog = logic.start_view_watchdog();
+        let result = run_method(&module, &import_object, method_name);
+        drop(watchdog);
+        let mut outcome = match result? {
+            Ok(()) => VMOutcome::ok(logic),
+            Err(err) => VMOutcome::abort(logic, err),
+        };
+        outcome.memory_usage.linear_memory_pages = memory.size_pages();
+        Ok(outcome)
     }
 
     fn precompile(
@@ -270,10 +270,17 @@ impl crate::runner::VM for WasmtimeVM {
         match linker.instantiate(&mut store, &module) {
             Ok(instance) => match instance.get_func(&mut store, method_name) {
                 Some(func) => match func.typed::<(), (), _>(&mut store) {
-                    Ok(run) => match run.call(&mut store, ()) {
-                        Ok(_) => Ok(VMOutcome::ok(logic)),
-                        Err(err) => Ok(VMOutcome::abort(logic, err.into_vm_error()?)),
-                    },
+                    Ok(run) => {
+                        let watchdog = logic.start_view_watchdog();
+                        let result = run.call(&mut store, ());
+                        drop(watchdog);
+                        let mut outcome = match result {
+                            Ok(_) => VMOutcome::ok(logic),
+                            Err(err) => VMOutcome::abort(logic, err.into_vm_error()?),
+                        };
+                        outcome.memory_usage.linear_memory_pages = memory_copy.size(&store) as u32;
+                        Ok(outcome)
+                    }
                     Err(err) => Ok(VMOutcome::abort(logic, err.into_vm_error()?)),
                 },
                 None => {
@@ -1,4 +1,5 @@
 use crate::db::{Db, EstimationRow};
+use crate::tag::{Tag, TagThreshold};
 use crate::zulip::{ZulipEndpoint, ZulipReport};
 use crate::Metric;
 use clap::Parser;
@@ -30,8 +31,21 @@ pub(crate) struct CheckConfig {
     /// comparison on all available estimations.
     #[clap(long)]
     estimations: Vec<String>,
+    /// Only check estimations with one of the given tags. Can be repeated.
+    /// Leave empty to check estimations of all subsystems.
+    #[clap(long, arg_enum)]
+    only_tag: Vec<Tag>,
+    /// Relative change above which estimations with the given tag are
+    /// reported, in the form `<tag>=<threshold>`, e.g. `crypto=0.5`. Can be
+    /// repeated. Other estimations use a threshold of 0.1.
+    #[clap(long)]
+    tag_threshold: Vec<TagThreshold>,
 }
 
+/// Relative change above which estimations are reported, unless overwritten
+/// for their tag with `--tag-threshold`.
+const DEFAULT_THRESHOLD: f64 = 0.1;
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
 pub(crate) enum Status {
     Ok = 0,
@@ -104,8 +118,24 @@ pub(crate) fn create_report(db: &Db, config: &CheckConfig) -> anyhow::Result<Zul
         let estimations_b = rows_b.into_iter().map(|row| row.name).collect::<BTreeSet<_>>();
         estimations_a.intersection(&estimations_b).cloned().collect()
     };
-    let warnings =
-        estimation_changes(db, &estimations, &commit_before, &commit_after, 0.1, config.metric)?;
+    let estimations: Vec<String> = if config.only_tag.is_empty() {
+        estimations
+    } else {
+        estimations
+            .into_iter()
+            .filter(|name| {
+                Tag::of_estimation(name).map_or(false, |tag| config.only_tag.contains(&tag))
+            })
+            .collect()
+    };
+    let warnings = estimation_changes(
+        db,
+        &estimations,
+        &commit_before,
+        &commit_after,
+        &config.tag_threshold,
+        config.metric,
+    )?;
 
     let warnings_uncertain = estimation_uncertain_changes(
         db,
@@ -130,13 +160,17 @@ fn estimation_changes(
     estimation_names: &[String],
     commit_before: &str,
     commit_after: &str,
-    tolerance: f64,
+    tag_thresholds: &[TagThreshold],
     metric: Metric,
 ) -> anyhow::Result<Vec<Notice>> {
     let mut warnings = Vec::new();
     for name in estimation_names {
         let b = &EstimationRow::get(db, name, commit_before, metric)?[0];
         let a = &EstimationRow::get(db, name, commit_after, metric)?[0];
+        let tolerance = b
+            .tag()
+            .and_then(|tag| tag_thresholds.iter().rev().find(|t| t.tag == tag))
+            .map_or(DEFAULT_THRESHOLD, |t| t.threshold);
         let rel_change = (b.gas - a.gas).abs() / b.gas;
         if rel_change > tolerance {
             warnings.push(Notice::RelativeChange(RelativeChange {
@@ -196,10 +230,33 @@ mod tests {
             commit_before: None,
             commit_after: None,
             estimations: estimations.iter().map(|&s| s.to_owned()).collect(),
+            only_tag: vec![],
+            tag_threshold: vec![],
         };
         create_report(&db, &config).unwrap()
     }
 
+    #[track_caller]
+    fn changed_estimations(
+        input: &str,
+        only_tag: Vec<Tag>,
+        tag_threshold: Vec<TagThreshold>,
+    ) -> Vec<String> {
+        let db = Db::test_with_data(input);
+        let config = CheckConfig {
+            zulip_stream: None,
+            zulip_user: None,
+            metric: Metric::ICount,
+            commit_before: None,
+            commit_after: None,
+            estimations: vec![],
+            only_tag,
+            tag_threshold,
+        };
+        let report = create_report(&db, &config).unwrap();
+        report.changes().iter().map(|change| change.estimation.clone()).collect()
+    }
+
     #[test]
     fn test_check_command() {
         let input_a = r#"
@@ -264,4 +321,36 @@ mod tests {
         let report = generate_test_report(&input_b, Metric::Time, &[]);
         insta::assert_snapshot!(report.to_string());
     }
+
+    #[test]
+    fn test_check_tags() {
+        let input = r#"
+        0000a
+        {"computed_in":{"nanos":0,"secs":1},"name":"LogBase","result":{"gas":1000000000.0,"instructions":8000.0,"io_r_bytes":0.0,"io_w_bytes":0.0,"metric":"icount","uncertain_reason":null}}
+        {"computed_in":{"nanos":0,"secs":1},"name":"Sha256Base","result":{"gas":1000000000.0,"instructions":8000.0,"io_r_bytes":0.0,"io_w_bytes":0.0,"metric":"icount","uncertain_reason":null}}
+        {"computed_in":{"nanos":0,"secs":1},"name":"StorageReadBase","result":{"gas":1000000000.0,"instructions":8000.0,"io_r_bytes":0.0,"io_w_bytes":0.0,"metric":"icount","uncertain_reason":null}}
+
+        WAIT
+
+        0001a
+        {"computed_in":{"nanos":0,"secs":1},"name":"LogBase","result":{"gas":1200000000.0,"instructions":9600.0,"io_r_bytes":0.0,"io_w_bytes":0.0,"metric":"icount","uncertain_reason":null}}
+        {"computed_in":{"nanos":0,"secs":1},"name":"Sha256Base","result":{"gas":1300000000.0,"instructions":10400.0,"io_r_bytes":0.0,"io_w_bytes":0.0,"metric":"icount","uncertain_reason":null}}
+        {"computed_in":{"nanos":0,"secs":1},"name":"StorageReadBase","result":{"gas":1150000000.0,"instructions":9200.0,"io_r_bytes":0.0,"io_w_bytes":0.0,"metric":"icount","uncertain_reason":null}}
+        "#;
+
+        assert_eq!(
+            changed_estimations(input, vec![], vec![]),
+            ["LogBase", "Sha256Base", "StorageReadBase"]
+        );
+        assert_eq!(changed_estimations(input, vec![Tag::Storage], vec![]), ["StorageReadBase"]);
+        assert_eq!(
+            changed_estimations(input, vec![Tag::Storage, Tag::Crypto], vec![]),
+            ["Sha256Base", "StorageReadBase"]
+        );
+        let thresholds = vec![
+            TagThreshold { tag: Tag::Crypto, threshold: 0.5 },
+            TagThreshold { tag: Tag::Storage, threshold: 0.12 },
+        ];
+        assert_eq!(changed_estimations(input, vec![], thresholds), ["LogBase", "StorageReadBase"]);
+    }
 }
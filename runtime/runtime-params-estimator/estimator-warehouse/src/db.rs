@@ -5,6 +5,7 @@ use std::path::Path;
 use chrono::NaiveDateTime;
 use rusqlite::{params, Connection, Row};
 
+use crate::tag::Tag;
 use crate::Metric;
 
 /// Wrapper around database connection
@@ -60,6 +61,15 @@ pub(crate) struct ParameterRow {
     pub protocol_version: u32,
 }
 
+/// A single data row in the bundle table
+#[derive(Debug, PartialEq)]
+pub(crate) struct BundleRow {
+    /// Which git commit the bundled run has been estimated on
+    pub commit_hash: String,
+    /// Location of the reproducibility bundle written by the estimator
+    pub path: String,
+}
+
 impl EstimationRow {
     const SELECT_ALL: &'static str =
         "name,gas,parameter,wall_clock_time,icount,io_read,io_write,uncertain_reason,commit_hash";
@@ -149,6 +159,10 @@ impl EstimationRow {
         let dt = db.conn.query_row::<Option<NaiveDateTime>, _, _>(sql, [], |row| row.get(0))?;
         Ok(dt)
     }
+    /// The subsystem this estimation belongs to, derived from its name.
+    pub fn tag(&self) -> Option<Tag> {
+        Tag::of_estimation(&self.name)
+    }
     fn is_metric(&self, metric: Metric) -> bool {
         match metric {
             Metric::ICount => self.icount.is_some(),
@@ -183,6 +197,26 @@ impl ParameterRow {
     }
 }
 
+impl BundleRow {
+    pub(crate) fn insert(&self, db: &Db) -> anyhow::Result<()> {
+        db.conn.execute(
+            "INSERT INTO bundle(commit_hash,path) values (?1,?2)",
+            params![self.commit_hash, self.path],
+        )?;
+        Ok(())
+    }
+    /// Returns all bundles recorded for a commit, in the order of recording.
+    pub fn select_by_commit(db: &Db, commit: &str) -> anyhow::Result<Vec<Self>> {
+        let mut stmt = db.conn.prepare(
+            "SELECT commit_hash,path FROM bundle WHERE commit_hash = ?1 ORDER BY date ASC;",
+        )?;
+        let data = stmt
+            .query_map([commit], |row| Ok(Self { commit_hash: row.get(0)?, path: row.get(1)? }))?
+            .collect::<Result<Vec<_>, rusqlite::Error>>()?;
+        Ok(data)
+    }
+}
+
 impl Metric {
     fn condition(&self) -> &'static str {
         match self {
@@ -227,6 +261,7 @@ mod tests {
                     let conf = ImportConfig {
                         commit_hash: Some(commit_hash.to_string()),
                         protocol_version: None,
+                        bundle: None,
                     };
                     db.import_json_lines(&conf, input).unwrap();
                 }
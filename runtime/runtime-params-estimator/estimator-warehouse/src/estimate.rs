@@ -17,6 +17,10 @@ pub(crate) struct EstimateConfig {
     /// Comma separated list of metrics to use in estimation.
     #[clap(long, default_value = "icount,time", possible_values = &["icount", "time"], use_value_delimiter = true)]
     pub metrics: Vec<String>,
+    /// Directory in which the estimator writes one reproducibility bundle per
+    /// metric. The bundle locations are recorded in the warehouse.
+    #[clap(long)]
+    pub bundle_dir: Option<String>,
 }
 
 pub(crate) fn run_estimation(db: &Db, config: &EstimateConfig) -> anyhow::Result<()> {
@@ -69,26 +73,46 @@ pub(crate) fn run_estimation(db: &Db, config: &EstimateConfig) -> anyhow::Result
             eprintln!("Running as non-root, storage related costs might be inaccurate because OS caches cannot be dropped");
         };
 
+        let bundle = bundle_path(config, &commit_hash, "time");
+        let bundle_arg = bundle_arg(&bundle);
         let estimation_output =
             cmd!(sh,
-                "{estimator_binary} --iters {iters} --warmup-iters {warmup_iters} --json-output --home {estimator_home} {maybe_drop_cache...} --metric time"
+                "{estimator_binary} --iters {iters} --warmup-iters {warmup_iters} --json-output --home {estimator_home} {maybe_drop_cache...} {bundle_arg...} --metric time"
             ).read()?;
         db.import_json_lines(
-            &ImportConfig { commit_hash: Some(commit_hash.clone()), protocol_version: None },
+            &ImportConfig {
+                commit_hash: Some(commit_hash.clone()),
+                protocol_version: None,
+                bundle,
+            },
             &estimation_output,
         )?;
     }
 
     if config.metrics.iter().any(|m| m == "icount") {
+        let bundle = bundle_path(config, &commit_hash, "icount");
+        let bundle_arg = bundle_arg(&bundle);
         let estimation_output =
             cmd!(sh,
-                "{estimator_binary} --iters {iters} --warmup-iters {warmup_iters} --json-output --home {estimator_home} --metric icount --docker --full"
+                "{estimator_binary} --iters {iters} --warmup-iters {warmup_iters} --json-output --home {estimator_home} {bundle_arg...} --metric icount --docker --full"
             ).read()?;
         db.import_json_lines(
-            &ImportConfig { commit_hash: Some(commit_hash), protocol_version: None },
+            &ImportConfig { commit_hash: Some(commit_hash), protocol_version: None, bundle },
             &estimation_output,
         )?;
     }
 
     Ok(())
 }
+
+/// Where the bundle for an estimation run with the given metric is stored.
+fn bundle_path(config: &EstimateConfig, commit_hash: &str, metric: &str) -> Option<String> {
+    config.bundle_dir.as_ref().map(|dir| format!("{dir}/{commit_hash}-{metric}"))
+}
+
+fn bundle_arg(bundle: &Option<String>) -> Vec<String> {
+    match bundle {
+        Some(path) => vec!["--bundle-dir".to_owned(), path.clone()],
+        None => vec![],
+    }
+}
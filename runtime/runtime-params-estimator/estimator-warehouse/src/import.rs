@@ -1,4 +1,4 @@
-use crate::db::{Db, EstimationRow};
+use crate::db::{BundleRow, Db, EstimationRow};
 use anyhow::Context;
 use clap::Parser;
 use serde::Deserialize;
@@ -15,6 +15,10 @@ pub(crate) struct ImportConfig {
     /// should be associated with.
     #[clap(long)]
     pub protocol_version: Option<u32>,
+    /// Optional path to the reproducibility bundle written by the estimator
+    /// with `--bundle-dir`. It is linked to the commit given by `--commit-hash`.
+    #[clap(long)]
+    pub bundle: Option<String>,
 }
 
 /// Estimation result as produced by the params-estimator
@@ -39,6 +43,12 @@ impl Db {
         for line in input.lines() {
             self.import(info, &line)?;
         }
+        if let Some(path) = &info.bundle {
+            let commit_hash = info.commit_hash.as_ref().with_context(|| {
+                "Missing --commit-hash argument while importing a bundle".to_owned()
+            })?;
+            BundleRow { commit_hash: commit_hash.clone(), path: path.clone() }.insert(self)?;
+        }
         Ok(())
     }
 
@@ -66,7 +76,7 @@ impl Db {
 
 #[cfg(test)]
 mod test {
-    use crate::db::{Db, EstimationRow};
+    use crate::db::{BundleRow, Db, EstimationRow};
     use crate::import::ImportConfig;
     use crate::Metric;
 
@@ -103,6 +113,7 @@ mod test {
         let info = ImportConfig {
             commit_hash: Some("53a3ccf3ef07".to_owned()),
             protocol_version: Some(0),
+            bundle: None,
         };
         assert_import(input, &info, &expected, Metric::Time);
     }
@@ -139,9 +150,28 @@ mod test {
         let info = ImportConfig {
             commit_hash: Some("53a3ccf3ef07".to_owned()),
             protocol_version: Some(0),
+            bundle: None,
         };
         assert_import(input, &info, &expected, Metric::ICount);
     }
+    #[test]
+    fn test_import_bundle() {
+        let input = r#"
+            {"computed_in":{"nanos":826929296,"secs":0},"name":"LogBase","result":{"gas":441061948,"metric":"time","time_ns":441.061948,"uncertain_reason":null}}
+        "#;
+        let info = ImportConfig {
+            commit_hash: Some("53a3ccf3ef07".to_owned()),
+            protocol_version: None,
+            bundle: Some("/bundles/53a3ccf3ef07-time".to_owned()),
+        };
+        let db = Db::test();
+        db.import_json_lines(&info, input).unwrap();
+        let expected = vec![BundleRow {
+            commit_hash: "53a3ccf3ef07".to_owned(),
+            path: "/bundles/53a3ccf3ef07-time".to_owned(),
+        }];
+        assert_eq!(expected, BundleRow::select_by_commit(&db, "53a3ccf3ef07").unwrap());
+    }
     #[track_caller]
     fn assert_import(
         input: &str,
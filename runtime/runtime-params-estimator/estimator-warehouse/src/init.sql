@@ -15,3 +15,8 @@ CREATE TABLE IF NOT EXISTS parameter (
     gas REAL NOT NULL,                              -- gas cost
     protocol_version INTEGER                       -- protocol version for which the parameter is valid
 );
+CREATE TABLE IF NOT EXISTS bundle (
+    date TEXT NOT NULL DEFAULT (datetime('now')),   -- when the bundle has been recorded
+    commit_hash TEXT NOT NULL,                      -- which git commit the bundled run has been estimated on
+    path TEXT NOT NULL                              -- location of the reproducibility bundle written by the estimator
+);
@@ -11,6 +11,7 @@ mod check;
 mod db;
 mod estimate;
 mod import;
+mod tag;
 mod zulip;
 
 #[derive(clap::Parser)]
@@ -0,0 +1,112 @@
+//! Classification of estimations by the subsystem they measure.
+
+use std::str::FromStr;
+
+/// Subsystem an estimation belongs to. Used to filter checks, so that changes
+/// in one subsystem are not drowned in noise from unrelated estimations.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, clap::ArgEnum)]
+pub(crate) enum Tag {
+    /// Trie, RocksDB and IO related estimations.
+    Storage,
+    /// Contract execution, compilation and host function overhead.
+    Wasm,
+    /// Actions and action receipts.
+    Actions,
+    /// Hash functions and elliptic curve operations.
+    Crypto,
+    /// Data receipts and promises sent between accounts.
+    Network,
+}
+
+/// Estimation name prefixes and their tag. The first matching prefix wins.
+const TAG_PREFIXES: &[(&str, Tag)] = &[
+    ("Storage", Tag::Storage),
+    ("TouchingTrieNode", Tag::Storage),
+    ("ReadCachedTrieNode", Tag::Storage),
+    ("RocksDb", Tag::Storage),
+    ("IoReadByte", Tag::Storage),
+    ("IoWriteByte", Tag::Storage),
+    ("FunctionCallPerStorageByte", Tag::Storage),
+    ("Sha256", Tag::Crypto),
+    ("Keccak", Tag::Crypto),
+    ("Ripemd160", Tag::Crypto),
+    ("Ecrecover", Tag::Crypto),
+    ("Ed25519", Tag::Crypto),
+    ("AltBn128", Tag::Crypto),
+    ("DataReceiptCreation", Tag::Network),
+    ("DataDependency", Tag::Network),
+    ("Promise", Tag::Network),
+    ("Action", Tag::Actions),
+    ("ApplyBlock", Tag::Actions),
+    ("HostFunctionCall", Tag::Wasm),
+    ("BlockGas", Tag::Wasm),
+    ("WasmInstruction", Tag::Wasm),
+    ("ReadMemory", Tag::Wasm),
+    ("WriteMemory", Tag::Wasm),
+    ("ReadRegister", Tag::Wasm),
+    ("WriteRegister", Tag::Wasm),
+    ("Utf8Decoding", Tag::Wasm),
+    ("Utf16Decoding", Tag::Wasm),
+    ("Log", Tag::Wasm),
+    ("Validator", Tag::Wasm),
+    ("Contract", Tag::Wasm),
+    ("DeployBytes", Tag::Wasm),
+    ("GasMetering", Tag::Wasm),
+];
+
+impl Tag {
+    /// Returns the tag of an estimation, or `None` for estimations that don't
+    /// belong to a single subsystem, like the CPU benchmarks.
+    pub(crate) fn of_estimation(name: &str) -> Option<Tag> {
+        TAG_PREFIXES.iter().find(|(prefix, _)| name.starts_with(prefix)).map(|&(_, tag)| tag)
+    }
+}
+
+/// Relative change threshold for the estimations of one tag, as passed on the
+/// command line in the form `<tag>=<threshold>`, e.g. `crypto=0.5`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct TagThreshold {
+    pub tag: Tag,
+    pub threshold: f64,
+}
+
+impl FromStr for TagThreshold {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (tag, threshold) =
+            s.split_once('=').ok_or_else(|| format!("expected <tag>=<threshold>, got `{s}`"))?;
+        let tag = <Tag as clap::ArgEnum>::from_str(tag, true)?;
+        let threshold = threshold
+            .parse::<f64>()
+            .map_err(|err| format!("invalid threshold `{threshold}`: {err}"))?;
+        Ok(Self { tag, threshold })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Tag, TagThreshold};
+
+    #[test]
+    fn test_tag_of_estimation() {
+        assert_eq!(Tag::of_estimation("StorageWriteBase"), Some(Tag::Storage));
+        assert_eq!(Tag::of_estimation("TouchingTrieNodeRead"), Some(Tag::Storage));
+        assert_eq!(Tag::of_estimation("Keccak256Byte"), Some(Tag::Crypto));
+        assert_eq!(Tag::of_estimation("ActionReceiptCreation"), Some(Tag::Actions));
+        assert_eq!(Tag::of_estimation("DataReceiptCreationBase"), Some(Tag::Network));
+        assert_eq!(Tag::of_estimation("LogBase"), Some(Tag::Wasm));
+        assert_eq!(Tag::of_estimation("CpuBenchmarkSha256"), None);
+    }
+
+    #[test]
+    fn test_parse_tag_threshold() {
+        assert_eq!(
+            "crypto=0.5".parse::<TagThreshold>(),
+            Ok(TagThreshold { tag: Tag::Crypto, threshold: 0.5 })
+        );
+        assert!("crypto".parse::<TagThreshold>().is_err());
+        assert!("crypto=high".parse::<TagThreshold>().is_err());
+        assert!("gpu=0.5".parse::<TagThreshold>().is_err());
+    }
+}
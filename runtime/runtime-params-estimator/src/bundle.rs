@@ -0,0 +1,66 @@
+//! Reproducibility bundles for estimator runs.
+//!
+//! A bundle is a directory containing everything needed to reproduce an
+//! estimation run later on: the parameters the runtime used, the commit the
+//! estimator was built from, the exact CLI flags, a checksum of the state dump
+//! the estimations ran against, and the resulting cost table.
+
+use anyhow::Context;
+use near_primitives::runtime::config_store::RuntimeConfigStore;
+use near_primitives::version::PROTOCOL_VERSION;
+use runtime_params_estimator::CostTable;
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::io;
+use std::path::Path;
+
+/// Files in the estimator home directory that define the state the estimations
+/// run against. Databases are excluded, they are fully determined by these.
+const STATE_DUMP_FILES: &[&str] = &["genesis.json", "genesis_roots", "state_dump"];
+
+/// Name of the file inside the bundle that holds the run metadata.
+const BUNDLE_META_FILE: &str = "bundle.json";
+
+/// Writes a bundle for this run into `dir`, creating it if necessary.
+///
+/// `commit` is the full git hash the estimator was built from, if known.
+pub(crate) fn write_bundle(
+    dir: &Path,
+    state_dump_path: &Path,
+    commit: Option<&str>,
+    cost_table: &CostTable,
+) -> anyhow::Result<()> {
+    let timestamp = chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Secs, true);
+    fs::create_dir_all(dir)
+        .with_context(|| format!("failed to create bundle directory {}", dir.display()))?;
+
+    let runtime_config = RuntimeConfigStore::new(None).get_config(PROTOCOL_VERSION).clone();
+    fs::write(dir.join("parameters.json"), serde_json::to_string_pretty(&*runtime_config)?)?;
+    fs::write(dir.join("costs.txt"), cost_table.to_string())?;
+
+    let meta = serde_json::json!({
+        "date": timestamp,
+        "commit": commit,
+        "protocol_version": PROTOCOL_VERSION,
+        "args": std::env::args().collect::<Vec<_>>(),
+        "state_dump_checksum": state_dump_checksum(state_dump_path)?,
+    });
+    fs::write(dir.join(BUNDLE_META_FILE), serde_json::to_string_pretty(&meta)?)?;
+    Ok(())
+}
+
+/// SHA-256 over all state dump files present in the estimator home directory.
+fn state_dump_checksum(state_dump_path: &Path) -> anyhow::Result<String> {
+    let mut hasher = Sha256::new();
+    for file_name in STATE_DUMP_FILES {
+        let path = state_dump_path.join(file_name);
+        if !path.exists() {
+            continue;
+        }
+        hasher.update(file_name.as_bytes());
+        let mut file = fs::File::open(&path)
+            .with_context(|| format!("failed to open state dump file {}", path.display()))?;
+        io::copy(&mut file, &mut hasher)?;
+    }
+    Ok(hex::encode(hasher.finalize()))
+}
@@ -40,4 +40,7 @@ pub struct Config {
     pub drop_os_cache: bool,
     /// Use in-memory test DB, useful to avoid variance caused by DB.
     pub in_memory_db: bool,
+    /// Record trie reads as for a state witness and report the witness size
+    /// next to each estimation.
+    pub record_storage: bool,
 }
@@ -79,6 +79,21 @@ pub enum Cost {
     /// a transaction. Subtract base costs and apply least-squares on the
     /// results to find the per-byte costs.
     ActionDeployContractPerByte,
+    /// Estimates `action_creation_config.state_init_cost`, which is charged
+    /// for every initial state entry of a `CreateAccountWithContract` action.
+    ///
+    /// Estimation: Measure creating accounts with a small contract and many
+    /// tiny state entries. Subtract the same without state entries and divide
+    /// by the number of entries.
+    ActionStateInitBase,
+    /// Estimates `action_creation_config.state_init_cost_per_byte`, which is
+    /// charged for every byte of keys and values in the initial state of a
+    /// `CreateAccountWithContract` action.
+    ///
+    /// Estimation: Measure creating accounts with a small contract and a few
+    /// large state entries. Subtract the same with tiny entries and divide by
+    /// the number of additional bytes.
+    ActionStateInitPerByte,
     /// Estimates `action_creation_config.function_call_cost`, which is the base
     /// cost for adding a `FunctionCallAction` to a receipt. It aims to account
     /// for all costs of calling a function that are already known on the caller
@@ -169,6 +184,18 @@ pub enum Cost {
     /// contract function that does nothing. Divide the difference by the number
     /// of host function calls.
     HostFunctionCall,
+    /// Estimates `block_gas_price_base`, charged instead of `base` for calls
+    /// to the `block_gas_price()` host function.
+    ///
+    /// Estimation: Same as for `HostFunctionCall`, but calling
+    /// `block_gas_price()`. Writing the result to WASM memory is included.
+    BlockGasPriceBase,
+    /// Estimates `block_gas_limit_base`, charged instead of `base` for calls
+    /// to the `block_gas_limit()` host function.
+    ///
+    /// Estimation: Same as for `HostFunctionCall`, but calling
+    /// `block_gas_limit()`.
+    BlockGasLimitBase,
     /// Estimates `wasm_config.regular_op_cost` which is charged for every
     /// executed WASM operation in function calls, as counted dynamically during
     /// execution.
@@ -342,6 +369,23 @@ pub enum Cost {
     /// Estimates `keccak512_byte`, the cost charged per input byte in calls to the
     /// keccak512-hash host function.
     Keccak512Byte,
+    /// Estimates `sha256_batch_base`, the cost charged once per call to the
+    /// batched sha256-hash host function.
+    ///
+    /// Estimation: Hash a batch of 10 small values many times and divide by
+    /// the number of calls. The per value overhead of the batch is included.
+    Sha256BatchBase,
+    /// Estimates `sha256_batch_byte`, the cost charged per input byte in calls
+    /// to the batched sha256-hash host function.
+    Sha256BatchByte,
+    /// Estimates `keccak256_batch_base`, the cost charged once per call to the
+    /// batched keccak256-hash host function.
+    ///
+    /// Estimation: Same as for `Sha256BatchBase`.
+    Keccak256BatchBase,
+    /// Estimates `keccak256_batch_byte`, the cost charged per input byte in
+    /// calls to the batched keccak256-hash host function.
+    Keccak256BatchByte,
     /// Estimates `ripemd160_base`, the cost charged once per call to the
     /// ripemd160-hash host function.
     Ripemd160Base,
@@ -354,6 +398,19 @@ pub enum Cost {
     /// function `ecrecover` to verify an ECDSA signature and extract the
     /// signer.
     EcrecoverBase,
+    /// Estimates `ecrecover_batch_base`, the cost charged once per call to the
+    /// host function `ecrecover_batch`.
+    ///
+    /// Estimation: Call `ecrecover_batch` with an empty batch many times and
+    /// divide by the number of calls.
+    EcrecoverBatchBase,
+    /// Estimates `ecrecover_batch_element`, the cost charged per signature in
+    /// calls to `ecrecover_batch`.
+    ///
+    /// Estimation: Recover a batch of 10 signatures many times and divide by
+    /// the total number of signatures. The per call overhead is negligible
+    /// compared to the elliptic curve operations.
+    EcrecoverBatchElement,
     /// Estimates `ed25519_verify_base`, which covers the base cost of the host
     /// function `ed25519_verify` to verify an ED25519 signature.
     ///
@@ -474,9 +531,22 @@ pub enum Cost {
     /// (10kiB) and divide the cost by total key bytes.
     StorageHasKeyByte,
 
-    /// DEPRECATED: Was charged in `storage_iter_prefix`
+    // `storage_iter_prefix_page` seeks to the start key in the trie and the
+    // in-memory changes, then reads keys and values in order until the limit
+    // or the end of the prefix is reached.
+    /// Estimates `ExtCost::storage_iter_create_prefix_base` which is charged
+    /// once per call to `storage_iter_prefix_page`. It was charged in the
+    /// deprecated `storage_iter_prefix` before.
+    ///
+    /// Estimation: Contract call that reads N pages of one small value each
+    /// and divide the cost by N. This includes reading the value.
     StorageIterCreatePrefixBase,
-    /// DEPRECATED: Was charged in `storage_iter_prefix`
+    /// Estimates `ExtCost::storage_iter_create_prefix_byte` which is charged
+    /// for each byte of the prefix and the start key of
+    /// `storage_iter_prefix_page` calls.
+    ///
+    /// Estimation: Contract call that reads N pages of one small value each,
+    /// with a big prefix (10kiB), and divide the cost by total prefix bytes.
     StorageIterCreatePrefixByte,
     /// DEPRECATED: Was charged in `storage_iter_range`
     StorageIterCreateRangeBase,
@@ -484,11 +554,23 @@ pub enum Cost {
     StorageIterCreateFromByte,
     /// DEPRECATED: Was charged in `storage_iter_range`
     StorageIterCreateToByte,
-    /// DEPRECATED: Was charged in `storage_iter_next`
+    /// Estimates `ExtCost::storage_iter_next_base` which is charged for each
+    /// key-value pair read by `storage_iter_prefix_page`.
+    ///
+    /// Estimation: Contract call that reads a page of N small values and
+    /// divide the cost by N.
     StorageIterNextBase,
-    /// DEPRECATED: Was charged in `storage_iter_next`
+    /// Estimates `ExtCost::storage_iter_next_key_byte` which is charged for
+    /// each byte in keys read by `storage_iter_prefix_page`.
+    ///
+    /// Estimation: Contract call that reads a page of N small values with big
+    /// keys (10kiB) and divide the cost by total number of key bytes.
     StorageIterNextKeyByte,
-    /// DEPRECATED: Was charged in `storage_iter_next`
+    /// Estimates `ExtCost::storage_iter_next_value_byte` which is charged for
+    /// each byte in values read by `storage_iter_prefix_page`.
+    ///
+    /// Estimation: Contract call that reads a page of N big values (10kiB) and
+    /// divide the cost by total number of value bytes.
     StorageIterNextValueByte,
 
     /// Estimates `touching_trie_node` which is charged when smart contracts
@@ -512,6 +594,19 @@ pub enum Cost {
     /// for this are a bit involved but roughly speaking, it just forces values
     /// out of CPU caches so that they are always read from memory.
     ReadCachedTrieNode,
+    /// Like `ReadCachedTrieNode` but for reads served by the shard cache while
+    /// view calls read from the same `TrieCache` in parallel. This is not a
+    /// parameter, it quantifies how much the lock around the shard cache slows
+    /// down chunk application under view load.
+    ///
+    /// Estimation: Load the largest nodes that the shard cache still keeps into
+    /// a cache shared by several storages. Threads emulating view calls keep
+    /// reading these nodes through their own storage while the main thread
+    /// measures reads through the storage used for chunk application. The 90th
+    /// percentile of measured samples is taken as the final cost. Only
+    /// meaningful with `--metric time`, instruction counts do not reflect
+    /// waiting on the lock.
+    ReadCachedTrieNodeContended,
     /// Helper estimation for `TouchingTrieNode`
     ///
     /// Estimation: Prepare an account that has many keys stored that are
@@ -530,13 +625,20 @@ pub enum Cost {
     /// `promise_and`. This should cover the base cost for creating receipt
     /// dependencies.
     ///
-    /// Estimation: Currently not estimated
+    /// Estimation: Measure a function that calls `promise_and` 100k times on
+    /// two promises. Subtract the cost of an empty function call and the
+    /// per-promise cost of the two promises, as estimated in
+    /// `PromiseAndPerPromise`, then divide by the number of calls.
     PromiseAndBase,
     /// Estimates `promise_and_per_promise` which is charged for every promise in
     /// calls to `promise_and`. This should cover the additional cost for each
-    /// extra receipt in the dependency.
+    /// extra receipt in the dependency. Note that the runtime charges it per
+    /// byte of promise indices, so 8 times per promise.
     ///
-    /// Estimation: Currently not estimated
+    /// Estimation: Measure two functions that each call `promise_and` 100k
+    /// times, one of them on two promises and the other on 1000 promises. The
+    /// difference in execution cost is divided by the difference in charged
+    /// bytes.
     PromiseAndPerPromise,
     /// Estimates `promise_return` which is charged when calling
     /// `promise_return`. This should cover the cost of the dependency between a
@@ -596,6 +698,16 @@ pub enum Cost {
     /// produces the steepest line.
     ContractCompileBaseV2,
     ContractCompileBytesV2,
+    /// Per-byte compilation cost of contracts that max out one of the contract
+    /// limits in `VMLimitConfig`: number of functions, globals, table size and
+    /// custom section bytes. This should stay below `ContractCompileBytesV2`,
+    /// otherwise the limits allow contracts that are more expensive to compile
+    /// than what deployment fees account for.
+    ///
+    /// Estimation: Compiles one contract per limit, filled up to the limit,
+    /// and computes the per-byte cost relative to the smallest possible
+    /// contract, like `ContractCompileBytesV2` does. Reports the maximum.
+    ContractCompileBytesAtLimits,
     /// The cost of contract deployment per byte, without the compilation cost.
     ///
     /// Estimation: Measure the deployment costs of two data-only contracts,
@@ -636,6 +748,36 @@ pub enum Cost {
     /// section and the other contract is very small. Divide the difference in
     /// size.
     FunctionCallPerStorageByte,
+    /// Base cost of resolving the data dependencies of a callback that waits
+    /// on several promises, independent of how many there are. The runtime
+    /// covers this with `data_receipt_creation_config.base_cost`, which is
+    /// charged per dependency. A significant base cost here indicates that
+    /// joint promises with few inputs are undercharged.
+    ///
+    /// Estimation: Like `DataReceiptCreationBase`, measure a function that
+    /// creates a callback on joined promises and subtract the same function
+    /// without callback. Do this for 10 and for 1000 joined promises and
+    /// extrapolate the linear function to 0 inputs.
+    DataDependencyBase,
+    /// Cost per input of resolving the data dependencies of a callback that
+    /// waits on several promises. Compare with `DataReceiptCreationBase`,
+    /// which is what the runtime charges per dependency.
+    ///
+    /// Estimation: See `DataDependencyBase`. The slope of the linear function.
+    DataDependencyPerInput,
+    /// Cost of a `ft_transfer` action on the fungible token contract from
+    /// near-sdk-rs examples, between two registered accounts. This is not a
+    /// parameter. It tracks a real contract workload that reads the same trie
+    /// keys more than once per function call: the contract state and the
+    /// balances are read and then written back, and writing a value looks up
+    /// the evicted value first. `TrieUpdate` remembers these lookups within a
+    /// chunk, so repeated reads do not walk the trie again.
+    ///
+    /// Estimation: Deploy and initialize the contract on the signer account and
+    /// register a receiver. Then measure a transaction that calls `ft_transfer`
+    /// n+1 times on the signer's own contract, subtract a transaction that
+    /// calls it once, and divide by n.
+    FtTransfer,
     GasMeteringBase,
     GasMeteringOp,
     /// Cost of inserting a new value directly into a RocksDB instance.
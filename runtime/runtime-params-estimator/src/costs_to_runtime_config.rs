@@ -41,6 +41,7 @@ pub fn costs_to_runtime_config(cost_table: &CostTable) -> anyhow::Result<Runtime
             grow_mem_cost: 1,
             regular_op_cost: u32::try_from(regular_op_cost).unwrap(),
             limit_config: vm_limit_config,
+            reuse_memory: false,
         },
         account_creation_config: AccountCreationConfig::default(),
     };
@@ -78,6 +79,10 @@ fn runtime_fees_config(cost_table: &CostTable) -> anyhow::Result<RuntimeFeesConf
             },
             delete_key_cost: fee(Cost::ActionDeleteKey)?,
             delete_account_cost: fee(Cost::ActionDeleteAccount)?,
+            #[cfg(feature = "protocol_feature_create_account_with_contract")]
+            state_init_cost: fee(Cost::ActionStateInitBase)?,
+            #[cfg(feature = "protocol_feature_create_account_with_contract")]
+            state_init_cost_per_byte: fee(Cost::ActionStateInitPerByte)?,
         },
         ..actual_fees_config.clone()
     };
@@ -91,6 +96,10 @@ fn ext_costs_config(cost_table: &CostTable) -> anyhow::Result<ExtCostsConfig> {
 
     let res = ExtCostsConfig {
         base: get(Cost::HostFunctionCall)?,
+        #[cfg(feature = "protocol_feature_block_gas_info")]
+        block_gas_price_base: get(Cost::BlockGasPriceBase)?,
+        #[cfg(feature = "protocol_feature_block_gas_info")]
+        block_gas_limit_base: get(Cost::BlockGasLimitBase)?,
         contract_loading_base: 0,
         contract_loading_bytes: 0,
         read_memory_base: get(Cost::ReadMemoryBase)?,
@@ -114,10 +123,22 @@ fn ext_costs_config(cost_table: &CostTable) -> anyhow::Result<ExtCostsConfig> {
         ripemd160_base: get(Cost::Ripemd160Base)?,
         ripemd160_block: get(Cost::Ripemd160Block)?,
         ecrecover_base: get(Cost::EcrecoverBase)?,
+        #[cfg(feature = "protocol_feature_ecrecover_batch")]
+        ecrecover_batch_base: get(Cost::EcrecoverBatchBase)?,
+        #[cfg(feature = "protocol_feature_ecrecover_batch")]
+        ecrecover_batch_element: get(Cost::EcrecoverBatchElement)?,
         #[cfg(feature = "protocol_feature_ed25519_verify")]
         ed25519_verify_base: get(Cost::Ed25519VerifyBase)?,
         #[cfg(feature = "protocol_feature_ed25519_verify")]
         ed25519_verify_byte: get(Cost::Ed25519VerifyByte)?,
+        #[cfg(feature = "protocol_feature_hash_batch")]
+        sha256_batch_base: get(Cost::Sha256BatchBase)?,
+        #[cfg(feature = "protocol_feature_hash_batch")]
+        sha256_batch_byte: get(Cost::Sha256BatchByte)?,
+        #[cfg(feature = "protocol_feature_hash_batch")]
+        keccak256_batch_base: get(Cost::Keccak256BatchBase)?,
+        #[cfg(feature = "protocol_feature_hash_batch")]
+        keccak256_batch_byte: get(Cost::Keccak256BatchByte)?,
         log_base: get(Cost::LogBase)?,
         log_byte: get(Cost::LogByte)?,
         storage_write_base: get(Cost::StorageWriteBase)?,
@@ -133,14 +154,30 @@ fn ext_costs_config(cost_table: &CostTable) -> anyhow::Result<ExtCostsConfig> {
         storage_has_key_base: get(Cost::StorageHasKeyBase)?,
         storage_has_key_byte: get(Cost::StorageHasKeyByte)?,
         // TODO: storage_iter_* operations below are deprecated, so just hardcode zero price,
-        // and remove those operations ASAP.
+        // and remove those operations ASAP. The prefix and next costs are used
+        // again by `storage_iter_prefix_page`.
+        #[cfg(feature = "protocol_feature_storage_iter_prefix_page")]
+        storage_iter_create_prefix_base: get(Cost::StorageIterCreatePrefixBase)?,
+        #[cfg(not(feature = "protocol_feature_storage_iter_prefix_page"))]
         storage_iter_create_prefix_base: 0,
+        #[cfg(feature = "protocol_feature_storage_iter_prefix_page")]
+        storage_iter_create_prefix_byte: get(Cost::StorageIterCreatePrefixByte)?,
+        #[cfg(not(feature = "protocol_feature_storage_iter_prefix_page"))]
         storage_iter_create_prefix_byte: 0,
         storage_iter_create_range_base: 0,
         storage_iter_create_from_byte: 0,
         storage_iter_create_to_byte: 0,
+        #[cfg(feature = "protocol_feature_storage_iter_prefix_page")]
+        storage_iter_next_base: get(Cost::StorageIterNextBase)?,
+        #[cfg(not(feature = "protocol_feature_storage_iter_prefix_page"))]
         storage_iter_next_base: 0,
+        #[cfg(feature = "protocol_feature_storage_iter_prefix_page")]
+        storage_iter_next_key_byte: get(Cost::StorageIterNextKeyByte)?,
+        #[cfg(not(feature = "protocol_feature_storage_iter_prefix_page"))]
         storage_iter_next_key_byte: 0,
+        #[cfg(feature = "protocol_feature_storage_iter_prefix_page")]
+        storage_iter_next_value_byte: get(Cost::StorageIterNextValueByte)?,
+        #[cfg(not(feature = "protocol_feature_storage_iter_prefix_page"))]
         storage_iter_next_value_byte: 0,
         touching_trie_node: get(Cost::TouchingTrieNode)?,
         read_cached_trie_node: get(Cost::ReadCachedTrieNode)?,
@@ -1,12 +1,16 @@
 use near_primitives::shard_layout::ShardUId;
 use std::collections::HashMap;
+use std::sync::atomic::AtomicUsize;
+use std::sync::Arc;
 
 use near_primitives::transaction::SignedTransaction;
-use near_store::{TrieCache, TrieCachingStorage, TrieConfig};
+use near_store::{Store, TrieCache, TrieCachingStorage, TrieConfig};
 use near_vm_logic::ExtCosts;
 
 use crate::config::{Config, GasMetric};
 use crate::gas_cost::GasCost;
+use crate::parallel_receipts::ParallelReceiptsStats;
+use crate::pipeline_latency::PipelineLatencyStats;
 use crate::testbed::RuntimeTestbed;
 use genesis_populate::get_account_id;
 
@@ -16,6 +20,10 @@ use super::transaction_builder::TransactionBuilder;
 pub(crate) struct EstimatorContext<'c> {
     pub(crate) config: &'c Config,
     pub(crate) cached: CachedCosts,
+    /// Size in bytes of the largest state witness produced by a block in the
+    /// testbeds of the current estimation. Only tracked with
+    /// `config.record_storage`.
+    pub(crate) max_recorded_storage_size: Arc<AtomicUsize>,
 }
 
 #[derive(Default)]
@@ -33,6 +41,8 @@ pub(crate) struct CachedCosts {
     pub(crate) apply_block: Option<GasCost>,
     pub(crate) touching_trie_node_read: Option<GasCost>,
     pub(crate) touching_trie_node_write: Option<GasCost>,
+    pub(crate) promise_and_base_per_promise: Option<(GasCost, GasCost)>,
+    pub(crate) data_dependency_base_per_input: Option<(GasCost, GasCost)>,
     #[cfg(feature = "protocol_feature_ed25519_verify")]
     pub(crate) ed25519_verify_base: Option<GasCost>,
 }
@@ -40,12 +50,15 @@ pub(crate) struct CachedCosts {
 impl<'c> EstimatorContext<'c> {
     pub(crate) fn new(config: &'c Config) -> Self {
         let cached = CachedCosts::default();
-        Self { cached, config }
+        Self { cached, config, max_recorded_storage_size: Default::default() }
     }
 
     pub(crate) fn testbed(&mut self) -> Testbed<'_> {
-        let inner =
+        let mut inner =
             RuntimeTestbed::from_state_dump(&self.config.state_dump_path, self.config.in_memory_db);
+        if self.config.record_storage {
+            inner.record_storage(self.max_recorded_storage_size.clone());
+        }
         Testbed {
             config: self.config,
             inner,
@@ -120,6 +133,50 @@ impl Testbed<'_> {
         assert_eq!(block_latency, extra_blocks);
     }
 
+    /// Like `process_block` but before each block with incoming receipts,
+    /// the receipts are additionally executed serially and in parallel
+    /// against the same state. See `crate::parallel_receipts`.
+    pub(crate) fn process_block_comparing_parallel_receipts(
+        &mut self,
+        block: Vec<SignedTransaction>,
+        block_latency: usize,
+        stats: &mut ParallelReceiptsStats,
+    ) -> anyhow::Result<()> {
+        let allow_failures = false;
+        self.inner.process_block(&block, allow_failures);
+        let mut extra_blocks = 0;
+        while self.inner.has_pending_receipts() {
+            stats.add(&self.inner.compare_parallel_receipts()?);
+            self.inner.process_block(&[], allow_failures);
+            extra_blocks += 1;
+        }
+        assert_eq!(block_latency, extra_blocks);
+        Ok(())
+    }
+
+    /// Like `process_block` but measures every stage of the block pipeline,
+    /// for the block and for the blocks executing its receipts. See
+    /// `crate::pipeline_latency`.
+    pub(crate) fn process_block_measuring_pipeline(
+        &mut self,
+        block: Vec<SignedTransaction>,
+        block_latency: usize,
+        stats: &mut PipelineLatencyStats,
+    ) -> anyhow::Result<()> {
+        stats.add_block(self.inner.process_block_pipeline(&block)?);
+        let mut extra_blocks = 0;
+        while self.inner.has_pending_receipts() {
+            stats.add_block(self.inner.process_block_pipeline(&[])?);
+            extra_blocks += 1;
+        }
+        assert_eq!(block_latency, extra_blocks);
+        Ok(())
+    }
+
+    pub(crate) fn store(&mut self) -> Store {
+        self.inner.store()
+    }
+
     pub(crate) fn trie_caching_storage(&mut self) -> TrieCachingStorage {
         let store = self.inner.store();
         let is_view = false;
@@ -38,6 +38,9 @@ pub(crate) struct GasCost {
 pub(crate) struct GasClock {
     start: Instant,
     metric: GasMetric,
+    /// Groups the IO trace of the measured code, annotated with the IO bytes
+    /// counted by QEMU. Used to cross-check the two sources after the run.
+    span: tracing::span::EnteredSpan,
 }
 
 #[derive(Clone, Copy, PartialEq, Eq)]
@@ -52,11 +55,18 @@ impl GasCost {
     }
 
     pub(crate) fn measure(metric: GasMetric) -> GasClock {
+        let span = tracing::debug_span!(
+            target: "io_tracer",
+            "measurement",
+            qemu_r_bytes = tracing::field::Empty,
+            qemu_w_bytes = tracing::field::Empty,
+        )
+        .entered();
         let start = Instant::now();
         if let GasMetric::ICount = metric {
             QemuMeasurement::start_count_instructions();
         };
-        GasClock { start, metric }
+        GasClock { start, metric, span }
     }
 
     /// Creates `GasCost` out of raw numeric value of gas. This is required mostly for
@@ -115,7 +125,7 @@ impl GasCost {
             }
             eprintln!("]");
         }
-        match least_squares_method_gas_cost_pos_neg(xs, ys, verbose) {
+        let (mut base, mut factor) = match least_squares_method_gas_cost_pos_neg(xs, ys, verbose) {
             Ok(res) => res,
             Err((mut pos, neg)) => {
                 // On negative parameters, return positive part and mark as uncertain if necessary
@@ -125,7 +135,19 @@ impl GasCost {
                 }
                 pos
             }
+        };
+        // A linear fit through a curve that goes down for larger inputs is
+        // meaningless, even if its parameters happen to be positive. This
+        // usually means the estimation setup measures something else than
+        // intended, e.g. a cache hit for some of the inputs.
+        if !tolerance.tolerates_curve(xs, ys) {
+            if verbose {
+                eprintln!("Least-squares input is not monotonically increasing");
+            }
+            base.set_uncertain("NON-MONOTONIC");
+            factor.set_uncertain("NON-MONOTONIC");
         }
+        (base, factor)
     }
 
     /// Subtracts two gas costs from each other without panicking on an arithmetic underflow.
@@ -197,10 +219,13 @@ impl GasCost {
 
 /// Defines what negative solutions are allowed in a least-squares result.
 /// Default is all negative values are treated as errors.
+/// Measurements of larger inputs may also be cheaper than those of smaller
+/// inputs by a small margin, to account for noise.
 #[derive(Clone, PartialEq)]
 pub(crate) struct LeastSquaresTolerance {
     base_nn_tolerance: NonNegativeTolerance,
     factor_nn_tolerance: NonNegativeTolerance,
+    monotonic_rel_tolerance: f64,
 }
 
 impl Default for LeastSquaresTolerance {
@@ -208,6 +233,7 @@ impl Default for LeastSquaresTolerance {
         Self {
             base_nn_tolerance: NonNegativeTolerance::Strict,
             factor_nn_tolerance: NonNegativeTolerance::Strict,
+            monotonic_rel_tolerance: LeastSquaresTolerance::DEFAULT_MONOTONIC_REL_TOLERANCE,
         }
     }
 }
@@ -234,6 +260,13 @@ impl LeastSquaresTolerance {
         self.factor_nn_tolerance = NonNegativeTolerance::AbsoluteTolerance(abs_tolerance);
         self
     }
+    /// Tolerate measurements that are cheaper than a measurement for a
+    /// smaller input by up to a factor of the latter
+    #[allow(dead_code)]
+    pub(crate) fn monotonic_rel_tolerance(mut self, rel_tolerance: f64) -> Self {
+        self.monotonic_rel_tolerance = rel_tolerance;
+        self
+    }
 }
 
 /// Defines what negative solutions are allowed in a least-squares result
@@ -248,10 +281,37 @@ pub(crate) enum NonNegativeTolerance {
 }
 
 impl LeastSquaresTolerance {
+    /// Measurement noise rarely exceeds a few percent, a bigger drop in cost
+    /// for larger inputs points to a broken estimation.
+    const DEFAULT_MONOTONIC_REL_TOLERANCE: f64 = 0.05;
+
     fn tolerates(&self, pos: &(GasCost, GasCost), neg: &(GasCost, GasCost)) -> bool {
         self.base_nn_tolerance.tolerates(&pos.0, &neg.0)
             && self.factor_nn_tolerance.tolerates(&pos.1, &neg.1)
     }
+
+    /// Checks that, sorted by input size, no measurement is cheaper than the
+    /// most expensive measurement of a strictly smaller input beyond the
+    /// tolerance.
+    fn tolerates_curve(&self, xs: &[u64], ys: &[GasCost]) -> bool {
+        let mut points: Vec<(u64, Gas)> =
+            xs.iter().copied().zip(ys.iter().map(GasCost::to_gas)).collect();
+        points.sort_unstable();
+        let mut max_seen = 0;
+        let mut max_smaller = 0;
+        let mut prev_x = None;
+        for (x, gas) in points {
+            if prev_x != Some(x) {
+                max_smaller = max_seen;
+                prev_x = Some(x);
+            }
+            if (gas as f64) < max_smaller as f64 * (1.0 - self.monotonic_rel_tolerance) {
+                return false;
+            }
+            max_seen = max_seen.max(gas);
+        }
+        true
+    }
 }
 impl NonNegativeTolerance {
     /// Tolerate negative values if they account for less than 0.1% of the total
@@ -344,6 +404,8 @@ impl GasClock {
         match self.metric {
             GasMetric::ICount => {
                 let qemu = QemuMeasurement::end_count_instructions();
+                self.span.record("qemu_r_bytes", &qemu.io_r_bytes.to_integer());
+                self.span.record("qemu_w_bytes", &qemu.io_w_bytes.to_integer());
                 result.qemu = Some(qemu);
             }
             GasMetric::Time => {
@@ -628,6 +690,39 @@ mod tests {
         check_uncertainty(&xs, &ys, rel_tolerance(0.1, 0.1), true);
     }
 
+    #[test]
+    fn least_squares_method_gas_cost_time_non_monotonic() {
+        let xs = [10, 20, 30, 40];
+
+        // Positive fit, but the cost drops for the third input.
+        let ys = [
+            GasCost::new_time_based(10_000),
+            GasCost::new_time_based(20_000),
+            GasCost::new_time_based(15_000),
+            GasCost::new_time_based(40_000),
+        ];
+        check_uncertainty(&xs, &ys, Default::default(), true);
+        check_uncertainty(
+            &xs,
+            &ys,
+            LeastSquaresTolerance::default().monotonic_rel_tolerance(0.3),
+            false,
+        );
+        let (base, _factor) =
+            GasCost::least_squares_method_gas_cost(&xs, &ys, &Default::default(), false);
+        assert_eq!(base.uncertain.unwrap().reason, "NON-MONOTONIC");
+
+        // Repeated measurements of the same input are not compared to each other.
+        let xs = [10, 10, 20, 20];
+        let ys = [
+            GasCost::new_time_based(10_000),
+            GasCost::new_time_based(8_000),
+            GasCost::new_time_based(21_000),
+            GasCost::new_time_based(19_000),
+        ];
+        check_uncertainty(&xs, &ys, Default::default(), false);
+    }
+
     #[test]
     fn least_squares_method_gas_cost_icount_ok() {
         let xs = [10, 20, 30];
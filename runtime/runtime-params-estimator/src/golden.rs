@@ -0,0 +1,162 @@
+//! Golden tests for the runtime.
+//!
+//! A few canonical workloads are applied on a fresh testbed each, and the
+//! results of every block (status, gas and receipts of each outcome, and the
+//! changed trie keys) are compared to a golden file written by an earlier
+//! run. Estimations only notice changes of the total gas or time, the golden
+//! files also catch a refactoring that changes which receipts are created or
+//! what state is touched.
+//!
+//! After an intended change, rewrite the golden files with `--bless` and
+//! review the diff. Gas depends on the runtime config and on the test
+//! contract, so golden files are only comparable between builds with the same
+//! protocol features.
+
+use crate::config::Config;
+use crate::testbed::RuntimeTestbed;
+use crate::transaction_builder::TransactionBuilder;
+use anyhow::Context;
+use near_primitives::transaction::{
+    Action, CreateAccountAction, FunctionCallAction, SignedTransaction, TransferAction,
+};
+use std::fmt::Write;
+use std::path::Path;
+
+/// A workload is applied as a single block of transactions, followed by
+/// blocks with the resulting receipts until no receipts are left.
+struct Workload {
+    name: &'static str,
+    transactions: fn(&mut TransactionBuilder) -> Vec<SignedTransaction>,
+}
+
+const WORKLOADS: &[Workload] = &[
+    Workload { name: "transfer", transactions: transfer },
+    Workload { name: "create_account", transactions: create_account },
+    Workload { name: "storage_write", transactions: storage_write },
+    Workload { name: "cross_contract_call", transactions: cross_contract_call },
+    Workload { name: "failing_call", transactions: failing_call },
+];
+
+fn transfer(tb: &mut TransactionBuilder) -> Vec<SignedTransaction> {
+    let (sender, receiver) = (tb.account(0), tb.account(1));
+    let actions = vec![Action::Transfer(TransferAction { deposit: 1 })];
+    vec![tb.transaction_from_actions(sender, receiver, actions)]
+}
+
+fn create_account(tb: &mut TransactionBuilder) -> Vec<SignedTransaction> {
+    let sender = tb.account(0);
+    let new_account = format!("golden.{sender}").parse().unwrap();
+    let actions = vec![
+        Action::CreateAccount(CreateAccountAction {}),
+        Action::Transfer(TransferAction { deposit: 10u128.pow(24) }),
+    ];
+    vec![tb.transaction_from_actions(sender, new_account, actions)]
+}
+
+fn storage_write(tb: &mut TransactionBuilder) -> Vec<SignedTransaction> {
+    (0..3u64)
+        .map(|i| {
+            let account = tb.account(i);
+            tb.account_insert_key(account, b"golden", &[i as u8; 10])
+        })
+        .collect()
+}
+
+fn cross_contract_call(tb: &mut TransactionBuilder) -> Vec<SignedTransaction> {
+    let (sender, receiver) = (tb.account(0), tb.account(1));
+    let actions = vec![Action::FunctionCall(FunctionCallAction {
+        method_name: "noop".to_string(),
+        args: vec![],
+        gas: 10u64.pow(18),
+        deposit: 0,
+    })];
+    vec![tb.transaction_from_actions(sender, receiver, actions)]
+}
+
+fn failing_call(tb: &mut TransactionBuilder) -> Vec<SignedTransaction> {
+    let sender = tb.account(0);
+    vec![tb.transaction_from_function_call(sender, "no_such_method", vec![])]
+}
+
+/// Applies all workloads and compares the results to the golden files in
+/// `golden_dir`. With `bless`, the golden files are written instead.
+pub fn check_golden_files(config: &Config, golden_dir: &Path, bless: bool) -> anyhow::Result<()> {
+    let mut mismatches = vec![];
+    for workload in WORKLOADS {
+        let actual = apply_workload(config, workload);
+        let path = golden_dir.join(format!("{}.txt", workload.name));
+        if bless {
+            std::fs::create_dir_all(golden_dir)?;
+            std::fs::write(&path, actual)
+                .with_context(|| format!("failed to write {}", path.display()))?;
+            continue;
+        }
+        let expected = std::fs::read_to_string(&path).with_context(|| {
+            format!("failed to read {}, run with --bless to create it", path.display())
+        })?;
+        if let Err(diff) = compare(&expected, &actual) {
+            eprintln!("{}: {diff}", workload.name);
+            mismatches.push(workload.name);
+        }
+    }
+    anyhow::ensure!(mismatches.is_empty(), "golden files differ for {}", mismatches.join(", "));
+    Ok(())
+}
+
+fn apply_workload(config: &Config, workload: &Workload) -> String {
+    let mut testbed = RuntimeTestbed::from_state_dump(&config.state_dump_path, config.in_memory_db);
+    let mut tb = TransactionBuilder::new(vec![]);
+    let transactions = (workload.transactions)(&mut tb);
+
+    let allow_failures = true;
+    let mut summary = String::new();
+    testbed.process_block(&transactions, allow_failures);
+    write!(summary, "block 0\n{}", testbed.last_block()).unwrap();
+    let mut height = 1;
+    while testbed.has_pending_receipts() {
+        testbed.process_block(&[], allow_failures);
+        write!(summary, "block {height}\n{}", testbed.last_block()).unwrap();
+        height += 1;
+    }
+    summary
+}
+
+/// Describes the first line that differs between the golden and the actual
+/// results.
+fn compare(expected: &str, actual: &str) -> Result<(), String> {
+    let mut expected_lines = expected.lines();
+    let mut actual_lines = actual.lines();
+    let mut line = 0;
+    loop {
+        line += 1;
+        match (expected_lines.next(), actual_lines.next()) {
+            (None, None) => return Ok(()),
+            (expected, actual) if expected == actual => continue,
+            (expected, actual) => {
+                return Err(format!(
+                    "line {line} differs\n  expected: {}\n  actual:   {}",
+                    expected.unwrap_or("<end of file>"),
+                    actual.unwrap_or("<end of file>")
+                ))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::compare;
+
+    #[test]
+    fn test_compare() {
+        assert_eq!(compare("a\nb\n", "a\nb\n"), Ok(()));
+        assert_eq!(
+            compare("a\nb\n", "a\nc\n"),
+            Err("line 2 differs\n  expected: b\n  actual:   c".to_string())
+        );
+        assert_eq!(
+            compare("a\n", "a\nb\n"),
+            Err("line 2 differs\n  expected: <end of file>\n  actual:   b".to_string())
+        );
+    }
+}
@@ -62,6 +62,9 @@ mod cost_table;
 mod costs_to_runtime_config;
 mod estimator_context;
 mod gas_cost;
+mod golden;
+mod parallel_receipts;
+mod pipeline_latency;
 mod qemu;
 mod rocksdb;
 mod transaction_builder;
@@ -81,10 +84,13 @@ pub mod testbed;
 pub mod config;
 mod function_call;
 mod gas_metering;
+// Summarizes IO traces recorded by a node or the estimator.
+pub mod replay;
 mod trie;
 
 use std::convert::TryFrom;
 use std::iter;
+use std::sync::atomic::Ordering;
 use std::time::Instant;
 
 use estimator_params::sha256_cost;
@@ -96,19 +102,20 @@ use near_primitives::contract::ContractCode;
 use near_primitives::runtime::fees::RuntimeFeesConfig;
 use near_primitives::transaction::{
     Action, AddKeyAction, CreateAccountAction, DeleteAccountAction, DeleteKeyAction,
-    DeployContractAction, SignedTransaction, StakeAction, TransferAction,
+    DeployContractAction, FunctionCallAction, SignedTransaction, StakeAction, TransferAction,
 };
-use near_primitives::types::AccountId;
+use near_primitives::types::{AccountId, Balance};
 use near_primitives::version::PROTOCOL_VERSION;
+use near_test_contracts::LargeContract;
 use near_vm_logic::mocks::mock_external::MockedExternal;
 use near_vm_logic::{ExtCosts, VMConfig};
 use near_vm_runner::MockCompiledContractCache;
 use rand::Rng;
 use serde_json::json;
 use utils::{
-    average_cost, fn_cost, fn_cost_count, fn_cost_in_contract, fn_cost_with_setup,
-    generate_data_only_contract, generate_fn_name, noop_function_call_cost, read_resource,
-    transaction_cost, transaction_cost_ext,
+    actions_cost_in_contract, average_cost, fn_cost, fn_cost_count, fn_cost_in_contract,
+    fn_cost_with_setup, generate_data_only_contract, generate_fn_name, noop_function_call_cost,
+    read_resource, transaction_cost, transaction_cost_ext,
 };
 use vm_estimator::{compile_single_contract_cost, compute_compile_cost_vm};
 
@@ -123,6 +130,9 @@ use crate::vm_estimator::create_context;
 pub use crate::cost::Cost;
 pub use crate::cost_table::CostTable;
 pub use crate::costs_to_runtime_config::costs_to_runtime_config;
+pub use crate::golden::check_golden_files;
+pub use crate::parallel_receipts::ParallelReceiptsStats;
+pub use crate::pipeline_latency::PipelineLatencyStats;
 pub use crate::qemu::QemuCommandBuilder;
 pub use crate::rocksdb::RocksDBTestConfig;
 
@@ -139,9 +149,17 @@ static ALL_COSTS: &[(Cost, fn(&mut EstimatorContext) -> GasCost)] = &[
     (Cost::ActionStake, action_stake),
     (Cost::ActionDeployContractBase, action_deploy_contract_base),
     (Cost::ActionDeployContractPerByte, action_deploy_contract_per_byte),
+    #[cfg(feature = "protocol_feature_create_account_with_contract")]
+    (Cost::ActionStateInitBase, action_state_init_base),
+    #[cfg(feature = "protocol_feature_create_account_with_contract")]
+    (Cost::ActionStateInitPerByte, action_state_init_per_byte),
     (Cost::ActionFunctionCallBase, action_function_call_base),
     (Cost::ActionFunctionCallPerByte, action_function_call_per_byte),
     (Cost::HostFunctionCall, host_function_call),
+    #[cfg(feature = "protocol_feature_block_gas_info")]
+    (Cost::BlockGasPriceBase, block_gas_price_base),
+    #[cfg(feature = "protocol_feature_block_gas_info")]
+    (Cost::BlockGasLimitBase, block_gas_limit_base),
     (Cost::WasmInstruction, wasm_instruction),
     (Cost::DataReceiptCreationBase, data_receipt_creation_base),
     (Cost::DataReceiptCreationPerByte, data_receipt_creation_per_byte),
@@ -165,9 +183,21 @@ static ALL_COSTS: &[(Cost, fn(&mut EstimatorContext) -> GasCost)] = &[
     (Cost::Keccak256Byte, keccak256_byte),
     (Cost::Keccak512Base, keccak512_base),
     (Cost::Keccak512Byte, keccak512_byte),
+    #[cfg(feature = "protocol_feature_hash_batch")]
+    (Cost::Sha256BatchBase, sha256_batch_base),
+    #[cfg(feature = "protocol_feature_hash_batch")]
+    (Cost::Sha256BatchByte, sha256_batch_byte),
+    #[cfg(feature = "protocol_feature_hash_batch")]
+    (Cost::Keccak256BatchBase, keccak256_batch_base),
+    #[cfg(feature = "protocol_feature_hash_batch")]
+    (Cost::Keccak256BatchByte, keccak256_batch_byte),
     (Cost::Ripemd160Base, ripemd160_base),
     (Cost::Ripemd160Block, ripemd160_block),
     (Cost::EcrecoverBase, ecrecover_base),
+    #[cfg(feature = "protocol_feature_ecrecover_batch")]
+    (Cost::EcrecoverBatchBase, ecrecover_batch_base),
+    #[cfg(feature = "protocol_feature_ecrecover_batch")]
+    (Cost::EcrecoverBatchElement, ecrecover_batch_element),
     #[cfg(feature = "protocol_feature_ed25519_verify")]
     (Cost::Ed25519VerifyBase, ed25519_verify_base),
     #[cfg(feature = "protocol_feature_ed25519_verify")]
@@ -190,19 +220,36 @@ static ALL_COSTS: &[(Cost, fn(&mut EstimatorContext) -> GasCost)] = &[
     (Cost::StorageRemoveBase, storage_remove_base),
     (Cost::StorageRemoveKeyByte, storage_remove_key_byte),
     (Cost::StorageRemoveRetValueByte, storage_remove_ret_value_byte),
+    #[cfg(feature = "protocol_feature_storage_iter_prefix_page")]
+    (Cost::StorageIterCreatePrefixBase, storage_iter_create_prefix_base),
+    #[cfg(feature = "protocol_feature_storage_iter_prefix_page")]
+    (Cost::StorageIterCreatePrefixByte, storage_iter_create_prefix_byte),
+    #[cfg(feature = "protocol_feature_storage_iter_prefix_page")]
+    (Cost::StorageIterNextBase, storage_iter_next_base),
+    #[cfg(feature = "protocol_feature_storage_iter_prefix_page")]
+    (Cost::StorageIterNextKeyByte, storage_iter_next_key_byte),
+    #[cfg(feature = "protocol_feature_storage_iter_prefix_page")]
+    (Cost::StorageIterNextValueByte, storage_iter_next_value_byte),
     (Cost::TouchingTrieNode, touching_trie_node),
     (Cost::ReadCachedTrieNode, read_cached_trie_node),
+    (Cost::ReadCachedTrieNodeContended, read_cached_trie_node_contended),
     (Cost::TouchingTrieNodeRead, touching_trie_node_read),
     (Cost::TouchingTrieNodeWrite, touching_trie_node_write),
+    (Cost::PromiseAndBase, promise_and_base),
+    (Cost::PromiseAndPerPromise, promise_and_per_promise),
     (Cost::ApplyBlock, apply_block_cost),
     (Cost::ContractCompileBase, contract_compile_base),
     (Cost::ContractCompileBytes, contract_compile_bytes),
     (Cost::ContractCompileBaseV2, contract_compile_base_v2),
     (Cost::ContractCompileBytesV2, contract_compile_bytes_v2),
+    (Cost::ContractCompileBytesAtLimits, contract_compile_bytes_at_limits),
     (Cost::DeployBytes, pure_deploy_bytes),
     (Cost::ContractLoadingBase, contract_loading_base),
     (Cost::ContractLoadingPerByte, contract_loading_per_byte),
     (Cost::FunctionCallPerStorageByte, function_call_per_storage_byte),
+    (Cost::DataDependencyBase, data_dependency_base),
+    (Cost::DataDependencyPerInput, data_dependency_per_input),
+    (Cost::FtTransfer, ft_transfer),
     (Cost::GasMeteringBase, gas_metering_base),
     (Cost::GasMeteringOp, gas_metering_op),
     (Cost::RocksDbInsertValueByte, rocks_db_insert_value_byte),
@@ -235,12 +282,18 @@ pub fn run(config: Config) -> CostTable {
             }
         }
 
+        let name = cost.to_string();
+        // Marks where the estimation starts in the IO trace. The measurements
+        // that follow are attributed to it when checking the trace.
+        tracing::debug_span!(target: "io_tracer", "estimation", cost = %name).in_scope(|| ());
+
+        ctx.max_recorded_storage_size.store(0, Ordering::Relaxed);
         let start = Instant::now();
         let measurement = f(&mut ctx);
         let time = start.elapsed();
-        let name = cost.to_string();
         let uncertain = if measurement.is_uncertain() { "UNCERTAIN " } else { "" };
         let gas = measurement.to_gas();
+        let witness_size = ctx.max_recorded_storage_size.load(Ordering::Relaxed);
         res.add(cost, gas);
 
         eprintln!(
@@ -252,13 +305,19 @@ pub fn run(config: Config) -> CostTable {
             time,
             measurement.uncertain_message().unwrap_or_default(),
         );
+        if config.record_storage {
+            eprintln!("{:<40} {:>25} bytes of state witness (largest block)", "", witness_size);
+        }
 
         if config.json_output {
-            let json = json! ({
+            let mut json = json! ({
                 "name": name,
                 "result": measurement.to_json(),
                 "computed_in": time,
             });
+            if config.record_storage {
+                json["max_state_witness_size"] = witness_size.into();
+            }
             println!("{json}");
         }
     }
@@ -267,6 +326,68 @@ pub fn run(config: Config) -> CostTable {
     res
 }
 
+/// Research mode, not an estimation: runs blocks of function calls between
+/// random accounts and executes the resulting receipts both serially and in
+/// parallel grouped by receiver. Fails if the final states differ.
+///
+/// See `parallel_receipts` module for details.
+pub fn parallel_receipts_experiment(config: &Config) -> anyhow::Result<ParallelReceiptsStats> {
+    let mut ctx = EstimatorContext::new(config);
+    let mut testbed = ctx.testbed();
+    let mut stats = ParallelReceiptsStats::default();
+
+    let block_size = 100;
+    let block_latency = 1;
+    for _ in 0..config.iter_per_block {
+        let tb = testbed.transaction_builder();
+        let block = (0..block_size)
+            .map(|_| {
+                let (sender, receiver) = tb.random_account_pair();
+                let actions = vec![Action::FunctionCall(FunctionCallAction {
+                    method_name: "noop".to_string(),
+                    args: vec![],
+                    gas: 10u64.pow(18),
+                    deposit: 0,
+                })];
+                tb.transaction_from_actions(sender, receiver, actions)
+            })
+            .collect();
+        testbed.process_block_comparing_parallel_receipts(block, block_latency, &mut stats)?;
+    }
+    Ok(stats)
+}
+
+/// Research mode, not an estimation: runs blocks of function calls between
+/// random accounts and measures every stage of processing each block, from
+/// transaction validation to the RocksDB flush.
+///
+/// See `pipeline_latency` module for details.
+pub fn pipeline_latency_experiment(config: &Config) -> anyhow::Result<PipelineLatencyStats> {
+    let mut ctx = EstimatorContext::new(config);
+    let mut testbed = ctx.testbed();
+    let mut stats = PipelineLatencyStats::default();
+
+    let block_size = 100;
+    let block_latency = 1;
+    for _ in 0..config.iter_per_block {
+        let tb = testbed.transaction_builder();
+        let block = (0..block_size)
+            .map(|_| {
+                let (sender, receiver) = tb.random_account_pair();
+                let actions = vec![Action::FunctionCall(FunctionCallAction {
+                    method_name: "noop".to_string(),
+                    args: vec![],
+                    gas: 10u64.pow(18),
+                    deposit: 0,
+                })];
+                tb.transaction_from_actions(sender, receiver, actions)
+            })
+            .collect();
+        testbed.process_block_measuring_pipeline(block, block_latency, &mut stats)?;
+    }
+    Ok(stats)
+}
+
 fn action_receipt_creation(ctx: &mut EstimatorContext) -> GasCost {
     if let Some(cached) = ctx.cached.action_receipt_creation.clone() {
         return cached;
@@ -396,6 +517,7 @@ fn action_add_function_access_key_base(ctx: &mut EstimatorContext) -> GasCost {
                 allowance: Some(100),
                 receiver_id,
                 method_names: vec!["m".to_string()],
+                method_allowances: Default::default(),
             });
             add_key_transaction(tb, sender, permission)
         };
@@ -413,11 +535,19 @@ fn action_add_function_access_key_per_byte(ctx: &mut EstimatorContext) -> GasCos
     let base_cost = action_add_function_access_key_base(ctx) + action_sir_receipt_creation(ctx);
 
     // Set up estimation with varying method length and total bytes.
-    let mut estimate = |method_len: usize, total_len: usize| {
-        // Nothing prevents a key to list the same method many times. Performance should not be affected.
-        let method_name = "x".repeat(method_len);
+    // With `with_allowances`, every method gets an allowance instead of being
+    // listed in the method names, which makes the stored key larger.
+    let mut estimate = |method_len: usize, total_len: usize, with_allowances: bool| {
         let num_methods = total_len / (method_len + 1);
-        let method_names = vec![method_name; num_methods];
+        let (method_names, method_allowances) = if with_allowances {
+            // Method allowances are a map, so the method names have to be unique.
+            let method_allowances: std::collections::BTreeMap<_, _> =
+                (0..num_methods).map(|i| (format!("{i:0>method_len$}"), 100)).collect();
+            (vec![], method_allowances)
+        } else {
+            // Nothing prevents a key to list the same method many times. Performance should not be affected.
+            (vec!["x".repeat(method_len); num_methods], Default::default())
+        };
 
         let mut make_transaction = |tb: &mut TransactionBuilder| -> SignedTransaction {
             let sender = tb.random_unused_account();
@@ -427,13 +557,19 @@ fn action_add_function_access_key_per_byte(ctx: &mut EstimatorContext) -> GasCos
                 allowance: Some(100),
                 receiver_id,
                 method_names: method_names.clone(),
+                method_allowances: method_allowances.clone(),
             });
             add_key_transaction(tb, sender, permission)
         };
 
         let total_cost = transaction_cost(ctx, &mut make_transaction);
-        // +1 for null-terminator
-        let actual_total_len = num_methods * (method_len + 1);
+        // +1 for null-terminator, allowances are charged for the method name and the balance.
+        let bytes_per_method = if with_allowances {
+            method_len + 1 + std::mem::size_of::<Balance>()
+        } else {
+            method_len + 1
+        };
+        let actual_total_len = num_methods * bytes_per_method;
         let per_byte_cost = total_cost.saturating_sub(&base_cost, &NonNegativeTolerance::PER_MILLE)
             / actual_total_len as u64;
 
@@ -450,13 +586,19 @@ fn action_add_function_access_key_per_byte(ctx: &mut EstimatorContext) -> GasCos
     let max_method_len = 256;
 
     // Try a couple of combinations that could potentially be the worst-case.
-    let cost_a = estimate(max_method_len, max_bytes);
-    let cost_b = estimate(1, max_bytes); // This is the worst at time of writing.
-    let cost_c = estimate(8, max_bytes);
-    let cost_d = estimate(max_method_len, max_method_len + 1);
-    let cost_e = estimate(max_method_len / 2, max_bytes / 2);
+    let cost_a = estimate(max_method_len, max_bytes, false);
+    let cost_b = estimate(1, max_bytes, false); // This is the worst at time of writing.
+    let cost_c = estimate(8, max_bytes, false);
+    let cost_d = estimate(max_method_len, max_method_len + 1, false);
+    let cost_e = estimate(max_method_len / 2, max_bytes / 2, false);
+    #[allow(unused_mut)]
+    let mut costs = vec![cost_a, cost_b, cost_c, cost_d, cost_e];
 
-    [cost_a, cost_b, cost_c, cost_d, cost_e].into_iter().max().unwrap()
+    // Keys with method allowances store a balance per method.
+    #[cfg(feature = "protocol_feature_function_call_method_allowances")]
+    costs.extend([estimate(max_method_len, max_bytes, true), estimate(8, max_bytes, true)]);
+
+    costs.into_iter().max().unwrap()
 }
 
 fn add_key_transaction(
@@ -608,6 +750,70 @@ fn deploy_contract_cost(
 
     total_cost.saturating_sub(&base_cost, &NonNegativeTolerance::PER_MILLE)
 }
+
+#[cfg(feature = "protocol_feature_create_account_with_contract")]
+fn action_state_init_base(ctx: &mut EstimatorContext) -> GasCost {
+    let num_entries = 100;
+    let total_cost = create_account_with_contract_cost(ctx, num_entries, 1);
+    let base_cost = create_account_with_contract_cost(ctx, 0, 0);
+
+    total_cost.saturating_sub(&base_cost, &NonNegativeTolerance::PER_MILLE) / num_entries
+}
+
+#[cfg(feature = "protocol_feature_create_account_with_contract")]
+fn action_state_init_per_byte(ctx: &mut EstimatorContext) -> GasCost {
+    let num_entries = 10;
+    let value_len = 10_000;
+    let total_cost = create_account_with_contract_cost(ctx, num_entries, value_len);
+    let base_cost = create_account_with_contract_cost(ctx, num_entries, 1);
+
+    total_cost.saturating_sub(&base_cost, &NonNegativeTolerance::PER_MILLE)
+        / (num_entries * (value_len - 1))
+}
+
+/// Cost for creating an account with the smallest contract and `num_entries`
+/// state entries with values of `value_len` bytes, without the base cost of
+/// the receipt.
+#[cfg(feature = "protocol_feature_create_account_with_contract")]
+fn create_account_with_contract_cost(
+    ctx: &mut EstimatorContext,
+    num_entries: u64,
+    value_len: u64,
+) -> GasCost {
+    use near_primitives::transaction::{ContractStateEntry, CreateAccountWithContractAction};
+
+    let code = near_test_contracts::smallest_rs_contract().to_vec();
+    let mut make_transaction = |tb: &mut TransactionBuilder| -> SignedTransaction {
+        let sender = tb.random_account();
+        let new_account =
+            AccountId::try_from(format!("{}_{}", sender, tb.rng().gen::<u64>())).unwrap();
+
+        let state = (0..num_entries)
+            .map(|i| ContractStateEntry {
+                key: i.to_le_bytes().to_vec(),
+                value: vec![1u8; value_len as usize],
+            })
+            .collect();
+        let actions = vec![
+            Action::CreateAccountWithContract(CreateAccountWithContractAction {
+                code: code.clone(),
+                state,
+            }),
+            Action::Transfer(TransferAction { deposit: 10u128.pow(26) }),
+        ];
+        tb.transaction_from_actions(sender, new_account, actions)
+    };
+    // Use a small block size since the state makes these transactions large.
+    let block_size = 5;
+    // Like `CreateAccount`, the receipt potentially executes on another shard.
+    let block_latency = 1;
+    let (total_cost, _ext) =
+        transaction_cost_ext(ctx, block_size, &mut make_transaction, block_latency);
+    let base_cost = action_receipt_creation(ctx);
+
+    total_cost.saturating_sub(&base_cost, &NonNegativeTolerance::PER_MILLE)
+}
+
 fn contract_compile_base(ctx: &mut EstimatorContext) -> GasCost {
     compilation_cost_base_per_byte(ctx).0
 }
@@ -659,6 +865,44 @@ fn contract_compile_base_per_byte_v2(ctx: &mut EstimatorContext) -> (GasCost, Ga
     ctx.cached.compile_cost_base_per_byte_v2 = Some(costs.clone());
     costs
 }
+fn contract_compile_bytes_at_limits(ctx: &mut EstimatorContext) -> GasCost {
+    let limits = VMConfig::test().limit_config;
+    let limit = |limit: Option<u64>| limit.map_or(0, |limit| limit as u32);
+
+    let smallest_contract = near_test_contracts::smallest_rs_contract();
+    let smallest_cost =
+        compile_single_contract_cost(ctx.config.metric, ctx.config.vm_kind, smallest_contract);
+    let smallest_size = smallest_contract.len() as u64;
+
+    let contracts = [
+        LargeContract {
+            functions: limit(limits.max_functions_number_per_contract),
+            ..Default::default()
+        },
+        LargeContract { globals: limit(limits.max_globals_per_contract), ..Default::default() },
+        LargeContract {
+            table_elements: limit(limits.max_table_elements_per_contract),
+            ..Default::default()
+        },
+        LargeContract {
+            // Leave room for the name of the custom section, which counts
+            // towards the limit as well.
+            custom_section_bytes: limit(limits.max_custom_sections_size_per_contract)
+                .saturating_sub(16),
+            ..Default::default()
+        },
+    ];
+
+    let mut max_bytes_cost = GasCost::zero();
+    for contract in contracts {
+        let binary = contract.make();
+        let cost = compile_single_contract_cost(ctx.config.metric, ctx.config.vm_kind, &binary);
+        let bytes_cost = cost.saturating_sub(&smallest_cost, &NonNegativeTolerance::PER_MILLE)
+            / (binary.len() as u64).saturating_sub(smallest_size).max(1);
+        max_bytes_cost = std::cmp::max(bytes_cost, max_bytes_cost);
+    }
+    max_bytes_cost
+}
 fn pure_deploy_bytes(ctx: &mut EstimatorContext) -> GasCost {
     let vm_config = VMConfig::test();
     let small_code = generate_data_only_contract(0, &vm_config);
@@ -771,6 +1015,121 @@ fn data_receipt_creation_per_byte(ctx: &mut EstimatorContext) -> GasCost {
     total_cost.saturating_sub(&base_cost, &NonNegativeTolerance::PER_MILLE) / bytes_per_transaction
 }
 
+fn data_dependency_base(ctx: &mut EstimatorContext) -> GasCost {
+    let (base, _per_input) = data_dependency_base_per_input(ctx);
+    base
+}
+
+fn data_dependency_per_input(ctx: &mut EstimatorContext) -> GasCost {
+    let (_base, per_input) = data_dependency_base_per_input(ctx);
+    per_input
+}
+
+fn data_dependency_base_per_input(ctx: &mut EstimatorContext) -> (GasCost, GasCost) {
+    if let Some(costs) = ctx.cached.data_dependency_base_per_input.clone() {
+        return costs;
+    }
+
+    // Cost of the data dependencies of a callback on `n` joined promises.
+    let mut join_cost = |n: u64| {
+        // NB: there isn't `ExtCosts` for data receipt creation, so we ignore (`_`) the counts.
+        // The function returns a chain of two promises.
+        let block_latency = 2;
+        let (total_cost, _) =
+            fn_cost_count(ctx, &format!("data_receipt_10b_{n}"), ExtCosts::base, block_latency);
+        // The function returns a promise.
+        let block_latency = 1;
+        let (base_cost, _) = fn_cost_count(
+            ctx,
+            &format!("data_receipt_base_10b_{n}"),
+            ExtCosts::base,
+            block_latency,
+        );
+        total_cost.saturating_sub(&base_cost, &NonNegativeTolerance::PER_MILLE)
+    };
+    let small_join = join_cost(10);
+    let large_join = join_cost(1000);
+
+    let per_input = large_join.saturating_sub(&small_join, &NonNegativeTolerance::PER_MILLE) / 990;
+    let base =
+        small_join.saturating_sub(&(per_input.clone() * 10), &NonNegativeTolerance::PER_MILLE);
+
+    ctx.cached.data_dependency_base_per_input = Some((base.clone(), per_input.clone()));
+    (base, per_input)
+}
+
+fn ft_transfer(ctx: &mut EstimatorContext) -> GasCost {
+    let n_actions = 50;
+    let code = read_resource("res/fungible_token.wasm");
+    let receiver: AccountId = "ft-receiver.near".parse().unwrap();
+    let call = |method: &str, args: serde_json::Value, deposit: Balance| {
+        Action::FunctionCall(FunctionCallAction {
+            method_name: method.to_string(),
+            args: args.to_string().into_bytes(),
+            gas: 10u64.pow(15),
+            deposit,
+        })
+    };
+    let setup = |account: &AccountId| {
+        vec![
+            Action::DeployContract(DeployContractAction { code: code.clone() }),
+            call(
+                "new_default_meta",
+                json!({ "owner_id": account, "total_supply": "1000000000" }),
+                0,
+            ),
+            // Exactly the minimum storage balance of the contract, anything
+            // above is refunded in a separate receipt.
+            call(
+                "storage_deposit",
+                json!({ "account_id": receiver }),
+                1_250_000_000_000_000_000_000,
+            ),
+        ]
+    };
+    let transfer = || call("ft_transfer", json!({ "receiver_id": receiver, "amount": "1" }), 1);
+    actions_cost_in_contract(ctx, setup, transfer, n_actions)
+}
+
+fn promise_and_base(ctx: &mut EstimatorContext) -> GasCost {
+    let (base, _per_promise) = promise_and_base_per_promise(ctx);
+    base
+}
+
+fn promise_and_per_promise(ctx: &mut EstimatorContext) -> GasCost {
+    let (_base, per_promise) = promise_and_base_per_promise(ctx);
+    per_promise
+}
+
+fn promise_and_base_per_promise(ctx: &mut EstimatorContext) -> (GasCost, GasCost) {
+    if let Some(costs) = ctx.cached.promise_and_base_per_promise.clone() {
+        return costs;
+    }
+
+    let block_latency = 0;
+    let (small_cost, count) =
+        fn_cost_count(ctx, "promise_and_100k", ExtCosts::promise_and_base, block_latency);
+    assert_eq!(count, 100_000);
+    // `promise_and_per_promise` is charged per byte of promise indices.
+    let small_bytes = count * 2 * std::mem::size_of::<u64>() as u64;
+    let (large_cost, large_bytes) = fn_cost_count(
+        ctx,
+        "promise_and_100k_on_1k_and",
+        ExtCosts::promise_and_per_promise,
+        block_latency,
+    );
+
+    let per_promise = large_cost.saturating_sub(&small_cost, &NonNegativeTolerance::PER_MILLE)
+        / (large_bytes - small_bytes);
+    let base = small_cost
+        .saturating_sub(&noop_function_call_cost(ctx), &NonNegativeTolerance::PER_MILLE)
+        .saturating_sub(&(per_promise.clone() * small_bytes), &NonNegativeTolerance::PER_MILLE)
+        / count;
+
+    ctx.cached.promise_and_base_per_promise = Some((base.clone(), per_promise.clone()));
+    (base, per_promise)
+}
+
 fn host_function_call(ctx: &mut EstimatorContext) -> GasCost {
     let block_latency = 0;
     let (total_cost, count) = fn_cost_count(ctx, "base_1M", ExtCosts::base, block_latency);
@@ -781,6 +1140,15 @@ fn host_function_call(ctx: &mut EstimatorContext) -> GasCost {
     total_cost.saturating_sub(&base_cost, &NonNegativeTolerance::PER_MILLE) / count
 }
 
+#[cfg(feature = "protocol_feature_block_gas_info")]
+fn block_gas_price_base(ctx: &mut EstimatorContext) -> GasCost {
+    fn_cost(ctx, "block_gas_price_1M", ExtCosts::block_gas_price_base, 1_000_000)
+}
+#[cfg(feature = "protocol_feature_block_gas_info")]
+fn block_gas_limit_base(ctx: &mut EstimatorContext) -> GasCost {
+    fn_cost(ctx, "block_gas_limit_1M", ExtCosts::block_gas_limit_base, 1_000_000)
+}
+
 fn wasm_instruction(ctx: &mut EstimatorContext) -> GasCost {
     let vm_kind = ctx.config.vm_kind;
 
@@ -920,6 +1288,29 @@ fn keccak512_byte(ctx: &mut EstimatorContext) -> GasCost {
     fn_cost(ctx, "keccak512_10kib_10k", ExtCosts::keccak512_byte, 10 * 1024 * 10_000)
 }
 
+#[cfg(feature = "protocol_feature_hash_batch")]
+fn sha256_batch_base(ctx: &mut EstimatorContext) -> GasCost {
+    fn_cost(ctx, "sha256_batch_10x10b_1k", ExtCosts::sha256_batch_base, 1_000)
+}
+#[cfg(feature = "protocol_feature_hash_batch")]
+fn sha256_batch_byte(ctx: &mut EstimatorContext) -> GasCost {
+    fn_cost(ctx, "sha256_batch_10x10kib_1k", ExtCosts::sha256_batch_byte, 10 * 10 * 1024 * 1_000)
+}
+
+#[cfg(feature = "protocol_feature_hash_batch")]
+fn keccak256_batch_base(ctx: &mut EstimatorContext) -> GasCost {
+    fn_cost(ctx, "keccak256_batch_10x10b_1k", ExtCosts::keccak256_batch_base, 1_000)
+}
+#[cfg(feature = "protocol_feature_hash_batch")]
+fn keccak256_batch_byte(ctx: &mut EstimatorContext) -> GasCost {
+    fn_cost(
+        ctx,
+        "keccak256_batch_10x10kib_1k",
+        ExtCosts::keccak256_batch_byte,
+        10 * 10 * 1024 * 1_000,
+    )
+}
+
 fn ripemd160_base(ctx: &mut EstimatorContext) -> GasCost {
     fn_cost(ctx, "ripemd160_10b_10k", ExtCosts::ripemd160_base, 10_000)
 }
@@ -931,6 +1322,15 @@ fn ecrecover_base(ctx: &mut EstimatorContext) -> GasCost {
     fn_cost(ctx, "ecrecover_10k", ExtCosts::ecrecover_base, 10_000)
 }
 
+#[cfg(feature = "protocol_feature_ecrecover_batch")]
+fn ecrecover_batch_base(ctx: &mut EstimatorContext) -> GasCost {
+    fn_cost(ctx, "ecrecover_batch_0x_10k", ExtCosts::ecrecover_batch_base, 10_000)
+}
+#[cfg(feature = "protocol_feature_ecrecover_batch")]
+fn ecrecover_batch_element(ctx: &mut EstimatorContext) -> GasCost {
+    fn_cost(ctx, "ecrecover_batch_10x_1k", ExtCosts::ecrecover_batch_element, 10 * 1_000)
+}
+
 #[cfg(feature = "protocol_feature_ed25519_verify")]
 fn ed25519_verify_base(ctx: &mut EstimatorContext) -> GasCost {
     if ctx.cached.ed25519_verify_base.is_none() {
@@ -1033,6 +1433,57 @@ fn storage_read_value_byte(ctx: &mut EstimatorContext) -> GasCost {
     )
 }
 
+#[cfg(feature = "protocol_feature_storage_iter_prefix_page")]
+fn storage_iter_create_prefix_base(ctx: &mut EstimatorContext) -> GasCost {
+    fn_cost_with_setup(
+        ctx,
+        "storage_write_10b_key_10b_value_1k",
+        "storage_iter_prefix_page_10b_key_1k",
+        ExtCosts::storage_iter_create_prefix_base,
+        1000,
+    )
+}
+#[cfg(feature = "protocol_feature_storage_iter_prefix_page")]
+fn storage_iter_create_prefix_byte(ctx: &mut EstimatorContext) -> GasCost {
+    fn_cost_with_setup(
+        ctx,
+        "storage_write_10kib_key_10b_value_1k",
+        "storage_iter_prefix_page_10kib_key_1k",
+        ExtCosts::storage_iter_create_prefix_byte,
+        10 * 1024 * 1000,
+    )
+}
+#[cfg(feature = "protocol_feature_storage_iter_prefix_page")]
+fn storage_iter_next_base(ctx: &mut EstimatorContext) -> GasCost {
+    fn_cost_with_setup(
+        ctx,
+        "storage_write_10b_key_10b_value_1k",
+        "storage_iter_prefix_page_1k",
+        ExtCosts::storage_iter_next_base,
+        1000,
+    )
+}
+#[cfg(feature = "protocol_feature_storage_iter_prefix_page")]
+fn storage_iter_next_key_byte(ctx: &mut EstimatorContext) -> GasCost {
+    fn_cost_with_setup(
+        ctx,
+        "storage_write_10kib_key_10b_value_1k",
+        "storage_iter_prefix_page_1k",
+        ExtCosts::storage_iter_next_key_byte,
+        10 * 1024 * 1000,
+    )
+}
+#[cfg(feature = "protocol_feature_storage_iter_prefix_page")]
+fn storage_iter_next_value_byte(ctx: &mut EstimatorContext) -> GasCost {
+    fn_cost_with_setup(
+        ctx,
+        "storage_write_10b_key_10kib_value_1k",
+        "storage_iter_prefix_page_1k",
+        ExtCosts::storage_iter_next_value_byte,
+        10 * 1024 * 1000,
+    )
+}
+
 fn storage_write_base(ctx: &mut EstimatorContext) -> GasCost {
     fn_cost(ctx, "storage_write_10b_key_10b_value_1k", ExtCosts::storage_write_base, 1000)
 }
@@ -1138,6 +1589,18 @@ fn read_cached_trie_node(ctx: &mut EstimatorContext) -> GasCost {
     average_cost(results)
 }
 
+fn read_cached_trie_node_contended(ctx: &mut EstimatorContext) -> GasCost {
+    let warmup_iters = ctx.config.warmup_iters_per_block;
+    let iters = ctx.config.iter_per_block;
+    let mut testbed = ctx.testbed();
+
+    let results = (0..(warmup_iters + iters))
+        .map(|_| trie::read_node_from_contended_shard_cache(&mut testbed))
+        .skip(warmup_iters)
+        .collect::<Vec<_>>();
+    average_cost(results)
+}
+
 fn apply_block_cost(ctx: &mut EstimatorContext) -> GasCost {
     if let Some(cost) = ctx.cached.apply_block.clone() {
         return cost;
@@ -6,8 +6,8 @@ use genesis_populate::GenesisBuilder;
 use near_chain_configs::GenesisValidationMode;
 use near_primitives::version::PROTOCOL_VERSION;
 use near_vm_runner::internal::VMKind;
-use replay::ReplayCmd;
 use runtime_params_estimator::config::{Config, GasMetric};
+use runtime_params_estimator::replay::ReplayCmd;
 use runtime_params_estimator::{
     costs_to_runtime_config, CostTable, QemuCommandBuilder, RocksDBTestConfig,
 };
@@ -20,7 +20,7 @@ use std::process::Command;
 use std::time;
 use tracing_subscriber::Layer;
 
-mod replay;
+mod bundle;
 
 #[derive(Parser)]
 struct CliArgs {
@@ -91,9 +91,38 @@ struct CliArgs {
     /// Records IO events in JSON format and stores it in a given file.
     #[clap(long)]
     record_io_trace: Option<PathBuf>,
+    /// Write a reproducibility bundle to the given directory. It contains the
+    /// runtime parameters, git commit, CLI flags, a checksum of the state dump,
+    /// and the raw cost table of this run.
+    #[clap(long)]
+    bundle_dir: Option<PathBuf>,
+    /// Research mode: instead of estimating costs, execute receipts of blocks
+    /// with random function calls both serially and in parallel grouped by
+    /// receiver, check that the final state is equal and report the speedup.
+    /// The number of blocks is given by `--iters`.
+    #[clap(long)]
+    parallel_receipts_experiment: bool,
+    /// Research mode: instead of estimating costs, process blocks of random
+    /// function calls and report the time spent per block in each stage of
+    /// the pipeline: transaction validation, apply, trie commit and RocksDB
+    /// flush. The number of blocks is given by `--iters`.
+    #[clap(long)]
+    pipeline_latency_experiment: bool,
+    /// Instead of estimating costs, apply canonical workloads and compare the
+    /// outcomes and changed state of every block to the golden files in the
+    /// given directory.
+    #[clap(long)]
+    golden_dir: Option<PathBuf>,
+    /// Write the golden files of `--golden-dir` instead of comparing to them.
+    #[clap(long, requires("golden-dir"))]
+    bless: bool,
     /// Use in-memory test DB, useful to avoid variance caused by DB.
     #[clap(long)]
     pub in_memory_db: bool,
+    /// Record all trie nodes read while applying blocks, as needed for a state
+    /// witness, and report the largest witness size of each estimation.
+    #[clap(long)]
+    pub record_storage: bool,
     /// Extra configuration parameters for RocksDB specific estimations
     #[clap(flatten)]
     db_test_config: RocksDBTestConfig,
@@ -179,6 +208,7 @@ fn main() -> anyhow::Result<()> {
             cli_args.docker_shell,
             cli_args.json_output,
             cli_args.debug,
+            cli_args.bundle_dir.as_deref(),
         );
     }
 
@@ -221,7 +251,7 @@ fn main() -> anyhow::Result<()> {
             .with_filter(tracing_subscriber::EnvFilter::from_default_env());
         let subscriber = tracing_subscriber::registry().with(log_layer);
         #[cfg(feature = "io_trace")]
-        let subscriber = subscriber.with(cli_args.record_io_trace.map(|path| {
+        let subscriber = subscriber.with(cli_args.record_io_trace.clone().map(|path| {
             let log_file =
                 fs::File::create(path).expect("unable to create or truncate IO trace output file");
             let (subscriber, guard) = near_o11y::make_io_tracing_layer(log_file);
@@ -263,7 +293,7 @@ fn main() -> anyhow::Result<()> {
         iter_per_block,
         active_accounts,
         block_sizes: vec![],
-        state_dump_path: state_dump_path,
+        state_dump_path: state_dump_path.clone(),
         metric,
         vm_kind,
         costs_to_measure,
@@ -272,7 +302,24 @@ fn main() -> anyhow::Result<()> {
         json_output: cli_args.json_output,
         drop_os_cache: cli_args.drop_os_cache,
         in_memory_db: cli_args.in_memory_db,
+        record_storage: cli_args.record_storage,
     };
+    if cli_args.parallel_receipts_experiment {
+        let stats = runtime_params_estimator::parallel_receipts_experiment(&config)?;
+        println!("{stats}");
+        return Ok(());
+    }
+    if cli_args.pipeline_latency_experiment {
+        let stats = runtime_params_estimator::pipeline_latency_experiment(&config)?;
+        println!("{stats}");
+        return Ok(());
+    }
+    if let Some(golden_dir) = &cli_args.golden_dir {
+        runtime_params_estimator::check_golden_files(&config, golden_dir, cli_args.bless)?;
+        let result = if cli_args.bless { "written to" } else { "match" };
+        eprintln!("\nGolden files {result} {}", golden_dir.display());
+        return Ok(());
+    }
     let cost_table = runtime_params_estimator::run(config);
 
     let output_path = {
@@ -290,6 +337,23 @@ fn main() -> anyhow::Result<()> {
         output_path.display()
     );
 
+    #[cfg(feature = "io_trace")]
+    if let (Some(guard), Some(trace_path)) = (_maybe_writer_guard.take(), &cli_args.record_io_trace)
+    {
+        if metric == GasMetric::ICount {
+            // Dropping the guard flushes the IO trace to the file.
+            drop(guard);
+            eprintln!("\nComparing IO bytes counted by QEMU with the IO trace:\n");
+            runtime_params_estimator::replay::check_io_bytes(trace_path, &mut std::io::stderr())?;
+        }
+    }
+
+    if let Some(bundle_dir) = &cli_args.bundle_dir {
+        let commit = exec("git rev-parse HEAD").ok();
+        bundle::write_bundle(bundle_dir, &state_dump_path, commit.as_deref(), &cost_table)?;
+        eprintln!("\nReproducibility bundle saved to:\n\n    {}", bundle_dir.display());
+    }
+
     Ok(())
 }
 
@@ -306,6 +370,7 @@ fn main_docker(
     debug_shell: bool,
     json_output: bool,
     debug: bool,
+    bundle_dir: Option<&Path>,
 ) -> anyhow::Result<()> {
     let profile = if full { "release" } else { "quick-release" };
     exec("docker --version").context("please install `docker`")?;
@@ -364,7 +429,7 @@ fn main_docker(
         while let Some(arg) = args.next() {
             match arg.as_str() {
                 "--docker" | "--full" => continue,
-                "--additional-accounts-num" | "--home" => {
+                "--additional-accounts-num" | "--home" | "--bundle-dir" => {
                     args.next();
                     continue;
                 }
@@ -385,6 +450,10 @@ fn main_docker(
         // Note that this still reads all values from the state dump and creates
         // a new testbed for each estimation, we only switch out the storage backend.
         write!(buf, " --in-memory-db").unwrap();
+        // The bundle directory is mounted into the container, see below.
+        if bundle_dir.is_some() {
+            write!(buf, " --bundle-dir /bundle").unwrap();
+        }
 
         buf
     };
@@ -400,6 +469,12 @@ fn main_docker(
         .args(&["--mount", "source=rust-emu-target-dir,target=/host/nearcore/target"])
         .args(&["--mount", "source=rust-emu-cargo-dir,target=/usr/local/cargo"])
         .args(&["--env", "RUST_BACKTRACE=full"]);
+    if let Some(bundle_dir) = bundle_dir {
+        fs::create_dir_all(bundle_dir)?;
+        let bundle_dir = fs::canonicalize(bundle_dir)?;
+        let bundle = format!("type=bind,source={},target=/bundle", bundle_dir.to_str().unwrap());
+        cmd.args(&["--mount", &bundle]);
+    }
     // Spawning an interactive shell and pseudo TTY is necessary for debug shell
     // and nice-to-have in the general case, for cargo to color its output. But
     // it also merges stderr and stdout, which is problem when the stdout should
@@ -0,0 +1,172 @@
+//! Research mode to evaluate intra-shard parallelism.
+//!
+//! A receipt only modifies the state of its receiver account. Receipts with
+//! different receivers should therefore be independent and could be executed
+//! in parallel within a chunk. To gather data on how much that would help,
+//! the testbed applies the incoming receipts of each block twice against the
+//! same state: once serially, as the runtime does today, and once split into
+//! groups by receiver, with all groups executed in parallel. Each group
+//! writes to its own overlay (the `TrieUpdate` inside `Runtime::apply`) and
+//! the overlays are merged afterwards. The merged state must be equal to the
+//! state produced by serial execution, otherwise the experiment fails.
+
+use near_primitives::receipt::Receipt;
+use near_primitives::types::{AccountId, RawStateChangesWithTrieKey};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+use std::time::Duration;
+
+/// Final value of every trie key written while applying receipts, keyed by
+/// the raw trie key. `None` means the key was deleted.
+pub(crate) type FinalState = BTreeMap<Vec<u8>, Option<Vec<u8>>>;
+
+/// Timings of serial and parallel receipt execution, accumulated over blocks.
+#[derive(Debug, Default, Clone)]
+pub struct ParallelReceiptsStats {
+    /// Number of blocks with incoming receipts that were compared.
+    pub blocks: usize,
+    pub receipts: usize,
+    /// Number of groups of receipts with the same receiver.
+    pub groups: usize,
+    /// Time to apply all receipts serially.
+    pub serial: Duration,
+    /// Wall-clock time to apply all groups in parallel.
+    pub parallel: Duration,
+    /// Sum over blocks of the slowest group. This is the best parallel
+    /// execution time possible with unlimited threads.
+    pub critical_path: Duration,
+}
+
+impl ParallelReceiptsStats {
+    pub(crate) fn add(&mut self, other: &ParallelReceiptsStats) {
+        self.blocks += other.blocks;
+        self.receipts += other.receipts;
+        self.groups += other.groups;
+        self.serial += other.serial;
+        self.parallel += other.parallel;
+        self.critical_path += other.critical_path;
+    }
+}
+
+impl fmt::Display for ParallelReceiptsStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let speedup = |d: Duration| self.serial.as_secs_f64() / d.as_secs_f64();
+        writeln!(
+            f,
+            "{} blocks, {} receipts in {} receiver groups ({:.2} receipts per group)",
+            self.blocks,
+            self.receipts,
+            self.groups,
+            self.receipts as f64 / self.groups as f64
+        )?;
+        writeln!(f, "serial:        {:>10.2?}", self.serial)?;
+        writeln!(
+            f,
+            "parallel:      {:>10.2?} (speedup {:.2}x)",
+            self.parallel,
+            speedup(self.parallel)
+        )?;
+        writeln!(
+            f,
+            "critical path: {:>10.2?} (speedup {:.2}x)",
+            self.critical_path,
+            speedup(self.critical_path)
+        )
+    }
+}
+
+/// Splits receipts into groups with the same receiver, keeping the relative
+/// order of receipts inside each group.
+pub(crate) fn group_by_receiver(receipts: &[Receipt]) -> Vec<Vec<Receipt>> {
+    let mut groups: BTreeMap<&AccountId, Vec<Receipt>> = BTreeMap::new();
+    for receipt in receipts {
+        groups.entry(&receipt.receiver_id).or_default().push(receipt.clone());
+    }
+    groups.into_values().collect()
+}
+
+pub(crate) fn final_state(changes: Vec<RawStateChangesWithTrieKey>) -> FinalState {
+    changes
+        .into_iter()
+        .filter_map(|RawStateChangesWithTrieKey { trie_key, changes }| {
+            Some((trie_key.to_vec(), changes.into_iter().last()?.data))
+        })
+        .collect()
+}
+
+/// Merges the overlays of all groups. Fails if two groups wrote the same key,
+/// which means the groups were not independent after all.
+pub(crate) fn merge_final_states(states: Vec<FinalState>) -> anyhow::Result<FinalState> {
+    let mut merged = FinalState::new();
+    for state in states {
+        for (key, value) in state {
+            if merged.insert(key.clone(), value).is_some() {
+                anyhow::bail!("trie key {:?} was written by more than one receiver group", key);
+            }
+        }
+    }
+    Ok(merged)
+}
+
+pub(crate) fn check_final_states_equal(
+    serial: &FinalState,
+    parallel: &FinalState,
+) -> anyhow::Result<()> {
+    let keys: BTreeSet<&Vec<u8>> = serial.keys().chain(parallel.keys()).collect();
+    let num_differences =
+        keys.into_iter().filter(|key| serial.get(*key) != parallel.get(*key)).count();
+    anyhow::ensure!(
+        num_differences == 0,
+        "parallel execution produced a different state: {} keys differ ({} keys written serially, {} in parallel)",
+        num_differences,
+        serial.len(),
+        parallel.len()
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{check_final_states_equal, group_by_receiver, merge_final_states, FinalState};
+    use near_primitives::receipt::Receipt;
+
+    fn state(entries: &[(&str, Option<&str>)]) -> FinalState {
+        entries
+            .iter()
+            .map(|(key, value)| (key.as_bytes().to_vec(), value.map(|v| v.as_bytes().to_vec())))
+            .collect()
+    }
+
+    #[test]
+    fn test_group_by_receiver() {
+        let receipt = |receiver: &str, nonce: u8| {
+            let mut receipt = Receipt::new_balance_refund(&receiver.parse().unwrap(), 1);
+            receipt.receipt_id = near_primitives::hash::hash(&[nonce]);
+            receipt
+        };
+        let receipts =
+            vec![receipt("bob.near", 0), receipt("alice.near", 1), receipt("bob.near", 2)];
+        let groups: Vec<Vec<_>> = group_by_receiver(&receipts)
+            .into_iter()
+            .map(|group| group.into_iter().map(|r| r.receipt_id).collect())
+            .collect();
+        assert_eq!(
+            groups,
+            vec![
+                vec![receipts[1].receipt_id],
+                vec![receipts[0].receipt_id, receipts[2].receipt_id]
+            ]
+        );
+    }
+
+    #[test]
+    fn test_merge_and_compare_final_states() {
+        let alice = state(&[("alice", Some("1"))]);
+        let bob = state(&[("bob", None)]);
+        let merged = merge_final_states(vec![alice.clone(), bob]).unwrap();
+        check_final_states_equal(&state(&[("alice", Some("1")), ("bob", None)]), &merged).unwrap();
+        check_final_states_equal(&state(&[("alice", Some("2")), ("bob", None)]), &merged)
+            .unwrap_err();
+        merge_final_states(vec![alice.clone(), alice]).unwrap_err();
+    }
+}
@@ -0,0 +1,131 @@
+//! Research mode to measure the latency of the full block pipeline.
+//!
+//! Estimations only measure `Runtime::apply`, but a node has to do more work
+//! within the block time: transactions are validated before they are
+//! included, and after applying a block the trie changes are committed to
+//! the database and eventually flushed to disk. To judge whether parameters
+//! fit into the real block-time budget, the testbed measures each of these
+//! stages per block, see `RuntimeTestbed::process_block_pipeline`.
+
+use near_primitives::types::Gas;
+use std::fmt;
+use std::time::Duration;
+
+/// Time spent in each stage of block processing, accumulated over blocks.
+#[derive(Debug, Default, Clone)]
+pub struct PipelineLatency {
+    pub blocks: usize,
+    pub gas_burnt: Gas,
+    /// Stateless checks and signature verification of the transactions.
+    pub validation: Duration,
+    /// `Runtime::apply`.
+    pub apply: Duration,
+    /// Applying the trie changes to the store and committing the update.
+    pub commit: Duration,
+    /// Flushing the RocksDB memtable.
+    pub flush: Duration,
+}
+
+impl PipelineLatency {
+    pub(crate) fn add(&mut self, other: &PipelineLatency) {
+        self.blocks += other.blocks;
+        self.gas_burnt += other.gas_burnt;
+        self.validation += other.validation;
+        self.apply += other.apply;
+        self.commit += other.commit;
+        self.flush += other.flush;
+    }
+
+    pub fn total(&self) -> Duration {
+        self.validation + self.apply + self.commit + self.flush
+    }
+}
+
+/// Accumulated stage breakdown and the slowest block seen.
+#[derive(Debug, Default, Clone)]
+pub struct PipelineLatencyStats {
+    pub total: PipelineLatency,
+    pub slowest_block: PipelineLatency,
+}
+
+impl PipelineLatencyStats {
+    pub(crate) fn add_block(&mut self, block: PipelineLatency) {
+        self.total.add(&block);
+        if block.total() > self.slowest_block.total() {
+            self.slowest_block = block;
+        }
+    }
+}
+
+impl fmt::Display for PipelineLatencyStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let total = &self.total;
+        let blocks = total.blocks.max(1) as u32;
+        writeln!(f, "{} blocks, {} Tgas burnt", total.blocks, total.gas_burnt / 10u64.pow(12))?;
+        writeln!(f, "{:<12} {:>12} {:>12} {:>7}", "stage", "total", "per block", "share")?;
+        let stages = [
+            ("validation", total.validation),
+            ("apply", total.apply),
+            ("commit", total.commit),
+            ("flush", total.flush),
+            ("total", total.total()),
+        ];
+        for (name, duration) in stages {
+            let share = duration.as_secs_f64() / total.total().as_secs_f64().max(f64::EPSILON);
+            writeln!(
+                f,
+                "{:<12} {:>12.2?} {:>12.2?} {:>6.1}%",
+                name,
+                duration,
+                duration / blocks,
+                share * 100.0
+            )?;
+        }
+        let slowest = &self.slowest_block;
+        writeln!(
+            f,
+            "slowest block: {:.2?} (validation {:.2?}, apply {:.2?}, commit {:.2?}, flush {:.2?})",
+            slowest.total(),
+            slowest.validation,
+            slowest.apply,
+            slowest.commit,
+            slowest.flush
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{PipelineLatency, PipelineLatencyStats};
+    use std::time::Duration;
+
+    fn block(apply_ms: u64, commit_ms: u64) -> PipelineLatency {
+        PipelineLatency {
+            blocks: 1,
+            gas_burnt: 10u64.pow(12),
+            validation: Duration::from_millis(1),
+            apply: Duration::from_millis(apply_ms),
+            commit: Duration::from_millis(commit_ms),
+            flush: Duration::from_millis(2),
+        }
+    }
+
+    #[test]
+    fn test_pipeline_latency_stats() {
+        let mut stats = PipelineLatencyStats::default();
+        stats.add_block(block(10, 5));
+        stats.add_block(block(20, 1));
+        stats.add_block(block(3, 4));
+
+        assert_eq!(stats.total.blocks, 3);
+        assert_eq!(stats.total.gas_burnt, 3 * 10u64.pow(12));
+        assert_eq!(stats.total.apply, Duration::from_millis(33));
+        assert_eq!(stats.total.total(), Duration::from_millis(3 + 33 + 10 + 6));
+        assert_eq!(stats.slowest_block.apply, Duration::from_millis(20));
+
+        let output = stats.to_string();
+        assert!(output.starts_with("3 blocks, 3 Tgas burnt\n"), "{output}");
+        assert!(output.contains("apply"), "{output}");
+        assert!(output.contains("slowest block: 24.00ms"), "{output}");
+    }
+}
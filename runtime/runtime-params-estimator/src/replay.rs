@@ -2,25 +2,37 @@ use anyhow::Context;
 use std::collections::BTreeMap;
 use std::fs::File;
 use std::io::{self, Write};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::str::SplitWhitespace;
-use tracing::log::error;
+use std::time::{Duration, Instant};
+use tracing::log::{error, info};
 
 use self::fold_db_ops::FoldDbOps;
 use self::gas_charges::ChargedVsFree;
+use self::io_bytes_check::IoBytesCheck;
+use self::span_counts::SpanCounts;
 
 mod cache_stats;
 mod fold_db_ops;
 mod gas_charges;
+mod io_bytes_check;
+mod span_counts;
+
+/// How often progress is logged with `--stats`, in trace lines.
+const PROGRESS_INTERVAL_LINES: u64 = 1_000_000;
 
 #[derive(clap::Parser)]
-pub(crate) struct ReplayCmd {
+pub struct ReplayCmd {
     trace: PathBuf,
     #[clap(subcommand)]
     mode: ReplayMode,
     /// Only show data for a specific smart contract, specified by account id.
     #[clap(long)]
     account: Option<String>,
+    /// Print processing time, number of lines and peak memory usage at the
+    /// end, and log progress while replaying.
+    #[clap(long)]
+    stats: bool,
 }
 
 #[derive(Clone, Copy, clap::Subcommand, Debug)]
@@ -37,23 +49,52 @@ pub(crate) enum ReplayMode {
     ChunkCacheStats,
     /// Go over DB operations and print how much of it is paid for with gas.
     GasCharges,
+    /// Print the exact span counts recorded in a sampled IO trace.
+    SpanCounts,
+    /// Compare the IO bytes counted by QEMU in an estimator run with the DB
+    /// operations in its IO trace and print discrepancies.
+    IoBytesCheck,
 }
 
 impl ReplayCmd {
-    pub(crate) fn run(&self, out: &mut dyn Write) -> anyhow::Result<()> {
+    pub fn run(&self, out: &mut dyn Write) -> anyhow::Result<()> {
         let file = File::open(&self.trace)?;
         self.run_on_input(io::BufReader::new(file), out)
     }
 
     fn run_on_input(&self, input: impl io::BufRead, out: &mut dyn Write) -> anyhow::Result<()> {
+        let start = Instant::now();
+        let mut stats = ReplayStats {
+            mode: self.mode,
+            lines: 0,
+            visitor_time: Duration::ZERO,
+            total_time: Duration::ZERO,
+        };
         let mut visitor = self.build_visitor();
         for line in input.lines() {
             let line = line?;
+            let visitor_start = Instant::now();
             if let Err(e) = visitor.eval_line(out, &line) {
                 error!("ERROR: {e} for input line: {line}");
             }
+            stats.visitor_time += visitor_start.elapsed();
+            stats.lines += 1;
+            if self.stats && stats.lines % PROGRESS_INTERVAL_LINES == 0 {
+                info!(
+                    "replayed {} lines in {:.2?}, peak memory {}",
+                    stats.lines,
+                    start.elapsed(),
+                    display_peak_memory()
+                );
+            }
         }
+        let visitor_start = Instant::now();
         visitor.flush(out)?;
+        stats.visitor_time += visitor_start.elapsed();
+        if self.stats {
+            stats.total_time = start.elapsed();
+            write!(out, "{stats}")?;
+        }
         Ok(())
     }
 
@@ -86,10 +127,34 @@ impl ReplayCmd {
                 }
                 Box::new(ChargedVsFree::default())
             }
+            ReplayMode::SpanCounts => {
+                if self.account.is_some() {
+                    unimplemented!("account filter does not work with span counts");
+                }
+                Box::new(SpanCounts::default())
+            }
+            ReplayMode::IoBytesCheck => {
+                if self.account.is_some() {
+                    unimplemented!("account filter does not work with IO bytes check");
+                }
+                Box::new(IoBytesCheck::default())
+            }
         }
     }
 }
 
+/// Checks the IO trace recorded by an estimator run with the `icount` metric
+/// against the IO bytes counted by QEMU, see [`ReplayMode::IoBytesCheck`].
+pub fn check_io_bytes(trace: &Path, out: &mut dyn Write) -> anyhow::Result<()> {
+    let cmd = ReplayCmd {
+        trace: trace.to_owned(),
+        mode: ReplayMode::IoBytesCheck,
+        account: None,
+        stats: false,
+    };
+    cmd.run(out)
+}
+
 /// Interface for processing an IO trace line-by-line.
 ///
 /// The visitor default methods take over the basic parsing of the IO trace.
@@ -108,7 +173,7 @@ trait Visitor {
             let mut tokens = line.split_whitespace();
             if let Some(keyword) = tokens.next() {
                 match keyword {
-                    "GET" | "SET" | "UPDATE_RC" => {
+                    "GET" | "SET" | "UPDATE_RC" | "INSERT" => {
                         let col = tokens.next().context("missing column field in DB operation")?;
                         let mut key_str = tokens.next().context("missing key in DB operation")?;
                         if key_str.starts_with('"') {
@@ -207,6 +272,39 @@ trait Visitor {
     }
 }
 
+/// Self-instrumentation of a replay, printed with `--stats`.
+struct ReplayStats {
+    mode: ReplayMode,
+    lines: u64,
+    /// Time spent inside the visitor, excluding reading the trace.
+    visitor_time: Duration,
+    total_time: Duration,
+}
+
+impl std::fmt::Display for ReplayStats {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "replay statistics for {:?}", self.mode)?;
+        writeln!(f, "  lines processed: {:>12}", self.lines)?;
+        writeln!(f, "  visitor time:    {:>12.2?}", self.visitor_time)?;
+        writeln!(f, "  total time:      {:>12.2?}", self.total_time)?;
+        writeln!(f, "  peak memory:     {:>12}", display_peak_memory())
+    }
+}
+
+/// Peak resident memory of the process so far, as reported by `getrusage`.
+fn display_peak_memory() -> String {
+    let mut usage = std::mem::MaybeUninit::<libc::rusage>::uninit();
+    // SAFETY: `getrusage` only writes to the provided struct.
+    if unsafe { libc::getrusage(libc::RUSAGE_SELF, usage.as_mut_ptr()) } != 0 {
+        return "unknown".to_owned();
+    }
+    // SAFETY: `getrusage` succeeded and initialized the struct.
+    let max_rss = unsafe { usage.assume_init() }.ru_maxrss as u64;
+    // Linux reports kilobytes, macOS reports bytes.
+    let bytes = if cfg!(target_os = "macos") { max_rss } else { max_rss * 1024 };
+    bytesize::ByteSize::b(bytes).to_string_as(true)
+}
+
 fn extract_key_values<'a>(
     mut tokens: SplitWhitespace<'a>,
 ) -> anyhow::Result<BTreeMap<&'a str, &'a str>> {
@@ -304,7 +402,7 @@ GET State "stateKey10" size=500
         for trace_name in INPUT_TRACES {
             let dir = env!("CARGO_MANIFEST_DIR");
             let trace_path = std::path::Path::new(dir).join("res").join(trace_name);
-            let cmd = ReplayCmd { trace: trace_path, mode, account: None };
+            let cmd = ReplayCmd { trace: trace_path, mode, account: None, stats: false };
             let mut buffer = Vec::new();
             cmd.run(&mut buffer).unwrap_or_else(|e| {
                 panic!("command should not fail for input {trace_name}, failure was {e}")
@@ -331,12 +429,87 @@ GET State "stateKey10" size=500
         check_account_filter(ReplayMode::ReceiptDbStats);
     }
 
+    #[test]
+    fn test_span_counts() {
+        let trace = r#"
+apply_transactions shard_id=0
+  process_receipt receipt_id=id0
+    GET State "stateKey0" size=300
+span_counts roots=10 sampled_roots=1 apply_transactions=10 process_receipt=25
+apply_transactions shard_id=0
+span_counts roots=10 sampled_roots=1 apply_transactions=10 process_receipt=15
+"#;
+        let cmd = ReplayCmd {
+            trace: PathBuf::new(),
+            mode: ReplayMode::SpanCounts,
+            account: None,
+            stats: false,
+        };
+        let mut buffer = Vec::new();
+        cmd.run_on_input(trace.as_bytes(), &mut buffer).expect("failed replaying");
+        let output = String::from_utf8(buffer).unwrap();
+        assert_eq!(
+            output,
+            "2 of 20 top-level spans sampled (10.00%)\n          20 apply_transactions\n          40 process_receipt\n"
+        );
+    }
+
+    #[test]
+    fn test_io_bytes_check() {
+        let trace = r#"
+estimation cost=ActionTransfer
+measurement qemu_r_bytes=1000 qemu_w_bytes=100000
+  apply_transactions shard_id=0
+    GET State "stateKeyA" size=900
+    SET State "stateKeyB" size=50000
+estimation cost=ActionReceiptCreation
+measurement qemu_r_bytes=300 qemu_w_bytes=0
+  GET State "stateKeyC" size=300
+measurement qemu_r_bytes=200 qemu_w_bytes=0
+  GET State "stateKeyD" size=200
+GET State "stateKeyE" size=100000
+estimation cost=WasmInstruction
+measurement 
+"#;
+        let cmd = ReplayCmd {
+            trace: PathBuf::new(),
+            mode: ReplayMode::IoBytesCheck,
+            account: None,
+            stats: false,
+        };
+        let mut buffer = Vec::new();
+        cmd.run_on_input(trace.as_bytes(), &mut buffer).expect("failed replaying");
+        let output = String::from_utf8(buffer).unwrap();
+        assert_eq!(
+            output,
+            "ActionTransfer                           write qemu=100000 trace=50000 diff=+50000 in 1 measurements\n\
+             1 of 4 IO byte counts differ between QEMU and the IO trace by more than 10%\n"
+        );
+    }
+
+    #[test]
+    fn test_stats() {
+        let cmd = ReplayCmd {
+            trace: PathBuf::new(),
+            mode: ReplayMode::GasCharges,
+            account: None,
+            stats: true,
+        };
+        let mut buffer = Vec::new();
+        cmd.run_on_input(SYNTHETIC_TRACE.as_bytes(), &mut buffer).expect("failed replaying");
+        let output = String::from_utf8(buffer).unwrap();
+        let stats = output.split_once("replay statistics for GasCharges\n").unwrap().1;
+        let lines = SYNTHETIC_TRACE.lines().count();
+        assert!(stats.starts_with(&format!("  lines processed: {lines:>12}\n")), "{stats}");
+        assert!(stats.contains("  peak memory:"), "{stats}");
+    }
+
     #[track_caller]
     fn check_account_filter(mode: ReplayMode) {
         let account = Some("alice.near".to_owned());
         // trace path not used, will be read from in-memory input instead
         let trace = PathBuf::new();
-        let cmd = ReplayCmd { trace, mode, account };
+        let cmd = ReplayCmd { trace, mode, account, stats: false };
         let mut buffer = Vec::new();
         cmd.run_on_input(SYNTHETIC_TRACE.as_bytes(), &mut buffer).expect("failed replaying");
         let output =
@@ -0,0 +1,193 @@
+use super::Visitor;
+use anyhow::Context;
+use std::collections::BTreeMap;
+use std::io::Write;
+
+/// Relative difference between QEMU and trace byte counts that is still
+/// considered consistent.
+const RELATIVE_TOLERANCE: f64 = 0.1;
+/// Absolute difference in bytes that is always considered consistent, to
+/// ignore noise on estimations with almost no IO. One page.
+const ABSOLUTE_TOLERANCE: u64 = 4096;
+
+/// Visitor that compares the IO bytes counted by QEMU with the DB operations
+/// recorded in the IO trace of the same estimator run.
+///
+/// The estimator wraps each measurement in a `measurement` span annotated with
+/// the bytes QEMU counted for it, and marks the start of each estimation with
+/// an `estimation` span. Everything indented below a measurement is summed up:
+/// reads for `GET` and writes for `SET`, `UPDATE_RC` and `INSERT`.
+///
+/// Caches between the DB operations and the syscalls mean the two sides never
+/// match exactly. A large discrepancy, however, means QEMU counted IO that the
+/// trace does not explain, or the other way around, which would silently bias
+/// IO dependent parameters.
+#[derive(Default)]
+pub(super) struct IoBytesCheck {
+    /// Estimation that the following measurements belong to.
+    estimation: Option<Estimation>,
+    /// Measurement whose trace is currently being summed up.
+    measurement: Option<Measurement>,
+    num_estimations: u64,
+    num_discrepancies: u64,
+}
+
+#[derive(Default)]
+struct Estimation {
+    name: String,
+    num_measurements: u64,
+    qemu: IoBytes,
+    trace: IoBytes,
+}
+
+struct Measurement {
+    indent: usize,
+    qemu: IoBytes,
+    trace: IoBytes,
+}
+
+#[derive(Clone, Copy, Default)]
+struct IoBytes {
+    read: u64,
+    written: u64,
+}
+
+impl IoBytesCheck {
+    /// Ends the current measurement if `indent` is not nested inside it.
+    fn update_measurement(&mut self, indent: usize) {
+        if self.measurement.as_ref().map_or(false, |m| indent <= m.indent) {
+            self.finish_measurement();
+        }
+    }
+
+    fn finish_measurement(&mut self) {
+        if let Some(measurement) = self.measurement.take() {
+            // Measurements before the first estimation marker are still
+            // checked, attributed to an unnamed estimation.
+            let estimation = self.estimation.get_or_insert_with(Default::default);
+            estimation.num_measurements += 1;
+            estimation.qemu += measurement.qemu;
+            estimation.trace += measurement.trace;
+        }
+    }
+
+    fn finish_estimation(&mut self, out: &mut dyn Write) -> anyhow::Result<()> {
+        self.finish_measurement();
+        let estimation = match self.estimation.take() {
+            // Estimations measured by time have no QEMU counts to compare to.
+            Some(estimation) if estimation.num_measurements > 0 => estimation,
+            _ => return Ok(()),
+        };
+        self.num_estimations += 1;
+        let checks = [
+            ("read", estimation.qemu.read, estimation.trace.read),
+            ("write", estimation.qemu.written, estimation.trace.written),
+        ];
+        for (kind, qemu, trace) in checks {
+            if !within_tolerance(qemu, trace) {
+                self.num_discrepancies += 1;
+                writeln!(
+                    out,
+                    "{:<40} {kind:<5} qemu={qemu} trace={trace} diff={:+} in {} measurements",
+                    estimation.name,
+                    qemu as i128 - trace as i128,
+                    estimation.num_measurements,
+                )?;
+            }
+        }
+        Ok(())
+    }
+}
+
+fn within_tolerance(qemu: u64, trace: u64) -> bool {
+    let diff = qemu.abs_diff(trace);
+    diff <= ABSOLUTE_TOLERANCE || diff as f64 <= RELATIVE_TOLERANCE * qemu.max(trace) as f64
+}
+
+impl Visitor for IoBytesCheck {
+    fn eval_db_op(
+        &mut self,
+        _out: &mut dyn Write,
+        indent: usize,
+        op: &str,
+        size: Option<u64>,
+        _key: &[u8],
+        _col: &str,
+    ) -> anyhow::Result<()> {
+        self.update_measurement(indent);
+        if let Some(measurement) = &mut self.measurement {
+            let size = size.unwrap_or(0);
+            match op {
+                "GET" => measurement.trace.read += size,
+                "SET" | "UPDATE_RC" | "INSERT" => measurement.trace.written += size,
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    fn eval_storage_op(
+        &mut self,
+        _out: &mut dyn Write,
+        indent: usize,
+        _op: &str,
+        _dict: &BTreeMap<&str, &str>,
+    ) -> anyhow::Result<()> {
+        self.update_measurement(indent);
+        Ok(())
+    }
+
+    fn eval_label(
+        &mut self,
+        out: &mut dyn Write,
+        indent: usize,
+        label: &str,
+        dict: &BTreeMap<&str, &str>,
+    ) -> anyhow::Result<()> {
+        self.update_measurement(indent);
+        match label {
+            "estimation" => {
+                self.finish_estimation(out)?;
+                let name = dict.get("cost").context("estimation without cost name")?;
+                self.estimation =
+                    Some(Estimation { name: (*name).to_owned(), ..Default::default() });
+            }
+            "measurement" => {
+                self.finish_measurement();
+                let (read, written) = match (dict.get("qemu_r_bytes"), dict.get("qemu_w_bytes")) {
+                    (Some(read), Some(written)) => (read.parse()?, written.parse()?),
+                    // Not measured with QEMU, nothing to compare.
+                    _ => return Ok(()),
+                };
+                self.measurement = Some(Measurement {
+                    indent,
+                    qemu: IoBytes { read, written },
+                    trace: IoBytes::default(),
+                });
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    fn flush(&mut self, out: &mut dyn Write) -> anyhow::Result<()> {
+        self.finish_estimation(out)?;
+        writeln!(
+            out,
+            "{} of {} IO byte counts differ between QEMU and the IO trace by more than {:.0}%",
+            self.num_discrepancies,
+            2 * self.num_estimations,
+            100.0 * RELATIVE_TOLERANCE,
+        )?;
+        self.num_estimations = 0;
+        self.num_discrepancies = 0;
+        Ok(())
+    }
+}
+
+impl std::ops::AddAssign for IoBytes {
+    fn add_assign(&mut self, rhs: Self) {
+        self.read += rhs.read;
+        self.written += rhs.written;
+    }
+}
@@ -0,0 +1,59 @@
+use super::Visitor;
+use anyhow::Context;
+use std::collections::BTreeMap;
+use std::io::Write;
+
+/// Visitor that sums up the exact span counts of a sampled IO trace.
+///
+/// A sampled IO trace only contains the full details of some top-level spans.
+/// After each of them, a `span_counts` line lists how many spans of each name
+/// were created in total since the previous such line.
+#[derive(Default)]
+pub(super) struct SpanCounts {
+    roots: u64,
+    sampled_roots: u64,
+    by_name: BTreeMap<String, u64>,
+}
+
+impl Visitor for SpanCounts {
+    fn eval_label(
+        &mut self,
+        _out: &mut dyn Write,
+        _indent: usize,
+        label: &str,
+        dict: &BTreeMap<&str, &str>,
+    ) -> anyhow::Result<()> {
+        if label != "span_counts" {
+            return Ok(());
+        }
+        for (&key, value) in dict {
+            let count: u64 =
+                value.parse().with_context(|| format!("invalid span count {key}={value}"))?;
+            match key {
+                "roots" => self.roots += count,
+                "sampled_roots" => self.sampled_roots += count,
+                name => *self.by_name.entry(name.to_owned()).or_default() += count,
+            }
+        }
+        Ok(())
+    }
+
+    fn flush(&mut self, out: &mut dyn Write) -> anyhow::Result<()> {
+        writeln!(
+            out,
+            "{} of {} top-level spans sampled ({:.2}%)",
+            self.sampled_roots,
+            self.roots,
+            100.0 * self.sampled_roots as f64 / self.roots as f64
+        )?;
+        for (name, count) in &self.by_name {
+            writeln!(out, "{count:>12} {name}")?;
+        }
+
+        self.roots = 0;
+        self.sampled_roots = 0;
+        self.by_name.clear();
+
+        Ok(())
+    }
+}
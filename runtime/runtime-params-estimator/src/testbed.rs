@@ -1,16 +1,26 @@
+use crate::parallel_receipts::{
+    check_final_states_equal, final_state, group_by_receiver, merge_final_states, FinalState,
+    ParallelReceiptsStats,
+};
+use crate::pipeline_latency::PipelineLatency;
 use genesis_populate::state_dump::StateDump;
+use near_primitives::hash::CryptoHash;
 use near_primitives::receipt::Receipt;
 use near_primitives::runtime::config_store::RuntimeConfigStore;
 use near_primitives::runtime::migration_data::{MigrationData, MigrationFlags};
 use near_primitives::test_utils::MockEpochInfoProvider;
-use near_primitives::transaction::{ExecutionStatus, SignedTransaction};
+use near_primitives::transaction::{ExecutionOutcomeWithId, ExecutionStatus, SignedTransaction};
+use near_primitives::trie_key::TrieKey;
 use near_primitives::types::{Gas, MerkleHash};
 use near_primitives::version::PROTOCOL_VERSION;
 use near_store::{ShardTries, ShardUId, Store, StoreCompiledContractCache};
-use near_vm_logic::VMLimitConfig;
-use node_runtime::{ApplyState, Runtime};
+use near_vm_logic::{MemoryUsage, VMLimitConfig};
+use node_runtime::{ApplyResult, ApplyState, Runtime};
+use std::fmt;
 use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 
 pub struct RuntimeTestbed {
     /// Directory where we temporarily keep the storage.
@@ -21,6 +31,63 @@ pub struct RuntimeTestbed {
     prev_receipts: Vec<Receipt>,
     apply_state: ApplyState,
     epoch_info_provider: MockEpochInfoProvider,
+    /// When set, trie reads are recorded as required for a state witness and
+    /// the size of the largest witness of a block is kept here.
+    max_recorded_storage_size: Option<Arc<AtomicUsize>>,
+    /// Peak memory used by a single function call in any block processed so far.
+    peak_memory_usage: MemoryUsage,
+    /// Results of the block processed last.
+    last_block: AppliedBlock,
+}
+
+/// Results of applying a single block, kept for assertions on the outcomes.
+#[derive(Debug, Default)]
+pub struct AppliedBlock {
+    /// Number of transactions in the block.
+    pub transactions: usize,
+    /// Outcomes of the transactions first, then of the executed receipts.
+    pub outcomes: Vec<ExecutionOutcomeWithId>,
+    pub outgoing_receipts: usize,
+    /// Trie keys written or deleted while applying the block, in order.
+    pub changed_keys: Vec<TrieKey>,
+}
+
+impl AppliedBlock {
+    /// Number of receipts executed in the block, local receipts included.
+    pub fn executed_receipts(&self) -> usize {
+        self.outcomes.len() - self.transactions
+    }
+}
+
+/// Summary of the block with one line per outcome and changed key. It only
+/// depends on the transactions and the runtime, not on timing, so it can be
+/// compared between runs.
+impl fmt::Display for AppliedBlock {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "transactions: {}, executed receipts: {}, outgoing receipts: {}",
+            self.transactions,
+            self.executed_receipts(),
+            self.outgoing_receipts
+        )?;
+        for ExecutionOutcomeWithId { id, outcome } in &self.outcomes {
+            writeln!(
+                f,
+                "outcome {id} executor={} gas_burnt={} tokens_burnt={} receipts={} logs={} status={:?}",
+                outcome.executor_id,
+                outcome.gas_burnt,
+                outcome.tokens_burnt,
+                outcome.receipt_ids.len(),
+                outcome.logs.len(),
+                outcome.status,
+            )?;
+        }
+        for key in &self.changed_keys {
+            writeln!(f, "changed {key:?}")?;
+        }
+        Ok(())
+    }
 }
 
 impl RuntimeTestbed {
@@ -41,6 +108,7 @@ impl RuntimeTestbed {
             trie_config,
             &shard_uids,
             near_store::flat_state::FlatStateFactory::new(store.clone()),
+            None,
         );
 
         assert!(roots.len() <= 1, "Parameter estimation works with one shard only.");
@@ -99,18 +167,78 @@ impl RuntimeTestbed {
             prev_receipts,
             apply_state,
             epoch_info_provider: MockEpochInfoProvider::default(),
+            max_recorded_storage_size: None,
+            peak_memory_usage: MemoryUsage::default(),
+            last_block: AppliedBlock::default(),
         }
     }
 
+    /// Record all trie nodes read while applying blocks, as is needed to
+    /// produce a state witness. The size of the largest witness of a block is
+    /// written to `max_size`, unless it already holds a larger value.
+    pub fn record_storage(&mut self, max_size: Arc<AtomicUsize>) {
+        self.max_recorded_storage_size = Some(max_size);
+    }
+
+    /// Peak memory used by a single function call in any block processed so
+    /// far, as a basis for memory-based fees.
+    pub fn peak_memory_usage(&self) -> MemoryUsage {
+        self.peak_memory_usage
+    }
+
     pub fn process_block(
         &mut self,
         transactions: &[SignedTransaction],
         allow_failures: bool,
     ) -> Gas {
+        let apply_result = self.apply_block(transactions);
+        self.commit_block(transactions.len(), apply_result, allow_failures)
+    }
+
+    /// Like `process_block`, but goes through all stages a node processing
+    /// the block goes through: stateless validation of the transactions,
+    /// `Runtime::apply`, committing the trie changes and flushing the RocksDB
+    /// memtable. Returns the time spent in each stage.
+    pub(crate) fn process_block_pipeline(
+        &mut self,
+        transactions: &[SignedTransaction],
+    ) -> anyhow::Result<PipelineLatency> {
+        let limit_config = &self.apply_state.config.wasm_config.limit_config;
+        let start = Instant::now();
+        for tx in transactions {
+            tx.validate(limit_config)?;
+            anyhow::ensure!(
+                tx.signature.verify(tx.get_hash().as_ref(), tx.transaction.public_key()),
+                "invalid signature of transaction {}",
+                tx.get_hash()
+            );
+        }
+        let validation = start.elapsed();
+
+        let start = Instant::now();
+        let apply_result = self.apply_block(transactions);
+        let apply = start.elapsed();
+
+        let start = Instant::now();
+        let allow_failures = false;
+        let gas_burnt = self.commit_block(transactions.len(), apply_result, allow_failures);
+        let commit = start.elapsed();
+
+        let start = Instant::now();
+        self.flush_db_write_buffer();
+        let flush = start.elapsed();
+
+        Ok(PipelineLatency { blocks: 1, gas_burnt, validation, apply, commit, flush })
+    }
+
+    fn apply_block(&mut self, transactions: &[SignedTransaction]) -> ApplyResult {
+        let trie = self.tries.get_trie_for_shard(ShardUId::single_shard(), self.root.clone());
+        let trie =
+            if self.max_recorded_storage_size.is_some() { trie.recording_reads() } else { trie };
         let apply_result = self
             .runtime
             .apply(
-                self.tries.get_trie_for_shard(ShardUId::single_shard(), self.root.clone()),
+                trie,
                 &None,
                 &self.apply_state,
                 &self.prev_receipts,
@@ -120,6 +248,23 @@ impl RuntimeTestbed {
             )
             .unwrap();
 
+        if let (Some(max_size), Some(proof)) =
+            (&self.max_recorded_storage_size, &apply_result.proof)
+        {
+            let size = proof.nodes.0.iter().map(|node| node.len()).sum();
+            max_size.fetch_max(size, Ordering::Relaxed);
+        }
+        self.peak_memory_usage.merge_max(apply_result.stats.peak_memory_usage);
+        apply_result
+    }
+
+    /// Commits the trie changes of the block and prepares the next block.
+    fn commit_block(
+        &mut self,
+        num_transactions: usize,
+        apply_result: ApplyResult,
+        allow_failures: bool,
+    ) -> Gas {
         let mut store_update = self.tries.store_update();
         self.root = self.tries.apply_all(
             &apply_result.trie_changes,
@@ -139,10 +284,72 @@ impl RuntimeTestbed {
                 }
             }
         }
+        self.last_block = AppliedBlock {
+            transactions: num_transactions,
+            outcomes: apply_result.outcomes,
+            outgoing_receipts: apply_result.outgoing_receipts.len(),
+            changed_keys: apply_result
+                .state_changes
+                .into_iter()
+                .map(|changes| changes.trie_key)
+                .collect(),
+        };
         self.prev_receipts = apply_result.outgoing_receipts;
         total_burnt_gas
     }
 
+    /// Results of the block processed last.
+    pub fn last_block(&self) -> &AppliedBlock {
+        &self.last_block
+    }
+
+    /// Panics unless the transaction or receipt `id` was executed in the last
+    /// block with the `expected` status.
+    #[track_caller]
+    pub fn expect_status(&self, id: &CryptoHash, expected: &ExecutionStatus) {
+        let outcome = self
+            .last_block
+            .outcomes
+            .iter()
+            .find(|outcome| &outcome.id == id)
+            .unwrap_or_else(|| panic!("{id} was not executed in the last block"));
+        assert_eq!(&outcome.outcome.status, expected, "unexpected status of {id}");
+    }
+
+    /// Panics unless all transactions and receipts of the last block succeeded.
+    #[track_caller]
+    pub fn expect_all_succeeded(&self) {
+        for outcome in &self.last_block.outcomes {
+            if let ExecutionStatus::Failure(err) = &outcome.outcome.status {
+                panic!("{} failed: {err:?}", outcome.id);
+            }
+        }
+    }
+
+    /// Panics unless the last block executed `executed` receipts and produced
+    /// `outgoing` receipts for the next block.
+    #[track_caller]
+    pub fn expect_receipt_counts(&self, executed: usize, outgoing: usize) {
+        assert_eq!(
+            (self.last_block.executed_receipts(), self.last_block.outgoing_receipts),
+            (executed, outgoing),
+            "unexpected (executed, outgoing) receipt counts"
+        );
+    }
+
+    /// Panics unless all of the `keys` were changed in the last block. Other
+    /// keys may have been changed as well.
+    #[track_caller]
+    pub fn expect_state_keys_changed(&self, keys: &[TrieKey]) {
+        let missing: Vec<&TrieKey> =
+            keys.iter().filter(|key| !self.last_block.changed_keys.contains(key)).collect();
+        assert!(
+            missing.is_empty(),
+            "keys not changed in the last block: {missing:?}, changed keys: {:?}",
+            self.last_block.changed_keys
+        );
+    }
+
     /// Returns the number of blocks required to reach quiescence
     pub fn process_blocks_until_no_receipts(&mut self, allow_failures: bool) -> usize {
         let mut n = 0;
@@ -153,6 +360,63 @@ impl RuntimeTestbed {
         n
     }
 
+    /// Research mode: applies the pending receipts serially and grouped by
+    /// receiver in parallel, both on top of the current state, and checks
+    /// that the final state is the same.
+    ///
+    /// Nothing is committed, the testbed is left unchanged. View tries are
+    /// used so that the parallel applies do not share a prefetcher.
+    pub(crate) fn compare_parallel_receipts(&self) -> anyhow::Result<ParallelReceiptsStats> {
+        let apply = |receipts: &[Receipt]| -> anyhow::Result<(FinalState, Duration)> {
+            let trie = self.tries.get_view_trie_for_shard(ShardUId::single_shard(), self.root);
+            let start = Instant::now();
+            let apply_result = self.runtime.apply(
+                trie,
+                &None,
+                &self.apply_state,
+                receipts,
+                &[],
+                &self.epoch_info_provider,
+                Default::default(),
+            )?;
+            Ok((final_state(apply_result.state_changes), start.elapsed()))
+        };
+        let apply = &apply;
+
+        let (serial_state, serial) = apply(&self.prev_receipts)?;
+
+        let groups = group_by_receiver(&self.prev_receipts);
+        let start = Instant::now();
+        let results = std::thread::scope(|scope| {
+            let handles: Vec<_> =
+                groups.iter().map(|group| scope.spawn(move || apply(group))).collect();
+            handles
+                .into_iter()
+                .map(|handle| handle.join().expect("receipt group panicked"))
+                .collect::<anyhow::Result<Vec<_>>>()
+        })?;
+        let parallel = start.elapsed();
+
+        let critical_path = results.iter().map(|(_, duration)| *duration).max().unwrap_or_default();
+        let parallel_state =
+            merge_final_states(results.into_iter().map(|(state, _)| state).collect())?;
+        check_final_states_equal(&serial_state, &parallel_state)?;
+
+        Ok(ParallelReceiptsStats {
+            blocks: 1,
+            receipts: self.prev_receipts.len(),
+            groups: groups.len(),
+            serial,
+            parallel,
+            critical_path,
+        })
+    }
+
+    /// Returns true if there are receipts to be processed in the next block.
+    pub fn has_pending_receipts(&self) -> bool {
+        !self.prev_receipts.is_empty()
+    }
+
     /// Flushes RocksDB memtable
     pub fn flush_db_write_buffer(&mut self) {
         self.tries.get_store().flush().unwrap();
@@ -3,10 +3,11 @@ use crate::gas_cost::{GasCost, NonNegativeTolerance};
 use crate::utils::{aggregate_per_block_measurements, overhead_per_measured_block, percentiles};
 use near_primitives::hash::hash;
 use near_primitives::types::TrieCacheMode;
-use near_store::{TrieCachingStorage, TrieStorage};
+use near_store::{ShardUId, Store, TrieCache, TrieCachingStorage, TrieConfig, TrieStorage};
 use near_vm_logic::ExtCosts;
 use std::iter;
-use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Barrier;
 
 static SINK: AtomicUsize = AtomicUsize::new(0);
 
@@ -328,6 +329,145 @@ fn read_node_from_chunk_cache_ext(
         .collect()
 }
 
+/// Measures reads served by the shard cache while other threads emulating view
+/// calls read from the same `TrieCache`, to quantify contention on the lock
+/// around it.
+pub(crate) fn read_node_from_contended_shard_cache(testbed: &mut Testbed) -> GasCost {
+    let debug = testbed.config.debug;
+    let percentiles_of_interest = &[0.5, 0.9, 0.99, 0.999];
+    // RPC nodes serve many view calls in parallel, a handful of them hitting
+    // the same shard at once is a realistic load.
+    let num_view_threads = 4;
+
+    let mut estimation = |debug_name: &str, num_view_threads: usize| {
+        let results = read_node_from_contended_shard_cache_ext(testbed, num_view_threads);
+        let p_results = percentiles(results, percentiles_of_interest).collect::<Vec<_>>();
+        if debug {
+            eprint!("{:<32}", debug_name);
+            for cost in p_results.iter() {
+                eprint!("{:>8} ", cost.to_gas() / 1_000_000);
+            }
+            eprintln!();
+        }
+        p_results
+    };
+
+    // Print header of debug table
+    if debug {
+        eprintln!(
+            "{:<32}{:>8.3} {:>8.3} {:>8.3} {:>8.3}",
+            "",
+            percentiles_of_interest[0],
+            percentiles_of_interest[1],
+            percentiles_of_interest[2],
+            percentiles_of_interest[3]
+        );
+    }
+
+    // Take the 90th percentile measured.
+    let base_case = estimation("Base Case", num_view_threads).swap_remove(1);
+
+    // If debug output is enabled, repeat the estimation with different numbers
+    // of view threads, to see how latency scales with the load.
+    if debug {
+        for num_view_threads in [0, 1, 2, 8, 16] {
+            estimation(&format!("{} view threads", num_view_threads), num_view_threads);
+        }
+    }
+
+    base_case
+}
+
+fn read_node_from_contended_shard_cache_ext(
+    testbed: &mut Testbed,
+    // Threads reading from the shard cache in a loop while measuring.
+    num_view_threads: usize,
+) -> Vec<GasCost> {
+    let iters = 200;
+    // Nodes read in each iteration. All of them fit in the shard cache.
+    let num_values = 64;
+    // The shard cache only keeps values below 1000 bytes, so nodes cannot be
+    // as large as in `read_node_from_chunk_cache_ext`. Extension nodes with a
+    // key of 900 bytes are about the largest nodes that are still cached.
+    let value_len: usize = 900;
+    let metric = testbed.config.metric;
+
+    // Setup: Insert worst-case trie nodes, the same way as
+    // `read_node_from_chunk_cache_ext` does.
+    let tb = testbed.transaction_builder();
+    let signer = tb.random_account();
+    let values: Vec<_> = (0..num_values)
+        .map(|_| {
+            let extension_key = tb.random_vec(value_len);
+            near_store::estimator::encode_extension_node(extension_key)
+        })
+        .collect();
+    let mut setup_block = Vec::new();
+    for (j, value) in values.iter().enumerate() {
+        let key = j.to_le_bytes().to_vec();
+        setup_block.push(tb.account_insert_key(signer.clone(), &key, value));
+    }
+    testbed.process_block(setup_block, 0);
+    let value_hashes: Vec<_> = values.iter().map(|value| hash(value)).collect();
+
+    // A shard cache shared between the storage used for chunk application and
+    // the storages of view calls, like `ShardTries` would share it. In the
+    // default mode, nodes are not kept in the chunk cache, so every read below
+    // goes through the shard cache and its lock.
+    let store = testbed.store();
+    let shard_cache = TrieCache::new(&TrieConfig::default(), ShardUId::single_shard(), false);
+    let caching_storage = shard_cache_storage(&store, &shard_cache, false);
+    caching_storage.set_mode(TrieCacheMode::CachingShard);
+    let dummy_sum = read_raw_nodes_from_storage(&caching_storage, &value_hashes);
+    SINK.fetch_add(dummy_sum, Ordering::SeqCst);
+
+    let stop = AtomicBool::new(false);
+    let started = Barrier::new(num_view_threads + 1);
+    std::thread::scope(|s| {
+        for _ in 0..num_view_threads {
+            s.spawn(|| {
+                let view_storage = shard_cache_storage(&store, &shard_cache, true);
+                let mut dummy_sum = read_raw_nodes_from_storage(&view_storage, &value_hashes);
+                started.wait();
+                while !stop.load(Ordering::Relaxed) {
+                    dummy_sum += read_raw_nodes_from_storage(&view_storage, &value_hashes);
+                }
+                SINK.fetch_add(dummy_sum, Ordering::SeqCst);
+            });
+        }
+        started.wait();
+
+        let results = (0..iters)
+            .map(|_| {
+                let start = GasCost::measure(metric);
+                let dummy_sum = read_raw_nodes_from_storage(&caching_storage, &value_hashes);
+                let cost = start.elapsed();
+                SINK.fetch_add(dummy_sum, Ordering::SeqCst);
+
+                cost / num_values as u64
+            })
+            .collect();
+        stop.store(true, Ordering::Relaxed);
+        results
+    })
+}
+
+/// Creates a storage reading through the given, possibly shared, shard cache.
+fn shard_cache_storage(
+    store: &Store,
+    shard_cache: &TrieCache,
+    is_view: bool,
+) -> TrieCachingStorage {
+    let prefetcher = None;
+    TrieCachingStorage::new(
+        store.clone(),
+        shard_cache.clone(),
+        ShardUId::single_shard(),
+        is_view,
+        prefetcher,
+    )
+}
+
 /// Read trie nodes directly from a `TrieCachingStorage`, without the runtime.
 /// Keys are hashes of the nodes.
 /// The return value is just a value to ensure nothing gets optimized out by the
@@ -5,6 +5,7 @@ use crate::transaction_builder::TransactionBuilder;
 use near_primitives::transaction::{
     Action, DeployContractAction, FunctionCallAction, SignedTransaction,
 };
+use near_primitives::types::AccountId;
 use near_vm_logic::{ExtCosts, VMConfig};
 use rand::distributions::Alphanumeric;
 use rand::Rng;
@@ -228,6 +229,24 @@ pub(crate) fn fn_cost_in_contract(
     method: &str,
     code: &[u8],
     n_actions: usize,
+) -> GasCost {
+    let setup =
+        |_: &AccountId| vec![Action::DeployContract(DeployContractAction { code: code.to_vec() })];
+    let action = || function_call_action(method.to_string());
+    actions_cost_in_contract(ctx, setup, action, n_actions)
+}
+
+/// Like `fn_cost_in_contract` but with arbitrary actions.
+///
+/// `setup` returns the actions that each account executes on itself before
+/// the measurement, usually a deployment followed by calls that initialize
+/// the contract. It must not produce receipts that outlive its block.
+/// `action` returns the action to measure.
+pub(crate) fn actions_cost_in_contract(
+    ctx: &mut EstimatorContext,
+    setup: impl Fn(&AccountId) -> Vec<Action>,
+    action: impl Fn() -> Action,
+    n_actions: usize,
 ) -> GasCost {
     let n_warmup_blocks = ctx.config.warmup_iters_per_block;
     let n_blocks = n_warmup_blocks + ctx.config.iter_per_block;
@@ -241,8 +260,8 @@ pub(crate) fn fn_cost_in_contract(
 
     for account in &chosen_accounts {
         let tb = testbed.transaction_builder();
-        let setup = vec![Action::DeployContract(DeployContractAction { code: code.to_vec() })];
-        let setup_tx = tb.transaction_from_actions(account.clone(), account.clone(), setup);
+        let setup_tx =
+            tb.transaction_from_actions(account.clone(), account.clone(), setup(account));
 
         testbed.process_block(vec![setup_tx], 0);
     }
@@ -250,9 +269,7 @@ pub(crate) fn fn_cost_in_contract(
     let mut blocks = Vec::with_capacity(n_blocks);
     // Measurement blocks with single tx with many actions.
     for account in chosen_accounts.drain(..n_blocks) {
-        let actions = iter::repeat_with(|| function_call_action(method.to_string()))
-            .take(n_actions)
-            .collect();
+        let actions = iter::repeat_with(&action).take(n_actions).collect();
         let tx = testbed.transaction_builder().transaction_from_actions(
             account.clone(),
             account,
@@ -265,7 +282,7 @@ pub(crate) fn fn_cost_in_contract(
     let base_tx = testbed.transaction_builder().transaction_from_actions(
         final_account.clone(),
         final_account,
-        vec![function_call_action(method.to_string())],
+        vec![action()],
     );
     blocks.insert(n_warmup_blocks, vec![base_tx]);
 
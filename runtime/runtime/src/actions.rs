@@ -12,12 +12,16 @@ use near_primitives::hash::CryptoHash;
 use near_primitives::receipt::{ActionReceipt, Receipt, ReceiptEnum};
 use near_primitives::runtime::config::AccountCreationConfig;
 use near_primitives::runtime::fees::RuntimeFeesConfig;
+#[cfg(feature = "protocol_feature_create_account_with_contract")]
+use near_primitives::transaction::CreateAccountWithContractAction;
 use near_primitives::transaction::{
     Action, AddKeyAction, DeleteAccountAction, DeleteKeyAction, DeployContractAction,
     FunctionCallAction, StakeAction, TransferAction,
 };
+#[cfg(feature = "protocol_feature_create_account_with_contract")]
+use near_primitives::trie_key::TrieKey;
 use near_primitives::types::validator_stake::ValidatorStake;
-use near_primitives::types::{AccountId, BlockHeight, EpochInfoProvider, TrieCacheMode};
+use near_primitives::types::{AccountId, BlockHeight, EpochInfoProvider, Gas, TrieCacheMode};
 use near_primitives::utils::create_random_seed;
 use near_primitives::version::{
     is_implicit_account_creation_enabled, ProtocolFeature, ProtocolVersion,
@@ -86,6 +90,8 @@ pub(crate) fn execute_function_call(
         block_height: apply_state.block_height,
         block_timestamp: apply_state.block_timestamp,
         epoch_height: apply_state.epoch_height,
+        block_gas_price: apply_state.gas_price,
+        block_gas_limit: apply_state.gas_limit.unwrap_or(Gas::MAX),
         account_balance: account.amount(),
         account_locked_balance: account.locked(),
         storage_usage: account.storage_usage(),
@@ -101,7 +107,7 @@ pub(crate) fn execute_function_call(
     // charge only for trie nodes touched during function calls.
     // TODO (#5920): Consider using RAII for switching the state back
     let protocol_version = runtime_ext.protocol_version();
-    if checked_feature!("stable", ChunkNodesCache, protocol_version) {
+    if ProtocolFeature::ChunkNodesCache.enabled(protocol_version) {
         runtime_ext.set_trie_cache_mode(TrieCacheMode::CachingChunk);
     }
     let result = near_vm_runner::run(
@@ -115,7 +121,7 @@ pub(crate) fn execute_function_call(
         apply_state.current_protocol_version,
         apply_state.cache.as_deref(),
     );
-    if checked_feature!("stable", ChunkNodesCache, protocol_version) {
+    if ProtocolFeature::ChunkNodesCache.enabled(protocol_version) {
         runtime_ext.set_trie_cache_mode(TrieCacheMode::CachingShard);
     }
 
@@ -238,6 +244,7 @@ pub(crate) fn action_function_call(
         }
         // Update action result with the abort error converted to the
         // transaction runtime's format of errors.
+        result.error_code = Some(err.code());
         let ser: FunctionCallErrorSer = err.into();
         let action_err: ActionError = ActionErrorKind::FunctionCallError(ser).into();
         result.result = Err(action_err);
@@ -251,7 +258,9 @@ pub(crate) fn action_function_call(
     // `FunctionCall`s error.
     result.gas_used = safe_add_gas(result.gas_used, outcome.used_gas)?;
     result.logs.extend(outcome.logs);
+    result.events.extend(outcome.events);
     result.profile.merge(&outcome.profile);
+    result.memory_usage.merge_max(outcome.memory_usage);
     if execution_succeeded {
         let new_receipts: Vec<_> = outcome
             .action_receipts
@@ -269,6 +278,7 @@ pub(crate) fn action_function_call(
                     output_data_receivers: receipt.output_data_receivers,
                     input_data_ids: receipt.input_data_ids,
                     actions: receipt.actions,
+                    priority: action_receipt.priority,
                 }),
             })
             .collect();
@@ -435,7 +445,7 @@ pub(crate) fn action_implicit_account_creation_transfer(
     let mut access_key = AccessKey::full_access();
     // Set default nonce for newly created access key to avoid transaction hash collision.
     // See <https://github.com/near/nearcore/issues/3779>.
-    if checked_feature!("stable", AccessKeyNonceForImplicitAccounts, current_protocol_version) {
+    if ProtocolFeature::AccessKeyNonceForImplicitAccounts.enabled(current_protocol_version) {
         access_key.nonce = (block_height - 1)
             * near_primitives::account::AccessKey::ACCESS_KEY_NONCE_RANGE_MULTIPLIER;
     }
@@ -471,8 +481,26 @@ pub(crate) fn action_deploy_contract(
     deploy_contract: &DeployContractAction,
     apply_state: &ApplyState,
     current_protocol_version: ProtocolVersion,
+    result: &mut ActionResult,
 ) -> Result<(), StorageError> {
     let _span = tracing::debug_span!(target: "runtime", "action_deploy_contract").entered();
+    if checked_feature!(
+        "protocol_feature_limit_contract_sections",
+        LimitContractSections,
+        current_protocol_version
+    ) {
+        if let Err(error) = near_vm_runner::prepare::check_contract_section_limits(
+            &deploy_contract.code,
+            &apply_state.config.wasm_config,
+        ) {
+            result.result = Err(ActionErrorKind::ContractLimitExceeded {
+                account_id: account_id.clone(),
+                error,
+            }
+            .into());
+            return Ok(());
+        }
+    }
     let code = ContractCode::new(deploy_contract.code.clone(), None);
     let prev_code = get_code(state_update, account_id, Some(account.code_hash()))?;
     let prev_code_length = prev_code.map(|code| code.code().len() as u64).unwrap_or_default();
@@ -500,6 +528,78 @@ pub(crate) fn action_deploy_contract(
     Ok(())
 }
 
+/// Creates an account like [`action_create_account`], deploys the contract to
+/// it and writes the initial contract state.  Nothing is deployed if the
+/// account can't be created.
+#[cfg(feature = "protocol_feature_create_account_with_contract")]
+pub(crate) fn action_create_account_with_contract(
+    state_update: &mut TrieUpdate,
+    apply_state: &ApplyState,
+    account: &mut Option<Account>,
+    actor_id: &mut AccountId,
+    account_id: &AccountId,
+    predecessor_id: &AccountId,
+    result: &mut ActionResult,
+    action: &CreateAccountWithContractAction,
+) -> Result<(), StorageError> {
+    action_create_account(
+        &apply_state.config.transaction_costs,
+        &apply_state.config.account_creation_config,
+        account,
+        actor_id,
+        account_id,
+        predecessor_id,
+        result,
+    );
+    if result.result.is_err() {
+        return Ok(());
+    }
+    let account = account.as_mut().expect("account was created above");
+    action_deploy_contract(
+        state_update,
+        account,
+        account_id,
+        &DeployContractAction { code: action.code.clone() },
+        apply_state,
+        apply_state.current_protocol_version,
+        result,
+    )?;
+    if result.result.is_err() {
+        return Ok(());
+    }
+
+    // Later entries overwrite earlier ones with the same key, collect them
+    // first so that storage usage is only counted for the stored values.
+    let state: std::collections::BTreeMap<&[u8], &[u8]> =
+        action.state.iter().map(|entry| (entry.key.as_slice(), entry.value.as_slice())).collect();
+    let num_extra_bytes_record =
+        apply_state.config.transaction_costs.storage_usage_config.num_extra_bytes_record;
+    let mut state_storage_usage: u64 = 0;
+    for (key, value) in state {
+        state_storage_usage = state_storage_usage
+            .checked_add(key.len() as u64 + value.len() as u64 + num_extra_bytes_record)
+            .ok_or_else(|| {
+                StorageError::StorageInconsistentState(format!(
+                    "Storage usage integer overflow for account {}",
+                    account_id
+                ))
+            })?;
+        state_update.set(
+            TrieKey::ContractData { account_id: account_id.clone(), key: key.to_vec() },
+            value.to_vec(),
+        );
+    }
+    account.set_storage_usage(
+        account.storage_usage().checked_add(state_storage_usage).ok_or_else(|| {
+            StorageError::StorageInconsistentState(format!(
+                "Storage usage integer overflow for account {}",
+                account_id
+            ))
+        })?,
+    );
+    Ok(())
+}
+
 pub(crate) fn action_delete_account(
     state_update: &mut TrieUpdate,
     account: &mut Option<Account>,
@@ -592,7 +692,7 @@ pub(crate) fn action_add_key(
         .into());
         return Ok(());
     }
-    if checked_feature!("stable", AccessKeyNonceRange, apply_state.current_protocol_version) {
+    if ProtocolFeature::AccessKeyNonceRange.enabled(apply_state.current_protocol_version) {
         let mut access_key = add_key.access_key.clone();
         access_key.nonce = (apply_state.block_height - 1)
             * near_primitives::account::AccessKey::ACCESS_KEY_NONCE_RANGE_MULTIPLIER;
@@ -657,6 +757,8 @@ pub(crate) fn check_actor_permissions(
             }
         }
         Action::CreateAccount(_) | Action::FunctionCall(_) | Action::Transfer(_) => (),
+        #[cfg(feature = "protocol_feature_create_account_with_contract")]
+        Action::CreateAccountWithContract(_) => (),
     };
     Ok(())
 }
@@ -671,30 +773,11 @@ pub(crate) fn check_account_existence(
 ) -> Result<(), ActionError> {
     match action {
         Action::CreateAccount(_) => {
-            if account.is_some() {
-                return Err(ActionErrorKind::AccountAlreadyExists {
-                    account_id: account_id.clone(),
-                }
-                .into());
-            } else {
-                if is_implicit_account_creation_enabled(current_protocol_version)
-                    && account_id.is_implicit()
-                {
-                    // If the account doesn't exist and it's 64-length hex account ID, then you
-                    // should only be able to create it using single transfer action.
-                    // Because you should not be able to add another access key to the account in
-                    // the same transaction.
-                    // Otherwise you can hijack an account without having the private key for the
-                    // public key. We've decided to make it an invalid transaction to have any other
-                    // actions on the 64-length hex accounts.
-                    // The easiest way is to reject the `CreateAccount` action.
-                    // See https://github.com/nearprotocol/NEPs/pull/71
-                    return Err(ActionErrorKind::OnlyImplicitAccountCreationAllowed {
-                        account_id: account_id.clone(),
-                    }
-                    .into());
-                }
-            }
+            check_account_can_be_created(account, account_id, current_protocol_version)?
+        }
+        #[cfg(feature = "protocol_feature_create_account_with_contract")]
+        Action::CreateAccountWithContract(_) => {
+            check_account_can_be_created(account, account_id, current_protocol_version)?
         }
         Action::Transfer(_) => {
             if account.is_none() {
@@ -735,6 +818,35 @@ pub(crate) fn check_account_existence(
     Ok(())
 }
 
+fn check_account_can_be_created(
+    account: &Option<Account>,
+    account_id: &AccountId,
+    current_protocol_version: ProtocolVersion,
+) -> Result<(), ActionError> {
+    if account.is_some() {
+        return Err(ActionErrorKind::AccountAlreadyExists { account_id: account_id.clone() }.into());
+    } else {
+        if is_implicit_account_creation_enabled(current_protocol_version)
+            && account_id.is_implicit()
+        {
+            // If the account doesn't exist and it's 64-length hex account ID, then you
+            // should only be able to create it using single transfer action.
+            // Because you should not be able to add another access key to the account in
+            // the same transaction.
+            // Otherwise you can hijack an account without having the private key for the
+            // public key. We've decided to make it an invalid transaction to have any other
+            // actions on the 64-length hex accounts.
+            // The easiest way is to reject the `CreateAccount` action.
+            // See https://github.com/nearprotocol/NEPs/pull/71
+            return Err(ActionErrorKind::OnlyImplicitAccountCreationAllowed {
+                account_id: account_id.clone(),
+            }
+            .into());
+        }
+    }
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use near_primitives::hash::hash;
@@ -4,30 +4,33 @@ use crate::config::{
     safe_add_balance, safe_add_gas, safe_gas_to_balance, total_deposit, total_prepaid_exec_fees,
     total_prepaid_gas,
 };
-use crate::{ApplyStats, DelayedReceiptIndices, ValidatorAccountsUpdate};
+use crate::{ApplyStats, ValidatorAccountsUpdate};
 use near_primitives::errors::{
     BalanceMismatchError, IntegerOverflowError, RuntimeError, StorageError,
 };
-use near_primitives::receipt::{Receipt, ReceiptEnum};
+use near_primitives::receipt::{DelayedReceiptQueue, Receipt, ReceiptEnum};
 use near_primitives::runtime::fees::RuntimeFeesConfig;
 use near_primitives::transaction::SignedTransaction;
 use near_primitives::trie_key::TrieKey;
 use near_primitives::types::{AccountId, Balance};
 use near_primitives::version::ProtocolVersion;
-use near_store::{get, get_account, get_postponed_receipt, TrieAccess, TrieUpdate};
+use near_store::{
+    get, get_account, get_postponed_receipt, DelayedReceiptQueues, TrieAccess, TrieUpdate,
+};
 use std::collections::HashSet;
 
-/// Returns delayed receipts with given range of indices.
+/// Returns delayed receipts of the given queue with given range of indices.
 fn get_delayed_receipts(
     state: &dyn TrieAccess,
+    queue: DelayedReceiptQueue,
     indexes: std::ops::Range<u64>,
 ) -> Result<Vec<Receipt>, StorageError> {
     indexes
         .map(|index| {
-            get(state, &TrieKey::DelayedReceipt { index })?.ok_or_else(|| {
+            get(state, &queue.receipt_key(index))?.ok_or_else(|| {
                 StorageError::StorageInconsistentState(format!(
-                    "Delayed receipt #{} should be in the state",
-                    index
+                    "Delayed receipt #{} of queue {:?} should be in the state",
+                    index, queue
                 ))
             })
         })
@@ -119,27 +122,33 @@ pub(crate) fn check_balance(
     let initial_state = final_state.trie().as_ref();
 
     // Delayed receipts
-    let initial_delayed_receipt_indices: DelayedReceiptIndices =
-        get(initial_state, &TrieKey::DelayedReceiptIndices)?.unwrap_or_default();
-    let final_delayed_receipt_indices: DelayedReceiptIndices =
-        get(final_state, &TrieKey::DelayedReceiptIndices)?.unwrap_or_default();
-
-    // Previously delayed receipts that were processed this time.
-    let processed_delayed_receipts = get_delayed_receipts(
-        initial_state,
-        initial_delayed_receipt_indices.first_index..final_delayed_receipt_indices.first_index,
-    )?;
-    // Receipts that were not processed this time and are delayed now.
-    let new_delayed_receipts = get_delayed_receipts(
-        final_state,
-        initial_delayed_receipt_indices.next_available_index
-            ..final_delayed_receipt_indices.next_available_index,
-    )?;
+    let queues = DelayedReceiptQueue::enabled(current_protocol_version);
+    let initial_delayed_receipts = DelayedReceiptQueues::load(initial_state, queues.clone())?;
+    let final_delayed_receipts = DelayedReceiptQueues::load(final_state, queues)?;
+
+    let mut processed_delayed_receipts = vec![];
+    let mut new_delayed_receipts = vec![];
+    for ((queue, initial_indices), (_, final_indices)) in
+        initial_delayed_receipts.indices().zip(final_delayed_receipts.indices())
+    {
+        // Previously delayed receipts that were processed this time.
+        processed_delayed_receipts.extend(get_delayed_receipts(
+            initial_state,
+            queue,
+            initial_indices.first_index..final_indices.first_index,
+        )?);
+        // Receipts that were not processed this time and are delayed now.
+        new_delayed_receipts.extend(get_delayed_receipts(
+            final_state,
+            queue,
+            initial_indices.next_available_index..final_indices.next_available_index,
+        )?);
+    }
 
     // Accounts
     let mut all_accounts_ids: HashSet<AccountId> = transactions
         .iter()
-        .map(|tx| tx.transaction.signer_id.clone())
+        .map(|tx| tx.transaction.signer_id().clone())
         .chain(incoming_receipts.iter().map(|r| r.receiver_id.clone()))
         .chain(processed_delayed_receipts.iter().map(|r| r.receiver_id.clone()))
         .collect();
@@ -418,16 +427,17 @@ mod tests {
             CryptoHash::default(),
         );
         let receipt = Receipt {
-            predecessor_id: tx.transaction.signer_id.clone(),
-            receiver_id: tx.transaction.receiver_id.clone(),
+            predecessor_id: tx.transaction.signer_id().clone(),
+            receiver_id: tx.transaction.receiver_id().clone(),
             receipt_id: Default::default(),
             receipt: ReceiptEnum::Action(ActionReceipt {
-                signer_id: tx.transaction.signer_id.clone(),
-                signer_public_key: tx.transaction.public_key.clone(),
+                signer_id: tx.transaction.signer_id().clone(),
+                signer_public_key: tx.transaction.public_key().clone(),
                 gas_price,
                 output_data_receivers: vec![],
                 input_data_ids: vec![],
                 actions: vec![Action::Transfer(TransferAction { deposit })],
+                priority: 0,
             }),
         };
 
@@ -443,6 +453,7 @@ mod tests {
                 gas_deficit_amount: 0,
                 other_burnt_amount: 0,
                 slashed_burnt_amount: 0,
+                peak_memory_usage: Default::default(),
             },
             PROTOCOL_VERSION,
         )
@@ -473,16 +484,17 @@ mod tests {
             SignedTransaction::send_money(0, alice_id, bob_id, &signer, 1, CryptoHash::default());
 
         let receipt = Receipt {
-            predecessor_id: tx.transaction.signer_id.clone(),
-            receiver_id: tx.transaction.receiver_id.clone(),
+            predecessor_id: tx.transaction.signer_id().clone(),
+            receiver_id: tx.transaction.receiver_id().clone(),
             receipt_id: Default::default(),
             receipt: ReceiptEnum::Action(ActionReceipt {
-                signer_id: tx.transaction.signer_id.clone(),
-                signer_public_key: tx.transaction.public_key.clone(),
+                signer_id: tx.transaction.signer_id().clone(),
+                signer_public_key: tx.transaction.public_key().clone(),
                 gas_price,
                 output_data_receivers: vec![],
                 input_data_ids: vec![],
                 actions: vec![Action::Transfer(TransferAction { deposit })],
+                priority: 0,
             }),
         };
 
@@ -4,7 +4,7 @@ use num_bigint::BigUint;
 use num_traits::cast::ToPrimitive;
 use num_traits::pow::Pow;
 
-use near_primitives::account::AccessKeyPermission;
+use near_primitives::account::{AccessKeyPermission, FunctionCallPermission};
 use near_primitives::errors::IntegerOverflowError;
 // Just re-exporting RuntimeConfig for backwards compatibility.
 pub use near_primitives::num_rational::Rational;
@@ -13,7 +13,7 @@ use near_primitives::runtime::fees::{transfer_exec_fee, transfer_send_fee, Runti
 use near_primitives::transaction::{
     Action, AddKeyAction, DeployContractAction, FunctionCallAction, Transaction,
 };
-use near_primitives::types::{AccountId, Balance, Gas};
+use near_primitives::types::{AccountId, Balance, Gas, NearBalance, NearGas};
 use near_primitives::version::{is_implicit_account_creation_enabled, ProtocolVersion};
 
 /// Describes the cost of converting this transaction into a receipt.
@@ -29,7 +29,8 @@ pub struct TransactionCost {
     pub receipt_gas_price: Balance,
     /// Total costs in tokens for this transaction (including all deposits).
     pub total_cost: Balance,
-    /// The amount of tokens burnt by converting this transaction to a receipt.
+    /// The amount of tokens burnt by converting this transaction to a receipt,
+    /// including the priority fee.
     pub burnt_amount: Balance,
 }
 
@@ -47,15 +48,24 @@ pub fn safe_gas_price_inflated(
 }
 
 pub fn safe_gas_to_balance(gas_price: Balance, gas: Gas) -> Result<Balance, IntegerOverflowError> {
-    gas_price.checked_mul(Balance::from(gas)).ok_or_else(|| IntegerOverflowError {})
+    NearGas::new(gas)
+        .checked_cost(NearBalance::new(gas_price))
+        .map(NearBalance::get)
+        .ok_or_else(|| IntegerOverflowError {})
 }
 
 pub fn safe_add_gas(a: Gas, b: Gas) -> Result<Gas, IntegerOverflowError> {
-    a.checked_add(b).ok_or_else(|| IntegerOverflowError {})
+    NearGas::new(a)
+        .checked_add(NearGas::new(b))
+        .map(NearGas::get)
+        .ok_or_else(|| IntegerOverflowError {})
 }
 
 pub fn safe_add_balance(a: Balance, b: Balance) -> Result<Balance, IntegerOverflowError> {
-    a.checked_add(b).ok_or_else(|| IntegerOverflowError {})
+    NearBalance::new(a)
+        .checked_add(NearBalance::new(b))
+        .map(NearBalance::get)
+        .ok_or_else(|| IntegerOverflowError {})
 }
 
 #[macro_export]
@@ -66,6 +76,22 @@ macro_rules! safe_add_balance_apply {
     }
 }
 
+/// Number of bytes of a function call access key which are charged per byte
+/// when the key is added.
+///
+/// Method names are counted with their null-terminating character.  Every
+/// method allowance also stores the method name and the allowance itself.
+fn function_call_permission_num_bytes(permission: &FunctionCallPermission) -> u64 {
+    let method_names =
+        permission.method_names.iter().map(|name| name.as_bytes().len() as u64 + 1).sum::<u64>();
+    let method_allowances = permission
+        .method_allowances
+        .keys()
+        .map(|name| name.as_bytes().len() as u64 + 1 + std::mem::size_of::<Balance>() as u64)
+        .sum::<u64>();
+    method_names + method_allowances
+}
+
 /// Total sum of gas that needs to be burnt to send these actions.
 pub fn total_send_fees(
     config: &RuntimeFeesConfig,
@@ -101,12 +127,7 @@ pub fn total_send_fees(
             Stake(_) => cfg.stake_cost.send_fee(sender_is_receiver),
             AddKey(AddKeyAction { access_key, .. }) => match &access_key.permission {
                 AccessKeyPermission::FunctionCall(call_perm) => {
-                    let num_bytes = call_perm
-                        .method_names
-                        .iter()
-                        // Account for null-terminating characters.
-                        .map(|name| name.as_bytes().len() as u64 + 1)
-                        .sum::<u64>();
+                    let num_bytes = function_call_permission_num_bytes(call_perm);
                     cfg.add_key_cost.function_call_cost.send_fee(sender_is_receiver)
                         + num_bytes
                             * cfg
@@ -120,6 +141,17 @@ pub fn total_send_fees(
             },
             DeleteKey(_) => cfg.delete_key_cost.send_fee(sender_is_receiver),
             DeleteAccount(_) => cfg.delete_account_cost.send_fee(sender_is_receiver),
+            #[cfg(feature = "protocol_feature_create_account_with_contract")]
+            CreateAccountWithContract(action) => {
+                let num_bytes = action.code.len() as u64;
+                let num_entries = action.state.len() as u64;
+                cfg.create_account_cost.send_fee(sender_is_receiver)
+                    + cfg.deploy_contract_cost.send_fee(sender_is_receiver)
+                    + cfg.deploy_contract_cost_per_byte.send_fee(sender_is_receiver) * num_bytes
+                    + cfg.state_init_cost.send_fee(sender_is_receiver) * num_entries
+                    + cfg.state_init_cost_per_byte.send_fee(sender_is_receiver)
+                        * action.state_num_bytes()
+            }
         };
         result = safe_add_gas(result, delta)?;
     }
@@ -157,12 +189,7 @@ pub fn exec_fee(
         Stake(_) => cfg.stake_cost.exec_fee(),
         AddKey(AddKeyAction { access_key, .. }) => match &access_key.permission {
             AccessKeyPermission::FunctionCall(call_perm) => {
-                let num_bytes = call_perm
-                    .method_names
-                    .iter()
-                    // Account for null-terminating characters.
-                    .map(|name| name.as_bytes().len() as u64 + 1)
-                    .sum::<u64>();
+                let num_bytes = function_call_permission_num_bytes(call_perm);
                 cfg.add_key_cost.function_call_cost.exec_fee()
                     + num_bytes * cfg.add_key_cost.function_call_cost_per_byte.exec_fee()
             }
@@ -170,6 +197,16 @@ pub fn exec_fee(
         },
         DeleteKey(_) => cfg.delete_key_cost.exec_fee(),
         DeleteAccount(_) => cfg.delete_account_cost.exec_fee(),
+        #[cfg(feature = "protocol_feature_create_account_with_contract")]
+        CreateAccountWithContract(action) => {
+            let num_bytes = action.code.len() as u64;
+            let num_entries = action.state.len() as u64;
+            cfg.create_account_cost.exec_fee()
+                + cfg.deploy_contract_cost.exec_fee()
+                + cfg.deploy_contract_cost_per_byte.exec_fee() * num_bytes
+                + cfg.state_init_cost.exec_fee() * num_entries
+                + cfg.state_init_cost_per_byte.exec_fee() * action.state_num_bytes()
+        }
     }
 }
 
@@ -187,30 +224,18 @@ pub fn tx_cost(
         total_send_fees(
             config,
             sender_is_receiver,
-            &transaction.actions,
-            &transaction.receiver_id,
+            transaction.actions(),
+            transaction.receiver_id(),
             current_protocol_version,
         )?,
     )?;
-    let prepaid_gas = total_prepaid_gas(&transaction.actions)?;
-    // If signer is equals to receiver the receipt will be processed at the same block as this
-    // transaction. Otherwise it will processed in the next block and the gas might be inflated.
-    let initial_receipt_hop = if transaction.signer_id == transaction.receiver_id { 0 } else { 1 };
-    let minimum_new_receipt_gas = config.min_receipt_with_function_call_gas();
-    // In case the config is free, we don't care about the maximum depth.
-    let receipt_gas_price = if gas_price == 0 {
-        0
-    } else {
-        let maximum_depth =
-            if minimum_new_receipt_gas > 0 { prepaid_gas / minimum_new_receipt_gas } else { 0 };
-        let inflation_exponent = u8::try_from(initial_receipt_hop + maximum_depth)
-            .map_err(|_| IntegerOverflowError {})?;
-        safe_gas_price_inflated(
-            gas_price,
-            config.pessimistic_gas_price_inflation_ratio,
-            inflation_exponent,
-        )?
-    };
+    let prepaid_gas = total_prepaid_gas(transaction.actions())?;
+    let receipt_gas_price = pessimistic_gas_price(
+        config,
+        gas_price,
+        prepaid_gas,
+        transaction.signer_id() == transaction.receiver_id(),
+    )?;
 
     let mut gas_remaining =
         safe_add_gas(prepaid_gas, config.action_receipt_creation_config.exec_fee())?;
@@ -218,18 +243,52 @@ pub fn tx_cost(
         gas_remaining,
         total_prepaid_exec_fees(
             config,
-            &transaction.actions,
-            &transaction.receiver_id,
+            transaction.actions(),
+            transaction.receiver_id(),
             current_protocol_version,
         )?,
     )?;
-    let burnt_amount = safe_gas_to_balance(gas_price, gas_burnt)?;
+    let mut burnt_amount = safe_gas_to_balance(gas_price, gas_burnt)?;
+    if let Some(priority_fee) = transaction.priority_fee() {
+        burnt_amount = safe_add_balance(burnt_amount, priority_fee)?;
+    }
     let remaining_gas_amount = safe_gas_to_balance(receipt_gas_price, gas_remaining)?;
     let mut total_cost = safe_add_balance(burnt_amount, remaining_gas_amount)?;
-    total_cost = safe_add_balance(total_cost, total_deposit(&transaction.actions)?)?;
+    total_cost = safe_add_balance(total_cost, total_deposit(transaction.actions())?)?;
     Ok(TransactionCost { gas_burnt, gas_remaining, receipt_gas_price, total_cost, burnt_amount })
 }
 
+/// Computes the gas price at which the prepaid gas of a transaction is purchased.
+///
+/// The receipts spawned by a transaction may be executed many blocks later, when the gas price
+/// has increased. The price is therefore inflated by `pessimistic_gas_price_inflation_ratio` for
+/// each block the receipts could possibly take, which is bounded by the prepaid gas. Wallets can
+/// use this to compute the exact balance required to submit a transaction.
+pub fn pessimistic_gas_price(
+    config: &RuntimeFeesConfig,
+    gas_price: Balance,
+    prepaid_gas: Gas,
+    signer_is_receiver: bool,
+) -> Result<Balance, IntegerOverflowError> {
+    // In case the config is free, we don't care about the maximum depth.
+    if gas_price == 0 {
+        return Ok(0);
+    }
+    // If signer is equals to receiver the receipt will be processed at the same block as this
+    // transaction. Otherwise it will processed in the next block and the gas might be inflated.
+    let initial_receipt_hop = if signer_is_receiver { 0 } else { 1 };
+    let minimum_new_receipt_gas = config.min_receipt_with_function_call_gas();
+    let maximum_depth =
+        if minimum_new_receipt_gas > 0 { prepaid_gas / minimum_new_receipt_gas } else { 0 };
+    let inflation_exponent =
+        u8::try_from(initial_receipt_hop + maximum_depth).map_err(|_| IntegerOverflowError {})?;
+    safe_gas_price_inflated(
+        gas_price,
+        config.pessimistic_gas_price_inflation_ratio,
+        inflation_exponent,
+    )
+}
+
 /// Total sum of gas that would need to be burnt before we start executing the given actions.
 pub fn total_prepaid_exec_fees(
     config: &RuntimeFeesConfig,
@@ -270,4 +329,24 @@ mod tests {
         assert_eq!(safe_gas_price_inflated(10000, Rational::new(101, 100), 3).unwrap(), 10304);
         assert_eq!(safe_gas_price_inflated(10000, Rational::new(101, 100), 32).unwrap(), 13750);
     }
+
+    #[test]
+    fn test_pessimistic_gas_price() {
+        let config = RuntimeFeesConfig::test();
+        let min_gas = config.min_receipt_with_function_call_gas();
+        let inflation = config.pessimistic_gas_price_inflation_ratio;
+        // Local receipt without enough gas for another hop is not inflated.
+        assert_eq!(pessimistic_gas_price(&config, 10000, 0, true).unwrap(), 10000);
+        assert_eq!(
+            pessimistic_gas_price(&config, 10000, 0, false).unwrap(),
+            safe_gas_price_inflated(10000, inflation, 1).unwrap()
+        );
+        assert_eq!(
+            pessimistic_gas_price(&config, 10000, 3 * min_gas, false).unwrap(),
+            safe_gas_price_inflated(10000, inflation, 4).unwrap()
+        );
+        assert_eq!(pessimistic_gas_price(&config, 0, 3 * min_gas, false).unwrap(), 0);
+        // Too many hops to represent the exponent.
+        assert!(pessimistic_gas_price(&config, 10000, 300 * min_gas, false).is_err());
+    }
 }
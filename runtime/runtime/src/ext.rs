@@ -164,6 +164,42 @@ impl<'a> External for RuntimeExt<'a> {
         Ok(())
     }
 
+    fn storage_iter_prefix(
+        &self,
+        prefix: &[u8],
+        start: &[u8],
+        visit: &mut dyn FnMut(&[u8], &dyn ValuePtr) -> ExtResult<bool>,
+    ) -> ExtResult<()> {
+        let raw_prefix =
+            trie_key_parsers::get_raw_prefix_for_contract_data(self.account_id, prefix);
+        let raw_start = trie_key_parsers::get_raw_prefix_for_contract_data(self.account_id, start);
+        for raw_key in
+            self.trie_update.iter_from(&raw_prefix, &raw_start).map_err(wrap_storage_error)?
+        {
+            let raw_key = raw_key.map_err(wrap_storage_error)?;
+            let key =
+                trie_key_parsers::parse_data_key_from_contract_data_key(&raw_key, self.account_id)
+                    .map_err(|_e| {
+                        wrap_storage_error(StorageError::StorageInconsistentState(
+                            "Can't parse data key from raw key for ContractData".to_string(),
+                        ))
+                    })?;
+            let value = self
+                .trie_update
+                .get_ref(&self.create_storage_key(key), KeyLookupMode::Trie)
+                .map_err(wrap_storage_error)?
+                .ok_or_else(|| {
+                    wrap_storage_error(StorageError::StorageInconsistentState(
+                        "Iterated key has no value".to_string(),
+                    ))
+                })?;
+            if !visit(key, &RuntimeExtValuePtr(value))? {
+                break;
+            }
+        }
+        Ok(())
+    }
+
     fn generate_data_id(&mut self) -> CryptoHash {
         let data_id = create_data_id(
             self.current_protocol_version,
@@ -192,3 +228,60 @@ impl<'a> External for RuntimeExt<'a> {
             .map_err(|e| ExternalError::ValidatorError(e).into())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use near_primitives::shard_layout::ShardUId;
+    use near_primitives::test_utils::MockEpochInfoProvider;
+    use near_primitives::types::StateChangeCause;
+    use near_primitives::version::PROTOCOL_VERSION;
+    use near_store::test_utils::create_tries;
+    use near_store::Trie;
+
+    /// A contract can pass a `start` key that sorts past all keys with the
+    /// given prefix.  This must yield nothing rather than panic.
+    #[test]
+    fn test_storage_iter_prefix_start_past_prefix() {
+        let tries = create_tries();
+        let mut trie_update = tries.new_trie_update(ShardUId::single_shard(), Trie::EMPTY_ROOT);
+        let account_id: AccountId = "alice.near".parse().unwrap();
+        let hash = CryptoHash::default();
+        let epoch_id = EpochId::default();
+        let epoch_info_provider = MockEpochInfoProvider::default();
+        let mut runtime_ext = RuntimeExt::new(
+            &mut trie_update,
+            &account_id,
+            &hash,
+            &epoch_id,
+            &hash,
+            &hash,
+            &epoch_info_provider,
+            PROTOCOL_VERSION,
+        );
+        for key in [&b"a1"[..], b"b1", b"c1"] {
+            runtime_ext.storage_set(key, b"value").unwrap();
+        }
+        runtime_ext
+            .trie_update
+            .commit(StateChangeCause::TransactionProcessing { tx_hash: CryptoHash::default() });
+        for key in [&b"a2"[..], b"b2", b"c2"] {
+            runtime_ext.storage_set(key, b"value").unwrap();
+        }
+
+        let iter_prefix = |prefix: &[u8], start: &[u8]| -> Vec<Vec<u8>> {
+            let mut keys = vec![];
+            runtime_ext
+                .storage_iter_prefix(prefix, start, &mut |key, _value| {
+                    keys.push(key.to_vec());
+                    Ok(true)
+                })
+                .unwrap();
+            keys
+        };
+        assert_eq!(iter_prefix(b"a", b"a2"), vec![b"a2".to_vec()]);
+        assert_eq!(iter_prefix(b"a", b"b"), Vec::<Vec<u8>>::new());
+        assert_eq!(iter_prefix(b"a", b"c"), Vec::<Vec<u8>>::new());
+        assert_eq!(iter_prefix(b"b", b"c2"), Vec::<Vec<u8>>::new());
+    }
+}
@@ -9,7 +9,7 @@ use near_primitives::shard_layout::ShardUId;
 use near_primitives::{
     account::{AccessKey, Account},
     contract::ContractCode,
-    receipt::{DelayedReceiptIndices, Receipt, ReceiptEnum, ReceivedData},
+    receipt::{DelayedReceiptQueue, Receipt, ReceiptEnum, ReceivedData},
     state_record::{state_record_to_account_id, StateRecord},
     trie_key::TrieKey,
     types::{AccountId, Balance, MerkleHash, ShardId, StateChangeCause, StateRoot},
@@ -18,11 +18,10 @@ use near_primitives::{
 use near_store::flat_state::FlatStateDelta;
 use near_store::{
     get_account, get_received_data, set, set_access_key, set_account, set_code,
-    set_postponed_receipt, set_received_data, ShardTries, TrieUpdate,
+    set_postponed_receipt, set_received_data, DelayedReceiptQueues, ShardTries, TrieUpdate,
 };
 
 use crate::config::RuntimeConfig;
-use crate::Runtime;
 /// Computes the expected storage per account for a given stream of StateRecord(s).
 /// For example: the storage for Contract depends on its length, we don't charge storage for receipts
 /// and we compute a fixed (config-configured) number of bytes for each account (to store account id).
@@ -106,7 +105,7 @@ impl GenesisStateApplier {
 
     fn apply_batch(
         current_state_root: &mut StateRoot,
-        delayed_receipts_indices: &mut DelayedReceiptIndices,
+        delayed_receipts: &mut DelayedReceiptQueues,
         tries: &mut ShardTries,
         shard_uid: ShardUId,
         validators: &[(AccountId, PublicKey, Balance)],
@@ -156,12 +155,7 @@ impl GenesisStateApplier {
                     );
                 }
                 StateRecord::DelayedReceipt(receipt) => {
-                    Runtime::delay_receipt(
-                        &mut state_update,
-                        delayed_receipts_indices,
-                        &*receipt,
-                    )
-                        .unwrap();
+                    delayed_receipts.push(&mut state_update, &receipt).unwrap();
                 }
             }
         });
@@ -229,15 +223,15 @@ impl GenesisStateApplier {
     }
 
     fn apply_delayed_receipts(
-        delayed_receipts_indices: DelayedReceiptIndices,
+        delayed_receipts: DelayedReceiptQueues,
         current_state_root: &mut StateRoot,
         tries: &mut ShardTries,
         shard_uid: ShardUId,
     ) {
         let mut state_update = tries.new_trie_update(shard_uid, *current_state_root);
 
-        if delayed_receipts_indices != DelayedReceiptIndices::default() {
-            set(&mut state_update, TrieKey::DelayedReceiptIndices, &delayed_receipts_indices);
+        if delayed_receipts.is_modified() {
+            delayed_receipts.save(&mut state_update);
             Self::commit(state_update, current_state_root, tries, shard_uid);
         }
     }
@@ -251,15 +245,19 @@ impl GenesisStateApplier {
         shard_account_ids: HashSet<AccountId>,
     ) -> StateRoot {
         let mut current_state_root = MerkleHash::default();
-        let mut delayed_receipts_indices = DelayedReceiptIndices::default();
         let shard_uid =
             ShardUId { version: genesis.config.shard_layout.version(), shard_id: shard_id as u32 };
+        let mut delayed_receipts = DelayedReceiptQueues::load(
+            &tries.new_trie_update(shard_uid, current_state_root),
+            DelayedReceiptQueue::enabled(genesis.config.protocol_version),
+        )
+        .expect("Genesis storage error");
         for batch_account_ids in
             shard_account_ids.into_iter().collect::<Vec<AccountId>>().chunks(300_000)
         {
             Self::apply_batch(
                 &mut current_state_root,
-                &mut delayed_receipts_indices,
+                &mut delayed_receipts,
                 &mut tries,
                 shard_uid,
                 validators,
@@ -269,7 +267,7 @@ impl GenesisStateApplier {
             );
         }
         Self::apply_delayed_receipts(
-            delayed_receipts_indices,
+            delayed_receipts,
             &mut current_state_root,
             &mut tries,
             shard_uid,
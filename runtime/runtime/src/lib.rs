@@ -16,7 +16,7 @@ pub use near_primitives::runtime::apply_state::ApplyState;
 use near_primitives::runtime::fees::RuntimeFeesConfig;
 use near_primitives::runtime::get_insufficient_storage_stake;
 use near_primitives::runtime::migration_data::{MigrationData, MigrationFlags};
-use near_primitives::transaction::ExecutionMetadata;
+use near_primitives::transaction::{Event, ExecutionMetadata, GasRefund};
 use near_primitives::version::{
     is_implicit_account_creation_enabled, ProtocolFeature, ProtocolVersion,
 };
@@ -26,7 +26,7 @@ use near_primitives::{
     errors::{ActionError, ActionErrorKind, RuntimeError, TxExecutionError},
     hash::CryptoHash,
     receipt::{
-        ActionReceipt, DataReceipt, DelayedReceiptIndices, Receipt, ReceiptEnum, ReceivedData,
+        ActionReceipt, DataReceipt, DelayedReceiptQueue, Receipt, ReceiptEnum, ReceivedData,
     },
     state_record::StateRecord,
     transaction::{
@@ -44,12 +44,12 @@ use near_primitives::{
 };
 use near_store::{
     get, get_account, get_postponed_receipt, get_received_data, remove_postponed_receipt, set,
-    set_account, set_postponed_receipt, set_received_data, PartialStorage, ShardTries,
-    StorageError, Trie, TrieChanges, TrieUpdate,
+    set_account, set_postponed_receipt, set_received_data, DelayedReceiptQueues, PartialStorage,
+    ShardTries, StorageError, Trie, TrieChanges, TrieUpdate,
 };
 use near_store::{set_access_key, set_code};
 use near_vm_logic::types::PromiseResult;
-use near_vm_logic::ReturnData;
+use near_vm_logic::{MemoryUsage, ReturnData};
 pub use near_vm_runner::with_ext_cost_counter;
 
 use crate::actions::*;
@@ -58,7 +58,8 @@ use crate::config::{
     exec_fee, safe_add_balance, safe_add_gas, safe_gas_to_balance, total_deposit,
     total_prepaid_exec_fees, total_prepaid_gas, RuntimeConfig,
 };
-use crate::genesis::{GenesisStateApplier, StorageComputer};
+use crate::genesis::GenesisStateApplier;
+pub use crate::genesis::StorageComputer;
 use crate::prefetch::TriePrefetcher;
 use crate::verifier::validate_receipt;
 pub use crate::verifier::{validate_transaction, verify_and_charge_transaction};
@@ -111,6 +112,8 @@ pub struct ApplyStats {
     /// This is a negative amount. This amount was not charged from the account that issued
     /// the transaction. It's likely due to the delayed queue of the receipts.
     pub gas_deficit_amount: Balance,
+    /// Peak memory used by a single function call.
+    pub peak_memory_usage: MemoryUsage,
 }
 
 pub struct ApplyResult {
@@ -132,9 +135,14 @@ pub struct ActionResult {
     pub gas_used: Gas,
     pub result: Result<ReturnData, ActionError>,
     pub logs: Vec<LogEntry>,
+    /// Events emitted by function calls, see `VMLogic::emit_event`.
+    pub events: Vec<Event>,
     pub new_receipts: Vec<Receipt>,
     pub validator_proposals: Vec<ValidatorStake>,
     pub profile: ProfileData,
+    pub memory_usage: MemoryUsage,
+    /// Code of the function call error, if a function call failed.
+    pub error_code: Option<u32>,
 }
 
 impl ActionResult {
@@ -153,8 +161,11 @@ impl ActionResult {
         )?;
         self.gas_used = safe_add_gas(self.gas_used, next_result.gas_used)?;
         self.profile.merge(&next_result.profile);
+        self.memory_usage.merge_max(next_result.memory_usage);
         self.result = next_result.result;
+        self.error_code = next_result.error_code;
         self.logs.append(&mut next_result.logs);
+        self.events.append(&mut next_result.events);
         if let Ok(ReturnData::ReceiptIndex(ref mut receipt_index)) = self.result {
             // Shifting local receipt index to be global receipt index.
             *receipt_index += self.new_receipts.len() as u64;
@@ -178,9 +189,12 @@ impl Default for ActionResult {
             gas_used: 0,
             result: Ok(ReturnData::None),
             logs: vec![],
+            events: vec![],
             new_receipts: vec![],
             validator_proposals: vec![],
             profile: Default::default(),
+            memory_usage: Default::default(),
+            error_code: None,
         }
     }
 }
@@ -247,16 +261,20 @@ impl Runtime {
                     &apply_state.block_hash,
                 );
                 let receipt = Receipt {
-                    predecessor_id: transaction.signer_id.clone(),
-                    receiver_id: transaction.receiver_id.clone(),
+                    predecessor_id: transaction.signer_id().clone(),
+                    receiver_id: transaction.receiver_id().clone(),
                     receipt_id,
                     receipt: ReceiptEnum::Action(ActionReceipt {
-                        signer_id: transaction.signer_id.clone(),
-                        signer_public_key: transaction.public_key.clone(),
+                        signer_id: transaction.signer_id().clone(),
+                        signer_public_key: transaction.public_key().clone(),
                         gas_price: verification_result.receipt_gas_price,
                         output_data_receivers: vec![],
                         input_data_ids: vec![],
-                        actions: transaction.actions.clone(),
+                        actions: transaction.actions().to_vec(),
+                        priority: ActionReceipt::priority_from_fee(
+                            transaction.priority_fee(),
+                            apply_state.current_protocol_version,
+                        ),
                     }),
                 };
                 stats.tx_burnt_amount =
@@ -269,7 +287,7 @@ impl Runtime {
                         receipt_ids: vec![receipt.receipt_id],
                         gas_burnt: verification_result.gas_burnt,
                         tokens_burnt: verification_result.burnt_amount,
-                        executor_id: transaction.signer_id.clone(),
+                        executor_id: transaction.signer_id().clone(),
                         // TODO: profile data is only counted in apply_action, which only happened at process_receipt
                         // VerificationResult needs updates to incorporate profile data to support profile data of txns
                         metadata: ExecutionMetadata::V1,
@@ -351,6 +369,7 @@ impl Runtime {
                     deploy_contract,
                     apply_state,
                     apply_state.current_protocol_version,
+                    &mut result,
                 )?;
             }
             Action::FunctionCall(function_call) => {
@@ -443,6 +462,19 @@ impl Runtime {
                     apply_state.current_protocol_version,
                 )?;
             }
+            #[cfg(feature = "protocol_feature_create_account_with_contract")]
+            Action::CreateAccountWithContract(create_account_with_contract) => {
+                action_create_account_with_contract(
+                    state_update,
+                    apply_state,
+                    account,
+                    actor_id,
+                    &receipt.receiver_id,
+                    &receipt.predecessor_id,
+                    &mut result,
+                    create_account_with_contract,
+                )?;
+            }
         };
         Ok(result)
     }
@@ -463,6 +495,7 @@ impl Runtime {
             _ => unreachable!("given receipt should be an action receipt"),
         };
         let account_id = &receipt.receiver_id;
+        let trie_nodes_before = state_update.trie().get_trie_nodes_count();
         // Collecting input data and removing it from the state
         let promise_results = action_receipt
             .input_data_ids
@@ -558,7 +591,7 @@ impl Runtime {
             }
         }
 
-        let gas_deficit_amount = if AccountId::is_system(&receipt.predecessor_id) {
+        let (gas_deficit_amount, gas_refund) = if AccountId::is_system(&receipt.predecessor_id) {
             // We will set gas_burnt for refund receipts to be 0 when we calculate tx_burnt_amount
             // Here we don't set result.gas_burnt to be zero if CountRefundReceiptsInGasLimit is
             // enabled because we want it to be counted in gas limit calculation later
@@ -578,7 +611,7 @@ impl Runtime {
                     total_deposit(&action_receipt.actions)?,
                 )?
             }
-            0
+            (0, GasRefund::default())
         } else {
             // Calculating and generating refunds
             self.generate_refund_receipts(
@@ -591,6 +624,7 @@ impl Runtime {
             )?
         };
         stats.gas_deficit_amount = safe_add_balance(stats.gas_deficit_amount, gas_deficit_amount)?;
+        stats.peak_memory_usage.merge_max(result.memory_usage);
 
         // Moving validator proposals
         validator_proposals.append(&mut result.validator_proposals);
@@ -724,6 +758,61 @@ impl Runtime {
 
         Self::print_log(&result.logs);
 
+        let trie_nodes_count = state_update.trie().get_trie_nodes_count() - trie_nodes_before;
+        let metadata = if checked_feature!(
+            "protocol_feature_outcome_metadata_commitment",
+            OutcomeMetadataCommitment,
+            apply_state.current_protocol_version
+        ) {
+            ExecutionMetadata::V7 {
+                profile: result.profile,
+                trie_nodes_count,
+                error_code: result.error_code,
+                gas_refund,
+                events: result.events,
+            }
+        } else if checked_feature!(
+            "protocol_feature_structured_events",
+            StructuredEvents,
+            apply_state.current_protocol_version
+        ) {
+            ExecutionMetadata::V6 {
+                profile: result.profile,
+                trie_nodes_count,
+                error_code: result.error_code,
+                gas_refund,
+                events: result.events,
+            }
+        } else if checked_feature!(
+            "protocol_feature_gas_refund_metadata",
+            GasRefundMetadata,
+            apply_state.current_protocol_version
+        ) {
+            ExecutionMetadata::V5 {
+                profile: result.profile,
+                trie_nodes_count,
+                error_code: result.error_code,
+                gas_refund,
+            }
+        } else if checked_feature!(
+            "protocol_feature_function_call_error_code",
+            FunctionCallErrorCode,
+            apply_state.current_protocol_version
+        ) {
+            ExecutionMetadata::V4 {
+                profile: result.profile,
+                trie_nodes_count,
+                error_code: result.error_code,
+            }
+        } else if checked_feature!(
+            "protocol_feature_trie_nodes_count_metadata",
+            TrieNodesCountMetadata,
+            apply_state.current_protocol_version
+        ) {
+            ExecutionMetadata::V3 { profile: result.profile, trie_nodes_count }
+        } else {
+            ExecutionMetadata::V2(result.profile)
+        };
         Ok(ExecutionOutcomeWithId {
             id: receipt.receipt_id,
             outcome: ExecutionOutcome {
@@ -733,7 +822,7 @@ impl Runtime {
                 gas_burnt: result.gas_burnt,
                 tokens_burnt,
                 executor_id: account_id.clone(),
-                metadata: ExecutionMetadata::V2(result.profile),
+                metadata,
             },
         })
     }
@@ -746,7 +835,7 @@ impl Runtime {
         result: &mut ActionResult,
         current_protocol_version: ProtocolVersion,
         transaction_costs: &RuntimeFeesConfig,
-    ) -> Result<Balance, RuntimeError> {
+    ) -> Result<(Balance, GasRefund), RuntimeError> {
         let total_deposit = total_deposit(&action_receipt.actions)?;
         let prepaid_gas = total_prepaid_gas(&action_receipt.actions)?;
         let prepaid_exec_gas = safe_add_gas(
@@ -759,10 +848,11 @@ impl Runtime {
             transaction_costs.action_receipt_creation_config.exec_fee(),
         )?;
         let deposit_refund = if result.result.is_err() { total_deposit } else { 0 };
+        let attached_gas = safe_add_gas(prepaid_gas, prepaid_exec_gas)?;
         let gas_refund = if result.result.is_err() {
-            safe_add_gas(prepaid_gas, prepaid_exec_gas)? - result.gas_burnt
+            attached_gas - result.gas_burnt
         } else {
-            safe_add_gas(prepaid_gas, prepaid_exec_gas)? - result.gas_used
+            attached_gas - result.gas_used
         };
         // Refund for the unused portion of the gas at the price at which this gas was purchased.
         let mut gas_balance_refund = safe_gas_to_balance(action_receipt.gas_price, gas_refund)?;
@@ -807,7 +897,7 @@ impl Runtime {
                 action_receipt.signer_public_key.clone(),
             ));
         }
-        Ok(gas_deficit_amount)
+        Ok((gas_deficit_amount, GasRefund { attached: attached_gas, refunded: gas_refund }))
     }
 
     fn process_receipt(
@@ -1235,7 +1325,7 @@ impl Runtime {
                 signed_transaction,
                 &mut stats,
             )?;
-            if receipt.receiver_id == signed_transaction.transaction.signer_id {
+            if &receipt.receiver_id == signed_transaction.transaction.signer_id() {
                 local_receipts.push(receipt);
             } else {
                 outgoing_receipts.push(receipt);
@@ -1246,9 +1336,10 @@ impl Runtime {
             outcomes.push(outcome_with_id);
         }
 
-        let mut delayed_receipts_indices: DelayedReceiptIndices =
-            get(&state_update, &TrieKey::DelayedReceiptIndices)?.unwrap_or_default();
-        let initial_delayed_receipt_indices = delayed_receipts_indices.clone();
+        let mut delayed_receipts = DelayedReceiptQueues::load(
+            &state_update,
+            DelayedReceiptQueue::enabled(apply_state.current_protocol_version),
+        )?;
 
         let mut process_receipt = |receipt: &Receipt,
                                    state_update: &mut TrieUpdate,
@@ -1295,22 +1386,16 @@ impl Runtime {
                 // the `verify_and_charge_transaction`.
                 process_receipt(receipt, &mut state_update, &mut total_gas_burnt)?;
             } else {
-                Self::delay_receipt(&mut state_update, &mut delayed_receipts_indices, receipt)?;
+                delayed_receipts.push(&mut state_update, receipt)?;
             }
         }
 
         // Then we process the delayed receipts. It's a backlog of receipts from the past blocks.
-        while delayed_receipts_indices.first_index < delayed_receipts_indices.next_available_index {
-            if total_gas_burnt >= gas_limit {
-                break;
-            }
-            let key = TrieKey::DelayedReceipt { index: delayed_receipts_indices.first_index };
-            let receipt: Receipt = get(&state_update, &key)?.ok_or_else(|| {
-                StorageError::StorageInconsistentState(format!(
-                    "Delayed receipt #{} should be in the state",
-                    delayed_receipts_indices.first_index
-                ))
-            })?;
+        while total_gas_burnt < gas_limit {
+            let receipt = match delayed_receipts.pop(&mut state_update)? {
+                Some(receipt) => receipt,
+                None => break,
+            };
 
             if let Some(prefetcher) = &mut prefetcher {
                 prefetcher.clear();
@@ -1321,15 +1406,12 @@ impl Runtime {
             validate_receipt(&apply_state.config.wasm_config.limit_config, &receipt).map_err(
                 |e| {
                     StorageError::StorageInconsistentState(format!(
-                        "Delayed receipt #{} in the state is invalid: {}",
-                        delayed_receipts_indices.first_index, e
+                        "Delayed receipt {} in the state is invalid: {}",
+                        receipt.receipt_id, e
                     ))
                 },
             )?;
 
-            state_update.remove(key);
-            // Math checked above: first_index is less than next_available_index
-            delayed_receipts_indices.first_index += 1;
             process_receipt(&receipt, &mut state_update, &mut total_gas_burnt)?;
             processed_delayed_receipts.push(receipt);
         }
@@ -1347,7 +1429,7 @@ impl Runtime {
             if total_gas_burnt < gas_limit {
                 process_receipt(receipt, &mut state_update, &mut total_gas_burnt)?;
             } else {
-                Self::delay_receipt(&mut state_update, &mut delayed_receipts_indices, receipt)?;
+                delayed_receipts.push(&mut state_update, receipt)?;
             }
         }
 
@@ -1356,9 +1438,7 @@ impl Runtime {
             prefetcher.clear();
         }
 
-        if delayed_receipts_indices != initial_delayed_receipt_indices {
-            set(&mut state_update, TrieKey::DelayedReceiptIndices, &delayed_receipts_indices);
-        }
+        delayed_receipts.save(&mut state_update);
 
         check_balance(
             &apply_state.config.transaction_costs,
@@ -1402,27 +1482,6 @@ impl Runtime {
         })
     }
 
-    // Adds the given receipt into the end of the delayed receipt queue in the state.
-    pub fn delay_receipt(
-        state_update: &mut TrieUpdate,
-        delayed_receipts_indices: &mut DelayedReceiptIndices,
-        receipt: &Receipt,
-    ) -> Result<(), StorageError> {
-        set(
-            state_update,
-            TrieKey::DelayedReceipt { index: delayed_receipts_indices.next_available_index },
-            receipt,
-        );
-        delayed_receipts_indices.next_available_index =
-            delayed_receipts_indices.next_available_index.checked_add(1).ok_or_else(|| {
-                StorageError::StorageInconsistentState(
-                    "Next available index for delayed receipt exceeded the integer limit"
-                        .to_string(),
-                )
-            })?;
-        Ok(())
-    }
-
     fn apply_state_patch(&self, state_update: &mut TrieUpdate, state_patch: SandboxStatePatch) {
         if state_patch.is_empty() {
             return;
@@ -1534,6 +1593,7 @@ mod tests {
                 output_data_receivers: vec![],
                 input_data_ids: vec![],
                 actions,
+                priority: 0,
             }),
         }]
     }
@@ -1901,6 +1961,7 @@ mod tests {
                         actions: vec![Action::Transfer(TransferAction {
                             deposit: small_transfer + Balance::from(i),
                         })],
+                        priority: 0,
                     }),
                 }
             })
@@ -2191,6 +2252,146 @@ mod tests {
         assert_eq!(result.stats.gas_deficit_amount, result.stats.tx_burnt_amount * 9)
     }
 
+    #[test]
+    #[cfg(feature = "protocol_feature_trie_nodes_count_metadata")]
+    fn test_apply_trie_nodes_count() {
+        let initial_balance = to_yocto(1_000_000);
+        let initial_locked = to_yocto(500_000);
+        let gas_limit = 10u64.pow(15);
+        let (runtime, tries, root, apply_state, _, epoch_info_provider) =
+            setup_runtime(initial_balance, initial_locked, gas_limit);
+
+        let receipts = generate_receipts(to_yocto(10_000), 1);
+        let result = runtime
+            .apply(
+                tries.get_trie_for_shard(ShardUId::single_shard(), root),
+                &None,
+                &apply_state,
+                &receipts,
+                &[],
+                &epoch_info_provider,
+                Default::default(),
+            )
+            .unwrap();
+        match &result.outcomes[0].outcome.metadata {
+            ExecutionMetadata::V3 { trie_nodes_count, .. }
+            | ExecutionMetadata::V4 { trie_nodes_count, .. }
+            | ExecutionMetadata::V5 { trie_nodes_count, .. }
+            | ExecutionMetadata::V6 { trie_nodes_count, .. }
+            | ExecutionMetadata::V7 { trie_nodes_count, .. } => {
+                // At least the account of the receiver is read from the trie.
+                assert!(trie_nodes_count.db_reads + trie_nodes_count.mem_reads > 0);
+            }
+            metadata => panic!("unexpected metadata {:?}", metadata),
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "protocol_feature_function_call_error_code")]
+    fn test_apply_function_call_error_code() {
+        let initial_balance = to_yocto(1_000_000);
+        let initial_locked = to_yocto(500_000);
+        let gas_limit = 10u64.pow(15);
+        let (runtime, tries, root, apply_state, _, epoch_info_provider) =
+            setup_runtime(initial_balance, initial_locked, gas_limit);
+
+        // Alice has no contract deployed, so the call fails to compile.
+        let receipts = vec![Receipt {
+            predecessor_id: bob_account(),
+            receiver_id: alice_account(),
+            receipt_id: CryptoHash::default(),
+            receipt: ReceiptEnum::Action(ActionReceipt {
+                signer_id: bob_account(),
+                signer_public_key: PublicKey::empty(KeyType::ED25519),
+                gas_price: GAS_PRICE,
+                output_data_receivers: vec![],
+                input_data_ids: vec![],
+                actions: vec![Action::FunctionCall(FunctionCallAction {
+                    method_name: "hello".to_string(),
+                    args: vec![],
+                    gas: 10u64.pow(14),
+                    deposit: 0,
+                })],
+                priority: 0,
+            }),
+        }];
+        let result = runtime
+            .apply(
+                tries.get_trie_for_shard(ShardUId::single_shard(), root),
+                &None,
+                &apply_state,
+                &receipts,
+                &[],
+                &epoch_info_provider,
+                Default::default(),
+            )
+            .unwrap();
+        assert!(matches!(result.outcomes[0].outcome.status, ExecutionStatus::Failure(_)));
+        match &result.outcomes[0].outcome.metadata {
+            ExecutionMetadata::V4 { error_code, .. }
+            | ExecutionMetadata::V5 { error_code, .. }
+            | ExecutionMetadata::V6 { error_code, .. }
+            | ExecutionMetadata::V7 { error_code, .. } => {
+                assert_eq!(*error_code, Some(1000))
+            }
+            metadata => panic!("unexpected metadata {:?}", metadata),
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "protocol_feature_gas_refund_metadata")]
+    fn test_apply_gas_refund_metadata() {
+        let initial_balance = to_yocto(1_000_000);
+        let initial_locked = to_yocto(500_000);
+        let gas_limit = 10u64.pow(15);
+        let (runtime, tries, root, apply_state, _, epoch_info_provider) =
+            setup_runtime(initial_balance, initial_locked, gas_limit);
+
+        // The call fails, so all attached gas that is not burnt is refunded.
+        let prepaid_gas = 10u64.pow(14);
+        let receipts = vec![Receipt {
+            predecessor_id: bob_account(),
+            receiver_id: alice_account(),
+            receipt_id: CryptoHash::default(),
+            receipt: ReceiptEnum::Action(ActionReceipt {
+                signer_id: bob_account(),
+                signer_public_key: PublicKey::empty(KeyType::ED25519),
+                gas_price: GAS_PRICE,
+                output_data_receivers: vec![],
+                input_data_ids: vec![],
+                actions: vec![Action::FunctionCall(FunctionCallAction {
+                    method_name: "hello".to_string(),
+                    args: vec![],
+                    gas: prepaid_gas,
+                    deposit: 0,
+                })],
+                priority: 0,
+            }),
+        }];
+        let result = runtime
+            .apply(
+                tries.get_trie_for_shard(ShardUId::single_shard(), root),
+                &None,
+                &apply_state,
+                &receipts,
+                &[],
+                &epoch_info_provider,
+                Default::default(),
+            )
+            .unwrap();
+        let outcome = &result.outcomes[0].outcome;
+        match &outcome.metadata {
+            ExecutionMetadata::V5 { gas_refund, .. }
+            | ExecutionMetadata::V6 { gas_refund, .. }
+            | ExecutionMetadata::V7 { gas_refund, .. } => {
+                assert!(gas_refund.attached > prepaid_gas);
+                assert!(gas_refund.refunded > 0);
+                assert_eq!(gas_refund.attached, gas_refund.refunded + outcome.gas_burnt);
+            }
+            metadata => panic!("unexpected metadata {:?}", metadata),
+        }
+    }
+
     #[test]
     fn test_apply_deficit_gas_for_function_call_covered() {
         let initial_balance = to_yocto(1_000_000);
@@ -2230,6 +2431,7 @@ mod tests {
                 output_data_receivers: vec![],
                 input_data_ids: vec![],
                 actions,
+                priority: 0,
             }),
         }];
         let total_receipt_cost = Balance::from(gas + expected_gas_burnt) * gas_price;
@@ -2299,6 +2501,7 @@ mod tests {
                 output_data_receivers: vec![],
                 input_data_ids: vec![],
                 actions,
+                priority: 0,
             }),
         }];
         let total_receipt_cost = Balance::from(gas + expected_gas_burnt) * gas_price;
@@ -2451,4 +2654,130 @@ mod tests {
             .expect("Compiled contract should be cached")
             .expect("Compilation result should be non-empty");
     }
+
+    #[test]
+    #[cfg(feature = "protocol_feature_limit_contract_sections")]
+    fn test_deploy_contract_exceeding_section_limits() {
+        let (runtime, tries, root, mut apply_state, signer, epoch_info_provider) =
+            setup_runtime(to_yocto(1_000_000), 0, 10u64.pow(15));
+
+        let max_globals = apply_state.config.wasm_config.limit_config.max_globals_per_contract;
+        let wasm_code = near_test_contracts::LargeContract {
+            globals: max_globals.unwrap() as u32 + 1,
+            ..Default::default()
+        }
+        .make();
+        let actions = vec![Action::DeployContract(DeployContractAction { code: wasm_code })];
+        let receipts = create_receipts_with_actions(alice_account(), signer, actions);
+
+        let protocol_version = ProtocolFeature::LimitContractSections.protocol_version();
+        for (protocol_version, expect_rejected) in
+            [(protocol_version - 1, false), (protocol_version, true)]
+        {
+            apply_state.current_protocol_version = protocol_version;
+            let apply_result = runtime
+                .apply(
+                    tries.get_trie_for_shard(ShardUId::single_shard(), root),
+                    &None,
+                    &apply_state,
+                    &receipts,
+                    &[],
+                    &epoch_info_provider,
+                    Default::default(),
+                )
+                .unwrap();
+            let status = &apply_result.outcomes[0].outcome.status;
+            if expect_rejected {
+                assert_eq!(
+                    status,
+                    &ExecutionStatus::Failure(TxExecutionError::ActionError(ActionError {
+                        index: Some(0),
+                        kind: ActionErrorKind::ContractLimitExceeded {
+                            account_id: alice_account(),
+                            error: near_vm_errors::PrepareError::TooManyGlobals,
+                        },
+                    }))
+                );
+            } else {
+                assert_eq!(status, &ExecutionStatus::SuccessValue(vec![]));
+            }
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "protocol_feature_create_account_with_contract")]
+    fn test_create_account_with_contract() {
+        use near_primitives::transaction::{ContractStateEntry, CreateAccountWithContractAction};
+
+        let (runtime, tries, root, apply_state, _, epoch_info_provider) =
+            setup_runtime(to_yocto(1_000_000), 0, 10u64.pow(15));
+
+        let account_id: AccountId = "contract.alice.near".parse().unwrap();
+        let wasm_code = near_test_contracts::rs_contract().to_vec();
+        let state = vec![
+            ContractStateEntry { key: b"a".to_vec(), value: b"1".to_vec() },
+            ContractStateEntry { key: b"b".to_vec(), value: b"2".to_vec() },
+            // Overwrites the first entry.
+            ContractStateEntry { key: b"a".to_vec(), value: b"33".to_vec() },
+        ];
+        let receipts = vec![Receipt {
+            predecessor_id: alice_account(),
+            receiver_id: account_id.clone(),
+            receipt_id: CryptoHash::default(),
+            receipt: ReceiptEnum::Action(ActionReceipt {
+                signer_id: alice_account(),
+                signer_public_key: PublicKey::empty(KeyType::ED25519),
+                gas_price: GAS_PRICE,
+                output_data_receivers: vec![],
+                input_data_ids: vec![],
+                actions: vec![
+                    Action::CreateAccountWithContract(CreateAccountWithContractAction {
+                        code: wasm_code.clone(),
+                        state,
+                    }),
+                    Action::Transfer(TransferAction { deposit: to_yocto(100) }),
+                ],
+                priority: 0,
+            }),
+        }];
+        let apply_result = runtime
+            .apply(
+                tries.get_trie_for_shard(ShardUId::single_shard(), root),
+                &None,
+                &apply_state,
+                &receipts,
+                &[],
+                &epoch_info_provider,
+                Default::default(),
+            )
+            .unwrap();
+        assert_eq!(apply_result.outcomes[0].outcome.status, ExecutionStatus::SuccessValue(vec![]));
+        let mut store_update = tries.store_update();
+        let root = tries.apply_all(
+            &apply_result.trie_changes,
+            ShardUId::single_shard(),
+            &mut store_update,
+        );
+        store_update.commit().unwrap();
+
+        let state_update = tries.new_trie_update(ShardUId::single_shard(), root);
+        let account = get_account(&state_update, &account_id).unwrap().unwrap();
+        assert_eq!(account.code_hash(), hash(&wasm_code));
+        let storage_config = &apply_state.config.transaction_costs.storage_usage_config;
+        assert_eq!(
+            account.storage_usage(),
+            storage_config.num_bytes_account
+                + wasm_code.len() as u64
+                + 2 * storage_config.num_extra_bytes_record
+                + 3
+                + 2
+        );
+        let value = |key: &[u8]| {
+            state_update
+                .get(&TrieKey::ContractData { account_id: account_id.clone(), key: key.to_vec() })
+                .unwrap()
+        };
+        assert_eq!(value(b"a"), Some(b"33".to_vec()));
+        assert_eq!(value(b"b"), Some(b"2".to_vec()));
+    }
 }
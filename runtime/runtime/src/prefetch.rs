@@ -63,9 +63,14 @@ pub(crate) struct TriePrefetcher {
 }
 
 impl TriePrefetcher {
+    /// Returns `None` if prefetching is disabled or currently paused, see
+    /// `PrefetchApi::begin_chunk`.
     pub(crate) fn new_if_enabled(trie: Rc<Trie>) -> Option<Self> {
         if let Some(caching_storage) = trie.storage.as_caching_storage() {
             if let Some(prefetch_api) = caching_storage.prefetch_api().clone() {
+                if !prefetch_api.begin_chunk() {
+                    return None;
+                }
                 let trie_root = *trie.get_root();
                 let shard_uid = prefetch_api.shard_uid;
                 let metrics_labels: [&str; 1] = [&shard_uid.shard_id.to_string()];
@@ -125,13 +130,13 @@ impl TriePrefetcher {
     ) -> Result<(), ()> {
         if self.prefetch_api.enable_receipt_prefetching {
             for t in transactions {
-                let account_id = t.transaction.signer_id.clone();
+                let account_id = t.transaction.signer_id().clone();
                 let trie_key = TrieKey::Account { account_id };
                 self.prefetch_trie_key(trie_key)?;
 
                 let trie_key = TrieKey::AccessKey {
-                    account_id: t.transaction.signer_id.clone(),
-                    public_key: t.transaction.public_key.clone(),
+                    account_id: t.transaction.signer_id().clone(),
+                    public_key: t.transaction.public_key().clone(),
                 };
                 self.prefetch_trie_key(trie_key)?;
             }
@@ -297,7 +302,7 @@ mod tests {
         trie_config.enable_receipt_prefetching = true;
         let store = create_test_store();
         let flat_storage_factory = near_store::flat_state::FlatStateFactory::new(store.clone());
-        let tries = ShardTries::new(store, trie_config, &shard_uids, flat_storage_factory);
+        let tries = ShardTries::new(store, trie_config, &shard_uids, flat_storage_factory, None);
 
         let mut kvs = vec![];
 
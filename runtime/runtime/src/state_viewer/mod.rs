@@ -216,6 +216,7 @@ impl TrieViewer {
             output_data_receivers: vec![],
             input_data_ids: vec![],
             actions: vec![],
+            priority: 0,
         };
         let function_call = FunctionCallAction {
             method_name: method_name.to_string(),
@@ -234,7 +235,7 @@ impl TrieViewer {
             &empty_hash,
             config,
             true,
-            Some(ViewConfig { max_gas_burnt: self.max_gas_burnt_view }),
+            Some(ViewConfig { max_gas_burnt: self.max_gas_burnt_view, max_duration: None }),
         )
         .map_err(|e| errors::CallFunctionError::InternalError { error_message: e.to_string() })?;
         let elapsed = now.elapsed();
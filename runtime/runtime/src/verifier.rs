@@ -1,27 +1,28 @@
-use near_crypto::key_conversion::is_valid_staking_key;
+#[cfg(any(
+    feature = "protocol_feature_create_account_with_contract",
+    feature = "protocol_feature_transaction_priority_fee",
+    feature = "protocol_feature_function_call_method_allowances"
+))]
+use near_primitives::errors::ActionsValidationError;
 use near_primitives::runtime::get_insufficient_storage_stake;
+#[cfg(feature = "protocol_feature_transaction_priority_fee")]
+use near_primitives::transaction::Transaction;
+use near_primitives::validation::validate_actions;
 use near_primitives::{
     account::AccessKeyPermission,
     config::VMLimitConfig,
-    errors::{
-        ActionsValidationError, InvalidAccessKeyError, InvalidTxError, ReceiptValidationError,
-        RuntimeError,
-    },
+    errors::{InvalidAccessKeyError, InvalidTxError, ReceiptValidationError, RuntimeError},
     receipt::{ActionReceipt, DataReceipt, Receipt, ReceiptEnum},
-    transaction::{
-        Action, AddKeyAction, DeployContractAction, FunctionCallAction, SignedTransaction,
-        StakeAction,
-    },
+    transaction::{Action, SignedTransaction},
     types::{AccountId, Balance},
-    version::ProtocolVersion,
+    version::{ProtocolFeature, ProtocolVersion},
 };
 use near_store::{
     get_access_key, get_account, set_access_key, set_account, StorageError, TrieUpdate,
 };
 
-use crate::config::{total_prepaid_gas, tx_cost, TransactionCost};
+use crate::config::{tx_cost, TransactionCost};
 use crate::VerificationResult;
-use near_primitives::checked_feature;
 use near_primitives::runtime::config::RuntimeConfig;
 use near_primitives::types::BlockHeight;
 
@@ -35,30 +36,28 @@ pub fn validate_transaction(
     current_protocol_version: ProtocolVersion,
 ) -> Result<TransactionCost, RuntimeError> {
     let transaction = &signed_transaction.transaction;
-    let signer_id = &transaction.signer_id;
+    let signer_id = transaction.signer_id();
 
     if verify_signature
         && !signed_transaction
             .signature
-            .verify(signed_transaction.get_hash().as_ref(), &transaction.public_key)
+            .verify(signed_transaction.get_hash().as_ref(), transaction.public_key())
     {
         return Err(InvalidTxError::InvalidSignature.into());
     }
 
-    let transaction_size = signed_transaction.get_size();
-    let max_transaction_size = config.wasm_config.limit_config.max_transaction_size;
-    if transaction_size > max_transaction_size {
-        return Err(InvalidTxError::TransactionSizeExceeded {
-            size: transaction_size,
-            limit: max_transaction_size,
-        }
-        .into());
-    }
-
-    validate_actions(&config.wasm_config.limit_config, &transaction.actions)
+    signed_transaction.validate(&config.wasm_config.limit_config)?;
+    #[cfg(feature = "protocol_feature_create_account_with_contract")]
+    validate_actions_enabled(transaction.actions(), current_protocol_version)
+        .map_err(InvalidTxError::ActionsValidation)?;
+    #[cfg(feature = "protocol_feature_transaction_priority_fee")]
+    validate_transaction_version_enabled(transaction, current_protocol_version)
+        .map_err(InvalidTxError::ActionsValidation)?;
+    #[cfg(feature = "protocol_feature_function_call_method_allowances")]
+    validate_method_allowances_enabled(transaction.actions(), current_protocol_version)
         .map_err(InvalidTxError::ActionsValidation)?;
 
-    let sender_is_receiver = &transaction.receiver_id == signer_id;
+    let sender_is_receiver = transaction.receiver_id() == signer_id;
 
     tx_cost(
         &config.transaction_costs,
@@ -90,7 +89,7 @@ pub fn verify_and_charge_transaction(
             current_protocol_version,
         )?;
     let transaction = &signed_transaction.transaction;
-    let signer_id = &transaction.signer_id;
+    let signer_id = transaction.signer_id();
 
     let mut signer = match get_account(state_update, signer_id)? {
         Some(signer) => signer,
@@ -98,33 +97,33 @@ pub fn verify_and_charge_transaction(
             return Err(InvalidTxError::SignerDoesNotExist { signer_id: signer_id.clone() }.into());
         }
     };
-    let mut access_key = match get_access_key(state_update, signer_id, &transaction.public_key)? {
+    let mut access_key = match get_access_key(state_update, signer_id, transaction.public_key())? {
         Some(access_key) => access_key,
         None => {
             return Err(InvalidTxError::InvalidAccessKeyError(
                 InvalidAccessKeyError::AccessKeyNotFound {
                     account_id: signer_id.clone(),
-                    public_key: transaction.public_key.clone(),
+                    public_key: transaction.public_key().clone(),
                 },
             )
             .into());
         }
     };
 
-    if transaction.nonce <= access_key.nonce {
+    if transaction.nonce() <= access_key.nonce {
         return Err(InvalidTxError::InvalidNonce {
-            tx_nonce: transaction.nonce,
+            tx_nonce: transaction.nonce(),
             ak_nonce: access_key.nonce,
         }
         .into());
     }
-    if checked_feature!("stable", AccessKeyNonceRange, current_protocol_version) {
+    if ProtocolFeature::AccessKeyNonceRange.enabled(current_protocol_version) {
         if let Some(height) = block_height {
             let upper_bound =
                 height * near_primitives::account::AccessKey::ACCESS_KEY_NONCE_RANGE_MULTIPLIER;
-            if transaction.nonce >= upper_bound {
+            if transaction.nonce() >= upper_bound {
                 return Err(InvalidTxError::NonceTooLarge {
-                    tx_nonce: transaction.nonce,
+                    tx_nonce: transaction.nonce(),
                     upper_bound,
                 }
                 .into());
@@ -132,7 +131,7 @@ pub fn verify_and_charge_transaction(
         }
     };
 
-    access_key.nonce = transaction.nonce;
+    access_key.nonce = transaction.nonce();
 
     signer.set_amount(signer.amount().checked_sub(total_cost).ok_or_else(|| {
         InvalidTxError::NotEnoughBalance {
@@ -149,12 +148,29 @@ pub fn verify_and_charge_transaction(
             *allowance = allowance.checked_sub(total_cost).ok_or_else(|| {
                 InvalidTxError::InvalidAccessKeyError(InvalidAccessKeyError::NotEnoughAllowance {
                     account_id: signer_id.clone(),
-                    public_key: transaction.public_key.clone(),
+                    public_key: transaction.public_key().clone(),
                     allowance: *allowance,
                     cost: total_cost,
                 })
             })?;
         }
+        if let Some(Action::FunctionCall(function_call)) = transaction.actions().get(0) {
+            let method_name = &function_call.method_name;
+            if let Some(allowance) = function_call_permission.method_allowances.get_mut(method_name)
+            {
+                *allowance = allowance.checked_sub(total_cost).ok_or_else(|| {
+                    InvalidTxError::InvalidAccessKeyError(
+                        InvalidAccessKeyError::NotEnoughMethodAllowance {
+                            account_id: signer_id.clone(),
+                            public_key: transaction.public_key().clone(),
+                            method_name: method_name.clone(),
+                            allowance: *allowance,
+                            cost: total_cost,
+                        },
+                    )
+                })?;
+            }
+        }
     }
 
     match get_insufficient_storage_stake(&signer, config) {
@@ -172,23 +188,23 @@ pub fn verify_and_charge_transaction(
     };
 
     if let AccessKeyPermission::FunctionCall(ref function_call_permission) = access_key.permission {
-        if transaction.actions.len() != 1 {
+        if transaction.actions().len() != 1 {
             return Err(InvalidTxError::InvalidAccessKeyError(
                 InvalidAccessKeyError::RequiresFullAccess,
             )
             .into());
         }
-        if let Some(Action::FunctionCall(ref function_call)) = transaction.actions.get(0) {
+        if let Some(Action::FunctionCall(ref function_call)) = transaction.actions().get(0) {
             if function_call.deposit > 0 {
                 return Err(InvalidTxError::InvalidAccessKeyError(
                     InvalidAccessKeyError::DepositWithFunctionCall,
                 )
                 .into());
             }
-            if transaction.receiver_id.as_ref() != function_call_permission.receiver_id {
+            if transaction.receiver_id().as_ref() != function_call_permission.receiver_id {
                 return Err(InvalidTxError::InvalidAccessKeyError(
                     InvalidAccessKeyError::ReceiverMismatch {
-                        tx_receiver: transaction.receiver_id.clone(),
+                        tx_receiver: transaction.receiver_id().clone(),
                         ak_receiver: function_call_permission.receiver_id.clone(),
                     },
                 )
@@ -215,7 +231,7 @@ pub fn verify_and_charge_transaction(
         }
     };
 
-    set_access_key(state_update, signer_id.clone(), transaction.public_key.clone(), &access_key);
+    set_access_key(state_update, signer_id.clone(), transaction.public_key().clone(), &access_key);
     set_account(state_update, signer_id.clone(), &signer);
 
     Ok(VerificationResult { gas_burnt, gas_remaining, receipt_gas_price, burnt_amount })
@@ -276,197 +292,86 @@ fn validate_data_receipt(
     Ok(())
 }
 
-/// Validates given actions:
+/// Checks that the given actions are supported by the current protocol version.
 ///
-/// - Checks limits if applicable.
-/// - Checks that the total number of actions doesn't exceed the limit.
-/// - Validates each individual action.
-/// - Checks that the total prepaid gas doesn't exceed the limit.
-pub(crate) fn validate_actions(
-    limit_config: &VMLimitConfig,
+/// Only transactions need to be checked, receipts can contain such actions
+/// only if the transaction creating them was valid.
+#[cfg(feature = "protocol_feature_create_account_with_contract")]
+fn validate_actions_enabled(
     actions: &[Action],
+    current_protocol_version: ProtocolVersion,
 ) -> Result<(), ActionsValidationError> {
-    if actions.len() as u64 > limit_config.max_actions_per_receipt {
-        return Err(ActionsValidationError::TotalNumberOfActionsExceeded {
-            total_number_of_actions: actions.len() as u64,
-            limit: limit_config.max_actions_per_receipt,
-        });
-    }
-
-    let mut iter = actions.iter().peekable();
-    while let Some(action) = iter.next() {
-        if let Action::DeleteAccount(_) = action {
-            if iter.peek().is_some() {
-                return Err(ActionsValidationError::DeleteActionMustBeFinal);
-            }
-        }
-        validate_action(limit_config, action)?;
-    }
-
-    let total_prepaid_gas =
-        total_prepaid_gas(actions).map_err(|_| ActionsValidationError::IntegerOverflow)?;
-    if total_prepaid_gas > limit_config.max_total_prepaid_gas {
-        return Err(ActionsValidationError::TotalPrepaidGasExceeded {
-            total_prepaid_gas,
-            limit: limit_config.max_total_prepaid_gas,
-        });
-    }
-
-    Ok(())
-}
-
-/// Validates a single given action. Checks limits if applicable.
-pub fn validate_action(
-    limit_config: &VMLimitConfig,
-    action: &Action,
-) -> Result<(), ActionsValidationError> {
-    match action {
-        Action::CreateAccount(_) => Ok(()),
-        Action::DeployContract(a) => validate_deploy_contract_action(limit_config, a),
-        Action::FunctionCall(a) => validate_function_call_action(limit_config, a),
-        Action::Transfer(_) => Ok(()),
-        Action::Stake(a) => validate_stake_action(a),
-        Action::AddKey(a) => validate_add_key_action(limit_config, a),
-        Action::DeleteKey(_) => Ok(()),
-        Action::DeleteAccount(_) => Ok(()),
-    }
-}
-
-/// Validates `DeployContractAction`. Checks that the given contract size doesn't exceed the limit.
-fn validate_deploy_contract_action(
-    limit_config: &VMLimitConfig,
-    action: &DeployContractAction,
-) -> Result<(), ActionsValidationError> {
-    if action.code.len() as u64 > limit_config.max_contract_size {
-        return Err(ActionsValidationError::ContractSizeExceeded {
-            size: action.code.len() as u64,
-            limit: limit_config.max_contract_size,
+    let feature = ProtocolFeature::CreateAccountWithContract;
+    if !feature.enabled(current_protocol_version)
+        && actions.iter().any(|action| matches!(action, Action::CreateAccountWithContract(_)))
+    {
+        return Err(ActionsValidationError::UnsupportedProtocolFeature {
+            protocol_feature: feature.to_string(),
+            version: feature.protocol_version(),
         });
     }
-
     Ok(())
 }
 
-/// Validates `FunctionCallAction`. Checks that the method name length doesn't exceed the limit and
-/// the length of the arguments doesn't exceed the limit.
-fn validate_function_call_action(
-    limit_config: &VMLimitConfig,
-    action: &FunctionCallAction,
+#[cfg(feature = "protocol_feature_transaction_priority_fee")]
+fn validate_transaction_version_enabled(
+    transaction: &Transaction,
+    current_protocol_version: ProtocolVersion,
 ) -> Result<(), ActionsValidationError> {
-    if action.gas == 0 {
-        return Err(ActionsValidationError::FunctionCallZeroAttachedGas);
-    }
-
-    if action.method_name.len() as u64 > limit_config.max_length_method_name {
-        return Err(ActionsValidationError::FunctionCallMethodNameLengthExceeded {
-            length: action.method_name.len() as u64,
-            limit: limit_config.max_length_method_name,
-        });
-    }
-
-    if action.args.len() as u64 > limit_config.max_arguments_length {
-        return Err(ActionsValidationError::FunctionCallArgumentsLengthExceeded {
-            length: action.args.len() as u64,
-            limit: limit_config.max_arguments_length,
+    let feature = ProtocolFeature::TransactionPriorityFee;
+    if !feature.enabled(current_protocol_version) && matches!(transaction, Transaction::V1(_)) {
+        return Err(ActionsValidationError::UnsupportedProtocolFeature {
+            protocol_feature: feature.to_string(),
+            version: feature.protocol_version(),
         });
     }
-
-    Ok(())
-}
-
-/// Validates `StakeAction`. Checks that the `public_key` is a valid staking key.
-fn validate_stake_action(action: &StakeAction) -> Result<(), ActionsValidationError> {
-    if !is_valid_staking_key(&action.public_key) {
-        return Err(ActionsValidationError::UnsuitableStakingKey {
-            public_key: action.public_key.clone(),
-        });
-    }
-
     Ok(())
 }
 
-/// Validates `AddKeyAction`. If the access key permission is `FunctionCall`, checks that the
-/// total number of bytes of the method names doesn't exceed the limit and
-/// every method name length doesn't exceed the limit.
-fn validate_add_key_action(
-    limit_config: &VMLimitConfig,
-    action: &AddKeyAction,
+/// Checks that access keys added by the transaction have method allowances
+/// only if they are supported by the current protocol version.
+///
+/// Receipts don't need to be checked, contracts can't add keys with method
+/// allowances.
+#[cfg(feature = "protocol_feature_function_call_method_allowances")]
+fn validate_method_allowances_enabled(
+    actions: &[Action],
+    current_protocol_version: ProtocolVersion,
 ) -> Result<(), ActionsValidationError> {
-    if let AccessKeyPermission::FunctionCall(fc) = &action.access_key.permission {
-        // Check whether `receiver_id` is a valid account_id. Historically, we
-        // allowed arbitrary strings there!
-        match limit_config.account_id_validity_rules_version {
-            near_vm_logic::AccountIdValidityRulesVersion::V0 => (),
-            near_vm_logic::AccountIdValidityRulesVersion::V1 => {
-                if let Err(_) = fc.receiver_id.parse::<AccountId>() {
-                    return Err(ActionsValidationError::InvalidAccountId {
-                        account_id: truncate_string(&fc.receiver_id, AccountId::MAX_LEN * 2),
-                    });
-                }
-            }
-        }
-
-        // Checking method name length limits
-        let mut total_number_of_bytes = 0;
-        for method_name in &fc.method_names {
-            let length = method_name.len() as u64;
-            if length > limit_config.max_length_method_name {
-                return Err(ActionsValidationError::AddKeyMethodNameLengthExceeded {
-                    length,
-                    limit: limit_config.max_length_method_name,
-                });
+    let feature = ProtocolFeature::FunctionCallMethodAllowances;
+    let has_method_allowances = |action: &Action| match action {
+        Action::AddKey(add_key) => match &add_key.access_key.permission {
+            AccessKeyPermission::FunctionCall(permission) => {
+                !permission.method_allowances.is_empty()
             }
-            // Adding terminating character to the total number of bytes
-            total_number_of_bytes += length + 1;
-        }
-        if total_number_of_bytes > limit_config.max_number_bytes_method_names {
-            return Err(ActionsValidationError::AddKeyMethodNamesNumberOfBytesExceeded {
-                total_number_of_bytes,
-                limit: limit_config.max_number_bytes_method_names,
-            });
-        }
+            AccessKeyPermission::FullAccess => false,
+        },
+        _ => false,
+    };
+    if !feature.enabled(current_protocol_version) && actions.iter().any(has_method_allowances) {
+        return Err(ActionsValidationError::UnsupportedProtocolFeature {
+            protocol_feature: feature.to_string(),
+            version: feature.protocol_version(),
+        });
     }
-
     Ok(())
 }
 
-fn truncate_string(s: &str, limit: usize) -> String {
-    for i in (0..=limit).rev() {
-        if let Some(s) = s.get(..i) {
-            return s.to_string();
-        }
-    }
-    unreachable!()
-}
-
-#[test]
-fn test_truncate_string() {
-    fn check(input: &str, limit: usize, want: &str) {
-        let got = truncate_string(input, limit);
-        assert_eq!(got, want)
-    }
-    check("", 10, "");
-    check("hello", 0, "");
-    check("hello", 2, "he");
-    check("hello", 4, "hell");
-    check("hello", 5, "hello");
-    check("hello", 6, "hello");
-    check("hello", 10, "hello");
-    check("привет", 3, "п");
-}
-
 #[cfg(test)]
 mod tests {
     use std::sync::Arc;
 
     use near_crypto::{InMemorySigner, KeyType, PublicKey, Signer};
     use near_primitives::account::{AccessKey, Account, FunctionCallPermission};
+    use near_primitives::errors::ActionsValidationError;
     use near_primitives::hash::{hash, CryptoHash};
     use near_primitives::test_utils::account_new;
     use near_primitives::transaction::{
-        CreateAccountAction, DeleteAccountAction, DeleteKeyAction, StakeAction, TransferAction,
+        AddKeyAction, CreateAccountAction, DeleteAccountAction, DeleteKeyAction,
+        DeployContractAction, FunctionCallAction, StakeAction, TransferAction,
     };
     use near_primitives::types::{AccountId, Balance, MerkleHash, StateChangeCause};
+    use near_primitives::validation::validate_action;
     use near_primitives::version::PROTOCOL_VERSION;
     use near_store::test_utils::create_tries;
     use testlib::runtime_utils::{alice_account, bob_account, eve_dot_alice_account};
@@ -604,6 +509,73 @@ mod tests {
         assert_eq!(access_key.nonce, 1);
     }
 
+    #[test]
+    #[cfg(feature = "protocol_feature_transaction_priority_fee")]
+    fn test_validate_transaction_priority_fee() {
+        use near_primitives::transaction::TransactionV1;
+
+        let config = RuntimeConfig::test();
+        let (signer, mut state_update, gas_price) =
+            setup_common(TESTING_INIT_BALANCE, 0, Some(AccessKey::full_access()));
+
+        let deposit = 100;
+        let priority_fee = 10 * NEAR_BASE;
+        let transaction = Transaction::V1(TransactionV1 {
+            signer_id: alice_account(),
+            public_key: signer.public_key(),
+            nonce: 1,
+            receiver_id: bob_account(),
+            block_hash: CryptoHash::default(),
+            actions: vec![Action::Transfer(TransferAction { deposit })],
+            priority_fee,
+        })
+        .sign(&*signer);
+
+        let feature = ProtocolFeature::TransactionPriorityFee;
+        assert_eq!(
+            validate_transaction(
+                &config,
+                gas_price,
+                &transaction,
+                true,
+                feature.protocol_version() - 1
+            )
+            .expect_err("expected an error"),
+            RuntimeError::InvalidTxError(InvalidTxError::ActionsValidation(
+                ActionsValidationError::UnsupportedProtocolFeature {
+                    protocol_feature: feature.to_string(),
+                    version: feature.protocol_version(),
+                }
+            )),
+        );
+
+        let verification_result = verify_and_charge_transaction(
+            &config,
+            &mut state_update,
+            gas_price,
+            &transaction,
+            true,
+            None,
+            PROTOCOL_VERSION,
+        )
+        .expect("valid transaction");
+        // The priority fee is burnt together with the gas.
+        assert_eq!(
+            verification_result.burnt_amount,
+            Balance::from(verification_result.gas_burnt) * gas_price + priority_fee
+        );
+
+        let account = get_account(&state_update, &alice_account()).unwrap().unwrap();
+        assert_eq!(
+            account.amount(),
+            TESTING_INIT_BALANCE
+                - Balance::from(verification_result.gas_remaining)
+                    * verification_result.receipt_gas_price
+                - verification_result.burnt_amount
+                - deposit
+        );
+    }
+
     #[test]
     fn test_validate_transaction_invalid_signature() {
         let config = RuntimeConfig::test();
@@ -827,6 +799,7 @@ mod tests {
                     allowance: Some(100),
                     receiver_id: bob_account().into(),
                     method_names: vec![],
+                    method_allowances: Default::default(),
                 }),
             }),
         );
@@ -866,6 +839,98 @@ mod tests {
         }
     }
 
+    #[test]
+    #[cfg(feature = "protocol_feature_function_call_method_allowances")]
+    fn test_validate_transaction_method_allowance() {
+        let config = RuntimeConfig::test();
+        let (signer, mut state_update, gas_price) = setup_common(
+            TESTING_INIT_BALANCE,
+            0,
+            Some(AccessKey {
+                nonce: 0,
+                permission: AccessKeyPermission::FunctionCall(FunctionCallPermission {
+                    allowance: None,
+                    receiver_id: bob_account().into(),
+                    method_names: vec![],
+                    method_allowances: [
+                        ("hello".to_string(), 100),
+                        ("world".to_string(), 10u128.pow(24)),
+                    ]
+                    .into_iter()
+                    .collect(),
+                }),
+            }),
+        );
+        let function_call = |nonce, method_name: &str| {
+            SignedTransaction::from_actions(
+                nonce,
+                alice_account(),
+                bob_account(),
+                &*signer,
+                vec![Action::FunctionCall(FunctionCallAction {
+                    method_name: method_name.to_string(),
+                    args: b"abc".to_vec(),
+                    gas: 300,
+                    deposit: 0,
+                })],
+                CryptoHash::default(),
+            )
+        };
+
+        let err = verify_and_charge_transaction(
+            &config,
+            &mut state_update,
+            gas_price,
+            &function_call(1, "hello"),
+            true,
+            None,
+            PROTOCOL_VERSION,
+        )
+        .expect_err("expected an error");
+        if let RuntimeError::InvalidTxError(InvalidTxError::InvalidAccessKeyError(
+            InvalidAccessKeyError::NotEnoughMethodAllowance {
+                account_id,
+                public_key,
+                method_name,
+                allowance,
+                cost,
+            },
+        )) = err
+        {
+            assert_eq!(account_id, alice_account());
+            assert_eq!(public_key, signer.public_key());
+            assert_eq!(method_name, "hello");
+            assert_eq!(allowance, 100);
+            assert!(cost > allowance);
+        } else {
+            panic!("Incorrect error");
+        }
+
+        let verification_result = verify_and_charge_transaction(
+            &config,
+            &mut state_update,
+            gas_price,
+            &function_call(1, "world"),
+            true,
+            None,
+            PROTOCOL_VERSION,
+        )
+        .expect("valid transaction");
+        assert!(verification_result.burnt_amount > 0);
+
+        // The method allowance is charged the same total cost as the account.
+        let account = get_account(&state_update, &alice_account()).unwrap().unwrap();
+        let total_cost = TESTING_INIT_BALANCE - account.amount();
+        let access_key =
+            get_access_key(&state_update, &alice_account(), &signer.public_key()).unwrap().unwrap();
+        if let AccessKeyPermission::FunctionCall(permission) = access_key.permission {
+            assert_eq!(permission.method_allowances["hello"], 100);
+            assert_eq!(permission.method_allowances["world"], 10u128.pow(24) - total_cost);
+        } else {
+            panic!("Incorrect permission");
+        }
+    }
+
     /// Setup: account has 1B yoctoN and is 180 bytes. Storage requirement is 1M per byte.
     /// Test that such account can not send 950M yoctoN out as that will leave it under storage requirements.
     #[test]
@@ -916,6 +981,7 @@ mod tests {
                     allowance: None,
                     receiver_id: bob_account().into(),
                     method_names: vec![],
+                    method_allowances: Default::default(),
                 }),
             }),
         );
@@ -1010,6 +1076,7 @@ mod tests {
                     allowance: None,
                     receiver_id: bob_account().into(),
                     method_names: vec![],
+                    method_allowances: Default::default(),
                 }),
             }),
         );
@@ -1058,6 +1125,7 @@ mod tests {
                     allowance: None,
                     receiver_id: bob_account().into(),
                     method_names: vec!["not_hello".to_string(), "world".to_string()],
+                    method_allowances: Default::default(),
                 }),
             }),
         );
@@ -1103,6 +1171,7 @@ mod tests {
                     allowance: None,
                     receiver_id: bob_account().into(),
                     method_names: vec![],
+                    method_allowances: Default::default(),
                 }),
             }),
         );
@@ -1207,7 +1276,8 @@ mod tests {
                     gas_price: 100,
                     output_data_receivers: vec![],
                     input_data_ids: vec![CryptoHash::default(), CryptoHash::default()],
-                    actions: vec![]
+                    actions: vec![],
+                    priority: 0,
                 }
             )
             .expect_err("expected an error"),
@@ -1486,6 +1556,7 @@ mod tests {
                         allowance: Some(1000),
                         receiver_id: alice_account().into(),
                         method_names: vec!["hello".to_string(), "world".to_string()],
+                        method_allowances: Default::default(),
                     }),
                 },
             }),
@@ -1493,6 +1564,31 @@ mod tests {
         .expect("valid action");
     }
 
+    #[test]
+    fn test_validate_action_invalid_add_key_method_allowance() {
+        assert_eq!(
+            validate_action(
+                &VMLimitConfig::test(),
+                &Action::AddKey(AddKeyAction {
+                    public_key: PublicKey::empty(KeyType::ED25519),
+                    access_key: AccessKey {
+                        nonce: 0,
+                        permission: AccessKeyPermission::FunctionCall(FunctionCallPermission {
+                            allowance: Some(1000),
+                            receiver_id: alice_account().into(),
+                            method_names: vec!["hello".to_string()],
+                            method_allowances: [("world".to_string(), 100)].into_iter().collect(),
+                        }),
+                    },
+                }),
+            )
+            .expect_err("expected an error"),
+            ActionsValidationError::AddKeyMethodAllowanceNotPermitted {
+                method_name: "world".to_string()
+            },
+        );
+    }
+
     #[test]
     fn test_validate_action_valid_delete_key() {
         validate_action(
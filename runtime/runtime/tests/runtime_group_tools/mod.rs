@@ -243,7 +243,7 @@ impl RuntimeGroup {
                 .0
                 .lock()
                 .unwrap()
-                .get_mut(&transaction.transaction.signer_id)
+                .get_mut(transaction.transaction.signer_id())
                 .unwrap()
                 .incoming_transactions
                 .push(transaction);
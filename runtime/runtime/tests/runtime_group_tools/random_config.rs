@@ -35,6 +35,10 @@ pub fn random_config() -> RuntimeConfig {
                 },
                 delete_key_cost: random_fee(),
                 delete_account_cost: random_fee(),
+                #[cfg(feature = "protocol_feature_create_account_with_contract")]
+                state_init_cost: random_fee(),
+                #[cfg(feature = "protocol_feature_create_account_with_contract")]
+                state_init_cost_per_byte: random_fee(),
             },
             storage_usage_config: StorageUsageConfig {
                 num_bytes_account: rng.next_u64() % 10000,
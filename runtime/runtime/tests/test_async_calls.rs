@@ -780,6 +780,7 @@ fn test_account_factory() {
                             allowance: Some(TESTING_INIT_BALANCE / 2),
                             receiver_id: "near_1".parse().unwrap(),
                             method_names: vec!["call_promise".to_string(), "hello".to_string()],
+                            method_allowances: Default::default(),
                         }));
                      },
                      a3, Action::DeployContract(DeployContractAction{code}), {
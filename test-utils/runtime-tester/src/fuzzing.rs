@@ -546,6 +546,7 @@ impl Scope {
                     allowance: None,
                     receiver_id: self.random_account(u)?.id.into(),
                     method_names: vec![],
+                    method_allowances: Default::default(),
                 })
             }
         };
@@ -1,3 +1,4 @@
 pub mod fees_utils;
 pub mod process_blocks;
 pub mod runtime_utils;
+pub mod workload;
@@ -0,0 +1,151 @@
+//! Reproducible transaction workloads.
+//!
+//! [`Workload`] generates a mix of transfers, contract deployments and
+//! function calls from a seed.  The same seed and [`WorkloadConfig`] always
+//! produce the same transactions, so runs on different branches can be
+//! compared on identical inputs.
+use near_crypto::{InMemorySigner, KeyType};
+use near_primitives::hash::CryptoHash;
+use near_primitives::transaction::{
+    Action, DeployContractAction, FunctionCallAction, SignedTransaction, TransferAction,
+};
+use near_primitives::types::{AccountId, Balance, Gas, Nonce};
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use std::collections::HashMap;
+
+/// Gas attached to generated function calls.
+const FUNCTION_CALL_GAS: Gas = 100_000_000_000_000;
+
+/// Largest deposit of generated transfers.
+const MAX_TRANSFER_DEPOSIT: Balance = 1_000_000;
+
+/// Accounts and relative frequencies of the kinds of transactions in a
+/// workload.
+#[derive(Debug, Clone)]
+pub struct WorkloadConfig {
+    /// Accounts sending and receiving the transactions.  The full access key
+    /// of each account must be derived from the account id as seed, as done
+    /// by `Genesis::test`.
+    pub accounts: Vec<AccountId>,
+    pub transfer_weight: u32,
+    pub deploy_weight: u32,
+    pub function_call_weight: u32,
+}
+
+impl WorkloadConfig {
+    pub fn new(accounts: Vec<AccountId>) -> Self {
+        Self { accounts, transfer_weight: 5, deploy_weight: 1, function_call_weight: 4 }
+    }
+}
+
+/// Generates signed transactions of a workload, see the module documentation.
+pub struct Workload {
+    config: WorkloadConfig,
+    rng: ChaCha8Rng,
+    signers: Vec<InMemorySigner>,
+    nonces: HashMap<AccountId, Nonce>,
+    /// Accounts the workload has deployed the test contract to, in order of
+    /// deployment.  Function calls only go to these accounts.
+    contracts: Vec<AccountId>,
+}
+
+impl Workload {
+    pub fn new(config: WorkloadConfig, seed: u64) -> Self {
+        assert!(!config.accounts.is_empty(), "a workload needs at least one account");
+        let signers = config
+            .accounts
+            .iter()
+            .map(|account_id| {
+                InMemorySigner::from_seed(account_id.clone(), KeyType::ED25519, account_id.as_ref())
+            })
+            .collect();
+        Self {
+            config,
+            rng: ChaCha8Rng::seed_from_u64(seed),
+            signers,
+            nonces: HashMap::new(),
+            contracts: vec![],
+        }
+    }
+
+    /// Generates the next transaction, referencing `block_hash`.
+    ///
+    /// Nonces start at 1 for every account, which assumes the access keys
+    /// haven't been used before.
+    pub fn next_transaction(&mut self, block_hash: CryptoHash) -> SignedTransaction {
+        let signer_idx = self.rng.gen_range(0..self.signers.len());
+        let signer_id = self.signers[signer_idx].account_id.clone();
+        let (receiver_id, action) = match self.next_kind() {
+            Kind::Transfer => {
+                let receiver_id = self.random_account();
+                let deposit = self.rng.gen_range(1..=MAX_TRANSFER_DEPOSIT);
+                (receiver_id, Action::Transfer(TransferAction { deposit }))
+            }
+            Kind::Deploy => {
+                if !self.contracts.contains(&signer_id) {
+                    self.contracts.push(signer_id.clone());
+                }
+                let code = near_test_contracts::rs_contract().to_vec();
+                (signer_id.clone(), Action::DeployContract(DeployContractAction { code }))
+            }
+            Kind::FunctionCall => {
+                let receiver_id =
+                    self.contracts[self.rng.gen_range(0..self.contracts.len())].clone();
+                // `write_key_value` takes a key followed by a u64 value.
+                let mut args = vec![0u8; 16];
+                self.rng.fill(&mut args[..]);
+                let action = Action::FunctionCall(FunctionCallAction {
+                    method_name: "write_key_value".to_string(),
+                    args,
+                    gas: FUNCTION_CALL_GAS,
+                    deposit: 0,
+                });
+                (receiver_id, action)
+            }
+        };
+        let nonce = self.nonces.entry(signer_id.clone()).or_insert(0);
+        *nonce += 1;
+        SignedTransaction::from_actions(
+            *nonce,
+            signer_id,
+            receiver_id,
+            &self.signers[signer_idx],
+            vec![action],
+            block_hash,
+        )
+    }
+
+    /// Generates the next `num` transactions, all referencing `block_hash`.
+    pub fn transactions(&mut self, num: usize, block_hash: CryptoHash) -> Vec<SignedTransaction> {
+        (0..num).map(|_| self.next_transaction(block_hash)).collect()
+    }
+
+    fn next_kind(&mut self) -> Kind {
+        let WorkloadConfig { transfer_weight, deploy_weight, function_call_weight, .. } =
+            self.config;
+        // Function calls need a deployed contract, so they are left out until
+        // the first deployment.
+        let function_call_weight = if self.contracts.is_empty() { 0 } else { function_call_weight };
+        let total = transfer_weight + deploy_weight + function_call_weight;
+        assert!(total > 0, "all transaction kinds of the workload have weight 0");
+        let x = self.rng.gen_range(0..total);
+        if x < transfer_weight {
+            Kind::Transfer
+        } else if x < transfer_weight + deploy_weight {
+            Kind::Deploy
+        } else {
+            Kind::FunctionCall
+        }
+    }
+
+    fn random_account(&mut self) -> AccountId {
+        self.config.accounts[self.rng.gen_range(0..self.config.accounts.len())].clone()
+    }
+}
+
+enum Kind {
+    Transfer,
+    Deploy,
+    FunctionCall,
+}
@@ -38,14 +38,14 @@ impl TxSendInfo {
         target_height: BlockHeight,
         now: Instant,
     ) -> Self {
-        let target_signer_id = if &tx.source_signer_id != &tx.target_tx.transaction.signer_id {
-            Some(tx.target_tx.transaction.signer_id.clone())
+        let target_signer_id = if &tx.source_signer_id != tx.target_tx.transaction.signer_id() {
+            Some(tx.target_tx.transaction.signer_id().clone())
         } else {
             None
         };
-        let target_receiver_id = if &tx.source_receiver_id != &tx.target_tx.transaction.receiver_id
+        let target_receiver_id = if &tx.source_receiver_id != tx.target_tx.transaction.receiver_id()
         {
-            Some(tx.target_tx.transaction.receiver_id.clone())
+            Some(tx.target_tx.transaction.receiver_id().clone())
         } else {
             None
         };
@@ -474,20 +474,20 @@ impl TxTracker {
         let txs = self
             .txs_by_signer
             .entry((
-                tx.target_tx.transaction.signer_id.clone(),
-                tx.target_tx.transaction.public_key.clone(),
+                tx.target_tx.transaction.signer_id().clone(),
+                tx.target_tx.transaction.public_key().clone(),
             ))
             .or_default();
 
         if let Some(highest_nonce) = txs.iter().next_back() {
-            if highest_nonce.nonce > tx.target_tx.transaction.nonce {
+            if highest_nonce.nonce > tx.target_tx.transaction.nonce() {
                 tracing::warn!(
                     target: "mirror", "transaction sent with out of order nonce: {}: {}. Sent so far: {:?}",
-                    &hash, tx.target_tx.transaction.nonce, txs
+                    &hash, tx.target_tx.transaction.nonce(), txs
                 );
             }
         }
-        if !txs.insert(TxId { hash, nonce: tx.target_tx.transaction.nonce }) {
+        if !txs.insert(TxId { hash, nonce: tx.target_tx.transaction.nonce() }) {
             tracing::warn!(target: "mirror", "inserted tx {} twice into txs_by_signer", &hash);
         }
     }
@@ -13,7 +13,7 @@ use near_indexer::{Indexer, StreamerMessage};
 use near_o11y::WithSpanContextExt;
 use near_primitives::hash::CryptoHash;
 use near_primitives::transaction::{
-    Action, AddKeyAction, DeleteKeyAction, SignedTransaction, Transaction,
+    Action, AddKeyAction, DeleteKeyAction, SignedTransaction, Transaction, TransactionV0,
 };
 use near_primitives::types::{
     AccountId, BlockHeight, BlockId, BlockReference, Finality, TransactionOrReceiptId,
@@ -157,14 +157,14 @@ struct TxAwaitingNonce {
     source_receiver_id: AccountId,
     source_tx_index: usize,
     target_private: SecretKey,
-    target_tx: Transaction,
+    target_tx: TransactionV0,
 }
 
 impl TxAwaitingNonce {
     fn new(
         source_tx: &SignedTransactionView,
         source_tx_index: usize,
-        target_tx: Transaction,
+        target_tx: TransactionV0,
         target_private: SecretKey,
     ) -> Self {
         Self {
@@ -217,9 +217,10 @@ impl TargetChainTx {
         match self {
             Self::AwaitingNonce(t) => {
                 t.target_tx.nonce = nonce;
+                let target_tx = Transaction::V0(t.target_tx.clone());
                 let target_tx = SignedTransaction::new(
-                    t.target_private.sign(&t.target_tx.get_hash_and_size().0.as_ref()),
-                    t.target_tx.clone(),
+                    t.target_private.sign(&target_tx.get_hash_and_size().0.as_ref()),
+                    target_tx,
                 );
                 tracing::debug!(
                     target: "mirror", "prepared a transaction for ({:?}, {:?}) that was previously waiting for the access key to appear on chain",
@@ -848,7 +849,7 @@ impl TxMirror {
                             nonce,
                             ref_hash.clone(),
                         );
-                        target_tx.actions = actions;
+                        *target_tx.actions_mut() = actions;
                         let target_tx = SignedTransaction::new(
                             mapped_key.sign(&target_tx.get_hash_and_size().0.as_ref()),
                             target_tx,
@@ -863,20 +864,20 @@ impl TxMirror {
                             continue;
                         }
                         MapNonceError::TargetKeyNotOnChain => {
-                            let mut target_tx = Transaction::new(
-                                crate::key_mapping::map_account(
+                            let target_tx = TransactionV0 {
+                                signer_id: crate::key_mapping::map_account(
                                     &source_tx.signer_id,
                                     self.secret.as_ref(),
                                 ),
                                 public_key,
-                                crate::key_mapping::map_account(
+                                receiver_id: crate::key_mapping::map_account(
                                     &source_tx.receiver_id,
                                     self.secret.as_ref(),
                                 ),
-                                source_tx.nonce,
-                                ref_hash.clone(),
-                            );
-                            target_tx.actions = actions;
+                                nonce: source_tx.nonce,
+                                block_hash: ref_hash.clone(),
+                                actions,
+                            };
                             txs.push(TargetChainTx::AwaitingNonce(TxAwaitingNonce::new(
                                 &source_tx, idx, target_tx, mapped_key,
                             )));
@@ -0,0 +1,59 @@
+#![doc = include_str!("../README.md")]
+
+use clap::{Parser, Subcommand};
+use near_chain_configs::GenesisValidationMode;
+use near_state_viewer::cli::{GasProfileCmd, ReplayReceiptCmd, RocksDBStatsCmd};
+use near_state_viewer::StateViewerSubCommand;
+use near_store::Mode;
+use runtime_params_estimator::replay::ReplayCmd;
+use std::path::PathBuf;
+
+/// Debugging and analysis tools for NEAR nodes.
+#[derive(Parser)]
+#[clap(subcommand_required = true, arg_required_else_help = true)]
+struct Cli {
+    /// Directory with the config and database of the node to analyse.
+    /// Defaults to `$NEAR_HOME` or `~/.near`.
+    #[clap(long, parse(from_os_str))]
+    home: Option<PathBuf>,
+    #[clap(subcommand)]
+    subcmd: SubCommand,
+}
+
+#[derive(Subcommand)]
+enum SubCommand {
+    /// Analyse IO traces recorded with `neard --record-io-trace`.
+    #[clap(subcommand)]
+    Trace(TraceCmd),
+    /// Print the gas profile of a receipt, optionally recomputed with
+    /// alternative parameters.
+    GasProfile(GasProfileCmd),
+    /// Re-execute a receipt with an alternative runtime config and compare
+    /// the outcome and state changes with the ones stored on chain.
+    WhatIf(ReplayReceiptCmd),
+    /// Print RocksDB statistics of the node database.
+    DbStats(RocksDBStatsCmd),
+}
+
+#[derive(Subcommand)]
+enum TraceCmd {
+    /// Summarize DB accesses, cache statistics or gas charges of a trace.
+    Summarize(ReplayCmd),
+}
+
+fn main() -> anyhow::Result<()> {
+    let cli = Cli::parse();
+    let home_dir = cli.home.unwrap_or_else(nearcore::get_default_home);
+    // None of the analyses write to the database, so it is always opened in
+    // read-only mode. This allows running them next to a live node.
+    let run_state_viewer = |cmd: StateViewerSubCommand| {
+        cmd.run(&home_dir, GenesisValidationMode::UnsafeFast, Mode::ReadOnly)
+    };
+    match cli.subcmd {
+        SubCommand::Trace(TraceCmd::Summarize(cmd)) => cmd.run(&mut std::io::stdout())?,
+        SubCommand::GasProfile(cmd) => run_state_viewer(StateViewerSubCommand::GasProfile(cmd)),
+        SubCommand::WhatIf(cmd) => run_state_viewer(StateViewerSubCommand::ReplayReceipt(cmd)),
+        SubCommand::DbStats(cmd) => run_state_viewer(StateViewerSubCommand::RocksDBStats(cmd)),
+    }
+    Ok(())
+}
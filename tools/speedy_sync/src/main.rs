@@ -240,6 +240,7 @@ fn load_snapshot(load_cmd: LoadCmd) {
         ChainConfig {
             save_trie_changes: !config.client_config.archive,
             background_migration_threads: 1,
+            apply_chunk_cache_size: 0,
         },
     )
     .unwrap();
@@ -1,11 +1,18 @@
+use crate::column_stats::column_stats;
 use crate::commands::*;
+use crate::diff_state::diff_state;
 use crate::dump_state_parts::dump_state_parts;
 use crate::epoch_info;
+use crate::protocol_upgrades::TimelineFormat;
+use crate::receipt_graph::GraphFormat;
+use crate::replay_receipt::replay_receipt;
+use crate::rich_list::{Cursor, RankBy, RichList};
 use crate::rocksdb_stats::get_rocksdb_stats;
 use clap::{Args, Parser, Subcommand};
 use near_chain_configs::{GenesisChangeConfig, GenesisValidationMode};
 use near_primitives::account::id::AccountId;
 use near_primitives::hash::CryptoHash;
+use near_primitives::shard_layout::ShardLayout;
 use near_primitives::sharding::ChunkHash;
 use near_primitives::types::{BlockHeight, ShardId};
 use near_store::{Mode, Store};
@@ -53,6 +60,10 @@ pub enum StateViewerSubCommand {
     /// Dump stats for the RocksDB storage.
     #[clap(name = "rocksdb-stats", alias = "rocksdb_stats")]
     RocksDBStats(RocksDBStatsCmd),
+    /// Print number of keys, size of keys and values and SST file sizes of
+    /// each column.  Reads the whole database.
+    #[clap(alias = "column_stats")]
+    ColumnStats(ColumnStatsCmd),
     Receipts(ReceiptsCmd),
     Chunks(ChunksCmd),
     #[clap(alias = "partial_chunks")]
@@ -73,6 +84,53 @@ pub enum StateViewerSubCommand {
     ViewTrie(ViewTrieCmd),
     /// Dump all or a single state part of a shard.
     DumpStateParts(DumpStatePartsCmd),
+    /// Print the gas profile of a single receipt as parameter counters.
+    #[clap(alias = "gas_profile")]
+    GasProfile(GasProfileCmd),
+    /// Recompute storage usage of all accounts from the trie and compare it
+    /// with the `storage_usage` stored in the accounts.
+    #[clap(alias = "check_storage_usage")]
+    CheckStorageUsage(CheckStorageUsageCmd),
+    /// List all accounts with a contract deployed, optionally dumping the
+    /// contract code to files.
+    #[clap(alias = "contract_accounts")]
+    ContractAccounts(ContractAccountsCmd),
+    /// Write the WASM code of all deployed contracts to a directory, with a
+    /// CSV manifest of code hashes and sizes.
+    #[clap(alias = "dump_contracts")]
+    DumpContracts(DumpContractsCmd),
+    /// Export all contract data of an account as JSON lines, optionally
+    /// decoding the values with a schema.
+    #[clap(alias = "contract_state")]
+    ContractState(ContractStateCmd),
+    /// Compare the state of a shard at a block with the database of another
+    /// node and report differing keys.
+    #[clap(alias = "diff_state")]
+    DiffState(DiffStateCmd),
+    /// Export the graph of all receipts produced by a transaction, following
+    /// the receipt ids of the execution outcomes.
+    #[clap(alias = "receipt_graph")]
+    ReceiptGraph(ReceiptGraphCmd),
+    /// Re-execute a receipt against the state of the block it was applied in
+    /// and compare the outcome and state changes with the ones stored on chain.
+    #[clap(alias = "replay_receipt")]
+    ReplayReceipt(ReplayReceiptCmd),
+    /// Report when each protocol version became active, which block
+    /// producers voted for it and how long the adoption took.
+    #[clap(alias = "protocol_upgrades")]
+    ProtocolUpgrades(ProtocolUpgradesCmd),
+    /// List the accounts with the highest liquid or locked balance as CSV,
+    /// page by page.
+    #[clap(alias = "rich_list")]
+    RichList(RichListCmd),
+    /// Check the compiled contract cache against the contracts in state and
+    /// report stale, undecodable or mismatching entries.
+    #[clap(alias = "check_contract_cache")]
+    CheckContractCache(CheckContractCacheCmd),
+    /// Report the state size, gas burnt and cross-shard receipts each shard
+    /// would have with a proposed set of boundary accounts.
+    #[clap(alias = "shard_rebalance")]
+    ShardRebalance(ShardRebalanceCmd),
 }
 
 impl StateViewerSubCommand {
@@ -100,6 +158,7 @@ impl StateViewerSubCommand {
             StateViewerSubCommand::DumpAccountStorage(cmd) => cmd.run(home_dir, near_config, hot),
             StateViewerSubCommand::EpochInfo(cmd) => cmd.run(home_dir, near_config, hot),
             StateViewerSubCommand::RocksDBStats(cmd) => cmd.run(store_opener.path()),
+            StateViewerSubCommand::ColumnStats(cmd) => cmd.run(hot),
             StateViewerSubCommand::Receipts(cmd) => cmd.run(near_config, hot),
             StateViewerSubCommand::Chunks(cmd) => cmd.run(near_config, hot),
             StateViewerSubCommand::PartialChunks(cmd) => cmd.run(near_config, hot),
@@ -107,6 +166,18 @@ impl StateViewerSubCommand {
             StateViewerSubCommand::ApplyTx(cmd) => cmd.run(home_dir, near_config, hot),
             StateViewerSubCommand::ApplyReceipt(cmd) => cmd.run(home_dir, near_config, hot),
             StateViewerSubCommand::ViewTrie(cmd) => cmd.run(hot),
+            StateViewerSubCommand::GasProfile(cmd) => cmd.run(home_dir, near_config, hot),
+            StateViewerSubCommand::CheckStorageUsage(cmd) => cmd.run(home_dir, near_config, hot),
+            StateViewerSubCommand::ContractAccounts(cmd) => cmd.run(home_dir, near_config, hot),
+            StateViewerSubCommand::DumpContracts(cmd) => cmd.run(home_dir, near_config, hot),
+            StateViewerSubCommand::ContractState(cmd) => cmd.run(home_dir, near_config, hot),
+            StateViewerSubCommand::DiffState(cmd) => cmd.run(home_dir, near_config, hot),
+            StateViewerSubCommand::ReceiptGraph(cmd) => cmd.run(near_config, hot),
+            StateViewerSubCommand::ReplayReceipt(cmd) => cmd.run(home_dir, near_config, hot),
+            StateViewerSubCommand::ProtocolUpgrades(cmd) => cmd.run(near_config, hot),
+            StateViewerSubCommand::RichList(cmd) => cmd.run(home_dir, near_config, hot),
+            StateViewerSubCommand::CheckContractCache(cmd) => cmd.run(home_dir, near_config, hot),
+            StateViewerSubCommand::ShardRebalance(cmd) => cmd.run(home_dir, near_config, hot),
         }
     }
 }
@@ -377,6 +448,22 @@ impl RocksDBStatsCmd {
     }
 }
 
+#[derive(Parser)]
+pub struct ColumnStatsCmd {
+    /// Only report the given column, e.g. `State`.
+    #[clap(long)]
+    column: Option<String>,
+    /// Location of the dumped column stats.
+    #[clap(long, parse(from_os_str))]
+    file: Option<PathBuf>,
+}
+
+impl ColumnStatsCmd {
+    pub fn run(self, store: Store) {
+        column_stats(store, self.column, self.file).expect("Couldn't get column stats");
+    }
+}
+
 #[derive(Parser)]
 pub struct ReceiptsCmd {
     #[clap(long)]
@@ -504,3 +591,289 @@ impl DumpStatePartsCmd {
         );
     }
 }
+
+#[derive(Parser)]
+pub struct GasProfileCmd {
+    /// Id of the receipt to look up the outcome for.
+    receipt_id: CryptoHash,
+    /// Optional JSON file with an alternative `RuntimeConfig`.
+    /// If set, the gas cost of the receipt is recomputed with these parameters.
+    #[clap(long, parse(from_os_str))]
+    alt_config: Option<PathBuf>,
+}
+
+impl GasProfileCmd {
+    pub fn run(self, home_dir: &Path, near_config: NearConfig, store: Store) {
+        gas_profile(self.receipt_id, self.alt_config.as_deref(), home_dir, near_config, store)
+            .unwrap();
+    }
+}
+
+#[derive(Parser)]
+pub struct CheckStorageUsageCmd {
+    /// Optionally, can specify at which height to check the state.
+    #[clap(long)]
+    height: Option<BlockHeight>,
+    /// Maximum number of mismatching accounts to print.
+    #[clap(long, default_value = "100")]
+    max_mismatches: usize,
+}
+
+impl CheckStorageUsageCmd {
+    pub fn run(self, home_dir: &Path, near_config: NearConfig, store: Store) {
+        check_storage_usage(self.height, self.max_mismatches, home_dir, near_config, store);
+    }
+}
+
+#[derive(Parser)]
+pub struct ContractAccountsCmd {
+    /// Write the WASM code of each contract to `<account>.<code hash>.wasm`
+    /// inside this directory.
+    #[clap(long, parse(from_os_str))]
+    dump_dir: Option<PathBuf>,
+    /// Only write one file per distinct code hash.
+    #[clap(long, requires = "dump-dir")]
+    dedup: bool,
+}
+
+impl ContractAccountsCmd {
+    pub fn run(self, home_dir: &Path, near_config: NearConfig, store: Store) {
+        contract_accounts(self.dump_dir.as_deref(), self.dedup, home_dir, near_config, store)
+            .unwrap();
+    }
+}
+
+#[derive(Parser)]
+pub struct DumpContractsCmd {
+    /// Directory to write `<account>.<code hash>.wasm` files and
+    /// `manifest.csv` to.
+    #[clap(long, parse(from_os_str))]
+    out: PathBuf,
+    /// Only dump the contracts of these accounts.
+    /// If not set, all contracts will be dumped.
+    #[clap(long)]
+    account_ids: Option<Vec<AccountId>>,
+}
+
+impl DumpContractsCmd {
+    pub fn run(self, home_dir: &Path, near_config: NearConfig, store: Store) {
+        dump_contracts(&self.out, self.account_ids.as_deref(), home_dir, near_config, store)
+            .unwrap();
+    }
+}
+
+#[derive(Parser)]
+pub struct ContractStateCmd {
+    /// Account whose contract data is exported.
+    account_id: AccountId,
+    /// JSON file with type definitions and the types of values under key
+    /// prefixes, see the README.
+    #[clap(long, parse(from_os_str))]
+    decode: Option<PathBuf>,
+    /// Write the export to this file instead of stdout.
+    #[clap(long, parse(from_os_str))]
+    output: Option<PathBuf>,
+}
+
+impl ContractStateCmd {
+    pub fn run(self, home_dir: &Path, near_config: NearConfig, store: Store) {
+        contract_state(
+            &self.account_id,
+            self.decode.as_deref(),
+            self.output.as_deref(),
+            home_dir,
+            near_config,
+            store,
+        )
+        .unwrap();
+    }
+}
+
+#[derive(Parser)]
+pub struct DiffStateCmd {
+    /// Home directory of the node to compare with. Its database is opened
+    /// read-only with the store config of this node.
+    #[clap(long, parse(from_os_str))]
+    other_db: PathBuf,
+    /// Shard to compare.
+    #[clap(long)]
+    shard: ShardId,
+    /// Compare the state after applying this block.
+    #[clap(long)]
+    block: CryptoHash,
+    /// Maximum number of keys to print for each key type and kind of difference.
+    #[clap(long, default_value = "10")]
+    max_samples: usize,
+}
+
+impl DiffStateCmd {
+    pub fn run(self, home_dir: &Path, near_config: NearConfig, store: Store) {
+        diff_state(
+            &self.other_db,
+            self.shard,
+            self.block,
+            self.max_samples,
+            home_dir,
+            near_config,
+            store,
+        )
+        .unwrap();
+    }
+}
+
+#[derive(Parser)]
+pub struct ReceiptGraphCmd {
+    /// Hash of the transaction to start from.
+    #[clap(long)]
+    tx_hash: CryptoHash,
+    /// Output format.
+    #[clap(long, arg_enum, default_value = "dot")]
+    format: GraphFormat,
+    /// File to write the graph to. Printed to stdout if not set.
+    #[clap(long, parse(from_os_str))]
+    output: Option<PathBuf>,
+}
+
+impl ReceiptGraphCmd {
+    pub fn run(self, near_config: NearConfig, store: Store) {
+        receipt_graph(self.tx_hash, self.format, self.output.as_deref(), near_config, store)
+            .unwrap();
+    }
+}
+
+#[derive(Parser)]
+pub struct ReplayReceiptCmd {
+    /// Id of the receipt to replay.
+    receipt_id: CryptoHash,
+    /// JSON file with the `RuntimeConfig` to execute the receipt with.
+    /// Defaults to the config of the protocol version the receipt was
+    /// originally executed with.
+    #[clap(long, parse(from_os_str))]
+    runtime_config: Option<PathBuf>,
+    /// Maximum number of keys to print for each key type and kind of difference.
+    #[clap(long, default_value = "10")]
+    max_samples: usize,
+}
+
+impl ReplayReceiptCmd {
+    pub fn run(self, home_dir: &Path, near_config: NearConfig, store: Store) {
+        replay_receipt(
+            self.receipt_id,
+            self.runtime_config.as_deref(),
+            self.max_samples,
+            home_dir,
+            near_config,
+            store,
+        )
+        .unwrap();
+    }
+}
+
+#[derive(Parser)]
+pub struct ProtocolUpgradesCmd {
+    /// First block height to scan. Defaults to the genesis height.
+    #[clap(long)]
+    start_height: Option<BlockHeight>,
+    /// Last block height to scan. Defaults to the height of the head.
+    #[clap(long)]
+    end_height: Option<BlockHeight>,
+    /// Output format.
+    #[clap(long, arg_enum, default_value = "json")]
+    format: TimelineFormat,
+    /// File to write the report to. Printed to stdout if not set.
+    #[clap(long, parse(from_os_str))]
+    output: Option<PathBuf>,
+}
+
+impl ProtocolUpgradesCmd {
+    pub fn run(self, near_config: NearConfig, store: Store) {
+        protocol_upgrades(
+            self.start_height,
+            self.end_height,
+            self.format,
+            self.output.as_deref(),
+            near_config,
+            store,
+        )
+        .unwrap();
+    }
+}
+
+#[derive(Parser)]
+pub struct RichListCmd {
+    /// Number of accounts to list.
+    #[clap(long, default_value = "100")]
+    top: usize,
+    /// Balance to rank the accounts by.
+    #[clap(long, arg_enum, default_value = "liquid")]
+    by: RankBy,
+    /// Only list accounts of this shard. All shards by default.
+    #[clap(long)]
+    shard: Option<ShardId>,
+    /// Start after this position in the ranking, as printed at the end of
+    /// the previous page.
+    #[clap(long)]
+    cursor: Option<Cursor>,
+    /// File to write the CSV to. Printed to stdout if not set.
+    #[clap(long, parse(from_os_str))]
+    output: Option<PathBuf>,
+}
+
+impl RichListCmd {
+    pub fn run(self, home_dir: &Path, near_config: NearConfig, store: Store) {
+        let list = RichList::new(self.by, self.top, self.cursor);
+        rich_list(list, self.shard, self.output.as_deref(), home_dir, near_config, store).unwrap();
+    }
+}
+
+#[derive(Parser)]
+pub struct CheckContractCacheCmd {
+    /// Number of cache entries, chosen at random, to recompile and compare
+    /// with the cached artifact.
+    #[clap(long, default_value = "100")]
+    sample: usize,
+    /// Recompile all cache entries of contracts in state.
+    #[clap(long, conflicts_with = "sample")]
+    all: bool,
+    /// Delete stale, undecodable and mismatching entries from the cache.
+    /// Requires `--readwrite` and the node must not be running.
+    #[clap(long)]
+    purge: bool,
+}
+
+impl CheckContractCacheCmd {
+    pub fn run(self, home_dir: &Path, near_config: NearConfig, store: Store) {
+        let sample = (!self.all).then(|| self.sample);
+        check_contract_cache(sample, self.purge, home_dir, near_config, store).unwrap();
+    }
+}
+
+#[derive(Parser)]
+pub struct ShardRebalanceCmd {
+    /// Boundary accounts of the proposed layout, separated by commas. Shard 0
+    /// holds the accounts ordered before the first boundary account, shard 1
+    /// the ones from the first up to the second boundary account and so on.
+    #[clap(long, required = true, use_value_delimiter = true)]
+    boundary_accounts: Vec<AccountId>,
+    /// Number of blocks up to the head of which the traffic is analyzed.
+    #[clap(long, default_value = "1000")]
+    num_blocks: BlockHeight,
+    /// File to write the CSV to. Printed to stdout if not set.
+    #[clap(long, parse(from_os_str))]
+    output: Option<PathBuf>,
+}
+
+impl ShardRebalanceCmd {
+    pub fn run(self, home_dir: &Path, near_config: NearConfig, store: Store) {
+        let layout = ShardLayout::v1(vec![], self.boundary_accounts, None, 0);
+        shard_rebalance(
+            layout,
+            self.num_blocks,
+            self.output.as_deref(),
+            home_dir,
+            near_config,
+            store,
+        )
+        .unwrap();
+    }
+}
@@ -0,0 +1,39 @@
+//! State viewer command to report how much space each column takes.
+
+use near_store::{ColumnStats, DBCol, Store};
+use serde::Serialize;
+use std::path::PathBuf;
+use strum::IntoEnumIterator;
+
+#[derive(Serialize)]
+struct ColumnStatsEntry {
+    col: &'static str,
+    #[serde(flatten)]
+    stats: ColumnStats,
+}
+
+pub(crate) fn column_stats(
+    store: Store,
+    column: Option<String>,
+    file: Option<PathBuf>,
+) -> anyhow::Result<()> {
+    let columns: Vec<DBCol> = match column {
+        None => DBCol::iter().collect(),
+        Some(name) => vec![DBCol::iter()
+            .find(|col| <&str>::from(col) == name)
+            .ok_or_else(|| anyhow::anyhow!("unknown column {name}"))?],
+    };
+    let mut entries = Vec::with_capacity(columns.len());
+    for col in columns {
+        eprintln!("Processing column {col}...");
+        entries.push(ColumnStatsEntry { col: col.into(), stats: store.column_stats_for(col)? });
+    }
+    entries.sort_by_key(|entry| std::cmp::Reverse(entry.stats.key_bytes + entry.stats.value_bytes));
+
+    let result = serde_json::to_string_pretty(&entries)?;
+    match file {
+        None => println!("{}", result),
+        Some(file) => std::fs::write(file, result)?,
+    }
+    Ok(())
+}
@@ -1,4 +1,12 @@
 use crate::apply_chain_range::apply_chain_range;
+use crate::contract_accounts::{ContractAccountIterator, ContractDumper};
+use crate::contract_cache::{ExpectedCacheKeys, Reservoir};
+use crate::contract_state::{ContractStateEntry, ContractStateIterator, StateSchema};
+use crate::gas_profile::{estimate_gas_counters, extract_gas_counters};
+use crate::protocol_upgrades::{TimelineFormat, UpgradeTimeline};
+use crate::receipt_graph::{GraphFormat, ReceiptGraph};
+use crate::rich_list::{self, RichList};
+use crate::shard_rebalance::RebalanceAnalysis;
 use crate::state_dump::state_dump;
 use crate::state_dump::state_dump_redis;
 use crate::tx_dump::dump_tx_from_block;
@@ -15,22 +23,27 @@ use near_epoch_manager::EpochManagerAdapter;
 use near_network::iter_peers_from_store;
 use near_primitives::account::id::AccountId;
 use near_primitives::block::{Block, BlockHeader};
+use near_primitives::contract::ContractCode;
 use near_primitives::hash::CryptoHash;
-use near_primitives::shard_layout::ShardUId;
+use near_primitives::receipt::{Receipt, ReceiptEnum};
+use near_primitives::runtime::config::RuntimeConfig;
+use near_primitives::shard_layout::{ShardLayout, ShardUId};
 use near_primitives::sharding::ChunkHash;
 use near_primitives::state_record::StateRecord;
 use near_primitives::trie_key::TrieKey;
 use near_primitives::types::chunk_extra::ChunkExtra;
-use near_primitives::types::{BlockHeight, ShardId, StateRoot};
+use near_primitives::types::{BlockHeight, CompiledContract, ShardId, StateRoot};
 use near_primitives_core::types::Gas;
 use near_store::test_utils::create_test_store;
 use near_store::Trie;
 use near_store::TrieCache;
 use near_store::TrieCachingStorage;
 use near_store::TrieConfig;
-use near_store::{NodeStorage, Store};
+use near_store::{DBCol, NodeStorage, Store};
+use near_vm_runner::internal::VMKind;
 use nearcore::{NearConfig, NightshadeRuntime};
 use node_runtime::adapter::ViewRuntimeAdapter;
+use node_runtime::StorageComputer;
 use serde_json::json;
 use std::collections::HashMap;
 use std::fs::{self, File};
@@ -255,6 +268,274 @@ pub(crate) fn dump_account_storage(
     std::process::exit(1);
 }
 
+pub(crate) fn check_storage_usage(
+    height: Option<BlockHeight>,
+    max_mismatches: usize,
+    home_dir: &Path,
+    near_config: NearConfig,
+    store: Store,
+) {
+    let mode = match height {
+        Some(h) => LoadTrieMode::Height(h),
+        None => LoadTrieMode::Latest,
+    };
+    let (runtime, state_roots, header) =
+        load_trie_stop_at_height(store, home_dir, &near_config, mode);
+    let epoch_id = header.epoch_id();
+    let runtime_config = runtime.get_protocol_config(epoch_id).unwrap().runtime_config;
+    println!("Checking storage usage at height {}", header.height());
+
+    let mut num_accounts = 0;
+    let mut num_mismatches = 0;
+    for (shard_id, state_root) in state_roots.iter().enumerate() {
+        let trie = runtime
+            .get_trie_for_shard(shard_id as u64, header.prev_hash(), state_root.clone(), false)
+            .unwrap();
+        let mut storage_computer = StorageComputer::new(&runtime_config);
+        let mut declared = HashMap::new();
+        for item in trie.iter().unwrap() {
+            let (key, value) = item.unwrap();
+            if let Some(state_record) = StateRecord::from_raw_key_value(key, value) {
+                if let StateRecord::Account { account_id, account } = &state_record {
+                    declared.insert(account_id.clone(), account.storage_usage());
+                }
+                storage_computer.process_record(&state_record);
+            }
+        }
+        let computed = storage_computer.finalize();
+        num_accounts += declared.len();
+        for (account_id, declared_usage) in declared {
+            let actual_usage = computed.get(&account_id).copied().unwrap_or_default();
+            if actual_usage != declared_usage {
+                num_mismatches += 1;
+                if num_mismatches <= max_mismatches {
+                    println!(
+                        "shard {} account {}: declared {} bytes, actual {} bytes, diff {}",
+                        shard_id,
+                        account_id,
+                        declared_usage,
+                        actual_usage,
+                        actual_usage as i128 - declared_usage as i128,
+                    );
+                }
+            }
+        }
+    }
+    println!(
+        "Checked {} accounts, {} with mismatching storage usage",
+        num_accounts, num_mismatches
+    );
+}
+
+pub(crate) fn contract_accounts(
+    dump_dir: Option<&Path>,
+    dedup: bool,
+    home_dir: &Path,
+    near_config: NearConfig,
+    store: Store,
+) -> anyhow::Result<()> {
+    let (runtime, state_roots, header) = load_trie(store, home_dir, &near_config);
+    let mut dumper = dump_dir.map(|dir| ContractDumper::new(dir, dedup)).transpose()?;
+
+    let mut num_contracts = 0;
+    let mut num_written = 0;
+    for (shard_id, state_root) in state_roots.iter().enumerate() {
+        let trie = runtime.get_trie_for_shard(
+            shard_id as u64,
+            header.prev_hash(),
+            state_root.clone(),
+            false,
+        )?;
+        for contract in ContractAccountIterator::new(&trie)? {
+            let contract = contract?;
+            num_contracts += 1;
+            println!(
+                "{} {} {}",
+                contract.account_id,
+                contract.code_hash(),
+                contract.source_wasm.len()
+            );
+            if let Some(dumper) = &mut dumper {
+                if dumper.dump(&contract)?.is_some() {
+                    num_written += 1;
+                }
+            }
+        }
+    }
+    eprintln!("Found {} contracts", num_contracts);
+    if let Some(dir) = dump_dir {
+        eprintln!("Wrote {} files to {}", num_written, dir.display());
+    }
+    Ok(())
+}
+
+pub(crate) fn dump_contracts(
+    out: &Path,
+    account_ids: Option<&[AccountId]>,
+    home_dir: &Path,
+    near_config: NearConfig,
+    store: Store,
+) -> anyhow::Result<()> {
+    let (runtime, state_roots, header) = load_trie(store, home_dir, &near_config);
+    let mut dumper = ContractDumper::new(out, false)?.with_manifest()?;
+
+    let mut num_written = 0;
+    for (shard_id, state_root) in state_roots.iter().enumerate() {
+        let trie = runtime.get_trie_for_shard(
+            shard_id as u64,
+            header.prev_hash(),
+            state_root.clone(),
+            false,
+        )?;
+        for contract in ContractAccountIterator::new(&trie)? {
+            let contract = contract?;
+            if account_ids.map_or(false, |ids| !ids.contains(&contract.account_id)) {
+                continue;
+            }
+            dumper.dump(&contract)?;
+            num_written += 1;
+        }
+    }
+    println!("Wrote {} contracts to {}", num_written, out.display());
+    Ok(())
+}
+
+pub(crate) fn contract_state(
+    account_id: &AccountId,
+    decode: Option<&Path>,
+    output: Option<&Path>,
+    home_dir: &Path,
+    near_config: NearConfig,
+    store: Store,
+) -> anyhow::Result<()> {
+    let schema = decode.map(StateSchema::from_file).transpose()?;
+    let (runtime, state_roots, header) = load_trie(store, home_dir, &near_config);
+    let mut out: Box<dyn Write> = match output {
+        Some(path) => Box::new(std::io::BufWriter::new(File::create(path)?)),
+        None => Box::new(std::io::stdout().lock()),
+    };
+
+    let mut num_entries = 0;
+    let mut num_decode_errors = 0;
+    for (shard_id, state_root) in state_roots.iter().enumerate() {
+        let trie = runtime.get_trie_for_shard(
+            shard_id as u64,
+            header.prev_hash(),
+            state_root.clone(),
+            false,
+        )?;
+        for item in ContractStateIterator::new(&trie, account_id)? {
+            let (key, value) = item?;
+            let entry = ContractStateEntry::new(&key, &value, schema.as_ref());
+            if entry.decode_error.is_some() {
+                num_decode_errors += 1;
+            }
+            serde_json::to_writer(&mut out, &entry)?;
+            writeln!(out)?;
+            num_entries += 1;
+        }
+    }
+    out.flush()?;
+    eprintln!("Exported {} entries of {}", num_entries, account_id);
+    if num_decode_errors > 0 {
+        eprintln!("Failed to decode {} values", num_decode_errors);
+    }
+    Ok(())
+}
+
+pub(crate) fn check_contract_cache(
+    sample: Option<usize>,
+    purge: bool,
+    home_dir: &Path,
+    near_config: NearConfig,
+    store: Store,
+) -> anyhow::Result<()> {
+    let (runtime, state_roots, header) = load_trie(store.clone(), home_dir, &near_config);
+    let protocol_config = runtime.get_protocol_config(header.epoch_id())?;
+    let protocol_version = protocol_config.genesis_config.protocol_version;
+    let vm_kind = VMKind::for_protocol_version(protocol_version);
+    println!(
+        "Checking compiled contract cache for {:?} at protocol version {}",
+        vm_kind, protocol_version
+    );
+    let mut expected =
+        ExpectedCacheKeys::new(vm_kind, protocol_config.runtime_config.wasm_config.clone());
+
+    // Contracts to recompile, only those with a cache entry are considered.
+    let mut rng = rand::thread_rng();
+    let mut reservoir = Reservoir::new(sample.unwrap_or(usize::MAX));
+    let mut num_missing = 0;
+    for (shard_id, state_root) in state_roots.iter().enumerate() {
+        let trie = runtime.get_trie_for_shard(
+            shard_id as u64,
+            header.prev_hash(),
+            state_root.clone(),
+            false,
+        )?;
+        for contract in ContractAccountIterator::new(&trie)? {
+            let contract = contract?;
+            let code = ContractCode::new(contract.source_wasm, None);
+            let key = match expected.add(&code) {
+                Some(key) => key,
+                None => continue,
+            };
+            if store.exists(DBCol::CachedContractCode, key.as_ref())? {
+                reservoir.add((key, code), &mut rng);
+            } else {
+                num_missing += 1;
+            }
+        }
+    }
+
+    let mut bad_keys = Vec::new();
+    let mut num_valid = 0;
+    for item in store.iter(DBCol::CachedContractCode) {
+        let (key, value) = item?;
+        let status = expected.check(&key, &value);
+        if status.is_bad() {
+            match CryptoHash::try_from(&key[..]) {
+                Ok(key) => println!("{} {:?}", key, status),
+                Err(_) => println!("{:?} {:?}", key, status),
+            }
+            bad_keys.push(key);
+        } else {
+            num_valid += 1;
+        }
+    }
+
+    let sampled = reservoir.into_items();
+    let mut num_mismatches = 0;
+    for (key, code) in &sampled {
+        // Undecodable entries have already been reported above.
+        let cached =
+            match store.get_ser::<CompiledContract>(DBCol::CachedContractCode, key.as_ref()) {
+                Ok(Some(cached)) => cached,
+                Ok(None) | Err(_) => continue,
+            };
+        if !expected.recompile_matches(code, &cached)? {
+            println!("{} Mismatch {{ code_hash: {} }}", key, code.hash());
+            bad_keys.push(key.as_ref().to_vec().into_boxed_slice());
+            num_mismatches += 1;
+        }
+    }
+
+    println!("Distinct contracts in state: {}", expected.len());
+    println!("Contracts without cache entry: {}", num_missing);
+    println!("Valid cache entries: {}", num_valid);
+    println!("Stale or undecodable cache entries: {}", bad_keys.len() - num_mismatches);
+    println!("Recompiled entries: {}, mismatching: {}", sampled.len(), num_mismatches);
+
+    if purge && !bad_keys.is_empty() {
+        let mut store_update = store.store_update();
+        for key in &bad_keys {
+            store_update.delete(DBCol::CachedContractCode, key);
+        }
+        store_update.commit()?;
+        println!("Purged {} cache entries", bad_keys.len());
+    }
+    Ok(())
+}
+
 pub(crate) fn print_chain(
     start_height: BlockHeight,
     end_height: BlockHeight,
@@ -828,3 +1109,189 @@ pub(crate) fn view_trie(
     trie.print_recursive(&mut std::io::stdout().lock(), &hash, max_depth);
     Ok(())
 }
+
+pub(crate) fn gas_profile(
+    receipt_id: CryptoHash,
+    alt_config: Option<&Path>,
+    home_dir: &Path,
+    near_config: NearConfig,
+    store: Store,
+) -> anyhow::Result<()> {
+    let chain_store = ChainStore::new(
+        store.clone(),
+        near_config.genesis.config.genesis_height,
+        !near_config.client_config.archive,
+    );
+    let runtime = NightshadeRuntime::from_config(home_dir, store, &near_config);
+    let outcomes = chain_store.get_outcomes_by_id(&receipt_id)?;
+    let outcome = outcomes
+        .first()
+        .ok_or_else(|| anyhow::anyhow!("no execution outcome found for receipt {receipt_id}"))?;
+    let header = chain_store.get_block_header(&outcome.block_hash)?;
+    let protocol_config = runtime.get_protocol_config(header.epoch_id())?;
+    let runtime_config = protocol_config.runtime_config;
+    let outcome = &outcome.outcome_with_id.outcome;
+
+    println!("Receipt {} executed in block {}", receipt_id, header.height());
+    let receipt = chain_store.get_receipt(&receipt_id)?;
+    let attached_gas: Gas = match receipt.as_deref() {
+        Some(Receipt { receipt: ReceiptEnum::Action(action_receipt), .. }) => {
+            action_receipt.actions.iter().map(|action| action.get_prepaid_gas()).sum()
+        }
+        _ => 0,
+    };
+    println!("Attached gas: {}", attached_gas);
+    println!("Burnt gas:    {}", outcome.gas_burnt);
+    let counters = extract_gas_counters(outcome, &runtime_config).unwrap_or_else(|| {
+        estimate_gas_counters(
+            outcome,
+            receipt.as_deref(),
+            &runtime_config,
+            protocol_config.genesis_config.protocol_version,
+        )
+    });
+    println!("Recomputed gas: {}", counters.gas_cost(&runtime_config));
+    print!("{}", counters);
+
+    if let Some(path) = alt_config {
+        let alt_config: RuntimeConfig = serde_json::from_slice(&fs::read(path)?)?;
+        println!("Gas with parameters from {}: {}", path.display(), counters.gas_cost(&alt_config));
+    }
+    Ok(())
+}
+
+pub(crate) fn receipt_graph(
+    tx_hash: CryptoHash,
+    format: GraphFormat,
+    output: Option<&Path>,
+    near_config: NearConfig,
+    store: Store,
+) -> anyhow::Result<()> {
+    let chain_store = ChainStore::new(
+        store,
+        near_config.genesis.config.genesis_height,
+        !near_config.client_config.archive,
+    );
+    let graph = ReceiptGraph::load(&chain_store, tx_hash)?;
+    let rendered = graph.render(format)?;
+    match output {
+        Some(path) => {
+            fs::write(path, rendered)?;
+            eprintln!("Wrote graph of {} receipts to {}", graph.receipts.len(), path.display());
+        }
+        None => print!("{}", rendered),
+    }
+    Ok(())
+}
+
+pub(crate) fn protocol_upgrades(
+    start_height: Option<BlockHeight>,
+    end_height: Option<BlockHeight>,
+    format: TimelineFormat,
+    output: Option<&Path>,
+    near_config: NearConfig,
+    store: Store,
+) -> anyhow::Result<()> {
+    let genesis_height = near_config.genesis.config.genesis_height;
+    let chain_store =
+        ChainStore::new(store.clone(), genesis_height, !near_config.client_config.archive);
+    let epoch_manager = EpochManager::new_from_genesis_config(store, &near_config.genesis.config)?;
+    let start_height = start_height.unwrap_or(genesis_height);
+    let end_height = match end_height {
+        Some(end_height) => end_height,
+        None => chain_store.head()?.height,
+    };
+    let timeline = UpgradeTimeline::load(&chain_store, &epoch_manager, start_height, end_height)?;
+    let rendered = timeline.render(format)?;
+    match output {
+        Some(path) => {
+            fs::write(path, rendered)?;
+            eprintln!(
+                "Wrote {} upgrades in {} epochs to {}",
+                timeline.upgrades.len(),
+                timeline.epochs.len(),
+                path.display()
+            );
+        }
+        None => print!("{}", rendered),
+    }
+    Ok(())
+}
+
+pub(crate) fn rich_list(
+    mut list: RichList,
+    shard_id: Option<ShardId>,
+    output: Option<&Path>,
+    home_dir: &Path,
+    near_config: NearConfig,
+    store: Store,
+) -> anyhow::Result<()> {
+    let (runtime, state_roots, header) = load_trie(store, home_dir, &near_config);
+    for (current_shard_id, state_root) in state_roots.iter().enumerate() {
+        let current_shard_id = current_shard_id as ShardId;
+        if shard_id.map_or(false, |shard_id| shard_id != current_shard_id) {
+            continue;
+        }
+        let trie = runtime.get_trie_for_shard(
+            current_shard_id,
+            header.prev_hash(),
+            state_root.clone(),
+            false,
+        )?;
+        list.add_trie(&trie)?;
+    }
+    let (entries, next) = list.finish();
+    let rendered = rich_list::to_csv(&entries);
+    match output {
+        Some(path) => {
+            fs::write(path, rendered)?;
+            eprintln!("Wrote {} accounts to {}", entries.len(), path.display());
+        }
+        None => print!("{}", rendered),
+    }
+    if let Some(next) = next {
+        eprintln!("Next page: --cursor {}", next);
+    }
+    Ok(())
+}
+
+pub(crate) fn shard_rebalance(
+    layout: ShardLayout,
+    num_blocks: BlockHeight,
+    output: Option<&Path>,
+    home_dir: &Path,
+    near_config: NearConfig,
+    store: Store,
+) -> anyhow::Result<()> {
+    let genesis_height = near_config.genesis.config.genesis_height;
+    let chain_store =
+        ChainStore::new(store.clone(), genesis_height, !near_config.client_config.archive);
+    let (runtime, state_roots, header) = load_trie(store, home_dir, &near_config);
+    let mut analysis = RebalanceAnalysis::new(layout);
+    for (shard_id, state_root) in state_roots.iter().enumerate() {
+        let trie = runtime.get_trie_for_shard(
+            shard_id as ShardId,
+            header.prev_hash(),
+            state_root.clone(),
+            false,
+        )?;
+        analysis.add_trie(&trie)?;
+    }
+    let end_height = header.height();
+    let start_height = (end_height + 1).saturating_sub(num_blocks).max(genesis_height);
+    analysis.add_blocks(&chain_store, start_height, end_height)?;
+
+    let rendered = analysis.to_csv();
+    match output {
+        Some(path) => {
+            fs::write(path, rendered)?;
+            eprintln!("Wrote {} shards to {}", analysis.shards.len(), path.display());
+        }
+        None => print!("{}", rendered),
+    }
+    eprintln!(
+        "State at height {}, traffic of {} blocks from height {}. {} bytes of state don't belong to an account.",
+        end_height, analysis.blocks, start_height, analysis.unassigned_state_bytes
+    );
+    Ok(())
+}
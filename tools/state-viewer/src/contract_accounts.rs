@@ -0,0 +1,192 @@
+//! State viewer functions to list and filter accounts that have contracts
+//! deployed.
+
+use anyhow::Context;
+use near_primitives::hash::{hash, CryptoHash};
+use near_primitives::trie_key::trie_key_parsers::parse_account_id_from_contract_code_key;
+use near_primitives::trie_key::TrieKey;
+use near_primitives::types::AccountId;
+use near_store::{Trie, TrieIterator};
+use std::collections::HashSet;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// A contract deployed to an account, as found in the trie.
+pub(crate) struct ContractAccount {
+    pub(crate) account_id: AccountId,
+    pub(crate) source_wasm: Vec<u8>,
+}
+
+/// Iterates over all contracts stored in a trie, in the order of account ids.
+pub(crate) struct ContractAccountIterator<'a> {
+    trie_iter: TrieIterator<'a>,
+}
+
+impl<'a> ContractAccountIterator<'a> {
+    pub(crate) fn new(trie: &'a Trie) -> anyhow::Result<Self> {
+        // `TrieKey::ContractCode` requires a valid account id, use "xx" and
+        // cut it off again to get the column prefix.
+        let mut prefix = TrieKey::ContractCode { account_id: "xx".parse()? }.to_vec();
+        prefix.truncate(prefix.len() - 2);
+        let trie_iter = trie.iter_prefix(&prefix)?;
+        Ok(Self { trie_iter })
+    }
+}
+
+impl Iterator for ContractAccountIterator<'_> {
+    type Item = anyhow::Result<ContractAccount>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (key, value) = match self.trie_iter.next()? {
+            Ok(item) => item,
+            Err(err) => return Some(Err(err.into())),
+        };
+        Some(
+            parse_account_id_from_contract_code_key(&key)
+                .context("expected TrieKey::ContractCode")
+                .map(|account_id| ContractAccount { account_id, source_wasm: value }),
+        )
+    }
+}
+
+impl ContractAccount {
+    pub(crate) fn code_hash(&self) -> CryptoHash {
+        hash(&self.source_wasm)
+    }
+}
+
+/// Writes contract code into a directory, one `<account>.<code hash>.wasm`
+/// file per contract.
+pub(crate) struct ContractDumper {
+    dir: PathBuf,
+    /// If set, contracts with a code hash that has already been written are
+    /// skipped.
+    dedup: Option<HashSet<CryptoHash>>,
+    /// If set, every written contract is also listed in `manifest.csv`.
+    manifest: Option<fs::File>,
+}
+
+impl ContractDumper {
+    pub(crate) const MANIFEST_FILE: &'static str = "manifest.csv";
+
+    pub(crate) fn new(dir: &Path, dedup: bool) -> anyhow::Result<Self> {
+        fs::create_dir_all(dir)
+            .with_context(|| format!("failed to create dump directory {}", dir.display()))?;
+        Ok(Self { dir: dir.to_path_buf(), dedup: dedup.then(HashSet::new), manifest: None })
+    }
+
+    /// Lists the account id, code hash and code size of every written
+    /// contract in a `manifest.csv` next to the code.
+    pub(crate) fn with_manifest(mut self) -> anyhow::Result<Self> {
+        let manifest_path = self.dir.join(Self::MANIFEST_FILE);
+        let mut manifest = fs::File::create(&manifest_path)
+            .with_context(|| format!("failed to create {}", manifest_path.display()))?;
+        writeln!(manifest, "account_id,code_hash,size")?;
+        self.manifest = Some(manifest);
+        Ok(self)
+    }
+
+    /// Returns the path of the written file, or `None` if the contract was
+    /// skipped as a duplicate.
+    pub(crate) fn dump(&mut self, contract: &ContractAccount) -> anyhow::Result<Option<PathBuf>> {
+        let code_hash = contract.code_hash();
+        if let Some(seen) = &mut self.dedup {
+            if !seen.insert(code_hash) {
+                return Ok(None);
+            }
+        }
+        let path = self.dir.join(format!("{}.{}.wasm", contract.account_id, code_hash));
+        fs::write(&path, &contract.source_wasm)
+            .with_context(|| format!("failed to write contract to {}", path.display()))?;
+        if let Some(manifest) = &mut self.manifest {
+            // Account ids cannot contain commas, so no quoting is needed.
+            writeln!(
+                manifest,
+                "{},{},{}",
+                contract.account_id,
+                code_hash,
+                contract.source_wasm.len()
+            )?;
+        }
+        Ok(Some(path))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ContractAccount, ContractAccountIterator, ContractDumper};
+    use near_primitives::hash::hash;
+    use near_primitives::trie_key::TrieKey;
+    use near_store::test_utils::{create_tries, test_populate_trie};
+    use near_store::{ShardUId, Trie};
+
+    #[test]
+    fn test_contract_account_iterator() {
+        let tries = create_tries();
+        let entries = vec![
+            (TrieKey::ContractCode { account_id: "alice.near".parse().unwrap() }, b"a".to_vec()),
+            (TrieKey::ContractCode { account_id: "bob.near".parse().unwrap() }, b"b".to_vec()),
+            (TrieKey::Account { account_id: "carol.near".parse().unwrap() }, b"c".to_vec()),
+            (
+                TrieKey::ContractData {
+                    account_id: "alice.near".parse().unwrap(),
+                    key: b"key".to_vec(),
+                },
+                b"d".to_vec(),
+            ),
+        ];
+        let changes = entries.into_iter().map(|(key, value)| (key.to_vec(), Some(value))).collect();
+        let root = test_populate_trie(&tries, &Trie::EMPTY_ROOT, ShardUId::single_shard(), changes);
+        let trie = tries.get_trie_for_shard(ShardUId::single_shard(), root);
+
+        let contracts: Vec<ContractAccount> =
+            ContractAccountIterator::new(&trie).unwrap().map(Result::unwrap).collect();
+        let summary: Vec<(String, Vec<u8>)> = contracts
+            .into_iter()
+            .map(|contract| (contract.account_id.to_string(), contract.source_wasm))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![("alice.near".to_owned(), b"a".to_vec()), ("bob.near".to_owned(), b"b".to_vec())]
+        );
+    }
+
+    #[test]
+    fn test_contract_dumper_dedup() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut dumper = ContractDumper::new(dir.path(), true).unwrap();
+        let alice = ContractAccount {
+            account_id: "alice.near".parse().unwrap(),
+            source_wasm: b"code".to_vec(),
+        };
+        let bob = ContractAccount {
+            account_id: "bob.near".parse().unwrap(),
+            source_wasm: b"code".to_vec(),
+        };
+        assert!(dumper.dump(&alice).unwrap().is_some());
+        assert!(dumper.dump(&bob).unwrap().is_none());
+        assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 1);
+    }
+
+    #[test]
+    fn test_contract_dumper_manifest() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut dumper = ContractDumper::new(dir.path(), false).unwrap().with_manifest().unwrap();
+        let alice = ContractAccount {
+            account_id: "alice.near".parse().unwrap(),
+            source_wasm: b"code".to_vec(),
+        };
+        let path = dumper.dump(&alice).unwrap().unwrap();
+        drop(dumper);
+
+        assert_eq!(path, dir.path().join(format!("alice.near.{}.wasm", hash(b"code"))));
+        assert_eq!(std::fs::read(path).unwrap(), b"code");
+        let manifest =
+            std::fs::read_to_string(dir.path().join(ContractDumper::MANIFEST_FILE)).unwrap();
+        assert_eq!(
+            manifest,
+            format!("account_id,code_hash,size\nalice.near,{},4\n", hash(b"code"))
+        );
+    }
+}
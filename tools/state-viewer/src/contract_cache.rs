@@ -0,0 +1,173 @@
+//! Consistency check of the compiled contract cache stored in
+//! `DBCol::CachedContractCode`.
+//!
+//! Cache keys are hashes of the code hash, the VM kind, the VM version and
+//! the VM config, so they cannot be mapped back to a contract. Instead, the
+//! keys expected for the contracts in state are computed and every entry of
+//! the column without an expected key is considered stale: it was either
+//! compiled by another VM kind, VM version or config, or its code is no
+//! longer deployed to any account.
+
+use borsh::BorshDeserialize;
+use near_primitives::config::VMConfig;
+use near_primitives::contract::ContractCode;
+use near_primitives::hash::CryptoHash;
+use near_primitives::types::{CompiledContract, CompiledContractCache};
+use near_vm_runner::internal::VMKind;
+use near_vm_runner::{get_contract_cache_key, MockCompiledContractCache};
+use rand::Rng;
+use std::collections::HashMap;
+
+/// Result of checking a single entry of the compiled contract cache.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum CacheEntryStatus {
+    /// The entry belongs to a contract in state and can be decoded.
+    Valid { code_hash: CryptoHash },
+    /// No contract in state maps to the key with the current VM and config.
+    Stale,
+    /// The entry belongs to a contract in state but cannot be decoded.
+    Undecodable { code_hash: CryptoHash },
+}
+
+impl CacheEntryStatus {
+    pub(crate) fn is_bad(&self) -> bool {
+        !matches!(self, CacheEntryStatus::Valid { .. })
+    }
+}
+
+/// Expected cache keys of the contracts in state, for one VM kind and config.
+pub(crate) struct ExpectedCacheKeys {
+    vm_kind: VMKind,
+    config: VMConfig,
+    /// Cache key to code hash of the contract.
+    keys: HashMap<CryptoHash, CryptoHash>,
+}
+
+impl ExpectedCacheKeys {
+    pub(crate) fn new(vm_kind: VMKind, config: VMConfig) -> Self {
+        Self { vm_kind, config, keys: HashMap::new() }
+    }
+
+    /// Adds a contract found in state and returns its cache key, or `None` if
+    /// a contract with the same code has already been added.
+    pub(crate) fn add(&mut self, code: &ContractCode) -> Option<CryptoHash> {
+        let key = get_contract_cache_key(code, self.vm_kind, &self.config);
+        self.keys.insert(key, *code.hash()).is_none().then(|| key)
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.keys.len()
+    }
+
+    pub(crate) fn check(&self, key: &[u8], value: &[u8]) -> CacheEntryStatus {
+        let code_hash = match CryptoHash::try_from(key).ok().and_then(|key| self.keys.get(&key)) {
+            Some(code_hash) => *code_hash,
+            None => return CacheEntryStatus::Stale,
+        };
+        match CompiledContract::try_from_slice(value) {
+            Ok(_) => CacheEntryStatus::Valid { code_hash },
+            Err(_) => CacheEntryStatus::Undecodable { code_hash },
+        }
+    }
+
+    /// Compiles the contract again and returns whether the result equals the
+    /// cached one.
+    pub(crate) fn recompile_matches(
+        &self,
+        code: &ContractCode,
+        cached: &CompiledContract,
+    ) -> anyhow::Result<bool> {
+        let runtime = self.vm_kind.runtime(self.config.clone()).ok_or_else(|| {
+            anyhow::anyhow!("the {:?} runtime has not been enabled at compile time", self.vm_kind)
+        })?;
+        let cache = MockCompiledContractCache::default();
+        // Compilation errors are cached as well, so the result of interest is
+        // the one put into `cache`, not the returned one.
+        let _ = runtime.precompile(code, &cache)?;
+        let key = get_contract_cache_key(code, self.vm_kind, &self.config);
+        let recompiled = cache.get(&key)?;
+        Ok(recompiled.as_ref() == Some(cached))
+    }
+}
+
+/// Uniform random sample of at most `capacity` items out of a stream of
+/// unknown length.
+pub(crate) struct Reservoir<T> {
+    capacity: usize,
+    seen: usize,
+    items: Vec<T>,
+}
+
+impl<T> Reservoir<T> {
+    pub(crate) fn new(capacity: usize) -> Self {
+        Self { capacity, seen: 0, items: Vec::new() }
+    }
+
+    pub(crate) fn add(&mut self, item: T, rng: &mut impl Rng) {
+        self.seen += 1;
+        if self.items.len() < self.capacity {
+            self.items.push(item);
+        } else {
+            let index = rng.gen_range(0..self.seen);
+            if index < self.capacity {
+                self.items[index] = item;
+            }
+        }
+    }
+
+    pub(crate) fn into_items(self) -> Vec<T> {
+        self.items
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{CacheEntryStatus, ExpectedCacheKeys, Reservoir};
+    use borsh::BorshSerialize;
+    use near_primitives::config::VMConfig;
+    use near_primitives::contract::ContractCode;
+    use near_primitives::types::CompiledContract;
+    use near_vm_runner::internal::VMKind;
+
+    #[test]
+    fn test_check_cache_entry() {
+        let vm_kind = VMKind::for_protocol_version(near_primitives::version::PROTOCOL_VERSION);
+        let mut expected = ExpectedCacheKeys::new(vm_kind, VMConfig::test());
+        let code = ContractCode::new(near_test_contracts::trivial_contract().to_vec(), None);
+        let key = expected.add(&code).unwrap();
+        assert_eq!(expected.add(&code), None);
+        assert_eq!(expected.len(), 1);
+
+        let value = CompiledContract::Code(vec![1, 2, 3]).try_to_vec().unwrap();
+        assert_eq!(
+            expected.check(key.as_ref(), &value),
+            CacheEntryStatus::Valid { code_hash: *code.hash() }
+        );
+        assert_eq!(
+            expected.check(key.as_ref(), &[42]),
+            CacheEntryStatus::Undecodable { code_hash: *code.hash() }
+        );
+        let other_config = VMConfig { regular_op_cost: 1, ..VMConfig::test() };
+        let other_key = ExpectedCacheKeys::new(vm_kind, other_config).add(&code).unwrap();
+        assert_eq!(expected.check(other_key.as_ref(), &value), CacheEntryStatus::Stale);
+        assert_eq!(expected.check(b"short", &value), CacheEntryStatus::Stale);
+    }
+
+    #[test]
+    fn test_reservoir() {
+        let mut rng = rand::thread_rng();
+        let mut reservoir = Reservoir::new(3);
+        for i in 0..2 {
+            reservoir.add(i, &mut rng);
+        }
+        assert_eq!(reservoir.into_items(), vec![0, 1]);
+
+        let mut reservoir = Reservoir::new(3);
+        for i in 0..100 {
+            reservoir.add(i, &mut rng);
+        }
+        let items = reservoir.into_items();
+        assert_eq!(items.len(), 3);
+        assert!(items.iter().all(|i| *i < 100));
+    }
+}
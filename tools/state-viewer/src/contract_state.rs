@@ -0,0 +1,388 @@
+//! Export of the contract data of an account, optionally decoding the values
+//! with a schema provided by the contract developers.
+//!
+//! The schema file describes types in the shape of borsh's
+//! `BorshSchemaContainer` definitions and maps key prefixes to types:
+//!
+//! ```json
+//! {
+//!   "definitions": {
+//!     "Contract": {"Struct": {"fields": {"NamedFields": [["owner", "string"], ["total", "u128"]]}}},
+//!     "Option<u64>": {"Enum": {"variants": [["None", "()"], ["Some", "u64"]]}}
+//!   },
+//!   "keys": [
+//!     {"prefix": "STATE", "type": "Contract"},
+//!     {"prefix": "m", "type": "Option<u64>"}
+//!   ]
+//! }
+//! ```
+//!
+//! Values are decoded with the type of the longest prefix matching their key.
+
+use anyhow::Context;
+use near_primitives::trie_key::trie_key_parsers::parse_data_key_from_contract_data_key;
+use near_primitives::trie_key::TrieKey;
+use near_primitives::types::AccountId;
+use near_primitives_core::serialize::to_base64;
+use near_store::{Trie, TrieIterator};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::Path;
+
+/// Iterates over the contract data of one account, in the order of keys.
+pub(crate) struct ContractStateIterator<'a> {
+    account_id: &'a AccountId,
+    trie_iter: TrieIterator<'a>,
+}
+
+impl<'a> ContractStateIterator<'a> {
+    pub(crate) fn new(trie: &'a Trie, account_id: &'a AccountId) -> anyhow::Result<Self> {
+        let prefix = TrieKey::ContractData { account_id: account_id.clone(), key: vec![] }.to_vec();
+        let trie_iter = trie.iter_prefix(&prefix)?;
+        Ok(Self { account_id, trie_iter })
+    }
+}
+
+impl Iterator for ContractStateIterator<'_> {
+    /// Key as used by the contract and value.
+    type Item = anyhow::Result<(Vec<u8>, Vec<u8>)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (key, value) = match self.trie_iter.next()? {
+            Ok(item) => item,
+            Err(err) => return Some(Err(err.into())),
+        };
+        Some(
+            parse_data_key_from_contract_data_key(&key, self.account_id)
+                .context("expected TrieKey::ContractData")
+                .map(|data_key| (data_key.to_vec(), value)),
+        )
+    }
+}
+
+/// Type definition, mirroring `borsh::schema::Definition`.
+#[derive(Deserialize, Debug)]
+pub(crate) enum Definition {
+    Array { length: u32, elements: String },
+    Sequence { elements: String },
+    Tuple { elements: Vec<String> },
+    Enum { variants: Vec<(String, String)> },
+    Struct { fields: Fields },
+}
+
+/// Fields of a struct, mirroring `borsh::schema::Fields`.
+#[derive(Deserialize, Debug)]
+pub(crate) enum Fields {
+    NamedFields(Vec<(String, String)>),
+    UnnamedFields(Vec<String>),
+    Empty,
+}
+
+#[derive(Deserialize, Debug)]
+struct KeyRule {
+    prefix: String,
+    #[serde(rename = "type")]
+    declaration: String,
+}
+
+/// Content of the file passed with `--decode`.
+#[derive(Deserialize, Debug)]
+pub(crate) struct StateSchema {
+    #[serde(default)]
+    definitions: HashMap<String, Definition>,
+    keys: Vec<KeyRule>,
+}
+
+/// Maximum nesting of types while decoding a value, protects against
+/// recursive definitions.
+const MAX_DEPTH: usize = 64;
+
+impl StateSchema {
+    pub(crate) fn from_file(path: &Path) -> anyhow::Result<Self> {
+        let file = std::fs::File::open(path)
+            .with_context(|| format!("failed to open schema {}", path.display()))?;
+        serde_json::from_reader(file)
+            .with_context(|| format!("failed to parse schema {}", path.display()))
+    }
+
+    /// Declaration of the type of values stored under `key`, if any rule
+    /// matches.
+    fn declaration_for(&self, key: &[u8]) -> Option<&str> {
+        self.keys
+            .iter()
+            .filter(|rule| key.starts_with(rule.prefix.as_bytes()))
+            .max_by_key(|rule| rule.prefix.len())
+            .map(|rule| rule.declaration.as_str())
+    }
+
+    /// Decodes the value stored under `key`. Returns `None` if no rule
+    /// matches the key.
+    pub(crate) fn decode(
+        &self,
+        key: &[u8],
+        value: &[u8],
+    ) -> Option<anyhow::Result<serde_json::Value>> {
+        let declaration = self.declaration_for(key)?;
+        let mut bytes = value;
+        Some(self.decode_value(declaration, &mut bytes, 0).and_then(|decoded| {
+            anyhow::ensure!(
+                bytes.is_empty(),
+                "{} trailing bytes after decoding {}",
+                bytes.len(),
+                declaration
+            );
+            Ok(decoded)
+        }))
+    }
+
+    fn decode_value(
+        &self,
+        declaration: &str,
+        bytes: &mut &[u8],
+        depth: usize,
+    ) -> anyhow::Result<serde_json::Value> {
+        use serde_json::Value;
+        anyhow::ensure!(depth < MAX_DEPTH, "type nesting exceeds {} levels", MAX_DEPTH);
+        let value = match declaration {
+            "()" => Value::Null,
+            "bool" => match take::<1>(bytes)? {
+                [0] => Value::Bool(false),
+                [1] => Value::Bool(true),
+                [b] => anyhow::bail!("invalid bool {}", b),
+            },
+            "u8" => u8::from_le_bytes(take(bytes)?).into(),
+            "u16" => u16::from_le_bytes(take(bytes)?).into(),
+            "u32" => u32::from_le_bytes(take(bytes)?).into(),
+            "u64" => u64::from_le_bytes(take(bytes)?).into(),
+            "i8" => i8::from_le_bytes(take(bytes)?).into(),
+            "i16" => i16::from_le_bytes(take(bytes)?).into(),
+            "i32" => i32::from_le_bytes(take(bytes)?).into(),
+            "i64" => i64::from_le_bytes(take(bytes)?).into(),
+            // Like balances in RPC views, 128 bit integers are strings to not
+            // lose precision in JSON parsers.
+            "u128" => u128::from_le_bytes(take(bytes)?).to_string().into(),
+            "i128" => i128::from_le_bytes(take(bytes)?).to_string().into(),
+            "f32" => f32::from_le_bytes(take(bytes)?).into(),
+            "f64" => f64::from_le_bytes(take(bytes)?).into(),
+            "string" => {
+                let len = u32::from_le_bytes(take(bytes)?) as usize;
+                anyhow::ensure!(bytes.len() >= len, "string of {} bytes exceeds the value", len);
+                let (string, rest) = bytes.split_at(len);
+                *bytes = rest;
+                Value::String(String::from_utf8(string.to_vec()).context("invalid string")?)
+            }
+            _ => {
+                let definition = self
+                    .definitions
+                    .get(declaration)
+                    .with_context(|| format!("unknown type {}", declaration))?;
+                self.decode_definition(definition, bytes, depth)
+                    .with_context(|| format!("failed to decode {}", declaration))?
+            }
+        };
+        Ok(value)
+    }
+
+    fn decode_definition(
+        &self,
+        definition: &Definition,
+        bytes: &mut &[u8],
+        depth: usize,
+    ) -> anyhow::Result<serde_json::Value> {
+        use serde_json::Value;
+        let depth = depth + 1;
+        let value = match definition {
+            Definition::Array { length, elements } => Value::Array(
+                (0..*length)
+                    .map(|_| self.decode_value(elements, bytes, depth))
+                    .collect::<anyhow::Result<_>>()?,
+            ),
+            Definition::Sequence { elements } => {
+                let length = u32::from_le_bytes(take(bytes)?);
+                Value::Array(
+                    (0..length)
+                        .map(|_| self.decode_value(elements, bytes, depth))
+                        .collect::<anyhow::Result<_>>()?,
+                )
+            }
+            Definition::Tuple { elements } => Value::Array(
+                elements
+                    .iter()
+                    .map(|element| self.decode_value(element, bytes, depth))
+                    .collect::<anyhow::Result<_>>()?,
+            ),
+            Definition::Enum { variants } => {
+                let [tag] = take::<1>(bytes)?;
+                let (name, declaration) = variants
+                    .get(tag as usize)
+                    .with_context(|| format!("invalid enum variant {}", tag))?;
+                let mut variant = serde_json::Map::new();
+                variant.insert(name.clone(), self.decode_value(declaration, bytes, depth)?);
+                Value::Object(variant)
+            }
+            Definition::Struct { fields: Fields::NamedFields(fields) } => Value::Object(
+                fields
+                    .iter()
+                    .map(|(name, declaration)| {
+                        Ok((name.clone(), self.decode_value(declaration, bytes, depth)?))
+                    })
+                    .collect::<anyhow::Result<_>>()?,
+            ),
+            Definition::Struct { fields: Fields::UnnamedFields(fields) } => Value::Array(
+                fields
+                    .iter()
+                    .map(|declaration| self.decode_value(declaration, bytes, depth))
+                    .collect::<anyhow::Result<_>>()?,
+            ),
+            Definition::Struct { fields: Fields::Empty } => Value::Null,
+        };
+        Ok(value)
+    }
+}
+
+fn take<const N: usize>(bytes: &mut &[u8]) -> anyhow::Result<[u8; N]> {
+    anyhow::ensure!(bytes.len() >= N, "unexpected end of value");
+    let (head, rest) = bytes.split_at(N);
+    *bytes = rest;
+    Ok(head.try_into().unwrap())
+}
+
+/// One line of the export.
+#[derive(Serialize, Debug, PartialEq)]
+pub(crate) struct ContractStateEntry {
+    /// The key, if it is valid UTF-8.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) key: Option<String>,
+    pub(crate) key_base64: String,
+    pub(crate) value_base64: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) decoded: Option<serde_json::Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) decode_error: Option<String>,
+}
+
+impl ContractStateEntry {
+    pub(crate) fn new(key: &[u8], value: &[u8], schema: Option<&StateSchema>) -> Self {
+        let (decoded, decode_error) = match schema.and_then(|schema| schema.decode(key, value)) {
+            Some(Ok(decoded)) => (Some(decoded), None),
+            Some(Err(err)) => (None, Some(format!("{:#}", err))),
+            None => (None, None),
+        };
+        Self {
+            key: std::str::from_utf8(key).ok().map(str::to_owned),
+            key_base64: to_base64(key),
+            value_base64: to_base64(value),
+            decoded,
+            decode_error,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ContractStateEntry, ContractStateIterator, StateSchema};
+    use borsh::BorshSerialize;
+    use near_primitives::trie_key::TrieKey;
+    use near_store::test_utils::{create_tries, test_populate_trie};
+    use near_store::{ShardUId, Trie};
+    use serde_json::json;
+
+    #[test]
+    fn test_contract_state_iterator() {
+        let tries = create_tries();
+        let data = |account_id: &str, key: &[u8]| TrieKey::ContractData {
+            account_id: account_id.parse().unwrap(),
+            key: key.to_vec(),
+        };
+        let entries = vec![
+            (data("alice.near", b"STATE"), b"a".to_vec()),
+            (data("alice.near", b"m\x01"), b"b".to_vec()),
+            (data("alice.nearx", b"STATE"), b"c".to_vec()),
+            (TrieKey::ContractCode { account_id: "alice.near".parse().unwrap() }, b"d".to_vec()),
+        ];
+        let changes = entries.into_iter().map(|(key, value)| (key.to_vec(), Some(value))).collect();
+        let root = test_populate_trie(&tries, &Trie::EMPTY_ROOT, ShardUId::single_shard(), changes);
+        let trie = tries.get_trie_for_shard(ShardUId::single_shard(), root);
+
+        let account_id = "alice.near".parse().unwrap();
+        let state: Vec<(Vec<u8>, Vec<u8>)> =
+            ContractStateIterator::new(&trie, &account_id).unwrap().map(Result::unwrap).collect();
+        assert_eq!(
+            state,
+            vec![(b"STATE".to_vec(), b"a".to_vec()), (b"m\x01".to_vec(), b"b".to_vec())]
+        );
+    }
+
+    fn schema() -> StateSchema {
+        serde_json::from_value(json!({
+            "definitions": {
+                "Contract": {"Struct": {"fields": {"NamedFields": [
+                    ["owner", "string"],
+                    ["total", "u128"],
+                    ["paused", "bool"],
+                    ["ids", "Vec<u32>"],
+                ]}}},
+                "Vec<u32>": {"Sequence": {"elements": "u32"}},
+                "Option<u64>": {"Enum": {"variants": [["None", "()"], ["Some", "u64"]]}},
+                "Loop": {"Tuple": {"elements": ["Loop"]}},
+            },
+            "keys": [
+                {"prefix": "STATE", "type": "Contract"},
+                {"prefix": "m", "type": "Option<u64>"},
+                {"prefix": "mx", "type": "(u8, u8)"},
+                {"prefix": "loop", "type": "Loop"},
+            ],
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_decode() {
+        let schema = schema();
+        let contract = ("owner.near".to_string(), u128::MAX, true, vec![1u32, 2]);
+        let value = contract.try_to_vec().unwrap();
+        assert_eq!(
+            schema.decode(b"STATE", &value).unwrap().unwrap(),
+            json!({
+                "owner": "owner.near",
+                "total": u128::MAX.to_string(),
+                "paused": true,
+                "ids": [1, 2],
+            })
+        );
+        assert_eq!(
+            schema.decode(b"m\x00", &Some(7u64).try_to_vec().unwrap()).unwrap().unwrap(),
+            json!({"Some": 7})
+        );
+        assert_eq!(schema.decode(b"m\x01", &[0]).unwrap().unwrap(), json!({"None": null}));
+        assert!(schema.decode(b"other", &[]).is_none());
+
+        // The longest prefix wins, the type is unknown.
+        let err = schema.decode(b"mx", &[1, 2]).unwrap().unwrap_err();
+        assert_eq!(err.to_string(), "unknown type (u8, u8)");
+        let err = schema.decode(b"m", &[1, 0, 0]).unwrap().unwrap_err();
+        assert_eq!(format!("{:#}", err), "failed to decode Option<u64>: unexpected end of value");
+        let err = schema.decode(b"m", &[0, 0]).unwrap().unwrap_err();
+        assert_eq!(err.to_string(), "1 trailing bytes after decoding Option<u64>");
+        assert!(schema.decode(b"loop", &[]).unwrap().is_err());
+    }
+
+    #[test]
+    fn test_contract_state_entry() {
+        let schema = schema();
+        let entry = ContractStateEntry::new(b"m\xff", &[1, 7, 0, 0, 0, 0, 0, 0, 0], Some(&schema));
+        assert_eq!(
+            serde_json::to_value(&entry).unwrap(),
+            json!({
+                "key_base64": "bf8=",
+                "value_base64": "AQcAAAAAAAAA",
+                "decoded": {"Some": 7},
+            })
+        );
+        let entry = ContractStateEntry::new(b"STATE", &[], None);
+        assert_eq!(
+            serde_json::to_value(&entry).unwrap(),
+            json!({"key": "STATE", "key_base64": "U1RBVEU=", "value_base64": ""})
+        );
+    }
+}
@@ -0,0 +1,313 @@
+//! Compares the state of a shard between two databases.
+
+use near_chain::{ChainStore, ChainStoreAccess, RuntimeAdapter};
+use near_primitives::hash::CryptoHash;
+use near_primitives::serialize::to_base64;
+use near_primitives::shard_layout::ShardUId;
+use near_primitives::trie_key::trie_key_parsers::{
+    parse_account_id_from_raw_key, parse_trie_key_type_name,
+};
+use near_primitives::types::{ShardId, StateRoot};
+use near_store::{
+    Mode, NibbleSlice, NodeStorage, RawTrieNode, RawTrieNodeWithSize, Store, Temperature, Trie,
+};
+use nearcore::{NearConfig, NightshadeRuntime};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::Path;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub(crate) enum DiffKind {
+    /// The key only exists in this node's database.
+    MissingInOther,
+    /// The key only exists in the other node's database.
+    MissingInThis,
+    /// The key exists in both databases with different values.
+    ValueDiffers,
+}
+
+#[derive(Debug, Default)]
+struct DiffGroup {
+    count: u64,
+    /// The first few raw keys of this group, up to the configured limit.
+    samples: Vec<Vec<u8>>,
+}
+
+/// Differences between two tries, grouped by `TrieKey` type and kind of
+/// difference.
+#[derive(Debug)]
+pub(crate) struct StateDiff {
+    max_samples: usize,
+    groups: BTreeMap<(&'static str, DiffKind), DiffGroup>,
+}
+
+impl StateDiff {
+    pub(crate) fn new(max_samples: usize) -> Self {
+        Self { max_samples, groups: BTreeMap::new() }
+    }
+
+    pub(crate) fn record(&mut self, key: Vec<u8>, kind: DiffKind) {
+        let key_type = parse_trie_key_type_name(&key).unwrap_or("Unknown");
+        let group = self.groups.entry((key_type, kind)).or_default();
+        group.count += 1;
+        if group.samples.len() < self.max_samples {
+            group.samples.push(key);
+        }
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.groups.is_empty()
+    }
+
+    pub(crate) fn count(&self, key_type: &str, kind: DiffKind) -> u64 {
+        self.groups.get(&(key_type, kind)).map_or(0, |group| group.count)
+    }
+}
+
+impl fmt::Display for StateDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_empty() {
+            return writeln!(f, "No differences found");
+        }
+        for ((key_type, kind), group) in &self.groups {
+            writeln!(f, "{} {:?}: {} keys", key_type, kind, group.count)?;
+            for key in &group.samples {
+                match parse_account_id_from_raw_key(key) {
+                    Ok(Some(account_id)) => {
+                        writeln!(f, "    account {} key {}", account_id, to_base64(key))?
+                    }
+                    _ => writeln!(f, "    key {}", to_base64(key))?,
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Position in a trie: `skip` nibbles into the key of the node with `hash`.
+///
+/// Leaf and extension nodes cover several nibbles of the key, the cursor
+/// steps through them one nibble at a time so that both tries can be compared
+/// at the same key prefix regardless of their shape.
+#[derive(Clone, Copy, PartialEq, Eq)]
+struct NodeCursor {
+    hash: CryptoHash,
+    skip: usize,
+}
+
+impl NodeCursor {
+    fn root(trie: &Trie) -> Option<Self> {
+        let root = *trie.get_root();
+        (root != Trie::EMPTY_ROOT).then(|| Self { hash: root, skip: 0 })
+    }
+}
+
+/// Hash of the value stored at a key prefix and cursors to the subtries of
+/// the next nibble.
+#[derive(Default)]
+struct ExpandedNode {
+    value: Option<CryptoHash>,
+    children: [Option<NodeCursor>; 16],
+}
+
+fn expand(trie: &Trie, cursor: Option<NodeCursor>) -> anyhow::Result<ExpandedNode> {
+    let mut expanded = ExpandedNode::default();
+    let mut cursor = match cursor {
+        Some(cursor) => cursor,
+        None => return Ok(expanded),
+    };
+    loop {
+        let bytes = trie.storage.retrieve_raw_bytes(&cursor.hash)?;
+        match RawTrieNodeWithSize::decode(&bytes)?.node {
+            RawTrieNode::Branch(children, value) => {
+                expanded.value = value.map(|(_, hash)| hash);
+                for (child, hash) in expanded.children.iter_mut().zip(children) {
+                    *child = hash.map(|hash| NodeCursor { hash, skip: 0 });
+                }
+            }
+            RawTrieNode::Extension(key, child) => {
+                let (key, _) = NibbleSlice::from_encoded(&key);
+                if cursor.skip == key.len() {
+                    cursor = NodeCursor { hash: child, skip: 0 };
+                    continue;
+                }
+                expanded.children[key.at(cursor.skip) as usize] =
+                    Some(NodeCursor { hash: cursor.hash, skip: cursor.skip + 1 });
+            }
+            RawTrieNode::Leaf(key, _, value_hash) => {
+                let (key, _) = NibbleSlice::from_encoded(&key);
+                if cursor.skip == key.len() {
+                    expanded.value = Some(value_hash);
+                } else {
+                    expanded.children[key.at(cursor.skip) as usize] =
+                        Some(NodeCursor { hash: cursor.hash, skip: cursor.skip + 1 });
+                }
+            }
+        }
+        return Ok(expanded);
+    }
+}
+
+fn nibbles_to_key(nibbles: &[u8]) -> Vec<u8> {
+    nibbles.chunks(2).map(|pair| pair[0] << 4 | pair.get(1).copied().unwrap_or(0)).collect()
+}
+
+/// Walks both tries from the root, in key order, and records every key that
+/// is missing on either side or has a different value.
+///
+/// Subtries with the same node hash are identical and are skipped, so only
+/// the nodes on the paths to differing keys are read.
+pub(crate) fn diff_tries(
+    this: &Trie,
+    other: &Trie,
+    max_samples: usize,
+) -> anyhow::Result<StateDiff> {
+    let mut diff = StateDiff::new(max_samples);
+    let mut stack = vec![(Vec::new(), NodeCursor::root(this), NodeCursor::root(other))];
+    while let Some((nibbles, this_cursor, other_cursor)) = stack.pop() {
+        if this_cursor == other_cursor {
+            continue;
+        }
+        let this_node = expand(this, this_cursor)?;
+        let other_node = expand(other, other_cursor)?;
+        let kind = match (this_node.value, other_node.value) {
+            (Some(this_value), Some(other_value)) if this_value != other_value => {
+                Some(DiffKind::ValueDiffers)
+            }
+            (Some(_), None) => Some(DiffKind::MissingInOther),
+            (None, Some(_)) => Some(DiffKind::MissingInThis),
+            _ => None,
+        };
+        if let Some(kind) = kind {
+            diff.record(nibbles_to_key(&nibbles), kind);
+        }
+        for nibble in (0..16).rev() {
+            let (this_child, other_child) =
+                (this_node.children[nibble], other_node.children[nibble]);
+            if this_child != other_child {
+                let mut child_nibbles = nibbles.clone();
+                child_nibbles.push(nibble as u8);
+                stack.push((child_nibbles, this_child, other_child));
+            }
+        }
+    }
+    Ok(diff)
+}
+
+pub(crate) fn diff_state(
+    other_home: &Path,
+    shard_id: ShardId,
+    block_hash: CryptoHash,
+    max_samples: usize,
+    home_dir: &Path,
+    near_config: NearConfig,
+    store: Store,
+) -> anyhow::Result<()> {
+    let other_store = NodeStorage::opener(other_home, &near_config.config.store, None)
+        .open_in_mode(Mode::ReadOnly)?
+        .get_store(Temperature::Hot);
+
+    let runtime = NightshadeRuntime::from_config(home_dir, store.clone(), &near_config);
+    let other_runtime =
+        NightshadeRuntime::from_config(other_home, other_store.clone(), &near_config);
+    let epoch_id = runtime.get_epoch_id(&block_hash)?;
+    let shard_uid = runtime.shard_id_to_uid(shard_id, &epoch_id)?;
+
+    let this_root = state_root_at(store, &near_config, &block_hash, &shard_uid)?;
+    let other_root = state_root_at(other_store, &near_config, &block_hash, &shard_uid)?;
+    println!("State root: {} (this) vs {} (other)", this_root, other_root);
+    if this_root == other_root {
+        println!("State roots are equal");
+        return Ok(());
+    }
+
+    let this_trie = runtime.get_trie_for_shard(shard_id, &block_hash, this_root, false)?;
+    let other_trie = other_runtime.get_trie_for_shard(shard_id, &block_hash, other_root, false)?;
+    let diff = diff_tries(&this_trie, &other_trie, max_samples)?;
+    print!("{}", diff);
+    Ok(())
+}
+
+/// State root of a shard after applying the given block.
+fn state_root_at(
+    store: Store,
+    near_config: &NearConfig,
+    block_hash: &CryptoHash,
+    shard_uid: &ShardUId,
+) -> anyhow::Result<StateRoot> {
+    let chain_store = ChainStore::new(
+        store,
+        near_config.genesis.config.genesis_height,
+        !near_config.client_config.archive,
+    );
+    Ok(*chain_store.get_chunk_extra(block_hash, shard_uid)?.state_root())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{diff_tries, DiffKind};
+    use near_primitives::trie_key::TrieKey;
+    use near_store::test_utils::{create_tries, test_populate_trie};
+    use near_store::{ShardUId, Trie};
+
+    #[test]
+    fn test_diff_tries() {
+        let alice = || "alice.near".parse().unwrap();
+        let bob = || "bob.near".parse().unwrap();
+        let this = vec![
+            (TrieKey::Account { account_id: alice() }, b"1".to_vec()),
+            (TrieKey::Account { account_id: bob() }, b"2".to_vec()),
+            (TrieKey::ContractCode { account_id: alice() }, b"code".to_vec()),
+            (TrieKey::DelayedReceipt { index: 0 }, b"r".to_vec()),
+        ];
+        let other = vec![
+            (TrieKey::Account { account_id: alice() }, b"1".to_vec()),
+            (TrieKey::Account { account_id: bob() }, b"3".to_vec()),
+            (TrieKey::ContractData { account_id: alice(), key: b"k".to_vec() }, b"v".to_vec()),
+        ];
+
+        let tries = create_tries();
+        let populate = |entries: Vec<(TrieKey, Vec<u8>)>| {
+            let changes = entries.into_iter().map(|(key, value)| (key.to_vec(), Some(value)));
+            let root = test_populate_trie(
+                &tries,
+                &Trie::EMPTY_ROOT,
+                ShardUId::single_shard(),
+                changes.collect(),
+            );
+            tries.get_trie_for_shard(ShardUId::single_shard(), root)
+        };
+        let this_trie = populate(this);
+        let other_trie = populate(other);
+
+        let diff = diff_tries(&this_trie, &other_trie, 1).unwrap();
+        assert_eq!(diff.count("Account", DiffKind::ValueDiffers), 1);
+        assert_eq!(diff.count("Account", DiffKind::MissingInOther), 0);
+        assert_eq!(diff.count("ContractCode", DiffKind::MissingInOther), 1);
+        assert_eq!(diff.count("DelayedReceipt", DiffKind::MissingInOther), 1);
+        assert_eq!(diff.count("ContractData", DiffKind::MissingInThis), 1);
+
+        assert!(diff_tries(&this_trie, &this_trie, 1).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_diff_tries_skips_equal_subtries() {
+        let tries = create_tries();
+        let shard_uid = ShardUId::single_shard();
+        let receipt = |index: u64, value: &[u8]| {
+            (TrieKey::DelayedReceipt { index }.to_vec(), Some(value.to_vec()))
+        };
+        let changes = (0..1000).map(|index| receipt(index, b"r")).collect();
+        let this_root = test_populate_trie(&tries, &Trie::EMPTY_ROOT, shard_uid, changes);
+        let other_root =
+            test_populate_trie(&tries, &this_root, shard_uid, vec![receipt(500, b"s")]);
+
+        let this_trie = tries.get_trie_for_shard(shard_uid, this_root).recording_reads();
+        let other_trie = tries.get_trie_for_shard(shard_uid, other_root);
+        let diff = diff_tries(&this_trie, &other_trie, 1).unwrap();
+        assert_eq!(diff.count("DelayedReceipt", DiffKind::ValueDiffers), 1);
+        // Only the nodes on the path to the changed receipt are read.
+        let read_nodes = this_trie.recorded_storage().unwrap().nodes.0.len();
+        assert!(read_nodes < 20, "read {} nodes", read_nodes);
+    }
+}
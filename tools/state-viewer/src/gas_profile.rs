@@ -0,0 +1,252 @@
+use near_primitives::config::ExtCosts;
+use near_primitives::profile::{Cost, ProfileData};
+use near_primitives::receipt::{Receipt, ReceiptEnum};
+use near_primitives::runtime::config::RuntimeConfig;
+use near_primitives::transaction::{Action, ExecutionMetadata, ExecutionOutcome};
+use near_primitives::types::{NearGas, ProtocolVersion};
+use std::collections::BTreeMap;
+use std::fmt;
+use strum::IntoEnumIterator;
+
+/// Number of times each parameter was charged during the execution of a
+/// receipt, reconstructed from the gas profile stored in its outcome.
+///
+/// Profiles only store gas values, so counters are derived by dividing by the
+/// parameter cost that was in effect when the receipt was executed. Action
+/// costs share profile entries and cannot be split into counters, they are
+/// kept as a gas value.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub(crate) struct GasFeeCounters {
+    counters: BTreeMap<ExtCosts, u64>,
+    /// Number of wasm instructions, measured in units of `regular_op_cost`.
+    wasm_ops: u64,
+    action_gas: NearGas,
+    /// Gas which could not be attributed to any category.
+    unknown_gas: NearGas,
+    /// Whether the counters were estimated without a profile, see
+    /// [`estimate_gas_counters`].
+    estimated: bool,
+}
+
+/// Reconstructs the parameter counters from the profile of an outcome.
+///
+/// Returns `None` if the outcome has no profile attached (`ExecutionMetadata::V1`).
+pub(crate) fn extract_gas_counters(
+    outcome: &ExecutionOutcome,
+    config: &RuntimeConfig,
+) -> Option<GasFeeCounters> {
+    match &outcome.metadata {
+        ExecutionMetadata::V1 => None,
+        ExecutionMetadata::V2(profile)
+        | ExecutionMetadata::V3 { profile, .. }
+        | ExecutionMetadata::V4 { profile, .. }
+        | ExecutionMetadata::V5 { profile, .. }
+        | ExecutionMetadata::V6 { profile, .. }
+        | ExecutionMetadata::V7 { profile, .. } => Some(counters_from_profile(profile, config)),
+    }
+}
+
+/// Best-effort split of the gas burnt by a receipt without a profile
+/// (`ExecutionMetadata::V1`) into coarse categories, so that outcomes from
+/// before profiles were stored can still be used in what-if analyses.
+///
+/// Action gas is the execution fee of the receipt and its actions under
+/// `config`.  If the receipt calls a contract, the rest of the burnt gas is
+/// counted as wasm instructions, although it also includes host functions and
+/// receipts created by the contract.  Otherwise the rest is unknown.  The
+/// result is marked as estimated and should be treated with low confidence.
+pub(crate) fn estimate_gas_counters(
+    outcome: &ExecutionOutcome,
+    receipt: Option<&Receipt>,
+    config: &RuntimeConfig,
+    protocol_version: ProtocolVersion,
+) -> GasFeeCounters {
+    let fees = &config.transaction_costs;
+    let mut action_gas = NearGas::ZERO;
+    let mut calls_contract = false;
+    if let Some(Receipt { receiver_id, receipt: ReceiptEnum::Action(action_receipt), .. }) = receipt
+    {
+        let actions = &action_receipt.actions;
+        action_gas = node_runtime::config::total_prepaid_exec_fees(
+            fees,
+            actions,
+            receiver_id,
+            protocol_version,
+        )
+        .map_or(NearGas::MAX, NearGas::new)
+        .saturating_add(NearGas::new(fees.action_receipt_creation_config.exec_fee()));
+        calls_contract = actions.iter().any(|action| matches!(action, Action::FunctionCall(_)));
+    }
+    let gas_burnt = NearGas::new(outcome.gas_burnt);
+    let action_gas = action_gas.min(gas_burnt);
+    let mut unknown_gas = gas_burnt.saturating_sub(action_gas);
+    let mut wasm_ops = 0;
+    let regular_op_cost = u64::from(config.wasm_config.regular_op_cost);
+    if calls_contract && regular_op_cost > 0 {
+        wasm_ops = unknown_gas.get() / regular_op_cost;
+        unknown_gas = NearGas::new(unknown_gas.get() % regular_op_cost);
+    }
+    GasFeeCounters { counters: BTreeMap::new(), wasm_ops, action_gas, unknown_gas, estimated: true }
+}
+
+fn counters_from_profile(profile: &ProfileData, config: &RuntimeConfig) -> GasFeeCounters {
+    let mut counters = BTreeMap::new();
+    for cost in ExtCosts::iter() {
+        let parameter_value = cost.value(&config.wasm_config.ext_costs);
+        let gas = profile.get_ext_cost(cost);
+        if gas > 0 && parameter_value > 0 {
+            counters.insert(cost, gas / parameter_value);
+        }
+    }
+    let regular_op_cost = u64::from(config.wasm_config.regular_op_cost);
+    let wasm_ops = profile[Cost::WasmInstruction].checked_div(regular_op_cost).unwrap_or(0);
+    GasFeeCounters {
+        counters,
+        wasm_ops,
+        action_gas: NearGas::new(profile.action_gas()),
+        unknown_gas: NearGas::ZERO,
+        estimated: false,
+    }
+}
+
+impl GasFeeCounters {
+    /// Gas the same execution would have cost with the parameters in `config`.
+    pub(crate) fn gas_cost(&self, config: &RuntimeConfig) -> NearGas {
+        let ext_costs = &config.wasm_config.ext_costs;
+        let host_gas = self
+            .counters
+            .iter()
+            .map(|(cost, count)| NearGas::new(cost.value(ext_costs)).saturating_mul(*count))
+            .fold(NearGas::ZERO, NearGas::saturating_add);
+        let wasm_gas = NearGas::new(u64::from(config.wasm_config.regular_op_cost))
+            .saturating_mul(self.wasm_ops);
+        host_gas
+            .saturating_add(wasm_gas)
+            .saturating_add(self.action_gas)
+            .saturating_add(self.unknown_gas)
+    }
+}
+
+impl fmt::Display for GasFeeCounters {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.estimated {
+            writeln!(f, "No gas profile, coarse estimation with low confidence:")?;
+        }
+        writeln!(f, "{:<40} {:>20}", "parameter", "count")?;
+        writeln!(f, "{:<40} {:>20}", "wasm_regular_op_cost", self.wasm_ops)?;
+        for (cost, count) in &self.counters {
+            writeln!(f, "{:<40} {:>20}", cost.to_string(), count)?;
+        }
+        writeln!(f, "{:<40} {:>20}", "action gas (not split)", self.action_gas)?;
+        if self.estimated {
+            writeln!(f, "{:<40} {:>20}", "unknown gas", self.unknown_gas)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_counters_roundtrip() {
+        let config = RuntimeConfig::test();
+        let ext_costs = &config.wasm_config.ext_costs;
+        let mut profile = ProfileData::new();
+        profile.add_ext_cost(ExtCosts::base, 3 * ExtCosts::base.value(ext_costs));
+        profile.add_ext_cost(
+            ExtCosts::storage_read_base,
+            2 * ExtCosts::storage_read_base.value(ext_costs),
+        );
+        let gas_burnt = 1_000 * u64::from(config.wasm_config.regular_op_cost)
+            + 3 * ExtCosts::base.value(ext_costs)
+            + 2 * ExtCosts::storage_read_base.value(ext_costs);
+        profile.compute_wasm_instruction_cost(gas_burnt);
+
+        let outcome =
+            ExecutionOutcome { metadata: ExecutionMetadata::V2(profile), ..Default::default() };
+        let counters = extract_gas_counters(&outcome, &config).unwrap();
+        assert_eq!(counters.wasm_ops, 1_000);
+        assert_eq!(counters.counters.get(&ExtCosts::base), Some(&3));
+        assert_eq!(counters.counters.get(&ExtCosts::storage_read_base), Some(&2));
+        assert_eq!(counters.gas_cost(&config), NearGas::new(gas_burnt));
+    }
+
+    #[test]
+    fn test_no_profile() {
+        let outcome = ExecutionOutcome::default();
+        assert_eq!(extract_gas_counters(&outcome, &RuntimeConfig::test()), None);
+    }
+
+    #[test]
+    fn test_estimate_without_profile() {
+        use near_crypto::{KeyType, PublicKey};
+        use near_primitives::receipt::ActionReceipt;
+        use near_primitives::transaction::{FunctionCallAction, TransferAction};
+        use near_primitives::version::PROTOCOL_VERSION;
+
+        let config = RuntimeConfig::test();
+        let regular_op_cost = u64::from(config.wasm_config.regular_op_cost);
+        let receipt = |action| Receipt {
+            predecessor_id: "alice.near".parse().unwrap(),
+            receiver_id: "bob.near".parse().unwrap(),
+            receipt_id: Default::default(),
+            receipt: ReceiptEnum::Action(ActionReceipt {
+                signer_id: "alice.near".parse().unwrap(),
+                signer_public_key: PublicKey::empty(KeyType::ED25519),
+                gas_price: 0,
+                output_data_receivers: vec![],
+                input_data_ids: vec![],
+                actions: vec![action],
+                priority: 0,
+            }),
+        };
+        let receipt_gas = |receipt: &Receipt| match &receipt.receipt {
+            ReceiptEnum::Action(action_receipt) => {
+                node_runtime::config::total_prepaid_exec_fees(
+                    &config.transaction_costs,
+                    &action_receipt.actions,
+                    &receipt.receiver_id,
+                    PROTOCOL_VERSION,
+                )
+                .unwrap()
+                    + config.transaction_costs.action_receipt_creation_config.exec_fee()
+            }
+            ReceiptEnum::Data(_) => unreachable!(),
+        };
+
+        let call = receipt(Action::FunctionCall(FunctionCallAction {
+            method_name: "main".to_string(),
+            args: vec![],
+            gas: 100,
+            deposit: 0,
+        }));
+        let action_gas = receipt_gas(&call);
+        let gas_burnt = action_gas + 1_000 * regular_op_cost + 7;
+        let outcome = ExecutionOutcome { gas_burnt, ..Default::default() };
+        let counters = estimate_gas_counters(&outcome, Some(&call), &config, PROTOCOL_VERSION);
+        assert!(counters.estimated);
+        assert_eq!(counters.action_gas, NearGas::new(action_gas));
+        assert_eq!(counters.wasm_ops, 1_000);
+        assert_eq!(counters.unknown_gas, NearGas::new(7));
+        assert_eq!(counters.gas_cost(&config), NearGas::new(gas_burnt));
+
+        // Without a contract call, the remaining gas is not attributed.
+        let transfer = receipt(Action::Transfer(TransferAction { deposit: 1 }));
+        let action_gas = receipt_gas(&transfer);
+        let outcome = ExecutionOutcome { gas_burnt: action_gas + 100, ..Default::default() };
+        let counters = estimate_gas_counters(&outcome, Some(&transfer), &config, PROTOCOL_VERSION);
+        assert_eq!(
+            (counters.action_gas, counters.wasm_ops, counters.unknown_gas),
+            (NearGas::new(action_gas), 0, NearGas::new(100))
+        );
+
+        let outcome = ExecutionOutcome { gas_burnt: 100, ..Default::default() };
+        let counters = estimate_gas_counters(&outcome, None, &config, PROTOCOL_VERSION);
+        assert_eq!(
+            (counters.action_gas, counters.wasm_ops, counters.unknown_gas),
+            (NearGas::ZERO, 0, NearGas::new(100))
+        );
+    }
+}
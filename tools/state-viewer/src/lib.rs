@@ -3,10 +3,21 @@
 mod apply_chain_range;
 mod apply_chunk;
 pub mod cli;
+mod column_stats;
 mod commands;
+mod contract_accounts;
+mod contract_cache;
+mod contract_state;
+mod diff_state;
 mod dump_state_parts;
 mod epoch_info;
+mod gas_profile;
+mod protocol_upgrades;
+mod receipt_graph;
+mod replay_receipt;
+mod rich_list;
 mod rocksdb_stats;
+mod shard_rebalance;
 mod state_dump;
 mod tx_dump;
 
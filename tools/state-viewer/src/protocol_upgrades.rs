@@ -0,0 +1,256 @@
+//! Reconstructs the timeline of protocol upgrades from stored block headers:
+//! when each protocol version became active, which block producers voted for
+//! it and how long it took from the first vote to the activation.
+
+use near_chain::{ChainStore, ChainStoreAccess};
+use near_epoch_manager::EpochManager;
+use near_primitives::hash::CryptoHash;
+use near_primitives::types::{AccountId, BlockHeight, EpochHeight, ProtocolVersion};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+/// Protocol versions voted for at the end of an epoch become active two
+/// epochs later.
+const UPGRADE_DELAY_EPOCHS: EpochHeight = 2;
+
+#[derive(clap::ArgEnum, Clone, Copy, Debug)]
+pub(crate) enum TimelineFormat {
+    Json,
+    Csv,
+}
+
+/// Protocol version and votes of a single epoch.
+#[derive(Serialize, Debug)]
+pub(crate) struct EpochVotes {
+    pub(crate) epoch_id: CryptoHash,
+    pub(crate) epoch_height: EpochHeight,
+    /// First scanned block of the epoch.
+    pub(crate) first_block_height: BlockHeight,
+    /// Timestamp of the first scanned block, in nanoseconds.
+    pub(crate) first_block_timestamp: u64,
+    pub(crate) protocol_version: ProtocolVersion,
+    /// Latest protocol version announced by each block producer in its
+    /// blocks of the epoch. These are the votes counted at the end of the
+    /// epoch.
+    pub(crate) votes: BTreeMap<AccountId, ProtocolVersion>,
+}
+
+/// When a protocol version was first voted for and when it became active.
+#[derive(Serialize, Debug, PartialEq)]
+pub(crate) struct ProtocolUpgrade {
+    pub(crate) protocol_version: ProtocolVersion,
+    /// First block announcing the version.
+    pub(crate) first_vote_height: BlockHeight,
+    pub(crate) first_vote_timestamp: u64,
+    /// First epoch running the version, `None` if it is not active yet.
+    pub(crate) activated_epoch_height: Option<EpochHeight>,
+    pub(crate) activated_height: Option<BlockHeight>,
+    pub(crate) activated_timestamp: Option<u64>,
+    /// Epochs and seconds from the first vote to the activation.
+    pub(crate) adoption_lag_epochs: Option<EpochHeight>,
+    pub(crate) adoption_lag_seconds: Option<u64>,
+    /// Block producers that voted for the version, or a later one, in the
+    /// epoch that decided the upgrade. For versions that are not active yet,
+    /// the votes of the last scanned epoch.
+    pub(crate) voters: Vec<AccountId>,
+}
+
+#[derive(Serialize, Debug)]
+pub(crate) struct UpgradeTimeline {
+    pub(crate) upgrades: Vec<ProtocolUpgrade>,
+    pub(crate) epochs: Vec<EpochVotes>,
+}
+
+impl UpgradeTimeline {
+    /// Scans the block headers at heights `start_height..=end_height`.
+    /// Heights without a block are skipped.
+    pub(crate) fn load(
+        chain_store: &ChainStore,
+        epoch_manager: &EpochManager,
+        start_height: BlockHeight,
+        end_height: BlockHeight,
+    ) -> anyhow::Result<Self> {
+        let mut epochs: Vec<EpochVotes> = vec![];
+        let mut first_votes = BTreeMap::new();
+        for height in start_height..=end_height {
+            let block_hash = match chain_store.get_block_hash_by_height(height) {
+                Ok(block_hash) => block_hash,
+                Err(near_chain::Error::DBNotFoundErr(_)) => continue,
+                Err(err) => return Err(err.into()),
+            };
+            let header = chain_store.get_block_header(&block_hash)?;
+            let epoch_id = header.epoch_id();
+            if epochs.last().map_or(true, |epoch| epoch.epoch_id != epoch_id.0) {
+                let epoch_info = epoch_manager.get_epoch_info(epoch_id)?;
+                epochs.push(EpochVotes {
+                    epoch_id: epoch_id.0,
+                    epoch_height: epoch_info.epoch_height(),
+                    first_block_height: height,
+                    first_block_timestamp: header.raw_timestamp(),
+                    protocol_version: epoch_info.protocol_version(),
+                    votes: BTreeMap::new(),
+                });
+            }
+            let producer = epoch_manager.get_block_producer_info(epoch_id, height)?;
+            let version = header.latest_protocol_version();
+            epochs.last_mut().unwrap().votes.insert(producer.take_account_id(), version);
+            first_votes.entry(version).or_insert((height, header.raw_timestamp()));
+        }
+        Ok(Self { upgrades: upgrades(&epochs, &first_votes), epochs })
+    }
+
+    pub(crate) fn render(&self, format: TimelineFormat) -> anyhow::Result<String> {
+        match format {
+            TimelineFormat::Json => Ok(serde_json::to_string_pretty(self)?),
+            TimelineFormat::Csv => Ok(self.to_csv()),
+        }
+    }
+
+    /// One line per upgrade, voters are separated by spaces.
+    pub(crate) fn to_csv(&self) -> String {
+        let mut out = String::from(
+            "protocol_version,first_vote_height,first_vote_timestamp,activated_epoch_height,\
+             activated_height,activated_timestamp,adoption_lag_epochs,adoption_lag_seconds,voters\n",
+        );
+        let opt = |value: Option<u64>| value.map_or_else(String::new, |value| value.to_string());
+        for upgrade in &self.upgrades {
+            let voters: Vec<_> = upgrade.voters.iter().map(AccountId::as_str).collect();
+            writeln!(
+                out,
+                "{},{},{},{},{},{},{},{},{}",
+                upgrade.protocol_version,
+                upgrade.first_vote_height,
+                upgrade.first_vote_timestamp,
+                opt(upgrade.activated_epoch_height),
+                opt(upgrade.activated_height),
+                opt(upgrade.activated_timestamp),
+                opt(upgrade.adoption_lag_epochs),
+                opt(upgrade.adoption_lag_seconds),
+                voters.join(" "),
+            )
+            .unwrap();
+        }
+        out
+    }
+}
+
+/// Computes the upgrades to all versions newer than the one of the first
+/// epoch, given the height and timestamp of the first vote for each version.
+fn upgrades(
+    epochs: &[EpochVotes],
+    first_votes: &BTreeMap<ProtocolVersion, (BlockHeight, u64)>,
+) -> Vec<ProtocolUpgrade> {
+    let (first_epoch, last_epoch) = match (epochs.first(), epochs.last()) {
+        (Some(first), Some(last)) => (first, last),
+        _ => return vec![],
+    };
+    let mut upgrades = vec![];
+    for (&protocol_version, &(first_vote_height, first_vote_timestamp)) in first_votes {
+        if protocol_version <= first_epoch.protocol_version {
+            continue;
+        }
+        let activated = epochs.iter().find(|epoch| epoch.protocol_version >= protocol_version);
+        let deciding_epoch = match activated {
+            Some(activated) => epochs
+                .iter()
+                .rev()
+                .find(|epoch| epoch.epoch_height + UPGRADE_DELAY_EPOCHS <= activated.epoch_height)
+                .unwrap_or(first_epoch),
+            None => last_epoch,
+        };
+        let first_vote_epoch_height = epochs
+            .iter()
+            .rev()
+            .find(|epoch| epoch.first_block_height <= first_vote_height)
+            .map_or(first_epoch.epoch_height, |epoch| epoch.epoch_height);
+        let voters = deciding_epoch
+            .votes
+            .iter()
+            .filter(|(_, &version)| version >= protocol_version)
+            .map(|(account_id, _)| account_id.clone())
+            .collect();
+        upgrades.push(ProtocolUpgrade {
+            protocol_version,
+            first_vote_height,
+            first_vote_timestamp,
+            activated_epoch_height: activated.map(|epoch| epoch.epoch_height),
+            activated_height: activated.map(|epoch| epoch.first_block_height),
+            activated_timestamp: activated.map(|epoch| epoch.first_block_timestamp),
+            adoption_lag_epochs: activated
+                .map(|epoch| epoch.epoch_height.saturating_sub(first_vote_epoch_height)),
+            adoption_lag_seconds: activated.map(|epoch| {
+                epoch.first_block_timestamp.saturating_sub(first_vote_timestamp) / 1_000_000_000
+            }),
+            voters,
+        });
+    }
+    upgrades
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{upgrades, EpochVotes, UpgradeTimeline};
+    use near_primitives::hash::hash;
+    use near_primitives::types::{AccountId, ProtocolVersion};
+    use std::collections::BTreeMap;
+
+    const SECOND: u64 = 1_000_000_000;
+
+    fn epoch(epoch_height: u64, protocol_version: u32, votes: &[(&str, u32)]) -> EpochVotes {
+        EpochVotes {
+            epoch_id: hash(&epoch_height.to_le_bytes()),
+            epoch_height,
+            first_block_height: epoch_height * 100,
+            first_block_timestamp: epoch_height * 100 * SECOND,
+            protocol_version,
+            votes: votes
+                .iter()
+                .map(|&(account_id, version)| (account(account_id), version))
+                .collect(),
+        }
+    }
+
+    fn account(account_id: &str) -> AccountId {
+        account_id.parse().unwrap()
+    }
+
+    #[test]
+    fn test_upgrades() {
+        let epochs = vec![
+            epoch(1, 57, &[("a.near", 57), ("b.near", 57)]),
+            epoch(2, 57, &[("a.near", 58), ("b.near", 57)]),
+            epoch(3, 57, &[("a.near", 58), ("b.near", 58)]),
+            epoch(4, 57, &[("a.near", 59), ("b.near", 58)]),
+            epoch(5, 58, &[("a.near", 59), ("b.near", 58)]),
+        ];
+        let first_votes: BTreeMap<ProtocolVersion, (u64, u64)> =
+            [(57, (100, 100 * SECOND)), (58, (250, 250 * SECOND)), (59, (420, 420 * SECOND))]
+                .into_iter()
+                .collect();
+        let upgrades = upgrades(&epochs, &first_votes);
+        assert_eq!(upgrades.len(), 2);
+
+        let upgrade = &upgrades[0];
+        assert_eq!(upgrade.protocol_version, 58);
+        assert_eq!(upgrade.activated_epoch_height, Some(5));
+        assert_eq!(upgrade.activated_height, Some(500));
+        assert_eq!(upgrade.adoption_lag_epochs, Some(3));
+        assert_eq!(upgrade.adoption_lag_seconds, Some(250));
+        assert_eq!(upgrade.voters, vec![account("a.near"), account("b.near")]);
+
+        let upgrade = &upgrades[1];
+        assert_eq!(upgrade.protocol_version, 59);
+        assert_eq!(upgrade.activated_epoch_height, None);
+        assert_eq!(upgrade.adoption_lag_seconds, None);
+        assert_eq!(upgrade.voters, vec![account("a.near")]);
+
+        let timeline = UpgradeTimeline { upgrades, epochs };
+        let csv = timeline.to_csv();
+        let lines: Vec<_> = csv.lines().collect();
+        assert_eq!(lines.len(), 3);
+        assert!(lines[0].starts_with("protocol_version,"));
+        assert_eq!(lines[1], "58,250,250000000000,5,500,500000000000,3,250,a.near b.near");
+        assert_eq!(lines[2], "59,420,420000000000,,,,,,a.near");
+    }
+}
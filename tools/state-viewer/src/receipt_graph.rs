@@ -0,0 +1,201 @@
+//! Collects all receipts spawned by a transaction into a graph that can be
+//! rendered for visualization.
+
+use near_chain::{ChainStore, ChainStoreAccess};
+use near_primitives::hash::CryptoHash;
+use near_primitives::receipt::ReceiptEnum;
+use near_primitives::transaction::Action;
+use near_primitives::types::{AccountId, Gas};
+use serde::Serialize;
+use std::collections::{HashSet, VecDeque};
+use std::fmt::Write;
+
+#[derive(clap::ArgEnum, Clone, Copy, Debug)]
+pub(crate) enum GraphFormat {
+    Dot,
+    Json,
+}
+
+/// A single receipt in the graph, together with the receipts its execution
+/// produced.
+#[derive(Serialize, Debug)]
+pub(crate) struct ReceiptNode {
+    pub(crate) receipt_id: CryptoHash,
+    /// `None` if the receipt is not stored in the database.
+    pub(crate) predecessor_id: Option<AccountId>,
+    pub(crate) receiver_id: AccountId,
+    /// Action kinds for action receipts, `["Data"]` for data receipts.
+    pub(crate) actions: Vec<String>,
+    pub(crate) gas_burnt: Gas,
+    pub(crate) children: Vec<CryptoHash>,
+}
+
+/// All receipts transitively produced by a transaction, in breadth-first
+/// order.
+#[derive(Serialize, Debug)]
+pub(crate) struct ReceiptGraph {
+    pub(crate) tx_hash: CryptoHash,
+    pub(crate) signer_id: AccountId,
+    pub(crate) tx_gas_burnt: Gas,
+    /// Receipts created directly by the transaction.
+    pub(crate) roots: Vec<CryptoHash>,
+    pub(crate) receipts: Vec<ReceiptNode>,
+}
+
+impl ReceiptGraph {
+    pub(crate) fn load(chain_store: &ChainStore, tx_hash: CryptoHash) -> anyhow::Result<Self> {
+        let tx_outcome = chain_store
+            .get_outcomes_by_id(&tx_hash)?
+            .into_iter()
+            .next()
+            .ok_or_else(|| anyhow::anyhow!("no execution outcome found for transaction {tx_hash}"))?
+            .outcome_with_id
+            .outcome;
+
+        let mut receipts = vec![];
+        let mut seen = HashSet::new();
+        let mut queue: VecDeque<CryptoHash> = tx_outcome.receipt_ids.iter().copied().collect();
+        while let Some(receipt_id) = queue.pop_front() {
+            if !seen.insert(receipt_id) {
+                continue;
+            }
+            let outcome = chain_store
+                .get_outcomes_by_id(&receipt_id)?
+                .into_iter()
+                .next()
+                .map(|outcome| outcome.outcome_with_id.outcome);
+            let receipt = chain_store.get_receipt(&receipt_id)?;
+            let (predecessor_id, receiver_id, actions) = match receipt.as_deref() {
+                Some(receipt) => (
+                    Some(receipt.predecessor_id.clone()),
+                    receipt.receiver_id.clone(),
+                    action_kinds(&receipt.receipt),
+                ),
+                None => match &outcome {
+                    Some(outcome) => (None, outcome.executor_id.clone(), vec![]),
+                    None => anyhow::bail!("receipt {receipt_id} not found"),
+                },
+            };
+            let (gas_burnt, children) = match outcome {
+                Some(outcome) => (outcome.gas_burnt, outcome.receipt_ids),
+                None => (0, vec![]),
+            };
+            queue.extend(children.iter().copied());
+            receipts.push(ReceiptNode {
+                receipt_id,
+                predecessor_id,
+                receiver_id,
+                actions,
+                gas_burnt,
+                children,
+            });
+        }
+        Ok(Self {
+            tx_hash,
+            signer_id: tx_outcome.executor_id,
+            tx_gas_burnt: tx_outcome.gas_burnt,
+            roots: tx_outcome.receipt_ids,
+            receipts,
+        })
+    }
+
+    pub(crate) fn render(&self, format: GraphFormat) -> anyhow::Result<String> {
+        match format {
+            GraphFormat::Dot => Ok(self.to_dot()),
+            GraphFormat::Json => Ok(serde_json::to_string_pretty(self)?),
+        }
+    }
+
+    pub(crate) fn to_dot(&self) -> String {
+        let mut out = String::new();
+        writeln!(out, "digraph receipts {{").unwrap();
+        writeln!(
+            out,
+            "  \"{}\" [shape=box, label=\"tx {}\\n{}\\ngas burnt: {}\"];",
+            self.tx_hash, self.tx_hash, self.signer_id, self.tx_gas_burnt
+        )
+        .unwrap();
+        for root in &self.roots {
+            writeln!(out, "  \"{}\" -> \"{}\";", self.tx_hash, root).unwrap();
+        }
+        for node in &self.receipts {
+            let predecessor =
+                node.predecessor_id.as_ref().map_or_else(|| "?".to_owned(), |id| id.to_string());
+            writeln!(
+                out,
+                "  \"{}\" [label=\"{}\\n{} -> {}\\n{}\\ngas burnt: {}\"];",
+                node.receipt_id,
+                node.receipt_id,
+                predecessor,
+                node.receiver_id,
+                node.actions.join(", "),
+                node.gas_burnt
+            )
+            .unwrap();
+            for child in &node.children {
+                writeln!(out, "  \"{}\" -> \"{}\";", node.receipt_id, child).unwrap();
+            }
+        }
+        writeln!(out, "}}").unwrap();
+        out
+    }
+}
+
+fn action_kinds(receipt: &ReceiptEnum) -> Vec<String> {
+    match receipt {
+        ReceiptEnum::Action(action_receipt) => action_receipt
+            .actions
+            .iter()
+            .map(|action| match action {
+                Action::FunctionCall(function_call) => {
+                    format!("FunctionCall({})", function_call.method_name)
+                }
+                other => other.as_ref().to_owned(),
+            })
+            .collect(),
+        ReceiptEnum::Data(_) => vec!["Data".to_owned()],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ReceiptGraph, ReceiptNode};
+    use near_primitives::hash::hash;
+
+    #[test]
+    fn test_to_dot() {
+        let tx_hash = hash(b"tx");
+        let call = hash(b"call");
+        let refund = hash(b"refund");
+        let graph = ReceiptGraph {
+            tx_hash,
+            signer_id: "alice.near".parse().unwrap(),
+            tx_gas_burnt: 100,
+            roots: vec![call],
+            receipts: vec![
+                ReceiptNode {
+                    receipt_id: call,
+                    predecessor_id: Some("alice.near".parse().unwrap()),
+                    receiver_id: "contract.near".parse().unwrap(),
+                    actions: vec!["FunctionCall(foo)".to_owned()],
+                    gas_burnt: 200,
+                    children: vec![refund],
+                },
+                ReceiptNode {
+                    receipt_id: refund,
+                    predecessor_id: None,
+                    receiver_id: "alice.near".parse().unwrap(),
+                    actions: vec![],
+                    gas_burnt: 0,
+                    children: vec![],
+                },
+            ],
+        };
+        let dot = graph.to_dot();
+        assert!(dot.starts_with("digraph receipts {\n"));
+        assert!(dot.contains(&format!("\"{}\" -> \"{}\";", tx_hash, call)));
+        assert!(dot.contains(&format!("\"{}\" -> \"{}\";", call, refund)));
+        assert!(dot.contains("alice.near -> contract.near\\nFunctionCall(foo)\\ngas burnt: 200"));
+        assert!(dot.contains("? -> alice.near"));
+    }
+}
@@ -0,0 +1,287 @@
+//! Re-executes a single receipt against the state it was originally applied
+//! to and compares the result with what is stored on chain.
+
+use crate::diff_state::{DiffKind, StateDiff};
+use anyhow::Context;
+use near_chain::{ChainStore, ChainStoreAccess, RuntimeAdapter};
+use near_epoch_manager::EpochManager;
+use near_primitives::hash::CryptoHash;
+use near_primitives::receipt::Receipt;
+use near_primitives::runtime::config::RuntimeConfig;
+use near_primitives::runtime::migration_data::{MigrationData, MigrationFlags};
+use near_primitives::transaction::ExecutionOutcome;
+use near_primitives::types::{RawStateChangesWithTrieKey, StateChangeCause};
+use near_store::{KeyForStateChanges, Store};
+use nearcore::{NearConfig, NightshadeRuntime};
+use node_runtime::{ApplyState, Runtime};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::Path;
+use std::sync::Arc;
+
+/// Final value of every trie key touched while processing one receipt, keyed
+/// by the raw trie key. `None` means the key was deleted.
+type ReceiptStateChanges = BTreeMap<Vec<u8>, Option<Vec<u8>>>;
+
+pub(crate) fn replay_receipt(
+    receipt_id: CryptoHash,
+    runtime_config_path: Option<&Path>,
+    max_samples: usize,
+    home_dir: &Path,
+    near_config: NearConfig,
+    store: Store,
+) -> anyhow::Result<()> {
+    let chain_store = ChainStore::new(
+        store.clone(),
+        near_config.genesis.config.genesis_height,
+        !near_config.client_config.archive,
+    );
+    let runtime = NightshadeRuntime::from_config(home_dir, store.clone(), &near_config);
+    let epoch_manager =
+        EpochManager::new_from_genesis_config(store.clone(), &near_config.genesis.config)?
+            .into_handle();
+
+    let stored = chain_store
+        .get_outcomes_by_id(&receipt_id)?
+        .into_iter()
+        .next()
+        .with_context(|| format!("no execution outcome found for receipt {receipt_id}"))?;
+    let block_hash = stored.block_hash;
+    let receipt = chain_store
+        .get_receipt(&receipt_id)?
+        .with_context(|| format!("receipt {receipt_id} not found"))?;
+
+    let header = chain_store.get_block_header(&block_hash)?;
+    let prev_header = chain_store.get_block_header(header.prev_hash())?;
+    let epoch_id = header.epoch_id().clone();
+    let shard_id = runtime.account_id_to_shard_id(&receipt.receiver_id, &epoch_id)?;
+    let shard_uid = runtime.shard_id_to_uid(shard_id, &epoch_id)?;
+    // The state before any chunk of this block was applied. Receipts that
+    // were processed earlier in the same chunk are not replayed, so their
+    // effects are missing from the pre-state.
+    let prev_state_root =
+        *chain_store.get_chunk_extra(prev_header.hash(), &shard_uid)?.state_root();
+    let trie = runtime.get_trie_for_shard(shard_id, prev_header.hash(), prev_state_root, false)?;
+
+    let protocol_version = runtime.get_epoch_protocol_version(&epoch_id)?;
+    let config = match runtime_config_path {
+        Some(path) => {
+            let file = std::fs::File::open(path)
+                .with_context(|| format!("failed to open {}", path.display()))?;
+            serde_json::from_reader::<_, RuntimeConfig>(file)
+                .with_context(|| format!("failed to parse runtime config {}", path.display()))?
+        }
+        None => runtime.get_protocol_config(&epoch_id)?.runtime_config,
+    };
+    let apply_state = ApplyState {
+        block_height: header.height(),
+        prev_block_hash: *prev_header.hash(),
+        block_hash,
+        epoch_id: epoch_id.clone(),
+        epoch_height: runtime.get_epoch_height_from_prev_block(prev_header.hash())?,
+        gas_price: prev_header.gas_price(),
+        block_timestamp: header.raw_timestamp(),
+        // Without a limit the receipt cannot end up in the delayed receipts
+        // queue.
+        gas_limit: None,
+        random_seed: *header.random_value(),
+        current_protocol_version: protocol_version,
+        config: Arc::new(config),
+        cache: None,
+        is_new_chunk: true,
+        migration_data: Arc::new(MigrationData::default()),
+        migration_flags: MigrationFlags::default(),
+    };
+
+    println!(
+        "Replaying receipt {} in block {} (height {}), shard {}",
+        receipt_id,
+        block_hash,
+        header.height(),
+        shard_id
+    );
+    let apply_result = Runtime::new().apply(
+        trie,
+        &None,
+        &apply_state,
+        &[Receipt::clone(&receipt)],
+        &[],
+        &epoch_manager,
+        Default::default(),
+    )?;
+
+    let replayed = apply_result
+        .outcomes
+        .into_iter()
+        .find(|outcome| outcome.id == receipt_id)
+        .context("replay did not produce an outcome for the receipt")?;
+    let outcome_diff = OutcomeDiff::new(&replayed.outcome, &stored.outcome_with_id.outcome);
+    print!("{}", outcome_diff);
+
+    let replayed_changes = receipt_state_changes(apply_result.state_changes, &receipt_id);
+    let stored_changes = receipt_state_changes(
+        KeyForStateChanges::for_block(&block_hash)
+            .find_iter(&store)
+            .collect::<Result<Vec<_>, _>>()?,
+        &receipt_id,
+    );
+    println!(
+        "State changes: {} keys replayed (this) vs {} keys stored (other)",
+        replayed_changes.len(),
+        stored_changes.len()
+    );
+    print!("{}", diff_state_changes(&replayed_changes, &stored_changes, max_samples));
+    Ok(())
+}
+
+fn caused_by_receipt(cause: &StateChangeCause, receipt_id: &CryptoHash) -> bool {
+    match cause {
+        StateChangeCause::ActionReceiptProcessingStarted { receipt_hash }
+        | StateChangeCause::ActionReceiptGasReward { receipt_hash }
+        | StateChangeCause::ReceiptProcessing { receipt_hash }
+        | StateChangeCause::PostponedReceipt { receipt_hash } => receipt_hash == receipt_id,
+        _ => false,
+    }
+}
+
+/// Collects the last value written to each trie key while processing the
+/// given receipt.
+fn receipt_state_changes(
+    changes: Vec<RawStateChangesWithTrieKey>,
+    receipt_id: &CryptoHash,
+) -> ReceiptStateChanges {
+    let mut result = BTreeMap::new();
+    for RawStateChangesWithTrieKey { trie_key, changes } in changes {
+        if let Some(change) =
+            changes.into_iter().filter(|change| caused_by_receipt(&change.cause, receipt_id)).last()
+        {
+            result.insert(trie_key.to_vec(), change.data);
+        }
+    }
+    result
+}
+
+fn diff_state_changes(
+    replayed: &ReceiptStateChanges,
+    stored: &ReceiptStateChanges,
+    max_samples: usize,
+) -> StateDiff {
+    let mut diff = StateDiff::new(max_samples);
+    for (key, value) in replayed {
+        match stored.get(key) {
+            None => diff.record(key.clone(), DiffKind::MissingInOther),
+            Some(stored_value) if stored_value != value => {
+                diff.record(key.clone(), DiffKind::ValueDiffers)
+            }
+            Some(_) => {}
+        }
+    }
+    for key in stored.keys().filter(|key| !replayed.contains_key(*key)) {
+        diff.record(key.clone(), DiffKind::MissingInThis);
+    }
+    diff
+}
+
+/// Fields of an execution outcome that differ between the replay and the
+/// outcome stored on chain.
+struct OutcomeDiff {
+    /// Pairs of (field, replayed value, stored value).
+    fields: Vec<(&'static str, String, String)>,
+}
+
+impl OutcomeDiff {
+    fn new(replayed: &ExecutionOutcome, stored: &ExecutionOutcome) -> Self {
+        let mut fields = vec![];
+        let mut compare = |name, replayed: &dyn fmt::Debug, stored: &dyn fmt::Debug| {
+            let (replayed, stored) = (format!("{:?}", replayed), format!("{:?}", stored));
+            if replayed != stored {
+                fields.push((name, replayed, stored));
+            }
+        };
+        compare("status", &replayed.status, &stored.status);
+        compare("gas_burnt", &replayed.gas_burnt, &stored.gas_burnt);
+        compare("tokens_burnt", &replayed.tokens_burnt, &stored.tokens_burnt);
+        compare("logs", &replayed.logs, &stored.logs);
+        compare("receipt_ids", &replayed.receipt_ids, &stored.receipt_ids);
+        compare("metadata", &replayed.metadata, &stored.metadata);
+        Self { fields }
+    }
+}
+
+impl fmt::Display for OutcomeDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.fields.is_empty() {
+            return writeln!(f, "Outcome matches the stored outcome");
+        }
+        for (name, replayed, stored) in &self.fields {
+            writeln!(
+                f,
+                "Outcome {} differs:\n    replayed: {}\n    stored:   {}",
+                name, replayed, stored
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{diff_state_changes, receipt_state_changes, OutcomeDiff};
+    use crate::diff_state::DiffKind;
+    use near_primitives::hash::hash;
+    use near_primitives::transaction::{ExecutionOutcome, ExecutionStatus};
+    use near_primitives::trie_key::TrieKey;
+    use near_primitives::types::{RawStateChange, RawStateChangesWithTrieKey, StateChangeCause};
+
+    #[test]
+    fn test_outcome_diff() {
+        let stored = ExecutionOutcome {
+            gas_burnt: 100,
+            status: ExecutionStatus::SuccessValue(vec![]),
+            ..Default::default()
+        };
+        assert!(OutcomeDiff::new(&stored, &stored).fields.is_empty());
+
+        let replayed = ExecutionOutcome { gas_burnt: 120, ..stored.clone() };
+        let diff = OutcomeDiff::new(&replayed, &stored);
+        assert_eq!(diff.fields, vec![("gas_burnt", "120".to_owned(), "100".to_owned())]);
+    }
+
+    #[test]
+    fn test_receipt_state_changes() {
+        let receipt_id = hash(b"receipt");
+        let other_id = hash(b"other");
+        let alice = TrieKey::Account { account_id: "alice.near".parse().unwrap() };
+        let bob = TrieKey::Account { account_id: "bob.near".parse().unwrap() };
+        let change = |receipt_hash, data: &[u8]| RawStateChange {
+            cause: StateChangeCause::ReceiptProcessing { receipt_hash },
+            data: Some(data.to_vec()),
+        };
+        let stored = receipt_state_changes(
+            vec![
+                RawStateChangesWithTrieKey {
+                    trie_key: alice.clone(),
+                    changes: vec![change(receipt_id, b"1"), change(other_id, b"2")],
+                },
+                RawStateChangesWithTrieKey {
+                    trie_key: bob.clone(),
+                    changes: vec![change(receipt_id, b"3")],
+                },
+            ],
+            &receipt_id,
+        );
+        assert_eq!(stored.get(&alice.to_vec()), Some(&Some(b"1".to_vec())));
+
+        let replayed = receipt_state_changes(
+            vec![RawStateChangesWithTrieKey {
+                trie_key: alice,
+                changes: vec![change(receipt_id, b"4")],
+            }],
+            &receipt_id,
+        );
+        let diff = diff_state_changes(&replayed, &stored, 1);
+        assert_eq!(diff.count("Account", DiffKind::ValueDiffers), 1);
+        assert_eq!(diff.count("Account", DiffKind::MissingInThis), 1);
+        assert_eq!(diff.count("Account", DiffKind::MissingInOther), 0);
+    }
+}
@@ -0,0 +1,200 @@
+//! Ranks the accounts of the state by their liquid or locked balance, for
+//! supply audits. The ranking can be paged through with a cursor, so that
+//! long lists don't have to be kept in memory.
+
+use anyhow::Context;
+use borsh::BorshDeserialize;
+use near_primitives::account::Account;
+use near_primitives::trie_key::trie_key_parsers::parse_account_id_from_account_key;
+use near_primitives::trie_key::TrieKey;
+use near_primitives::types::{AccountId, Balance};
+use near_store::Trie;
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+use std::fmt::Write;
+use std::str::FromStr;
+
+#[derive(clap::ArgEnum, Clone, Copy, Debug)]
+pub(crate) enum RankBy {
+    Liquid,
+    Locked,
+}
+
+/// Balances of a single account.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub(crate) struct RichListEntry {
+    pub(crate) account_id: AccountId,
+    pub(crate) liquid: Balance,
+    pub(crate) locked: Balance,
+}
+
+impl RichListEntry {
+    fn balance(&self, rank_by: RankBy) -> Balance {
+        match rank_by {
+            RankBy::Liquid => self.liquid,
+            RankBy::Locked => self.locked,
+        }
+    }
+}
+
+/// Position in the ranking, formatted as `<balance>:<account id>`. A page
+/// starts with the first account ranked after the cursor.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Cursor {
+    balance: Balance,
+    account_id: AccountId,
+}
+
+impl Cursor {
+    /// Accounts are ranked by descending balance, ties by account id.
+    fn rank_key(&self) -> (Reverse<Balance>, &AccountId) {
+        (Reverse(self.balance), &self.account_id)
+    }
+}
+
+impl std::fmt::Display for Cursor {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}", self.balance, self.account_id)
+    }
+}
+
+impl FromStr for Cursor {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (balance, account_id) =
+            s.split_once(':').context("expected cursor in the form <balance>:<account id>")?;
+        Ok(Self { balance: balance.parse()?, account_id: account_id.parse()? })
+    }
+}
+
+/// Keeps the `top` highest ranked accounts after the cursor.
+pub(crate) struct RichList {
+    rank_by: RankBy,
+    top: usize,
+    after: Option<Cursor>,
+    /// Max-heap on the rank key, so the lowest ranked account is on top.
+    entries: BinaryHeap<(Reverse<Balance>, RichListEntry)>,
+}
+
+impl RichList {
+    pub(crate) fn new(rank_by: RankBy, top: usize, after: Option<Cursor>) -> Self {
+        Self { rank_by, top, after, entries: BinaryHeap::with_capacity(top + 1) }
+    }
+
+    pub(crate) fn add(&mut self, entry: RichListEntry) {
+        let balance = entry.balance(self.rank_by);
+        if let Some(after) = &self.after {
+            if (Reverse(balance), &entry.account_id) <= after.rank_key() {
+                return;
+            }
+        }
+        self.entries.push((Reverse(balance), entry));
+        if self.entries.len() > self.top {
+            self.entries.pop();
+        }
+    }
+
+    /// Adds the balances of all accounts stored in a trie.
+    pub(crate) fn add_trie(&mut self, trie: &Trie) -> anyhow::Result<()> {
+        // `TrieKey::Account` requires a valid account id, use "xx" and cut it
+        // off again to get the column prefix.
+        let mut prefix = TrieKey::Account { account_id: "xx".parse()? }.to_vec();
+        prefix.truncate(prefix.len() - 2);
+        for item in trie.iter_prefix(&prefix)? {
+            let (key, value) = item?;
+            let account_id = parse_account_id_from_account_key(&key)?;
+            let account = Account::try_from_slice(&value)
+                .with_context(|| format!("failed to parse account {}", account_id))?;
+            self.add(RichListEntry {
+                account_id,
+                liquid: account.amount(),
+                locked: account.locked(),
+            });
+        }
+        Ok(())
+    }
+
+    /// Returns the accounts in ranking order and the cursor to resume from,
+    /// `None` if the page is not full and thus the last one.
+    pub(crate) fn finish(self) -> (Vec<RichListEntry>, Option<Cursor>) {
+        let rank_by = self.rank_by;
+        let full = self.entries.len() == self.top;
+        let entries: Vec<_> =
+            self.entries.into_sorted_vec().into_iter().map(|(_, entry)| entry).collect();
+        let next = match entries.last() {
+            Some(last) if full => {
+                Some(Cursor { balance: last.balance(rank_by), account_id: last.account_id.clone() })
+            }
+            _ => None,
+        };
+        (entries, next)
+    }
+}
+
+/// One line per account, in ranking order.
+pub(crate) fn to_csv(entries: &[RichListEntry]) -> String {
+    let mut out = String::from("account_id,liquid,locked\n");
+    for entry in entries {
+        writeln!(out, "{},{},{}", entry.account_id, entry.liquid, entry.locked).unwrap();
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{to_csv, Cursor, RankBy, RichList, RichListEntry};
+
+    fn entry(account_id: &str, liquid: u128, locked: u128) -> RichListEntry {
+        RichListEntry { account_id: account_id.parse().unwrap(), liquid, locked }
+    }
+
+    fn rich_list(rank_by: RankBy, top: usize, after: Option<Cursor>) -> RichList {
+        let mut list = RichList::new(rank_by, top, after);
+        for entry in [
+            entry("a.near", 10, 0),
+            entry("b.near", 30, 5),
+            entry("c.near", 20, 50),
+            entry("d.near", 30, 0),
+            entry("e.near", 5, 40),
+        ] {
+            list.add(entry);
+        }
+        list
+    }
+
+    fn account_ids(entries: &[RichListEntry]) -> Vec<&str> {
+        entries.iter().map(|entry| entry.account_id.as_str()).collect()
+    }
+
+    #[test]
+    fn test_pages() {
+        let (entries, next) = rich_list(RankBy::Liquid, 2, None).finish();
+        assert_eq!(account_ids(&entries), ["b.near", "d.near"]);
+        let next = next.unwrap();
+        assert_eq!(next.to_string(), "30:d.near");
+
+        let (entries, next) = rich_list(RankBy::Liquid, 2, Some(next)).finish();
+        assert_eq!(account_ids(&entries), ["c.near", "a.near"]);
+
+        let (entries, next) = rich_list(RankBy::Liquid, 2, next).finish();
+        assert_eq!(account_ids(&entries), ["e.near"]);
+        assert_eq!(next, None);
+
+        let (entries, next) = rich_list(RankBy::Locked, 3, None).finish();
+        assert_eq!(account_ids(&entries), ["c.near", "e.near", "b.near"]);
+        assert_eq!(next, Some("5:b.near".parse().unwrap()));
+        assert_eq!(
+            to_csv(&entries),
+            "account_id,liquid,locked\nc.near,20,50\ne.near,5,40\nb.near,30,5\n"
+        );
+    }
+
+    #[test]
+    fn test_parse_cursor() {
+        assert!("100".parse::<Cursor>().is_err());
+        assert!("x:a.near".parse::<Cursor>().is_err());
+        let cursor: Cursor = "100:a.near".parse().unwrap();
+        assert_eq!(cursor.to_string(), "100:a.near");
+    }
+}
@@ -0,0 +1,224 @@
+//! Estimates how a proposed shard layout would split the current state and
+//! the recent traffic, as a quantitative basis for choosing the boundary
+//! accounts of a resharding.
+
+use near_chain::{ChainStore, ChainStoreAccess};
+use near_primitives::shard_layout::{account_id_to_shard_id, ShardLayout};
+use near_primitives::trie_key::trie_key_parsers::{
+    parse_account_id_from_account_key, parse_account_id_from_raw_key,
+};
+use near_primitives::types::{AccountId, BlockHeight, Gas, ShardId};
+use near_store::Trie;
+use std::fmt::Write;
+
+/// State and traffic that would end up in one shard of the proposed layout.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub(crate) struct ShardStats {
+    pub(crate) accounts: u64,
+    pub(crate) state_keys: u64,
+    /// Size of all keys and values.
+    pub(crate) state_bytes: u64,
+    /// Gas burnt by receipts and transactions executed on the shard.
+    pub(crate) gas_burnt: Gas,
+    /// Receipts executed on the shard, not counting refunds and other
+    /// receipts sent by the system account.
+    pub(crate) receipts: u64,
+    /// Receipts of which the predecessor is on another shard.
+    pub(crate) cross_shard_receipts: u64,
+}
+
+impl ShardStats {
+    pub(crate) fn cross_shard_ratio(&self) -> f64 {
+        if self.receipts == 0 {
+            return 0.0;
+        }
+        self.cross_shard_receipts as f64 / self.receipts as f64
+    }
+}
+
+pub(crate) struct RebalanceAnalysis {
+    layout: ShardLayout,
+    pub(crate) shards: Vec<ShardStats>,
+    /// Size of state items that don't belong to an account, like delayed
+    /// receipts. They stay on the shard they were created on.
+    pub(crate) unassigned_state_bytes: u64,
+    /// Number of blocks of which the traffic was added.
+    pub(crate) blocks: u64,
+}
+
+impl RebalanceAnalysis {
+    pub(crate) fn new(layout: ShardLayout) -> Self {
+        let shards = (0..layout.num_shards()).map(|_| ShardStats::default()).collect();
+        Self { layout, shards, unassigned_state_bytes: 0, blocks: 0 }
+    }
+
+    fn shard(&mut self, account_id: &AccountId) -> &mut ShardStats {
+        let shard_id = account_id_to_shard_id(account_id, &self.layout);
+        &mut self.shards[shard_id as usize]
+    }
+
+    pub(crate) fn add_state_item(&mut self, key: &[u8], value_len: usize) -> anyhow::Result<()> {
+        let size = (key.len() + value_len) as u64;
+        let account_id = match parse_account_id_from_raw_key(key)? {
+            Some(account_id) => account_id,
+            None => {
+                self.unassigned_state_bytes += size;
+                return Ok(());
+            }
+        };
+        let is_account = parse_account_id_from_account_key(key).is_ok();
+        let shard = self.shard(&account_id);
+        shard.state_keys += 1;
+        shard.state_bytes += size;
+        if is_account {
+            shard.accounts += 1;
+        }
+        Ok(())
+    }
+
+    /// Adds all items of the state of a current shard.
+    pub(crate) fn add_trie(&mut self, trie: &Trie) -> anyhow::Result<()> {
+        for item in trie.iter()? {
+            let (key, value) = item?;
+            self.add_state_item(&key, value.len())?;
+        }
+        Ok(())
+    }
+
+    /// Adds an execution outcome. `predecessor_id` is `None` for
+    /// transactions.
+    pub(crate) fn add_outcome(
+        &mut self,
+        executor_id: &AccountId,
+        gas_burnt: Gas,
+        predecessor_id: Option<&AccountId>,
+    ) {
+        let executor_shard_id = account_id_to_shard_id(executor_id, &self.layout);
+        let predecessor_shard_id = predecessor_id
+            .filter(|predecessor_id| !predecessor_id.is_system())
+            .map(|predecessor_id| account_id_to_shard_id(predecessor_id, &self.layout));
+        let shard = &mut self.shards[executor_shard_id as usize];
+        shard.gas_burnt += gas_burnt;
+        if let Some(predecessor_shard_id) = predecessor_shard_id {
+            shard.receipts += 1;
+            if predecessor_shard_id != executor_shard_id {
+                shard.cross_shard_receipts += 1;
+            }
+        }
+    }
+
+    /// Adds the outcomes of all chunks of the blocks in the height range.
+    /// Missing heights are skipped.
+    pub(crate) fn add_blocks(
+        &mut self,
+        chain_store: &ChainStore,
+        start_height: BlockHeight,
+        end_height: BlockHeight,
+    ) -> anyhow::Result<()> {
+        for height in start_height..=end_height {
+            let block_hash = match chain_store.get_block_hash_by_height(height) {
+                Ok(block_hash) => block_hash,
+                Err(_) => continue,
+            };
+            let block = chain_store.get_block(&block_hash)?;
+            for shard_id in 0..block.chunks().len() as ShardId {
+                let outcome_ids =
+                    chain_store.get_outcomes_by_block_hash_and_shard_id(&block_hash, shard_id)?;
+                for outcome_id in outcome_ids {
+                    let outcome = match chain_store
+                        .get_outcome_by_id_and_block_hash(&outcome_id, &block_hash)?
+                    {
+                        Some(outcome) => outcome.outcome,
+                        None => continue,
+                    };
+                    let receipt = chain_store.get_receipt(&outcome_id)?;
+                    let predecessor_id = receipt.as_ref().map(|receipt| &receipt.predecessor_id);
+                    self.add_outcome(&outcome.executor_id, outcome.gas_burnt, predecessor_id);
+                }
+            }
+            self.blocks += 1;
+        }
+        Ok(())
+    }
+
+    /// One line per shard of the proposed layout.
+    pub(crate) fn to_csv(&self) -> String {
+        let mut out = String::from(
+            "shard_id,accounts,state_keys,state_bytes,gas_burnt,receipts,cross_shard_receipts,cross_shard_ratio\n",
+        );
+        for (shard_id, shard) in self.shards.iter().enumerate() {
+            writeln!(
+                out,
+                "{},{},{},{},{},{},{},{:.4}",
+                shard_id,
+                shard.accounts,
+                shard.state_keys,
+                shard.state_bytes,
+                shard.gas_burnt,
+                shard.receipts,
+                shard.cross_shard_receipts,
+                shard.cross_shard_ratio()
+            )
+            .unwrap();
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{RebalanceAnalysis, ShardStats};
+    use near_primitives::shard_layout::ShardLayout;
+    use near_primitives::trie_key::TrieKey;
+    use near_primitives::types::AccountId;
+
+    fn account(account_id: &str) -> AccountId {
+        account_id.parse().unwrap()
+    }
+
+    #[test]
+    fn test_rebalance_analysis() {
+        // Shard 0: accounts < "middle.near", shard 1: accounts >= "middle.near".
+        let layout = ShardLayout::v1(vec![], vec![account("middle.near")], None, 1);
+        let mut analysis = RebalanceAnalysis::new(layout);
+
+        let alice = TrieKey::Account { account_id: account("alice.near") }.to_vec();
+        analysis.add_state_item(&alice, 100).unwrap();
+        let data = TrieKey::ContractData { account_id: account("zoo.near"), key: b"key".to_vec() }
+            .to_vec();
+        analysis.add_state_item(&data, 10).unwrap();
+        analysis.add_state_item(&TrieKey::DelayedReceipt { index: 0 }.to_vec(), 50).unwrap();
+
+        analysis.add_outcome(&account("alice.near"), 1_000, None);
+        analysis.add_outcome(&account("zoo.near"), 200, Some(&account("alice.near")));
+        analysis.add_outcome(&account("zoo.near"), 300, Some(&account("zoo.near")));
+        analysis.add_outcome(&account("alice.near"), 5, Some(&account("system")));
+
+        assert_eq!(
+            analysis.shards,
+            vec![
+                ShardStats {
+                    accounts: 1,
+                    state_keys: 1,
+                    state_bytes: alice.len() as u64 + 100,
+                    gas_burnt: 1_005,
+                    receipts: 0,
+                    cross_shard_receipts: 0,
+                },
+                ShardStats {
+                    accounts: 0,
+                    state_keys: 1,
+                    state_bytes: data.len() as u64 + 10,
+                    gas_burnt: 500,
+                    receipts: 2,
+                    cross_shard_receipts: 1,
+                },
+            ]
+        );
+        assert_eq!(analysis.unassigned_state_bytes, 9 + 50);
+        assert_eq!(
+            analysis.to_csv().lines().nth(2),
+            Some(format!("1,0,1,{},500,2,1,0.5000", data.len() + 10).as_str())
+        );
+    }
+}